@@ -13,6 +13,18 @@ pub enum DiagKind {
     ExpectedSpecificDirective(&'static str, Span),
     #[error("Unexpected token")]
     UnexpectedToken(Span),
+    #[error("Unknown directive '{0}'")]
+    UnknownDirective(String, Span),
+    #[error("Expected a constant expression")]
+    ExpectedConstant(Span),
+    #[error("Expected a symbol with an optional constant offset")]
+    ExpectedSymbol(Span),
+    #[error("Expected a string")]
+    ExpectedString(Span),
+    #[error("Value {0} does not fit in {1} bytes")]
+    DataOutOfRange(i64, u8, Span),
+    #[error("Alignment must be a power of two, got {0}")]
+    InvalidAlignment(i64, Span),
     #[error("End of stream")]
     EndOfStream,
 }
@@ -29,6 +41,12 @@ impl DiagnosticLike for DiagKind {
             DiagKind::ExpectedLabel(span) => span.clone(),
             DiagKind::ExpectedSpecificDirective(_, span) => span.clone(),
             DiagKind::UnexpectedToken(span) => span.clone(),
+            DiagKind::UnknownDirective(_, span) => span.clone(),
+            DiagKind::ExpectedConstant(span) => span.clone(),
+            DiagKind::ExpectedSymbol(span) => span.clone(),
+            DiagKind::ExpectedString(span) => span.clone(),
+            DiagKind::DataOutOfRange(_, _, span) => span.clone(),
+            DiagKind::InvalidAlignment(_, span) => span.clone(),
             DiagKind::EndOfStream => Span::empty(),
         }
     }
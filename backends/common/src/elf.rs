@@ -0,0 +1,1005 @@
+//! ELF64 files: writing of relocatable object files and reading of object files,
+//! executables and shared libraries
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::Endianness;
+
+pub const EM_RISCV: u16 = 243;
+
+const ET_REL: u16 = 1;
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_RELA: u32 = 4;
+const SHT_NOBITS: u32 = 8;
+const SHT_REL: u32 = 9;
+
+const SHF_WRITE: u64 = 0x1;
+const SHF_ALLOC: u64 = 0x2;
+const SHF_EXECINSTR: u64 = 0x4;
+const SHF_INFO_LINK: u64 = 0x40;
+
+const SHN_LORESERVE: u16 = 0xff00;
+
+const EHDR_SIZE: u64 = 64;
+const SHDR_SIZE: u64 = 64;
+const SYM_SIZE: u64 = 24;
+const RELA_SIZE: u64 = 24;
+const REL_SIZE: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolBinding {
+    Local,
+    Global,
+    Weak,
+}
+
+impl SymbolBinding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "local" => Some(SymbolBinding::Local),
+            "global" => Some(SymbolBinding::Global),
+            "weak" => Some(SymbolBinding::Weak),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SymbolBinding::Local => "local",
+            SymbolBinding::Global => "global",
+            SymbolBinding::Weak => "weak",
+        }
+    }
+
+    fn encode(&self) -> u8 {
+        match self {
+            SymbolBinding::Local => 0,
+            SymbolBinding::Global => 1,
+            SymbolBinding::Weak => 2,
+        }
+    }
+
+    /// `STB_GNU_UNIQUE` is treated as global
+    fn decode(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(SymbolBinding::Local),
+            1 | 10 => Some(SymbolBinding::Global),
+            2 => Some(SymbolBinding::Weak),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    NoType,
+    Object,
+    Func,
+    Section,
+}
+
+impl SymbolKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "notype" => Some(SymbolKind::NoType),
+            "object" => Some(SymbolKind::Object),
+            "function" => Some(SymbolKind::Func),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SymbolKind::NoType => "notype",
+            SymbolKind::Object => "object",
+            SymbolKind::Func => "function",
+            SymbolKind::Section => "section",
+        }
+    }
+
+    fn encode(&self) -> u8 {
+        match self {
+            SymbolKind::NoType => 0,
+            SymbolKind::Object => 1,
+            SymbolKind::Func => 2,
+            SymbolKind::Section => 3,
+        }
+    }
+
+    /// Common and thread-local symbols are treated as objects, indirect functions as
+    /// functions. File symbols have no kind.
+    fn decode(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(SymbolKind::NoType),
+            1 | 5 | 6 => Some(SymbolKind::Object),
+            2 | 10 => Some(SymbolKind::Func),
+            3 => Some(SymbolKind::Section),
+            _ => None,
+        }
+    }
+}
+
+/// Symbol of the object file. Symbols without a section are undefined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    /// Index of the section in [`ObjectFile::sections`]
+    pub section: Option<usize>,
+    pub value: u64,
+    pub size: u64,
+    pub binding: SymbolBinding,
+    pub kind: SymbolKind,
+}
+
+/// Relocation at the offset in the section, that refers to a symbol by its name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relocation {
+    pub offset: u64,
+    pub kind: u32,
+    pub symbol: String,
+    pub addend: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Section {
+    pub name: String,
+    pub data: Vec<u8>,
+    /// Size of the section, that holds no data, like `.bss`
+    pub zero_size: u64,
+    pub align: u64,
+    pub relocations: Vec<Relocation>,
+}
+
+impl Section {
+    pub fn new(name: &str) -> Self {
+        Section {
+            name: name.to_string(),
+            align: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.name == ".text" || self.name.starts_with(".text.")
+    }
+
+    /// Uninitialized sections occupy no space in the file
+    pub fn is_nobits(&self) -> bool {
+        [".bss", ".sbss", ".tbss"]
+            .iter()
+            .any(|prefix| self.name == *prefix || self.name.starts_with(&format!("{}.", prefix)))
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.data.len() as u64 + self.zero_size
+    }
+
+    /// Flags of the `.section` directive, i.e. `ax` for code
+    pub fn get_flag_chars(&self) -> String {
+        let flags = self.get_flags();
+        [(SHF_ALLOC, 'a'), (SHF_WRITE, 'w'), (SHF_EXECINSTR, 'x')]
+            .iter()
+            .filter(|(flag, _)| flags & flag != 0)
+            .map(|(_, c)| *c)
+            .collect()
+    }
+
+    fn get_flags(&self) -> u64 {
+        let is_prefix =
+            |prefix: &str| self.name == prefix || self.name.starts_with(&format!("{}.", prefix));
+        if self.is_executable() {
+            SHF_ALLOC | SHF_EXECINSTR
+        } else if self.is_nobits() || [".data", ".sdata", ".tdata"].iter().any(|p| is_prefix(p)) {
+            SHF_ALLOC | SHF_WRITE
+        } else if self.name.starts_with(".note") || self.name.starts_with(".comment") {
+            0
+        } else {
+            SHF_ALLOC
+        }
+    }
+}
+
+/// Relocatable object file
+#[derive(Debug)]
+pub struct ObjectFile {
+    pub endianness: Endianness,
+    pub machine: u16,
+    pub flags: u32,
+    pub sections: Vec<Section>,
+    pub symbols: Vec<Symbol>,
+}
+
+/// Table of null-terminated strings
+#[derive(Default)]
+struct StringTable {
+    data: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl StringTable {
+    fn new() -> Self {
+        StringTable {
+            data: vec![0],
+            offsets: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, name: &str) -> u32 {
+        if name.is_empty() {
+            return 0;
+        }
+        if let Some(offset) = self.offsets.get(name) {
+            return *offset;
+        }
+        let offset = self.data.len() as u32;
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.push(0);
+        self.offsets.insert(name.to_string(), offset);
+        offset
+    }
+}
+
+/// Byte buffer, that writes integers in the given byte order
+struct Writer {
+    data: Vec<u8>,
+    endianness: Endianness,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        match self.endianness {
+            Endianness::Little => self.data.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => self.data.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    fn u32(&mut self, value: u32) {
+        match self.endianness {
+            Endianness::Little => self.data.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => self.data.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        match self.endianness {
+            Endianness::Little => self.data.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => self.data.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    fn align(&mut self, align: u64) {
+        let len = (self.data.len() as u64).next_multiple_of(align.max(1));
+        self.data.resize(len as usize, 0);
+    }
+
+    fn offset(&self) -> u64 {
+        self.data.len() as u64
+    }
+}
+
+/// Section header, as written to the section header table
+struct SectionHeader {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    info: u32,
+    align: u64,
+    entsize: u64,
+}
+
+impl ObjectFile {
+    pub fn new(endianness: Endianness, machine: u16, flags: u32) -> Self {
+        ObjectFile {
+            endianness,
+            machine,
+            flags,
+            sections: vec![],
+            symbols: vec![],
+        }
+    }
+
+    /// Symbol table in the ELF order: the null symbol, section symbols, local symbols
+    /// and then global symbols. Symbols, that relocations refer to, but are not
+    /// defined, are added as undefined global symbols.
+    fn get_symbol_table(&self) -> Vec<Symbol> {
+        let section_symbols = self.sections.iter().enumerate().map(|(idx, _)| Symbol {
+            name: String::new(),
+            section: Some(idx),
+            value: 0,
+            size: 0,
+            binding: SymbolBinding::Local,
+            kind: SymbolKind::Section,
+        });
+
+        let mut undefined: Vec<Symbol> = vec![];
+        for reloc in self
+            .sections
+            .iter()
+            .flat_map(|section| &section.relocations)
+        {
+            let is_known = |symbol: &Symbol| symbol.name == reloc.symbol;
+            if !self.symbols.iter().any(is_known) && !undefined.iter().any(is_known) {
+                undefined.push(Symbol {
+                    name: reloc.symbol.clone(),
+                    section: None,
+                    value: 0,
+                    size: 0,
+                    binding: SymbolBinding::Global,
+                    kind: SymbolKind::NoType,
+                });
+            }
+        }
+
+        let (locals, globals): (Vec<_>, Vec<_>) = self
+            .symbols
+            .iter()
+            .cloned()
+            .chain(undefined)
+            .partition(|symbol| symbol.binding == SymbolBinding::Local);
+
+        let null = Symbol {
+            name: String::new(),
+            section: None,
+            value: 0,
+            size: 0,
+            binding: SymbolBinding::Local,
+            kind: SymbolKind::NoType,
+        };
+        std::iter::once(null)
+            .chain(section_symbols)
+            .chain(locals)
+            .chain(globals)
+            .collect()
+    }
+
+    /// Serialize the object file
+    pub fn write(&self) -> Vec<u8> {
+        let symbols = self.get_symbol_table();
+        let symbol_index: HashMap<&str, usize> = symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| !symbol.name.is_empty())
+            .map(|(idx, symbol)| (symbol.name.as_str(), idx))
+            .rev()
+            .collect();
+        let first_global = symbols
+            .iter()
+            .position(|symbol| symbol.binding != SymbolBinding::Local)
+            .unwrap_or(symbols.len());
+
+        let mut out = Writer {
+            data: vec![0; EHDR_SIZE as usize],
+            endianness: self.endianness,
+        };
+        let mut shstrtab = StringTable::new();
+        let mut headers = vec![SectionHeader {
+            name: 0,
+            kind: 0,
+            flags: 0,
+            offset: 0,
+            size: 0,
+            link: 0,
+            info: 0,
+            align: 0,
+            entsize: 0,
+        }];
+
+        for section in &self.sections {
+            out.align(section.align);
+            let offset = out.offset();
+            let kind = if section.is_nobits() {
+                SHT_NOBITS
+            } else {
+                out.data.extend_from_slice(&section.data);
+                SHT_PROGBITS
+            };
+            headers.push(SectionHeader {
+                name: shstrtab.add(&section.name),
+                kind,
+                flags: section.get_flags(),
+                offset,
+                size: section.get_size(),
+                link: 0,
+                info: 0,
+                align: section.align.max(1),
+                entsize: 0,
+            });
+        }
+
+        // Relocation sections link to the symbol table, that follows them
+        let num_relas = self
+            .sections
+            .iter()
+            .filter(|section| !section.relocations.is_empty())
+            .count();
+        let symtab_index = (headers.len() + num_relas) as u32;
+        for (idx, section) in self.sections.iter().enumerate() {
+            if section.relocations.is_empty() {
+                continue;
+            }
+            out.align(8);
+            let offset = out.offset();
+            for reloc in &section.relocations {
+                let symbol = symbol_index[reloc.symbol.as_str()] as u64;
+                out.u64(reloc.offset);
+                out.u64((symbol << 32) | reloc.kind as u64);
+                out.u64(reloc.addend as u64);
+            }
+            headers.push(SectionHeader {
+                name: shstrtab.add(&format!(".rela{}", section.name)),
+                kind: SHT_RELA,
+                flags: SHF_INFO_LINK,
+                offset,
+                size: out.offset() - offset,
+                link: symtab_index,
+                info: idx as u32 + 1,
+                align: 8,
+                entsize: RELA_SIZE,
+            });
+        }
+
+        let mut strtab = StringTable::new();
+        out.align(8);
+        let symtab_offset = out.offset();
+        for symbol in &symbols {
+            out.u32(strtab.add(&symbol.name));
+            out.u8((symbol.binding.encode() << 4) | symbol.kind.encode());
+            out.u8(0);
+            out.u16(symbol.section.map_or(0, |idx| idx as u16 + 1));
+            out.u64(symbol.value);
+            out.u64(symbol.size);
+        }
+        headers.push(SectionHeader {
+            name: shstrtab.add(".symtab"),
+            kind: SHT_SYMTAB,
+            flags: 0,
+            offset: symtab_offset,
+            size: out.offset() - symtab_offset,
+            link: symtab_index + 1,
+            info: first_global as u32,
+            align: 8,
+            entsize: SYM_SIZE,
+        });
+
+        let strtab_offset = out.offset();
+        out.data.extend_from_slice(&strtab.data);
+        headers.push(SectionHeader {
+            name: shstrtab.add(".strtab"),
+            kind: SHT_STRTAB,
+            flags: 0,
+            offset: strtab_offset,
+            size: strtab.data.len() as u64,
+            link: 0,
+            info: 0,
+            align: 1,
+            entsize: 0,
+        });
+
+        let shstrtab_name = shstrtab.add(".shstrtab");
+        let shstrtab_offset = out.offset();
+        out.data.extend_from_slice(&shstrtab.data);
+        headers.push(SectionHeader {
+            name: shstrtab_name,
+            kind: SHT_STRTAB,
+            flags: 0,
+            offset: shstrtab_offset,
+            size: shstrtab.data.len() as u64,
+            link: 0,
+            info: 0,
+            align: 1,
+            entsize: 0,
+        });
+
+        out.align(8);
+        let shoff = out.offset();
+        for header in &headers {
+            out.u32(header.name);
+            out.u32(header.kind);
+            out.u64(header.flags);
+            out.u64(0);
+            out.u64(header.offset);
+            out.u64(header.size);
+            out.u32(header.link);
+            out.u32(header.info);
+            out.u64(header.align);
+            out.u64(header.entsize);
+        }
+
+        let body = std::mem::take(&mut out.data);
+        out.data.extend_from_slice(b"\x7fELF");
+        out.u8(2); // ELFCLASS64
+        out.u8(match self.endianness {
+            Endianness::Little => 1,
+            Endianness::Big => 2,
+        });
+        out.u8(1); // EV_CURRENT
+        out.data.resize(16, 0);
+        out.u16(ET_REL);
+        out.u16(self.machine);
+        out.u32(1);
+        out.u64(0); // e_entry
+        out.u64(0); // e_phoff
+        out.u64(shoff);
+        out.u32(self.flags);
+        out.u16(EHDR_SIZE as u16);
+        out.u16(0); // e_phentsize
+        out.u16(0); // e_phnum
+        out.u16(SHDR_SIZE as u16);
+        out.u16(headers.len() as u16);
+        out.u16(headers.len() as u16 - 1);
+        out.data.extend_from_slice(&body[EHDR_SIZE as usize..]);
+
+        out.data
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ElfError {
+    #[error("not an ELF file")]
+    BadMagic,
+    #[error("only 64-bit ELF files are supported")]
+    UnsupportedClass,
+    #[error("unknown data encoding `{0}`")]
+    UnknownEncoding(u8),
+    #[error("unexpected end of file, {0} bytes at offset {1:#x} are out of bounds")]
+    OutOfBounds(u64, u64),
+}
+
+/// Section of an ELF file, that has been read
+#[derive(Debug, Clone)]
+pub struct ElfSection {
+    pub name: String,
+    pub kind: u32,
+    pub flags: u64,
+    /// Virtual address of the section, that is zero in relocatable object files
+    pub address: u64,
+    pub size: u64,
+    pub align: u64,
+    /// Offset of the contents in the file
+    pub offset: u64,
+    /// Section specific information, i.e. the section header index of the section,
+    /// that relocations apply to
+    pub info: u32,
+    /// Contents of the section, that are empty for sections without data
+    pub data: Vec<u8>,
+    /// Relocations, that apply to the section. Relocations against a section symbol
+    /// refer to the name of the section.
+    pub relocations: Vec<Relocation>,
+}
+
+impl ElfSection {
+    /// Section occupies memory at runtime
+    pub fn is_alloc(&self) -> bool {
+        self.flags & SHF_ALLOC != 0
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.flags & SHF_EXECINSTR != 0
+    }
+
+    pub fn is_nobits(&self) -> bool {
+        self.kind == SHT_NOBITS
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.address && address - self.address < self.size
+    }
+}
+
+/// Object file, executable or shared library, that has been read
+#[derive(Debug)]
+pub struct ElfFile {
+    pub endianness: Endianness,
+    /// Type of the file, i.e. `ET_REL` or `ET_EXEC`
+    pub kind: u16,
+    pub machine: u16,
+    pub flags: u32,
+    pub entry: u64,
+    /// Sections in the order of the section header table without the null section
+    pub sections: Vec<ElfSection>,
+    /// Named symbols of the static symbol table. Section and file symbols, as well as
+    /// absolute symbols, are omitted.
+    pub symbols: Vec<Symbol>,
+}
+
+/// Bounds-checked view of the file, that reads integers in the given byte order
+struct Reader<'a> {
+    data: &'a [u8],
+    endianness: Endianness,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, offset: u64, size: u64) -> Result<&'a [u8], ElfError> {
+        offset
+            .checked_add(size)
+            .filter(|end| *end <= self.data.len() as u64)
+            .map(|end| &self.data[offset as usize..end as usize])
+            .ok_or(ElfError::OutOfBounds(size, offset))
+    }
+
+    fn u8(&self, offset: u64) -> Result<u8, ElfError> {
+        Ok(self.bytes(offset, 1)?[0])
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16, ElfError> {
+        let bytes = self.bytes(offset, 2)?.try_into().unwrap();
+        Ok(match self.endianness {
+            Endianness::Little => u16::from_le_bytes(bytes),
+            Endianness::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32, ElfError> {
+        let bytes = self.bytes(offset, 4)?.try_into().unwrap();
+        Ok(match self.endianness {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64, ElfError> {
+        let bytes = self.bytes(offset, 8)?.try_into().unwrap();
+        Ok(match self.endianness {
+            Endianness::Little => u64::from_le_bytes(bytes),
+            Endianness::Big => u64::from_be_bytes(bytes),
+        })
+    }
+
+    /// Null-terminated string at the offset of the string table
+    fn string(&self, table: &[u8], offset: u32) -> String {
+        let tail = table.get(offset as usize..).unwrap_or_default();
+        let end = tail.iter().position(|c| *c == 0).unwrap_or(tail.len());
+        String::from_utf8_lossy(&tail[..end]).into_owned()
+    }
+}
+
+impl ElfFile {
+    /// Read the headers, sections and the symbol table of an ELF64 file
+    pub fn read(data: &[u8]) -> Result<ElfFile, ElfError> {
+        if !data.starts_with(b"\x7fELF") {
+            return Err(ElfError::BadMagic);
+        }
+        let mut reader = Reader {
+            data,
+            endianness: Endianness::Little,
+        };
+        if reader.u8(4)? != 2 {
+            return Err(ElfError::UnsupportedClass);
+        }
+        reader.endianness = match reader.u8(5)? {
+            1 => Endianness::Little,
+            2 => Endianness::Big,
+            encoding => return Err(ElfError::UnknownEncoding(encoding)),
+        };
+
+        let shoff = reader.u64(40)?;
+        let shentsize = reader.u16(58)? as u64;
+        let shnum = reader.u16(60)? as u64;
+        let shstrndx = reader.u16(62)? as u64;
+
+        let mut headers = vec![];
+        for idx in 0..shnum {
+            let header = shoff + idx * shentsize;
+            let kind = reader.u32(header + 4)?;
+            let offset = reader.u64(header + 24)?;
+            let size = reader.u64(header + 32)?;
+            let data = if kind == SHT_NOBITS {
+                vec![]
+            } else {
+                reader.bytes(offset, size)?.to_vec()
+            };
+            let section = ElfSection {
+                name: String::new(),
+                kind,
+                flags: reader.u64(header + 8)?,
+                address: reader.u64(header + 16)?,
+                size,
+                align: reader.u64(header + 48)?,
+                offset,
+                info: reader.u32(header + 44)?,
+                data,
+                relocations: vec![],
+            };
+            headers.push((reader.u32(header)?, reader.u32(header + 40)?, section));
+        }
+
+        let shstrtab = headers
+            .get(shstrndx as usize)
+            .map(|(_, _, section)| section.data.clone())
+            .unwrap_or_default();
+        for (name, _, section) in headers.iter_mut() {
+            section.name = reader.string(&shstrtab, *name);
+        }
+
+        let mut symbols = vec![];
+        // Names of all entries of the symbol table, that relocations refer to by index
+        let mut symbol_names = vec![String::new()];
+        let symtab = headers
+            .iter()
+            .find(|(_, _, section)| section.kind == SHT_SYMTAB);
+        if let Some((_, link, symtab)) = symtab {
+            let strtab = headers
+                .get(*link as usize)
+                .map(|(_, _, section)| section.data.as_slice())
+                .unwrap_or_default();
+            let symtab = Reader {
+                data: &symtab.data,
+                endianness: reader.endianness,
+            };
+            // The first symbol is the null symbol
+            for idx in 1..symtab.data.len() as u64 / SYM_SIZE {
+                let entry = idx * SYM_SIZE;
+                let info = symtab.u8(entry + 4)?;
+                let shndx = symtab.u16(entry + 6)?;
+                let binding = SymbolBinding::decode(info >> 4);
+                let kind = SymbolKind::decode(info & 0xf);
+                let name = match (kind, headers.get(shndx as usize)) {
+                    (Some(SymbolKind::Section), Some((_, _, section))) => section.name.clone(),
+                    _ => reader.string(strtab, symtab.u32(entry)?),
+                };
+                symbol_names.push(name.clone());
+                let (Some(binding), Some(kind)) = (binding, kind) else {
+                    continue;
+                };
+                if kind == SymbolKind::Section || shndx >= SHN_LORESERVE {
+                    continue;
+                }
+                symbols.push(Symbol {
+                    name,
+                    section: (shndx != 0).then(|| shndx as usize - 1),
+                    value: symtab.u64(entry + 8)?,
+                    size: symtab.u64(entry + 16)?,
+                    binding,
+                    kind,
+                });
+            }
+        }
+
+        let mut relocations = vec![vec![]; headers.len()];
+        for (_, _, section) in &headers {
+            let entry_size = match section.kind {
+                SHT_RELA => RELA_SIZE,
+                SHT_REL => REL_SIZE,
+                _ => continue,
+            };
+            let Some(target) = relocations.get_mut(section.info as usize) else {
+                continue;
+            };
+            let entries = Reader {
+                data: &section.data,
+                endianness: reader.endianness,
+            };
+            for idx in 0..section.data.len() as u64 / entry_size {
+                let entry = idx * entry_size;
+                let info = entries.u64(entry + 8)?;
+                let addend = match section.kind {
+                    SHT_RELA => entries.u64(entry + 16)? as i64,
+                    _ => 0,
+                };
+                target.push(Relocation {
+                    offset: entries.u64(entry)?,
+                    kind: info as u32,
+                    symbol: symbol_names
+                        .get((info >> 32) as usize)
+                        .cloned()
+                        .unwrap_or_default(),
+                    addend,
+                });
+            }
+        }
+        for ((_, _, section), relocations) in headers.iter_mut().zip(relocations) {
+            section.relocations = relocations;
+        }
+
+        Ok(ElfFile {
+            endianness: reader.endianness,
+            kind: reader.u16(16)?,
+            machine: reader.u16(18)?,
+            flags: reader.u32(48)?,
+            entry: reader.u64(24)?,
+            sections: headers
+                .into_iter()
+                .skip(1)
+                .map(|(_, _, section)| section)
+                .collect(),
+            symbols,
+        })
+    }
+
+    pub fn is_relocatable(&self) -> bool {
+        self.kind == ET_REL
+    }
+
+    /// Offsets of the relocations, that apply to the section. Offsets are relative to
+    /// the section in relocatable files and virtual addresses otherwise.
+    pub fn get_relocation_offsets(&self, section: usize) -> Vec<u64> {
+        self.sections[section]
+            .relocations
+            .iter()
+            .map(|relocation| relocation.offset)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_object() {
+        let mut object = ObjectFile::new(Endianness::Little, EM_RISCV, 0x5);
+        let mut text = Section::new(".text");
+        text.data = vec![0x13, 0, 0, 0];
+        text.align = 4;
+        text.relocations.push(Relocation {
+            offset: 0,
+            kind: 19,
+            symbol: "puts".to_string(),
+            addend: 0,
+        });
+        object.sections.push(text);
+        let mut bss = Section::new(".bss");
+        bss.zero_size = 16;
+        object.sections.push(bss);
+        object.symbols.push(Symbol {
+            name: "main".to_string(),
+            section: Some(0),
+            value: 0,
+            size: 4,
+            binding: SymbolBinding::Global,
+            kind: SymbolKind::Func,
+        });
+        object.symbols.push(Symbol {
+            name: "counter".to_string(),
+            section: Some(1),
+            value: 0,
+            size: 16,
+            binding: SymbolBinding::Local,
+            kind: SymbolKind::Object,
+        });
+
+        // Local symbols precede the global ones, undefined symbols are added
+        let names: Vec<_> = object
+            .get_symbol_table()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.binding))
+            .collect();
+        assert_eq!(
+            names[3..],
+            [
+                ("counter".to_string(), SymbolBinding::Local),
+                ("main".to_string(), SymbolBinding::Global),
+                ("puts".to_string(), SymbolBinding::Global),
+            ]
+        );
+
+        let data = object.write();
+        assert_eq!(&data[0..4], b"\x7fELF");
+        assert_eq!(data[4], 2);
+        assert_eq!(data[5], 1);
+        assert_eq!(u16::from_le_bytes([data[16], data[17]]), ET_REL);
+        assert_eq!(u16::from_le_bytes([data[18], data[19]]), EM_RISCV);
+        assert_eq!(u32::from_le_bytes(data[48..52].try_into().unwrap()), 0x5);
+        // null, .text, .bss, .rela.text, .symtab, .strtab, .shstrtab
+        assert_eq!(u16::from_le_bytes([data[60], data[61]]), 7);
+        assert_eq!(u16::from_le_bytes([data[62], data[63]]), 6);
+
+        let shoff = u64::from_le_bytes(data[40..48].try_into().unwrap()) as usize;
+        assert_eq!(data.len(), shoff + 7 * SHDR_SIZE as usize);
+        let section_header = |idx: usize| &data[shoff + idx * SHDR_SIZE as usize..];
+        // .bss occupies no space in the file
+        let bss = section_header(2);
+        assert_eq!(
+            u32::from_le_bytes(bss[4..8].try_into().unwrap()),
+            SHT_NOBITS
+        );
+        assert_eq!(u64::from_le_bytes(bss[32..40].try_into().unwrap()), 16);
+        // sh_info of .symtab is the index of the first global symbol
+        let symtab = section_header(4);
+        assert_eq!(
+            u32::from_le_bytes(symtab[4..8].try_into().unwrap()),
+            SHT_SYMTAB
+        );
+        assert_eq!(u32::from_le_bytes(symtab[44..48].try_into().unwrap()), 4);
+    }
+
+    #[test]
+    fn read_object() {
+        let mut object = ObjectFile::new(Endianness::Little, EM_RISCV, 0x1);
+        let mut text = Section::new(".text");
+        text.data = vec![0x67, 0x80, 0x00, 0x00];
+        text.relocations.push(Relocation {
+            offset: 0,
+            kind: 18,
+            symbol: "main".to_string(),
+            addend: 0,
+        });
+        object.sections.push(text);
+        let mut bss = Section::new(".bss");
+        bss.zero_size = 8;
+        object.sections.push(bss);
+        object.symbols.push(Symbol {
+            name: "main".to_string(),
+            section: Some(0),
+            value: 0,
+            size: 4,
+            binding: SymbolBinding::Global,
+            kind: SymbolKind::Func,
+        });
+        object.symbols.push(Symbol {
+            name: "counter".to_string(),
+            section: Some(1),
+            value: 0,
+            size: 8,
+            binding: SymbolBinding::Local,
+            kind: SymbolKind::Object,
+        });
+
+        let data = object.write();
+        let elf = ElfFile::read(&data).unwrap();
+        assert_eq!(elf.kind, ET_REL);
+        assert_eq!(elf.machine, EM_RISCV);
+        assert_eq!(elf.flags, 0x1);
+
+        let names: Vec<_> = elf
+            .sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ".text",
+                ".bss",
+                ".rela.text",
+                ".symtab",
+                ".strtab",
+                ".shstrtab"
+            ]
+        );
+        assert!(elf.sections[0].is_executable());
+        assert_eq!(elf.sections[0].data, [0x67, 0x80, 0x00, 0x00]);
+        let offset = elf.sections[0].offset as usize;
+        assert_eq!(data[offset..offset + 4], elf.sections[0].data);
+        assert_eq!(elf.get_relocation_offsets(0), [0]);
+        assert_eq!(elf.sections[0].relocations, object.sections[0].relocations);
+        assert!(elf.get_relocation_offsets(1).is_empty());
+        assert!(elf.sections[1].is_nobits());
+        assert!(elf.sections[1].data.is_empty());
+        assert_eq!(elf.sections[1].size, 8);
+
+        // Section symbols are omitted, locals precede globals
+        assert_eq!(
+            elf.symbols,
+            [object.symbols[1].clone(), object.symbols[0].clone()]
+        );
+    }
+
+    #[test]
+    fn read_errors() {
+        assert_eq!(ElfFile::read(b"\x7fELX").unwrap_err(), ElfError::BadMagic);
+        assert_eq!(
+            ElfFile::read(b"\x7fELF\x01\x01").unwrap_err(),
+            ElfError::UnsupportedClass
+        );
+
+        let mut data = ObjectFile::new(Endianness::Little, EM_RISCV, 0).write();
+        data.truncate(data.len() - SHDR_SIZE as usize / 2);
+        assert!(matches!(
+            ElfFile::read(&data).unwrap_err(),
+            ElfError::OutOfBounds(..)
+        ));
+    }
+}
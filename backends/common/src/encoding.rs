@@ -0,0 +1,115 @@
+//! Bit strings of instruction encodings, that are built field by field, starting from
+//! the most significant one. Used by the encoders and decoders generated from tmdl.
+
+/// Encoding, that fields are appended to from the most significant end
+#[derive(Default)]
+pub struct BitEncoder {
+    bits: u64,
+    width: u32,
+}
+
+impl BitEncoder {
+    /// Append the lower `width` bits of the value to the encoding
+    pub fn push(mut self, value: u64, width: u32) -> Self {
+        self.bits = (self.bits << width) | (value & mask(width));
+        self.width += width;
+        self
+    }
+
+    /// Little-endian bytes of the encoding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = self.width.div_ceil(8) as usize;
+        self.bits.to_le_bytes()[..len].to_vec()
+    }
+}
+
+/// Encoding, that fields are taken from, starting with the most significant one
+pub struct BitDecoder {
+    bits: u64,
+    offset: u32,
+}
+
+impl BitDecoder {
+    /// Read the encoding of the given width in bits from the start of the stream.
+    /// Returns `None` if the stream is too short.
+    pub fn new(stream: &[u8], width: u32) -> Option<Self> {
+        let len = width.div_ceil(8) as usize;
+        let bits = stream
+            .get(..len)?
+            .iter()
+            .rev()
+            .fold(0, |value, byte| (value << 8) | *byte as u64);
+        Some(Self {
+            bits: bits & mask(width),
+            offset: width,
+        })
+    }
+
+    /// Take the next `width` bits as an unsigned value
+    pub fn take(&mut self, width: u32) -> u64 {
+        self.offset -= width;
+        (self.bits >> self.offset) & mask(width)
+    }
+
+    /// Take the next `width` bits as a two's complement value
+    pub fn take_signed(&mut self, width: u32) -> i64 {
+        let value = self.take(width);
+        sign_extend(value, width)
+    }
+}
+
+/// Interpret the lower `width` bits of the value as a two's complement number
+pub fn sign_extend(value: u64, width: u32) -> i64 {
+    let shift = u64::BITS - width;
+    ((value << shift) as i64) >> shift
+}
+
+fn mask(width: u32) -> u64 {
+    u64::MAX.checked_shr(u64::BITS - width).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        // add x28, x6, x7
+        let bytes = BitEncoder::default()
+            .push(0, 7)
+            .push(7, 5)
+            .push(6, 5)
+            .push(0, 3)
+            .push(28, 5)
+            .push(0b0110011, 7)
+            .to_bytes();
+        assert_eq!(bytes, vec![0x33, 0x0e, 0x73, 0x00]);
+
+        let mut fields = BitDecoder::new(&bytes, 32).unwrap();
+        assert_eq!(fields.take(7), 0);
+        assert_eq!(fields.take(5), 7);
+        assert_eq!(fields.take(5), 6);
+        assert_eq!(fields.take(3), 0);
+        assert_eq!(fields.take(5), 28);
+        assert_eq!(fields.take(7), 0b0110011);
+
+        assert!(BitDecoder::new(&bytes[..3], 32).is_none());
+    }
+
+    #[test]
+    fn test_signed_fields() {
+        let bytes = BitEncoder::default()
+            .push(-2048i64 as u64, 12)
+            .push(0xf, 4)
+            .to_bytes();
+        assert_eq!(bytes, vec![0x0f, 0x80]);
+
+        let mut fields = BitDecoder::new(&bytes, 16).unwrap();
+        assert_eq!(fields.take_signed(12), -2048);
+        assert_eq!(fields.take_signed(4), -1);
+
+        // Fields that are assembled from several slices
+        assert_eq!(sign_extend(0b1_0000_0000_0000, 13), -4096);
+        assert_eq!(sign_extend(0b0_1111_1111_1110, 13), 4094);
+    }
+}
@@ -40,10 +40,39 @@ populate_dialect_ops!(
     SllOp,
     SrlOp,
     SraOp,
+    SltOp,
+    SltuOp,
+    MulOp,
+    MulhOp,
+    DivOp,
+    RemOp,
     CompInstrOp,
     CompInstrEndOp,
     LoadOp,
-    StoreOp
+    StoreOp,
+    BranchOp,
+    JumpOp,
+    LoadImmOp,
+    FenceOp,
+    TrapOp,
+    SysRegOp,
+    LoadReservedOp,
+    StoreConditionalOp,
+    AtomicRmwOp,
+    FAddOp,
+    FSubOp,
+    FMulOp,
+    FDivOp,
+    FSqrtOp,
+    FMaddOp,
+    FMinOp,
+    FMaxOp,
+    FSgnjOp,
+    FCmpOp,
+    FClassOp,
+    FCvtOp,
+    FMvOp,
+    FLoadOp
 );
 populate_dialect_types!();
 
@@ -1,114 +1,91 @@
-use lpl::{ParseResult, ParseStream, Parser};
-use tir_core::{
-    parser::single_block_region, IRFormatter, IRStrStream, Op, OpAssembly, OpImpl, OpRef,
-    Printable, RegionRef, Terminator,
-};
+use lpl::{ParseStream, Parser};
+use tir_core::*;
 use tir_macros::{op_implements, Op, OpAssembly, OpValidator};
 
 use crate::isema::DIALECT_NAME;
 
-/// A compound instruction
-///
-/// Sometimes operations can do multiple things at once. To allow one model complex instructions
-/// and still preserve operation atomicity, introduce a container operation, that can represent
-/// instructions as a combination of simpler operations.
-#[derive(Op, Debug, Clone, OpValidator)]
-#[operation(name = "comp_instr", dialect = isema, known_attrs(asm: String))]
-pub struct CompInstrOp {
-    #[region(single_block, no_args)]
-    body: RegionRef,
-    r#impl: OpImpl,
-}
+include!(concat!(env!("OUT_DIR"), "/defs/isema.rs"));
 
-/// Terminator for compound instructions
-#[derive(Op, Debug, Clone, OpAssembly, OpValidator)]
-#[operation(name = "comp_instr_end", dialect = isema)]
-pub struct CompInstrEndOp {
-    r#impl: OpImpl,
+#[op_implements(dialect = isema)]
+impl MemoryEffects for LoadOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read]
+    }
 }
 
 #[op_implements(dialect = isema)]
-impl Terminator for CompInstrEndOp {}
+impl MemoryEffects for StoreOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Write]
+    }
+}
 
-impl OpAssembly for CompInstrOp {
-    fn parse_assembly(input: IRStrStream) -> ParseResult<IRStrStream, OpRef>
-    where
-        Self: Sized,
-    {
-        let parser = single_block_region().map_with(|ops, extra| {
-            let state = extra.unwrap();
-            let context = state.context();
+#[op_implements(dialect = isema)]
+impl MemoryEffects for FenceOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read, MemoryEffect::Write]
+    }
+}
 
-            let comp = CompInstrOp::builder(&context).build();
-            for op in ops {
-                comp.borrow_mut().get_body().push(&op);
-            }
+/// The execution environment may access any memory
+#[op_implements(dialect = isema)]
+impl MemoryEffects for TrapOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read, MemoryEffect::Write]
+    }
+}
 
-            let comp: OpRef = comp;
-            comp
-        });
+#[op_implements(dialect = isema)]
+impl MemoryEffects for LoadReservedOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read]
+    }
+}
 
-        parser.parse(input)
+#[op_implements(dialect = isema)]
+impl MemoryEffects for StoreConditionalOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Write]
     }
+}
 
-    fn print_assembly(&self, fmt: &mut dyn IRFormatter) {
-        fmt.start_region();
-        let body = self.get_body();
-        for op in body.iter() {
-            op.borrow().print(fmt);
-        }
-        fmt.end_region();
+#[op_implements(dialect = isema)]
+impl MemoryEffects for AtomicRmwOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read, MemoryEffect::Write]
     }
 }
 
-/// Load data from memory to register
-#[derive(Op, Debug, Clone, OpAssembly, OpValidator)]
-#[operation(
-    name = "load",
-    dialect = isema,
-    known_attrs(dst: String, base_addr: String, offset: i16, width: u8, sign_extend: bool)
-)]
-pub struct LoadOp {
-    r#impl: OpImpl,
+#[op_implements(dialect = isema)]
+impl MemoryEffects for FLoadOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read]
+    }
 }
 
-/// Store data from register to memory
-#[derive(Op, Debug, Clone, OpAssembly, OpValidator)]
-#[operation(
-    name = "store",
-    dialect = isema,
-    known_attrs(src: String, base_addr: String, offset: i16, width: u8)
-)]
-pub struct StoreOp {
-    r#impl: OpImpl,
+/// Resolve a label to a block of the region, that contains the operation
+fn resolve_label(op: &dyn Op, label: &str) -> Option<BlockRef> {
+    op.get_parent_region()?.get_block_by_name(label)
 }
 
-// Three-register operations
+#[op_implements(dialect = isema)]
+impl BranchOpInterface for BranchOp {
+    fn get_successors(&self) -> Vec<BlockRef> {
+        resolve_label(self, self.target()).into_iter().collect()
+    }
 
-macro_rules! three_reg_ops {
-    ($($struct_name:ident => { name = $op_name:literal, doc = $doc:literal })*) => {
-        $(
-            #[doc = $doc]
-            #[derive(Op, Debug, Clone, OpAssembly, OpValidator)]
-            #[operation(
-                name = $op_name,
-                dialect = isema,
-                known_attrs(rs1: String, rs2: Option<String>, imm: Option<I16Attr>, rd: String, width: u8)
-            )]
-            pub struct $struct_name {
-                r#impl: OpImpl,
-            }
-        )*
-    };
+    fn may_fall_through(&self) -> bool {
+        true
+    }
 }
 
-three_reg_ops! {
-    AddOp => {name = "add", doc = "Compute rs1 + rs2 and store result to rd"}
-    SubOp => {name = "sub", doc = "Compute rs1 - rs2 and store result to rd"}
-    AndOp => {name = "and", doc = "Compute bitwise rs1 `and` rs2 and store result to rd"}
-    OrOp => {name = "or", doc = "Compute bitwise rs1 `or` rs2 and store result to rd"}
-    XorOp => {name = "xor", doc = "Compute bitwise rs1 `xor` rs2 and store result to rd"}
-    SllOp => {name = "sll", doc = "Compute shift left logical rs1 << rs2 and store result to rd"}
-    SrlOp => {name = "srl", doc = "Compute shift right logical rs1 >> rs2 and store result to rd"}
-    SraOp => {name = "sra", doc = "Compute shift right arithmetic rs1 >> rs2 and store result to rd"}
+#[op_implements(dialect = isema)]
+impl BranchOpInterface for JumpOp {
+    /// Indirect jumps have no statically known successors
+    fn get_successors(&self) -> Vec<BlockRef> {
+        self.target()
+            .and_then(|target| resolve_label(self, target))
+            .into_iter()
+            .collect()
+    }
 }
@@ -5,36 +5,58 @@ use std::{
     collections::HashMap,
     ops::{Bound, RangeBounds},
     rc::Rc,
+    sync::Arc,
 };
 
 use lpl::{
     combinators::{
-        any_whitespace1, eof, interleaved, lang::ident, lang::line_comment, literal, spaced,
-        text::dec_number,
+        any_whitespace1, eof, interleaved,
+        lang::{ident, line_comment},
+        literal, spaced,
+        text::{string_literal, StringConfig},
     },
     Diagnostic, ParseStream, Parser, Spanned, StrStream,
 };
-use tir_core::OpBuilder;
+use tir_core::{OpBuilder, RwCell};
 
 use crate::target::SectionOp;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AsmToken<'a> {
+    /// Directive name including the leading dot, i.e. `.text`. Local symbols, like
+    /// `.LBB0_1`, are lexed as directives too.
     Directive(&'a str),
     Ident(&'a str),
     Label(&'a str),
     Comment(&'a str),
     Number(i64),
+    /// String literal without quotes. Escape sequences are not processed.
+    String(&'a str),
+    /// Relocation operator, i.e. `hi` for `%hi`
+    Modifier(&'a str),
+    /// Symbol or section type, i.e. `function` for `@function`
+    Tag(&'a str),
     OpenParen,
     CloseParen,
     Comma,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Tilde,
+    Ampersand,
+    Pipe,
+    Caret,
+    ShiftLeft,
+    ShiftRight,
 }
 
 #[derive(Debug, Clone)]
 pub struct AsmParserContext {
     builder: OpBuilder,
-    sections: Rc<RefCell<HashMap<String, Rc<RefCell<SectionOp>>>>>,
-    active_section: Rc<RefCell<Option<Rc<RefCell<SectionOp>>>>>,
+    sections: Rc<RefCell<HashMap<String, Arc<RwCell<SectionOp>>>>>,
+    active_section: Rc<RefCell<Option<Arc<RwCell<SectionOp>>>>>,
+    constants: Rc<RefCell<HashMap<String, i64>>>,
 }
 
 impl AsmParserContext {
@@ -43,6 +65,7 @@ impl AsmParserContext {
             builder,
             sections: Rc::new(RefCell::new(HashMap::new())),
             active_section: Rc::new(RefCell::new(None)),
+            constants: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
@@ -50,23 +73,32 @@ impl AsmParserContext {
         self.builder.clone()
     }
 
-    pub fn get_section(&self, name: &str) -> Option<Rc<RefCell<SectionOp>>> {
+    pub fn get_section(&self, name: &str) -> Option<Arc<RwCell<SectionOp>>> {
         self.sections.borrow().get(name).cloned()
     }
 
-    pub fn add_section(&self, name: &str, section: &Rc<RefCell<SectionOp>>) {
+    pub fn add_section(&self, name: &str, section: &Arc<RwCell<SectionOp>>) {
         self.sections
             .borrow_mut()
             .insert(name.to_owned(), section.clone());
     }
 
-    pub fn get_active_section(&self) -> Option<Rc<RefCell<SectionOp>>> {
+    pub fn get_active_section(&self) -> Option<Arc<RwCell<SectionOp>>> {
         self.active_section.borrow().clone()
     }
 
-    pub fn set_active_section(&self, section: Rc<RefCell<SectionOp>>) {
+    pub fn set_active_section(&self, section: Arc<RwCell<SectionOp>>) {
         *self.active_section.borrow_mut() = Some(section);
     }
+
+    /// Value of a symbol, that is defined by `.equ` or `.set`
+    pub fn get_constant(&self, name: &str) -> Option<i64> {
+        self.constants.borrow().get(name).cloned()
+    }
+
+    pub fn set_constant(&self, name: &str, value: i64) {
+        self.constants.borrow_mut().insert(name.to_owned(), value);
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -150,14 +182,20 @@ impl<'a> ParseStream<'a> for TokenStream<'a> {
     }
 }
 
+/// Mnemonics of some instructions contain dots, like `fence.tso`
 fn allowed_ident_char(c: char) -> bool {
-    c == '_'
+    c == '_' || c == '.'
 }
 
 fn directive<'a>() -> impl Parser<'a, StrStream<'a>, AsmToken<'a>> {
-    literal(".")
-        .and_then(ident(allowed_ident_char))
-        .map(|(_, ident_str)| AsmToken::Directive(ident_str))
+    ident(allowed_ident_char)
+        .try_map(|ident_str, span| {
+            if ident_str.starts_with('.') {
+                Ok(AsmToken::Directive(ident_str))
+            } else {
+                Err(lpl::InternalError::PredNotSatisfied(span).into())
+            }
+        })
         .label("asm_directive")
 }
 
@@ -168,21 +206,80 @@ fn label<'a>() -> impl Parser<'a, StrStream<'a>, AsmToken<'a>> {
         .label("asm_label")
 }
 
+/// Unsigned decimal, hexadecimal (`0x`) or binary (`0b`) number. Numbers, that exceed
+/// `i64`, wrap around, so that `0xffffffffffffffff` is -1.
+fn number<'a>() -> impl Parser<'a, StrStream<'a>, AsmToken<'a>> {
+    move |input: StrStream<'a>| {
+        let (radix, prefix_len) = match input.substr(0..2) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("0x") => (16, 2),
+            Some(prefix) if prefix.eq_ignore_ascii_case("0b") => (2, 2),
+            _ => (10, 0),
+        };
+        let digits: usize = input
+            .chars()
+            .skip(prefix_len)
+            .take_while(|c| c.is_digit(radix))
+            .map(|c| c.len_utf8())
+            .sum();
+        let number = input
+            .substr(prefix_len..prefix_len + digits)
+            .filter(|_| digits > 0)
+            .and_then(|digits| u64::from_str_radix(digits, radix).ok())
+            .ok_or_else(|| Diagnostic::from(lpl::InternalError::PredNotSatisfied(input.span())))?;
+        Ok((
+            AsmToken::Number(number as i64),
+            input.slice(prefix_len + digits..input.len()),
+        ))
+    }
+}
+
+fn string<'a>() -> impl Parser<'a, StrStream<'a>, AsmToken<'a>> {
+    string_literal(StringConfig::default())
+        .map(|literal| AsmToken::String(&literal[1..literal.len() - 1]))
+        .label("asm_string")
+}
+
+/// Relocation operators, like `%hi`, and symbol types, like `@function`
+fn prefixed<'a>() -> impl Parser<'a, StrStream<'a>, AsmToken<'a>> {
+    literal("%")
+        .and_then(ident(allowed_ident_char))
+        .map(|(_, name)| AsmToken::Modifier(name))
+        .or_else(
+            literal("@")
+                .and_then(ident(allowed_ident_char))
+                .map(|(_, name)| AsmToken::Tag(name)),
+        )
+        .label("asm_prefixed")
+}
+
 fn punct<'a>() -> impl Parser<'a, StrStream<'a>, AsmToken<'a>> {
     literal("(")
         .map(|_| AsmToken::OpenParen)
         .or_else(literal(")").map(|_| AsmToken::CloseParen))
         .or_else(literal(",").map(|_| AsmToken::Comma))
+        .or_else(literal("+").map(|_| AsmToken::Plus))
+        .or_else(literal("-").map(|_| AsmToken::Minus))
+        .or_else(literal("*").map(|_| AsmToken::Star))
+        .or_else(literal("/").map(|_| AsmToken::Slash))
+        .or_else(literal("~").map(|_| AsmToken::Tilde))
+        .or_else(literal("&").map(|_| AsmToken::Ampersand))
+        .or_else(literal("|").map(|_| AsmToken::Pipe))
+        .or_else(literal("^").map(|_| AsmToken::Caret))
+        .or_else(literal("<<").map(|_| AsmToken::ShiftLeft))
+        .or_else(literal(">>").map(|_| AsmToken::ShiftRight))
         .label("asm_punct")
 }
 
-pub fn lex_asm(input: &str) -> Result<Vec<Spanned<AsmToken>>, Diagnostic> {
+pub fn lex_asm(input: &str) -> Result<Vec<Spanned<AsmToken<'_>>>, Diagnostic> {
     let stream: StrStream = input.into();
 
-    let token = directive()
-        .or_else(label())
+    // Labels go first, as local labels start with a dot, like directives
+    let token = label()
+        .or_else(directive())
         .or_else(ident(allowed_ident_char).map(AsmToken::Ident))
-        .or_else(dec_number().map(AsmToken::Number))
+        .or_else(number())
+        .or_else(string())
+        .or_else(prefixed())
         .or_else(punct())
         .label("asm_token");
 
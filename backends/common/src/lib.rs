@@ -1,4 +1,6 @@
 mod diagnostic;
+pub mod elf;
+pub mod encoding;
 pub mod isema;
 mod lexer;
 pub mod parser;
@@ -7,10 +9,16 @@ mod target_options;
 
 pub use diagnostic::*;
 pub use lexer::*;
-use lpl::{combinators::NotTuple, ParseResult};
+use lpl::{
+    combinators::{literal, text::take_while, NotTuple},
+    InternalError, ParseResult, Parser,
+};
 pub use target_options::*;
 
-use tir_core::{parser::Parsable, Attr, Printable, Result};
+use tir_core::{
+    parser::Parsable, Attr, ContextRef, Generate, GenerateResult, OpGenerator, OperandType,
+    Printable, Result, Type,
+};
 
 use thiserror::Error;
 
@@ -29,10 +37,9 @@ pub trait BinaryEmittable {
         -> Result<()>;
 }
 
+/// Operation, that is printed as a single line of assembly, i.e. `addi a0, a0, 1`
 pub trait AsmPrintable {
-    fn print(&self, target_opts: &TargetOptions)
-    where
-        Self: Sized;
+    fn print(&self, target_opts: &TargetOptions) -> String;
 }
 
 pub trait ISAParser {
@@ -43,8 +50,14 @@ pub trait ISAParser {
 pub enum DisassemblerError {
     #[error("unexpected end of stream, need `{0}` more bytes, only `{1}` bytes left")]
     UnexpectedEndOfStream(usize, usize),
-    #[error("unknown disassembler error")]
-    Unknown,
+    /// Address of the word, that is its offset in raw streams, its raw encoding and
+    /// its length in bytes
+    #[error("unknown instruction `{1:#0w$x}` at `{0:#x}`", w = .2 * 2 + 2)]
+    UnknownInstruction(u64, u32, usize),
+    #[error(transparent)]
+    Elf(#[from] elf::ElfError),
+    #[error("unsupported machine `{0}`")]
+    UnsupportedMachine(u16),
 }
 
 impl<T: Into<Register<T>> + Printable + Parsable<T> + Copy> Printable for Register<T> {
@@ -58,11 +71,39 @@ impl<T: Into<Register<T>> + Printable + Parsable<T> + Copy> Printable for Regist
 
 impl<T: Into<Register<T>> + Printable + Parsable<T> + Copy> Parsable<Register<T>> for Register<T> {
     fn parse(input: tir_core::IRStrStream) -> ParseResult<tir_core::IRStrStream, Register<T>> {
+        let virtual_reg = literal("virt_reg<")
+            .and_then(take_while(|&c| c.is_ascii_digit()))
+            .and_then(literal(">"))
+            .flat()
+            .try_map(|(_, num, _), span| {
+                num.parse::<u64>()
+                    .map_err(|_| InternalError::PredNotSatisfied(span).into())
+            });
+        if let Ok((num, ni)) = virtual_reg.parse(input.clone()) {
+            return Ok((Register::Virtual(num), ni));
+        }
+
         let (reg, ni) = T::parse(input)?;
         Ok((Register::Architecture(reg), ni))
     }
 }
 
+impl<T: Into<Register<T>> + Printable + Parsable<T> + Copy> OperandType for Register<T> {
+    fn operand_type(&self, context: &ContextRef) -> Type {
+        target::RegType::build(context.clone()).into()
+    }
+}
+
+impl<T: Into<Register<T>> + Printable + Parsable<T> + Copy + Generate> Generate for Register<T> {
+    fn generate(gen: &mut OpGenerator) -> GenerateResult<Self> {
+        if gen.unstructured().ratio(1, 8)? {
+            Ok(Register::Virtual(gen.generate()?))
+        } else {
+            Ok(Register::Architecture(gen.generate()?))
+        }
+    }
+}
+
 #[allow(clippy::from_over_into)]
 impl<T: Into<Register<T>> + Printable + Parsable<T> + Into<tir_core::Attr> + Copy>
     Into<tir_core::Attr> for Register<T>
@@ -75,6 +116,20 @@ impl<T: Into<Register<T>> + Printable + Parsable<T> + Into<tir_core::Attr> + Cop
     }
 }
 
+impl<T: Into<Register<T>> + Printable + Parsable<T> + Into<tir_core::Attr> + Copy> From<Register<T>>
+    for String
+{
+    fn from(reg: Register<T>) -> String {
+        match reg {
+            Register::Virtual(virt) => format!("virt_reg<{}>", virt),
+            Register::Architecture(arch) => match arch.into() {
+                Attr::String(name) => name,
+                _ => unreachable!("architecture registers are named"),
+            },
+        }
+    }
+}
+
 impl<T: Into<Register<T>> + Printable + Parsable<T> + Copy> Register<T> {
     pub fn as_arch(&self) -> T {
         match &self {
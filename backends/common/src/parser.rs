@@ -1,8 +1,14 @@
 use crate::target::SectionOp;
 use crate::{AsmToken, DiagKind, TokenStream};
-use lpl::combinators::one_of;
+use lpl::combinators::{one_of, zero_or_more};
 use lpl::{Diagnostic, ParseStream, Parser};
-use tir_core::{Block, Region};
+use tir_core::{Block, Op, Region};
+
+mod directives;
+mod expr;
+
+pub use directives::*;
+pub use expr::*;
 
 pub fn parse_asm<'a>(
     input: TokenStream<'a>,
@@ -18,6 +24,8 @@ pub fn asm_ident<'a>() -> impl Parser<'a, TokenStream<'a>, &'a str> {
         let first = input.peek().unwrap();
         match first {
             AsmToken::Ident(ident) => Ok((ident, input.slice(1..input.len()))),
+            // Local symbols, like `.LBB0_1`, are lexed as directives
+            AsmToken::Directive(ident) if ident != "." => Ok((ident, input.slice(1..input.len()))),
             _ => Err(Into::<Diagnostic>::into(DiagKind::ExpectedIdent(
                 input.span(),
             ))),
@@ -78,14 +86,17 @@ pub fn close_paren<'a>() -> impl Parser<'a, TokenStream<'a>, ()> {
     parser.label("close_paren")
 }
 
+/// Integer literal with an optional minus sign
 pub fn number<'a>() -> impl Parser<'a, TokenStream<'a>, i64> {
     let parser = move |input: TokenStream<'a>| {
         if input.len() == 0 {
             return Err(Into::<Diagnostic>::into(DiagKind::EndOfStream));
         }
-        let first = input.peek().unwrap();
-        match first {
-            AsmToken::Number(num) => Ok((num, input.slice(1..input.len()))),
+        match (input.peek().unwrap(), input.nth(1)) {
+            (AsmToken::Number(num), _) => Ok((num, input.slice(1..input.len()))),
+            (AsmToken::Minus, Some(AsmToken::Number(num))) => {
+                Ok((num.wrapping_neg(), input.slice(2..input.len())))
+            }
             _ => Err(Into::<Diagnostic>::into(DiagKind::UnexpectedToken(
                 input.span(),
             ))),
@@ -103,25 +114,64 @@ pub fn section<'a>() -> impl Parser<'a, TokenStream<'a>, ()> {
             let builder = asm_ctx.get_builder();
             let context = builder.get_context();
 
-            let section = if let Some(section) = asm_ctx.get_section(name) {
+            let section = if let Some(section) = asm_ctx.get_section(&name) {
                 section
             } else {
+                // Sections follow each other at the top level
+                let parent = asm_ctx
+                    .get_active_section()
+                    .and_then(|active| active.borrow().get_parent_region())
+                    .and_then(|region| region.iter().last());
+                if let Some(block) = parent {
+                    builder.set_insertion_point_to_end(block);
+                }
+
                 let body = Region::empty(&context);
-                // FIXME(alexbatashev): need a better way to handle section names
                 let section = SectionOp::builder(&context)
-                    .name(format!(".{}", name).into())
+                    .name(name.clone())
                     .body(body)
                     .build();
                 builder.insert(&section);
-                asm_ctx.add_section(name, &section);
+                asm_ctx.add_section(&name, &section);
                 section
             };
 
+            // Continue at the end of a section, that is entered again
+            let last_block = section.borrow().get_body_region().iter().last();
+            if let Some(block) = last_block {
+                builder.set_insertion_point_to_end(block);
+            }
+
             asm_ctx.set_active_section(section);
         })
         .label("asm_section")
 }
 
+/// Directive or instruction, that belongs to the active section. Sections, that do not
+/// start with a label, get an `entry` block.
+pub fn section_statement<'a, P>(instr: P) -> impl Parser<'a, TokenStream<'a>, ()>
+where
+    P: Parser<'a, TokenStream<'a>, ()>,
+{
+    let directive = directive();
+    move |input: TokenStream<'a>| {
+        let asm_ctx = input.get_extra().unwrap();
+        if let Some(section) = asm_ctx.get_active_section() {
+            let body = section.borrow().get_body_region();
+            if body.first().is_none() {
+                let block = Block::empty(&body);
+                body.add_block(block.clone());
+                asm_ctx.get_builder().set_insertion_point_to_start(block);
+            }
+        }
+
+        match input.peek() {
+            Some(AsmToken::Directive(_)) => directive.parse(input),
+            _ => instr.parse(input),
+        }
+    }
+}
+
 pub fn label<'a>() -> impl Parser<'a, TokenStream<'a>, ()> {
     asm_label()
         .map_with(|name, asm_ctx| {
@@ -151,16 +201,76 @@ fn asm_label<'a>() -> impl Parser<'a, TokenStream<'a>, &'a str> {
     }
 }
 
-fn generic_section<'a>() -> impl Parser<'a, TokenStream<'a>, &'a str> {
+/// Section name, that may contain dashes, like `.note.GNU-stack`, or be quoted. Names
+/// without a leading dot, like `text`, get one.
+fn section_name<'a>() -> impl Parser<'a, TokenStream<'a>, String> {
+    let quoted = move |input: TokenStream<'a>| match input.peek() {
+        Some(AsmToken::String(name)) => Ok((name.to_string(), input.slice(1..input.len()))),
+        _ => Err(Into::<Diagnostic>::into(DiagKind::ExpectedString(
+            input.span(),
+        ))),
+    };
+    let part = move |input: TokenStream<'a>| match input.peek() {
+        Some(AsmToken::Ident(part) | AsmToken::Directive(part)) => {
+            Ok((part.to_string(), input.slice(1..input.len())))
+        }
+        Some(AsmToken::Number(num)) => Ok((num.to_string(), input.slice(1..input.len()))),
+        _ => Err(Into::<Diagnostic>::into(DiagKind::ExpectedIdent(
+            input.span(),
+        ))),
+    };
+    let dash = move |input: TokenStream<'a>| match input.peek() {
+        Some(AsmToken::Minus) => Ok(((), input.slice(1..input.len()))),
+        _ => Err(Into::<Diagnostic>::into(DiagKind::UnexpectedToken(
+            input.span(),
+        ))),
+    };
+
+    let dashed = part
+        .and_then(zero_or_more(dash.and_then(part).map(|(_, part)| part)))
+        .map(|(first, rest)| {
+            std::iter::once(first)
+                .chain(rest)
+                .collect::<Vec<_>>()
+                .join("-")
+        });
+
+    quoted
+        .or_else(dashed)
+        .map(|name| {
+            if name.starts_with('.') {
+                name
+            } else {
+                format!(".{}", name)
+            }
+        })
+        .label("section_name")
+}
+
+/// Flags, type and other arguments of `.section`, i.e. `"ax",@progbits`
+fn section_args<'a>() -> impl Parser<'a, TokenStream<'a>, ()> {
+    let arg = move |input: TokenStream<'a>| match input.peek() {
+        Some(AsmToken::String(_) | AsmToken::Tag(_) | AsmToken::Number(_) | AsmToken::Ident(_)) => {
+            Ok(((), input.slice(1..input.len())))
+        }
+        _ => Err(Into::<Diagnostic>::into(DiagKind::UnexpectedToken(
+            input.span(),
+        ))),
+    };
+    zero_or_more(comma().and_then(arg)).map(|_| ())
+}
+
+fn generic_section<'a>() -> impl Parser<'a, TokenStream<'a>, String> {
     directive_as_str()
         .try_map(|d, s| match d {
-            "section" => Ok(()),
+            ".section" => Ok(()),
             _ => Err(Into::<Diagnostic>::into(
                 DiagKind::ExpectedSpecificDirective("section", s),
             )),
         })
-        .and_then(asm_ident())
-        .map(|(_, name)| name)
+        .and_then(section_name())
+        .and_then(section_args())
+        .map(|((_, name), _)| name)
         .label("generic_section")
 }
 
@@ -173,11 +283,10 @@ fn directive_as_str<'a>() -> impl Parser<'a, TokenStream<'a>, &'a str> {
     }
 }
 
-fn known_section<'a>() -> impl Parser<'a, TokenStream<'a>, &'a str> {
+fn known_section<'a>() -> impl Parser<'a, TokenStream<'a>, String> {
     directive_as_str()
         .try_map(|d, s| match d {
-            "text" => Ok(d),
-            "data" => Ok(d),
+            ".text" | ".data" | ".rodata" | ".bss" => Ok(d.to_string()),
             _ => Err(Into::<Diagnostic>::into(DiagKind::ExpectedDirective(s))),
         })
         .label("known_section")
@@ -187,8 +296,10 @@ fn known_section<'a>() -> impl Parser<'a, TokenStream<'a>, &'a str> {
 mod tests {
     use crate::{lex_asm, target::create_dialect, TokenStream};
 
-    use super::section;
+    use super::{label, section};
+    use crate::target::SectionOp;
     use lpl::Parser;
+    use tir_core::utils::op_cast;
     use tir_core::{builtin::ModuleOp, Context, OpBuilder};
 
     #[test]
@@ -208,4 +319,40 @@ label:";
 
         section().parse(stream).expect("section");
     }
+
+    #[test]
+    fn sections_at_top_level() {
+        let context = Context::new();
+        context.add_dialect(create_dialect());
+
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+
+        let input = ".text
+main:
+.section .rodata.str1.1,\"aMS\",@progbits,1
+.data
+.text";
+        let tokens = lex_asm(input).expect("lex");
+        let stream = TokenStream::new(&tokens, builder);
+
+        let statement = section().or_else(label());
+        let mut next_input = Some(stream);
+        while let Some(input) = next_input {
+            next_input = statement.parse(input).expect("statement").1;
+        }
+
+        let body = module.borrow().get_body();
+        let names: Vec<_> = body
+            .iter()
+            .map(|op| {
+                op_cast::<SectionOp>(op)
+                    .unwrap()
+                    .borrow()
+                    .name()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(names, [".text", ".rodata.str1.1", ".data"]);
+    }
 }
@@ -0,0 +1,388 @@
+use crate::target::{
+    AlignOp, DataOp, DataRefOp, SymbolBindingOp, SymbolSizeOp, SymbolTypeOp, ZeroOp,
+};
+use crate::{AsmToken, DiagKind, TokenStream};
+use lpl::{Diagnostic, ParseResult, ParseStream, Parser, Span};
+
+use super::{asm_ident, comma, const_expr, expr};
+
+/// Directives, that only carry information for debuggers and other tools
+const IGNORED_DIRECTIVES: &[&str] = &[".file", ".ident", ".attribute", ".option", ".addrsig_sym"];
+
+/// Ignored directives without arguments
+const IGNORED_BARE_DIRECTIVES: &[&str] = &[
+    ".addrsig",
+    ".cfi_startproc",
+    ".cfi_endproc",
+    ".cfi_remember_state",
+    ".cfi_restore_state",
+    ".cfi_signal_frame",
+];
+
+/// Size in bytes of the values of data directives
+fn data_size(name: &str) -> Option<u8> {
+    let size = match name {
+        ".byte" => 1,
+        ".half" | ".short" | ".2byte" => 2,
+        ".word" | ".long" | ".4byte" => 4,
+        ".dword" | ".quad" | ".8byte" => 8,
+        _ => return None,
+    };
+    Some(size)
+}
+
+fn rest(input: Option<TokenStream>) -> Result<TokenStream, Diagnostic> {
+    input.ok_or(DiagKind::EndOfStream.into())
+}
+
+/// Items separated by commas
+fn list<'a, T>(
+    item: impl Parser<'a, TokenStream<'a>, T>,
+    input: TokenStream<'a>,
+) -> ParseResult<TokenStream<'a>, Vec<T>> {
+    let (first, mut next_input) = item.parse(input)?;
+    let mut items = vec![first];
+    while let Some(input) = next_input.clone() {
+        let Ok((_, ni)) = comma().parse(input) else {
+            break;
+        };
+        let (next, ni) = item.parse(rest(ni)?)?;
+        items.push(next);
+        next_input = ni;
+    }
+    Ok((items, next_input))
+}
+
+/// Argument of an ignored directive
+fn any_arg<'a>() -> impl Parser<'a, TokenStream<'a>, ()> {
+    let token = |input: TokenStream<'a>| match input.peek() {
+        Some(AsmToken::String(_) | AsmToken::Tag(_)) => Ok(((), input.slice(1..input.len()))),
+        _ => Err(DiagKind::UnexpectedToken(input.span()).into()),
+    };
+    token.or_else(expr().map(|_| ()))
+}
+
+fn string<'a>() -> impl Parser<'a, TokenStream<'a>, Vec<u8>> {
+    |input: TokenStream<'a>| match input.peek() {
+        Some(AsmToken::String(literal)) => Ok((unescape(literal), input.slice(1..input.len()))),
+        _ => Err(DiagKind::ExpectedString(input.span()).into()),
+    }
+}
+
+/// Bytes of a string literal with C escape sequences
+fn unescape(literal: &str) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut chars = literal.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        let Some(escape) = chars.next() else {
+            bytes.push(b'\\');
+            break;
+        };
+        let byte = match escape {
+            'n' => b'\n',
+            't' => b'\t',
+            'r' => b'\r',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            'x' => {
+                let mut value = 0_u8;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    value = value.wrapping_mul(16).wrapping_add(digit as u8);
+                    chars.next();
+                }
+                value
+            }
+            '0'..='7' => {
+                let mut value = escape.to_digit(8).unwrap() as u8;
+                for _ in 0..2 {
+                    let Some(digit) = chars.peek().and_then(|c| c.to_digit(8)) else {
+                        break;
+                    };
+                    value = value.wrapping_mul(8).wrapping_add(digit as u8);
+                    chars.next();
+                }
+                value
+            }
+            other => other as u8,
+        };
+        bytes.push(byte);
+    }
+    bytes
+}
+
+/// Check, that the value fits in `size` bytes as either a signed or an unsigned number
+fn check_data_range(value: i64, size: u8, span: Span) -> Result<i64, Diagnostic> {
+    let bits = size as u32 * 8;
+    if bits >= 64 || (-(1 << (bits - 1))..(1 << bits)).contains(&value) {
+        Ok(value)
+    } else {
+        Err(DiagKind::DataOutOfRange(value, size, span).into())
+    }
+}
+
+/// Data directive, i.e. `.word 1, 2, sym+4`. Consecutive constants are emitted as a
+/// single data operation.
+fn data<'a>(size: u8, input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+    let builder = asm_ctx.get_builder();
+    let context = builder.get_context();
+
+    let (values, ni) = list(expr().spanned(), input)?;
+
+    let mut constants = vec![];
+    let flush = |constants: &mut Vec<i64>| {
+        if !constants.is_empty() {
+            let op = DataOp::builder(&context)
+                .size(size)
+                .values(std::mem::take(constants))
+                .build();
+            builder.insert(&op);
+        }
+    };
+    for (value, span) in values {
+        if let Some(constant) = value.evaluate(&asm_ctx) {
+            constants.push(check_data_range(constant, size, span)?);
+        } else if let Some((symbol, addend)) = value.symbol_offset(&asm_ctx) {
+            flush(&mut constants);
+            let op = DataRefOp::builder(&context)
+                .size(size)
+                .symbol(symbol)
+                .addend(addend)
+                .build();
+            builder.insert(&op);
+        } else {
+            return Err(DiagKind::ExpectedSymbol(span).into());
+        }
+    }
+    flush(&mut constants);
+
+    Ok(((), ni))
+}
+
+/// `.ascii` and `.asciz` directives, the latter terminates every string with zero
+fn ascii<'a>(zero_terminated: bool, input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+    let builder = asm_ctx.get_builder();
+
+    let (strings, ni) = list(string(), input)?;
+    let values = strings
+        .into_iter()
+        .flat_map(|mut bytes| {
+            if zero_terminated {
+                bytes.push(0);
+            }
+            bytes
+        })
+        .map(|byte| byte as i64)
+        .collect();
+    let op = DataOp::builder(&builder.get_context())
+        .size(1)
+        .values(values)
+        .build();
+    builder.insert(&op);
+
+    Ok(((), ni))
+}
+
+/// `.zero size[, fill]`
+fn zero<'a>(input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+    let builder = asm_ctx.get_builder();
+    let context = builder.get_context();
+
+    let span = input.span();
+    let (args, ni) = list(const_expr(), input)?;
+    let (size, fill) = match args[..] {
+        [size] => (size, 0),
+        [size, fill] => (size, check_data_range(fill, 1, span.clone())?),
+        _ => return Err(DiagKind::UnexpectedToken(span).into()),
+    };
+    // The bytes are materialized when the object is written
+    let size = u32::try_from(size).map_err(|_| DiagKind::DataOutOfRange(size, 4, span))?;
+
+    let mut op = ZeroOp::builder(&context).size(size as u64);
+    if fill != 0 {
+        op = op.fill(fill as u8);
+    }
+    builder.insert(&op.build());
+
+    Ok(((), ni))
+}
+
+/// `.p2align` takes the power of two, `.balign` takes bytes. Fill value and the maximum
+/// padding are ignored.
+fn align<'a>(power_of_two: bool, input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+    let builder = asm_ctx.get_builder();
+
+    let span = input.span();
+    let (args, ni) = list(const_expr(), input)?;
+    let value = args[0];
+    let align = if power_of_two {
+        u32::try_from(value)
+            .ok()
+            .and_then(|shift| 1_u64.checked_shl(shift))
+    } else {
+        u64::try_from(value)
+            .ok()
+            .filter(|value| value.is_power_of_two())
+    };
+    let align = align.ok_or(Into::<Diagnostic>::into(DiagKind::InvalidAlignment(
+        value, span,
+    )))?;
+
+    let op = AlignOp::builder(&builder.get_context())
+        .align(align)
+        .build();
+    builder.insert(&op);
+
+    Ok(((), ni))
+}
+
+fn binding<'a>(binding: &str, input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+    let builder = asm_ctx.get_builder();
+
+    let (names, ni) = list(asm_ident(), input)?;
+    for name in names {
+        let op = SymbolBindingOp::builder(&builder.get_context())
+            .name(name.to_string())
+            .binding(binding.to_string())
+            .build();
+        builder.insert(&op);
+    }
+
+    Ok(((), ni))
+}
+
+/// `.type sym, @function`
+fn symbol_type<'a>(input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+    let builder = asm_ctx.get_builder();
+
+    let kind = |input: TokenStream<'a>| match input.peek() {
+        Some(AsmToken::Tag(kind) | AsmToken::Modifier(kind)) => {
+            Ok((kind, input.slice(1..input.len())))
+        }
+        _ => Err(DiagKind::UnexpectedToken(input.span()).into()),
+    };
+    let ((name, kind), ni) = asm_ident()
+        .and_then(comma())
+        .and_then(kind)
+        .map(|((name, _), kind)| (name, kind))
+        .parse(input)?;
+
+    let op = SymbolTypeOp::builder(&builder.get_context())
+        .name(name.to_string())
+        .kind(kind.to_string())
+        .build();
+    builder.insert(&op);
+
+    Ok(((), ni))
+}
+
+/// `.size sym, expr`, where the expression is either a constant or a difference of
+/// labels, like `.-sym`
+fn symbol_size<'a>(input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+    let builder = asm_ctx.get_builder();
+
+    let ((name, (size, span)), ni) = asm_ident()
+        .and_then(comma())
+        .and_then(expr().spanned())
+        .map(|((name, _), size)| (name, size))
+        .parse(input)?;
+
+    let (value, start, end) = if let Some(value) = size.evaluate(&asm_ctx) {
+        (Some(value), None, None)
+    } else if let Some((end, start)) = size.label_difference() {
+        (None, Some(start), end)
+    } else {
+        return Err(DiagKind::ExpectedConstant(span).into());
+    };
+
+    let op = SymbolSizeOp::builder(&builder.get_context())
+        .name(name.to_string())
+        .build();
+    op.borrow_mut().set_value(value);
+    op.borrow_mut().set_start(start);
+    op.borrow_mut().set_end(end);
+    builder.insert(&op);
+
+    Ok(((), ni))
+}
+
+/// `.equ name, value` and `.set name, value`
+fn constant<'a>(input: TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> {
+    let asm_ctx = input.get_extra().unwrap().clone();
+
+    let ((name, value), ni) = asm_ident()
+        .and_then(comma())
+        .and_then(const_expr())
+        .map(|((name, _), value)| (name, value))
+        .parse(input)?;
+    asm_ctx.set_constant(name, value);
+
+    Ok(((), ni))
+}
+
+/// Data, alignment, symbol and constant directives. Sections are handled by
+/// [`super::section`].
+pub fn directive<'a>() -> impl Parser<'a, TokenStream<'a>, ()> {
+    let parser = move |input: TokenStream<'a>| {
+        let span = input.span();
+        let name = match input.peek() {
+            Some(AsmToken::Directive(name)) => name,
+            _ => return Err(DiagKind::ExpectedDirective(span).into()),
+        };
+        let args = input.slice(1..input.len());
+
+        if IGNORED_BARE_DIRECTIVES.contains(&name) {
+            return Ok(((), args));
+        }
+        if IGNORED_DIRECTIVES.contains(&name) || name.starts_with(".cfi_") {
+            let (_, ni) = list(any_arg(), rest(args)?)?;
+            return Ok(((), ni));
+        }
+        if let Some(size) = data_size(name) {
+            return data(size, rest(args)?);
+        }
+
+        match name {
+            ".ascii" => ascii(false, rest(args)?),
+            ".asciz" | ".string" => ascii(true, rest(args)?),
+            ".zero" | ".skip" | ".space" => zero(rest(args)?),
+            // RISC-V follows the ARM convention, where .align takes the power of two
+            ".align" | ".p2align" => align(true, rest(args)?),
+            ".balign" => align(false, rest(args)?),
+            ".globl" | ".global" => binding("global", rest(args)?),
+            ".local" => binding("local", rest(args)?),
+            ".weak" => binding("weak", rest(args)?),
+            ".type" => symbol_type(rest(args)?),
+            ".size" => symbol_size(rest(args)?),
+            ".equ" | ".set" => constant(rest(args)?),
+            _ => Err(DiagKind::UnknownDirective(name.to_string(), span).into()),
+        }
+    };
+
+    parser.label("asm_directive")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unescape;
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(unescape("a\\n\\t\\\"\\\\"), b"a\n\t\"\\");
+        assert_eq!(unescape("\\0\\101\\x41\\x7f"), b"\0AA\x7f");
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{AsmParserContext, AsmToken, DiagKind, TokenStream};
+use lpl::{Diagnostic, ParseResult, ParseStream, Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+}
+
+/// Expression of an instruction operand or a directive argument
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsmExpr {
+    Number(i64),
+    Symbol(String),
+    /// Current location, `.`
+    Location,
+    Neg(Box<AsmExpr>),
+    Not(Box<AsmExpr>),
+    Binary(BinaryOp, Box<AsmExpr>, Box<AsmExpr>),
+    /// Relocation operator applied to an expression, i.e. `%hi(sym)`
+    Modifier(String, Box<AsmExpr>),
+}
+
+impl AsmExpr {
+    /// Value of the expression, that only consists of numbers and symbols defined by
+    /// `.equ` or `.set`
+    pub fn evaluate(&self, asm_ctx: &AsmParserContext) -> Option<i64> {
+        match self {
+            AsmExpr::Number(num) => Some(*num),
+            AsmExpr::Symbol(name) => asm_ctx.get_constant(name),
+            AsmExpr::Location | AsmExpr::Modifier(_, _) => None,
+            AsmExpr::Neg(expr) => Some(expr.evaluate(asm_ctx)?.wrapping_neg()),
+            AsmExpr::Not(expr) => Some(!expr.evaluate(asm_ctx)?),
+            AsmExpr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.evaluate(asm_ctx)?;
+                let rhs = rhs.evaluate(asm_ctx)?;
+                match op {
+                    BinaryOp::Add => Some(lhs.wrapping_add(rhs)),
+                    BinaryOp::Sub => Some(lhs.wrapping_sub(rhs)),
+                    BinaryOp::Mul => Some(lhs.wrapping_mul(rhs)),
+                    BinaryOp::Div => lhs.checked_div(rhs),
+                    BinaryOp::And => Some(lhs & rhs),
+                    BinaryOp::Or => Some(lhs | rhs),
+                    BinaryOp::Xor => Some(lhs ^ rhs),
+                    BinaryOp::Shl => lhs.checked_shl(rhs.try_into().ok()?),
+                    BinaryOp::Shr => lhs.checked_shr(rhs.try_into().ok()?),
+                }
+            }
+        }
+    }
+
+    /// Symbol with a constant addend, i.e. `sym+4`
+    pub fn symbol_offset(&self, asm_ctx: &AsmParserContext) -> Option<(String, i64)> {
+        if self.evaluate(asm_ctx).is_some() {
+            return None;
+        }
+
+        match self {
+            AsmExpr::Symbol(name) => Some((name.clone(), 0)),
+            AsmExpr::Binary(BinaryOp::Add, lhs, rhs) => {
+                if let Some(addend) = rhs.evaluate(asm_ctx) {
+                    let (name, offset) = lhs.symbol_offset(asm_ctx)?;
+                    Some((name, offset.wrapping_add(addend)))
+                } else {
+                    let (name, offset) = rhs.symbol_offset(asm_ctx)?;
+                    Some((name, offset.wrapping_add(lhs.evaluate(asm_ctx)?)))
+                }
+            }
+            AsmExpr::Binary(BinaryOp::Sub, lhs, rhs) => {
+                let (name, offset) = lhs.symbol_offset(asm_ctx)?;
+                Some((name, offset.wrapping_sub(rhs.evaluate(asm_ctx)?)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Distance between two labels, i.e. `.Lend-start`, as the pair of the end and the
+    /// start label. The end label is `None` for the current location, i.e. `.-start`.
+    pub fn label_difference(&self) -> Option<(Option<String>, String)> {
+        let AsmExpr::Binary(BinaryOp::Sub, end, start) = self else {
+            return None;
+        };
+        let AsmExpr::Symbol(start) = start.as_ref() else {
+            return None;
+        };
+        match end.as_ref() {
+            AsmExpr::Location => Some((None, start.clone())),
+            AsmExpr::Symbol(end) => Some((Some(end.clone()), start.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Binary operator and its precedence
+fn binary_op(token: &AsmToken) -> Option<(BinaryOp, u8)> {
+    let op = match token {
+        AsmToken::Pipe => (BinaryOp::Or, 1),
+        AsmToken::Caret => (BinaryOp::Xor, 2),
+        AsmToken::Ampersand => (BinaryOp::And, 3),
+        AsmToken::ShiftLeft => (BinaryOp::Shl, 4),
+        AsmToken::ShiftRight => (BinaryOp::Shr, 4),
+        AsmToken::Plus => (BinaryOp::Add, 5),
+        AsmToken::Minus => (BinaryOp::Sub, 5),
+        AsmToken::Star => (BinaryOp::Mul, 6),
+        AsmToken::Slash => (BinaryOp::Div, 6),
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// Tokens after the first one, that must exist
+fn skip_token(input: TokenStream) -> Result<TokenStream, Diagnostic> {
+    input
+        .slice(1..input.len())
+        .ok_or(Into::<Diagnostic>::into(DiagKind::EndOfStream))
+}
+
+fn binary_expr(input: TokenStream, min_prec: u8) -> ParseResult<TokenStream, AsmExpr> {
+    let (mut lhs, mut next_input) = unary_expr(input)?;
+
+    while let Some(input) = next_input.clone() {
+        let Some((op, prec)) = input.peek().as_ref().and_then(binary_op) else {
+            break;
+        };
+        if prec < min_prec {
+            break;
+        }
+
+        let (rhs, ni) = binary_expr(skip_token(input)?, prec + 1)?;
+        lhs = AsmExpr::Binary(op, Box::new(lhs), Box::new(rhs));
+        next_input = ni;
+    }
+
+    Ok((lhs, next_input))
+}
+
+/// Expression in parentheses
+fn paren_expr(input: TokenStream) -> ParseResult<TokenStream, AsmExpr> {
+    if input.peek() != Some(AsmToken::OpenParen) {
+        return Err(DiagKind::UnexpectedToken(input.span()).into());
+    }
+    let (expr, ni) = binary_expr(skip_token(input)?, 0)?;
+    let input = ni.ok_or(Into::<Diagnostic>::into(DiagKind::EndOfStream))?;
+    if input.peek() != Some(AsmToken::CloseParen) {
+        return Err(DiagKind::UnexpectedToken(input.span()).into());
+    }
+    Ok((expr, input.slice(1..input.len())))
+}
+
+fn unary_expr(input: TokenStream) -> ParseResult<TokenStream, AsmExpr> {
+    let Some(token) = input.peek() else {
+        return Err(DiagKind::EndOfStream.into());
+    };
+    let next_input = input.slice(1..input.len());
+
+    match token {
+        AsmToken::Number(num) => Ok((AsmExpr::Number(num), next_input)),
+        AsmToken::Ident(name) => Ok((AsmExpr::Symbol(name.to_string()), next_input)),
+        AsmToken::Directive(".") => Ok((AsmExpr::Location, next_input)),
+        AsmToken::Directive(name) => Ok((AsmExpr::Symbol(name.to_string()), next_input)),
+        AsmToken::Plus => unary_expr(skip_token(input)?),
+        AsmToken::Minus => {
+            let (expr, ni) = unary_expr(skip_token(input)?)?;
+            Ok((AsmExpr::Neg(Box::new(expr)), ni))
+        }
+        AsmToken::Tilde => {
+            let (expr, ni) = unary_expr(skip_token(input)?)?;
+            Ok((AsmExpr::Not(Box::new(expr)), ni))
+        }
+        AsmToken::OpenParen => paren_expr(input),
+        AsmToken::Modifier(name) => {
+            let (expr, ni) = paren_expr(skip_token(input)?)?;
+            Ok((AsmExpr::Modifier(name.to_string(), Box::new(expr)), ni))
+        }
+        _ => Err(DiagKind::UnexpectedToken(input.span()).into()),
+    }
+}
+
+/// Expression with the usual precedence of arithmetic and bitwise operators
+pub fn expr<'a>() -> impl Parser<'a, TokenStream<'a>, AsmExpr> {
+    (|input: TokenStream<'a>| binary_expr(input, 0)).label("expr")
+}
+
+/// Expression, that evaluates to a constant
+pub fn const_expr<'a>() -> impl Parser<'a, TokenStream<'a>, i64> {
+    expr()
+        .map_with(|expr, asm_ctx| expr.evaluate(asm_ctx.unwrap()))
+        .try_map(|value, s| value.ok_or(Into::<Diagnostic>::into(DiagKind::ExpectedConstant(s))))
+        .label("const_expr")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lex_asm, TokenStream};
+    use lpl::{ParseStream, Parser};
+    use tir_core::{builtin::ModuleOp, Context, OpBuilder};
+
+    use super::*;
+
+    fn evaluate(input: &str) -> Option<i64> {
+        let context = Context::new();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let tokens = lex_asm(input).expect("lex");
+        let stream = TokenStream::new(&tokens, builder);
+        stream.get_extra().unwrap().set_constant("SIZE", 16);
+
+        let (expr, ni) = expr().parse(stream.clone()).expect("expr");
+        assert!(ni.is_none());
+        expr.evaluate(stream.get_extra().unwrap())
+    }
+
+    #[test]
+    fn constant_expressions() {
+        assert_eq!(evaluate("1 + 2 * 3"), Some(7));
+        assert_eq!(evaluate("(1 + 2) * 3"), Some(9));
+        assert_eq!(evaluate("-0x10 + 1"), Some(-15));
+        assert_eq!(evaluate("1 << 4 | 0b11"), Some(19));
+        assert_eq!(evaluate("~0 & 0xff"), Some(255));
+        assert_eq!(evaluate("SIZE * 4 - 1"), Some(63));
+        assert_eq!(evaluate("1 / 0"), None);
+        assert_eq!(evaluate("label + 4"), None);
+    }
+
+    #[test]
+    fn symbolic_expressions() {
+        let context = Context::new();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let tokens = lex_asm("%lo(.L.str + 8) .-main").expect("lex");
+        let stream = TokenStream::new(&tokens, builder);
+        let asm_ctx = stream.get_extra().unwrap().clone();
+
+        let (modifier, ni) = expr().parse(stream).expect("expr");
+        let AsmExpr::Modifier(name, operand) = modifier else {
+            panic!("expected a modifier");
+        };
+        assert_eq!(name, "lo");
+        assert_eq!(
+            operand.symbol_offset(&asm_ctx),
+            Some((".L.str".to_string(), 8))
+        );
+
+        let (size, _) = expr().parse(ni.unwrap()).expect("expr");
+        assert_eq!(size.label_difference(), Some((None, "main".to_string())));
+    }
+}
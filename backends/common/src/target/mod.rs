@@ -0,0 +1,28 @@
+use tir_core::Dialect;
+use tir_core::{OpAssembly, Ty, TyAssembly};
+
+mod object;
+mod ops;
+mod printer;
+mod types;
+
+pub use object::*;
+pub use ops::*;
+pub use printer::*;
+use tir_macros::{dialect, populate_dialect_ops, populate_dialect_types};
+pub use types::*;
+
+dialect!(target);
+populate_dialect_ops!(
+    SectionOp,
+    FunctionOp,
+    SectionEndOp,
+    DataOp,
+    DataRefOp,
+    ZeroOp,
+    AlignOp,
+    SymbolBindingOp,
+    SymbolTypeOp,
+    SymbolSizeOp
+);
+populate_dialect_types!(RegType);
@@ -0,0 +1,403 @@
+//! Layout of the sections of a module into a relocatable object file
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use thiserror::Error;
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::op_cast;
+use tir_core::{Op, OpRef, RwCell};
+
+use super::ops::*;
+use crate::elf::{ObjectFile, Relocation, Section, Symbol, SymbolBinding, SymbolKind};
+use crate::{BinaryEmittable, BinaryStream, TargetOptions};
+
+#[derive(Error, Debug)]
+pub enum ObjectError {
+    #[error("failed to encode `{0}` at offset {1:#x} of section `{2}`")]
+    Encode(String, u64, String),
+    #[error("address of {0} bytes can not be relocated")]
+    DataRelocation(u8),
+    #[error("symbol `{0}` is not defined in section `{1}`")]
+    UndefinedLabel(String, String),
+    #[error("unknown symbol binding `{0}`")]
+    UnknownBinding(String),
+    #[error("unknown symbol type `{0}`")]
+    UnknownSymbolType(String),
+    #[error("operation `{0}` can not be emitted to an object file")]
+    UnsupportedOp(String),
+}
+
+/// Target specific part of the object file emission
+pub trait ObjectTarget {
+    fn get_target_options(&self) -> &TargetOptions;
+
+    /// `e_machine` of the ELF header
+    fn get_machine(&self) -> u16;
+
+    /// `e_flags` of the ELF header
+    fn get_flags(&self) -> u32;
+
+    /// Minimal alignment of executable sections
+    fn get_code_align(&self) -> u64;
+
+    /// Encode an instruction and record its relocations. Returns `None` for operations,
+    /// that do not belong to the target.
+    fn encode_instr(
+        &self,
+        op: &OpRef,
+        writer: &mut SectionWriter,
+    ) -> Option<Result<(), ObjectError>>;
+
+    /// Relocation type of an absolute address of the given size
+    fn get_data_reloc(&self, size: u8) -> Option<u32>;
+
+    /// Padding of the given size in executable sections, i.e. no-op instructions
+    fn get_code_padding(&self, size: u64) -> Vec<u8>;
+}
+
+struct SectionStream(Rc<RefCell<Vec<u8>>>);
+
+impl BinaryStream for SectionStream {
+    fn write(&mut self, data: &[u8]) {
+        self.0.borrow_mut().extend_from_slice(data);
+    }
+}
+
+/// Contents of the section, that is being laid out
+pub struct SectionWriter {
+    name: String,
+    data: Rc<RefCell<Vec<u8>>>,
+    stream: Box<dyn BinaryStream>,
+    relocations: Vec<Relocation>,
+    temp_labels: Vec<(String, u64)>,
+    num_temp_labels: usize,
+}
+
+impl SectionWriter {
+    fn new(name: &str, num_temp_labels: usize) -> Self {
+        let data = Rc::new(RefCell::new(vec![]));
+        SectionWriter {
+            name: name.to_string(),
+            stream: Box::new(SectionStream(data.clone())),
+            data,
+            relocations: vec![],
+            temp_labels: vec![],
+            num_temp_labels,
+        }
+    }
+
+    pub fn get_section_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Offset of the next byte in the section
+    pub fn get_offset(&self) -> u64 {
+        self.data.borrow().len() as u64
+    }
+
+    pub fn get_stream(&mut self) -> &mut Box<dyn BinaryStream> {
+        &mut self.stream
+    }
+
+    pub fn add_relocation(&mut self, offset: u64, kind: u32, symbol: &str, addend: i64) {
+        self.relocations.push(Relocation {
+            offset,
+            kind,
+            symbol: symbol.to_string(),
+            addend,
+        });
+    }
+
+    /// Define a unique local label at the offset, that relocations can refer to, i.e.
+    /// `.Lpcrel_hi0`
+    pub fn add_temp_label_at(&mut self, prefix: &str, offset: u64) -> String {
+        let name = format!(".L{}{}", prefix, self.num_temp_labels);
+        self.num_temp_labels += 1;
+        self.temp_labels.push((name.clone(), offset));
+        name
+    }
+
+    /// Error of the operation, that failed to encode at the current offset
+    pub fn encode_error(&self, op: &dyn Op) -> ObjectError {
+        ObjectError::Encode(
+            op.get_operation_name().to_string(),
+            self.get_offset(),
+            self.name.clone(),
+        )
+    }
+}
+
+/// Attributes of symbols, that are set by the symbol directives
+#[derive(Default)]
+struct SymbolAttrs {
+    bindings: HashMap<String, SymbolBinding>,
+    kinds: HashMap<String, SymbolKind>,
+    sizes: HashMap<String, u64>,
+}
+
+impl SymbolAttrs {
+    fn is_named(&self, name: &str) -> bool {
+        self.bindings.contains_key(name)
+            || self.kinds.contains_key(name)
+            || self.sizes.contains_key(name)
+    }
+
+    /// Record the binding or the type of a symbol. Returns `false` for other operations.
+    fn add(&mut self, op: &OpRef) -> Result<bool, ObjectError> {
+        if let Some(op) = op_cast::<SymbolBindingOp>(op.clone()) {
+            let op = op.borrow();
+            let binding = SymbolBinding::from_name(op.binding())
+                .ok_or(ObjectError::UnknownBinding(op.binding().to_string()))?;
+            self.bindings.insert(op.name().to_string(), binding);
+            return Ok(true);
+        }
+        if let Some(op) = op_cast::<SymbolTypeOp>(op.clone()) {
+            let op = op.borrow();
+            let kind = SymbolKind::from_name(op.kind())
+                .ok_or(ObjectError::UnknownSymbolType(op.kind().to_string()))?;
+            self.kinds.insert(op.name().to_string(), kind);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+/// Label of a block, that becomes a symbol, if it is referenced
+struct Label {
+    name: String,
+    section: usize,
+    offset: u64,
+    /// Temporary labels, like `.L.str`, and the implicit `entry` block are only
+    /// emitted, when something refers to them
+    is_temp: bool,
+}
+
+/// Symbol size, that is only known after the layout of the section
+struct PendingSize {
+    name: String,
+    start: String,
+    end: Option<String>,
+    offset: u64,
+}
+
+fn emit_section(
+    section: &SectionOp,
+    index: usize,
+    target: &dyn ObjectTarget,
+    symbols: &mut SymbolAttrs,
+    labels: &mut Vec<Label>,
+    num_temp_labels: &mut usize,
+) -> Result<Section, ObjectError> {
+    let mut object_section = Section::new(section.name());
+    let mut writer = SectionWriter::new(section.name(), *num_temp_labels);
+    let is_code = object_section.is_executable();
+    if is_code {
+        object_section.align = target.get_code_align();
+    }
+
+    let mut section_labels = HashMap::new();
+    let mut pending_sizes = vec![];
+
+    for (block_idx, block) in section.get_body_region().iter().enumerate() {
+        let name = block.get_name();
+        section_labels.insert(name.clone(), writer.get_offset());
+        labels.push(Label {
+            is_temp: name.starts_with(".L") || (block_idx == 0 && name == "entry"),
+            name,
+            section: index,
+            offset: writer.get_offset(),
+        });
+
+        for op in block.iter() {
+            if let Some(result) = target.encode_instr(&op, &mut writer) {
+                result?;
+                continue;
+            }
+            if symbols.add(&op)? {
+                continue;
+            }
+
+            let offset = writer.get_offset();
+            let target_opts = target.get_target_options();
+            if let Some(data) = op_cast::<DataOp>(op.clone()) {
+                data.borrow()
+                    .encode(target_opts, writer.get_stream())
+                    .map_err(|_| writer.encode_error(&*op.borrow()))?;
+            } else if let Some(zero) = op_cast::<ZeroOp>(op.clone()) {
+                zero.borrow()
+                    .encode(target_opts, writer.get_stream())
+                    .map_err(|_| writer.encode_error(&*op.borrow()))?;
+            } else if let Some(data_ref) = op_cast::<DataRefOp>(op.clone()) {
+                let data_ref = data_ref.borrow();
+                let kind = target
+                    .get_data_reloc(data_ref.size())
+                    .ok_or(ObjectError::DataRelocation(data_ref.size()))?;
+                writer.add_relocation(offset, kind, data_ref.symbol(), data_ref.addend());
+                data_ref
+                    .encode(target_opts, writer.get_stream())
+                    .map_err(|_| writer.encode_error(&*op.borrow()))?;
+            } else if let Some(align) = op_cast::<AlignOp>(op.clone()) {
+                let align = align.borrow();
+                let padding = align.get_padding(offset);
+                let bytes = if is_code {
+                    target.get_code_padding(padding)
+                } else {
+                    vec![0; padding as usize]
+                };
+                writer.get_stream().write(&bytes);
+                object_section.align = object_section.align.max(align.align());
+            } else if let Some(size) = op_cast::<SymbolSizeOp>(op.clone()) {
+                let size = size.borrow();
+                match (size.value(), size.start()) {
+                    (Some(value), _) => {
+                        symbols.sizes.insert(size.name().to_string(), value as u64);
+                    }
+                    (None, Some(start)) => pending_sizes.push(PendingSize {
+                        name: size.name().to_string(),
+                        start: start.to_string(),
+                        end: size.end().map(str::to_string),
+                        offset,
+                    }),
+                    (None, None) => {}
+                }
+            } else if op_cast::<SectionEndOp>(op.clone()).is_none() {
+                return Err(ObjectError::UnsupportedOp(
+                    op.borrow().get_operation_name().to_string(),
+                ));
+            }
+        }
+    }
+
+    for size in pending_sizes {
+        let label_offset = |name: &str| {
+            section_labels
+                .get(name)
+                .copied()
+                .ok_or(ObjectError::UndefinedLabel(
+                    name.to_string(),
+                    section.name().to_string(),
+                ))
+        };
+        let start = label_offset(&size.start)?;
+        let end = match &size.end {
+            Some(end) => label_offset(end)?,
+            None => size.offset,
+        };
+        symbols.sizes.insert(size.name, end.wrapping_sub(start));
+    }
+
+    labels.extend(writer.temp_labels.drain(..).map(|(name, offset)| Label {
+        name,
+        section: index,
+        offset,
+        is_temp: false,
+    }));
+    *num_temp_labels = writer.num_temp_labels;
+
+    object_section.data = writer.data.take();
+    object_section.relocations = writer.relocations;
+    if object_section.is_nobits() {
+        object_section.zero_size = object_section.data.len() as u64;
+        object_section.data.clear();
+    }
+
+    Ok(object_section)
+}
+
+/// Lay out every section of the module and collect the symbols and the relocations.
+/// Branches to labels of the same section are expected to be resolved by the
+/// target, references to other symbols become relocations.
+pub fn emit_object(
+    module: &Arc<RwCell<ModuleOp>>,
+    target: &dyn ObjectTarget,
+) -> Result<ObjectFile, ObjectError> {
+    let mut object = ObjectFile::new(
+        target.get_target_options().endiannes,
+        target.get_machine(),
+        target.get_flags(),
+    );
+
+    let mut symbols = SymbolAttrs::default();
+    let mut labels = vec![];
+    let mut num_temp_labels = 0;
+
+    let body = module.borrow().get_body();
+    for op in body.iter() {
+        if symbols.add(&op)? {
+            continue;
+        }
+        let Some(section) = op_cast::<SectionOp>(op.clone()) else {
+            return Err(ObjectError::UnsupportedOp(
+                op.borrow().get_operation_name().to_string(),
+            ));
+        };
+        let index = object.sections.len();
+        let section = emit_section(
+            &section.borrow(),
+            index,
+            target,
+            &mut symbols,
+            &mut labels,
+            &mut num_temp_labels,
+        )?;
+        object.sections.push(section);
+    }
+
+    let referenced: HashSet<&str> = object
+        .sections
+        .iter()
+        .flat_map(|section| &section.relocations)
+        .map(|reloc| reloc.symbol.as_str())
+        .collect();
+
+    let mut defined = HashSet::new();
+    for label in &labels {
+        let is_used = referenced.contains(label.name.as_str()) || symbols.is_named(&label.name);
+        if (label.is_temp && !is_used) || !defined.insert(label.name.clone()) {
+            continue;
+        }
+        object.symbols.push(Symbol {
+            name: label.name.clone(),
+            section: Some(label.section),
+            value: label.offset,
+            size: symbols.sizes.get(&label.name).copied().unwrap_or(0),
+            binding: symbols
+                .bindings
+                .get(&label.name)
+                .copied()
+                .unwrap_or(SymbolBinding::Local),
+            kind: symbols
+                .kinds
+                .get(&label.name)
+                .copied()
+                .unwrap_or(SymbolKind::NoType),
+        });
+    }
+
+    // Declared symbols, that are defined elsewhere
+    let mut undefined: Vec<_> = symbols
+        .bindings
+        .iter()
+        .filter(|(name, binding)| **binding != SymbolBinding::Local && !defined.contains(*name))
+        .collect();
+    undefined.sort_by_key(|(name, _)| name.to_string());
+    for (name, binding) in undefined {
+        object.symbols.push(Symbol {
+            name: name.clone(),
+            section: None,
+            value: 0,
+            size: 0,
+            binding: *binding,
+            kind: symbols
+                .kinds
+                .get(name)
+                .copied()
+                .unwrap_or(SymbolKind::NoType),
+        });
+    }
+
+    Ok(object)
+}
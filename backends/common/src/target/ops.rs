@@ -0,0 +1,252 @@
+use lpl::{ParseStream, Parser};
+use tir_core::*;
+use tir_macros::{op_implements, Op, OpAssembly, OpValidator};
+
+use crate::target::DIALECT_NAME;
+use crate::{AsmPrintable, BinaryEmittable, BinaryStream, Endianness, TargetOptions};
+
+#[derive(Op, Debug, Clone, OpValidator, OpAssembly)]
+#[operation(
+    name = "section",
+    dialect = target,
+    known_attrs(name: String),
+    assembly_format = "$name $body attr-dict"
+)]
+pub struct SectionOp {
+    #[region]
+    body: RegionRef,
+    r#impl: OpImpl,
+}
+
+/// Function, that has been recovered from machine code. Blocks of the body form the
+/// control flow graph of the function and are laid out in the address order.
+#[derive(Op, Debug, Clone, OpValidator, OpAssembly)]
+#[operation(
+    name = "function",
+    dialect = target,
+    known_attrs(name: String),
+    assembly_format = "$name $body attr-dict"
+)]
+pub struct FunctionOp {
+    #[region]
+    body: RegionRef,
+    r#impl: OpImpl,
+}
+
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(name = "section_end", dialect = target)]
+pub struct SectionEndOp {
+    r#impl: OpImpl,
+}
+
+#[op_implements(dialect = target)]
+impl Terminator for SectionEndOp {}
+
+/// Constant values of `size` bytes each, that are emitted by the `.byte`, `.half`,
+/// `.word` and `.dword` directives. String directives emit bytes.
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(name = "data", dialect = target, known_attrs(size: u8, values: Vec<i64>))]
+pub struct DataOp {
+    r#impl: OpImpl,
+}
+
+/// Address of a symbol with a constant addend, that occupies `size` bytes. The value
+/// is only known after linking.
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(
+    name = "data_ref",
+    dialect = target,
+    known_attrs(size: u8, symbol: String, addend: i64)
+)]
+pub struct DataRefOp {
+    r#impl: OpImpl,
+}
+
+/// Bytes filled with `fill`, or zeros by default, that are emitted by the `.zero`
+/// directive
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(name = "zero", dialect = target, known_attrs(size: u64, fill: Option<u8>))]
+pub struct ZeroOp {
+    r#impl: OpImpl,
+}
+
+/// Padding, that aligns the following data to `align` bytes
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(name = "align", dialect = target, known_attrs(align: u64))]
+pub struct AlignOp {
+    r#impl: OpImpl,
+}
+
+/// Binding of a symbol, that is either `global`, `local` or `weak`
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(
+    name = "symbol_binding",
+    dialect = target,
+    known_attrs(name: String, binding: String)
+)]
+pub struct SymbolBindingOp {
+    r#impl: OpImpl,
+}
+
+/// Type of a symbol, i.e. `function` or `object`
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(name = "symbol_type", dialect = target, known_attrs(name: String, kind: String))]
+pub struct SymbolTypeOp {
+    r#impl: OpImpl,
+}
+
+/// Size of a symbol. It is either a constant `value`, or the distance from the `start`
+/// label to the `end` label. A missing `end` stands for the location of the operation.
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(
+    name = "symbol_size",
+    dialect = target,
+    known_attrs(name: String, value: Option<i64>, start: Option<String>, end: Option<String>)
+)]
+pub struct SymbolSizeOp {
+    r#impl: OpImpl,
+}
+
+impl BinaryEmittable for DataOp {
+    fn encode(
+        &self,
+        target_opts: &TargetOptions,
+        stream: &mut Box<dyn BinaryStream>,
+    ) -> Result<()> {
+        let size = self.size() as usize;
+        for value in self.values() {
+            let bytes = match target_opts.endiannes {
+                Endianness::Little => value.to_le_bytes(),
+                Endianness::Big => value.to_be_bytes(),
+            };
+            match target_opts.endiannes {
+                Endianness::Little => stream.write(&bytes[..size]),
+                Endianness::Big => stream.write(&bytes[8 - size..]),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BinaryEmittable for DataRefOp {
+    /// The address is filled in by the linker
+    fn encode(
+        &self,
+        _target_opts: &TargetOptions,
+        stream: &mut Box<dyn BinaryStream>,
+    ) -> Result<()> {
+        stream.write(&vec![0; self.size() as usize]);
+        Ok(())
+    }
+}
+
+impl BinaryEmittable for ZeroOp {
+    fn encode(
+        &self,
+        _target_opts: &TargetOptions,
+        stream: &mut Box<dyn BinaryStream>,
+    ) -> Result<()> {
+        stream.write(&vec![self.fill().unwrap_or(0); self.size() as usize]);
+        Ok(())
+    }
+}
+
+impl AlignOp {
+    /// Number of padding bytes at the given offset in the section
+    pub fn get_padding(&self, offset: u64) -> u64 {
+        offset.next_multiple_of(self.align().max(1)) - offset
+    }
+}
+
+/// Directive of data values of the given size
+fn data_directive(size: u8) -> &'static str {
+    match size {
+        1 => ".byte",
+        2 => ".half",
+        4 => ".word",
+        _ => ".dword",
+    }
+}
+
+/// Symbol with a constant addend, i.e. `sym+4`
+fn symbol_offset(symbol: &str, addend: i64) -> String {
+    match addend {
+        0 => symbol.to_string(),
+        _ => format!("{}{:+}", symbol, addend),
+    }
+}
+
+impl AsmPrintable for DataOp {
+    fn print(&self, _target_opts: &TargetOptions) -> String {
+        let values: Vec<_> = self.values().iter().map(i64::to_string).collect();
+        format!("{} {}", data_directive(self.size()), values.join(", "))
+    }
+}
+
+impl AsmPrintable for DataRefOp {
+    fn print(&self, _target_opts: &TargetOptions) -> String {
+        format!(
+            "{} {}",
+            data_directive(self.size()),
+            symbol_offset(self.symbol(), self.addend())
+        )
+    }
+}
+
+impl AsmPrintable for ZeroOp {
+    fn print(&self, _target_opts: &TargetOptions) -> String {
+        match self.fill() {
+            Some(fill) => format!(".zero {}, {}", self.size(), fill),
+            None => format!(".zero {}", self.size()),
+        }
+    }
+}
+
+impl AsmPrintable for AlignOp {
+    fn print(&self, _target_opts: &TargetOptions) -> String {
+        format!(".balign {}", self.align())
+    }
+}
+
+impl AsmPrintable for SymbolBindingOp {
+    fn print(&self, _target_opts: &TargetOptions) -> String {
+        match self.binding() {
+            "global" => format!(".globl {}", self.name()),
+            binding => format!(".{} {}", binding, self.name()),
+        }
+    }
+}
+
+impl AsmPrintable for SymbolTypeOp {
+    fn print(&self, _target_opts: &TargetOptions) -> String {
+        format!(".type {}, @{}", self.name(), self.kind())
+    }
+}
+
+impl AsmPrintable for SymbolSizeOp {
+    /// Sizes without an end label are measured to the location counter `.`
+    fn print(&self, _target_opts: &TargetOptions) -> String {
+        let size = match (self.value(), self.start()) {
+            (Some(value), _) => value.to_string(),
+            (None, Some(start)) => format!("{}-{}", self.end().unwrap_or("."), start),
+            (None, None) => "0".to_string(),
+        };
+        format!(".size {}, {}", self.name(), size)
+    }
+}
+
+/// Number of bytes, that a data operation of the target dialect occupies at the given
+/// offset in the section. Returns `None` for other operations.
+pub fn get_data_size(op: &dyn Op, offset: u64) -> Option<u64> {
+    let op = op as &dyn std::any::Any;
+    if let Some(data) = op.downcast_ref::<DataOp>() {
+        Some(data.size() as u64 * data.values().len() as u64)
+    } else if let Some(data_ref) = op.downcast_ref::<DataRefOp>() {
+        Some(data_ref.size() as u64)
+    } else if let Some(zero) = op.downcast_ref::<ZeroOp>() {
+        Some(zero.size())
+    } else {
+        op.downcast_ref::<AlignOp>()
+            .map(|align| align.get_padding(offset))
+    }
+}
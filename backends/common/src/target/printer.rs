@@ -0,0 +1,142 @@
+//! Printing of the sections of a module as GNU assembly
+
+use std::sync::Arc;
+
+use thiserror::Error;
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::op_cast;
+use tir_core::{BlockRef, OpRef, RwCell};
+
+use super::ops::*;
+use crate::elf::Section;
+use crate::{AsmPrintable, TargetOptions};
+
+#[derive(Error, Debug)]
+pub enum AsmPrintError {
+    #[error("operation `{0}` can not be printed as assembly")]
+    UnsupportedOp(String),
+}
+
+/// Target specific part of the assembly printing
+pub trait AsmTarget {
+    fn get_target_options(&self) -> &TargetOptions;
+
+    /// Print an instruction. Returns `None` for operations, that do not belong to the
+    /// target.
+    fn print_instr(&self, op: &OpRef) -> Option<String>;
+}
+
+/// Directive of a data or symbol operation of the target dialect. Returns `None` for
+/// other operations.
+pub fn print_directive(op: &OpRef, target_opts: &TargetOptions) -> Option<String> {
+    if let Some(data) = op_cast::<DataOp>(op.clone()) {
+        Some(data.borrow().print(target_opts))
+    } else if let Some(data_ref) = op_cast::<DataRefOp>(op.clone()) {
+        Some(data_ref.borrow().print(target_opts))
+    } else if let Some(zero) = op_cast::<ZeroOp>(op.clone()) {
+        Some(zero.borrow().print(target_opts))
+    } else if let Some(align) = op_cast::<AlignOp>(op.clone()) {
+        Some(align.borrow().print(target_opts))
+    } else if let Some(binding) = op_cast::<SymbolBindingOp>(op.clone()) {
+        Some(binding.borrow().print(target_opts))
+    } else if let Some(kind) = op_cast::<SymbolTypeOp>(op.clone()) {
+        Some(kind.borrow().print(target_opts))
+    } else {
+        op_cast::<SymbolSizeOp>(op.clone()).map(|size| size.borrow().print(target_opts))
+    }
+}
+
+/// `.text`, `.data` and `.bss` have their own directives, other sections are entered
+/// with `.section` and the flags, that the object file emission gives them
+fn section_directive(name: &str) -> String {
+    match name {
+        ".text" | ".data" | ".bss" => name.to_string(),
+        _ => {
+            let section = Section::new(name);
+            let kind = match section.is_nobits() {
+                true => "@nobits",
+                false => "@progbits",
+            };
+            format!(
+                ".section {},\"{}\",{}",
+                name,
+                section.get_flag_chars(),
+                kind
+            )
+        }
+    }
+}
+
+struct AsmWriter<'a> {
+    target: &'a dyn AsmTarget,
+    out: String,
+}
+
+impl AsmWriter<'_> {
+    fn print_op(&mut self, op: &OpRef) -> Result<(), AsmPrintError> {
+        if let Some(function) = op_cast::<FunctionOp>(op.clone()) {
+            for block in function.borrow().get_body_region().iter() {
+                self.print_block(&block, false)?;
+            }
+            return Ok(());
+        }
+        if op_cast::<SectionEndOp>(op.clone()).is_some() {
+            return Ok(());
+        }
+
+        let line = self
+            .target
+            .print_instr(op)
+            .or_else(|| print_directive(op, self.target.get_target_options()))
+            .ok_or(AsmPrintError::UnsupportedOp(
+                op.borrow().get_operation_name().to_string(),
+            ))?;
+        self.out.push('\t');
+        self.out.push_str(&line);
+        self.out.push('\n');
+        Ok(())
+    }
+
+    /// Label of the block followed by its operations. The implicit `entry` block of a
+    /// section has no label.
+    fn print_block(&mut self, block: &BlockRef, is_implicit: bool) -> Result<(), AsmPrintError> {
+        if !is_implicit {
+            self.out.push_str(&format!("{}:\n", block.get_name()));
+        }
+        for op in block.iter() {
+            self.print_op(&op)?;
+        }
+        Ok(())
+    }
+}
+
+/// Print the module as assembly, that the parser of the target reads back to the same
+/// module. Blocks of recovered functions are printed in place of the function.
+pub fn print_asm(
+    module: &Arc<RwCell<ModuleOp>>,
+    target: &dyn AsmTarget,
+) -> Result<String, AsmPrintError> {
+    let mut writer = AsmWriter {
+        target,
+        out: String::new(),
+    };
+
+    let body = module.borrow().get_body();
+    for op in body.iter() {
+        let Some(section) = op_cast::<SectionOp>(op.clone()) else {
+            writer.print_op(&op)?;
+            continue;
+        };
+
+        let section = section.borrow();
+        writer
+            .out
+            .push_str(&format!("\t{}\n", section_directive(section.name())));
+        for (block_idx, block) in section.get_body_region().iter().enumerate() {
+            let is_implicit = block_idx == 0 && block.get_name() == "entry";
+            writer.print_block(&block, is_implicit)?;
+        }
+    }
+
+    Ok(writer.out)
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use lpl::{ParseResult, Parser};
+use tir_core::parser::skip_attrs;
+use tir_core::{Attr, ContextRef, IRStrStream, Ty, TyAssembly, Type};
+use tir_macros::dialect_type;
+
+use super::DIALECT_NAME;
+
+dialect_type!(RegType);
+
+impl TyAssembly for RegType {
+    fn print_assembly(
+        _attrs: &HashMap<String, tir_core::Attr>,
+        fmt: &mut dyn tir_core::IRFormatter,
+    ) {
+        fmt.write_direct("reg");
+    }
+
+    fn parse_assembly(
+        input: IRStrStream<'_>,
+    ) -> ParseResult<IRStrStream<'_>, HashMap<String, Attr>> {
+        let parser = skip_attrs();
+        parser.parse(input)
+    }
+}
+
+impl RegType {
+    pub fn build(context: ContextRef) -> RegType {
+        let dialect = context.get_dialect_by_name(DIALECT_NAME).unwrap();
+        // we are sure the type exists, because we are the type!
+        let type_id = dialect.get_type_id(RegType::get_type_name()).unwrap();
+        let r#type = Type::new(context, dialect.get_id(), type_id, HashMap::new());
+
+        RegType { r#type }
+    }
+}
@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Endianness {
     Big,
     Little,
@@ -8,3 +9,15 @@ pub struct TargetOptions {
     pub word_size: u8,
     pub flags: Vec<String>,
 }
+
+impl TargetOptions {
+    /// Whether the flag is set in the target options
+    pub fn has_flag<F: TargetFlag>(&self) -> bool {
+        self.flags.iter().any(|flag| flag == F::NAME)
+    }
+}
+
+/// Flag of the target options, that is declared with `flag` in tmdl
+pub trait TargetFlag {
+    const NAME: &'static str;
+}
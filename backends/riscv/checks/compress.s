@@ -0,0 +1,36 @@
+# RUN: tir asm --march=rv64gc --compress %s | filecheck %s
+# RUN: tir asm --march=rv64g --compress %s | filecheck %s --check-prefix=NOC
+
+.text
+compress:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    add a0, a0, a1
+    addi a0, zero, 3
+    lw a0, 8(a1)
+    beq a0, zero, compress
+    bne zero, s1, compress
+    sub s0, s1, s0
+    jalr zero, 0(ra)
+
+# CHECK: ^compress:
+# CHECK-NEXT: riscv.c_addi rd = x2, attrs = {imm = <i16: -16>}
+# CHECK-NEXT: riscv.c_sdsp rs2 = x1, attrs = {offset = <i16: 8>}
+# CHECK-NEXT: riscv.c_add rd = x10, rs2 = x11, attrs = {}
+# CHECK-NEXT: riscv.c_li rd = x10, attrs = {imm = <i16: 3>}
+# CHECK-NEXT: riscv.c_lw rd = x10, rs1 = x11, attrs = {offset = <i16: 8>}
+# CHECK-NEXT: riscv.c_beqz rs1 = x10, attrs = {target = <str: "compress">}
+# CHECK-NEXT: riscv.bne rs1 = x0, rs2 = x9, attrs = {target = <str: "compress">}
+# CHECK-NEXT: riscv.sub rd = x8, rs1 = x9, rs2 = x8, attrs = {}
+# CHECK-NEXT: riscv.c_jr rs1 = x1, attrs = {}
+
+# NOC: ^compress:
+# NOC-NEXT: riscv.addi rd = x2, rs1 = x2, attrs = {imm = <i16: -16>}
+# NOC-NEXT: riscv.sd rs1 = x2, rs2 = x1, attrs = {offset = <i16: 8>}
+# NOC-NEXT: riscv.add rd = x10, rs1 = x10, rs2 = x11, attrs = {}
+# NOC-NEXT: riscv.addi rd = x10, rs1 = x0, attrs = {imm = <i16: 3>}
+# NOC-NEXT: riscv.lw rd = x10, rs1 = x11, attrs = {offset = <i16: 8>}
+# NOC-NEXT: riscv.beq rs1 = x10, rs2 = x0, attrs = {target = <str: "compress">}
+# NOC-NEXT: riscv.bne rs1 = x0, rs2 = x9, attrs = {target = <str: "compress">}
+# NOC-NEXT: riscv.sub rd = x8, rs1 = x9, rs2 = x8, attrs = {}
+# NOC-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
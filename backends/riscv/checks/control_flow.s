@@ -0,0 +1,40 @@
+# RUN: tir asm %s | filecheck %s
+
+.text
+start:
+    lui x5, 74565
+    auipc x6, 1
+loop:
+    addi x5, x5, -1
+    bne x5, x0, loop
+    beq x5, x6, end
+    blt x5, x6, loop
+    bge x5, x6, end
+    bltu x5, x6, loop
+    bgeu x5, x6, end
+    jal x1, start
+    jalr x0, 0(x1)
+    jalr x1, x5, 16
+end:
+    jal x0, loop
+
+# CHECK: module {
+# CHECK-NEXT: target.section ".text" {
+# CHECK-NEXT: ^start:
+# CHECK-NEXT: riscv.lui rd = x5, attrs = {imm = <i32: 74565>}
+# CHECK-NEXT: riscv.auipc rd = x6, attrs = {imm = <i32: 1>}
+# CHECK-NEXT: ^loop:
+# CHECK-NEXT: riscv.addi rd = x5, rs1 = x5, attrs = {imm = <i16: -1>}
+# CHECK-NEXT: riscv.bne rs1 = x5, rs2 = x0, attrs = {target = <str: "loop">}
+# CHECK-NEXT: riscv.beq rs1 = x5, rs2 = x6, attrs = {target = <str: "end">}
+# CHECK-NEXT: riscv.blt rs1 = x5, rs2 = x6, attrs = {target = <str: "loop">}
+# CHECK-NEXT: riscv.bge rs1 = x5, rs2 = x6, attrs = {target = <str: "end">}
+# CHECK-NEXT: riscv.bltu rs1 = x5, rs2 = x6, attrs = {target = <str: "loop">}
+# CHECK-NEXT: riscv.bgeu rs1 = x5, rs2 = x6, attrs = {target = <str: "end">}
+# CHECK-NEXT: riscv.jal rd = x1, attrs = {target = <str: "start">}
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: riscv.jalr rd = x1, rs1 = x5, attrs = {offset = <i16: 16>}
+# CHECK-NEXT: ^end:
+# CHECK-NEXT: riscv.jal rd = x0, attrs = {target = <str: "loop">}
+# CHECK-NEXT: }
+# CHECK-NEXT: }
@@ -0,0 +1,94 @@
+# RUN: tir asm %s | filecheck %s
+# RUN: tir asm -o /dev/null %s
+
+	.file	"hello.c"
+	.option	nopic
+	.attribute	arch, "rv64i2p1_m2p0_a2p1_f2p2_d2p2_c2p0"
+	.equ	BUF_LEN, 4 * 16
+	.set	MASK, (1 << 12) - 1
+	.text
+	.globl	main
+	.p2align	1
+	.type	main,@function
+main:
+	lui	a0, %hi(.L.str)
+	addi	a0, a0, %lo(.L.str)
+	ld	a1, %lo(counter)(a0)
+	sd	a1, %lo(counter+8)(a0)
+.Lpcrel_hi0:
+	auipc	a2, %pcrel_hi(table)
+	addi	a2, a2, %pcrel_lo(.Lpcrel_hi0)
+	andi	a3, a2, MASK & 0xff
+	li	a4, BUF_LEN
+	call	puts@plt
+	ret
+.Lfunc_end0:
+	.size	main, .Lfunc_end0-main
+
+	.section	.rodata.str1.1,"aMS",@progbits,1
+.L.str:
+	.asciz	"Hello\n"
+
+	.data
+	.globl	table
+	.p2align	3
+table:
+	.word	1, -2, 0x7fffffff
+	.half	BUF_LEN
+	.byte	97, 0xff
+	.dword	.L.str+4
+	.size	table, 24
+
+	.bss
+	.local	counter
+	.type	counter,@object
+counter:
+	.zero	16
+	.section	".note.GNU-stack","",@progbits
+
+# CHECK: module {
+# CHECK-NEXT: target.section ".text" {
+# CHECK-NEXT: ^entry:
+# CHECK-NEXT: target.symbol_binding attrs = {binding = <str: "global">, name = <str: "main">}
+# CHECK-NEXT: target.align attrs = {align = <u64: 2>}
+# CHECK-NEXT: target.symbol_type attrs = {kind = <str: "function">, name = <str: "main">}
+# CHECK-NEXT: ^main:
+# CHECK-NEXT: riscv.lui rd = x10, attrs = {imm = <i32: 0>, reloc = <str: "%hi(.L.str)">}
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: 0>, reloc = <str: "%lo(.L.str)">}
+# CHECK-NEXT: riscv.ld rd = x11, rs1 = x10, attrs = {offset = <i16: 0>, reloc = <str: "%lo(counter)">}
+# CHECK-NEXT: riscv.sd rs1 = x10, rs2 = x11, attrs = {offset = <i16: 0>, reloc = <str: "%lo(counter+8)">}
+# CHECK-NEXT: ^.Lpcrel_hi0:
+# CHECK-NEXT: riscv.auipc rd = x12, attrs = {imm = <i32: 0>, reloc = <str: "%pcrel_hi(table)">}
+# CHECK-NEXT: riscv.addi rd = x12, rs1 = x12, attrs = {imm = <i16: 0>, reloc = <str: "%pcrel_lo(.Lpcrel_hi0)">}
+# CHECK-NEXT: riscv.andi rd = x13, rs1 = x12, attrs = {imm = <i16: 255>}
+# CHECK-NEXT: riscv.addi rd = x14, rs1 = x0, attrs = {imm = <i16: 64>}
+# CHECK-NEXT: riscv.call attrs = {target = <str: "puts">}
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: ^.Lfunc_end0:
+# CHECK-NEXT: target.symbol_size attrs = {end = <str: ".Lfunc_end0">, name = <str: "main">, start = <str: "main">}
+# CHECK-NEXT: }
+# CHECK-NEXT: target.section ".rodata.str1.1" {
+# CHECK-NEXT: ^.L.str:
+# CHECK-NEXT: target.data attrs = {size = <u8: 1>, values = <i64s: [72, 101, 108, 108, 111, 10, 0]>}
+# CHECK-NEXT: }
+# CHECK-NEXT: target.section ".data" {
+# CHECK-NEXT: ^entry:
+# CHECK-NEXT: target.symbol_binding attrs = {binding = <str: "global">, name = <str: "table">}
+# CHECK-NEXT: target.align attrs = {align = <u64: 8>}
+# CHECK-NEXT: ^table:
+# CHECK-NEXT: target.data attrs = {size = <u8: 4>, values = <i64s: [1, -2, 2147483647]>}
+# CHECK-NEXT: target.data attrs = {size = <u8: 2>, values = <i64s: [64]>}
+# CHECK-NEXT: target.data attrs = {size = <u8: 1>, values = <i64s: [97, 255]>}
+# CHECK-NEXT: target.data_ref attrs = {addend = <i64: 4>, size = <u8: 8>, symbol = <str: ".L.str">}
+# CHECK-NEXT: target.symbol_size attrs = {name = <str: "table">, value = <i64: 24>}
+# CHECK-NEXT: }
+# CHECK-NEXT: target.section ".bss" {
+# CHECK-NEXT: ^entry:
+# CHECK-NEXT: target.symbol_binding attrs = {binding = <str: "local">, name = <str: "counter">}
+# CHECK-NEXT: target.symbol_type attrs = {kind = <str: "object">, name = <str: "counter">}
+# CHECK-NEXT: ^counter:
+# CHECK-NEXT: target.zero attrs = {size = <u64: 16>}
+# CHECK-NEXT: }
+# CHECK-NEXT: target.section ".note.GNU-stack" {
+# CHECK-NEXT: }
+# CHECK-NEXT: }
@@ -0,0 +1,8 @@
+# RUN: not tir asm --march=rv64i_zicsr %s 2>&1 | filecheck %s
+
+.text
+rv64i:
+    csrrs x5, cycle, x0
+    amoswap.w.aq x5, x7, (x6)
+
+# CHECK: DisabledExtension("amoswap.w.aq", "A"
@@ -0,0 +1,57 @@
+# RUN: tir asm --march rv64imac -o /dev/stdout %s | tir disasm --march rv64imac --print-debuginfo - | filecheck %s
+
+	.text
+	.globl	main
+	.type	main,@function
+main:
+	c.addi	sp, -16
+	c.beqz	a0, .Lskip
+	addi	a0, a0, 1
+.Lskip:
+	jal	ra, helper
+	bnez	a0, main
+	ret
+.Lfunc_end0:
+	.size	main, .Lfunc_end0-main
+helper:
+	c.nop
+	c.jr	ra
+
+	.data
+	.globl	table
+table:
+	.word	1, 2
+	.size	table, 8
+
+	.bss
+buf:
+	.zero	16
+
+# CHECK: module {
+# CHECK-NEXT: target.section ".text" {
+# CHECK-NEXT: ^main:
+# CHECK-NEXT: target.symbol_binding attrs = {binding = <str: "global">, name = <str: "main">} loc(0x0)
+# CHECK-NEXT: target.symbol_type attrs = {kind = <str: "function">, name = <str: "main">} loc(0x0)
+# CHECK-NEXT: target.symbol_size attrs = {name = <str: "main">, value = <i64: 20>} loc(0x0)
+# CHECK-NEXT: riscv.c_addi rd = x2, attrs = {imm = <i16: -16>} loc(0x0)
+# CHECK-NEXT: riscv.c_beqz rs1 = x10, attrs = {target = <str: "L0x8">} loc(0x2)
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: 1>} loc(0x4)
+# CHECK-NEXT: ^L0x8:
+# CHECK-NEXT: riscv.jal rd = x1, attrs = {target = <str: "helper">} loc(0x8)
+# CHECK-NEXT: riscv.bne rs1 = x10, rs2 = x0, attrs = {target = <str: "main">} loc(0xc)
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>} loc(0x10)
+# CHECK-NEXT: ^helper:
+# CHECK-NEXT: riscv.c_nop attrs = {} loc(0x14)
+# CHECK-NEXT: riscv.c_jr rs1 = x1, attrs = {} loc(0x16)
+# CHECK-NEXT: } loc(0x0)
+# CHECK-NEXT: target.section ".data" {
+# CHECK-NEXT: ^table:
+# CHECK-NEXT: target.symbol_binding attrs = {binding = <str: "global">, name = <str: "table">} loc(0x0)
+# CHECK-NEXT: target.symbol_size attrs = {name = <str: "table">, value = <i64: 8>} loc(0x0)
+# CHECK-NEXT: target.data attrs = {size = <u8: 1>, values = <i64s: [1, 0, 0, 0, 2, 0, 0, 0]>} loc(0x0)
+# CHECK-NEXT: } loc(0x0)
+# CHECK-NEXT: target.section ".bss" {
+# CHECK-NEXT: ^buf:
+# CHECK-NEXT: target.zero attrs = {size = <u64: 16>} loc(0x0)
+# CHECK-NEXT: } loc(0x0)
+# CHECK-NEXT: } loc(unknown)
@@ -0,0 +1,20 @@
+# RUN: tir asm --march rv64imac -o /dev/stdout %s | tir disasm --march rv64i --recover - 2>&1 | filecheck %s
+# RUN: tir asm --march rv64imac -o /dev/stdout %s | not tir disasm --march rv64i - 2>&1 | filecheck %s --check-prefix=ERR
+
+	.text
+main:
+	addi	a0, a0, 1
+	mul	a0, a0, a1
+	c.addi	sp, -16
+	.word	0x0000100b
+	ret
+
+# CHECK: note: decoded 2 instructions (8 bytes), 3 unknown words (10 bytes), 44.4% coverage
+# CHECK: ^main:
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: 1>}
+# CHECK-NEXT: riscv.unknown_word attrs = {size = <i64: 4>, value = <i64: 45417779>}
+# CHECK-NEXT: riscv.unknown_word attrs = {size = <i64: 2>, value = <i64: 4417>}
+# CHECK-NEXT: riscv.unknown_word attrs = {size = <i64: 4>, value = <i64: 4107>}
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+
+# ERR: unknown instruction `0x02b50533` at `0x4`
@@ -0,0 +1,20 @@
+# RUN: tir asm --march rv64imac -o /dev/stdout %s | tir disasm --march rv64imac --print-debuginfo - | filecheck %s
+
+# Jumps to undefined symbols are encoded with a zero offset and a relocation, that
+# names the symbol
+
+	.text
+main:
+	jal	ra, puts
+	beq	a0, a1, other
+	call	puts
+	tail	other
+	j	main
+
+# CHECK: ^main:
+# CHECK-NEXT: riscv.jal rd = x1, attrs = {target = <str: "puts">} loc(0x0)
+# CHECK-NEXT: riscv.beq rs1 = x10, rs2 = x11, attrs = {target = <str: "other">} loc(0x4)
+# CHECK-NEXT: riscv.call attrs = {target = <str: "puts">} loc(0x8)
+# CHECK-NEXT: riscv.tail attrs = {target = <str: "other">} loc(0x10)
+# CHECK-NEXT: riscv.jal rd = x0, attrs = {target = <str: "main">} loc(0x18)
+# CHECK-NEXT: } loc(0x0)
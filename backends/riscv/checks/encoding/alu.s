@@ -0,0 +1,93 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+# RUN: tir asm --march rv64gc -o /dev/stdout %s | tir disasm --march rv64gc - | filecheck %s --check-prefix=DISASM
+
+.text
+alu:
+    add x28, x6, x7
+    sub x28, x6, x7
+    sll x28, x6, x7
+    slt x28, x6, x7
+    sltu x28, x6, x7
+    xor x28, x6, x7
+    srl x28, x6, x7
+    sra x28, x6, x7
+    or x28, x6, x7
+    and x28, x6, x7
+    addw x28, x6, x7
+    subw x28, x6, x7
+    sllw x28, x6, x7
+    srlw x28, x6, x7
+    sraw x28, x6, x7
+    addi x28, x6, -42
+    slli x28, x6, 63
+    slti x28, x6, -8
+    sltiu x28, x6, 2047
+    xori x28, x6, -1
+    srli x28, x6, 33
+    srai x28, x6, 63
+    ori x28, x6, 1
+    andi x28, x6, -2048
+    addiw x28, x6, -1
+    slliw x28, x6, 31
+    srliw x28, x6, 5
+    sraiw x28, x6, 31
+
+# CHECK: alu:
+# CHECK-NEXT: add x28, x6, x7 # encoding: [0x33,0x0e,0x73,0x00]
+# CHECK-NEXT: sub x28, x6, x7 # encoding: [0x33,0x0e,0x73,0x40]
+# CHECK-NEXT: sll x28, x6, x7 # encoding: [0x33,0x1e,0x73,0x00]
+# CHECK-NEXT: slt x28, x6, x7 # encoding: [0x33,0x2e,0x73,0x00]
+# CHECK-NEXT: sltu x28, x6, x7 # encoding: [0x33,0x3e,0x73,0x00]
+# CHECK-NEXT: xor x28, x6, x7 # encoding: [0x33,0x4e,0x73,0x00]
+# CHECK-NEXT: srl x28, x6, x7 # encoding: [0x33,0x5e,0x73,0x00]
+# CHECK-NEXT: sra x28, x6, x7 # encoding: [0x33,0x5e,0x73,0x40]
+# CHECK-NEXT: or x28, x6, x7 # encoding: [0x33,0x6e,0x73,0x00]
+# CHECK-NEXT: and x28, x6, x7 # encoding: [0x33,0x7e,0x73,0x00]
+# CHECK-NEXT: addw x28, x6, x7 # encoding: [0x3b,0x0e,0x73,0x00]
+# CHECK-NEXT: subw x28, x6, x7 # encoding: [0x3b,0x0e,0x73,0x40]
+# CHECK-NEXT: sllw x28, x6, x7 # encoding: [0x3b,0x1e,0x73,0x00]
+# CHECK-NEXT: srlw x28, x6, x7 # encoding: [0x3b,0x5e,0x73,0x00]
+# CHECK-NEXT: sraw x28, x6, x7 # encoding: [0x3b,0x5e,0x73,0x40]
+# CHECK-NEXT: addi x28, x6, -42 # encoding: [0x13,0x0e,0x63,0xfd]
+# CHECK-NEXT: slli x28, x6, 63 # encoding: [0x13,0x1e,0xf3,0x03]
+# CHECK-NEXT: slti x28, x6, -8 # encoding: [0x13,0x2e,0x83,0xff]
+# CHECK-NEXT: sltiu x28, x6, 2047 # encoding: [0x13,0x3e,0xf3,0x7f]
+# CHECK-NEXT: xori x28, x6, -1 # encoding: [0x13,0x4e,0xf3,0xff]
+# CHECK-NEXT: srli x28, x6, 33 # encoding: [0x13,0x5e,0x13,0x02]
+# CHECK-NEXT: srai x28, x6, 63 # encoding: [0x13,0x5e,0xf3,0x43]
+# CHECK-NEXT: ori x28, x6, 1 # encoding: [0x13,0x6e,0x13,0x00]
+# CHECK-NEXT: andi x28, x6, -2048 # encoding: [0x13,0x7e,0x03,0x80]
+# CHECK-NEXT: addiw x28, x6, -1 # encoding: [0x1b,0x0e,0xf3,0xff]
+# CHECK-NEXT: slliw x28, x6, 31 # encoding: [0x1b,0x1e,0xf3,0x01]
+# CHECK-NEXT: srliw x28, x6, 5 # encoding: [0x1b,0x5e,0x53,0x00]
+# CHECK-NEXT: sraiw x28, x6, 31 # encoding: [0x1b,0x5e,0xf3,0x41]
+
+# DISASM: ^alu:
+# DISASM-NEXT: riscv.add rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.sub rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.sll rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.slt rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.sltu rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.xor rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.srl rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.sra rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.or rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.and rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.addw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.subw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.sllw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.srlw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.sraw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.addi rd = x28, rs1 = x6, attrs = {imm = <i16: -42>}
+# DISASM-NEXT: riscv.slli rd = x28, rs1 = x6, attrs = {imm = <i16: 63>}
+# DISASM-NEXT: riscv.slti rd = x28, rs1 = x6, attrs = {imm = <i16: -8>}
+# DISASM-NEXT: riscv.sltiu rd = x28, rs1 = x6, attrs = {imm = <i16: 2047>}
+# DISASM-NEXT: riscv.xori rd = x28, rs1 = x6, attrs = {imm = <i16: -1>}
+# DISASM-NEXT: riscv.srli rd = x28, rs1 = x6, attrs = {imm = <i16: 33>}
+# DISASM-NEXT: riscv.srai rd = x28, rs1 = x6, attrs = {imm = <i16: 63>}
+# DISASM-NEXT: riscv.ori rd = x28, rs1 = x6, attrs = {imm = <i16: 1>}
+# DISASM-NEXT: riscv.andi rd = x28, rs1 = x6, attrs = {imm = <i16: -2048>}
+# DISASM-NEXT: riscv.addiw rd = x28, rs1 = x6, attrs = {imm = <i16: -1>}
+# DISASM-NEXT: riscv.slliw rd = x28, rs1 = x6, attrs = {imm = <i16: 31>}
+# DISASM-NEXT: riscv.srliw rd = x28, rs1 = x6, attrs = {imm = <i16: 5>}
+# DISASM-NEXT: riscv.sraiw rd = x28, rs1 = x6, attrs = {imm = <i16: 31>}
@@ -0,0 +1,75 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+# RUN: tir asm --march rv64gc -o /dev/stdout %s | tir disasm --march rv64gc - | filecheck %s --check-prefix=DISASM
+
+.text
+atomic:
+    lr.w x5, (x6)
+    lr.d.aq x5, (x6)
+    sc.w.rl x5, x7, (x6)
+    sc.d x5, x7, (x6)
+    amoswap.w x5, x7, (x6)
+    amoadd.w.aqrl x5, x7, (x6)
+    amoxor.w x5, x7, (x6)
+    amoand.w x5, x7, (x6)
+    amoor.w x5, x7, (x6)
+    amomin.w x5, x7, (x6)
+    amomax.w x5, x7, (x6)
+    amominu.w x5, x7, (x6)
+    amomaxu.w x5, x7, (x6)
+    amoswap.d.aq x5, x7, (x6)
+    amoadd.d x5, x7, 0(x6)
+    amoxor.d x5, x7, (x6)
+    amoand.d x5, x7, (x6)
+    amoor.d.rl x5, x7, (x6)
+    amomin.d x5, x7, (x6)
+    amomax.d x5, x7, (x6)
+    amominu.d x5, x7, (x6)
+    amomaxu.d x5, x7, (x6)
+
+# CHECK: atomic:
+# CHECK-NEXT: lr.w x5, (x6) # encoding: [0xaf,0x22,0x03,0x10]
+# CHECK-NEXT: lr.d.aq x5, (x6) # encoding: [0xaf,0x32,0x03,0x14]
+# CHECK-NEXT: sc.w.rl x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0x1a]
+# CHECK-NEXT: sc.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0x18]
+# CHECK-NEXT: amoswap.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0x08]
+# CHECK-NEXT: amoadd.w.aqrl x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0x06]
+# CHECK-NEXT: amoxor.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0x20]
+# CHECK-NEXT: amoand.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0x60]
+# CHECK-NEXT: amoor.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0x40]
+# CHECK-NEXT: amomin.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0x80]
+# CHECK-NEXT: amomax.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0xa0]
+# CHECK-NEXT: amominu.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0xc0]
+# CHECK-NEXT: amomaxu.w x5, x7, (x6) # encoding: [0xaf,0x22,0x73,0xe0]
+# CHECK-NEXT: amoswap.d.aq x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0x0c]
+# CHECK-NEXT: amoadd.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0x00]
+# CHECK-NEXT: amoxor.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0x20]
+# CHECK-NEXT: amoand.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0x60]
+# CHECK-NEXT: amoor.d.rl x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0x42]
+# CHECK-NEXT: amomin.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0x80]
+# CHECK-NEXT: amomax.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0xa0]
+# CHECK-NEXT: amominu.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0xc0]
+# CHECK-NEXT: amomaxu.d x5, x7, (x6) # encoding: [0xaf,0x32,0x73,0xe0]
+
+# DISASM: ^atomic:
+# DISASM-NEXT: riscv.lr_w rd = x5, rs1 = x6, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.lr_d rd = x5, rs1 = x6, attrs = {aq = <bool: true>, rl = <bool: false>}
+# DISASM-NEXT: riscv.sc_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: true>}
+# DISASM-NEXT: riscv.sc_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoswap_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoadd_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: true>, rl = <bool: true>}
+# DISASM-NEXT: riscv.amoxor_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoand_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoor_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amomin_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amomax_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amominu_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amomaxu_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoswap_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: true>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoadd_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoxor_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoand_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amoor_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: true>}
+# DISASM-NEXT: riscv.amomin_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amomax_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amominu_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# DISASM-NEXT: riscv.amomaxu_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
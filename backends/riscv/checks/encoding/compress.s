@@ -0,0 +1,76 @@
+# RUN: tir asm --march rv64gc --compress --emit=asm --show-encoding %s | filecheck %s
+
+.text
+start:
+    addi sp, sp, -64
+    sd ra, 56(sp)
+    fsd fs0, 48(sp)
+    addi s0, sp, 64
+    add a0, a0, a1
+    add a0, a1, a0
+    add a0, zero, a1
+    addi a1, a0, 0
+    addi a2, zero, -5
+    addi a0, a0, 31
+    addi a0, a0, 32
+    addiw a1, a1, -1
+    slli t0, t0, 3
+    srli a5, a5, 1
+    srai a4, a4, 63
+    andi a3, a3, 15
+    sub s0, s0, s1
+    sub s0, s1, s0
+    and a0, a1, a0
+    lui t1, 1048575
+    lui t1, 32
+    lw a0, 8(a1)
+    ld a0, 8(a6)
+    sw a2, 4(sp)
+    fld fa5, 8(a0)
+    beq a0, zero, start
+    bne zero, s1, end
+    jal zero, start
+    jalr zero, 0(ra)
+    jalr ra, 0(t0)
+    ebreak
+    addi zero, zero, 0
+    ld ra, 56(sp)
+end:
+    addi sp, sp, 64
+
+# CHECK: start:
+# CHECK-NEXT: c.addi16sp x2, -64 # encoding: [0x39,0x71]
+# CHECK-NEXT: c.sdsp x1, 56(x2) # encoding: [0x06,0xfc]
+# CHECK-NEXT: c.fsdsp f8, 48(x2) # encoding: [0x22,0xb8]
+# CHECK-NEXT: c.addi4spn x8, x2, 64 # encoding: [0x80,0x00]
+# CHECK-NEXT: c.add x10, x11 # encoding: [0x2e,0x95]
+# CHECK-NEXT: c.add x10, x11 # encoding: [0x2e,0x95]
+# CHECK-NEXT: c.mv x10, x11 # encoding: [0x2e,0x85]
+# CHECK-NEXT: c.mv x11, x10 # encoding: [0xaa,0x85]
+# CHECK-NEXT: c.li x12, -5 # encoding: [0x6d,0x56]
+# CHECK-NEXT: c.addi x10, 31 # encoding: [0x7d,0x05]
+# CHECK-NEXT: addi x10, x10, 32 # encoding: [0x13,0x05,0x05,0x02]
+# CHECK-NEXT: c.addiw x11, -1 # encoding: [0xfd,0x35]
+# CHECK-NEXT: c.slli x5, 3 # encoding: [0x8e,0x02]
+# CHECK-NEXT: c.srli x15, 1 # encoding: [0x85,0x83]
+# CHECK-NEXT: c.srai x14, 63 # encoding: [0x7d,0x97]
+# CHECK-NEXT: c.andi x13, 15 # encoding: [0xbd,0x8a]
+# CHECK-NEXT: c.sub x8, x9 # encoding: [0x05,0x8c]
+# CHECK-NEXT: sub x8, x9, x8 # encoding: [0x33,0x84,0x84,0x40]
+# CHECK-NEXT: c.and x10, x11 # encoding: [0x6d,0x8d]
+# CHECK-NEXT: c.lui x6, 0xfffff # encoding: [0x7d,0x73]
+# CHECK-NEXT: lui x6, 0x20 # encoding: [0x37,0x03,0x02,0x00]
+# CHECK-NEXT: c.lw x10, 8(x11) # encoding: [0x88,0x45]
+# CHECK-NEXT: ld x10, 8(x16) # encoding: [0x03,0x35,0x88,0x00]
+# CHECK-NEXT: c.swsp x12, 4(x2) # encoding: [0x32,0xc2]
+# CHECK-NEXT: c.fld f15, 8(x10) # encoding: [0x1c,0x25]
+# CHECK-NEXT: c.beqz x10, start # encoding: [0x79,0xd1]
+# CHECK-NEXT: bne x0, x9, end # encoding: [0x63,0x18,0x90,0x00]
+# CHECK-NEXT: c.j start # encoding: [0xc1,0xb7]
+# CHECK-NEXT: c.jr x1 # encoding: [0x82,0x80]
+# CHECK-NEXT: c.jalr x5 # encoding: [0x82,0x92]
+# CHECK-NEXT: c.ebreak # encoding: [0x02,0x90]
+# CHECK-NEXT: c.nop # encoding: [0x01,0x00]
+# CHECK-NEXT: c.ldsp x1, 56(x2) # encoding: [0xe2,0x70]
+# CHECK-NEXT: end:
+# CHECK-NEXT: c.addi16sp x2, 64 # encoding: [0x21,0x61]
@@ -0,0 +1,123 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+# RUN: tir asm --march rv64gc -o /dev/stdout %s | tir disasm --march rv64gc - | filecheck %s --check-prefix=DISASM
+
+.text
+l:
+    c.addi4spn x8, x2, 1020
+    c.addi4spn x15, x2, 4
+    c.fld f10, 8(x11)
+    c.lw x10, 124(x11)
+    c.ld x9, 248(x15)
+    c.fsd f8, 248(x10)
+    c.sw x10, 4(x11)
+    c.sd x15, 8(x8)
+    c.nop
+    c.addi x10, -32
+    c.addi x31, 31
+    c.addiw x10, 1
+    c.li x1, -1
+    c.addi16sp x2, -512
+    c.addi16sp x2, 496
+    c.lui x8, 1
+    c.lui x5, 1048544
+    c.srli x10, 63
+    c.srai x9, 1
+    c.andi x15, -32
+    c.sub x10, x11
+    c.xor x8, x9
+    c.or x12, x13
+    c.and x14, x15
+    c.subw x10, x11
+    c.addw x10, x11
+    c.slli x1, 1
+    c.fldsp f0, 504(x2)
+    c.lwsp x1, 252(x2)
+    c.ldsp x5, 8(x2)
+    c.fsdsp f1, 0(x2)
+    c.swsp x10, 4(x2)
+    c.sdsp x27, 504(x2)
+    c.jr x1
+    c.mv x10, x11
+    c.ebreak
+    c.jalr x5
+    c.add x10, x11
+
+# CHECK: l:
+# CHECK-NEXT: c.addi4spn x8, x2, 1020 # encoding: [0xe0,0x1f]
+# CHECK-NEXT: c.addi4spn x15, x2, 4 # encoding: [0x5c,0x00]
+# CHECK-NEXT: c.fld f10, 8(x11) # encoding: [0x88,0x25]
+# CHECK-NEXT: c.lw x10, 124(x11) # encoding: [0xe8,0x5d]
+# CHECK-NEXT: c.ld x9, 248(x15) # encoding: [0xe4,0x7f]
+# CHECK-NEXT: c.fsd f8, 248(x10) # encoding: [0x60,0xbd]
+# CHECK-NEXT: c.sw x10, 4(x11) # encoding: [0xc8,0xc1]
+# CHECK-NEXT: c.sd x15, 8(x8) # encoding: [0x1c,0xe4]
+# CHECK-NEXT: c.nop # encoding: [0x01,0x00]
+# CHECK-NEXT: c.addi x10, -32 # encoding: [0x01,0x15]
+# CHECK-NEXT: c.addi x31, 31 # encoding: [0xfd,0x0f]
+# CHECK-NEXT: c.addiw x10, 1 # encoding: [0x05,0x25]
+# CHECK-NEXT: c.li x1, -1 # encoding: [0xfd,0x50]
+# CHECK-NEXT: c.addi16sp x2, -512 # encoding: [0x01,0x71]
+# CHECK-NEXT: c.addi16sp x2, 496 # encoding: [0x7d,0x61]
+# CHECK-NEXT: c.lui x8, 0x1 # encoding: [0x05,0x64]
+# CHECK-NEXT: c.lui x5, 0xfffe0 # encoding: [0x81,0x72]
+# CHECK-NEXT: c.srli x10, 63 # encoding: [0x7d,0x91]
+# CHECK-NEXT: c.srai x9, 1 # encoding: [0x85,0x84]
+# CHECK-NEXT: c.andi x15, -32 # encoding: [0x81,0x9b]
+# CHECK-NEXT: c.sub x10, x11 # encoding: [0x0d,0x8d]
+# CHECK-NEXT: c.xor x8, x9 # encoding: [0x25,0x8c]
+# CHECK-NEXT: c.or x12, x13 # encoding: [0x55,0x8e]
+# CHECK-NEXT: c.and x14, x15 # encoding: [0x7d,0x8f]
+# CHECK-NEXT: c.subw x10, x11 # encoding: [0x0d,0x9d]
+# CHECK-NEXT: c.addw x10, x11 # encoding: [0x2d,0x9d]
+# CHECK-NEXT: c.slli x1, 1 # encoding: [0x86,0x00]
+# CHECK-NEXT: c.fldsp f0, 504(x2) # encoding: [0x7e,0x30]
+# CHECK-NEXT: c.lwsp x1, 252(x2) # encoding: [0xfe,0x50]
+# CHECK-NEXT: c.ldsp x5, 8(x2) # encoding: [0xa2,0x62]
+# CHECK-NEXT: c.fsdsp f1, 0(x2) # encoding: [0x06,0xa0]
+# CHECK-NEXT: c.swsp x10, 4(x2) # encoding: [0x2a,0xc2]
+# CHECK-NEXT: c.sdsp x27, 504(x2) # encoding: [0xee,0xff]
+# CHECK-NEXT: c.jr x1 # encoding: [0x82,0x80]
+# CHECK-NEXT: c.mv x10, x11 # encoding: [0x2e,0x85]
+# CHECK-NEXT: c.ebreak # encoding: [0x02,0x90]
+# CHECK-NEXT: c.jalr x5 # encoding: [0x82,0x92]
+# CHECK-NEXT: c.add x10, x11 # encoding: [0x2e,0x95]
+
+# DISASM: ^l:
+# DISASM-NEXT: riscv.c_addi4spn rd = x8, attrs = {imm = <i16: 1020>}
+# DISASM-NEXT: riscv.c_addi4spn rd = x15, attrs = {imm = <i16: 4>}
+# DISASM-NEXT: riscv.c_fld rd = f10, rs1 = x11, attrs = {offset = <i16: 8>}
+# DISASM-NEXT: riscv.c_lw rd = x10, rs1 = x11, attrs = {offset = <i16: 124>}
+# DISASM-NEXT: riscv.c_ld rd = x9, rs1 = x15, attrs = {offset = <i16: 248>}
+# DISASM-NEXT: riscv.c_fsd rs1 = x10, rs2 = f8, attrs = {offset = <i16: 248>}
+# DISASM-NEXT: riscv.c_sw rs1 = x11, rs2 = x10, attrs = {offset = <i16: 4>}
+# DISASM-NEXT: riscv.c_sd rs1 = x8, rs2 = x15, attrs = {offset = <i16: 8>}
+# DISASM-NEXT: riscv.c_nop attrs = {}
+# DISASM-NEXT: riscv.c_addi rd = x10, attrs = {imm = <i16: -32>}
+# DISASM-NEXT: riscv.c_addi rd = x31, attrs = {imm = <i16: 31>}
+# DISASM-NEXT: riscv.c_addiw rd = x10, attrs = {imm = <i16: 1>}
+# DISASM-NEXT: riscv.c_li rd = x1, attrs = {imm = <i16: -1>}
+# DISASM-NEXT: riscv.c_addi16sp attrs = {imm = <i16: -512>}
+# DISASM-NEXT: riscv.c_addi16sp attrs = {imm = <i16: 496>}
+# DISASM-NEXT: riscv.c_lui rd = x8, attrs = {imm = <i32: 1>}
+# DISASM-NEXT: riscv.c_lui rd = x5, attrs = {imm = <i32: 1048544>}
+# DISASM-NEXT: riscv.c_srli rd = x10, attrs = {imm = <i16: 63>}
+# DISASM-NEXT: riscv.c_srai rd = x9, attrs = {imm = <i16: 1>}
+# DISASM-NEXT: riscv.c_andi rd = x15, attrs = {imm = <i16: -32>}
+# DISASM-NEXT: riscv.c_sub rd = x10, rs2 = x11, attrs = {}
+# DISASM-NEXT: riscv.c_xor rd = x8, rs2 = x9, attrs = {}
+# DISASM-NEXT: riscv.c_or rd = x12, rs2 = x13, attrs = {}
+# DISASM-NEXT: riscv.c_and rd = x14, rs2 = x15, attrs = {}
+# DISASM-NEXT: riscv.c_subw rd = x10, rs2 = x11, attrs = {}
+# DISASM-NEXT: riscv.c_addw rd = x10, rs2 = x11, attrs = {}
+# DISASM-NEXT: riscv.c_slli rd = x1, attrs = {imm = <i16: 1>}
+# DISASM-NEXT: riscv.c_fldsp rd = f0, attrs = {offset = <i16: 504>}
+# DISASM-NEXT: riscv.c_lwsp rd = x1, attrs = {offset = <i16: 252>}
+# DISASM-NEXT: riscv.c_ldsp rd = x5, attrs = {offset = <i16: 8>}
+# DISASM-NEXT: riscv.c_fsdsp rs2 = f1, attrs = {offset = <i16: 0>}
+# DISASM-NEXT: riscv.c_swsp rs2 = x10, attrs = {offset = <i16: 4>}
+# DISASM-NEXT: riscv.c_sdsp rs2 = x27, attrs = {offset = <i16: 504>}
+# DISASM-NEXT: riscv.c_jr rs1 = x1, attrs = {}
+# DISASM-NEXT: riscv.c_mv rd = x10, rs2 = x11, attrs = {}
+# DISASM-NEXT: riscv.c_ebreak attrs = {}
+# DISASM-NEXT: riscv.c_jalr rs1 = x5, attrs = {}
+# DISASM-NEXT: riscv.c_add rd = x10, rs2 = x11, attrs = {}
@@ -0,0 +1,20 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+
+.text
+start:
+    c.beqz x10, fwd
+    c.bnez x9, start
+    c.j start
+    c.j fwd
+    add x10, x11, x12
+fwd:
+    c.nop
+
+# CHECK: start:
+# CHECK-NEXT: c.beqz x10, fwd # encoding: [0x11,0xc5]
+# CHECK-NEXT: c.bnez x9, start # encoding: [0xfd,0xfc]
+# CHECK-NEXT: c.j start # encoding: [0xf5,0xbf]
+# CHECK-NEXT: c.j fwd # encoding: [0x19,0xa0]
+# CHECK-NEXT: add x10, x11, x12 # encoding: [0x33,0x85,0xc5,0x00]
+# CHECK-NEXT: fwd:
+# CHECK-NEXT: c.nop # encoding: [0x01,0x00]
@@ -0,0 +1,34 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+
+.text
+start:
+    lui x5, 74565
+    auipc x6, 1
+loop:
+    addi x5, x5, -1
+    bne x5, x0, loop
+    beq x5, x6, end
+    blt x5, x6, loop
+    bge x5, x6, end
+    bltu x5, x6, loop
+    bgeu x5, x6, end
+    jal x1, start
+    jalr x0, 0(x1)
+end:
+    jal x0, loop
+
+# CHECK: start:
+# CHECK-NEXT: lui x5, 0x12345 # encoding: [0xb7,0x52,0x34,0x12]
+# CHECK-NEXT: auipc x6, 0x1 # encoding: [0x17,0x13,0x00,0x00]
+# CHECK-NEXT: loop:
+# CHECK-NEXT: addi x5, x5, -1 # encoding: [0x93,0x82,0xf2,0xff]
+# CHECK-NEXT: bne x5, x0, loop # encoding: [0xe3,0x9e,0x02,0xfe]
+# CHECK-NEXT: beq x5, x6, end # encoding: [0x63,0x8e,0x62,0x00]
+# CHECK-NEXT: blt x5, x6, loop # encoding: [0xe3,0xca,0x62,0xfe]
+# CHECK-NEXT: bge x5, x6, end # encoding: [0x63,0xda,0x62,0x00]
+# CHECK-NEXT: bltu x5, x6, loop # encoding: [0xe3,0xe6,0x62,0xfe]
+# CHECK-NEXT: bgeu x5, x6, end # encoding: [0x63,0xf6,0x62,0x00]
+# CHECK-NEXT: jal x1, start # encoding: [0xef,0xf0,0xdf,0xfd]
+# CHECK-NEXT: jalr x0, 0(x1) # encoding: [0x67,0x80,0x00,0x00]
+# CHECK-NEXT: end:
+# CHECK-NEXT: jal x0, loop # encoding: [0x6f,0xf0,0xdf,0xfd]
@@ -0,0 +1,195 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+# RUN: tir asm --march rv64gc -o /dev/stdout %s | tir disasm --march rv64gc - | filecheck %s --check-prefix=DISASM
+
+.text
+float:
+    flw ft0, 8(a0)
+    fsw ft1, -4(sp)
+    fld fa0, 16(a1)
+    fsd fs0, 0(sp)
+    fmadd.s ft0, ft1, ft2, ft3
+    fmsub.s ft0, ft1, ft2, ft3, rtz
+    fnmsub.s ft0, ft1, ft2, ft3, rne
+    fnmadd.s ft0, ft1, ft2, ft3, dyn
+    fadd.s ft0, ft1, ft2
+    fsub.s ft0, ft1, ft2, rdn
+    fmul.s ft0, ft1, ft2, rup
+    fdiv.s ft0, ft1, ft2, rmm
+    fsqrt.s ft0, ft1
+    fsgnj.s ft0, ft1, ft2
+    fsgnjn.s ft0, ft1, ft2
+    fsgnjx.s ft0, ft1, ft2
+    fmin.s ft0, ft1, ft2
+    fmax.s ft0, ft1, ft2
+    fcvt.w.s a0, ft0
+    fcvt.wu.s a0, ft0, rtz
+    fcvt.l.s a0, ft0
+    fcvt.lu.s a0, ft0
+    fmv.x.w a0, ft0
+    feq.s a0, ft0, ft1
+    flt.s a0, ft0, ft1
+    fle.s a0, ft0, ft1
+    fclass.s a0, ft0
+    fcvt.s.w ft0, a0
+    fcvt.s.wu ft0, a0
+    fcvt.s.l ft0, a0
+    fcvt.s.lu ft0, a0, rne
+    fmv.w.x ft0, a0
+    fmadd.d fa0, fa1, fa2, fa3
+    fmsub.d fa0, fa1, fa2, fa3
+    fnmsub.d fa0, fa1, fa2, fa3
+    fnmadd.d fa0, fa1, fa2, fa3
+    fadd.d fa0, fa1, fa2
+    fsub.d fa0, fa1, fa2
+    fmul.d fa0, fa1, fa2
+    fdiv.d fa0, fa1, fa2
+    fsqrt.d fa0, fa1, rtz
+    fsgnj.d fa0, fa1, fa2
+    fsgnjn.d fa0, fa1, fa2
+    fsgnjx.d fa0, fa1, fa2
+    fmin.d fa0, fa1, fa2
+    fmax.d fa0, fa1, fa2
+    fcvt.s.d ft0, fa0
+    fcvt.d.s fa0, ft0
+    feq.d a0, fa0, fa1
+    flt.d a0, fa0, fa1
+    fle.d a0, fa0, fa1
+    fclass.d a0, fa0
+    fcvt.w.d a0, fa0
+    fcvt.wu.d a0, fa0
+    fcvt.d.w fa0, a0
+    fcvt.d.wu fa0, a0
+    fcvt.l.d a0, fa0
+    fcvt.lu.d a0, fa0
+    fmv.x.d a0, fa0
+    fcvt.d.l fa0, a0
+    fcvt.d.lu fa0, a0
+    fmv.d.x fa0, a0
+
+# CHECK: float:
+# CHECK-NEXT: flw f0, 8(x10) # encoding: [0x07,0x20,0x85,0x00]
+# CHECK-NEXT: fsw f1, -4(x2) # encoding: [0x27,0x2e,0x11,0xfe]
+# CHECK-NEXT: fld f10, 16(x11) # encoding: [0x07,0xb5,0x05,0x01]
+# CHECK-NEXT: fsd f8, 0(x2) # encoding: [0x27,0x30,0x81,0x00]
+# CHECK-NEXT: fmadd.s f0, f1, f2, f3 # encoding: [0x43,0xf0,0x20,0x18]
+# CHECK-NEXT: fmsub.s f0, f1, f2, f3, rtz # encoding: [0x47,0x90,0x20,0x18]
+# CHECK-NEXT: fnmsub.s f0, f1, f2, f3, rne # encoding: [0x4b,0x80,0x20,0x18]
+# CHECK-NEXT: fnmadd.s f0, f1, f2, f3 # encoding: [0x4f,0xf0,0x20,0x18]
+# CHECK-NEXT: fadd.s f0, f1, f2 # encoding: [0x53,0xf0,0x20,0x00]
+# CHECK-NEXT: fsub.s f0, f1, f2, rdn # encoding: [0x53,0xa0,0x20,0x08]
+# CHECK-NEXT: fmul.s f0, f1, f2, rup # encoding: [0x53,0xb0,0x20,0x10]
+# CHECK-NEXT: fdiv.s f0, f1, f2, rmm # encoding: [0x53,0xc0,0x20,0x18]
+# CHECK-NEXT: fsqrt.s f0, f1 # encoding: [0x53,0xf0,0x00,0x58]
+# CHECK-NEXT: fsgnj.s f0, f1, f2 # encoding: [0x53,0x80,0x20,0x20]
+# CHECK-NEXT: fsgnjn.s f0, f1, f2 # encoding: [0x53,0x90,0x20,0x20]
+# CHECK-NEXT: fsgnjx.s f0, f1, f2 # encoding: [0x53,0xa0,0x20,0x20]
+# CHECK-NEXT: fmin.s f0, f1, f2 # encoding: [0x53,0x80,0x20,0x28]
+# CHECK-NEXT: fmax.s f0, f1, f2 # encoding: [0x53,0x90,0x20,0x28]
+# CHECK-NEXT: fcvt.w.s x10, f0 # encoding: [0x53,0x75,0x00,0xc0]
+# CHECK-NEXT: fcvt.wu.s x10, f0, rtz # encoding: [0x53,0x15,0x10,0xc0]
+# CHECK-NEXT: fcvt.l.s x10, f0 # encoding: [0x53,0x75,0x20,0xc0]
+# CHECK-NEXT: fcvt.lu.s x10, f0 # encoding: [0x53,0x75,0x30,0xc0]
+# CHECK-NEXT: fmv.x.w x10, f0 # encoding: [0x53,0x05,0x00,0xe0]
+# CHECK-NEXT: feq.s x10, f0, f1 # encoding: [0x53,0x25,0x10,0xa0]
+# CHECK-NEXT: flt.s x10, f0, f1 # encoding: [0x53,0x15,0x10,0xa0]
+# CHECK-NEXT: fle.s x10, f0, f1 # encoding: [0x53,0x05,0x10,0xa0]
+# CHECK-NEXT: fclass.s x10, f0 # encoding: [0x53,0x15,0x00,0xe0]
+# CHECK-NEXT: fcvt.s.w f0, x10 # encoding: [0x53,0x70,0x05,0xd0]
+# CHECK-NEXT: fcvt.s.wu f0, x10 # encoding: [0x53,0x70,0x15,0xd0]
+# CHECK-NEXT: fcvt.s.l f0, x10 # encoding: [0x53,0x70,0x25,0xd0]
+# CHECK-NEXT: fcvt.s.lu f0, x10, rne # encoding: [0x53,0x00,0x35,0xd0]
+# CHECK-NEXT: fmv.w.x f0, x10 # encoding: [0x53,0x00,0x05,0xf0]
+# CHECK-NEXT: fmadd.d f10, f11, f12, f13 # encoding: [0x43,0xf5,0xc5,0x6a]
+# CHECK-NEXT: fmsub.d f10, f11, f12, f13 # encoding: [0x47,0xf5,0xc5,0x6a]
+# CHECK-NEXT: fnmsub.d f10, f11, f12, f13 # encoding: [0x4b,0xf5,0xc5,0x6a]
+# CHECK-NEXT: fnmadd.d f10, f11, f12, f13 # encoding: [0x4f,0xf5,0xc5,0x6a]
+# CHECK-NEXT: fadd.d f10, f11, f12 # encoding: [0x53,0xf5,0xc5,0x02]
+# CHECK-NEXT: fsub.d f10, f11, f12 # encoding: [0x53,0xf5,0xc5,0x0a]
+# CHECK-NEXT: fmul.d f10, f11, f12 # encoding: [0x53,0xf5,0xc5,0x12]
+# CHECK-NEXT: fdiv.d f10, f11, f12 # encoding: [0x53,0xf5,0xc5,0x1a]
+# CHECK-NEXT: fsqrt.d f10, f11, rtz # encoding: [0x53,0x95,0x05,0x5a]
+# CHECK-NEXT: fsgnj.d f10, f11, f12 # encoding: [0x53,0x85,0xc5,0x22]
+# CHECK-NEXT: fsgnjn.d f10, f11, f12 # encoding: [0x53,0x95,0xc5,0x22]
+# CHECK-NEXT: fsgnjx.d f10, f11, f12 # encoding: [0x53,0xa5,0xc5,0x22]
+# CHECK-NEXT: fmin.d f10, f11, f12 # encoding: [0x53,0x85,0xc5,0x2a]
+# CHECK-NEXT: fmax.d f10, f11, f12 # encoding: [0x53,0x95,0xc5,0x2a]
+# CHECK-NEXT: fcvt.s.d f0, f10 # encoding: [0x53,0x70,0x15,0x40]
+# CHECK-NEXT: fcvt.d.s f10, f0 # encoding: [0x53,0x05,0x00,0x42]
+# CHECK-NEXT: feq.d x10, f10, f11 # encoding: [0x53,0x25,0xb5,0xa2]
+# CHECK-NEXT: flt.d x10, f10, f11 # encoding: [0x53,0x15,0xb5,0xa2]
+# CHECK-NEXT: fle.d x10, f10, f11 # encoding: [0x53,0x05,0xb5,0xa2]
+# CHECK-NEXT: fclass.d x10, f10 # encoding: [0x53,0x15,0x05,0xe2]
+# CHECK-NEXT: fcvt.w.d x10, f10 # encoding: [0x53,0x75,0x05,0xc2]
+# CHECK-NEXT: fcvt.wu.d x10, f10 # encoding: [0x53,0x75,0x15,0xc2]
+# CHECK-NEXT: fcvt.d.w f10, x10 # encoding: [0x53,0x05,0x05,0xd2]
+# CHECK-NEXT: fcvt.d.wu f10, x10 # encoding: [0x53,0x05,0x15,0xd2]
+# CHECK-NEXT: fcvt.l.d x10, f10 # encoding: [0x53,0x75,0x25,0xc2]
+# CHECK-NEXT: fcvt.lu.d x10, f10 # encoding: [0x53,0x75,0x35,0xc2]
+# CHECK-NEXT: fmv.x.d x10, f10 # encoding: [0x53,0x05,0x05,0xe2]
+# CHECK-NEXT: fcvt.d.l f10, x10 # encoding: [0x53,0x75,0x25,0xd2]
+# CHECK-NEXT: fcvt.d.lu f10, x10 # encoding: [0x53,0x75,0x35,0xd2]
+# CHECK-NEXT: fmv.d.x f10, x10 # encoding: [0x53,0x05,0x05,0xf2]
+
+# DISASM: ^float:
+# DISASM-NEXT: riscv.flw rd = f0, rs1 = x10, attrs = {offset = <i16: 8>}
+# DISASM-NEXT: riscv.fsw rs1 = x2, rs2 = f1, attrs = {offset = <i16: -4>}
+# DISASM-NEXT: riscv.fld rd = f10, rs1 = x11, attrs = {offset = <i16: 16>}
+# DISASM-NEXT: riscv.fsd rs1 = x2, rs2 = f8, attrs = {offset = <i16: 0>}
+# DISASM-NEXT: riscv.fmadd_s rd = f0, rs1 = f1, rs2 = f2, rs3 = f3, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fmsub_s rd = f0, rs1 = f1, rs2 = f2, rs3 = f3, attrs = {rm = <u8: 1>}
+# DISASM-NEXT: riscv.fnmsub_s rd = f0, rs1 = f1, rs2 = f2, rs3 = f3, attrs = {rm = <u8: 0>}
+# DISASM-NEXT: riscv.fnmadd_s rd = f0, rs1 = f1, rs2 = f2, rs3 = f3, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fadd_s rd = f0, rs1 = f1, rs2 = f2, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fsub_s rd = f0, rs1 = f1, rs2 = f2, attrs = {rm = <u8: 2>}
+# DISASM-NEXT: riscv.fmul_s rd = f0, rs1 = f1, rs2 = f2, attrs = {rm = <u8: 3>}
+# DISASM-NEXT: riscv.fdiv_s rd = f0, rs1 = f1, rs2 = f2, attrs = {rm = <u8: 4>}
+# DISASM-NEXT: riscv.fsqrt_s rd = f0, rs1 = f1, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fsgnj_s rd = f0, rs1 = f1, rs2 = f2, attrs = {}
+# DISASM-NEXT: riscv.fsgnjn_s rd = f0, rs1 = f1, rs2 = f2, attrs = {}
+# DISASM-NEXT: riscv.fsgnjx_s rd = f0, rs1 = f1, rs2 = f2, attrs = {}
+# DISASM-NEXT: riscv.fmin_s rd = f0, rs1 = f1, rs2 = f2, attrs = {}
+# DISASM-NEXT: riscv.fmax_s rd = f0, rs1 = f1, rs2 = f2, attrs = {}
+# DISASM-NEXT: riscv.fcvt_w_s rd = x10, rs1 = f0, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_wu_s rd = x10, rs1 = f0, attrs = {rm = <u8: 1>}
+# DISASM-NEXT: riscv.fcvt_l_s rd = x10, rs1 = f0, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_lu_s rd = x10, rs1 = f0, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fmv_x_w rd = x10, rs1 = f0, attrs = {}
+# DISASM-NEXT: riscv.feq_s rd = x10, rs1 = f0, rs2 = f1, attrs = {}
+# DISASM-NEXT: riscv.flt_s rd = x10, rs1 = f0, rs2 = f1, attrs = {}
+# DISASM-NEXT: riscv.fle_s rd = x10, rs1 = f0, rs2 = f1, attrs = {}
+# DISASM-NEXT: riscv.fclass_s rd = x10, rs1 = f0, attrs = {}
+# DISASM-NEXT: riscv.fcvt_s_w rd = f0, rs1 = x10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_s_wu rd = f0, rs1 = x10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_s_l rd = f0, rs1 = x10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_s_lu rd = f0, rs1 = x10, attrs = {rm = <u8: 0>}
+# DISASM-NEXT: riscv.fmv_w_x rd = f0, rs1 = x10, attrs = {}
+# DISASM-NEXT: riscv.fmadd_d rd = f10, rs1 = f11, rs2 = f12, rs3 = f13, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fmsub_d rd = f10, rs1 = f11, rs2 = f12, rs3 = f13, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fnmsub_d rd = f10, rs1 = f11, rs2 = f12, rs3 = f13, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fnmadd_d rd = f10, rs1 = f11, rs2 = f12, rs3 = f13, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fadd_d rd = f10, rs1 = f11, rs2 = f12, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fsub_d rd = f10, rs1 = f11, rs2 = f12, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fmul_d rd = f10, rs1 = f11, rs2 = f12, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fdiv_d rd = f10, rs1 = f11, rs2 = f12, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fsqrt_d rd = f10, rs1 = f11, attrs = {rm = <u8: 1>}
+# DISASM-NEXT: riscv.fsgnj_d rd = f10, rs1 = f11, rs2 = f12, attrs = {}
+# DISASM-NEXT: riscv.fsgnjn_d rd = f10, rs1 = f11, rs2 = f12, attrs = {}
+# DISASM-NEXT: riscv.fsgnjx_d rd = f10, rs1 = f11, rs2 = f12, attrs = {}
+# DISASM-NEXT: riscv.fmin_d rd = f10, rs1 = f11, rs2 = f12, attrs = {}
+# DISASM-NEXT: riscv.fmax_d rd = f10, rs1 = f11, rs2 = f12, attrs = {}
+# DISASM-NEXT: riscv.fcvt_s_d rd = f0, rs1 = f10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_d_s rd = f10, rs1 = f0, attrs = {rm = <u8: 0>}
+# DISASM-NEXT: riscv.feq_d rd = x10, rs1 = f10, rs2 = f11, attrs = {}
+# DISASM-NEXT: riscv.flt_d rd = x10, rs1 = f10, rs2 = f11, attrs = {}
+# DISASM-NEXT: riscv.fle_d rd = x10, rs1 = f10, rs2 = f11, attrs = {}
+# DISASM-NEXT: riscv.fclass_d rd = x10, rs1 = f10, attrs = {}
+# DISASM-NEXT: riscv.fcvt_w_d rd = x10, rs1 = f10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_wu_d rd = x10, rs1 = f10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_d_w rd = f10, rs1 = x10, attrs = {rm = <u8: 0>}
+# DISASM-NEXT: riscv.fcvt_d_wu rd = f10, rs1 = x10, attrs = {rm = <u8: 0>}
+# DISASM-NEXT: riscv.fcvt_l_d rd = x10, rs1 = f10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_lu_d rd = x10, rs1 = f10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fmv_x_d rd = x10, rs1 = f10, attrs = {}
+# DISASM-NEXT: riscv.fcvt_d_l rd = f10, rs1 = x10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fcvt_d_lu rd = f10, rs1 = x10, attrs = {rm = <u8: 7>}
+# DISASM-NEXT: riscv.fmv_d_x rd = f10, rs1 = x10, attrs = {}
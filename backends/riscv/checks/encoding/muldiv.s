@@ -0,0 +1,48 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+# RUN: tir asm --march rv64gc -o /dev/stdout %s | tir disasm --march rv64gc - | filecheck %s --check-prefix=DISASM
+
+.text
+muldiv:
+    mul x28, x6, x7
+    mulh x28, x6, x7
+    mulhsu x28, x6, x7
+    mulhu x28, x6, x7
+    div x28, x6, x7
+    divu x28, x6, x7
+    rem x28, x6, x7
+    remu x28, x6, x7
+    mulw x28, x6, x7
+    divw x28, x6, x7
+    divuw x28, x6, x7
+    remw x28, x6, x7
+    remuw x28, x6, x7
+
+# CHECK: muldiv:
+# CHECK-NEXT: mul x28, x6, x7 # encoding: [0x33,0x0e,0x73,0x02]
+# CHECK-NEXT: mulh x28, x6, x7 # encoding: [0x33,0x1e,0x73,0x02]
+# CHECK-NEXT: mulhsu x28, x6, x7 # encoding: [0x33,0x2e,0x73,0x02]
+# CHECK-NEXT: mulhu x28, x6, x7 # encoding: [0x33,0x3e,0x73,0x02]
+# CHECK-NEXT: div x28, x6, x7 # encoding: [0x33,0x4e,0x73,0x02]
+# CHECK-NEXT: divu x28, x6, x7 # encoding: [0x33,0x5e,0x73,0x02]
+# CHECK-NEXT: rem x28, x6, x7 # encoding: [0x33,0x6e,0x73,0x02]
+# CHECK-NEXT: remu x28, x6, x7 # encoding: [0x33,0x7e,0x73,0x02]
+# CHECK-NEXT: mulw x28, x6, x7 # encoding: [0x3b,0x0e,0x73,0x02]
+# CHECK-NEXT: divw x28, x6, x7 # encoding: [0x3b,0x4e,0x73,0x02]
+# CHECK-NEXT: divuw x28, x6, x7 # encoding: [0x3b,0x5e,0x73,0x02]
+# CHECK-NEXT: remw x28, x6, x7 # encoding: [0x3b,0x6e,0x73,0x02]
+# CHECK-NEXT: remuw x28, x6, x7 # encoding: [0x3b,0x7e,0x73,0x02]
+
+# DISASM: ^muldiv:
+# DISASM-NEXT: riscv.mul rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.mulh rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.mulhsu rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.mulhu rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.div rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.divu rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.rem rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.remu rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.mulw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.divw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.divuw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.remw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
+# DISASM-NEXT: riscv.remuw rd = x28, rs1 = x6, rs2 = x7, attrs = {}
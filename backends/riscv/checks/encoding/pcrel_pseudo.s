@@ -0,0 +1,20 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+
+.text
+start:
+    la a0, data
+    call func
+    tail start
+func:
+    ret
+data:
+    nop
+
+# CHECK: start:
+# CHECK-NEXT: la x10, data # encoding: [0x17,0x05,0x00,0x00,0x13,0x05,0xc5,0x01]
+# CHECK-NEXT: call func # encoding: [0x97,0x00,0x00,0x00,0xe7,0x80,0x00,0x01]
+# CHECK-NEXT: tail start # encoding: [0x17,0x03,0x00,0x00,0x67,0x00,0x03,0xff]
+# CHECK-NEXT: func:
+# CHECK-NEXT: jalr x0, 0(x1) # encoding: [0x67,0x80,0x00,0x00]
+# CHECK-NEXT: data:
+# CHECK-NEXT: addi x0, x0, 0 # encoding: [0x13,0x00,0x00,0x00]
@@ -0,0 +1,44 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+
+.text
+start:
+    li a0, 305419896
+    li t0, -1
+    li a1, 4886718345
+    mv a0, a1
+    not a0, a1
+    neg a0, a1
+    nop
+    seqz a0, a1
+    snez a0, a1
+    sext.w a0, a1
+    beqz a0, start
+    bnez a1, end
+    j start
+    jr t0
+    ret
+end:
+    j end
+
+# CHECK: start:
+# CHECK-NEXT: lui x10, 0x12345 # encoding: [0x37,0x55,0x34,0x12]
+# CHECK-NEXT: addiw x10, x10, 1656 # encoding: [0x1b,0x05,0x85,0x67]
+# CHECK-NEXT: addi x5, x0, -1 # encoding: [0x93,0x02,0xf0,0xff]
+# CHECK-NEXT: lui x11, 0x92 # encoding: [0xb7,0x25,0x09,0x00]
+# CHECK-NEXT: addiw x11, x11, -1493 # encoding: [0x9b,0x85,0xb5,0xa2]
+# CHECK-NEXT: slli x11, x11, 13 # encoding: [0x93,0x95,0xd5,0x00]
+# CHECK-NEXT: addi x11, x11, 1929 # encoding: [0x93,0x85,0x95,0x78]
+# CHECK-NEXT: addi x10, x11, 0 # encoding: [0x13,0x85,0x05,0x00]
+# CHECK-NEXT: xori x10, x11, -1 # encoding: [0x13,0xc5,0xf5,0xff]
+# CHECK-NEXT: sub x10, x0, x11 # encoding: [0x33,0x05,0xb0,0x40]
+# CHECK-NEXT: addi x0, x0, 0 # encoding: [0x13,0x00,0x00,0x00]
+# CHECK-NEXT: sltiu x10, x11, 1 # encoding: [0x13,0xb5,0x15,0x00]
+# CHECK-NEXT: sltu x10, x0, x11 # encoding: [0x33,0x35,0xb0,0x00]
+# CHECK-NEXT: addiw x10, x11, 0 # encoding: [0x1b,0x85,0x05,0x00]
+# CHECK-NEXT: beq x10, x0, start # encoding: [0xe3,0x04,0x05,0xfc]
+# CHECK-NEXT: bne x11, x0, end # encoding: [0x63,0x98,0x05,0x00]
+# CHECK-NEXT: jal x0, start # encoding: [0x6f,0xf0,0x1f,0xfc]
+# CHECK-NEXT: jalr x0, 0(x5) # encoding: [0x67,0x80,0x02,0x00]
+# CHECK-NEXT: jalr x0, 0(x1) # encoding: [0x67,0x80,0x00,0x00]
+# CHECK-NEXT: end:
+# CHECK-NEXT: jal x0, end # encoding: [0x6f,0x00,0x00,0x00]
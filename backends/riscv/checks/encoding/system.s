@@ -0,0 +1,48 @@
+# RUN: tir asm --march rv64gc --emit=asm --show-encoding %s | filecheck %s
+# RUN: tir asm --march rv64gc -o /dev/stdout %s | tir disasm --march rv64gc - | filecheck %s --check-prefix=DISASM
+
+.text
+sys:
+    fence
+    fence rw, w
+    fence i, o
+    fence.tso
+    fence.i
+    ecall
+    ebreak
+    csrrw x5, mstatus, x6
+    csrrs x5, cycle, x0
+    csrrc x0, 3, x7
+    csrrwi x5, mtvec, 31
+    csrrsi x5, mhartid, 1
+    csrrci x5, 4095, 0
+
+# CHECK: sys:
+# CHECK-NEXT: fence iorw, iorw # encoding: [0x0f,0x00,0xf0,0x0f]
+# CHECK-NEXT: fence rw, w # encoding: [0x0f,0x00,0x10,0x03]
+# CHECK-NEXT: fence i, o # encoding: [0x0f,0x00,0x40,0x08]
+# CHECK-NEXT: fence.tso # encoding: [0x0f,0x00,0x30,0x83]
+# CHECK-NEXT: fence.i # encoding: [0x0f,0x10,0x00,0x00]
+# CHECK-NEXT: ecall # encoding: [0x73,0x00,0x00,0x00]
+# CHECK-NEXT: ebreak # encoding: [0x73,0x00,0x10,0x00]
+# CHECK-NEXT: csrrw x5, mstatus, x6 # encoding: [0xf3,0x12,0x03,0x30]
+# CHECK-NEXT: csrrs x5, cycle, x0 # encoding: [0xf3,0x22,0x00,0xc0]
+# CHECK-NEXT: csrrc x0, fcsr, x7 # encoding: [0x73,0xb0,0x33,0x00]
+# CHECK-NEXT: csrrwi x5, mtvec, 31 # encoding: [0xf3,0xd2,0x5f,0x30]
+# CHECK-NEXT: csrrsi x5, mhartid, 1 # encoding: [0xf3,0xe2,0x40,0xf1]
+# CHECK-NEXT: csrrci x5, 0xfff, 0 # encoding: [0xf3,0x72,0xf0,0xff]
+
+# DISASM: ^sys:
+# DISASM-NEXT: riscv.fence attrs = {pred = <i64: 15>, succ = <i64: 15>}
+# DISASM-NEXT: riscv.fence attrs = {pred = <i64: 3>, succ = <i64: 1>}
+# DISASM-NEXT: riscv.fence attrs = {pred = <i64: 8>, succ = <i64: 4>}
+# DISASM-NEXT: riscv.fence_tso attrs = {}
+# DISASM-NEXT: riscv.fence_i attrs = {}
+# DISASM-NEXT: riscv.ecall attrs = {}
+# DISASM-NEXT: riscv.ebreak attrs = {}
+# DISASM-NEXT: riscv.csrrw rd = x5, rs1 = x6, attrs = {csr = <i64: 768>}
+# DISASM-NEXT: riscv.csrrs rd = x5, rs1 = x0, attrs = {csr = <i64: 3072>}
+# DISASM-NEXT: riscv.csrrc rd = x0, rs1 = x7, attrs = {csr = <i64: 3>}
+# DISASM-NEXT: riscv.csrrwi rd = x5, attrs = {csr = <i64: 773>, uimm = <i64: 31>}
+# DISASM-NEXT: riscv.csrrsi rd = x5, attrs = {csr = <i64: 3860>, uimm = <i64: 1>}
+# DISASM-NEXT: riscv.csrrci rd = x5, attrs = {csr = <i64: 4095>, uimm = <i64: 0>}
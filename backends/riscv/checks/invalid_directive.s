@@ -0,0 +1,5 @@
+# RUN: not tir asm %s
+
+.data
+    .word 1
+    .unknown_directive 2
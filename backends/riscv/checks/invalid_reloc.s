@@ -0,0 +1,4 @@
+# RUN: not tir asm %s
+
+.text
+    lui a0, %lo(msg)
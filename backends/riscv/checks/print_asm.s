@@ -0,0 +1,52 @@
+# RUN: tir asm --march rv64gc --emit=asm %s | filecheck %s
+# RUN: tir asm --march rv64gc --emit=asm --pseudo --abi-names %s | filecheck %s --check-prefix=ABI
+# RUN: tir asm --march rv64gc --emit=asm %s | tir asm --march rv64gc --emit=asm - | filecheck %s
+
+	.text
+	.globl	main
+	.type	main, @function
+main:
+	addi	sp, sp, -16
+	sd	ra, 8(sp)
+	lui	a0, %hi(msg)
+	lw	a1, %lo(msg)(a0)
+	amoswap.w.aq	a2, a1, (a0)
+	fmadd.d	fa0, fa1, fa2, fa3, rne
+	csrrs	a0, fcsr, zero
+	c.mv	a0, a1
+	beqz	a0, .Lend
+	call	puts
+.Lend:
+	ret
+	.size	main, .-main
+	.section	.rodata.str
+msg:
+	.word	42
+	.balign	8
+
+# CHECK: .text
+# CHECK-NEXT: .globl main
+# CHECK-NEXT: .type main, @function
+# CHECK-NEXT: main:
+# CHECK-NEXT: addi x2, x2, -16
+# CHECK-NEXT: sd x1, 8(x2)
+# CHECK-NEXT: lui x10, %hi(msg)
+# CHECK-NEXT: lw x11, %lo(msg)(x10)
+# CHECK-NEXT: amoswap.w.aq x12, x11, (x10)
+# CHECK-NEXT: fmadd.d f10, f11, f12, f13, rne
+# CHECK-NEXT: csrrs x10, fcsr, x0
+# CHECK-NEXT: c.mv x10, x11
+# CHECK-NEXT: beq x10, x0, .Lend
+# CHECK-NEXT: call puts
+# CHECK-NEXT: .Lend:
+# CHECK-NEXT: jalr x0, 0(x1)
+# CHECK-NEXT: .size main, .-main
+# CHECK-NEXT: .section .rodata.str,"a",@progbits
+# CHECK-NEXT: msg:
+# CHECK-NEXT: .word 42
+# CHECK-NEXT: .balign 8
+
+# ABI: addi sp, sp, -16
+# ABI: c.mv a0, a1
+# ABI: beqz a0, .Lend
+# ABI: ret
@@ -0,0 +1,70 @@
+# RUN: tir asm %s | filecheck %s
+# RUN: tir asm --pseudo %s | filecheck %s --check-prefix=PSEUDO
+
+.text
+start:
+    nop
+    li a0, 1311768467463790320
+    li a1, -1
+    mv a2, a1
+    not a2, a1
+    neg a2, a1
+    seqz a3, a2
+    snez a3, a2
+    sext.w a3, a2
+    beqz a3, start
+    bnez a3, end
+    la a4, start
+    call start
+    tail start
+    j start
+    jr a4
+end:
+    ret
+
+# CHECK: ^start:
+# CHECK-NEXT: riscv.addi rd = x0, rs1 = x0, attrs = {imm = <i16: 0>}
+# CHECK-NEXT: riscv.lui rd = x10, attrs = {imm = <i32: 583>}
+# CHECK-NEXT: riscv.addiw rd = x10, rs1 = x10, attrs = {imm = <i16: -1875>}
+# CHECK-NEXT: riscv.slli rd = x10, rs1 = x10, attrs = {imm = <i16: 14>}
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: -947>}
+# CHECK-NEXT: riscv.slli rd = x10, rs1 = x10, attrs = {imm = <i16: 12>}
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: 1511>}
+# CHECK-NEXT: riscv.slli rd = x10, rs1 = x10, attrs = {imm = <i16: 13>}
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: -272>}
+# CHECK-NEXT: riscv.addi rd = x11, rs1 = x0, attrs = {imm = <i16: -1>}
+# CHECK-NEXT: riscv.addi rd = x12, rs1 = x11, attrs = {imm = <i16: 0>}
+# CHECK-NEXT: riscv.xori rd = x12, rs1 = x11, attrs = {imm = <i16: -1>}
+# CHECK-NEXT: riscv.sub rd = x12, rs1 = x0, rs2 = x11, attrs = {}
+# CHECK-NEXT: riscv.sltiu rd = x13, rs1 = x12, attrs = {imm = <i16: 1>}
+# CHECK-NEXT: riscv.sltu rd = x13, rs1 = x0, rs2 = x12, attrs = {}
+# CHECK-NEXT: riscv.addiw rd = x13, rs1 = x12, attrs = {imm = <i16: 0>}
+# CHECK-NEXT: riscv.beq rs1 = x13, rs2 = x0, attrs = {target = <str: "start">}
+# CHECK-NEXT: riscv.bne rs1 = x13, rs2 = x0, attrs = {target = <str: "end">}
+# CHECK-NEXT: riscv.la rd = x14, attrs = {target = <str: "start">}
+# CHECK-NEXT: riscv.call attrs = {target = <str: "start">}
+# CHECK-NEXT: riscv.tail attrs = {target = <str: "start">}
+# CHECK-NEXT: riscv.jal rd = x0, attrs = {target = <str: "start">}
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x14, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: ^end:
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+
+# PSEUDO: ^start:
+# PSEUDO-NEXT: riscv.nop attrs = {}
+# PSEUDO-NEXT: riscv.li rd = x10, attrs = {imm = <i64: 1311768467463790320>}
+# PSEUDO-NEXT: riscv.li rd = x11, attrs = {imm = <i64: -1>}
+# PSEUDO-NEXT: riscv.mv rd = x12, rs1 = x11, attrs = {}
+# PSEUDO-NEXT: riscv.not rd = x12, rs1 = x11, attrs = {}
+# PSEUDO-NEXT: riscv.neg rd = x12, rs1 = x11, attrs = {}
+# PSEUDO-NEXT: riscv.seqz rd = x13, rs1 = x12, attrs = {}
+# PSEUDO-NEXT: riscv.snez rd = x13, rs1 = x12, attrs = {}
+# PSEUDO-NEXT: riscv.sext_w rd = x13, rs1 = x12, attrs = {}
+# PSEUDO-NEXT: riscv.beqz rs1 = x13, attrs = {target = <str: "start">}
+# PSEUDO-NEXT: riscv.bnez rs1 = x13, attrs = {target = <str: "end">}
+# PSEUDO-NEXT: riscv.la rd = x14, attrs = {target = <str: "start">}
+# PSEUDO-NEXT: riscv.call attrs = {target = <str: "start">}
+# PSEUDO-NEXT: riscv.tail attrs = {target = <str: "start">}
+# PSEUDO-NEXT: riscv.j attrs = {target = <str: "start">}
+# PSEUDO-NEXT: riscv.jr rs1 = x14, attrs = {}
+# PSEUDO-NEXT: ^end:
+# PSEUDO-NEXT: riscv.ret attrs = {}
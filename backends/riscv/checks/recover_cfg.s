@@ -0,0 +1,40 @@
+# RUN: tir asm --march rv64imac -o /dev/stdout %s | tir disasm --march rv64imac --pass recover-cfg - | filecheck %s
+
+	.text
+	.globl	main
+	.type	main,@function
+main:
+	addi	sp, sp, -16
+	beqz	a0, .Lskip
+	addi	a0, a0, 1
+.Lskip:
+	jal	ra, helper
+	bnez	a0, main
+	ret
+	.word	0x00000013
+helper:
+	c.nop
+	ret
+
+# CHECK: target.section ".text" {
+# CHECK-NEXT: ^entry:
+# CHECK-NEXT: target.symbol_binding attrs = {binding = <str: "global">, name = <str: "main">}
+# CHECK-NEXT: target.symbol_type attrs = {kind = <str: "function">, name = <str: "main">}
+# CHECK-NEXT: target.function "main" {
+# CHECK-NEXT: ^main:
+# CHECK-NEXT: riscv.addi rd = x2, rs1 = x2, attrs = {imm = <i16: -16>}
+# CHECK-NEXT: riscv.beq rs1 = x10, rs2 = x0, attrs = {target = <str: "L0xc">}
+# CHECK-NEXT: ^L0x8:
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: 1>}
+# CHECK-NEXT: ^L0xc:
+# CHECK-NEXT: riscv.jal rd = x1, attrs = {target = <str: "helper">}
+# CHECK-NEXT: riscv.bne rs1 = x10, rs2 = x0, attrs = {target = <str: "main">}
+# CHECK-NEXT: ^L0x14:
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: }
+# CHECK-NEXT: target.data attrs = {size = <u8: 1>, values = <i64s: [19, 0, 0, 0]>}
+# CHECK-NEXT: target.function "helper" {
+# CHECK-NEXT: ^helper:
+# CHECK-NEXT: riscv.c_nop attrs = {}
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: }
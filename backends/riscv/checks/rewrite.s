@@ -0,0 +1,24 @@
+# RUN: tir asm --march rv64imac -o /dev/stdout %s | tir rewrite --march rv64imac --profile %S/Inputs/rewrite.fdata | tir disasm --march rv64imac --pass recover-cfg - | filecheck %s
+
+	.text
+	.globl	main
+	.type	main,@function
+main:
+	beq	a0, zero, .Lhot
+	addi	a0, zero, 1
+	ret
+.Lhot:
+	addi	a0, a0, 2
+	ret
+
+# The taken branch is hot, so the branch target follows the branch
+# CHECK: target.function "main" {
+# CHECK-NEXT: ^main:
+# CHECK-NEXT: riscv.bne rs1 = x10, rs2 = x0, attrs = {target = <str: "L0xc">}
+# CHECK-NEXT: ^L0x4:
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x10, attrs = {imm = <i16: 2>}
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: ^L0xc:
+# CHECK-NEXT: riscv.addi rd = x10, rs1 = x0, attrs = {imm = <i16: 1>}
+# CHECK-NEXT: riscv.jalr rd = x0, rs1 = x1, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: }
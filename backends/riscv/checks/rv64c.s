@@ -0,0 +1,44 @@
+# RUN: tir asm --march=rv64gc %s | filecheck %s
+
+.text
+rv64c:
+    c.addi4spn s0, sp, 16
+    c.lw a0, 4(a1)
+    c.sd a5, 8(s0)
+    c.nop
+    c.addi a0, -1
+    c.li ra, 5
+    c.addi16sp sp, -64
+    c.lui t0, 1048544
+    c.srai s1, 3
+    c.sub a0, a1
+    c.slli ra, 1
+    c.ldsp t0, 8(sp)
+    c.fsdsp fs0, 16(sp)
+    c.mv a0, a1
+    c.beqz a0, rv64c
+    c.j rv64c
+    c.jr ra
+
+# CHECK: module {
+# CHECK-NEXT: target.section ".text" {
+# CHECK-NEXT: ^rv64c:
+# CHECK-NEXT: riscv.c_addi4spn rd = x8, attrs = {imm = <i16: 16>}
+# CHECK-NEXT: riscv.c_lw rd = x10, rs1 = x11, attrs = {offset = <i16: 4>}
+# CHECK-NEXT: riscv.c_sd rs1 = x8, rs2 = x15, attrs = {offset = <i16: 8>}
+# CHECK-NEXT: riscv.c_nop attrs = {}
+# CHECK-NEXT: riscv.c_addi rd = x10, attrs = {imm = <i16: -1>}
+# CHECK-NEXT: riscv.c_li rd = x1, attrs = {imm = <i16: 5>}
+# CHECK-NEXT: riscv.c_addi16sp attrs = {imm = <i16: -64>}
+# CHECK-NEXT: riscv.c_lui rd = x5, attrs = {imm = <i32: 1048544>}
+# CHECK-NEXT: riscv.c_srai rd = x9, attrs = {imm = <i16: 3>}
+# CHECK-NEXT: riscv.c_sub rd = x10, rs2 = x11, attrs = {}
+# CHECK-NEXT: riscv.c_slli rd = x1, attrs = {imm = <i16: 1>}
+# CHECK-NEXT: riscv.c_ldsp rd = x5, attrs = {offset = <i16: 8>}
+# CHECK-NEXT: riscv.c_fsdsp rs2 = f8, attrs = {offset = <i16: 16>}
+# CHECK-NEXT: riscv.c_mv rd = x10, rs2 = x11, attrs = {}
+# CHECK-NEXT: riscv.c_beqz rs1 = x10, attrs = {target = <str: "rv64c">}
+# CHECK-NEXT: riscv.c_j attrs = {target = <str: "rv64c">}
+# CHECK-NEXT: riscv.c_jr rs1 = x1, attrs = {}
+# CHECK-NEXT: }
+# CHECK-NEXT: }
@@ -0,0 +1,34 @@
+# RUN: tir asm --march=rv64imafd %s | filecheck %s
+
+.text
+rv64fd:
+    flw ft0, 8(a0)
+    fsd fs0, 0(sp)
+    fmadd.s ft0, ft1, ft2, ft3
+    fnmsub.d fa0, fa1, fa2, fa3, rtz
+    fadd.s ft0, ft1, ft2
+    fdiv.d fa0, fa1, fa2, rmm
+    fsgnjx.s ft0, ft1, ft2
+    feq.d a0, fa0, fa1
+    fcvt.w.s a0, ft0, rtz
+    fcvt.d.wu fa0, a0
+    fmv.x.d a0, fa0
+    fclass.s a0, ft0
+
+# CHECK: module {
+# CHECK-NEXT: target.section ".text" {
+# CHECK-NEXT: ^rv64fd:
+# CHECK-NEXT: riscv.flw rd = f0, rs1 = x10, attrs = {offset = <i16: 8>}
+# CHECK-NEXT: riscv.fsd rs1 = x2, rs2 = f8, attrs = {offset = <i16: 0>}
+# CHECK-NEXT: riscv.fmadd_s rd = f0, rs1 = f1, rs2 = f2, rs3 = f3, attrs = {rm = <u8: 7>}
+# CHECK-NEXT: riscv.fnmsub_d rd = f10, rs1 = f11, rs2 = f12, rs3 = f13, attrs = {rm = <u8: 1>}
+# CHECK-NEXT: riscv.fadd_s rd = f0, rs1 = f1, rs2 = f2, attrs = {rm = <u8: 7>}
+# CHECK-NEXT: riscv.fdiv_d rd = f10, rs1 = f11, rs2 = f12, attrs = {rm = <u8: 4>}
+# CHECK-NEXT: riscv.fsgnjx_s rd = f0, rs1 = f1, rs2 = f2, attrs = {}
+# CHECK-NEXT: riscv.feq_d rd = x10, rs1 = f10, rs2 = f11, attrs = {}
+# CHECK-NEXT: riscv.fcvt_w_s rd = x10, rs1 = f0, attrs = {rm = <u8: 1>}
+# CHECK-NEXT: riscv.fcvt_d_wu rd = f10, rs1 = x10, attrs = {rm = <u8: 0>}
+# CHECK-NEXT: riscv.fmv_x_d rd = x10, rs1 = f10, attrs = {}
+# CHECK-NEXT: riscv.fclass_s rd = x10, rs1 = f0, attrs = {}
+# CHECK-NEXT: }
+# CHECK-NEXT: }
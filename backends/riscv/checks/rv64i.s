@@ -0,0 +1,56 @@
+# RUN: tir asm %s | filecheck %s
+
+.text
+rv64i:
+    xor x5, x6, x7
+    xori x5, x6, -1
+    addw x5, x6, x7
+    subw x5, x6, x7
+    sllw x5, x6, x7
+    srlw x5, x6, x7
+    sraw x5, x6, x7
+    addiw x5, x6, -1
+    slliw x5, x6, 31
+    srliw x5, x6, 1
+    sraiw x5, x6, 0
+    srai x5, x6, 63
+    fence
+    fence rw, w
+    fence.tso
+    ecall
+    ebreak
+    csrrw x5, mstatus, x6
+    csrrs x5, cycle, x0
+    csrrc x0, 3, x7
+    csrrwi x5, mtvec, 31
+    csrrsi x5, mhartid, 1
+    csrrci x5, 4095, 0
+
+# CHECK: module {
+# CHECK-NEXT: target.section ".text" {
+# CHECK-NEXT: ^rv64i:
+# CHECK-NEXT: riscv.xor rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.xori rd = x5, rs1 = x6, attrs = {imm = <i16: -1>}
+# CHECK-NEXT: riscv.addw rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.subw rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.sllw rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.srlw rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.sraw rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.addiw rd = x5, rs1 = x6, attrs = {imm = <i16: -1>}
+# CHECK-NEXT: riscv.slliw rd = x5, rs1 = x6, attrs = {imm = <i16: 31>}
+# CHECK-NEXT: riscv.srliw rd = x5, rs1 = x6, attrs = {imm = <i16: 1>}
+# CHECK-NEXT: riscv.sraiw rd = x5, rs1 = x6, attrs = {imm = <i16: 0>}
+# CHECK-NEXT: riscv.srai rd = x5, rs1 = x6, attrs = {imm = <i16: 63>}
+# CHECK-NEXT: riscv.fence attrs = {pred = <u16: 15>, succ = <u16: 15>}
+# CHECK-NEXT: riscv.fence attrs = {pred = <u16: 3>, succ = <u16: 1>}
+# CHECK-NEXT: riscv.fence_tso attrs = {}
+# CHECK-NEXT: riscv.ecall attrs = {}
+# CHECK-NEXT: riscv.ebreak attrs = {}
+# CHECK-NEXT: riscv.csrrw rd = x5, rs1 = x6, attrs = {csr = <u16: 768>}
+# CHECK-NEXT: riscv.csrrs rd = x5, rs1 = x0, attrs = {csr = <u16: 3072>}
+# CHECK-NEXT: riscv.csrrc rd = x0, rs1 = x7, attrs = {csr = <u16: 3>}
+# CHECK-NEXT: riscv.csrrwi rd = x5, attrs = {csr = <u16: 773>, uimm = <u8: 31>}
+# CHECK-NEXT: riscv.csrrsi rd = x5, attrs = {csr = <u16: 3860>, uimm = <u8: 1>}
+# CHECK-NEXT: riscv.csrrci rd = x5, attrs = {csr = <u16: 4095>, uimm = <u8: 0>}
+# CHECK-NEXT: }
+# CHECK-NEXT: }
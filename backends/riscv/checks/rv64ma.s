@@ -0,0 +1,26 @@
+# RUN: tir asm --march=rv64ima %s | filecheck %s
+
+.text
+rv64ma:
+    mul x5, x6, x7
+    mulhsu x5, x6, x7
+    divu x5, x6, x7
+    remw x5, x6, x7
+    lr.w x5, (x6)
+    sc.d.rl x5, x7, (x6)
+    amoadd.w.aqrl x5, x7, (x6)
+    amomaxu.d x5, x7, 0(x6)
+
+# CHECK: module {
+# CHECK-NEXT: target.section ".text" {
+# CHECK-NEXT: ^rv64ma:
+# CHECK-NEXT: riscv.mul rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.mulhsu rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.divu rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.remw rd = x5, rs1 = x6, rs2 = x7, attrs = {}
+# CHECK-NEXT: riscv.lr_w rd = x5, rs1 = x6, attrs = {aq = <bool: false>, rl = <bool: false>}
+# CHECK-NEXT: riscv.sc_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: true>}
+# CHECK-NEXT: riscv.amoadd_w rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: true>, rl = <bool: true>}
+# CHECK-NEXT: riscv.amomaxu_d rd = x5, rs1 = x6, rs2 = x7, attrs = {aq = <bool: false>, rl = <bool: false>}
+# CHECK-NEXT: }
+# CHECK-NEXT: }
@@ -15,7 +15,7 @@ example:
     slli x28, x6, 9
     slti x28, x6, 8
     sltiu x28, x6, 25
-    srli x28, x6, -3
+    srli x28, x6, 3
     srai x28, x6, 0
     ori x28, x6, 1
     andi x28, x6, 1
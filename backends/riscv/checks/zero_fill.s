@@ -0,0 +1,21 @@
+# RUN: tir asm %s | filecheck %s
+# RUN: tir asm --emit=asm %s | filecheck %s --check-prefix=ASM
+# RUN: tir asm -o /dev/stdout %s | tir disasm - | filecheck %s --check-prefix=OBJ
+
+	.data
+	.zero	4
+	.zero	3, 0xff
+	.skip	2, 1
+
+# CHECK: target.section ".data" {
+# CHECK-NEXT: ^entry:
+# CHECK-NEXT: target.zero attrs = {size = <u64: 4>}
+# CHECK-NEXT: target.zero attrs = {fill = <u8: 255>, size = <u64: 3>}
+# CHECK-NEXT: target.zero attrs = {fill = <u8: 1>, size = <u64: 2>}
+
+# ASM: .zero 4
+# ASM-NEXT: .zero 3, 255
+# ASM-NEXT: .zero 2, 1
+
+# OBJ: target.section ".data" {
+# OBJ: values = <i64s: [0, 0, 0, 0, 255, 255, 255, 1, 1]>
@@ -0,0 +1,5 @@
+# RUN: not tir asm %s 2>&1 | filecheck %s
+
+# CHECK: DataOutOfRange(9223372036854775807, 4
+	.data
+	.zero	0x7fffffffffffffff, 1
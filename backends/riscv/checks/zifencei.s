@@ -0,0 +1,11 @@
+# RUN: tir asm --march=rv64i_zifencei %s | filecheck %s
+# RUN: not tir asm --march=rv64i %s 2>&1 | filecheck %s --check-prefix=NOEXT
+
+.text
+zifencei:
+    fence.i
+
+# CHECK: ^zifencei:
+# CHECK-NEXT: riscv.fence_i attrs = {}
+
+# NOEXT: DisabledExtension("fence.i", "Zifencei"
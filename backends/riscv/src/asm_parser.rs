@@ -1,39 +1,79 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::Arc;
 
-use lpl::combinators::{eof, zero_or_more};
-use lpl::{Diagnostic, ParseResult, ParseStream, Parser};
-use tir_backend::parser::{label, section};
+use lpl::{Diagnostic, ParseStream, Parser};
+use tir_backend::parser::{asm_ident, label, section, section_statement};
 use tir_backend::{lex_asm, TokenStream};
-use tir_core::{builtin::ModuleOp, ContextRef, OpBuilder};
-
-use crate::{DiagKind, RVExt};
-
-fn asm_instr(input: TokenStream) -> ParseResult<TokenStream, ()> {
-    let asm_ctx = input.get_extra().unwrap();
-    let builder = asm_ctx.get_builder();
-    let context = builder.get_context();
-    let dialect = context.get_dialect_by_name(crate::DIALECT_NAME).unwrap();
-
-    let parsers = dialect
-        .get_dialect_extension()
-        .unwrap()
-        .downcast_ref::<RVExt>()
-        .unwrap()
-        .get_asm_parsers();
-
-    for p in parsers {
-        let result = p.parse(input.clone());
-        if result.is_ok() {
-            return result;
+use tir_core::{builtin::ModuleOp, ContextRef, OpBuilder, RwCell};
+
+use crate::{DiagKind, ISAFeatures, RVExt};
+
+/// Operation name of an assembly mnemonic, i.e. `fence_tso` for `fence.tso`. Ordering
+/// suffixes of atomic instructions are dropped.
+fn mnemonic_op_name(mnemonic: &str) -> String {
+    let mnemonic = mnemonic.to_ascii_lowercase();
+    let mnemonic = [".aqrl", ".aq", ".rl"]
+        .iter()
+        .find_map(|suffix| mnemonic.strip_suffix(suffix))
+        .unwrap_or(&mnemonic);
+    mnemonic.replace('.', "_")
+}
+
+fn asm_instr<'a>(features: &ISAFeatures) -> impl Parser<'a, TokenStream<'a>, ()> + '_ {
+    move |input: TokenStream<'a>| {
+        let asm_ctx = input.get_extra().unwrap();
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let dialect = context.get_dialect_by_name(crate::DIALECT_NAME).unwrap();
+
+        let rv_ext = dialect
+            .get_dialect_extension()
+            .unwrap()
+            .downcast_ref::<RVExt>()
+            .unwrap();
+
+        for p in rv_ext.get_asm_parsers(features) {
+            let result = p.parse(input.clone());
+            if result.is_ok() {
+                return result;
+            }
         }
+
+        // Report instructions, that exist, but belong to a disabled extension, and operand
+        // errors of known instructions
+        if let Ok((mnemonic, _)) = asm_ident().parse(input.clone()) {
+            let op_name = mnemonic_op_name(mnemonic);
+            if let Some(ext) = rv_ext.get_op_name_extension(&op_name) {
+                if !features.has(ext) {
+                    return Err(DiagKind::DisabledExtension(
+                        mnemonic.to_string(),
+                        ext.to_string(),
+                        input.span(),
+                    )
+                    .into());
+                }
+                if let Some(parser) = rv_ext.get_asm_parser(&op_name) {
+                    return parser.parse(input.clone());
+                }
+            }
+        }
+
+        Err(DiagKind::UnknownOpcode(input.span()).into())
     }
+}
 
-    Err(DiagKind::UnknownOpcode(input.span()).into())
+/// Parse assembly for the default extension set
+#[allow(clippy::result_large_err)]
+pub fn parse_asm(context: &ContextRef, input: &str) -> Result<Arc<RwCell<ModuleOp>>, Diagnostic> {
+    parse_asm_with_features(context, input, &ISAFeatures::default())
 }
 
+/// Parse assembly, accepting only instructions of the enabled extensions
 #[allow(clippy::result_large_err)]
-pub fn parse_asm(context: &ContextRef, input: &str) -> Result<Rc<RefCell<ModuleOp>>, Diagnostic> {
+pub fn parse_asm_with_features(
+    context: &ContextRef,
+    input: &str,
+    features: &ISAFeatures,
+) -> Result<Arc<RwCell<ModuleOp>>, Diagnostic> {
     let module = ModuleOp::builder(context).build();
     let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
 
@@ -41,13 +81,17 @@ pub fn parse_asm(context: &ContextRef, input: &str) -> Result<Rc<RefCell<ModuleO
 
     let stream = TokenStream::new(&tokens, builder);
 
-    let parser = eof(zero_or_more(
-        section()
-            .or_else(label())
-            .or_else(asm_instr.label("asm_instr")),
-    ));
+    let statement = section()
+        .or_else(label())
+        .or_else(section_statement(asm_instr(features).label("asm_instr")));
+
+    // Parse statements one by one to report the error of the statement, that failed
+    let mut next_input = Some(stream).filter(|input| !input.is_empty());
+    while let Some(input) = next_input {
+        let (_, ni) = statement.parse(input)?;
+        next_input = ni;
+    }
 
-    parser.parse(stream)?;
     Ok(module)
 }
 
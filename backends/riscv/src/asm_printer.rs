@@ -0,0 +1,135 @@
+//! GNU assembly of the RISC-V dialect
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use tir_backend::target::{AsmPrintError, AsmTarget};
+use tir_backend::{BinaryStream, TargetFlag, TargetOptions};
+use tir_core::builtin::ModuleOp;
+use tir_core::{OpRef, RwCell};
+
+use crate::cfg::ByteStream;
+
+/// Print the bytes of every instruction in a comment, the same way as
+/// `llvm-mc -show-encoding` does
+pub struct ShowEncoding;
+
+impl TargetFlag for ShowEncoding {
+    const NAME: &'static str = "ShowEncoding";
+}
+
+struct RVAsmTarget<'a> {
+    target_opts: &'a TargetOptions,
+}
+
+impl AsmTarget for RVAsmTarget<'_> {
+    fn get_target_options(&self) -> &TargetOptions {
+        self.target_opts
+    }
+
+    fn print_instr(&self, op: &OpRef) -> Option<String> {
+        let line = crate::print_op(op, self.target_opts)?;
+        if !self.target_opts.has_flag::<ShowEncoding>() {
+            return Some(line);
+        }
+
+        let data = Rc::new(RefCell::new(vec![]));
+        let mut stream: Box<dyn BinaryStream> = Box::new(ByteStream(data.clone()));
+        match crate::encode_op(op, self.target_opts, &mut stream) {
+            Some(Ok(())) => {
+                let bytes: Vec<_> = data.take().iter().map(|b| format!("{:#04x}", b)).collect();
+                Some(format!("{} # encoding: [{}]", line, bytes.join(",")))
+            }
+            _ => Some(line),
+        }
+    }
+}
+
+/// Print the sections of the module as assembly, that `parse_asm` reads back. Registers
+/// are printed by their ABI names, if the `ABIRegName` flag is set, and instructions are
+/// followed by their encoding, if the `ShowEncoding` flag is set.
+pub fn print_asm(
+    module: &Arc<RwCell<ModuleOp>>,
+    target_opts: &TargetOptions,
+) -> Result<String, AsmPrintError> {
+    let target = RVAsmTarget { target_opts };
+    tir_backend::target::print_asm(module, &target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::{context, target_options};
+    use crate::{emit_elf_object, parse_asm};
+
+    const INPUT: &str = ".text
+    .globl main
+    .type main, @function
+main:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    lui a0, %hi(msg)
+    ld a1, %lo(msg+8)(a0)
+    la a2, msg
+    call puts
+    fadd.d fa0, fa1, fa2, rtz
+    fcvt.w.d a0, fa0
+    amoadd.w.aqrl a0, a1, (a2)
+    csrrs a0, mstatus, zero
+    fence rw, w
+    c.addi a0, 1
+    c.lui a0, 0xfffe0
+    beqz a0, .Lend
+    ret
+.Lend:
+    .size main, .Lend-main
+.section .rodata
+msg:
+    .word 1, 2
+    .dword msg+4
+    .zero 3
+    .balign 8
+";
+
+    #[test]
+    fn test_print_asm() {
+        let context = context();
+        let module = parse_asm(&context, INPUT).unwrap();
+        let asm = print_asm(&module, &target_options(&[])).unwrap();
+        let lines: Vec<_> = asm.lines().collect();
+        assert_eq!(lines[0], "\t.text");
+        assert_eq!(lines[3], "main:");
+        assert_eq!(lines[4], "\taddi x2, x2, -16");
+        assert_eq!(lines[6], "\tlui x10, %hi(msg)");
+        assert_eq!(lines[7], "\tld x11, %lo(msg+8)(x10)");
+        assert!(lines.contains(&"\tfadd.d f10, f11, f12, rtz"));
+        assert!(lines.contains(&"\tamoadd.w.aqrl x10, x11, (x12)"));
+        assert!(lines.contains(&"\t.section .rodata,\"a\",@progbits"));
+        assert!(lines.contains(&"\t.dword msg+4"));
+
+        // Printing the parsed output gives the same text and the same encoding
+        let reparsed = parse_asm(&context, &asm).unwrap();
+        assert_eq!(print_asm(&reparsed, &target_options(&[])).unwrap(), asm);
+        assert_eq!(
+            emit_elf_object(&reparsed, "rv64gc").unwrap(),
+            emit_elf_object(&module, "rv64gc").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_print_asm_abi_names() {
+        let context = context();
+        let module = parse_asm(&context, INPUT).unwrap();
+        let asm = print_asm(&module, &target_options(&["ABIRegName"])).unwrap();
+        assert!(asm.contains("\taddi sp, sp, -16\n"));
+        assert!(asm.contains("\tfadd.d fa0, fa1, fa2, rtz\n"));
+        assert!(asm.contains("\tc.addi a0, 1\n"));
+
+        let reparsed = parse_asm(&context, &asm).unwrap();
+        assert_eq!(
+            emit_elf_object(&reparsed, "rv64gc").unwrap(),
+            emit_elf_object(&module, "rv64gc").unwrap()
+        );
+    }
+}
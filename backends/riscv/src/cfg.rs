@@ -0,0 +1,797 @@
+//! Recovery of functions and their control flow graphs from disassembled code
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use tir_backend::target::{
+    get_data_size, DataOp, FunctionOp, SectionOp, SymbolBindingOp, SymbolSizeOp, SymbolTypeOp,
+};
+use tir_backend::{BinaryStream, Endianness, Register, TargetOptions};
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::{op_cast, op_dyn_cast, op_has_trait};
+use tir_core::{
+    Attr, Block, BlockRef, BranchOpInterface, CallOpInterface, Location, Op, OpRef, PassError,
+    Region, RwCell, Terminator,
+};
+use tir_macros::pass;
+
+use crate::{
+    address_label, encode_op, get_instr_size, get_op_extension, get_target, parse_address_label,
+    AddImmOp, AuipcOp, CJumpAndLinkRegOp, CJumpRegOp, JumpAndLinkOp, JumpAndLinkRegOp, LabelRef,
+    LuiOp, UnknownWordOp, DEFAULT_ISA, GPR, MARCH_FLAG,
+};
+
+/// Attribute of indirect jumps with the addresses of the jump table entries
+pub const JUMP_TABLE_ATTR: &str = "jump_table";
+
+/// Number of instructions before an indirect jump, that are searched for the address
+/// of a jump table
+const JUMP_TABLE_WINDOW: usize = 16;
+const MAX_JUMP_TABLE_SIZE: usize = 4096;
+
+/// Recover functions of the executable sections.
+///
+/// Functions are discovered by recursive descent, that starts at the function and
+/// global symbols, follows branches and jumps, and treats the targets of calls as
+/// entries of further functions. Each function becomes a `target.function` with a
+/// block per branch target and per instruction following a branch. Targets of indirect
+/// jumps are recovered from jump tables in data sections, if the address of the table
+/// is computed right before the jump. Unknown words are assumed to fall through, like
+/// most instructions of vendor extensions. Instructions, that are not reachable from any
+/// entry, are turned into data.
+#[pass(name = "recover-cfg", wrapper = tir_core::ModulePassWrapper)]
+pub fn recover_cfg(module: &Arc<RwCell<ModuleOp>>) -> Result<(), PassError> {
+    let memory = Memory::new(module);
+    let sections: Vec<_> = module
+        .borrow()
+        .get_body()
+        .iter()
+        .filter_map(op_cast::<SectionOp>)
+        .collect();
+    for section in sections {
+        let code = SectionCode::new(&section.borrow())?;
+        if !code.instrs.is_empty() {
+            let functions = code.find_functions(&memory);
+            code.rebuild(&section.borrow(), &functions);
+        }
+    }
+    Ok(())
+}
+
+/// Successors of a block of a recovered function. A block falls through to the block,
+/// that follows it in the function.
+pub fn get_block_successors(block: &BlockRef) -> Vec<BlockRef> {
+    let region = block.get_parent_region();
+    let blocks: Vec<_> = region.iter().collect();
+    let next = blocks
+        .iter()
+        .position(|candidate| Arc::ptr_eq(candidate, block))
+        .and_then(|idx| blocks.get(idx + 1))
+        .cloned();
+
+    let Some(last) = block.last() else {
+        return next.into_iter().collect();
+    };
+    let (mut successors, falls_through) = match get_flow(&last, &HashMap::new()) {
+        Flow::Next | Flow::Call(_) => (vec![], true),
+        Flow::Branch(_, falls_through) => {
+            let successors = op_dyn_cast::<dyn BranchOpInterface>(last.clone())
+                .map(|branch| branch.borrow().get_successors())
+                .unwrap_or_default();
+            (successors, falls_through)
+        }
+        Flow::IndirectJump(_) => {
+            let targets = match last.borrow().get_attrs().get(JUMP_TABLE_ATTR) {
+                Some(Attr::U64Array(targets)) => targets.clone(),
+                _ => vec![],
+            };
+            let successors = targets
+                .into_iter()
+                .filter_map(|target| find_block_at(&blocks, target))
+                .collect();
+            (successors, false)
+        }
+        Flow::Return | Flow::Stop => (vec![], false),
+    };
+    if falls_through {
+        successors.extend(next);
+    }
+    successors
+}
+
+fn find_block_at(blocks: &[BlockRef], address: u64) -> Option<BlockRef> {
+    blocks
+        .iter()
+        .find(|block| {
+            block
+                .first()
+                .is_some_and(|op| op.borrow().get_location() == Location::Address(address))
+        })
+        .cloned()
+}
+
+/// Transfer of control by an instruction
+pub(crate) enum Flow {
+    Next,
+    /// Call of the function at the address, if it is known
+    Call(Option<u64>),
+    /// Jump to the address, if it is in the section, and whether the next instruction
+    /// may be executed as well
+    Branch(Option<u64>, bool),
+    /// Jump to the address in the register
+    IndirectJump(u8),
+    Return,
+    /// Control is transferred elsewhere, i.e. a tail call
+    Stop,
+}
+
+/// Operation of the RISC-V dialect, that occupies [`get_instr_size`] bytes
+pub(crate) fn is_instr(op: &OpRef) -> bool {
+    get_op_extension(op).is_some() || op_cast::<UnknownWordOp>(op.clone()).is_some()
+}
+
+pub(crate) fn reg_num(reg: Register<GPR>) -> Option<u8> {
+    match reg {
+        Register::Architecture(reg) => Some(reg.encode()),
+        Register::Virtual(_) => None,
+    }
+}
+
+pub(crate) fn get_flow(op: &OpRef, labels: &HashMap<String, u64>) -> Flow {
+    let target = get_target(op).and_then(|label| {
+        labels
+            .get(&label)
+            .copied()
+            .or_else(|| parse_address_label(&label))
+    });
+    let ra = GPR::X1.encode();
+
+    if let Some(jal) = op_cast::<JumpAndLinkOp>(op.clone()) {
+        if reg_num(jal.borrow().get_rd()) != Some(0) {
+            return Flow::Call(target);
+        }
+        return Flow::Branch(target, false);
+    }
+    if let Some(call) = op_dyn_cast::<dyn CallOpInterface>(op.clone()) {
+        if call.borrow().returns() {
+            return Flow::Call(target);
+        }
+        return Flow::Stop;
+    }
+    if let Some(branch) = op_dyn_cast::<dyn BranchOpInterface>(op.clone()) {
+        return Flow::Branch(target, branch.borrow().may_fall_through());
+    }
+    if let Some(jalr) = op_cast::<JumpAndLinkRegOp>(op.clone()) {
+        let jalr = jalr.borrow();
+        return match (reg_num(jalr.get_rd()), reg_num(jalr.get_rs1())) {
+            (Some(0), Some(rs1)) if rs1 == ra && jalr.offset() == 0 => Flow::Return,
+            (Some(0), Some(rs1)) if jalr.offset() == 0 => Flow::IndirectJump(rs1),
+            (Some(0), _) => Flow::Stop,
+            _ => Flow::Call(None),
+        };
+    }
+    if let Some(jr) = op_cast::<CJumpRegOp>(op.clone()) {
+        return match reg_num(jr.borrow().get_rs1()) {
+            Some(rs1) if rs1 == ra => Flow::Return,
+            Some(rs1) => Flow::IndirectJump(rs1),
+            None => Flow::Stop,
+        };
+    }
+    if op_cast::<CJumpAndLinkRegOp>(op.clone()).is_some() {
+        return Flow::Call(None);
+    }
+    Flow::Next
+}
+
+/// Contents of the data sections by their address
+struct Memory(Vec<(u64, Vec<u8>)>);
+
+impl Memory {
+    fn new(module: &Arc<RwCell<ModuleOp>>) -> Self {
+        let mut chunks = vec![];
+        for section in module.borrow().get_body().iter() {
+            let Some(section) = op_cast::<SectionOp>(section) else {
+                continue;
+            };
+            for op in section
+                .borrow()
+                .get_body_region()
+                .iter()
+                .flat_map(|b| b.iter())
+            {
+                let Some(data) = op_cast::<DataOp>(op.clone()) else {
+                    continue;
+                };
+                let Location::Address(address) = op.borrow().get_location() else {
+                    continue;
+                };
+                let data = data.borrow();
+                let size = data.size() as usize;
+                let bytes = data
+                    .values()
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes()[..size].to_vec())
+                    .collect();
+                chunks.push((address, bytes));
+            }
+        }
+        Memory(chunks)
+    }
+
+    fn read(&self, address: u64, size: usize) -> Option<u64> {
+        let (start, bytes) = self
+            .0
+            .iter()
+            .find(|(start, bytes)| address >= *start && address - start < bytes.len() as u64)?;
+        let offset = (address - start) as usize;
+        let bytes = bytes.get(offset..offset + size)?;
+        let mut value = [0; 8];
+        value[..size].copy_from_slice(bytes);
+        Some(u64::from_le_bytes(value))
+    }
+}
+
+/// Function, that has been discovered from its entry
+struct Function {
+    instrs: BTreeSet<usize>,
+    block_starts: HashSet<usize>,
+}
+
+/// Operations of a section in the address order
+#[derive(Default)]
+struct SectionCode {
+    instrs: Vec<(u64, OpRef)>,
+    /// Index of the instruction at each address
+    index: HashMap<u64, usize>,
+    labels: HashMap<String, u64>,
+    /// Labels at each address in the order of definition
+    names: BTreeMap<u64, Vec<String>>,
+    /// Operations of the target dialect, that occupy space in the section
+    data: Vec<(u64, OpRef)>,
+    directives: Vec<OpRef>,
+    /// Labels, that are declared as functions or global symbols
+    symbols: Vec<String>,
+}
+
+impl SectionCode {
+    /// Collect the operations of the section. Operations are located at their address,
+    /// if it is known, or else right after the previous operation. Fails on operations,
+    /// that do not occupy a known number of bytes, since the addresses of the following
+    /// ones would be unknown.
+    fn new(section: &SectionOp) -> Result<Self, PassError> {
+        let mut code = SectionCode::default();
+        let mut next = 0;
+        let mut pending = vec![];
+        for (block_idx, block) in section.get_body_region().iter().enumerate() {
+            // The implicit first block of assembly input is not a label
+            if block_idx != 0 || block.get_name() != "entry" {
+                pending.push(block.get_name());
+            }
+            for op in block.iter() {
+                let address = match op.borrow().get_location() {
+                    Location::Address(address) => address,
+                    _ => next,
+                };
+                if op_cast::<SymbolBindingOp>(op.clone()).is_some()
+                    || op_cast::<SymbolTypeOp>(op.clone()).is_some()
+                    || op_cast::<SymbolSizeOp>(op.clone()).is_some()
+                {
+                    code.add_symbol(&op);
+                    code.directives.push(op);
+                    continue;
+                }
+
+                for name in pending.drain(..) {
+                    code.add_label(name, address);
+                }
+                if is_instr(&op) {
+                    next = address + get_instr_size(&*op.borrow());
+                    code.index.insert(address, code.instrs.len());
+                    code.instrs.push((address, op));
+                } else {
+                    let size = match get_data_size(&*op.borrow(), address) {
+                        Some(size) => size,
+                        None if op_has_trait::<dyn Terminator>(op.clone()) => 0,
+                        None => {
+                            return Err(PassError::InvalidOp(
+                                op.borrow().get_location(),
+                                format!(
+                                    "Operation '{}' has no known size in section '{}'",
+                                    op.borrow().get_operation_name(),
+                                    section.name()
+                                ),
+                            ))
+                        }
+                    };
+                    next = address + size;
+                    code.data.push((address, op));
+                }
+            }
+        }
+        for name in pending {
+            code.add_label(name, next);
+        }
+        Ok(code)
+    }
+
+    fn add_label(&mut self, name: String, address: u64) {
+        self.labels.insert(name.clone(), address);
+        self.names.entry(address).or_default().push(name);
+    }
+
+    fn add_symbol(&mut self, op: &OpRef) {
+        if let Some(kind) = op_cast::<SymbolTypeOp>(op.clone()) {
+            if kind.borrow().kind() == "function" {
+                self.symbols.push(kind.borrow().name().to_string());
+            }
+        }
+        if let Some(binding) = op_cast::<SymbolBindingOp>(op.clone()) {
+            if binding.borrow().binding() != "local" {
+                self.symbols.push(binding.borrow().name().to_string());
+            }
+        }
+    }
+
+    fn find_functions(&self, memory: &Memory) -> Vec<Function> {
+        let mut entries: VecDeque<usize> = self
+            .symbols
+            .iter()
+            .filter_map(|name| self.index.get(self.labels.get(name)?).copied())
+            .collect();
+        if entries.is_empty() {
+            entries.push_back(0);
+        }
+        let mut is_entry: HashSet<usize> = entries.iter().copied().collect();
+
+        let mut owner: Vec<Option<usize>> = vec![None; self.instrs.len()];
+        let mut functions = vec![];
+        while let Some(entry) = entries.pop_front() {
+            if owner[entry].is_some() {
+                continue;
+            }
+            let func_idx = functions.len();
+            let mut function = Function {
+                instrs: BTreeSet::new(),
+                block_starts: HashSet::new(),
+            };
+
+            let mut stack = vec![entry];
+            while let Some(start) = stack.pop() {
+                let mut idx = start;
+                function.block_starts.insert(start);
+                loop {
+                    if owner[idx].is_some() || (idx != entry && is_entry.contains(&idx)) {
+                        // Control flows into an instruction, that has been visited already
+                        if owner[idx] == Some(func_idx) {
+                            function.block_starts.insert(idx);
+                        }
+                        break;
+                    }
+                    owner[idx] = Some(func_idx);
+                    function.instrs.insert(idx);
+
+                    let (address, op) = &self.instrs[idx];
+                    let (targets, falls_through) = match get_flow(op, &self.labels) {
+                        Flow::Next => (vec![], true),
+                        Flow::Call(target) => {
+                            let target = target.and_then(|target| self.index.get(&target));
+                            if let Some(target) = target {
+                                is_entry.insert(*target);
+                                entries.push_back(*target);
+                            }
+                            (vec![], true)
+                        }
+                        Flow::Branch(target, falls_through) => {
+                            function.block_starts.insert(idx + 1);
+                            (target.into_iter().collect(), falls_through)
+                        }
+                        Flow::IndirectJump(reg) => {
+                            let targets = self.find_jump_table(idx, reg, memory);
+                            if !targets.is_empty() {
+                                let attrs = HashMap::from([(
+                                    JUMP_TABLE_ATTR.to_string(),
+                                    Attr::U64Array(targets.clone()),
+                                )]);
+                                op.borrow_mut().add_attrs(&attrs);
+                            }
+                            (targets, false)
+                        }
+                        Flow::Return | Flow::Stop => (vec![], false),
+                    };
+                    // Jumps to other functions are tail calls
+                    let targets = targets
+                        .iter()
+                        .filter_map(|target| self.index.get(target))
+                        .filter(|target| !is_entry.contains(target));
+                    stack.extend(targets);
+
+                    let next = address + get_instr_size(&*op.borrow());
+                    match self.index.get(&next) {
+                        Some(next) if falls_through => idx = *next,
+                        _ => break,
+                    }
+                }
+            }
+            functions.push(function);
+        }
+        functions
+    }
+
+    /// Targets of the jump table, that the indirect jump at the index dispatches with.
+    ///
+    /// Addresses, that are materialized with `auipc` or `lui` followed by `addi` right
+    /// before the jump, are tried as jump tables of 8-byte absolute addresses, 4-byte
+    /// offsets from the table and 4-byte absolute addresses. The first format with at
+    /// least two consecutive entries, that point to instructions of the section, wins.
+    fn find_jump_table(&self, idx: usize, reg: u8, memory: &Memory) -> Vec<u64> {
+        let mut values: HashMap<u8, u64> = HashMap::new();
+        let mut candidates = vec![];
+        for (address, op) in &self.instrs[idx.saturating_sub(JUMP_TABLE_WINDOW)..idx] {
+            let upper = |imm: i64| (((imm as u32) << 12) as i32) as i64;
+            let value = if let Some(auipc) = op_cast::<AuipcOp>(op.clone()) {
+                let auipc = auipc.borrow();
+                reg_num(auipc.get_rd())
+                    .map(|rd| (rd, address.wrapping_add_signed(upper(auipc.imm()))))
+            } else if let Some(lui) = op_cast::<LuiOp>(op.clone()) {
+                let lui = lui.borrow();
+                reg_num(lui.get_rd()).map(|rd| (rd, upper(lui.imm()) as u64))
+            } else if let Some(addi) = op_cast::<AddImmOp>(op.clone()) {
+                let addi = addi.borrow();
+                let base = reg_num(addi.get_rs1()).and_then(|rs1| values.get(&rs1));
+                base.zip(reg_num(addi.get_rd()))
+                    .map(|(base, rd)| (rd, base.wrapping_add_signed(addi.imm())))
+            } else {
+                None
+            };
+            if let Some((rd, value)) = value {
+                values.insert(rd, value);
+                candidates.push(value);
+            }
+        }
+        if let Some(value) = values.get(&reg) {
+            candidates.push(*value);
+        }
+
+        for table in candidates.into_iter().rev() {
+            for (size, relative) in [(8, false), (4, true), (4, false)] {
+                let mut targets = vec![];
+                for entry in 0..MAX_JUMP_TABLE_SIZE {
+                    let Some(value) = memory.read(table + (entry * size) as u64, size) else {
+                        break;
+                    };
+                    let target = match relative {
+                        true => table.wrapping_add_signed(value as u32 as i32 as i64),
+                        false => value,
+                    };
+                    if !self.index.contains_key(&target) {
+                        break;
+                    }
+                    targets.push(target);
+                }
+                if targets.len() >= 2 {
+                    return targets;
+                }
+            }
+        }
+        vec![]
+    }
+
+    /// Replace the blocks of the section with symbol directives, functions and data
+    /// in the address order
+    fn rebuild(&self, section: &SectionOp, functions: &[Function]) {
+        let context = section.get_context();
+        let body = section.get_body_region();
+        for block in body.iter().collect::<Vec<_>>() {
+            body.remove_block(&block);
+        }
+        let entry = Block::empty(&body);
+        body.add_block(entry.clone());
+        for op in &self.directives {
+            entry.push(op);
+        }
+
+        let owned: HashSet<usize> = functions.iter().flat_map(|f| f.instrs.clone()).collect();
+        let owned_addresses: HashSet<u64> = owned.iter().map(|idx| self.instrs[*idx].0).collect();
+
+        // Everything, that does not belong to a function, in the address order
+        let mut data: BTreeMap<u64, Vec<OpRef>> = BTreeMap::new();
+        for address in self.names.keys() {
+            if !owned_addresses.contains(address) {
+                data.entry(*address).or_default();
+            }
+        }
+        for (address, op) in &self.data {
+            data.entry(*address).or_default().push(op.clone());
+        }
+        let mut run: Option<(u64, Vec<u8>, Location)> = None;
+        for (idx, (address, op)) in self.instrs.iter().enumerate() {
+            if owned.contains(&idx) {
+                continue;
+            }
+            let Some(bytes) = self.encode_instr(*address, op) else {
+                data.entry(*address).or_default().push(op.clone());
+                continue;
+            };
+            // Unreachable instructions are merged, until a label is met
+            match &mut run {
+                Some((start, run_bytes, _))
+                    if *start + run_bytes.len() as u64 == *address
+                        && !self.names.contains_key(address) =>
+                {
+                    run_bytes.extend(bytes);
+                }
+                _ => {
+                    if let Some(run) = run.replace((*address, bytes, op.borrow().get_location())) {
+                        data.entry(run.0).or_default().push(data_op(&context, run));
+                    }
+                }
+            }
+        }
+        if let Some(run) = run {
+            data.entry(run.0).or_default().push(data_op(&context, run));
+        }
+
+        let mut items: Vec<(u64, Option<&Function>)> = functions
+            .iter()
+            .map(|function| {
+                let first = function.instrs.first().unwrap();
+                (self.instrs[*first].0, Some(function))
+            })
+            .collect();
+        items.extend(data.keys().map(|address| (*address, None)));
+        items.sort_by_key(|(address, function)| (*address, function.is_some()));
+
+        let mut block = entry;
+        for (address, function) in items {
+            match function {
+                Some(function) => block.push(&self.function_op(&context, function)),
+                None => {
+                    for name in self.names.get(&address).into_iter().flatten() {
+                        block = Block::with_arguments::<&str>(name, &body, &[], &[]);
+                        body.add_block(block.clone());
+                    }
+                    for op in &data[&address] {
+                        block.push(op);
+                    }
+                }
+            }
+        }
+    }
+
+    fn function_op(&self, context: &tir_core::ContextRef, function: &Function) -> OpRef {
+        let first = *function.instrs.first().unwrap();
+        let (address, _) = &self.instrs[first];
+        let names = self.names.get(address).cloned().unwrap_or_default();
+        let name = names
+            .last()
+            .cloned()
+            .unwrap_or_else(|| address_label(*address));
+
+        let body = Region::empty(context);
+        let mut block: Option<BlockRef> = None;
+        let mut prev = None;
+        for idx in &function.instrs {
+            let (address, op) = &self.instrs[*idx];
+            let contiguous = prev.is_some_and(|prev| prev + 1 == *idx);
+            if block.is_none() || !contiguous || function.block_starts.contains(idx) {
+                let names = match self.names.get(address) {
+                    Some(names) => names.clone(),
+                    None => vec![address_label(*address)],
+                };
+                for name in names {
+                    let new_block = Block::with_arguments::<&str>(&name, &body, &[], &[]);
+                    body.add_block(new_block.clone());
+                    block = Some(new_block);
+                }
+            }
+            block.as_ref().unwrap().push(op);
+            prev = Some(*idx);
+        }
+
+        let function = FunctionOp::builder(context).name(name).body(body).build();
+        function
+            .borrow_mut()
+            .set_location(Location::Address(*address));
+        function
+    }
+
+    /// Bytes of an unreachable instruction. Labels are resolved by their addresses.
+    fn encode_instr(&self, address: u64, op: &OpRef) -> Option<Vec<u8>> {
+        let target_opts = TargetOptions {
+            endiannes: Endianness::Little,
+            word_size: 8,
+            flags: vec![format!("{}{}", MARCH_FLAG, DEFAULT_ISA)],
+        };
+        let bytes = Rc::new(RefCell::new(vec![]));
+        let mut stream: Box<dyn BinaryStream> = Box::new(ByteStream(bytes.clone()));
+
+        if let Some(label_ref) = op_dyn_cast::<dyn LabelRef>(op.clone()) {
+            let label_ref = label_ref.borrow();
+            let target = self
+                .labels
+                .get(label_ref.get_label())
+                .copied()
+                .or_else(|| parse_address_label(label_ref.get_label()))?;
+            let offset = target.wrapping_sub(address) as i64;
+            label_ref
+                .encode_with_offset(Some(offset), &target_opts, &mut stream)
+                .ok()?;
+        } else {
+            encode_op(op, &target_opts, &mut stream)?.ok()?;
+        }
+        let bytes = bytes.borrow().clone();
+        Some(bytes)
+    }
+}
+
+fn data_op(
+    context: &tir_core::ContextRef,
+    (_, bytes, location): (u64, Vec<u8>, Location),
+) -> OpRef {
+    let values = bytes.into_iter().map(|byte| byte as i64).collect();
+    let op = DataOp::builder(context).size(1).values(values).build();
+    op.borrow_mut().set_location(location);
+    op
+}
+
+pub(crate) struct ByteStream(pub(crate) Rc<RefCell<Vec<u8>>>);
+
+impl BinaryStream for ByteStream {
+    fn write(&mut self, data: &[u8]) {
+        self.0.borrow_mut().extend_from_slice(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+    use tir_backend::target::get_data_size;
+
+    /// Locate the operations of each section at the given base address, as if the
+    /// module had been linked
+    fn place(module: &Arc<RwCell<ModuleOp>>, bases: &[u64]) {
+        let sections = module.borrow().get_body().iter();
+        for (section, base) in sections.filter_map(op_cast::<SectionOp>).zip(bases) {
+            let mut address = *base;
+            for op in section
+                .borrow()
+                .get_body_region()
+                .iter()
+                .flat_map(|b| b.iter())
+            {
+                op.borrow_mut().set_location(Location::Address(address));
+                address += match is_instr(&op) {
+                    true => get_instr_size(&*op.borrow()),
+                    false => get_data_size(&*op.borrow(), address).unwrap_or(0),
+                };
+            }
+        }
+    }
+
+    fn functions(module: &Arc<RwCell<ModuleOp>>) -> Vec<Arc<RwCell<FunctionOp>>> {
+        let section = op_cast::<SectionOp>(module.borrow().get_body().first().unwrap()).unwrap();
+        let body = section.borrow().get_body_region();
+        body.iter()
+            .flat_map(|block| block.iter())
+            .filter_map(op_cast::<FunctionOp>)
+            .collect()
+    }
+
+    fn block_names(blocks: &[BlockRef]) -> Vec<String> {
+        blocks.iter().map(|block| block.get_name()).collect()
+    }
+
+    #[test]
+    fn test_recover_functions() {
+        let context = context();
+        let module = parse_asm(
+            &context,
+            ".text
+    .globl main
+main:
+    beqz a0, .Lskip
+    addi a0, a0, 1
+.Lskip:
+    jal ra, helper
+    ret
+    .word 0x13
+helper:
+    c.nop
+    tail main",
+        )
+        .unwrap();
+        recover_cfg(&module).unwrap();
+
+        let functions = functions(&module);
+        let names: Vec<_> = functions
+            .iter()
+            .map(|f| f.borrow().name().to_string())
+            .collect();
+        assert_eq!(names, ["main", "helper"]);
+
+        let main = functions[0].borrow().get_body_region();
+        let blocks: Vec<_> = main.iter().collect();
+        assert_eq!(block_names(&blocks), ["main", "L0x4", ".Lskip"]);
+        assert_eq!(
+            block_names(&get_block_successors(&blocks[0])),
+            [".Lskip", "L0x4"]
+        );
+        assert_eq!(block_names(&get_block_successors(&blocks[1])), [".Lskip"]);
+        assert!(get_block_successors(&blocks[2]).is_empty());
+
+        // The word after the return is not reachable
+        let section = op_cast::<SectionOp>(module.borrow().get_body().first().unwrap()).unwrap();
+        let ops: Vec<_> = section
+            .borrow()
+            .get_body_region()
+            .first()
+            .unwrap()
+            .iter()
+            .collect();
+        let data = op_cast::<DataOp>(ops[2].clone()).unwrap();
+        assert_eq!(data.borrow().size(), 4);
+        assert_eq!(data.borrow().values(), &[0x13]);
+    }
+
+    #[test]
+    fn test_recover_jump_table() {
+        let context = context();
+        let module = parse_asm(
+            &context,
+            ".text
+    .globl dispatch
+    .type dispatch, @function
+dispatch:
+    addi t0, zero, 2
+    bgeu a0, t0, .Ldefault
+    slli a0, a0, 3
+    lui a5, 2
+    addi a5, a5, 0
+    add a5, a5, a0
+    ld a5, 0(a5)
+    jalr zero, 0(a5)
+.Lcase0:
+    addi a0, zero, 10
+    ret
+.Lcase1:
+    addi a0, zero, 20
+    ret
+.Ldefault:
+    addi a0, zero, 0
+    ret
+.section .rodata
+table:
+    .dword 0x1020, 0x1028, 0",
+        )
+        .unwrap();
+        place(&module, &[0x1000, 0x2000]);
+        recover_cfg(&module).unwrap();
+
+        let functions = functions(&module);
+        assert_eq!(functions.len(), 1);
+        let body = functions[0].borrow().get_body_region();
+        let blocks: Vec<_> = body.iter().collect();
+        assert_eq!(
+            block_names(&blocks),
+            ["dispatch", "L0x1008", ".Lcase0", ".Lcase1", ".Ldefault"]
+        );
+
+        let jump = blocks[1].last().unwrap();
+        assert_eq!(
+            jump.borrow().get_attrs().get(JUMP_TABLE_ATTR),
+            Some(&Attr::U64Array(vec![0x1020, 0x1028]))
+        );
+        assert_eq!(
+            block_names(&get_block_successors(&blocks[1])),
+            [".Lcase0", ".Lcase1"]
+        );
+        assert_eq!(
+            block_names(&get_block_successors(&blocks[0])),
+            [".Ldefault", "L0x1008"]
+        );
+    }
+}
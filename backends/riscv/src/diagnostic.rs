@@ -7,6 +7,26 @@ pub enum DiagKind {
     UnknownRegister(String, Span),
     #[error("Unknown opcode")]
     UnknownOpcode(Span),
+    #[error("Immediate value {0} is out of range [{1}, {2}]")]
+    ImmOutOfRange(i64, i64, i64, Span),
+    #[error("Invalid fence access set '{0}', expected a subset of 'iorw'")]
+    InvalidFenceSet(String, Span),
+    #[error("Unknown CSR '{0}'")]
+    UnknownCSR(String, Span),
+    #[error("Instruction '{0}' requires the {1} extension, that is not enabled")]
+    DisabledExtension(String, String, Span),
+    #[error("Unknown rounding mode '{0}', expected one of rne, rtz, rdn, rup, rmm or dyn")]
+    UnknownRoundingMode(String, Span),
+    #[error("Register '{0}' can not be encoded in the compressed instruction")]
+    InvalidCompressedRegister(String, Span),
+    #[error("Immediate value {0} must be a multiple of {1}")]
+    MisalignedImm(i64, i64, Span),
+    #[error("Immediate value must not be zero")]
+    ZeroImm(Span),
+    #[error("Unknown relocation '%{0}'")]
+    UnknownRelocation(String, Span),
+    #[error("Relocation '%{0}' is not allowed for this operand")]
+    InvalidRelocation(String, Span),
 }
 
 impl DiagnosticLike for DiagKind {
@@ -18,6 +38,16 @@ impl DiagnosticLike for DiagKind {
         match self {
             DiagKind::UnknownRegister(_, span) => span.clone(),
             DiagKind::UnknownOpcode(span) => span.clone(),
+            DiagKind::ImmOutOfRange(_, _, _, span) => span.clone(),
+            DiagKind::InvalidFenceSet(_, span) => span.clone(),
+            DiagKind::UnknownCSR(_, span) => span.clone(),
+            DiagKind::DisabledExtension(_, _, span) => span.clone(),
+            DiagKind::UnknownRoundingMode(_, span) => span.clone(),
+            DiagKind::InvalidCompressedRegister(_, span) => span.clone(),
+            DiagKind::MisalignedImm(_, _, span) => span.clone(),
+            DiagKind::ZeroImm(span) => span.clone(),
+            DiagKind::UnknownRelocation(_, span) => span.clone(),
+            DiagKind::InvalidRelocation(_, span) => span.clone(),
         }
     }
 
@@ -0,0 +1,318 @@
+//! RISC-V ISA strings and extension sets
+//!
+//! The set of enabled extensions is described by an ISA string, like `rv64imac` or
+//! `rv64gc_zicsr`, and is passed to the backend with the `-march=<isa>` target flag.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use thiserror::Error;
+use tir_backend::TargetOptions;
+
+/// Target flag, that selects the ISA string
+pub const MARCH_FLAG: &str = "-march=";
+
+/// ISA string of the extension set enabled by default
+pub const DEFAULT_ISA: &str = "rv64gc";
+
+/// Standard ISA extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Extension {
+    /// Base integer instruction set
+    I,
+    /// Integer multiplication and division
+    M,
+    /// Atomic instructions
+    A,
+    /// Single-precision floating-point
+    F,
+    /// Double-precision floating-point
+    D,
+    /// Compressed instructions
+    C,
+    /// Control and status register instructions
+    Zicsr,
+    /// Instruction-fetch fence
+    Zifencei,
+    /// Compressed double-precision loads and stores, enabled by C together with D
+    Zcd,
+}
+
+impl Extension {
+    /// Single-letter extensions in the canonical order of the ISA string
+    const SINGLE_LETTER: [(char, Extension); 5] = [
+        ('m', Extension::M),
+        ('a', Extension::A),
+        ('f', Extension::F),
+        ('d', Extension::D),
+        ('c', Extension::C),
+    ];
+
+    const MULTI_LETTER: [(&'static str, Extension); 2] = [
+        ("zicsr", Extension::Zicsr),
+        ("zifencei", Extension::Zifencei),
+    ];
+
+    /// Extensions, that must be enabled together with this one
+    fn implies(&self) -> &'static [Extension] {
+        match self {
+            Extension::F => &[Extension::Zicsr],
+            Extension::D => &[Extension::F, Extension::Zicsr],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Extension::I => "I",
+            Extension::M => "M",
+            Extension::A => "A",
+            Extension::F => "F",
+            Extension::D => "D",
+            Extension::C => "C",
+            Extension::Zicsr => "Zicsr",
+            Extension::Zifencei => "Zifencei",
+            Extension::Zcd => "Zcd",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ISAError {
+    #[error("ISA string must start with 'rv32' or 'rv64', got '{0}'")]
+    InvalidPrefix(String),
+    #[error("Only RV64 is supported, got RV{0}")]
+    UnsupportedXLen(u8),
+    #[error("Unsupported base ISA '{0}', expected 'i' or 'g'")]
+    UnsupportedBase(char),
+    #[error("Unknown extension '{0}'")]
+    UnknownExtension(String),
+    #[error("Extension '{0}' is out of the canonical order")]
+    NonCanonicalOrder(char),
+    #[error("Extension '{0}' is specified more than once")]
+    DuplicateExtension(String),
+}
+
+/// Set of extensions enabled for the target
+#[derive(Debug, Clone, PartialEq)]
+pub struct ISAFeatures {
+    xlen: u8,
+    extensions: BTreeSet<Extension>,
+}
+
+impl ISAFeatures {
+    /// Parse an ISA string, i.e. `rv64imac` or `rv64i_m_zicsr`. Version numbers of
+    /// extensions, like `m2p0`, are accepted and ignored.
+    pub fn parse(isa: &str) -> Result<Self, ISAError> {
+        let isa = isa.to_ascii_lowercase();
+
+        let xlen = match isa.get(0..4) {
+            Some("rv32") => 32,
+            Some("rv64") => 64,
+            _ => return Err(ISAError::InvalidPrefix(isa)),
+        };
+        if xlen != 64 {
+            return Err(ISAError::UnsupportedXLen(xlen));
+        }
+
+        let mut features = ISAFeatures {
+            xlen,
+            extensions: BTreeSet::new(),
+        };
+
+        let mut chunks = isa[4..].split('_');
+        let mut letters = chunks.next().unwrap_or_default().chars().peekable();
+
+        match letters.next() {
+            Some('i') => features.enable(Extension::I),
+            Some('g') => {
+                for ext in [
+                    Extension::I,
+                    Extension::M,
+                    Extension::A,
+                    Extension::F,
+                    Extension::D,
+                    Extension::Zicsr,
+                    Extension::Zifencei,
+                ] {
+                    features.enable(ext);
+                }
+            }
+            Some(base) => return Err(ISAError::UnsupportedBase(base)),
+            None => return Err(ISAError::InvalidPrefix(isa)),
+        }
+        skip_version(&mut letters);
+
+        let mut order = 0;
+        let mut single_letter = |c: char, features: &mut ISAFeatures| -> Result<(), ISAError> {
+            let idx = Extension::SINGLE_LETTER
+                .iter()
+                .position(|(letter, _)| *letter == c)
+                .ok_or(ISAError::UnknownExtension(c.to_string()))?;
+            if idx < order {
+                return Err(ISAError::NonCanonicalOrder(c));
+            }
+            order = idx + 1;
+            features.enable(Extension::SINGLE_LETTER[idx].1);
+            Ok(())
+        };
+
+        while let Some(c) = letters.next() {
+            single_letter(c, &mut features)?;
+            skip_version(&mut letters);
+        }
+
+        let mut seen = BTreeSet::new();
+        for chunk in chunks {
+            let name = chunk.trim_end_matches(|c: char| c.is_ascii_digit() || c == 'p');
+            match Extension::MULTI_LETTER
+                .iter()
+                .find(|(ext_name, _)| *ext_name == name)
+            {
+                Some((_, ext)) => {
+                    if !seen.insert(*ext) {
+                        return Err(ISAError::DuplicateExtension(name.to_string()));
+                    }
+                    features.enable(*ext);
+                }
+                // Single-letter extensions may also be separated by underscores
+                None if name.len() == 1 => {
+                    single_letter(name.chars().next().unwrap(), &mut features)?
+                }
+                None => return Err(ISAError::UnknownExtension(chunk.to_string())),
+            }
+        }
+
+        Ok(features)
+    }
+
+    /// Extension set selected by the `-march=<isa>` target flag, or the default one
+    pub fn from_target_options(opts: &TargetOptions) -> Result<Self, ISAError> {
+        let isa = opts
+            .flags
+            .iter()
+            .rev()
+            .find_map(|flag| flag.strip_prefix(MARCH_FLAG))
+            .unwrap_or(DEFAULT_ISA);
+        Self::parse(isa)
+    }
+
+    pub fn xlen(&self) -> u8 {
+        self.xlen
+    }
+
+    /// Check if the extension is enabled
+    pub fn has(&self, ext: Extension) -> bool {
+        self.extensions.contains(&ext)
+    }
+
+    pub fn extensions(&self) -> impl Iterator<Item = Extension> + '_ {
+        self.extensions.iter().copied()
+    }
+
+    fn enable(&mut self, ext: Extension) {
+        self.extensions.insert(ext);
+        for implied in ext.implies() {
+            self.extensions.insert(*implied);
+        }
+        if self.has(Extension::C) && self.has(Extension::D) {
+            self.extensions.insert(Extension::Zcd);
+        }
+    }
+}
+
+impl Default for ISAFeatures {
+    fn default() -> Self {
+        Self::parse(DEFAULT_ISA).unwrap()
+    }
+}
+
+/// Skip an optional version number of an extension, like `2p1`
+fn skip_version(letters: &mut std::iter::Peekable<std::str::Chars>) {
+    while letters.next_if(|c| c.is_ascii_digit()).is_some() {}
+    if letters.peek() == Some(&'p') {
+        letters.next();
+        while letters.next_if(|c| c.is_ascii_digit()).is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions(isa: &str) -> Vec<Extension> {
+        ISAFeatures::parse(isa).unwrap().extensions().collect()
+    }
+
+    #[test]
+    fn test_parse_isa() {
+        use Extension::*;
+
+        assert_eq!(extensions("rv64i"), vec![I]);
+        assert_eq!(extensions("rv64imac"), vec![I, M, A, C]);
+        assert_eq!(extensions("RV64IMAC"), vec![I, M, A, C]);
+        assert_eq!(extensions("rv64im_zicsr"), vec![I, M, Zicsr]);
+        assert_eq!(extensions("rv64i_m_a"), vec![I, M, A]);
+        assert_eq!(extensions("rv64i2p1m2p0"), vec![I, M]);
+        assert_eq!(extensions("rv64id"), vec![I, F, D, Zicsr]);
+        assert_eq!(extensions("rv64if_zicsr"), vec![I, F, Zicsr]);
+        assert_eq!(
+            extensions("rv64gc"),
+            vec![I, M, A, F, D, C, Zicsr, Zifencei, Zcd]
+        );
+        assert_eq!(extensions("rv64ifc"), vec![I, F, C, Zicsr]);
+        assert_eq!(extensions("rv64idc"), vec![I, F, D, C, Zicsr, Zcd]);
+    }
+
+    #[test]
+    fn test_parse_isa_errors() {
+        assert_eq!(
+            ISAFeatures::parse("x86"),
+            Err(ISAError::InvalidPrefix("x86".to_string()))
+        );
+        assert_eq!(
+            ISAFeatures::parse("rv32i"),
+            Err(ISAError::UnsupportedXLen(32))
+        );
+        assert_eq!(
+            ISAFeatures::parse("rv64e"),
+            Err(ISAError::UnsupportedBase('e'))
+        );
+        assert_eq!(
+            ISAFeatures::parse("rv64iam"),
+            Err(ISAError::NonCanonicalOrder('m'))
+        );
+        assert_eq!(
+            ISAFeatures::parse("rv64imm"),
+            Err(ISAError::NonCanonicalOrder('m'))
+        );
+        assert_eq!(
+            ISAFeatures::parse("rv64iv"),
+            Err(ISAError::UnknownExtension("v".to_string()))
+        );
+        assert_eq!(
+            ISAFeatures::parse("rv64i_zicsr_zicsr"),
+            Err(ISAError::DuplicateExtension("zicsr".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_target_options() {
+        let opts = |flags: &[&str]| TargetOptions {
+            endiannes: tir_backend::Endianness::Little,
+            word_size: 8,
+            flags: flags.iter().map(|flag| flag.to_string()).collect(),
+        };
+
+        let features = ISAFeatures::from_target_options(&opts(&[])).unwrap();
+        assert_eq!(features, ISAFeatures::default());
+
+        let features = ISAFeatures::from_target_options(&opts(&["-march=rv64im"])).unwrap();
+        assert!(features.has(Extension::M));
+        assert!(!features.has(Extension::A));
+        assert_eq!(features.xlen(), 64);
+    }
+}
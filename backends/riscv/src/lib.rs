@@ -1,28 +1,97 @@
 use lpl::ParseResult;
-use tir_backend::{DisassemblerError, ISAParser, TokenStream};
+use tir_backend::{
+    AsmPrintable, BinaryEmittable, BinaryStream, DisassemblerError, ISAParser, TargetOptions,
+    TokenStream,
+};
 use tir_core::Dialect;
-use tir_core::{ContextRef, OpAssembly, OpBuilder};
+use tir_core::{ContextRef, Location, OpAssembly, OpBuilder, OpRef};
 
 mod asm_parser;
+mod asm_printer;
+mod cfg;
 mod diagnostic;
+mod isa;
+mod loader;
+mod object;
 mod ops;
 mod registers;
+mod rewrite;
 pub mod utils;
 pub use asm_parser::*;
+pub use asm_printer::*;
+pub use cfg::*;
 pub use diagnostic::*;
+pub use isa::*;
+pub use loader::*;
+pub use object::*;
 pub use ops::*;
 pub use registers::*;
+pub use rewrite::*;
 
 use tir_macros::{dialect, populate_dialect_ops, populate_dialect_types};
 
 macro_rules! populate_riscv_ops {
-    ($($op:ident,)+) => {
-        populate_dialect_ops!($($op),*);
+    ($($ext:ident => { $($op:ident,)+ })+) => {
+        populate_dialect_ops!($($($op),*),*, UnknownWordOp);
 
         fn populate_riscv_asm_parsers(ext: &mut RVExt) {
-            $(
-            ext.register_asm_parser(Box::new(<$op as ISAParser>::parse));
-            )*
+            $($(
+            ext.register_asm_parser(
+                Extension::$ext,
+                <$op>::get_operation_name(),
+                Box::new(<$op as ISAParser>::parse),
+            );
+            )*)*
+        }
+
+        /// Extension, that an operation of the RISC-V dialect belongs to. Returns `None` for
+        /// operations of other dialects.
+        pub fn get_op_extension(op: &OpRef) -> Option<Extension> {
+            $($(
+            if tir_core::utils::op_cast::<$op>(op.clone()).is_some() {
+                return Some(Extension::$ext);
+            }
+            )*)*
+            None
+        }
+
+        /// Encode an operation of the RISC-V dialect. Returns `None` for operations of
+        /// other dialects. Operations of extensions, that are not enabled by the target
+        /// options, are rejected. Unknown words are emitted as they are.
+        pub fn encode_op(
+            op: &OpRef,
+            target_opts: &TargetOptions,
+            stream: &mut Box<dyn BinaryStream>,
+        ) -> Option<tir_core::Result<()>> {
+            if let Some(word) = tir_core::utils::op_cast::<UnknownWordOp>(op.clone()) {
+                return Some(word.borrow().encode(target_opts, stream));
+            }
+            $($(
+            if let Some(op) = tir_core::utils::op_cast::<$op>(op.clone()) {
+                let features = ISAFeatures::from_target_options(target_opts);
+                return Some(match features {
+                    Ok(features) if features.has(Extension::$ext) => {
+                        op.borrow().encode(target_opts, stream)
+                    }
+                    _ => Err(tir_core::Error::Unknown),
+                });
+            }
+            )*)*
+            None
+        }
+
+        /// Print an operation of the RISC-V dialect as a line of assembly. Returns `None`
+        /// for operations of other dialects.
+        pub fn print_op(op: &OpRef, target_opts: &TargetOptions) -> Option<String> {
+            if let Some(word) = tir_core::utils::op_cast::<UnknownWordOp>(op.clone()) {
+                return Some(word.borrow().print(target_opts));
+            }
+            $($(
+            if let Some(op) = tir_core::utils::op_cast::<$op>(op.clone()) {
+                return Some(op.borrow().print(target_opts));
+            }
+            )*)*
+            None
         }
     };
 }
@@ -34,85 +103,471 @@ dialect!(riscv, |dialect: &mut Dialect| {
     dialect.set_dialect_extension(Box::new(ext));
 });
 populate_riscv_ops!(
-    // R-type ALU ops
-    AddOp,
-    SubOp,
-    SllOp,
-    SltOp,
-    SltuOp,
-    SrlOp,
-    SraOp,
-    OrOp,
-    AndOp,
-    // I-type ALU ops
-    AddImmOp,
-    SllImmOp,
-    SltImmOp,
-    SltuImmOp,
-    SrlImmOp,
-    SraImmOp,
-    OrImmOp,
-    AndImmOp,
-    // Load ops
-    LoadByte,
-    LoadHalfword,
-    LoadWord,
-    LoadDouble,
-    LoadByteUnsigned,
-    LoadHalfwordUnsigned,
-    LoadWordUnsigned,
-    // Store ops
-    StoreByte,
-    StoreHalfword,
-    StoreWord,
-    StoreDouble,
+    I => {
+        // R-type ALU ops
+        AddOp,
+        SubOp,
+        SllOp,
+        SltOp,
+        SltuOp,
+        XorOp,
+        SrlOp,
+        SraOp,
+        OrOp,
+        AndOp,
+        AddWOp,
+        SubWOp,
+        SllWOp,
+        SrlWOp,
+        SraWOp,
+        // I-type ALU ops
+        AddImmOp,
+        SllImmOp,
+        SltImmOp,
+        SltuImmOp,
+        XorImmOp,
+        SrlImmOp,
+        SraImmOp,
+        OrImmOp,
+        AndImmOp,
+        AddImmWOp,
+        SllImmWOp,
+        SrlImmWOp,
+        SraImmWOp,
+        // Load ops
+        LoadByte,
+        LoadHalfword,
+        LoadWord,
+        LoadDouble,
+        LoadByteUnsigned,
+        LoadHalfwordUnsigned,
+        LoadWordUnsigned,
+        // Store ops
+        StoreByte,
+        StoreHalfword,
+        StoreWord,
+        StoreDouble,
+        // Branch ops
+        BranchEqOp,
+        BranchNeOp,
+        BranchLtOp,
+        BranchGeOp,
+        BranchLtuOp,
+        BranchGeuOp,
+        // Jump ops
+        JumpAndLinkOp,
+        JumpAndLinkRegOp,
+        // Upper immediate ops
+        LuiOp,
+        AuipcOp,
+        // System ops
+        FenceOp,
+        FenceTsoOp,
+        EcallOp,
+        EbreakOp,
+        // Pseudo-instructions
+        PseudoLoadImmOp,
+        PseudoLoadAddrOp,
+        PseudoMoveOp,
+        PseudoNotOp,
+        PseudoNegOp,
+        PseudoNopOp,
+        PseudoJumpOp,
+        PseudoJumpRegOp,
+        PseudoRetOp,
+        PseudoCallOp,
+        PseudoTailOp,
+        PseudoBranchEqzOp,
+        PseudoBranchNezOp,
+        PseudoSetEqzOp,
+        PseudoSetNezOp,
+        PseudoSextWOp,
+    }
+    M => {
+        MulOp,
+        MulhOp,
+        MulhsuOp,
+        MulhuOp,
+        DivOp,
+        DivuOp,
+        RemOp,
+        RemuOp,
+        MulWOp,
+        DivWOp,
+        DivuWOp,
+        RemWOp,
+        RemuWOp,
+    }
+    A => {
+        LoadReservedWOp,
+        StoreConditionalWOp,
+        AmoSwapWOp,
+        AmoAddWOp,
+        AmoXorWOp,
+        AmoAndWOp,
+        AmoOrWOp,
+        AmoMinWOp,
+        AmoMaxWOp,
+        AmoMinuWOp,
+        AmoMaxuWOp,
+        LoadReservedDOp,
+        StoreConditionalDOp,
+        AmoSwapDOp,
+        AmoAddDOp,
+        AmoXorDOp,
+        AmoAndDOp,
+        AmoOrDOp,
+        AmoMinDOp,
+        AmoMaxDOp,
+        AmoMinuDOp,
+        AmoMaxuDOp,
+    }
+    Zicsr => {
+        CsrReadWriteOp,
+        CsrReadSetOp,
+        CsrReadClearOp,
+        CsrReadWriteImmOp,
+        CsrReadSetImmOp,
+        CsrReadClearImmOp,
+    }
+    Zifencei => {
+        FenceIOp,
+    }
+    F => {
+        FLoadWord,
+        FStoreWord,
+        FMaddSOp,
+        FMsubSOp,
+        FNmsubSOp,
+        FNmaddSOp,
+        FAddSOp,
+        FSubSOp,
+        FMulSOp,
+        FDivSOp,
+        FSqrtSOp,
+        FSgnjSOp,
+        FSgnjnSOp,
+        FSgnjxSOp,
+        FMinSOp,
+        FMaxSOp,
+        FCvtWSOp,
+        FCvtWuSOp,
+        FCvtLSOp,
+        FCvtLuSOp,
+        FCvtSWOp,
+        FCvtSWuOp,
+        FCvtSLOp,
+        FCvtSLuOp,
+        FMvXWOp,
+        FMvWXOp,
+        FEqSOp,
+        FLtSOp,
+        FLeSOp,
+        FClassSOp,
+    }
+    D => {
+        FLoadDouble,
+        FStoreDouble,
+        FMaddDOp,
+        FMsubDOp,
+        FNmsubDOp,
+        FNmaddDOp,
+        FAddDOp,
+        FSubDOp,
+        FMulDOp,
+        FDivDOp,
+        FSqrtDOp,
+        FSgnjDOp,
+        FSgnjnDOp,
+        FSgnjxDOp,
+        FMinDOp,
+        FMaxDOp,
+        FCvtWDOp,
+        FCvtWuDOp,
+        FCvtLDOp,
+        FCvtLuDOp,
+        FCvtDWOp,
+        FCvtDWuOp,
+        FCvtDLOp,
+        FCvtDLuOp,
+        FCvtSDOp,
+        FCvtDSOp,
+        FMvXDOp,
+        FMvDXOp,
+        FEqDOp,
+        FLtDOp,
+        FLeDOp,
+        FClassDOp,
+    }
+    C => {
+        CAddi4spnOp,
+        CLoadWord,
+        CLoadDouble,
+        CStoreWord,
+        CStoreDouble,
+        CNopOp,
+        CAddImmOp,
+        CAddImmWOp,
+        CLoadImmOp,
+        CAddi16spOp,
+        CLuiOp,
+        CSrlImmOp,
+        CSraImmOp,
+        CAndImmOp,
+        CSubOp,
+        CXorOp,
+        COrOp,
+        CAndOp,
+        CSubWOp,
+        CAddWOp,
+        CJumpOp,
+        CBranchEqzOp,
+        CBranchNezOp,
+        CSllImmOp,
+        CLoadWordSp,
+        CLoadDoubleSp,
+        CJumpRegOp,
+        CMoveOp,
+        CEbreakOp,
+        CJumpAndLinkRegOp,
+        CAddOp,
+        CStoreWordSp,
+        CStoreDoubleSp,
+    }
+    Zcd => {
+        CFLoadDouble,
+        CFStoreDouble,
+        CFLoadDoubleSp,
+        CFStoreDoubleSp,
+    }
 );
 populate_dialect_types!();
 
-type AsmParseFn = dyn for<'a> Fn(TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> + 'static;
+type AsmParseFn =
+    dyn for<'a> Fn(TokenStream<'a>) -> ParseResult<TokenStream<'a>, ()> + Send + Sync + 'static;
+
+/// Assembly parser of an operation, along with the extension and the name of the operation
+struct AsmParser {
+    extension: Extension,
+    op_name: &'static str,
+    parse: Box<AsmParseFn>,
+}
 
 #[derive(Default)]
 pub struct RVExt {
-    asm_parsers: Vec<Box<AsmParseFn>>,
+    asm_parsers: Vec<AsmParser>,
 }
 
 impl RVExt {
-    pub fn register_asm_parser(&mut self, parser: Box<AsmParseFn>) {
-        self.asm_parsers.push(parser);
+    pub fn register_asm_parser(
+        &mut self,
+        extension: Extension,
+        op_name: &'static str,
+        parser: Box<AsmParseFn>,
+    ) {
+        self.asm_parsers.push(AsmParser {
+            extension,
+            op_name,
+            parse: parser,
+        });
+    }
+
+    /// Assembly parsers of operations of the enabled extensions
+    pub fn get_asm_parsers<'a>(
+        &'a self,
+        features: &'a ISAFeatures,
+    ) -> impl Iterator<Item = &'a AsmParseFn> + 'a {
+        self.asm_parsers
+            .iter()
+            .filter(|parser| features.has(parser.extension))
+            .map(|parser| parser.parse.as_ref())
     }
 
-    pub fn get_asm_parsers(&self) -> &[Box<AsmParseFn>] {
-        &self.asm_parsers
+    /// Assembly parser of the operation with the given name
+    pub fn get_asm_parser(&self, op_name: &str) -> Option<&AsmParseFn> {
+        self.asm_parsers
+            .iter()
+            .find(|parser| parser.op_name == op_name)
+            .map(|parser| parser.parse.as_ref())
+    }
+
+    /// Extension of the operation with the given name
+    pub fn get_op_name_extension(&self, op_name: &str) -> Option<Extension> {
+        self.asm_parsers
+            .iter()
+            .find(|parser| parser.op_name == op_name)
+            .map(|parser| parser.extension)
     }
 }
 
+/// Disassemble the instructions of the default extension set
 pub fn disassemble(
     context: &ContextRef,
     builder: OpBuilder,
     stream: &[u8],
 ) -> Result<(), DisassemblerError> {
-    if stream.len() % 4 != 0 {
+    disassemble_with_features(context, builder, stream, &ISAFeatures::default())
+}
+
+/// Disassemble the instructions of the enabled extensions. Instructions of other extensions
+/// are treated as unknown, and the first unknown word is reported as an error.
+///
+/// The length of each instruction is determined by its lowest bits, so 16-bit instructions
+/// of the C extension may be freely mixed with 32-bit ones.
+pub fn disassemble_with_features(
+    context: &ContextRef,
+    builder: OpBuilder,
+    stream: &[u8],
+    features: &ISAFeatures,
+) -> Result<(), DisassemblerError> {
+    let (instrs, _) = decode_stream(context, stream, 0, features, false)?;
+    insert_instrs(&builder, instrs);
+    Ok(())
+}
+
+/// Disassemble the instructions of the enabled extensions, keeping the words, that can
+/// not be decoded, as `riscv.unknown_word` operations. Returns the coverage of the stream.
+pub fn disassemble_with_recovery(
+    context: &ContextRef,
+    builder: OpBuilder,
+    stream: &[u8],
+    features: &ISAFeatures,
+) -> Result<Coverage, DisassemblerError> {
+    let (instrs, coverage) = decode_stream(context, stream, 0, features, true)?;
+    insert_instrs(&builder, instrs);
+    Ok(coverage)
+}
+
+fn insert_instrs(builder: &OpBuilder, instrs: Vec<(u64, OpRef)>) {
+    for (address, op) in instrs {
+        builder.set_location(Location::Address(address));
+        builder.insert_generic(&op);
+    }
+}
+
+/// Number and size of the decoded instructions and of the unknown words
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// Number of decoded instructions
+    pub instrs: usize,
+    /// Size of the decoded instructions in bytes
+    pub instr_bytes: usize,
+    /// Number of words, that could not be decoded
+    pub unknown_words: usize,
+    /// Size of the unknown words in bytes
+    pub unknown_bytes: usize,
+}
+
+impl Coverage {
+    /// Share of the bytes, that were decoded to instructions
+    pub fn ratio(&self) -> f64 {
+        let total = self.instr_bytes + self.unknown_bytes;
+        if total == 0 {
+            1.0
+        } else {
+            self.instr_bytes as f64 / total as f64
+        }
+    }
+}
+
+impl std::ops::AddAssign for Coverage {
+    fn add_assign(&mut self, other: Coverage) {
+        self.instrs += other.instrs;
+        self.instr_bytes += other.instr_bytes;
+        self.unknown_words += other.unknown_words;
+        self.unknown_bytes += other.unknown_bytes;
+    }
+}
+
+impl std::fmt::Display for Coverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "decoded {} instructions ({} bytes), {} unknown words ({} bytes), {:.1}% coverage",
+            self.instrs,
+            self.instr_bytes,
+            self.unknown_words,
+            self.unknown_bytes,
+            self.ratio() * 100.0
+        )
+    }
+}
+
+/// Decode the instructions of the stream, that starts at the given address. In recovery
+/// mode, words that can not be decoded, including an incomplete instruction at the end of
+/// the stream, become unknown words. Otherwise the first of them is reported.
+pub(crate) fn decode_stream(
+    context: &ContextRef,
+    stream: &[u8],
+    address: u64,
+    features: &ISAFeatures,
+    recover: bool,
+) -> Result<(Vec<(u64, OpRef)>, Coverage), DisassemblerError> {
+    let mut instrs = vec![];
+    let mut coverage = Coverage::default();
+    let mut offset = 0;
+    while offset < stream.len() {
+        let address = address + offset as u64;
+        let (op, length) = match decode_instr(context, &stream[offset..], address, features) {
+            Ok((op, length)) => {
+                coverage.instrs += 1;
+                coverage.instr_bytes += length;
+                (op, length)
+            }
+            Err(_) if recover => {
+                let length = instr_length(stream[offset]).min(stream.len() - offset);
+                coverage.unknown_words += 1;
+                coverage.unknown_bytes += length;
+                let bytes = &stream[offset..offset + length];
+                (UnknownWordOp::from_bytes(context, bytes), length)
+            }
+            Err(err) => return Err(err),
+        };
+        instrs.push((address, op));
+        offset += length;
+    }
+
+    Ok((instrs, coverage))
+}
+
+/// Decode the instruction at the start of the stream, that is located at the given
+/// address. Returns the operation and the length of the instruction in bytes.
+pub(crate) fn decode_instr(
+    context: &ContextRef,
+    stream: &[u8],
+    address: u64,
+    features: &ISAFeatures,
+) -> Result<(OpRef, usize), DisassemblerError> {
+    let length = instr_length(stream[0]);
+    if stream.len() < length {
         return Err(DisassemblerError::UnexpectedEndOfStream(
-            4,
-            stream.len() % 4,
+            length,
+            stream.len(),
         ));
     }
 
-    for i in 0..(stream.len() / 4) {
-        let offset = i * 4;
-        if let Some(op) = disassemble_alu_instr(context, &stream[offset..]) {
-            builder.insert_generic(&op);
-        } else if let Some(op) = disassemble_load_instr(context, &stream[offset..]) {
-            builder.insert_generic(&op);
-        } else if let Some(op) = disassemble_store_instr(context, &stream[offset..]) {
-            builder.insert_generic(&op);
-        } else {
-            // FIXME add an appropriate error
-            return Err(DisassemblerError::Unknown);
+    let op = disassemble_instr(context, &stream[..length], address)
+        .filter(|op| get_op_extension(op).is_some_and(|ext| features.has(ext)));
+    match op {
+        Some(op) => Ok((op, length)),
+        None => {
+            let encoding = stream[..length]
+                .iter()
+                .rev()
+                .fold(0, |value, byte| (value << 8) | *byte as u32);
+            Err(DisassemblerError::UnknownInstruction(
+                address, encoding, length,
+            ))
         }
     }
+}
 
-    Ok(())
+fn disassemble_instr(context: &ContextRef, stream: &[u8], offset: u64) -> Option<OpRef> {
+    if stream.len() == 2 {
+        return disassemble_compressed_instr(context, stream, offset);
+    }
+
+    decode(context, stream, offset)
 }
 
 #[cfg(test)]
@@ -120,6 +575,7 @@ mod tests {
     use std::any::TypeId;
 
     use super::*;
+    use crate::ops::testing;
     use tir_core::builtin::ModuleOp;
     use tir_core::{Context, OpBuilder};
 
@@ -173,11 +629,19 @@ mod tests {
         assert_eq!(ops[6].borrow().type_id(), TypeId::of::<SraOp>());
         assert_eq!(ops[7].borrow().type_id(), TypeId::of::<OrOp>());
         assert_eq!(ops[8].borrow().type_id(), TypeId::of::<AndOp>());
+
+        for (idx, op) in ops.iter().enumerate() {
+            assert_eq!(
+                op.borrow().get_location(),
+                Location::Address((idx * 4) as u64)
+            );
+        }
     }
 
     #[test]
     fn test_disassembler_negative() {
-        let instructions = vec![0x7fffff3_u32];
+        // All-zero word is defined to be an illegal instruction
+        let instructions = vec![0x00000000_u32];
 
         let mut data = vec![];
 
@@ -192,6 +656,204 @@ mod tests {
 
         let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
 
-        assert!(disassemble(&context, builder, &data).is_err());
+        let err = disassemble(&context, builder, &data).unwrap_err();
+        assert!(matches!(
+            err,
+            DisassemblerError::UnknownInstruction(0, 0, 2)
+        ));
+        assert_eq!(err.to_string(), "unknown instruction `0x0000` at `0x0`");
+    }
+
+    #[test]
+    fn test_disassembler_recovery() {
+        let context = testing::context();
+        // add x28, x6, x7, a custom-0 instruction, c.nop, mul x28, x6, x7 and half of
+        // another add
+        let data = [
+            0x33, 0x0e, 0x73, 0x00, 0x0b, 0x10, 0x00, 0x00, 0x01, 0x00, 0x33, 0x0e, 0x73, 0x02,
+            0x33, 0x0e,
+        ];
+        let rv64i = ISAFeatures::parse("rv64i").unwrap();
+
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let err = disassemble_with_features(&context, builder, &data, &rv64i).unwrap_err();
+        assert_eq!(err.to_string(), "unknown instruction `0x0000100b` at `0x4`");
+
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let coverage = disassemble_with_recovery(&context, builder, &data, &rv64i).unwrap();
+        assert_eq!(
+            coverage,
+            Coverage {
+                instrs: 1,
+                instr_bytes: 4,
+                unknown_words: 4,
+                unknown_bytes: 12,
+            }
+        );
+        assert_eq!(
+            coverage.to_string(),
+            "decoded 1 instructions (4 bytes), 4 unknown words (12 bytes), 25.0% coverage"
+        );
+
+        let ops: Vec<_> = module.borrow().get_body().iter().collect();
+        let sizes: Vec<_> = ops
+            .iter()
+            .filter_map(|op| tir_core::utils::op_cast::<UnknownWordOp>(op.clone()))
+            .map(|word| (word.borrow().value(), word.borrow().size()))
+            .collect();
+        assert_eq!(
+            sizes,
+            [(0x100b, 4), (0x0001, 2), (0x02730e33, 4), (0x0e33, 2)]
+        );
+        let locations: Vec<_> = ops.iter().map(|op| op.borrow().get_location()).collect();
+        assert_eq!(locations, [0, 4, 8, 10, 14].map(Location::Address));
+
+        // Unknown words are emitted unchanged
+        let bytes = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+        let mut stream: Box<dyn BinaryStream> = Box::new(testing::VecStream(bytes.clone()));
+        let opts = testing::target_options(&["-march=rv64i"]);
+        for op in &ops {
+            assert!(matches!(encode_op(op, &opts, &mut stream), Some(Ok(()))));
+        }
+        assert_eq!(bytes.take(), data);
+    }
+
+    #[test]
+    fn test_memory_effects() {
+        // lw x5, 8(x6)
+        // sw x5, 8(x6)
+        // add x28, x6, x7
+        let instructions = [0x00832283_u32, 0x00532423, 0x00730e33];
+
+        let data: Vec<u8> = instructions.iter().flat_map(|i| i.to_le_bytes()).collect();
+
+        let context = Context::new();
+        context.add_dialect(crate::create_dialect());
+
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        disassemble(&context, builder, &data).expect("disassembly failed");
+
+        let effects: Vec<_> = module
+            .borrow()
+            .get_body()
+            .iter()
+            .map(|op| tir_core::get_memory_effects(&op))
+            .collect();
+        assert_eq!(
+            effects,
+            vec![
+                Some(vec![tir_core::MemoryEffect::Read]),
+                Some(vec![tir_core::MemoryEffect::Write]),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extension_features() {
+        let context = testing::context();
+        let rv64i = ISAFeatures::parse("rv64i").unwrap();
+        let rv64ia = ISAFeatures::parse("rv64ia").unwrap();
+
+        let input = ".text\nl:\n mul x5, x6, x7";
+        assert!(parse_asm(&context, input).is_ok());
+        let err = parse_asm_with_features(&context, input, &rv64i).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Instruction 'mul' requires the M extension, that is not enabled"
+        );
+
+        let input = ".text\nl:\n amoadd.w.aq x5, x7, (x6)";
+        assert!(parse_asm_with_features(&context, input, &rv64ia).is_ok());
+        assert!(parse_asm_with_features(&context, input, &rv64i).is_err());
+
+        let rv64if = ISAFeatures::parse("rv64if").unwrap();
+        let input = ".text\nl:\n fadd.s ft0, ft1, ft2";
+        assert!(parse_asm_with_features(&context, input, &rv64if).is_ok());
+        let err = parse_asm_with_features(&context, input, &rv64i).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Instruction 'fadd.s' requires the F extension, that is not enabled"
+        );
+        let input = ".text\nl:\n fld fa0, 16(a1)";
+        assert!(parse_asm_with_features(&context, input, &rv64if).is_err());
+
+        // mul x28, x6, x7
+        let data = 0x02730e33_u32.to_le_bytes();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        assert!(disassemble_with_features(&context, builder.clone(), &data, &rv64ia).is_err());
+        assert!(
+            disassemble_with_features(&context, builder, &data, &ISAFeatures::default()).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_compressed_features() {
+        let context = testing::context();
+        let rv64ic = ISAFeatures::parse("rv64ic").unwrap();
+
+        let input = ".text\nl:\n c.fld f10, 8(x11)";
+        assert!(parse_asm_with_features(&context, input, &ISAFeatures::default()).is_ok());
+        let err = parse_asm_with_features(&context, input, &rv64ic).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Instruction 'c.fld' requires the Zcd extension, that is not enabled"
+        );
+        let input = ".text\nl:\n c.add x10, x11";
+        assert!(parse_asm_with_features(&context, input, &rv64ic).is_ok());
+        let err = parse_asm_with_features(&context, input, &ISAFeatures::parse("rv64i").unwrap())
+            .unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Instruction 'c.add' requires the C extension, that is not enabled"
+        );
+
+        // c.add a0, a1 followed by add a0, a1, a2
+        let data = [0x2e, 0x95, 0x33, 0x85, 0xc5, 0x00];
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let rv64i = ISAFeatures::parse("rv64i").unwrap();
+        assert!(disassemble_with_features(&context, builder.clone(), &data, &rv64i).is_err());
+        assert!(disassemble_with_features(&context, builder, &data, &rv64ic).is_ok());
+
+        let ops: Vec<_> = module.borrow().get_body().iter().collect();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].borrow().type_id(), TypeId::of::<CAddOp>());
+        assert_eq!(ops[1].borrow().type_id(), TypeId::of::<AddOp>());
+        assert_eq!(ops[1].borrow().get_location(), Location::Address(2));
+
+        // The stream ends in the middle of a 32-bit instruction
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        assert!(matches!(
+            disassemble(&context, builder, &data[..4]),
+            Err(DisassemblerError::UnexpectedEndOfStream(4, 2))
+        ));
+    }
+
+    #[test]
+    fn test_encode_disabled_extension() {
+        let context = testing::context();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let op: OpRef = CsrReadSetOp::builder(&context)
+            .rd(GPR::X5.into())
+            .rs1(GPR::X0.into())
+            .csr(0xc00_i64.into())
+            .build();
+        builder.insert_generic(&op);
+
+        let mut stream: Box<dyn BinaryStream> = Box::new(testing::VecStream::default());
+        let opts = testing::target_options(&["-march=rv64im_zicsr"]);
+        assert!(matches!(encode_op(&op, &opts, &mut stream), Some(Ok(()))));
+        let opts = testing::target_options(&["-march=rv64im"]);
+        assert!(matches!(encode_op(&op, &opts, &mut stream), Some(Err(_))));
+        let opts = testing::target_options(&["-march=rv32i"]);
+        assert!(matches!(encode_op(&op, &opts, &mut stream), Some(Err(_))));
+        assert_eq!(get_op_extension(&op), Some(Extension::Zicsr));
     }
 }
@@ -0,0 +1,448 @@
+//! Disassembly of ELF files into sections of the RISC-V dialect
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use tir_backend::elf::{ElfFile, Relocation, SymbolBinding, SymbolKind, EM_RISCV};
+use tir_backend::target::{DataOp, SectionOp, SymbolBindingOp, SymbolSizeOp, SymbolTypeOp, ZeroOp};
+use tir_backend::DisassemblerError;
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::op_cast;
+use tir_core::{Attr, Block, ContextRef, Location, OpBuilder, OpRef, Region, RwCell};
+
+use crate::{
+    address_label, decode_stream, parse_address_label, reg_num, AuipcOp, Coverage, ISAFeatures,
+    JumpAndLinkRegOp, PseudoCallOp, PseudoTailOp, R_RISCV_BRANCH, R_RISCV_CALL, R_RISCV_CALL_PLT,
+    R_RISCV_JAL, R_RISCV_RVC_BRANCH, R_RISCV_RVC_JUMP,
+};
+
+/// Attribute of the operations, that jump to a label
+pub(crate) const TARGET_ATTR: &str = "target";
+
+/// Instructions of an executable section with their addresses
+type Code = Vec<(u64, OpRef)>;
+
+/// Labels of a section by their address. Several symbols may share an address.
+type Labels = BTreeMap<u64, Vec<String>>;
+
+/// Disassemble an ELF file of the enabled extensions into a module.
+///
+/// Every allocated section becomes a `target.section`. Executable sections are
+/// decoded and split into blocks at symbols and at the targets of branches and jumps,
+/// other sections keep their contents as data. Each operation is located at its
+/// original address. Branches, jumps and calls of object files refer to the symbols
+/// of their relocations.
+pub fn disassemble_elf(
+    context: &ContextRef,
+    data: &[u8],
+    features: &ISAFeatures,
+) -> Result<Arc<RwCell<ModuleOp>>, DisassemblerError> {
+    load_elf(context, data, features, false).map(|(module, _)| module)
+}
+
+/// Disassemble an ELF file like [`disassemble_elf`], keeping the words of executable
+/// sections, that can not be decoded, as `riscv.unknown_word` operations. Returns the
+/// module along with the coverage of all executable sections.
+pub fn disassemble_elf_with_recovery(
+    context: &ContextRef,
+    data: &[u8],
+    features: &ISAFeatures,
+) -> Result<(Arc<RwCell<ModuleOp>>, Coverage), DisassemblerError> {
+    load_elf(context, data, features, true)
+}
+
+fn load_elf(
+    context: &ContextRef,
+    data: &[u8],
+    features: &ISAFeatures,
+    recover: bool,
+) -> Result<(Arc<RwCell<ModuleOp>>, Coverage), DisassemblerError> {
+    let elf = ElfFile::read(data)?;
+    if elf.machine != EM_RISCV {
+        return Err(DisassemblerError::UnsupportedMachine(elf.machine));
+    }
+
+    // Decode all executable sections first, as branches may target other sections
+    let mut code: HashMap<usize, Code> = HashMap::new();
+    let mut coverage = Coverage::default();
+    for (idx, section) in elf.sections.iter().enumerate() {
+        if section.is_alloc() && section.is_executable() && !section.is_nobits() {
+            let (mut instrs, section_coverage) =
+                decode_stream(context, &section.data, section.address, features, recover)?;
+            if elf.is_relocatable() {
+                apply_relocations(context, &elf, idx, &mut instrs);
+            }
+            code.insert(idx, instrs);
+            coverage += section_coverage;
+        }
+    }
+
+    let mut labels: Vec<Labels> = vec![Labels::new(); elf.sections.len()];
+    for symbol in &elf.symbols {
+        let Some(idx) = symbol.section else {
+            continue;
+        };
+        let section = &elf.sections[idx];
+        let in_bounds = section.contains(symbol.value) || symbol.value == section.address;
+        if !symbol.name.is_empty() && section.is_alloc() && in_bounds {
+            labels[idx]
+                .entry(symbol.value)
+                .or_default()
+                .push(symbol.name.clone());
+        }
+    }
+
+    // Targets, that are not at a symbol, keep the labels of the disassembler. Others
+    // are renamed to the first symbol at the address.
+    for (idx, instrs) in &code {
+        for (_, op) in instrs {
+            let Some(target) = get_target(op).as_deref().and_then(parse_address_label) else {
+                continue;
+            };
+            let Some(target_idx) = find_instr_section(&elf, &code, *idx, target) else {
+                continue;
+            };
+            let names = labels[target_idx]
+                .entry(target)
+                .or_insert_with(|| vec![address_label(target)]);
+            if names[0] != address_label(target) {
+                let attrs =
+                    HashMap::from([(TARGET_ATTR.to_string(), Attr::String(names[0].clone()))]);
+                op.borrow_mut().add_attrs(&attrs);
+            }
+        }
+    }
+
+    let module = ModuleOp::builder(context).build();
+    let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+    for (idx, section) in elf.sections.iter().enumerate() {
+        if !section.is_alloc() {
+            continue;
+        }
+
+        builder.set_location(Location::Address(section.address));
+        let body = Region::empty(context);
+        let section_op = SectionOp::builder(context)
+            .name(section.name.clone())
+            .body(body.clone())
+            .build();
+        builder.insert(&section_op);
+
+        let mut section_labels = std::mem::take(&mut labels[idx]);
+        section_labels
+            .entry(section.address)
+            .or_insert_with(|| vec![address_label(section.address)]);
+        let starts: Vec<u64> = section_labels.keys().copied().collect();
+        for (block_idx, (address, names)) in section_labels.into_iter().enumerate() {
+            let end = starts
+                .get(block_idx + 1)
+                .copied()
+                .unwrap_or(section.address + section.size);
+
+            let mut block = None;
+            for name in &names {
+                let new_block = Block::with_arguments::<&str>(name, &body, &[], &[]);
+                body.add_block(new_block.clone());
+                block = Some(new_block);
+            }
+            let block_builder = OpBuilder::new(context.clone(), block.unwrap());
+            block_builder.set_location(Location::Address(address));
+            add_symbol_directives(&block_builder, &elf, idx, address);
+
+            if let Some(instrs) = code.get(&idx) {
+                for (address, op) in instrs.iter().filter(|(a, _)| *a >= address && *a < end) {
+                    block_builder.set_location(Location::Address(*address));
+                    block_builder.insert_generic(op);
+                }
+            } else if section.is_nobits() {
+                block_builder.insert(&ZeroOp::builder(context).size(end - address).build());
+            } else {
+                let start = (address - section.address) as usize;
+                let values = section.data[start..(end - section.address) as usize]
+                    .iter()
+                    .map(|byte| *byte as i64)
+                    .collect();
+                block_builder.insert(&DataOp::builder(context).size(1).values(values).build());
+            }
+        }
+    }
+
+    Ok((module, coverage))
+}
+
+/// Refer to the symbols of the relocations in the instructions, that jump to them.
+/// Such instructions of relocatable objects are encoded with a zero offset, so the
+/// decoded target would be the instruction itself.
+fn apply_relocations(context: &ContextRef, elf: &ElfFile, section: usize, instrs: &mut Code) {
+    let section_address = elf.sections[section].address;
+    for relocation in &elf.sections[section].relocations {
+        let address = section_address + relocation.offset;
+        let Ok(pos) = instrs.binary_search_by_key(&address, |(address, _)| *address) else {
+            continue;
+        };
+        let Some(label) = get_relocation_label(elf, relocation) else {
+            continue;
+        };
+        match relocation.kind {
+            R_RISCV_BRANCH | R_RISCV_JAL | R_RISCV_RVC_BRANCH | R_RISCV_RVC_JUMP => {
+                let op = &instrs[pos].1;
+                if get_target(op).is_some() {
+                    let attrs = HashMap::from([(TARGET_ATTR.to_string(), Attr::String(label))]);
+                    op.borrow_mut().add_attrs(&attrs);
+                }
+            }
+            R_RISCV_CALL | R_RISCV_CALL_PLT => {
+                if let Some(call) = fuse_call(context, &instrs[pos..], label) {
+                    instrs[pos].1 = call;
+                    instrs.remove(pos + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Label of the relocation target. Relocations against a symbol without an addend
+/// refer to the symbol, so that undefined symbols are kept. Others refer to the
+/// address of the target.
+fn get_relocation_label(elf: &ElfFile, relocation: &Relocation) -> Option<String> {
+    if relocation.symbol.is_empty() {
+        return None;
+    }
+    let symbol = elf
+        .symbols
+        .iter()
+        .find(|symbol| symbol.name == relocation.symbol);
+    let base = match symbol {
+        Some(symbol) if relocation.addend == 0 => return Some(symbol.name.clone()),
+        Some(symbol) => symbol.section.map(|_| symbol.value)?,
+        // Relocations against section symbols refer to the name of the section
+        None => {
+            elf.sections
+                .iter()
+                .find(|section| section.name == relocation.symbol)?
+                .address
+        }
+    };
+    Some(address_label(base.wrapping_add_signed(relocation.addend)))
+}
+
+/// `call` or `tail` of the `auipc` and `jalr` pair at the start of the instructions
+fn fuse_call(context: &ContextRef, instrs: &[(u64, OpRef)], target: String) -> Option<OpRef> {
+    let auipc = op_cast::<AuipcOp>(instrs.first()?.1.clone())?;
+    let jalr = op_cast::<JumpAndLinkRegOp>(instrs.get(1)?.1.clone())?;
+    let auipc = auipc.borrow();
+    let jalr = jalr.borrow();
+    if auipc.imm() != 0 || jalr.offset() != 0 {
+        return None;
+    }
+
+    let op: OpRef = match (
+        reg_num(auipc.get_rd())?,
+        reg_num(jalr.get_rd())?,
+        reg_num(jalr.get_rs1())?,
+    ) {
+        (1, 1, 1) => PseudoCallOp::builder(context).target(target).build(),
+        (6, 0, 6) => PseudoTailOp::builder(context).target(target).build(),
+        _ => return None,
+    };
+    Some(op)
+}
+
+pub(crate) fn get_target(op: &OpRef) -> Option<String> {
+    match op.borrow().get_attrs().get(TARGET_ATTR) {
+        Some(Attr::String(target)) => Some(target.clone()),
+        _ => None,
+    }
+}
+
+/// Executable section, that has an instruction at the address. The section of the
+/// jump is preferred, as sections of object files all start at zero.
+fn find_instr_section(
+    elf: &ElfFile,
+    code: &HashMap<usize, Code>,
+    preferred: usize,
+    address: u64,
+) -> Option<usize> {
+    let has_instr = |idx: &usize| {
+        code[idx]
+            .binary_search_by_key(&address, |(address, _)| *address)
+            .is_ok()
+    };
+    std::iter::once(preferred)
+        .chain((0..elf.sections.len()).filter(|idx| code.contains_key(idx)))
+        .find(has_instr)
+}
+
+/// Binding, type and size of the symbols at the address, that are not implied by
+/// the label
+fn add_symbol_directives(builder: &OpBuilder, elf: &ElfFile, section: usize, address: u64) {
+    let context = builder.get_context();
+    let symbols = elf
+        .symbols
+        .iter()
+        .filter(|symbol| symbol.section == Some(section) && symbol.value == address)
+        .filter(|symbol| !symbol.name.is_empty());
+    for symbol in symbols {
+        if symbol.binding != SymbolBinding::Local {
+            let op = SymbolBindingOp::builder(&context)
+                .name(symbol.name.clone())
+                .binding(symbol.binding.get_name().to_string())
+                .build();
+            builder.insert(&op);
+        }
+        if matches!(symbol.kind, SymbolKind::Func | SymbolKind::Object) {
+            let op = SymbolTypeOp::builder(&context)
+                .name(symbol.name.clone())
+                .kind(symbol.kind.get_name().to_string())
+                .build();
+            builder.insert(&op);
+        }
+        if symbol.size != 0 {
+            let op = SymbolSizeOp::builder(&context)
+                .name(symbol.name.clone())
+                .build();
+            op.borrow_mut().set_value(Some(symbol.size as i64));
+            builder.insert(&op);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::{emit_elf_object, parse_asm};
+    use tir_core::utils::op_cast;
+
+    fn object(input: &str) -> Vec<u8> {
+        let context = context();
+        let module = parse_asm(&context, input).unwrap();
+        emit_elf_object(&module, "rv64imac").unwrap()
+    }
+
+    #[test]
+    fn test_disassemble_elf() {
+        let data = object(
+            ".text
+main:
+    beq a0, a1, .Lloop
+    addi a0, a0, 1
+.Lloop:
+    jal ra, main
+    ret
+.rodata
+msg:
+    .byte 1, 2
+end:
+    .byte 3",
+        );
+
+        let context = context();
+        let features = ISAFeatures::parse("rv64imac").unwrap();
+        let module = disassemble_elf(&context, &data, &features).unwrap();
+        let sections: Vec<_> = module
+            .borrow()
+            .get_body()
+            .iter()
+            .map(|op| op_cast::<SectionOp>(op).unwrap())
+            .collect();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].borrow().name(), ".text");
+
+        let text = sections[0].borrow().get_body_region();
+        let blocks: Vec<_> = text.iter().map(|block| block.get_name()).collect();
+        assert_eq!(blocks, ["main", "L0x8"]);
+
+        // Jumps to symbols refer to the symbol
+        let ops: Vec<_> = text.iter().flat_map(|block| block.iter()).collect();
+        assert_eq!(get_target(&ops[0]).unwrap(), "L0x8");
+        assert_eq!(get_target(&ops[2]).unwrap(), "main");
+        let locations: Vec<_> = ops.iter().map(|op| op.borrow().get_location()).collect();
+        assert_eq!(locations, [0, 4, 8, 12].map(Location::Address),);
+
+        let rodata = sections[1].borrow().get_body_region();
+        let blocks: Vec<_> = rodata.iter().map(|block| block.get_name()).collect();
+        assert_eq!(blocks, ["msg", "end"]);
+        let data = op_cast::<DataOp>(rodata.first().unwrap().first().unwrap()).unwrap();
+        assert_eq!(data.borrow().values(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_disassemble_elf_relocations() {
+        let data = object(".text\nmain:\n    jal ra, puts\n    call puts\n    bnez a0, main");
+
+        let context = context();
+        let features = ISAFeatures::parse("rv64imac").unwrap();
+        let module = disassemble_elf(&context, &data, &features).unwrap();
+        let text = op_cast::<SectionOp>(module.borrow().get_body().first().unwrap()).unwrap();
+        let blocks: Vec<_> = text
+            .borrow()
+            .get_body_region()
+            .iter()
+            .map(|block| block.get_name())
+            .collect();
+        assert_eq!(blocks, ["main"]);
+
+        let ops: Vec<_> = text
+            .borrow()
+            .get_body_region()
+            .first()
+            .unwrap()
+            .iter()
+            .collect();
+        assert_eq!(ops.len(), 3);
+        assert_eq!(get_target(&ops[0]).unwrap(), "puts");
+        assert!(op_cast::<PseudoCallOp>(ops[1].clone()).is_some());
+        assert_eq!(get_target(&ops[1]).unwrap(), "puts");
+        assert_eq!(get_target(&ops[2]).unwrap(), "main");
+    }
+
+    #[test]
+    fn test_disassemble_elf_recovery() {
+        let data = object(".text\nmain:\n    mul a0, a0, a1\n    c.jr ra\n.data\n    .word 0");
+
+        let context = context();
+        let features = ISAFeatures::parse("rv64i").unwrap();
+        assert!(matches!(
+            disassemble_elf(&context, &data, &features),
+            Err(DisassemblerError::UnknownInstruction(0, 0x02b50533, 4))
+        ));
+
+        let (module, coverage) = disassemble_elf_with_recovery(&context, &data, &features).unwrap();
+        assert_eq!(coverage.unknown_words, 2);
+        assert_eq!(coverage.unknown_bytes, 6);
+        assert_eq!(coverage.instrs, 0);
+
+        let text = op_cast::<SectionOp>(module.borrow().get_body().first().unwrap()).unwrap();
+        let ops: Vec<_> = text
+            .borrow()
+            .get_body_region()
+            .first()
+            .unwrap()
+            .iter()
+            .collect();
+        assert!(ops
+            .iter()
+            .all(|op| op_cast::<crate::UnknownWordOp>(op.clone()).is_some()));
+        assert_eq!(ops[1].borrow().get_location(), Location::Address(4));
+    }
+
+    #[test]
+    fn test_disassemble_elf_errors() {
+        let context = context();
+        let features = ISAFeatures::default();
+        let mut data = object(".text\n    ret");
+
+        // EM_X86_64
+        data[18] = 62;
+        assert!(matches!(
+            disassemble_elf(&context, &data, &features),
+            Err(DisassemblerError::UnsupportedMachine(62))
+        ));
+        assert!(matches!(
+            disassemble_elf(&context, &data[..16], &features),
+            Err(DisassemblerError::Elf(_))
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+//! Relocatable object files of the RISC-V dialect
+
+use std::sync::Arc;
+
+use tir_backend::elf::EM_RISCV;
+use tir_backend::target::{emit_object, ObjectError, ObjectTarget, SectionWriter};
+use tir_backend::{Endianness, TargetOptions};
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::op_dyn_cast;
+use tir_core::{OpRef, RwCell};
+
+use crate::{
+    get_label_offset, get_reloc, Extension, ISAFeatures, LabelRef, LabelReloc, MARCH_FLAG,
+    R_RISCV_32, R_RISCV_64, R_RISCV_BRANCH, R_RISCV_CALL_PLT, R_RISCV_JAL, R_RISCV_PCREL_HI20,
+    R_RISCV_PCREL_LO12_I,
+};
+
+const EF_RISCV_RVC: u32 = 0x1;
+const EF_RISCV_FLOAT_ABI_SINGLE: u32 = 0x2;
+const EF_RISCV_FLOAT_ABI_DOUBLE: u32 = 0x4;
+
+/// Operations, that store a register to memory, take an S-type immediate
+const STORE_OPS: &[&str] = &["sb", "sh", "sw", "sd", "fsw", "fsd"];
+
+struct RVObjectTarget {
+    target_opts: TargetOptions,
+    features: ISAFeatures,
+}
+
+impl RVObjectTarget {
+    /// Encode an instruction, that refers to a label of another section, with a zero
+    /// offset and a relocation of the label
+    fn encode_label_reloc(
+        &self,
+        op: &OpRef,
+        label_ref: &dyn LabelRef,
+        writer: &mut SectionWriter,
+    ) -> Result<(), ObjectError> {
+        let offset = writer.get_offset();
+        let error = writer.encode_error(&*op.borrow());
+        label_ref
+            .encode_with_offset(Some(0), &self.target_opts, writer.get_stream())
+            .map_err(|_| error)?;
+
+        let label = label_ref.get_label();
+        match label_ref.get_label_reloc() {
+            LabelReloc::Branch => writer.add_relocation(offset, R_RISCV_BRANCH, label, 0),
+            LabelReloc::Jal => writer.add_relocation(offset, R_RISCV_JAL, label, 0),
+            LabelReloc::Call => writer.add_relocation(offset, R_RISCV_CALL_PLT, label, 0),
+            LabelReloc::Pcrel => {
+                let hi = writer.add_temp_label_at("pcrel_hi", offset);
+                writer.add_relocation(offset, R_RISCV_PCREL_HI20, label, 0);
+                writer.add_relocation(offset + 4, R_RISCV_PCREL_LO12_I, &hi, 0);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ObjectTarget for RVObjectTarget {
+    fn get_target_options(&self) -> &TargetOptions {
+        &self.target_opts
+    }
+
+    fn get_machine(&self) -> u16 {
+        EM_RISCV
+    }
+
+    /// Compressed instructions and the floating-point ABI, that the extensions imply
+    fn get_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.features.has(Extension::C) {
+            flags |= EF_RISCV_RVC;
+        }
+        if self.features.has(Extension::D) {
+            flags |= EF_RISCV_FLOAT_ABI_DOUBLE;
+        } else if self.features.has(Extension::F) {
+            flags |= EF_RISCV_FLOAT_ABI_SINGLE;
+        }
+        flags
+    }
+
+    fn get_code_align(&self) -> u64 {
+        if self.features.has(Extension::C) {
+            2
+        } else {
+            4
+        }
+    }
+
+    fn encode_instr(
+        &self,
+        op: &OpRef,
+        writer: &mut SectionWriter,
+    ) -> Option<Result<(), ObjectError>> {
+        if let Some(label_ref) = op_dyn_cast::<dyn LabelRef>(op.clone()) {
+            let label_ref = label_ref.borrow();
+            if get_label_offset(&*op.borrow(), label_ref.get_label()).is_none() {
+                return Some(self.encode_label_reloc(op, &*label_ref, writer));
+            }
+        }
+
+        let offset = writer.get_offset();
+        let error = writer.encode_error(&*op.borrow());
+        let result = crate::encode_op(op, &self.target_opts, writer.get_stream())?;
+        if result.is_err() {
+            return Some(Err(error));
+        }
+
+        if let Some(reloc) = get_reloc(&*op.borrow()) {
+            let is_store = STORE_OPS.contains(&op.borrow().get_operation_name());
+            let kind = reloc.kind.get_elf_type(is_store);
+            writer.add_relocation(offset, kind, &reloc.symbol, reloc.addend);
+        }
+        Some(Ok(()))
+    }
+
+    fn get_data_reloc(&self, size: u8) -> Option<u32> {
+        match size {
+            4 => Some(R_RISCV_32),
+            8 => Some(R_RISCV_64),
+            _ => None,
+        }
+    }
+
+    fn get_code_padding(&self, size: u64) -> Vec<u8> {
+        code_padding(size)
+    }
+}
+
+/// `nop`, preceded by `c.nop` to reach a 4-byte boundary
+pub(crate) fn code_padding(size: u64) -> Vec<u8> {
+    let mut padding = vec![];
+    if size % 4 == 2 {
+        padding.extend_from_slice(&0x0001_u16.to_le_bytes());
+    }
+    for _ in 0..size / 4 {
+        padding.extend_from_slice(&0x00000013_u32.to_le_bytes());
+    }
+    padding
+}
+
+/// Lay out the sections of the module into an ELF64 relocatable object file for the
+/// given extension set
+pub fn emit_elf_object(
+    module: &Arc<RwCell<ModuleOp>>,
+    isa: &str,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let target = RVObjectTarget {
+        target_opts: TargetOptions {
+            endiannes: Endianness::Little,
+            word_size: 8,
+            flags: vec![format!("{}{}", MARCH_FLAG, isa)],
+        },
+        features: ISAFeatures::parse(isa)?,
+    };
+    let object = emit_object(module, &target)?;
+    Ok(object.write())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+    use crate::{R_RISCV_HI20, R_RISCV_LO12_S, R_RISCV_PCREL_LO12_I};
+    use tir_backend::elf::{SymbolBinding, SymbolKind};
+
+    fn object(input: &str) -> tir_backend::elf::ObjectFile {
+        let context = context();
+        let module = parse_asm(&context, input).unwrap();
+        let target = RVObjectTarget {
+            target_opts: crate::ops::testing::target_options(&[]),
+            features: ISAFeatures::default(),
+        };
+        emit_object(&module, &target).unwrap()
+    }
+
+    #[test]
+    fn test_object_relocations() {
+        let object = object(
+            ".text
+    .globl main
+    .type main, @function
+main:
+    beq a0, a1, main
+    beq a0, a1, other
+    lui a0, %hi(msg)
+    sd a1, %lo(msg+8)(a0)
+    la a2, msg
+    call puts
+.Lend:
+    .size main, .Lend-main
+.section .text.other
+other:
+    ret
+.data
+msg:
+    .dword msg",
+        );
+
+        let relocs: Vec<_> = object.sections[0]
+            .relocations
+            .iter()
+            .map(|reloc| {
+                (
+                    reloc.offset,
+                    reloc.kind,
+                    reloc.symbol.as_str(),
+                    reloc.addend,
+                )
+            })
+            .collect();
+        assert_eq!(
+            relocs,
+            [
+                (4, R_RISCV_BRANCH, "other", 0),
+                (8, R_RISCV_HI20, "msg", 0),
+                (12, R_RISCV_LO12_S, "msg", 8),
+                (16, R_RISCV_PCREL_HI20, "msg", 0),
+                (20, R_RISCV_PCREL_LO12_I, ".Lpcrel_hi0", 0),
+                (24, R_RISCV_CALL_PLT, "puts", 0),
+            ]
+        );
+        assert_eq!(object.sections[2].relocations[0].kind, R_RISCV_64);
+
+        // Branches to labels of the same section are resolved
+        assert_eq!(
+            &object.sections[0].data[0..4],
+            &0x00b50063_u32.to_le_bytes()
+        );
+
+        let main = &object.symbols[0];
+        assert_eq!(main.name, "main");
+        assert_eq!(main.size, 32);
+        assert_eq!(main.binding, SymbolBinding::Global);
+        assert_eq!(main.kind, SymbolKind::Func);
+
+        // Temporary labels are only emitted, when relocations refer to them
+        let names: Vec<_> = object.symbols.iter().map(|symbol| &symbol.name).collect();
+        assert_eq!(names, ["main", ".Lpcrel_hi0", "other", "msg"]);
+    }
+
+    #[test]
+    fn test_object_padding() {
+        let object = object(
+            ".text
+    c.nop
+    .p2align 3
+    ret
+.data
+    .byte 1
+    .p2align 2
+    .word 2",
+        );
+
+        assert_eq!(
+            object.sections[0].data,
+            [0x01, 0x00, 0x01, 0x00, 0x13, 0x00, 0x00, 0x00, 0x67, 0x80, 0x00, 0x00]
+        );
+        assert_eq!(object.sections[0].align, 8);
+        assert_eq!(object.sections[1].data, [1, 0, 0, 0, 2, 0, 0, 0]);
+    }
+}
@@ -1,140 +1,64 @@
-use crate::utils::ITypeInstr;
-use crate::utils::RTypeInstr;
-use crate::{parse_gpr, DiagKind, GPR};
 use tir_backend::isema;
 use tir_backend::isema::WithISema;
-use tir_backend::parser::{asm_ident, comma, number};
-use tir_backend::BinaryEmittable;
+use tir_backend::parser::comma;
+use tir_backend::AsmPrintable;
 use tir_backend::ISAParser;
 use tir_backend::TokenStream;
-use tir_core::OpAssembly;
 use tir_core::*;
-use tir_macros::{lowercase, uppercase};
-use tir_macros::{Op, OpAssembly, OpValidator};
 
-use lpl::{combinators::separated_ignore, Diagnostic, ParseResult, ParseStream, Parser};
+use lpl::{ParseResult, ParseStream, Parser};
 
-use crate::DIALECT_NAME;
-
-const ALU_OPCODE: u8 = 0b110011;
-const ALU_IMM_OPCODE: u8 = 0b0010011;
+use super::asm::{imm_str, opcode, reg, reg_name, reloc_imm, LO_RELOCS};
+use super::defs::*;
+use super::reloc::{set_reloc, RelocKind};
 
 macro_rules! alu_op_base {
-    ($struct_name:ident, $op_name:literal, $funct3:literal, $funct7:literal) => {
-#[rustfmt::skip]
-        #[derive(Op, OpAssembly, OpValidator)]
-        #[operation(name = $op_name, dialect = riscv)]
-        pub struct $struct_name {
-            #[operand]
-            rd: tir_backend::Register::<GPR>,
-            #[operand]
-            rs1: tir_backend::Register::<GPR>,
-            #[operand]
-            rs2: tir_backend::Register::<GPR>,
-            r#impl: OpImpl,
-        }
-
+    ($struct_name:ident, $width:literal) => {
         impl $struct_name {
             pub fn get_op_width(&self) -> u8 {
-                64
+                $width
             }
-        }
 
-        impl BinaryEmittable for $struct_name {
-            fn encode(
-                &self,
-                _target_opts: &tir_backend::TargetOptions,
-                stream: &mut Box<dyn tir_backend::BinaryStream>,
-            ) -> tir_core::Result<()> {
-                let instr = RTypeInstr::builder()
-                    .opcode(ALU_OPCODE)
-                    .rd(self.get_rd().as_arch().encode())
-                    .funct3($funct3)
-                    .rs1(self.get_rs1().as_arch().encode())
-                    .rs2(self.get_rs2().as_arch().encode())
-                    .funct7($funct7)
-                    .build();
-                stream.write(&instr.to_bytes());
-                Ok(())
-            }
-        }
-
-        impl ISAParser for $struct_name {
-            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
-                let asm_ctx = input.get_extra().unwrap().clone();
-
-                let opcode = asm_ident().try_map(|t, s| match t {
-                    lowercase!($op_name) | uppercase!($op_name) => Ok(()),
-                    _ => Err(Into::<Diagnostic>::into(DiagKind::UnknownOpcode(s))),
-                });
-                let reg = asm_ident().try_map(|r, s| {
-                    parse_gpr(r).ok_or(Into::<Diagnostic>::into(DiagKind::UnknownRegister(
-                        r.to_string(),
-                        s,
-                    )))
-                });
-
-                let (regs, ni): (Vec<GPR>, _) = opcode
-                    .and_then(separated_ignore(reg, comma()))
-                    .map(|(_, r)| r)
-                    .label($op_name)
-                    .parse(input)?;
-
-                let (rd, rs1, rs2) = (regs[0], regs[1], regs[2]);
-
-                let builder = asm_ctx.get_builder();
-                let context = builder.get_context();
-                let op = $struct_name::builder(&context)
-                    .rs1(rs1.into())
-                    .rs2(rs2.into())
-                    .rd(rd.into())
-                    .build();
-                builder.insert(&op);
-
-                Ok(((), ni))
+            /// Word operations sign-extend the result to the full register
+            pub fn get_sign_extend(&self) -> bool {
+                $width < 64
             }
         }
     };
 }
 
 macro_rules! alu_imm_op_base {
-    ($struct_name:ident, $op_name:literal, $funct3:literal) => {
-#[rustfmt::skip]
-        #[derive(Op, OpAssembly, OpValidator)]
-        #[operation(name = $op_name, dialect = riscv, known_attrs(imm: IntegerAttr))]
-        pub struct $struct_name {
-            #[operand]
-            rd: tir_backend::Register::<GPR>,
-            #[operand]
-            rs1: tir_backend::Register::<GPR>,
-            r#impl: OpImpl,
-        }
-
+    ($struct_name:ident, $op_name:literal, $width:literal, $shift:expr) => {
         impl $struct_name {
             pub fn get_op_width(&self) -> u8 {
-                64
+                $width
             }
-        }
 
-        impl BinaryEmittable for $struct_name {
-            fn encode(
-                &self,
-                _target_opts: &tir_backend::TargetOptions,
-                stream: &mut Box<dyn tir_backend::BinaryStream>,
-            ) -> tir_core::Result<()> {
-                let instr = ITypeInstr::builder()
-                    .opcode(ALU_IMM_OPCODE)
-                    .rd(self.get_rd().as_arch().encode())
-                    .funct3($funct3)
-                    .rs1(self.get_rs1().as_arch().encode())
-                    .imm(
-                        self.get_imm_attr()
-                            .try_into()
-                            .map_err(|_| tir_core::Error::Unknown)?,
-                    )
-                    .build();
-                stream.write(&instr.to_bytes());
-                Ok(())
+            /// Word operations sign-extend the result to the full register
+            pub fn get_sign_extend(&self) -> bool {
+                $width < 64
+            }
+
+            pub fn get_imm(&self) -> i16 {
+                self.imm() as i16
+            }
+
+            /// Relocations accepted in place of the immediate
+            fn imm_relocs() -> &'static [RelocKind] {
+                if $shift {
+                    &[]
+                } else {
+                    LO_RELOCS
+                }
+            }
+
+            /// Range of the immediate accepted by the instruction
+            fn imm_range() -> (i64, i64) {
+                if $shift {
+                    (0, $width - 1)
+                } else {
+                    (-2048, 2047)
+                }
             }
         }
 
@@ -142,82 +66,68 @@ macro_rules! alu_imm_op_base {
             fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
                 let asm_ctx = input.get_extra().unwrap().clone();
 
-                let opcode = asm_ident().try_map(|t, s| match t {
-                    lowercase!($op_name) | uppercase!($op_name) => Ok(()),
-                    _ => Err(Into::<Diagnostic>::into(DiagKind::UnknownOpcode(s))),
-                });
-                let reg = move || {
-                    asm_ident().try_map(|r, s| {
-                        parse_gpr(r).ok_or(Into::<Diagnostic>::into(DiagKind::UnknownRegister(
-                            r.to_string(),
-                            s,
-                        )))
-                    })
-                };
-
-                let imm = number().map(|num| num as i16);
-
-                let parser = opcode
+                let (min, max) = Self::imm_range();
+                let parser = opcode($op_name)
                     .and_then(reg())
                     .and_then(comma())
                     .and_then(reg())
                     .and_then(comma())
-                    .and_then(imm)
+                    .and_then(reloc_imm(min, max, Self::imm_relocs()))
                     .map(|(((((_, rd), _), rs1), _), imm_value)| (rd, rs1, imm_value))
                     .label($op_name);
-                let ((rd, rs1, imm_value), ni) = parser.parse(input)?;
+                let ((rd, rs1, (imm_value, reloc)), ni) = parser.parse(input)?;
 
                 let builder = asm_ctx.get_builder();
                 let context = builder.get_context();
                 let op = $struct_name::builder(&context)
                     .rs1(rs1.into())
-                    .imm(imm_value.into())
+                    .imm((imm_value as i16).into())
                     .rd(rd.into())
                     .build();
+                set_reloc(&mut *op.borrow_mut(), reloc);
                 builder.insert(&op);
 
                 Ok(((), ni))
             }
         }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts),
+                    imm_str(self, self.imm())
+                )
+            }
+        }
+    };
+}
+
+macro_rules! shift {
+    () => {
+        false
+    };
+    ($shift:literal) => {
+        $shift
     };
 }
 
 macro_rules! alu_ops {
-    // R-format ALU operations
-    ($($struct_name:ident => { name = $op_name:literal, funct7 = $funct7:literal, funct3 = $funct3:literal })*) => {
+    // R-format ALU operations. Encoders, parsers and decoders are generated from the
+    // instruction templates.
+    ($($struct_name:ident => { width = $width:literal })*) => {
         $(
-        alu_op_base!($struct_name, $op_name, $funct3, $funct7);
+        alu_op_base!($struct_name, $width);
         )*
-
-        pub fn disassemble_alu_instr(context: &ContextRef, stream: &[u8]) -> Option<OpRef> {
-            if stream.len() < 4 {
-                return None;
-            }
-
-            let instr = RTypeInstr::from_bytes(&stream[0..4].try_into().unwrap());
-            if instr.opcode() != ALU_OPCODE {
-                return None;
-            }
-
-            let rd = GPR::try_from(instr.rd() as usize).ok()?;
-            let rs1 = GPR::try_from(instr.rs1() as usize).ok()?;
-            let rs2 = GPR::try_from(instr.rs2() as usize).ok()?;
-
-            match (instr.funct3(), instr.funct7()) {
-                $(
-                ($funct3, $funct7) => {
-                    let op = $struct_name::builder(&context).rs1(rs1.into()).rs2(rs2.into()).rd(rd.into()).build();
-                    Some(op)
-                },
-                )*
-                _ => None,
-            }
-        }
     };
 
-    ($($struct_name:ident => { name = $op_name:literal, funct3 = $funct3:literal })*) => {
+    // I-format ALU operations. Shifts take the unsigned shift amount in place of the
+    // immediate.
+    ($($struct_name:ident => { name = $op_name:literal, width = $width:literal $(, shift = $shift:literal)? })*) => {
         $(
-        alu_imm_op_base!($struct_name, $op_name, $funct3);
+        alu_imm_op_base!($struct_name, $op_name, $width, shift!($($shift)?));
         )*
     };
 }
@@ -225,26 +135,51 @@ macro_rules! alu_ops {
 // FIXME: all popular CPUs (x86, arm, risc-v) use little-endian. What happens if this code is
 // compiled on a big-endian host?
 alu_ops! {
-    AddOp => { name = "add", funct7 = 0b0000000, funct3 = 0b000 }
-    SubOp => { name = "sub", funct7 = 0b0100000, funct3 = 0b000 }
-    SllOp => { name = "sll", funct7 = 0b0000000, funct3 = 0b001 }
-    SltOp => { name = "slt", funct7 = 0b0000000, funct3 = 0b010 }
-    SltuOp => { name = "sltu", funct7 = 0b0000000, funct3 = 0b011 }
-    SrlOp => { name = "srl", funct7 = 0b0000000, funct3 = 0b101 }
-    SraOp => { name = "sra", funct7 = 0b0100000, funct3 = 0b101 }
-    OrOp => { name = "or", funct7 = 0b0000000, funct3 = 0b110 }
-    AndOp => { name = "and", funct7 = 0b0000000, funct3 = 0b111 }
+    AddOp => { width = 64 }
+    SubOp => { width = 64 }
+    SllOp => { width = 64 }
+    SltOp => { width = 64 }
+    SltuOp => { width = 64 }
+    XorOp => { width = 64 }
+    SrlOp => { width = 64 }
+    SraOp => { width = 64 }
+    OrOp => { width = 64 }
+    AndOp => { width = 64 }
+    AddWOp => { width = 32 }
+    SubWOp => { width = 32 }
+    SllWOp => { width = 32 }
+    SrlWOp => { width = 32 }
+    SraWOp => { width = 32 }
+    // M extension
+    MulOp => { width = 64 }
+    MulhOp => { width = 64 }
+    MulhsuOp => { width = 64 }
+    MulhuOp => { width = 64 }
+    DivOp => { width = 64 }
+    DivuOp => { width = 64 }
+    RemOp => { width = 64 }
+    RemuOp => { width = 64 }
+    MulWOp => { width = 32 }
+    DivWOp => { width = 32 }
+    DivuWOp => { width = 32 }
+    RemWOp => { width = 32 }
+    RemuWOp => { width = 32 }
 }
 
 alu_ops! {
-    AddImmOp => { name = "addi", funct3 = 0b000 }
-    SllImmOp => { name = "slli", funct3 = 0b001 }
-    SltImmOp => { name = "slti", funct3 = 0b010 }
-    SltuImmOp => { name = "sltiu", funct3 = 0b011 }
-    SrlImmOp => { name = "srli", funct3 = 0b101 }
-    SraImmOp => { name = "srai", funct3 = 0b101 }
-    OrImmOp => { name = "ori", funct3 = 0b110 }
-    AndImmOp => { name = "andi", funct3 = 0b111 }
+    AddImmOp => { name = "addi", width = 64 }
+    SllImmOp => { name = "slli", width = 64, shift = true }
+    SltImmOp => { name = "slti", width = 64 }
+    SltuImmOp => { name = "sltiu", width = 64 }
+    XorImmOp => { name = "xori", width = 64 }
+    SrlImmOp => { name = "srli", width = 64, shift = true }
+    SraImmOp => { name = "srai", width = 64, shift = true }
+    OrImmOp => { name = "ori", width = 64 }
+    AndImmOp => { name = "andi", width = 64 }
+    AddImmWOp => { name = "addiw", width = 32 }
+    SllImmWOp => { name = "slliw", width = 32, shift = true }
+    SrlImmWOp => { name = "srliw", width = 32, shift = true }
+    SraImmWOp => { name = "sraiw", width = 32, shift = true }
 }
 
 isema::def! {dialect = riscv, AddOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
@@ -254,90 +189,117 @@ isema::def! {dialect = riscv, OrOp => tir_backend::isema::OrOp{rd = get_rd, rs1
 isema::def! {dialect = riscv, SllOp => tir_backend::isema::SllOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
 isema::def! {dialect = riscv, SrlOp => tir_backend::isema::SrlOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
 isema::def! {dialect = riscv, SraOp => tir_backend::isema::SraOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, XorOp => tir_backend::isema::XorOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, SltOp => tir_backend::isema::SltOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, SltuOp => tir_backend::isema::SltuOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+
+isema::def! {dialect = riscv, AddImmOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, AndImmOp => tir_backend::isema::AndOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, OrImmOp => tir_backend::isema::OrOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, SllImmOp => tir_backend::isema::SllOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, SrlImmOp => tir_backend::isema::SrlOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, SraImmOp => tir_backend::isema::SraOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, XorImmOp => tir_backend::isema::XorOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, SltImmOp => tir_backend::isema::SltOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, SltuImmOp => tir_backend::isema::SltuOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+
+isema::def! {dialect = riscv, AddWOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, SubWOp => tir_backend::isema::SubOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, SllWOp => tir_backend::isema::SllOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, SrlWOp => tir_backend::isema::SrlOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, SraWOp => tir_backend::isema::SraOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+
+isema::def! {dialect = riscv, AddImmWOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, SllImmWOp => tir_backend::isema::SllOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, SrlImmWOp => tir_backend::isema::SrlOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, SraImmWOp => tir_backend::isema::SraOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width, sign_extend = get_sign_extend}}
+
+/// Signedness of the operands of multiplication and division
+macro_rules! muldiv_signedness {
+    ($($struct_name:ident => { rs1_signed = $rs1_signed:literal, rs2_signed = $rs2_signed:literal })*) => {
+        $(
+        impl $struct_name {
+            pub fn get_rs1_signed(&self) -> bool {
+                $rs1_signed
+            }
+
+            pub fn get_rs2_signed(&self) -> bool {
+                $rs2_signed
+            }
 
-isema::def! {dialect = riscv, AddImmOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, imm = get_imm_attr, width = get_op_width}}
-isema::def! {dialect = riscv, AndImmOp => tir_backend::isema::AndOp{rd = get_rd, rs1 = get_rs1, imm = get_imm_attr, width = get_op_width}}
-isema::def! {dialect = riscv, OrImmOp => tir_backend::isema::OrOp{rd = get_rd, rs1 = get_rs1, imm = get_imm_attr, width = get_op_width}}
-isema::def! {dialect = riscv, SllImmOp => tir_backend::isema::SllOp{rd = get_rd, rs1 = get_rs1, imm = get_imm_attr, width = get_op_width}}
-isema::def! {dialect = riscv, SrlImmOp => tir_backend::isema::SrlOp{rd = get_rd, rs1 = get_rs1, imm = get_imm_attr, width = get_op_width}}
-isema::def! {dialect = riscv, SraImmOp => tir_backend::isema::SraOp{rd = get_rd, rs1 = get_rs1, imm = get_imm_attr, width = get_op_width}}
+            /// Division and remainder treat both operands the same way
+            pub fn get_signed(&self) -> bool {
+                $rs1_signed && $rs2_signed
+            }
+        }
+        )*
+    };
+}
+
+muldiv_signedness! {
+    MulhOp => { rs1_signed = true, rs2_signed = true }
+    MulhsuOp => { rs1_signed = true, rs2_signed = false }
+    MulhuOp => { rs1_signed = false, rs2_signed = false }
+    DivOp => { rs1_signed = true, rs2_signed = true }
+    DivuOp => { rs1_signed = false, rs2_signed = false }
+    RemOp => { rs1_signed = true, rs2_signed = true }
+    RemuOp => { rs1_signed = false, rs2_signed = false }
+    DivWOp => { rs1_signed = true, rs2_signed = true }
+    DivuWOp => { rs1_signed = false, rs2_signed = false }
+    RemWOp => { rs1_signed = true, rs2_signed = true }
+    RemuWOp => { rs1_signed = false, rs2_signed = false }
+}
+
+isema::def! {dialect = riscv, MulOp => tir_backend::isema::MulOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, MulhOp => tir_backend::isema::MulhOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, rs1_signed = get_rs1_signed, rs2_signed = get_rs2_signed}}
+isema::def! {dialect = riscv, MulhsuOp => tir_backend::isema::MulhOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, rs1_signed = get_rs1_signed, rs2_signed = get_rs2_signed}}
+isema::def! {dialect = riscv, MulhuOp => tir_backend::isema::MulhOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, rs1_signed = get_rs1_signed, rs2_signed = get_rs2_signed}}
+isema::def! {dialect = riscv, DivOp => tir_backend::isema::DivOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed}}
+isema::def! {dialect = riscv, DivuOp => tir_backend::isema::DivOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed}}
+isema::def! {dialect = riscv, RemOp => tir_backend::isema::RemOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed}}
+isema::def! {dialect = riscv, RemuOp => tir_backend::isema::RemOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed}}
+
+isema::def! {dialect = riscv, MulWOp => tir_backend::isema::MulOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, DivWOp => tir_backend::isema::DivOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, DivuWOp => tir_backend::isema::DivOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, RemWOp => tir_backend::isema::RemOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, RemuWOp => tir_backend::isema::RemOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, signed = get_signed, sign_extend = get_sign_extend}}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::disassemble_alu_instr;
-    use std::any::TypeId;
+    use crate::decode;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+    use crate::GPR;
+    use tir_core::utils::op_cast;
 
     use builtin::ModuleOp;
     use isema::convert_to_isema;
     use tir_core::Context;
 
-    #[test]
-    fn test_alu_disassembler() {
-        // add x28, x6, x7
-        // sub x28, x6, x7
-        // sll x28, x6, x7
-        // slt x28, x6, x7
-        // sltu x28, x6, x7
-        // srl x28, x6, x7
-        // sra x28, x6, x7
-        // or x28, x6, x7
-        // and x28, x6, x7
-        let instructions = vec![
-            0x00730e33_u32,
-            0x40730e33,
-            0x00731e33,
-            0x00732e33,
-            0x00733e33,
-            0x00735e33,
-            0x40735e33,
-            0x00736e33,
-            0x00737e33,
-        ];
-
-        let context = Context::new();
-        context.add_dialect(crate::create_dialect());
-
-        let mut ops = vec![];
-
-        for instr in instructions {
-            if let Some(op) = disassemble_alu_instr(&context, &instr.to_le_bytes()) {
-                ops.push(op);
-            }
-        }
-
-        assert_eq!(ops.len(), 9);
-        // assert_eq!(ops[0].borrow().type_id(), TypeId::of::<AddOp>());
-        assert_eq!(ops[1].borrow().type_id(), TypeId::of::<SubOp>());
-        assert_eq!(ops[2].borrow().type_id(), TypeId::of::<SllOp>());
-        assert_eq!(ops[3].borrow().type_id(), TypeId::of::<SltOp>());
-        assert_eq!(ops[4].borrow().type_id(), TypeId::of::<SltuOp>());
-        assert_eq!(ops[5].borrow().type_id(), TypeId::of::<SrlOp>());
-        assert_eq!(ops[6].borrow().type_id(), TypeId::of::<SraOp>());
-        assert_eq!(ops[7].borrow().type_id(), TypeId::of::<OrOp>());
-        assert_eq!(ops[8].borrow().type_id(), TypeId::of::<AndOp>());
-    }
-
     #[test]
     fn test_alu_disassembler_negative() {
         // _boot:
         //   addi x28, x6, 1000
         //   jal _boot
         // some bogus instr
-        let instructions = vec![0x3e830e13_u32, 0xffdff0ef, 0x7fffff3];
+        let instructions = vec![0x3e830e13_u32, 0xffdff0ef, 0xffffffff];
 
         let context = Context::new();
         context.add_dialect(crate::create_dialect());
 
         let mut ops = vec![];
 
-        for instr in instructions {
-            if let Some(op) = disassemble_alu_instr(&context, &instr.to_le_bytes()) {
+        for (i, instr) in instructions.into_iter().enumerate() {
+            if let Some(op) = decode(&context, &instr.to_le_bytes(), i as u64 * 4) {
                 ops.push(op);
             }
         }
 
-        assert_eq!(ops.len(), 0);
+        assert_eq!(ops.len(), 2);
+        assert!(op_cast::<AddImmOp>(ops[0].clone()).is_some());
+        assert!(op_cast::<JumpAndLinkOp>(ops[1].clone()).is_some());
     }
 
     #[test]
@@ -360,4 +322,55 @@ mod tests {
 
         assert!(convert_to_isema(&module).is_ok());
     }
+
+    #[test]
+    fn test_alu_imm_disassembler_negative() {
+        let context = context();
+
+        // slli with funct6 of srai
+        assert!(decode(&context, &0x43f31e13_u32.to_le_bytes(), 0).is_none());
+        // slliw with a 6-bit shift amount
+        assert!(decode(&context, &0x02031e1b_u32.to_le_bytes(), 0).is_none());
+    }
+
+    #[test]
+    fn test_shift_amount_range() {
+        let context = context();
+        assert!(parse_asm(&context, ".text\nl:\n slli x5, x5, 63").is_ok());
+        assert!(parse_asm(&context, ".text\nl:\n slli x5, x5, 64").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n srai x5, x5, -1").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n sraiw x5, x5, 32").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n addiw x5, x5, 2048").is_err());
+    }
+
+    #[test]
+    fn test_muldiv_sema() {
+        let context = context();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+
+        let op = MulhsuOp::builder(&context)
+            .rd(GPR::X5.into())
+            .rs1(GPR::X6.into())
+            .rs2(GPR::X7.into())
+            .build();
+        builder.insert(&op);
+        let op = RemuWOp::builder(&context)
+            .rd(GPR::X5.into())
+            .rs1(GPR::X6.into())
+            .rs2(GPR::X7.into())
+            .build();
+        builder.insert(&op);
+
+        assert!(convert_to_isema(&module).is_ok());
+
+        let ops: Vec<_> = module.borrow().get_body().iter().collect();
+        let mulh = op_cast::<tir_backend::isema::MulhOp>(ops[0].clone()).unwrap();
+        assert!(mulh.borrow().rs1_signed());
+        assert!(!mulh.borrow().rs2_signed());
+        let rem = op_cast::<tir_backend::isema::RemOp>(ops[1].clone()).unwrap();
+        assert!(!rem.borrow().signed());
+        assert_eq!(rem.borrow().width(), 32);
+        assert_eq!(rem.borrow().sign_extend(), Some(true));
+    }
 }
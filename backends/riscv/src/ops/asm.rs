@@ -0,0 +1,203 @@
+use crate::{parse_fpr, parse_gpr, ABIRegName, DiagKind, FPR, GPR};
+use tir_backend::parser::{asm_ident, close_paren, expr, open_paren, AsmExpr};
+use tir_backend::{AsmParserContext, Register, TargetOptions, TokenStream};
+use tir_core::parser::Parsable;
+use tir_core::{Op, Printable};
+
+use lpl::{Diagnostic, Parser, Span};
+
+use super::reloc::{get_reloc, Reloc, RelocKind};
+
+/// Instruction mnemonic, case-insensitive
+pub(crate) fn opcode<'a>(name: &'static str) -> impl Parser<'a, TokenStream<'a>, ()> {
+    asm_ident().try_map(move |t, s| {
+        if t.eq_ignore_ascii_case(name) {
+            Ok(())
+        } else {
+            Err(Into::<Diagnostic>::into(DiagKind::UnknownOpcode(s)))
+        }
+    })
+}
+
+/// Register class, that can be referred to in assembly
+pub(crate) trait AsmRegister: Sized {
+    fn parse_name(name: &str) -> Option<Self>;
+
+    /// Architectural name of the register, followed by its ABI names
+    fn names(&self) -> &'static [&'static str];
+
+    /// Number of the register in the instruction encoding
+    fn reg_num(&self) -> u8;
+}
+
+impl AsmRegister for GPR {
+    fn parse_name(name: &str) -> Option<Self> {
+        parse_gpr(name)
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        self.get_names()
+    }
+
+    fn reg_num(&self) -> u8 {
+        self.encode()
+    }
+}
+
+impl AsmRegister for FPR {
+    fn parse_name(name: &str) -> Option<Self> {
+        parse_fpr(name)
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        self.get_names()
+    }
+
+    fn reg_num(&self) -> u8 {
+        self.encode()
+    }
+}
+
+/// Register of the given class, either by its architectural or ABI name
+pub(crate) fn register<'a, R: AsmRegister + 'a>() -> impl Parser<'a, TokenStream<'a>, R> {
+    asm_ident()
+        .try_map(|r, s| {
+            R::parse_name(r).ok_or(Into::<Diagnostic>::into(DiagKind::UnknownRegister(
+                r.to_string(),
+                s,
+            )))
+        })
+        .label("register")
+}
+
+/// Name of the register in assembly. The ABI name is used, if the `ABIRegName` flag is
+/// set in the target options.
+pub(crate) fn reg_name<R>(reg: Register<R>, target_opts: &TargetOptions) -> String
+where
+    R: AsmRegister + Into<Register<R>> + Printable + Parsable<R> + Copy,
+{
+    match reg {
+        Register::Architecture(reg) => {
+            let names = reg.names();
+            let abi = target_opts.has_flag::<ABIRegName>() && names.len() > 1;
+            names[abi as usize].to_string()
+        }
+        Register::Virtual(num) => format!("virt_reg<{}>", num),
+    }
+}
+
+/// Immediate operand in assembly, that is the relocation of the operation, if it has one
+pub(crate) fn imm_str(op: &dyn Op, imm: i64) -> String {
+    match get_reloc(op) {
+        Some(reloc) => reloc.to_string(),
+        None => imm.to_string(),
+    }
+}
+
+/// Address of a load or a store in assembly, `offset(rs1)`
+pub(crate) fn mem_addr_str(
+    op: &dyn Op,
+    offset: i64,
+    rs1: Register<GPR>,
+    target_opts: &TargetOptions,
+) -> String {
+    format!("{}({})", imm_str(op, offset), reg_name(rs1, target_opts))
+}
+
+/// General purpose register, either by its architectural or ABI name
+pub(crate) fn reg<'a>() -> impl Parser<'a, TokenStream<'a>, GPR> {
+    register::<GPR>()
+}
+
+/// Immediate value or a relocation of a symbol. Relocation operators of constants,
+/// like `%lo(0x1234)`, are evaluated.
+fn imm_operand(
+    expr: AsmExpr,
+    relocs: &[RelocKind],
+    asm_ctx: &AsmParserContext,
+    span: Span,
+) -> Result<(i64, Option<Reloc>), Diagnostic> {
+    let expected_constant =
+        || Into::<Diagnostic>::into(tir_backend::DiagKind::ExpectedConstant(span.clone()));
+
+    let AsmExpr::Modifier(modifier, operand) = expr else {
+        return expr
+            .evaluate(asm_ctx)
+            .map(|value| (value, None))
+            .ok_or_else(expected_constant);
+    };
+    let kind = RelocKind::from_modifier(&modifier).ok_or(Into::<Diagnostic>::into(
+        DiagKind::UnknownRelocation(modifier.clone(), span.clone()),
+    ))?;
+
+    if let Some(value) = operand.evaluate(asm_ctx) {
+        return kind
+            .apply(value)
+            .map(|value| (value, None))
+            .ok_or_else(expected_constant);
+    }
+    if !relocs.contains(&kind) {
+        return Err(DiagKind::InvalidRelocation(modifier, span).into());
+    }
+    let (symbol, addend) = operand
+        .symbol_offset(asm_ctx)
+        .ok_or(Into::<Diagnostic>::into(
+            tir_backend::DiagKind::ExpectedSymbol(span),
+        ))?;
+
+    Ok((
+        0,
+        Some(Reloc {
+            kind,
+            symbol,
+            addend,
+        }),
+    ))
+}
+
+/// Immediate value in the `[min, max]` range, or a relocation of one of the given kinds
+pub(crate) fn reloc_imm<'a>(
+    min: i64,
+    max: i64,
+    relocs: &'static [RelocKind],
+) -> impl Parser<'a, TokenStream<'a>, (i64, Option<Reloc>)> {
+    expr()
+        .spanned()
+        .map_with(move |(expr, span), asm_ctx| {
+            imm_operand(expr, relocs, asm_ctx.unwrap(), span.clone()).map(|imm| (imm, span))
+        })
+        .try_map(move |imm, _| {
+            let ((value, reloc), span) = imm?;
+            if (min..=max).contains(&value) {
+                Ok((value, reloc))
+            } else {
+                Err(Into::<Diagnostic>::into(DiagKind::ImmOutOfRange(
+                    value, min, max, span,
+                )))
+            }
+        })
+        .label("imm")
+}
+
+/// Immediate value in the `[min, max]` range
+pub(crate) fn imm<'a>(min: i64, max: i64) -> impl Parser<'a, TokenStream<'a>, i64> {
+    reloc_imm(min, max, &[]).map(|(value, _)| value)
+}
+
+/// Relocations of the lower 12 bits of addresses
+pub(crate) const LO_RELOCS: &[RelocKind] = &[RelocKind::Lo, RelocKind::PcrelLo];
+
+/// 12-bit offset, that may refer to the lower bits of a symbol address
+pub(crate) fn lo_imm<'a>() -> impl Parser<'a, TokenStream<'a>, (i64, Option<Reloc>)> {
+    reloc_imm(-2048, 2047, LO_RELOCS).label("offset")
+}
+
+/// Address of a load or a store, `offset(rs1)`
+pub(crate) fn mem_addr<'a>() -> impl Parser<'a, TokenStream<'a>, ((i64, Option<Reloc>), GPR)> {
+    lo_imm()
+        .and_then(open_paren())
+        .and_then(reg())
+        .and_then(close_paren())
+        .map(|(((offset, _), rs1), _)| (offset, rs1))
+        .label("address")
+}
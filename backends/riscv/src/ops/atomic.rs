@@ -0,0 +1,329 @@
+use crate::{DiagKind, GPR};
+use tir_backend::isema::WithISema;
+use tir_backend::parser::{asm_ident, close_paren, comma, open_paren};
+use tir_backend::AsmPrintable;
+use tir_backend::ISAParser;
+use tir_backend::TokenStream;
+use tir_core::*;
+
+use lpl::combinators::optional;
+use lpl::{Diagnostic, ParseResult, ParseStream, Parser};
+
+use super::asm::{imm, reg, reg_name};
+use super::defs::*;
+
+/// Ordering suffixes of atomic mnemonics and the (aq, rl) bits they set
+const ORDERING_SUFFIXES: [(&str, (bool, bool)); 4] = [
+    ("", (false, false)),
+    (".aq", (true, false)),
+    (".rl", (false, true)),
+    (".aqrl", (true, true)),
+];
+
+/// Atomic instruction mnemonic with an optional `.aq`, `.rl` or `.aqrl` suffix, returns the
+/// ordering bits
+fn atomic_opcode<'a>(name: &'static str) -> impl Parser<'a, TokenStream<'a>, (bool, bool)> {
+    asm_ident().try_map(move |t, s| {
+        let suffix = t
+            .get(..name.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(name))
+            .map(|_| &t[name.len()..]);
+        suffix
+            .and_then(|suffix| {
+                ORDERING_SUFFIXES
+                    .iter()
+                    .find(|(ordering, _)| ordering.eq_ignore_ascii_case(suffix))
+            })
+            .map(|(_, bits)| *bits)
+            .ok_or(Into::<Diagnostic>::into(DiagKind::UnknownOpcode(s)))
+    })
+}
+
+/// Atomic instruction mnemonic with the suffix of its ordering bits
+fn atomic_mnemonic(name: &str, aq: bool, rl: bool) -> String {
+    let (suffix, _) = ORDERING_SUFFIXES
+        .iter()
+        .find(|(_, bits)| *bits == (aq, rl))
+        .unwrap();
+    format!("{}{}", name, suffix)
+}
+
+/// Address of an atomic access, `(rs1)`. Atomics have no offset, but `0(rs1)` is accepted too.
+fn atomic_addr<'a>() -> impl Parser<'a, TokenStream<'a>, GPR> {
+    let zero_offset = imm(0, 0);
+    let base = open_paren()
+        .and_then(reg())
+        .and_then(close_paren())
+        .map(|((_, rs1), _)| rs1);
+    optional(zero_offset)
+        .and_then(base)
+        .map(|(_, rs1)| rs1)
+        .label("address")
+}
+
+macro_rules! lr_op_base {
+    ($struct_name:ident, $op_name:literal, $width:literal) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Read]
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::LoadReservedOp::builder(&context)
+                    .dst(self.get_rd().into())
+                    .base_addr(self.get_rs1().into())
+                    .width($width)
+                    .acquire(self.aq())
+                    .release(self.rl())
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = atomic_opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(atomic_addr())
+                    .map(|((((aq, rl), rd), _), rs1)| (aq, rl, rd, rs1))
+                    .label($op_name);
+                let ((aq, rl, rd, rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .aq(aq)
+                    .rl(rl)
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, ({})",
+                    atomic_mnemonic($op_name, self.aq(), self.rl()),
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts)
+                )
+            }
+        }
+    };
+}
+
+macro_rules! amo_op_base {
+    ($struct_name:ident, $op_name:literal, $width:literal, $effects:expr, $isema:expr) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                $effects
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let op = $isema(self, $width);
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = atomic_opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(atomic_addr())
+                    .map(|((((((aq, rl), rd), _), rs2), _), rs1)| (aq, rl, rd, rs1, rs2))
+                    .label($op_name);
+                let ((aq, rl, rd, rs1, rs2), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .rs2(rs2.into())
+                    .aq(aq)
+                    .rl(rl)
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, ({})",
+                    atomic_mnemonic($op_name, self.aq(), self.rl()),
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs2(), target_opts),
+                    reg_name(self.get_rs1(), target_opts)
+                )
+            }
+        }
+    };
+}
+
+macro_rules! atomic_ops {
+    (
+        lr { $($lr_struct_name:ident => { name = $lr_op_name:literal, width = $lr_width:literal })* }
+        sc { $($sc_struct_name:ident => { name = $sc_op_name:literal, width = $sc_width:literal })* }
+        amo { $($struct_name:ident => { name = $op_name:literal, width = $width:literal, operation = $operation:literal })* }
+    ) => {
+        $(
+        lr_op_base!($lr_struct_name, $lr_op_name, $lr_width);
+        )*
+        $(
+        amo_op_base!(
+            $sc_struct_name,
+            $sc_op_name,
+            $sc_width,
+            vec![MemoryEffect::Write],
+            |op: &$sc_struct_name, width| {
+                tir_backend::isema::StoreConditionalOp::builder(&op.get_context())
+                    .rd(op.get_rd().into())
+                    .src(op.get_rs2().into())
+                    .base_addr(op.get_rs1().into())
+                    .width(width)
+                    .acquire(op.aq())
+                    .release(op.rl())
+                    .build()
+            }
+        );
+        )*
+        $(
+        amo_op_base!(
+            $struct_name,
+            $op_name,
+            $width,
+            vec![MemoryEffect::Read, MemoryEffect::Write],
+            |op: &$struct_name, width| {
+                tir_backend::isema::AtomicRmwOp::builder(&op.get_context())
+                    .dst(op.get_rd().into())
+                    .src(op.get_rs2().into())
+                    .base_addr(op.get_rs1().into())
+                    .width(width)
+                    .operation($operation.to_string())
+                    .acquire(op.aq())
+                    .release(op.rl())
+                    .build()
+            }
+        );
+        )*
+    };
+}
+
+atomic_ops! {
+    lr {
+        LoadReservedWOp => { name = "lr.w", width = 32 }
+        LoadReservedDOp => { name = "lr.d", width = 64 }
+    }
+    sc {
+        StoreConditionalWOp => { name = "sc.w", width = 32 }
+        StoreConditionalDOp => { name = "sc.d", width = 64 }
+    }
+    amo {
+        AmoSwapWOp => { name = "amoswap.w", width = 32, operation = "swap" }
+        AmoAddWOp => { name = "amoadd.w", width = 32, operation = "add" }
+        AmoXorWOp => { name = "amoxor.w", width = 32, operation = "xor" }
+        AmoAndWOp => { name = "amoand.w", width = 32, operation = "and" }
+        AmoOrWOp => { name = "amoor.w", width = 32, operation = "or" }
+        AmoMinWOp => { name = "amomin.w", width = 32, operation = "min" }
+        AmoMaxWOp => { name = "amomax.w", width = 32, operation = "max" }
+        AmoMinuWOp => { name = "amominu.w", width = 32, operation = "minu" }
+        AmoMaxuWOp => { name = "amomaxu.w", width = 32, operation = "maxu" }
+        AmoSwapDOp => { name = "amoswap.d", width = 64, operation = "swap" }
+        AmoAddDOp => { name = "amoadd.d", width = 64, operation = "add" }
+        AmoXorDOp => { name = "amoxor.d", width = 64, operation = "xor" }
+        AmoAndDOp => { name = "amoand.d", width = 64, operation = "and" }
+        AmoOrDOp => { name = "amoor.d", width = 64, operation = "or" }
+        AmoMinDOp => { name = "amomin.d", width = 64, operation = "min" }
+        AmoMaxDOp => { name = "amomax.d", width = 64, operation = "max" }
+        AmoMinuDOp => { name = "amominu.d", width = 64, operation = "minu" }
+        AmoMaxuDOp => { name = "amomaxu.d", width = 64, operation = "maxu" }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+    use tir_core::builtin::ModuleOp;
+    use tir_core::utils::op_cast;
+
+    #[test]
+    fn test_disassemble_atomic_invalid() {
+        let context = context();
+
+        // lr.w with non-zero rs2
+        assert!(decode(&context, &0x107322af_u32.to_le_bytes(), 0).is_none());
+        // amoadd.b is not defined
+        assert!(decode(&context, &0x007302af_u32.to_le_bytes(), 0).is_none());
+    }
+
+    #[test]
+    fn test_parse_atomic_errors() {
+        let context = context();
+        assert!(parse_asm(&context, ".text\nl:\n lr.w.ar x5, (x6)").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n lr.w x5, 4(x6)").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n sc.w x5, (x6)").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n amoadd.w x5, x7, x6").is_err());
+    }
+
+    #[test]
+    fn test_atomic_sema() {
+        let context = context();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+
+        let op = StoreConditionalWOp::builder(&context)
+            .rd(GPR::X5.into())
+            .rs1(GPR::X6.into())
+            .rs2(GPR::X7.into())
+            .aq(false)
+            .rl(true)
+            .build();
+        builder.insert(&op);
+        let op = AmoMaxuDOp::builder(&context)
+            .rd(GPR::X5.into())
+            .rs1(GPR::X6.into())
+            .rs2(GPR::X7.into())
+            .aq(true)
+            .rl(false)
+            .build();
+        builder.insert(&op);
+
+        assert!(tir_backend::isema::convert_to_isema(&module).is_ok());
+
+        let ops: Vec<_> = module.borrow().get_body().iter().collect();
+        let sc = op_cast::<tir_backend::isema::StoreConditionalOp>(ops[0].clone()).unwrap();
+        assert_eq!(sc.borrow().src(), "x7");
+        assert_eq!(sc.borrow().width(), 32);
+        assert!(sc.borrow().release());
+        let amo = op_cast::<tir_backend::isema::AtomicRmwOp>(ops[1].clone()).unwrap();
+        assert_eq!(amo.borrow().operation(), "maxu");
+        assert_eq!(amo.borrow().width(), 64);
+        assert!(amo.borrow().acquire());
+    }
+}
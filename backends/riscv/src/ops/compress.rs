@@ -0,0 +1,330 @@
+//! Replacement of instructions with the equivalent 16-bit instructions of the C extension
+
+use std::sync::Arc;
+
+use tir_backend::Register;
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::op_cast;
+use tir_core::*;
+
+use crate::{Extension, ISAFeatures, FPR, GPR};
+
+use super::compressed::*;
+use super::control::get_label_offset;
+use super::defs::*;
+use super::reloc::get_reloc;
+
+/// Number of the architecture register. Virtual registers are never compressed.
+pub(super) fn reg_num(reg: Register<GPR>) -> Option<u8> {
+    match reg {
+        Register::Architecture(reg) => Some(reg.encode()),
+        Register::Virtual(_) => None,
+    }
+}
+
+fn fp_reg_num(reg: Register<FPR>) -> Option<u8> {
+    match reg {
+        Register::Architecture(reg) => Some(reg.encode()),
+        Register::Virtual(_) => None,
+    }
+}
+
+/// Compressed equivalent of `rd = rd op rs2`. Commutative operations also accept
+/// `rd = rs1 op rd`.
+macro_rules! compress_alu {
+    ($op:expr, $base:ident => $compressed:ident, commutative = $commutative:literal) => {
+        if let Some(op) = op_cast::<$base>($op.clone()) {
+            let op = op.borrow();
+            let (rd, rs1, rs2) = (op.get_rd(), op.get_rs1(), op.get_rs2());
+            let rs2 = if reg_num(rd)? == reg_num(rs1)? {
+                rs2
+            } else if $commutative && reg_num(rd)? == reg_num(rs2)? {
+                rs1
+            } else {
+                return None;
+            };
+            if !is_compressed_reg(reg_num(rd)?) || !is_compressed_reg(reg_num(rs2)?) {
+                return None;
+            }
+            return Some(
+                $compressed::builder(&op.get_context())
+                    .rd(rd.into())
+                    .rs2(rs2.into())
+                    .build(),
+            );
+        }
+    };
+}
+
+/// Compressed equivalent of `rd = rd op imm`
+macro_rules! compress_alu_imm {
+    ($op:expr, $base:ident => $compressed:ident, rd = $rd_valid:ident, imm = $imm:ident) => {
+        if let Some(op) = op_cast::<$base>($op.clone()) {
+            let op = op.borrow();
+            let rd = reg_num(op.get_rd())?;
+            if rd != reg_num(op.get_rs1())? || !$rd_valid(rd) || !$imm.is_valid(op.imm()) {
+                return None;
+            }
+            return Some(
+                $compressed::builder(&op.get_context())
+                    .rd(op.get_rd())
+                    .imm((op.imm() as i16).into())
+                    .build(),
+            );
+        }
+    };
+}
+
+/// Compressed equivalent of a load, either relative to sp or with both registers in x8-x15
+macro_rules! compress_load {
+    ($op:expr, $base:ident => $compressed:ident, $offset:ident, $sp_compressed:ident, $sp_offset:ident, rd = $rd_num:ident, sp_rd = $sp_rd_valid:expr) => {
+        if let Some(op) = op_cast::<$base>($op.clone()) {
+            let op = op.borrow();
+            let (rd, rs1, offset) = ($rd_num(op.get_rd())?, reg_num(op.get_rs1())?, op.offset());
+            let context = op.get_context();
+            if is_sp(rs1) && $sp_rd_valid(rd) && $sp_offset.is_valid(offset) {
+                return Some(
+                    $sp_compressed::builder(&context)
+                        .rd(op.get_rd())
+                        .offset((offset as i16).into())
+                        .build(),
+                );
+            }
+            if is_compressed_reg(rd) && is_compressed_reg(rs1) && $offset.is_valid(offset) {
+                return Some(
+                    $compressed::builder(&context)
+                        .rd(op.get_rd())
+                        .rs1(op.get_rs1())
+                        .offset((offset as i16).into())
+                        .build(),
+                );
+            }
+            return None;
+        }
+    };
+}
+
+/// Compressed equivalent of a store, either relative to sp or with both registers in x8-x15
+macro_rules! compress_store {
+    ($op:expr, $base:ident => $compressed:ident, $offset:ident, $sp_compressed:ident, $sp_offset:ident, rs2 = $rs2_num:ident) => {
+        if let Some(op) = op_cast::<$base>($op.clone()) {
+            let op = op.borrow();
+            let (rs1, rs2, offset) = (reg_num(op.get_rs1())?, $rs2_num(op.get_rs2())?, op.offset());
+            let context = op.get_context();
+            if is_sp(rs1) && $sp_offset.is_valid(offset) {
+                return Some(
+                    $sp_compressed::builder(&context)
+                        .rs2(op.get_rs2().into())
+                        .offset((offset as i16).into())
+                        .build(),
+                );
+            }
+            if is_compressed_reg(rs1) && is_compressed_reg(rs2) && $offset.is_valid(offset) {
+                return Some(
+                    $compressed::builder(&context)
+                        .rs1(op.get_rs1())
+                        .rs2(op.get_rs2().into())
+                        .offset((offset as i16).into())
+                        .build(),
+                );
+            }
+            return None;
+        }
+    };
+}
+
+/// Compressed equivalent of a branch, that compares rs1 in x8-x15 with zero
+macro_rules! compress_branch {
+    ($op:expr, $base:ident => $compressed:ident) => {
+        if let Some(op) = op_cast::<$base>($op.clone()) {
+            let op = op.borrow();
+            let rs1 = op.get_rs1();
+            if reg_num(op.get_rs2())? != 0 {
+                return None;
+            }
+            let offset = get_label_offset(&*op, op.target())?;
+            if !is_compressed_reg(reg_num(rs1)?) || !CB_OFFSET.is_valid(offset) {
+                return None;
+            }
+            return Some(
+                $compressed::builder(&op.get_context())
+                    .rs1(rs1.into())
+                    .target(op.target().to_string())
+                    .build(),
+            );
+        }
+    };
+}
+
+fn compress_add(op: &AddOp) -> Option<OpRef> {
+    let context = op.get_context();
+    let (rd, rs1, rs2) = (op.get_rd(), op.get_rs1(), op.get_rs2());
+    let (rd_num, rs1_num, rs2_num) = (reg_num(rd)?, reg_num(rs1)?, reg_num(rs2)?);
+    if rd_num == 0 {
+        return None;
+    }
+
+    let op: OpRef = match (rs1_num, rs2_num) {
+        (0, 0) => return None,
+        (0, _) => CMoveOp::builder(&context).rd(rd).rs2(rs2).build(),
+        (_, 0) => CMoveOp::builder(&context).rd(rd).rs2(rs1).build(),
+        _ if rd_num == rs1_num => CAddOp::builder(&context).rd(rd).rs2(rs2).build(),
+        _ if rd_num == rs2_num => CAddOp::builder(&context).rd(rd).rs2(rs1).build(),
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn compress_add_imm(op: &AddImmOp) -> Option<OpRef> {
+    let context = op.get_context();
+    let (rd, rs1, imm) = (op.get_rd(), op.get_rs1(), op.imm());
+    let (rd_num, rs1_num) = (reg_num(rd)?, reg_num(rs1)?);
+
+    let op: OpRef = if rd_num == 0 {
+        if rs1_num != 0 || imm != 0 {
+            return None;
+        }
+        CNopOp::builder(&context).build()
+    } else if rs1_num == 0 && CI_IMM.is_valid(imm) {
+        CLoadImmOp::builder(&context)
+            .rd(rd)
+            .imm((imm as i16).into())
+            .build()
+    } else if rs1_num != 0 && imm == 0 {
+        CMoveOp::builder(&context).rd(rd).rs2(rs1).build()
+    } else if rd_num == rs1_num && CI_NZ_IMM.is_valid(imm) {
+        CAddImmOp::builder(&context)
+            .rd(rd)
+            .imm((imm as i16).into())
+            .build()
+    } else if is_sp(rd_num) && is_sp(rs1_num) && ADDI16SP_IMM.is_valid(imm) {
+        CAddi16spOp::builder(&context)
+            .imm((imm as i16).into())
+            .build()
+    } else if is_compressed_reg(rd_num) && is_sp(rs1_num) && ADDI4SPN_IMM.is_valid(imm) {
+        CAddi4spnOp::builder(&context)
+            .rd(rd)
+            .imm((imm as i16).into())
+            .build()
+    } else {
+        return None;
+    };
+    Some(op)
+}
+
+fn compress_lui(op: &LuiOp) -> Option<OpRef> {
+    let rd = reg_num(op.get_rd())?;
+    if rd == 0 || is_sp(rd) || !CI_NZ_IMM.is_valid(CLuiOp::from_upper_imm(op.imm())) {
+        return None;
+    }
+    Some(
+        CLuiOp::builder(&op.get_context())
+            .rd(op.get_rd())
+            .imm((op.imm() as i32).into())
+            .build(),
+    )
+}
+
+fn compress_jal(op: &JumpAndLinkOp) -> Option<OpRef> {
+    let offset = get_label_offset(op, op.target())?;
+    if reg_num(op.get_rd())? != 0 || !CJ_OFFSET.is_valid(offset) {
+        return None;
+    }
+    Some(
+        CJumpOp::builder(&op.get_context())
+            .target(op.target().to_string())
+            .build(),
+    )
+}
+
+fn compress_jalr(op: &JumpAndLinkRegOp) -> Option<OpRef> {
+    let context = op.get_context();
+    if op.offset() != 0 || reg_num(op.get_rs1())? == 0 {
+        return None;
+    }
+
+    let op: OpRef = match reg_num(op.get_rd())? {
+        0 => CJumpRegOp::builder(&context).rs1(op.get_rs1()).build(),
+        1 => CJumpAndLinkRegOp::builder(&context)
+            .rs1(op.get_rs1())
+            .build(),
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// Compressed equivalent of the operation, if the operation has one and its extension
+/// is enabled
+fn compress_op(op: &OpRef, features: &ISAFeatures) -> Option<OpRef> {
+    // The compressed forms have no room for relocated immediates
+    if get_reloc(&*op.borrow()).is_some() {
+        return None;
+    }
+    if let Some(add) = op_cast::<AddOp>(op.clone()) {
+        return compress_add(&add.borrow());
+    }
+    if let Some(addi) = op_cast::<AddImmOp>(op.clone()) {
+        return compress_add_imm(&addi.borrow());
+    }
+    if let Some(lui) = op_cast::<LuiOp>(op.clone()) {
+        return compress_lui(&lui.borrow());
+    }
+    if let Some(jal) = op_cast::<JumpAndLinkOp>(op.clone()) {
+        return compress_jal(&jal.borrow());
+    }
+    if let Some(jalr) = op_cast::<JumpAndLinkRegOp>(op.clone()) {
+        return compress_jalr(&jalr.borrow());
+    }
+    if let Some(ebreak) = op_cast::<EbreakOp>(op.clone()) {
+        return Some(CEbreakOp::builder(&ebreak.borrow().get_context()).build());
+    }
+
+    compress_alu!(op, SubOp => CSubOp, commutative = false);
+    compress_alu!(op, XorOp => CXorOp, commutative = true);
+    compress_alu!(op, OrOp => COrOp, commutative = true);
+    compress_alu!(op, AndOp => CAndOp, commutative = true);
+    compress_alu!(op, SubWOp => CSubWOp, commutative = false);
+    compress_alu!(op, AddWOp => CAddWOp, commutative = true);
+
+    compress_alu_imm!(op, AddImmWOp => CAddImmWOp, rd = is_nonzero_reg, imm = CI_IMM);
+    compress_alu_imm!(op, SllImmOp => CSllImmOp, rd = is_nonzero_reg, imm = CI_SHAMT);
+    compress_alu_imm!(op, SrlImmOp => CSrlImmOp, rd = is_compressed_reg, imm = CI_SHAMT);
+    compress_alu_imm!(op, SraImmOp => CSraImmOp, rd = is_compressed_reg, imm = CI_SHAMT);
+    compress_alu_imm!(op, AndImmOp => CAndImmOp, rd = is_compressed_reg, imm = CI_IMM);
+
+    compress_load!(op, LoadWord => CLoadWord, CL_WORD_OFFSET, CLoadWordSp, LWSP_OFFSET, rd = reg_num, sp_rd = is_nonzero_reg);
+    compress_load!(op, LoadDouble => CLoadDouble, CL_DOUBLE_OFFSET, CLoadDoubleSp, LDSP_OFFSET, rd = reg_num, sp_rd = is_nonzero_reg);
+    compress_store!(op, StoreWord => CStoreWord, CL_WORD_OFFSET, CStoreWordSp, SWSP_OFFSET, rs2 = reg_num);
+    compress_store!(op, StoreDouble => CStoreDouble, CL_DOUBLE_OFFSET, CStoreDoubleSp, SDSP_OFFSET, rs2 = reg_num);
+    if features.has(Extension::Zcd) {
+        compress_load!(op, FLoadDouble => CFLoadDouble, CL_DOUBLE_OFFSET, CFLoadDoubleSp, LDSP_OFFSET, rd = fp_reg_num, sp_rd = |_| true);
+        compress_store!(op, FStoreDouble => CFStoreDouble, CL_DOUBLE_OFFSET, CFStoreDoubleSp, SDSP_OFFSET, rs2 = fp_reg_num);
+    }
+
+    compress_branch!(op, BranchEqOp => CBranchEqzOp);
+    compress_branch!(op, BranchNeOp => CBranchNezOp);
+
+    None
+}
+
+/// Replace instructions of the module with the equivalent compressed instructions. Does
+/// nothing unless the C extension is enabled.
+///
+/// Compression only makes code shorter, so the branches, that are in range of the
+/// compressed encoding when they are visited, remain in range.
+pub fn compress_instrs(module: &Arc<RwCell<ModuleOp>>, features: &ISAFeatures) {
+    if !features.has(Extension::C) {
+        return;
+    }
+
+    let builder = OpBuilder::new(module.borrow().get_context(), module.borrow().get_body());
+    let module: OpRef = module.clone();
+    dfs_walk(module, |cand| {
+        if let Some(op) = compress_op(cand, features) {
+            builder.set_insertion_point_after(cand);
+            builder.set_location(cand.borrow().get_location());
+            builder.insert_generic(&op);
+            builder.erase(cand);
+        }
+    });
+}
@@ -0,0 +1,1792 @@
+use std::any::TypeId;
+
+use crate::utils::{CImmLayout, CInstr};
+use crate::{DiagKind, FPR, GPR};
+use tir_backend::isema::{self, WithISema};
+use tir_backend::parser::{asm_ident, close_paren, comma, number, open_paren};
+use tir_backend::AsmPrintable;
+use tir_backend::BinaryEmittable;
+use tir_backend::ISAParser;
+use tir_backend::Register;
+use tir_backend::TokenStream;
+use tir_core::*;
+
+use lpl::{Diagnostic, ParseResult, ParseStream, Parser};
+
+use super::asm::{imm, opcode, reg_name, AsmRegister};
+use super::control::{address_label, get_label_offset};
+use super::defs::*;
+
+const QUADRANT_0: u8 = 0b00;
+const QUADRANT_1: u8 = 0b01;
+const QUADRANT_2: u8 = 0b10;
+
+/// Stack pointer, the implicit base register of the CSS and CI-format loads and stores
+const SP: GPR = GPR::X2;
+
+/// Registers, that the 3-bit register fields of the CIW, CL, CS, CA and CB formats refer to
+pub(super) fn is_compressed_reg(num: u8) -> bool {
+    (8..16).contains(&num)
+}
+
+pub(super) fn is_nonzero_reg(num: u8) -> bool {
+    num != 0
+}
+
+fn is_any_reg(_num: u8) -> bool {
+    true
+}
+
+pub(super) fn is_sp(num: u8) -> bool {
+    num == SP.encode()
+}
+
+/// Register number, if the register can be encoded in the field, that accepts `valid`
+/// registers
+fn encode_reg(num: u8, valid: fn(u8) -> bool) -> tir_core::Result<u8> {
+    Some(num)
+        .filter(|num| valid(*num))
+        .ok_or(tir_core::Error::Unknown)
+}
+
+/// Register of the given class, that can be encoded in the field, that accepts `valid`
+/// registers
+fn c_register<'a, R: AsmRegister + 'a>(
+    valid: fn(u8) -> bool,
+) -> impl Parser<'a, TokenStream<'a>, R> {
+    asm_ident()
+        .try_map(move |r, s| {
+            let reg = R::parse_name(r).ok_or(Into::<Diagnostic>::into(
+                DiagKind::UnknownRegister(r.to_string(), s.clone()),
+            ))?;
+            if valid(reg.reg_num()) {
+                Ok(reg)
+            } else {
+                Err(DiagKind::InvalidCompressedRegister(r.to_string(), s).into())
+            }
+        })
+        .label("register")
+}
+
+/// Immediate of a compressed instruction along with the constraints of its encoding
+#[derive(Clone, Copy)]
+pub(super) struct CImm {
+    layout: CImmLayout,
+    min: i64,
+    max: i64,
+    scale: i64,
+    nonzero: bool,
+}
+
+impl CImm {
+    const fn new(layout: CImmLayout, min: i64, max: i64, scale: i64, nonzero: bool) -> Self {
+        CImm {
+            layout,
+            min,
+            max,
+            scale,
+            nonzero,
+        }
+    }
+
+    pub(super) fn is_valid(&self, value: i64) -> bool {
+        (self.min..=self.max).contains(&value)
+            && value % self.scale == 0
+            && !(self.nonzero && value == 0)
+    }
+
+    /// Bits of the immediate to scatter over the instruction
+    fn encode(&self, value: i64) -> tir_core::Result<u32> {
+        if self.is_valid(value) {
+            Ok(value as u32)
+        } else {
+            Err(tir_core::Error::Unknown)
+        }
+    }
+
+    /// Immediate value of the instruction. Negative ranges are sign-extended from the
+    /// highest bit of the layout.
+    fn decode(&self, instr: &CInstr) -> Option<i16> {
+        let value = instr.imm(&self.layout) as i64;
+        let value = if self.min < 0 {
+            let bits = self.layout.iter().flatten().max().unwrap() + 1;
+            (value << (64 - bits)) >> (64 - bits)
+        } else {
+            value
+        };
+        Some(value)
+            .filter(|value| self.is_valid(*value))
+            .map(|value| value as i16)
+    }
+
+    fn parser<'a>(self) -> impl Parser<'a, TokenStream<'a>, i16> {
+        imm(self.min, self.max)
+            .try_map(move |num, s| {
+                if num % self.scale != 0 {
+                    Err(Into::<Diagnostic>::into(DiagKind::MisalignedImm(
+                        num, self.scale, s,
+                    )))
+                } else if self.nonzero && num == 0 {
+                    Err(DiagKind::ZeroImm(s).into())
+                } else {
+                    Ok(num as i16)
+                }
+            })
+            .label("imm")
+    }
+}
+
+/// Layout of an immediate, that lists the immediate bits held by the instruction bits 12
+/// down to 2. `_` marks the bits of other fields.
+macro_rules! layout {
+    ($($bit:tt),*) => { [$(layout!(@bit $bit)),*] };
+    (@bit _) => { None };
+    (@bit $bit:literal) => { Some($bit) };
+}
+
+const CI_LAYOUT: CImmLayout = layout![5, _, _, _, _, _, 4, 3, 2, 1, 0];
+pub(super) const CI_IMM: CImm = CImm::new(CI_LAYOUT, -32, 31, 1, false);
+pub(super) const CI_NZ_IMM: CImm = CImm::new(CI_LAYOUT, -32, 31, 1, true);
+pub(super) const CI_SHAMT: CImm = CImm::new(CI_LAYOUT, 0, 63, 1, true);
+pub(super) const ADDI16SP_IMM: CImm = CImm::new(
+    layout![9, _, _, _, _, _, 4, 6, 8, 7, 5],
+    -512,
+    496,
+    16,
+    true,
+);
+pub(super) const ADDI4SPN_IMM: CImm =
+    CImm::new(layout![5, 4, 9, 8, 7, 6, 2, 3, _, _, _], 0, 1020, 4, true);
+pub(super) const CL_WORD_OFFSET: CImm =
+    CImm::new(layout![5, 4, 3, _, _, _, 2, 6, _, _, _], 0, 124, 4, false);
+pub(super) const CL_DOUBLE_OFFSET: CImm =
+    CImm::new(layout![5, 4, 3, _, _, _, 7, 6, _, _, _], 0, 248, 8, false);
+pub(super) const LWSP_OFFSET: CImm =
+    CImm::new(layout![5, _, _, _, _, _, 4, 3, 2, 7, 6], 0, 252, 4, false);
+pub(super) const LDSP_OFFSET: CImm =
+    CImm::new(layout![5, _, _, _, _, _, 4, 3, 8, 7, 6], 0, 504, 8, false);
+pub(super) const SWSP_OFFSET: CImm =
+    CImm::new(layout![5, 4, 3, 2, 7, 6, _, _, _, _, _], 0, 252, 4, false);
+pub(super) const SDSP_OFFSET: CImm =
+    CImm::new(layout![5, 4, 3, 8, 7, 6, _, _, _, _, _], 0, 504, 8, false);
+pub(super) const CJ_OFFSET: CImm = CImm::new(
+    layout![11, 4, 9, 8, 10, 6, 7, 3, 2, 1, 5],
+    -2048,
+    2046,
+    2,
+    false,
+);
+pub(super) const CB_OFFSET: CImm = CImm::new(
+    layout![8, 4, 3, _, _, _, 7, 6, 2, 1, 5],
+    -256,
+    254,
+    2,
+    false,
+);
+
+/// Address of a compressed load or store, `offset(rs1)`
+fn c_addr<'a>(offset: CImm, valid: fn(u8) -> bool) -> impl Parser<'a, TokenStream<'a>, (i16, GPR)> {
+    offset
+        .parser()
+        .and_then(open_paren())
+        .and_then(c_register::<GPR>(valid))
+        .and_then(close_paren())
+        .map(|(((offset, _), rs1), _)| (offset, rs1))
+        .label("address")
+}
+
+fn write_c_instr(stream: &mut Box<dyn tir_backend::BinaryStream>, instr: CInstr) {
+    stream.write(&instr.to_bytes());
+}
+
+/// Getters of the two-address operations, that read and write rd, to describe their
+/// semantics in terms of the base instructions
+macro_rules! two_address_impl {
+    ($struct_name:ident, $width:literal) => {
+        impl $struct_name {
+            pub fn get_rs1(&self) -> Register<GPR> {
+                self.get_rd()
+            }
+
+            pub fn get_op_width(&self) -> u8 {
+                $width
+            }
+
+            /// Word operations sign-extend the result to the full register
+            pub fn get_sign_extend(&self) -> bool {
+                $width < 64
+            }
+        }
+    };
+    ($struct_name:ident, $width:literal, imm) => {
+        two_address_impl!($struct_name, $width);
+
+        impl $struct_name {
+            pub fn get_imm(&self) -> i16 {
+                self.imm() as i16
+            }
+        }
+    };
+}
+
+macro_rules! compressed_op_base {
+    // CL format loads, `rd', offset(rs1')`
+    (load $struct_name:ident { name = $op_name:literal, funct3 = $funct3:literal, rd = $rd:ident, offset = $offset:ident, isema = $isema:ident { $($attr:ident = $value:expr),* } }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3()) != (QUADRANT_0, $funct3) {
+                    return None;
+                }
+
+                let rd = $rd::try_from(instr.rs2_prime() as usize).ok()?;
+                let rs1 = GPR::try_from(instr.rs1_prime() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rd(rd.into())
+                        .rs1(rs1.into())
+                        .offset($offset.decode(instr)?.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_0)
+                    .funct3($funct3)
+                    .rs2_prime(encode_reg(self.get_rd().as_arch().encode(), is_compressed_reg)?)
+                    .rs1_prime(encode_reg(self.get_rs1().as_arch().encode(), is_compressed_reg)?)
+                    .imm(&$offset.layout, $offset.encode(self.offset())?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Read]
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::$isema::builder(&context)
+                    .dst(self.get_rd().into())
+                    .base_addr(self.get_rs1().into())
+                    .offset(self.offset() as i16)
+                    $(.$attr($value.into()))*
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<$rd>(is_compressed_reg))
+                    .and_then(comma())
+                    .and_then(c_addr($offset, is_compressed_reg))
+                    .map(|(((_, rd), _), (offset, rs1))| (rd, offset, rs1))
+                    .label($op_name);
+                let ((rd, offset, rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .offset(offset.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    format!("{}({})", self.offset(), reg_name(self.get_rs1(), target_opts)),
+                )
+            }
+        }
+    };
+
+    // CS format stores, `rs2', offset(rs1')`
+    (store $struct_name:ident { name = $op_name:literal, funct3 = $funct3:literal, rs2 = $rs2:ident, offset = $offset:ident, width = $width:literal }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3()) != (QUADRANT_0, $funct3) {
+                    return None;
+                }
+
+                let rs1 = GPR::try_from(instr.rs1_prime() as usize).ok()?;
+                let rs2 = $rs2::try_from(instr.rs2_prime() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rs1(rs1.into())
+                        .rs2(rs2.into())
+                        .offset($offset.decode(instr)?.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_0)
+                    .funct3($funct3)
+                    .rs2_prime(encode_reg(self.get_rs2().as_arch().encode(), is_compressed_reg)?)
+                    .rs1_prime(encode_reg(self.get_rs1().as_arch().encode(), is_compressed_reg)?)
+                    .imm(&$offset.layout, $offset.encode(self.offset())?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Write]
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::StoreOp::builder(&context)
+                    .src(self.get_rs2().into())
+                    .base_addr(self.get_rs1().into())
+                    .offset(self.offset() as i16)
+                    .width($width)
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<$rs2>(is_compressed_reg))
+                    .and_then(comma())
+                    .and_then(c_addr($offset, is_compressed_reg))
+                    .map(|(((_, rs2), _), (offset, rs1))| (rs2, offset, rs1))
+                    .label($op_name);
+                let ((rs2, offset, rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rs1(rs1.into())
+                    .rs2(rs2.into())
+                    .offset(offset.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rs2(), target_opts),
+                    format!("{}({})", self.offset(), reg_name(self.get_rs1(), target_opts)),
+                )
+            }
+        }
+    };
+
+    // CI format loads relative to the stack pointer, `rd, offset(sp)`
+    (load_sp $struct_name:ident { name = $op_name:literal, funct3 = $funct3:literal, rd = $rd:ident, rd_valid = $rd_valid:ident, offset = $offset:ident, isema = $isema:ident { $($attr:ident = $value:expr),* } }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3()) != (QUADRANT_2, $funct3) || !$rd_valid(instr.rd()) {
+                    return None;
+                }
+
+                let rd = $rd::try_from(instr.rd() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rd(rd.into())
+                        .offset($offset.decode(instr)?.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_2)
+                    .funct3($funct3)
+                    .rd(encode_reg(self.get_rd().as_arch().encode(), $rd_valid)?)
+                    .imm(&$offset.layout, $offset.encode(self.offset())?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Read]
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::$isema::builder(&context)
+                    .dst(self.get_rd().into())
+                    .base_addr(Register::from(SP).into())
+                    .offset(self.offset() as i16)
+                    $(.$attr($value.into()))*
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<$rd>($rd_valid))
+                    .and_then(comma())
+                    .and_then(c_addr($offset, is_sp))
+                    .map(|(((_, rd), _), (offset, _))| (rd, offset))
+                    .label($op_name);
+                let ((rd, offset), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .offset(offset.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    format!("{}({})", self.offset(), reg_name(Register::Architecture(SP), target_opts)),
+                )
+            }
+        }
+    };
+
+    // CSS format stores relative to the stack pointer, `rs2, offset(sp)`
+    (store_sp $struct_name:ident { name = $op_name:literal, funct3 = $funct3:literal, rs2 = $rs2:ident, offset = $offset:ident, width = $width:literal }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3()) != (QUADRANT_2, $funct3) {
+                    return None;
+                }
+
+                let rs2 = $rs2::try_from(instr.rs2() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rs2(rs2.into())
+                        .offset($offset.decode(instr)?.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_2)
+                    .funct3($funct3)
+                    .rs2(self.get_rs2().as_arch().encode())
+                    .imm(&$offset.layout, $offset.encode(self.offset())?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Write]
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::StoreOp::builder(&context)
+                    .src(self.get_rs2().into())
+                    .base_addr(Register::from(SP).into())
+                    .offset(self.offset() as i16)
+                    .width($width)
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<$rs2>(is_any_reg))
+                    .and_then(comma())
+                    .and_then(c_addr($offset, is_sp))
+                    .map(|(((_, rs2), _), (offset, _))| (rs2, offset))
+                    .label($op_name);
+                let ((rs2, offset), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rs2(rs2.into())
+                    .offset(offset.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rs2(), target_opts),
+                    format!("{}({})", self.offset(), reg_name(Register::Architecture(SP), target_opts)),
+                )
+            }
+        }
+    };
+
+    // CI format operations with an immediate, `rd, imm`. rd must not be x0.
+    (imm $struct_name:ident { name = $op_name:literal, quadrant = $quadrant:ident, funct3 = $funct3:literal, imm = $imm:ident }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3()) != ($quadrant, $funct3) || instr.rd() == 0 {
+                    return None;
+                }
+
+                let rd = GPR::try_from(instr.rd() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rd(rd.into())
+                        .imm($imm.decode(instr)?.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode($quadrant)
+                    .funct3($funct3)
+                    .rd(encode_reg(self.get_rd().as_arch().encode(), is_nonzero_reg)?)
+                    .imm(&$imm.layout, $imm.encode(self.imm())?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<GPR>(is_nonzero_reg))
+                    .and_then(comma())
+                    .and_then($imm.parser())
+                    .map(|(((_, rd), _), imm)| (rd, imm))
+                    .label($op_name);
+                let ((rd, imm), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .imm(imm.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    self.imm(),
+                )
+            }
+        }
+    };
+
+    // CB format operations with an immediate, `rd', imm`
+    (imm_prime $struct_name:ident { name = $op_name:literal, funct2 = $funct2:literal, imm = $imm:ident }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3(), instr.funct2()) != (QUADRANT_1, 0b100, $funct2) {
+                    return None;
+                }
+
+                let rd = GPR::try_from(instr.rs1_prime() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rd(rd.into())
+                        .imm($imm.decode(instr)?.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_1)
+                    .funct3(0b100)
+                    .funct2($funct2)
+                    .rs1_prime(encode_reg(self.get_rd().as_arch().encode(), is_compressed_reg)?)
+                    .imm(&$imm.layout, $imm.encode(self.imm())?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<GPR>(is_compressed_reg))
+                    .and_then(comma())
+                    .and_then($imm.parser())
+                    .map(|(((_, rd), _), imm)| (rd, imm))
+                    .label($op_name);
+                let ((rd, imm), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .imm(imm.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    self.imm(),
+                )
+            }
+        }
+    };
+
+    // CA format register-register operations, `rd', rs2'`
+    (alu $struct_name:ident { name = $op_name:literal, bit12 = $bit12:literal, funct2 = $funct2:literal }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                let fields = (instr.opcode(), instr.funct3(), instr.bit12(), instr.funct2(), instr.ca_funct2());
+                if fields != (QUADRANT_1, 0b100, $bit12, 0b11, $funct2) {
+                    return None;
+                }
+
+                let rd = GPR::try_from(instr.rs1_prime() as usize).ok()?;
+                let rs2 = GPR::try_from(instr.rs2_prime() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rd(rd.into())
+                        .rs2(rs2.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_1)
+                    .funct3(0b100)
+                    .bit12($bit12)
+                    .funct2(0b11)
+                    .ca_funct2($funct2)
+                    .rs1_prime(encode_reg(self.get_rd().as_arch().encode(), is_compressed_reg)?)
+                    .rs2_prime(encode_reg(self.get_rs2().as_arch().encode(), is_compressed_reg)?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<GPR>(is_compressed_reg))
+                    .and_then(comma())
+                    .and_then(c_register::<GPR>(is_compressed_reg))
+                    .map(|(((_, rd), _), rs2)| (rd, rs2))
+                    .label($op_name);
+                let ((rd, rs2), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs2(rs2.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs2(), target_opts),
+                )
+            }
+        }
+    };
+
+    // CR format register moves and additions, `rd, rs2`. Neither register may be x0.
+    (mv $struct_name:ident { name = $op_name:literal, bit12 = $bit12:literal }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3(), instr.bit12()) != (QUADRANT_2, 0b100, $bit12)
+                    || instr.rd() == 0
+                    || instr.rs2() == 0
+                {
+                    return None;
+                }
+
+                let rd = GPR::try_from(instr.rd() as usize).ok()?;
+                let rs2 = GPR::try_from(instr.rs2() as usize).ok()?;
+                Some(
+                    $struct_name::builder(context)
+                        .rd(rd.into())
+                        .rs2(rs2.into())
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_2)
+                    .funct3(0b100)
+                    .bit12($bit12)
+                    .rd(encode_reg(self.get_rd().as_arch().encode(), is_nonzero_reg)?)
+                    .rs2(encode_reg(self.get_rs2().as_arch().encode(), is_nonzero_reg)?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<GPR>(is_nonzero_reg))
+                    .and_then(comma())
+                    .and_then(c_register::<GPR>(is_nonzero_reg))
+                    .map(|(((_, rd), _), rs2)| (rd, rs2))
+                    .label($op_name);
+                let ((rd, rs2), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs2(rs2.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs2(), target_opts),
+                )
+            }
+        }
+    };
+
+    // CR format indirect jumps, `rs1`. rd of the equivalent jalr is x0 or ra.
+    (jump_reg $struct_name:ident { name = $op_name:literal, bit12 = $bit12:literal, rd = $rd:expr }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3(), instr.bit12()) != (QUADRANT_2, 0b100, $bit12)
+                    || instr.rd() == 0
+                    || instr.rs2() != 0
+                {
+                    return None;
+                }
+
+                let rs1 = GPR::try_from(instr.rd() as usize).ok()?;
+                Some($struct_name::builder(context).rs1(rs1.into()).build())
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_2)
+                    .funct3(0b100)
+                    .bit12($bit12)
+                    .rd(encode_reg(self.get_rs1().as_arch().encode(), is_nonzero_reg)?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::JumpOp::builder(&context)
+                    .rd(Register::from($rd).into())
+                    .base_addr(self.get_rs1().into())
+                    .offset(0)
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<GPR>(is_nonzero_reg))
+                    .map(|(_, rs1)| rs1)
+                    .label($op_name);
+                let (rs1, ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context).rs1(rs1.into()).build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}",
+                    $op_name,
+                    reg_name(self.get_rs1(), target_opts),
+                )
+            }
+        }
+    };
+
+    // CB format branches, that compare rs1' with zero, `rs1', label`
+    (branch $struct_name:ident { name = $op_name:literal, funct3 = $funct3:literal, cond = $cond:literal }) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, address: u64) -> Option<OpRef> {
+                if (instr.opcode(), instr.funct3()) != (QUADRANT_1, $funct3) {
+                    return None;
+                }
+
+                let rs1 = GPR::try_from(instr.rs1_prime() as usize).ok()?;
+                let target = address.checked_add_signed(CB_OFFSET.decode(instr)?.into())?;
+                Some(
+                    $struct_name::builder(context)
+                        .rs1(rs1.into())
+                        .target(address_label(target))
+                        .build(),
+                )
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let offset =
+                    get_label_offset(self, self.target()).ok_or(tir_core::Error::Unknown)?;
+                let instr = CInstr::builder()
+                    .opcode(QUADRANT_1)
+                    .funct3($funct3)
+                    .rs1_prime(encode_reg(self.get_rs1().as_arch().encode(), is_compressed_reg)?)
+                    .imm(&CB_OFFSET.layout, CB_OFFSET.encode(offset)?)
+                    .build();
+                write_c_instr(stream, instr);
+                Ok(())
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl BranchOpInterface for $struct_name {
+            fn get_successors(&self) -> Vec<BlockRef> {
+                self.get_parent_region()
+                    .and_then(|region| region.get_block_by_name(self.target()))
+                    .into_iter()
+                    .collect()
+            }
+
+            fn may_fall_through(&self) -> bool {
+                true
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::BranchOp::builder(&context)
+                    .rs1(self.get_rs1().into())
+                    .rs2(Register::from(GPR::X0).into())
+                    .cond($cond.to_string())
+                    .target(self.target().to_string())
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(c_register::<GPR>(is_compressed_reg))
+                    .and_then(comma())
+                    .and_then(asm_ident().label("label"))
+                    .map(|(((_, rs1), _), target)| (rs1, target))
+                    .label($op_name);
+                let ((rs1, target), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rs1(rs1.into())
+                    .target(target.to_string())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rs1(), target_opts),
+                    self.target(),
+                )
+            }
+        }
+    };
+}
+
+/// Compressed instructions, that are decoded from a fixed encoding
+macro_rules! fixed_encoding_impl {
+    ($struct_name:ident, $op_name:literal, $encoding:literal) => {
+        impl $struct_name {
+            fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+                if u16::from_le_bytes(instr.to_bytes()) != $encoding {
+                    return None;
+                }
+
+                Some($struct_name::builder(context).build())
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                stream.write(&($encoding as u16).to_le_bytes());
+                Ok(())
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let (_, ni) = opcode($op_name).label($op_name).parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context).build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+                $op_name.to_string()
+            }
+        }
+    };
+}
+
+fixed_encoding_impl!(CNopOp, "c.nop", 0x0001);
+fixed_encoding_impl!(CEbreakOp, "c.ebreak", 0x9002);
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CNopOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let zero: String = Register::from(GPR::X0).into();
+        let op = tir_backend::isema::AddOp::builder(&context)
+            .rd(zero.clone())
+            .rs1(zero)
+            .imm(0)
+            .width(64)
+            .build();
+        builder.insert(&op);
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CEbreakOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::TrapOp::builder(&context)
+            .cause("breakpoint".to_string())
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl CAddi4spnOp {
+    fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+        if (instr.opcode(), instr.funct3()) != (QUADRANT_0, 0b000) {
+            return None;
+        }
+
+        let rd = GPR::try_from(instr.rs2_prime() as usize).ok()?;
+        Some(
+            CAddi4spnOp::builder(context)
+                .rd(rd.into())
+                .imm(ADDI4SPN_IMM.decode(instr)?.into())
+                .build(),
+        )
+    }
+}
+
+impl BinaryEmittable for CAddi4spnOp {
+    fn encode(
+        &self,
+        _target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        let instr = CInstr::builder()
+            .opcode(QUADRANT_0)
+            .funct3(0b000)
+            .rs2_prime(encode_reg(
+                self.get_rd().as_arch().encode(),
+                is_compressed_reg,
+            )?)
+            .imm(&ADDI4SPN_IMM.layout, ADDI4SPN_IMM.encode(self.imm())?)
+            .build();
+        write_c_instr(stream, instr);
+        Ok(())
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CAddi4spnOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::AddOp::builder(&context)
+            .rd(self.get_rd().into())
+            .rs1(Register::from(SP).into())
+            .imm(self.imm() as i16)
+            .width(64)
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for CAddi4spnOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let parser = opcode("c.addi4spn")
+            .and_then(c_register::<GPR>(is_compressed_reg))
+            .and_then(comma())
+            .and_then(c_register::<GPR>(is_sp))
+            .and_then(comma())
+            .and_then(ADDI4SPN_IMM.parser())
+            .map(|(((((_, rd), _), _), _), imm)| (rd, imm))
+            .label("c.addi4spn");
+        let ((rd, imm), ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = CAddi4spnOp::builder(&context)
+            .rd(rd.into())
+            .imm(imm.into())
+            .build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for CAddi4spnOp {
+    fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+        format!(
+            "c.addi4spn {}, {}, {}",
+            reg_name(self.get_rd(), target_opts),
+            reg_name(Register::Architecture(SP), target_opts),
+            self.imm()
+        )
+    }
+}
+
+impl CAddi16spOp {
+    fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+        if (instr.opcode(), instr.funct3()) != (QUADRANT_1, 0b011) || !is_sp(instr.rd()) {
+            return None;
+        }
+
+        Some(
+            CAddi16spOp::builder(context)
+                .imm(ADDI16SP_IMM.decode(instr)?.into())
+                .build(),
+        )
+    }
+}
+
+impl BinaryEmittable for CAddi16spOp {
+    fn encode(
+        &self,
+        _target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        let instr = CInstr::builder()
+            .opcode(QUADRANT_1)
+            .funct3(0b011)
+            .rd(SP.encode())
+            .imm(&ADDI16SP_IMM.layout, ADDI16SP_IMM.encode(self.imm())?)
+            .build();
+        write_c_instr(stream, instr);
+        Ok(())
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CAddi16spOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let sp: String = Register::from(SP).into();
+        let op = tir_backend::isema::AddOp::builder(&context)
+            .rd(sp.clone())
+            .rs1(sp)
+            .imm(self.imm() as i16)
+            .width(64)
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for CAddi16spOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let parser = opcode("c.addi16sp")
+            .and_then(c_register::<GPR>(is_sp))
+            .and_then(comma())
+            .and_then(ADDI16SP_IMM.parser())
+            .map(|(_, imm)| imm)
+            .label("c.addi16sp");
+        let (imm, ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = CAddi16spOp::builder(&context).imm(imm.into()).build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+impl CLuiOp {
+    /// Largest value of the 20-bit upper immediate
+    const MAX_UPPER_IMM: i64 = 0xfffff;
+
+    /// The 6-bit immediate is sign-extended to the 20-bit upper immediate field
+    fn to_upper_imm(imm: i16) -> i32 {
+        (imm as i64 & Self::MAX_UPPER_IMM) as i32
+    }
+
+    pub(super) fn from_upper_imm(imm: i64) -> i64 {
+        (imm << 44) >> 44
+    }
+
+    fn decode(context: &ContextRef, instr: &CInstr, _address: u64) -> Option<OpRef> {
+        if (instr.opcode(), instr.funct3()) != (QUADRANT_1, 0b011)
+            || instr.rd() == 0
+            || is_sp(instr.rd())
+        {
+            return None;
+        }
+
+        let rd = GPR::try_from(instr.rd() as usize).ok()?;
+        Some(
+            CLuiOp::builder(context)
+                .rd(rd.into())
+                .imm(Self::to_upper_imm(CI_NZ_IMM.decode(instr)?).into())
+                .build(),
+        )
+    }
+}
+
+impl BinaryEmittable for CLuiOp {
+    fn encode(
+        &self,
+        _target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        let rd = encode_reg(self.get_rd().as_arch().encode(), |num| {
+            is_nonzero_reg(num) && !is_sp(num)
+        })?;
+        let imm = CI_NZ_IMM.encode(Self::from_upper_imm(self.imm()))?;
+        let instr = CInstr::builder()
+            .opcode(QUADRANT_1)
+            .funct3(0b011)
+            .rd(rd)
+            .imm(&CI_NZ_IMM.layout, imm)
+            .build();
+        write_c_instr(stream, instr);
+        Ok(())
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CLuiOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::LoadImmOp::builder(&context)
+            .rd(self.get_rd().into())
+            .imm(Self::from_upper_imm(self.imm()) << 12)
+            .pc_relative(false)
+            .width(64)
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl AsmPrintable for CAddi16spOp {
+    fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+        format!(
+            "c.addi16sp {}, {}",
+            reg_name(Register::Architecture(SP), target_opts),
+            self.imm()
+        )
+    }
+}
+
+impl AsmPrintable for CLuiOp {
+    /// The immediate is printed as the 20-bit upper immediate, that the parser expects
+    fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+        format!(
+            "c.lui {}, {:#x}",
+            reg_name(self.get_rd(), target_opts),
+            self.imm()
+        )
+    }
+}
+
+impl ISAParser for CLuiOp {
+    /// The immediate is written as the 20-bit upper immediate of `lui`, i.e. `0xfffe0`
+    /// for -32
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let upper_imm = number()
+            .try_map(|num, s| {
+                if (1..=Self::MAX_UPPER_IMM).contains(&num)
+                    && CI_NZ_IMM.is_valid(Self::from_upper_imm(num))
+                {
+                    Ok(num)
+                } else {
+                    Err(Into::<Diagnostic>::into(DiagKind::ImmOutOfRange(
+                        num,
+                        1,
+                        CI_NZ_IMM.max,
+                        s,
+                    )))
+                }
+            })
+            .label("upper_imm");
+        let parser = opcode("c.lui")
+            .and_then(c_register::<GPR>(|num| is_nonzero_reg(num) && !is_sp(num)))
+            .and_then(comma())
+            .and_then(upper_imm)
+            .map(|(((_, rd), _), imm)| (rd, imm))
+            .label("c.lui");
+        let ((rd, imm), ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = CLuiOp::builder(&context)
+            .rd(rd.into())
+            .imm((imm as i32).into())
+            .build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CLoadImmOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::LoadImmOp::builder(&context)
+            .rd(self.get_rd().into())
+            .imm(self.imm())
+            .pc_relative(false)
+            .width(64)
+            .build();
+        builder.insert(&op);
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CMoveOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::AddOp::builder(&context)
+            .rd(self.get_rd().into())
+            .rs1(Register::from(GPR::X0).into())
+            .rs2(self.get_rs2().into())
+            .width(64)
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl CJumpOp {
+    fn decode(context: &ContextRef, instr: &CInstr, address: u64) -> Option<OpRef> {
+        if (instr.opcode(), instr.funct3()) != (QUADRANT_1, 0b101) {
+            return None;
+        }
+
+        let target = address.checked_add_signed(CJ_OFFSET.decode(instr)?.into())?;
+        Some(
+            CJumpOp::builder(context)
+                .target(address_label(target))
+                .build(),
+        )
+    }
+}
+
+impl BinaryEmittable for CJumpOp {
+    fn encode(
+        &self,
+        _target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        let offset = get_label_offset(self, self.target()).ok_or(tir_core::Error::Unknown)?;
+        let instr = CInstr::builder()
+            .opcode(QUADRANT_1)
+            .funct3(0b101)
+            .imm(&CJ_OFFSET.layout, CJ_OFFSET.encode(offset)?)
+            .build();
+        write_c_instr(stream, instr);
+        Ok(())
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl BranchOpInterface for CJumpOp {
+    fn get_successors(&self) -> Vec<BlockRef> {
+        self.get_parent_region()
+            .and_then(|region| region.get_block_by_name(self.target()))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for CJumpOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::JumpOp::builder(&context)
+            .rd(Register::from(GPR::X0).into())
+            .target(self.target().to_string())
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for CJumpOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let parser = opcode("c.j")
+            .and_then(asm_ident().label("label"))
+            .map(|(_, target)| target)
+            .label("c.j");
+        let (target, ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = CJumpOp::builder(&context)
+            .target(target.to_string())
+            .build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for CJumpOp {
+    fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+        format!("c.j {}", self.target())
+    }
+}
+
+two_address_impl!(CAddImmOp, 64, imm);
+two_address_impl!(CAddImmWOp, 32, imm);
+two_address_impl!(CSllImmOp, 64, imm);
+two_address_impl!(CSrlImmOp, 64, imm);
+two_address_impl!(CSraImmOp, 64, imm);
+two_address_impl!(CAndImmOp, 64, imm);
+two_address_impl!(CSubOp, 64);
+two_address_impl!(CXorOp, 64);
+two_address_impl!(COrOp, 64);
+two_address_impl!(CAndOp, 64);
+two_address_impl!(CSubWOp, 32);
+two_address_impl!(CAddWOp, 32);
+two_address_impl!(CAddOp, 64);
+
+isema::def! {dialect = riscv, CAddImmOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, CAddImmWOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, CSllImmOp => tir_backend::isema::SllOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, CSrlImmOp => tir_backend::isema::SrlOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, CSraImmOp => tir_backend::isema::SraOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, CAndImmOp => tir_backend::isema::AndOp{rd = get_rd, rs1 = get_rs1, imm = get_imm, width = get_op_width}}
+isema::def! {dialect = riscv, CSubOp => tir_backend::isema::SubOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, CXorOp => tir_backend::isema::XorOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, COrOp => tir_backend::isema::OrOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, CAndOp => tir_backend::isema::AndOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+isema::def! {dialect = riscv, CSubWOp => tir_backend::isema::SubOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, CAddWOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width, sign_extend = get_sign_extend}}
+isema::def! {dialect = riscv, CAddOp => tir_backend::isema::AddOp{rd = get_rd, rs1 = get_rs1, rs2 = get_rs2, width = get_op_width}}
+
+macro_rules! compressed_ops {
+    ($($kind:ident { $($struct_name:ident => { $($fields:tt)* })* })*) => {
+        $($(
+        compressed_op_base!($kind $struct_name { $($fields)* });
+        )*)*
+
+        /// Disassemble a 16-bit instruction of the C extension located at the given address
+        pub fn disassemble_compressed_instr(
+            context: &ContextRef,
+            stream: &[u8],
+            address: u64,
+        ) -> Option<OpRef> {
+            if stream.len() < 2 {
+                return None;
+            }
+
+            let instr = CInstr::from_bytes(stream[0..2].try_into().unwrap());
+            $($(
+            if let Some(op) = $struct_name::decode(context, &instr, address) {
+                return Some(op);
+            }
+            )*)*
+            [
+                CAddi4spnOp::decode,
+                CAddi16spOp::decode,
+                CLuiOp::decode,
+                CJumpOp::decode,
+                CNopOp::decode,
+                CEbreakOp::decode,
+            ]
+            .iter()
+            .find_map(|decode| decode(context, &instr, address))
+        }
+
+        /// Check if the operation has a 16-bit encoding
+        pub(super) fn is_compressed(op: &dyn Op) -> bool {
+            [
+                $($(TypeId::of::<$struct_name>(),)*)*
+                TypeId::of::<CAddi4spnOp>(),
+                TypeId::of::<CAddi16spOp>(),
+                TypeId::of::<CLuiOp>(),
+                TypeId::of::<CJumpOp>(),
+                TypeId::of::<CNopOp>(),
+                TypeId::of::<CEbreakOp>(),
+            ]
+            .contains(&<dyn Op as std::any::Any>::type_id(op))
+        }
+    };
+}
+
+compressed_ops! {
+    load {
+        CFLoadDouble => { name = "c.fld", funct3 = 0b001, rd = FPR, offset = CL_DOUBLE_OFFSET, isema = FLoadOp { width = 64 } }
+        CLoadWord => { name = "c.lw", funct3 = 0b010, rd = GPR, offset = CL_WORD_OFFSET, isema = LoadOp { width = 32, sign_extend = true } }
+        CLoadDouble => { name = "c.ld", funct3 = 0b011, rd = GPR, offset = CL_DOUBLE_OFFSET, isema = LoadOp { width = 64, sign_extend = false } }
+    }
+    store {
+        CFStoreDouble => { name = "c.fsd", funct3 = 0b101, rs2 = FPR, offset = CL_DOUBLE_OFFSET, width = 64 }
+        CStoreWord => { name = "c.sw", funct3 = 0b110, rs2 = GPR, offset = CL_WORD_OFFSET, width = 32 }
+        CStoreDouble => { name = "c.sd", funct3 = 0b111, rs2 = GPR, offset = CL_DOUBLE_OFFSET, width = 64 }
+    }
+    imm {
+        CAddImmOp => { name = "c.addi", quadrant = QUADRANT_1, funct3 = 0b000, imm = CI_NZ_IMM }
+        CAddImmWOp => { name = "c.addiw", quadrant = QUADRANT_1, funct3 = 0b001, imm = CI_IMM }
+        CLoadImmOp => { name = "c.li", quadrant = QUADRANT_1, funct3 = 0b010, imm = CI_IMM }
+        CSllImmOp => { name = "c.slli", quadrant = QUADRANT_2, funct3 = 0b000, imm = CI_SHAMT }
+    }
+    imm_prime {
+        CSrlImmOp => { name = "c.srli", funct2 = 0b00, imm = CI_SHAMT }
+        CSraImmOp => { name = "c.srai", funct2 = 0b01, imm = CI_SHAMT }
+        CAndImmOp => { name = "c.andi", funct2 = 0b10, imm = CI_IMM }
+    }
+    alu {
+        CSubOp => { name = "c.sub", bit12 = 0, funct2 = 0b00 }
+        CXorOp => { name = "c.xor", bit12 = 0, funct2 = 0b01 }
+        COrOp => { name = "c.or", bit12 = 0, funct2 = 0b10 }
+        CAndOp => { name = "c.and", bit12 = 0, funct2 = 0b11 }
+        CSubWOp => { name = "c.subw", bit12 = 1, funct2 = 0b00 }
+        CAddWOp => { name = "c.addw", bit12 = 1, funct2 = 0b01 }
+    }
+    branch {
+        CBranchEqzOp => { name = "c.beqz", funct3 = 0b110, cond = "eq" }
+        CBranchNezOp => { name = "c.bnez", funct3 = 0b111, cond = "ne" }
+    }
+    load_sp {
+        CFLoadDoubleSp => { name = "c.fldsp", funct3 = 0b001, rd = FPR, rd_valid = is_any_reg, offset = LDSP_OFFSET, isema = FLoadOp { width = 64 } }
+        CLoadWordSp => { name = "c.lwsp", funct3 = 0b010, rd = GPR, rd_valid = is_nonzero_reg, offset = LWSP_OFFSET, isema = LoadOp { width = 32, sign_extend = true } }
+        CLoadDoubleSp => { name = "c.ldsp", funct3 = 0b011, rd = GPR, rd_valid = is_nonzero_reg, offset = LDSP_OFFSET, isema = LoadOp { width = 64, sign_extend = false } }
+    }
+    store_sp {
+        CFStoreDoubleSp => { name = "c.fsdsp", funct3 = 0b101, rs2 = FPR, offset = SDSP_OFFSET, width = 64 }
+        CStoreWordSp => { name = "c.swsp", funct3 = 0b110, rs2 = GPR, offset = SWSP_OFFSET, width = 32 }
+        CStoreDoubleSp => { name = "c.sdsp", funct3 = 0b111, rs2 = GPR, offset = SDSP_OFFSET, width = 64 }
+    }
+    mv {
+        CMoveOp => { name = "c.mv", bit12 = 0 }
+        CAddOp => { name = "c.add", bit12 = 1 }
+    }
+    jump_reg {
+        CJumpRegOp => { name = "c.jr", bit12 = 0, rd = GPR::X0 }
+        CJumpAndLinkRegOp => { name = "c.jalr", bit12 = 1, rd = GPR::X1 }
+    }
+}
+
+/// Length in bytes of the instruction, that starts with the given byte. Instructions of the
+/// C extension never have both lowest bits set.
+pub fn instr_length(first_byte: u8) -> usize {
+    if first_byte & 0b11 == 0b11 {
+        4
+    } else {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::{context, encode_bytes};
+    use crate::{compress_instrs, parse_asm, ISAFeatures};
+    use tir_core::builtin::ModuleOp;
+    use tir_core::utils::op_cast;
+
+    #[test]
+    fn test_disassemble_compressed_branches() {
+        let context = context();
+
+        // c.bnez s1, -2
+        let op = disassemble_compressed_instr(&context, &0xfcfd_u16.to_le_bytes(), 2).unwrap();
+        let op = op_cast::<CBranchNezOp>(op).unwrap();
+        assert_eq!(op.borrow().target(), "L0x0");
+        // c.j 6
+        let op = disassemble_compressed_instr(&context, &0xa019_u16.to_le_bytes(), 6).unwrap();
+        let op = op_cast::<CJumpOp>(op).unwrap();
+        assert_eq!(op.borrow().target(), "L0xc");
+    }
+
+    #[test]
+    fn test_parse_compressed_errors() {
+        let context = context();
+
+        let err = parse_asm(&context, ".text\nl:\n c.lw x10, 6(x11)").unwrap_err();
+        assert_eq!(err.message(), "Immediate value 6 must be a multiple of 4");
+        let err = parse_asm(&context, ".text\nl:\n c.sub x10, x16").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Register 'x16' can not be encoded in the compressed instruction"
+        );
+        let err = parse_asm(&context, ".text\nl:\n c.addi16sp x2, 0").unwrap_err();
+        assert_eq!(err.message(), "Immediate value must not be zero");
+        assert!(parse_asm(&context, ".text\nl:\n c.lwsp x1, 4(x3)").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n c.lwsp x0, 4(x2)").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n c.li x10, 32").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n c.lui x2, 1").is_err());
+    }
+
+    #[test]
+    fn test_compressed_sema() {
+        let context = context();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+
+        let op = CAddImmWOp::builder(&context)
+            .rd(GPR::X10.into())
+            .imm((-1_i64).into())
+            .build();
+        builder.insert(&op);
+        let op = CLoadDoubleSp::builder(&context)
+            .rd(GPR::X1.into())
+            .offset(8_i64.into())
+            .build();
+        builder.insert(&op);
+        let op = CMoveOp::builder(&context)
+            .rd(GPR::X10.into())
+            .rs2(GPR::X11.into())
+            .build();
+        builder.insert(&op);
+        let op = CJumpAndLinkRegOp::builder(&context)
+            .rs1(GPR::X5.into())
+            .build();
+        builder.insert(&op);
+
+        assert!(tir_backend::isema::convert_to_isema(&module).is_ok());
+
+        let ops: Vec<_> = module.borrow().get_body().iter().collect();
+        let add = op_cast::<tir_backend::isema::AddOp>(ops[0].clone()).unwrap();
+        assert_eq!((add.borrow().rd(), add.borrow().rs1()), ("x10", "x10"));
+        assert_eq!(add.borrow().imm(), Some(-1));
+        assert_eq!(add.borrow().width(), 32);
+        let load = op_cast::<tir_backend::isema::LoadOp>(ops[1].clone()).unwrap();
+        assert_eq!(load.borrow().base_addr(), "x2");
+        assert_eq!(load.borrow().width(), 64);
+        let mv = op_cast::<tir_backend::isema::AddOp>(ops[2].clone()).unwrap();
+        assert_eq!(mv.borrow().rs1(), "x0");
+        assert_eq!(mv.borrow().rs2(), Some("x11"));
+        let jump = op_cast::<tir_backend::isema::JumpOp>(ops[3].clone()).unwrap();
+        assert_eq!(jump.borrow().rd(), "x1");
+        assert_eq!(jump.borrow().base_addr(), Some("x5"));
+    }
+
+    #[test]
+    fn test_compress_requires_extension() {
+        let context = context();
+        let module = parse_asm(&context, ".text\nl:\n add a0, a0, a1").unwrap();
+        compress_instrs(&module, &ISAFeatures::parse("rv64g").unwrap());
+        let module: OpRef = module;
+        assert_eq!(encode_bytes(&module), 0x00b50533_u32.to_le_bytes());
+
+        // Double-precision loads are only compressed with the D extension
+        let module = parse_asm(&context, ".text\nl:\n fld fa5, 8(a0)").unwrap();
+        compress_instrs(&module, &ISAFeatures::parse("rv64ifc").unwrap());
+        let module: OpRef = module;
+        assert_eq!(encode_bytes(&module).len(), 4);
+    }
+}
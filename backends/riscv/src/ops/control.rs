@@ -0,0 +1,479 @@
+use crate::GPR;
+use tir_backend::isema::WithISema;
+use tir_backend::parser::{asm_ident, close_paren, comma, open_paren};
+use tir_backend::target::get_data_size;
+use tir_backend::AsmPrintable;
+use tir_backend::ISAParser;
+use tir_backend::TokenStream;
+use tir_core::*;
+
+use lpl::{ParseResult, ParseStream, Parser};
+
+use super::asm::{lo_imm, mem_addr_str, opcode, reg, reg_name, reloc_imm};
+use super::defs::*;
+use super::get_instr_size;
+use super::reloc::{get_reloc, set_reloc, LabelRef, LabelReloc, Reloc, RelocKind};
+
+/// Largest value of a 20-bit upper immediate
+const MAX_UPPER_IMM: i64 = 0xfffff;
+
+/// Name of the label, that the disassembler assigns to a branch target address
+pub fn address_label(address: u64) -> String {
+    format!("L0x{:x}", address)
+}
+
+/// Address of a label, that has been assigned by the disassembler
+pub fn parse_address_label(label: &str) -> Option<u64> {
+    u64::from_str_radix(label.strip_prefix("L0x")?, 16).ok()
+}
+
+/// Byte offset from the operation to the block with the given label.
+///
+/// Both the operation and the label must belong to the same region. Operations of the
+/// RISC-V dialect occupy [`get_instr_size`] bytes, data and alignment of the target
+/// dialect occupy [`get_data_size`] bytes, other operations are not encoded.
+pub fn get_label_offset(op: &dyn Op, label: &str) -> Option<i64> {
+    let region = op.get_parent_region()?;
+    let op_id = op.get_alloc_id();
+    let dialect_id = op.get_dialect_id();
+
+    let mut address = 0;
+    let mut op_address = None;
+    let mut label_address = None;
+
+    for block in region.iter() {
+        if block.get_name() == label {
+            label_address = Some(address);
+        }
+
+        // The operation itself is already borrowed by the caller
+        let op_index = block.find(op_id);
+        for (idx, cand) in block.iter().enumerate() {
+            if Some(idx) == op_index {
+                op_address = Some(address);
+                address += get_instr_size(op) as i64;
+            } else if cand.borrow().get_dialect_id() == dialect_id {
+                address += get_instr_size(&*cand.borrow()) as i64;
+            } else {
+                address += get_data_size(&*cand.borrow(), address as u64).unwrap_or(0) as i64;
+            }
+        }
+    }
+
+    Some(label_address? - op_address?)
+}
+
+fn upper_imm<'a>(
+    relocs: &'static [RelocKind],
+) -> impl Parser<'a, TokenStream<'a>, (i32, Option<Reloc>)> {
+    reloc_imm(0, MAX_UPPER_IMM, relocs)
+        .map(|(num, reloc)| (num as i32, reloc))
+        .label("upper_imm")
+}
+
+macro_rules! branch_op_base {
+    ($struct_name:ident, $op_name:literal, $cond:literal) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl LabelRef for $struct_name {
+            fn get_label(&self) -> &str {
+                self.target()
+            }
+
+            fn get_label_reloc(&self) -> LabelReloc {
+                LabelReloc::Branch
+            }
+
+            fn encode_with_offset(
+                &self,
+                offset: Option<i64>,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                self.encode_with_label_offset(offset, stream)
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl BranchOpInterface for $struct_name {
+            fn get_successors(&self) -> Vec<BlockRef> {
+                self.get_parent_region()
+                    .and_then(|region| region.get_block_by_name(self.target()))
+                    .into_iter()
+                    .collect()
+            }
+
+            fn may_fall_through(&self) -> bool {
+                true
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::BranchOp::builder(&context)
+                    .rs1(self.get_rs1().into())
+                    .rs2(self.get_rs2().into())
+                    .cond($cond.to_string())
+                    .target(self.target().to_string())
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(asm_ident().label("label"))
+                    .map(|(((((_, rs1), _), rs2), _), target)| (rs1, rs2, target))
+                    .label($op_name);
+
+                let ((rs1, rs2, target), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rs1(rs1.into())
+                    .rs2(rs2.into())
+                    .target(target.to_string())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, {}",
+                    $op_name,
+                    reg_name(self.get_rs1(), target_opts),
+                    reg_name(self.get_rs2(), target_opts),
+                    self.target()
+                )
+            }
+        }
+    };
+}
+
+macro_rules! branch_ops {
+    // B-format branch operations
+    ($($struct_name:ident => { name = $op_name:literal, cond = $cond:literal })*) => {
+        $(
+        branch_op_base!($struct_name, $op_name, $cond);
+        )*
+    };
+}
+
+branch_ops! {
+    BranchEqOp => { name = "beq", cond = "eq" }
+    BranchNeOp => { name = "bne", cond = "ne" }
+    BranchLtOp => { name = "blt", cond = "lt" }
+    BranchGeOp => { name = "bge", cond = "ge" }
+    BranchLtuOp => { name = "bltu", cond = "ltu" }
+    BranchGeuOp => { name = "bgeu", cond = "geu" }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl LabelRef for JumpAndLinkOp {
+    fn get_label(&self) -> &str {
+        self.target()
+    }
+
+    fn get_label_reloc(&self) -> LabelReloc {
+        LabelReloc::Jal
+    }
+
+    fn encode_with_offset(
+        &self,
+        offset: Option<i64>,
+        _target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        self.encode_with_label_offset(offset, stream)
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl BranchOpInterface for JumpAndLinkOp {
+    fn get_successors(&self) -> Vec<BlockRef> {
+        self.get_parent_region()
+            .and_then(|region| region.get_block_by_name(self.target()))
+            .into_iter()
+            .collect()
+    }
+
+    /// Jumps that save the return address are calls, the callee returns to the next
+    /// instruction
+    fn may_fall_through(&self) -> bool {
+        !matches!(self.get_rd(), tir_backend::Register::Architecture(GPR::X0))
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for JumpAndLinkOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::JumpOp::builder(&context)
+            .rd(self.get_rd().into())
+            .target(self.target().to_string())
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for JumpAndLinkOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let parser = opcode("jal")
+            .and_then(reg())
+            .and_then(comma())
+            .and_then(asm_ident().label("label"))
+            .map(|(((_, rd), _), target)| (rd, target))
+            .label("jal");
+
+        let ((rd, target), ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = JumpAndLinkOp::builder(&context)
+            .rd(rd.into())
+            .target(target.to_string())
+            .build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for JumpAndLinkRegOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::JumpOp::builder(&context)
+            .rd(self.get_rd().into())
+            .base_addr(self.get_rs1().into())
+            .offset(self.offset() as i16)
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for JumpAndLinkRegOp {
+    /// Both `jalr rd, offset(rs1)` and `jalr rd, rs1, offset` forms are accepted
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let offset = lo_imm;
+
+        let addr = offset()
+            .and_then(open_paren())
+            .and_then(reg())
+            .and_then(close_paren())
+            .map(|(((offset, _), rs1), _)| (rs1, offset))
+            .label("address");
+        let reg_imm = reg()
+            .and_then(comma())
+            .and_then(offset())
+            .map(|((rs1, _), offset)| (rs1, offset));
+
+        let parser = opcode("jalr")
+            .and_then(reg())
+            .and_then(comma())
+            .and_then(addr.or_else(reg_imm))
+            .map(|(((_, rd), _), (rs1, offset))| (rd, rs1, offset))
+            .label("jalr");
+
+        let ((rd, rs1, (offset_value, reloc)), ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = JumpAndLinkRegOp::builder(&context)
+            .rd(rd.into())
+            .rs1(rs1.into())
+            .offset((offset_value as i16).into())
+            .build();
+        set_reloc(&mut *op.borrow_mut(), reloc);
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for JumpAndLinkOp {
+    fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+        format!(
+            "jal {}, {}",
+            reg_name(self.get_rd(), target_opts),
+            self.target()
+        )
+    }
+}
+
+impl AsmPrintable for JumpAndLinkRegOp {
+    fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+        format!(
+            "jalr {}, {}",
+            reg_name(self.get_rd(), target_opts),
+            mem_addr_str(self, self.offset(), self.get_rs1(), target_opts)
+        )
+    }
+}
+
+macro_rules! upper_imm_op_base {
+    ($struct_name:ident, $op_name:literal, $pc_relative:literal, $relocs:expr) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                // The immediate occupies bits 31:12 and is sign-extended to XLEN
+                let value = ((self.imm() << 12) as i32) as i64;
+                let op = tir_backend::isema::LoadImmOp::builder(&context)
+                    .rd(self.get_rd().into())
+                    .imm(value)
+                    .pc_relative($pc_relative)
+                    .width(64)
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(upper_imm($relocs))
+                    .map(|(((_, rd), _), imm)| (rd, imm))
+                    .label($op_name);
+
+                let ((rd, (imm_value, reloc)), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .imm(imm_value.into())
+                    .build();
+                set_reloc(&mut *op.borrow_mut(), reloc);
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                let imm = match get_reloc(self) {
+                    Some(reloc) => reloc.to_string(),
+                    None => format!("{:#x}", self.imm()),
+                };
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    imm
+                )
+            }
+        }
+    };
+}
+
+upper_imm_op_base!(LuiOp, "lui", false, &[RelocKind::Hi]);
+upper_imm_op_base!(AuipcOp, "auipc", true, &[RelocKind::PcrelHi]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::{context, encode};
+    use crate::parse_asm;
+    use tir_core::utils::op_cast;
+
+    #[test]
+    fn test_label_offset_over_data() {
+        let input = ".text
+start:
+    jal x0, end
+    .p2align 3
+    .dword 1
+end:
+    beq x0, x0, start";
+
+        let context = context();
+        let module: OpRef = parse_asm(&context, input).unwrap();
+        assert_eq!(
+            encode(&module),
+            vec![
+                0x0100006f, // jal zero, end
+                0xfe0008e3, // beq zero, zero, start
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_control_flow() {
+        let context = context();
+
+        // bne t0, zero, -8
+        let op = decode(&context, &0xfe029ce3_u32.to_le_bytes(), 12).unwrap();
+        let op = op_cast::<BranchNeOp>(op).unwrap();
+        assert_eq!(op.borrow().target(), "L0x4");
+
+        // jal ra, -32
+        let op = decode(&context, &0xfe1ff0ef_u32.to_le_bytes(), 36).unwrap();
+        let op = op_cast::<JumpAndLinkOp>(op).unwrap();
+        assert_eq!(op.borrow().target(), "L0x4");
+
+        // jalr zero, 0(ra)
+        let op = decode(&context, &0x00008067_u32.to_le_bytes(), 0).unwrap();
+        assert!(op_cast::<JumpAndLinkRegOp>(op).is_some());
+
+        // Branch before the start of the stream
+        assert!(decode(&context, &0xfe029ce3_u32.to_le_bytes(), 4).is_none());
+
+        // lui t0, 0x12345
+        let op = decode(&context, &0x123452b7_u32.to_le_bytes(), 0).unwrap();
+        let op = op_cast::<LuiOp>(op).unwrap();
+        assert_eq!(op.borrow().imm(), 0x12345);
+    }
+
+    #[test]
+    fn test_branch_successors() {
+        let input = ".text
+loop:
+    addi x5, x5, -1
+    bne x5, x0, loop
+    jal x0, loop";
+
+        let context = context();
+        let module: OpRef = parse_asm(&context, input).unwrap();
+        let section = module.borrow().get_regions().next().unwrap();
+        let section = section.first().unwrap().first().unwrap();
+        let block = section
+            .borrow()
+            .get_regions()
+            .next()
+            .unwrap()
+            .first()
+            .unwrap();
+
+        let ops: Vec<_> = block.iter().collect();
+        let bne = tir_core::utils::op_dyn_cast::<dyn BranchOpInterface>(ops[1].clone()).unwrap();
+        assert_eq!(bne.borrow().get_successors().len(), 1);
+        assert_eq!(bne.borrow().get_successors()[0].get_name(), "loop");
+        assert!(bne.borrow().may_fall_through());
+
+        let jal = tir_core::utils::op_dyn_cast::<dyn BranchOpInterface>(ops[2].clone()).unwrap();
+        assert!(!jal.borrow().may_fall_through());
+    }
+}
@@ -0,0 +1,11 @@
+use lpl::{ParseStream, Parser};
+use tir_backend::Register;
+use tir_core::*;
+use tir_macros::{Op, OpAssembly, OpValidator};
+
+use super::asm::reg_name;
+use super::control::{address_label, get_label_offset};
+use super::float::verify_rounding_mode;
+use crate::{parse_gpr, DiagKind, DIALECT_NAME, FPR, GPR};
+
+include!(concat!(env!("OUT_DIR"), "/defs/ops.rs"));
@@ -0,0 +1,696 @@
+use crate::{DiagKind, FPR, GPR};
+use tir_backend::isema::WithISema;
+use tir_backend::parser::{asm_ident, comma};
+use tir_backend::AsmPrintable;
+use tir_backend::ISAParser;
+use tir_backend::TokenStream;
+use tir_core::*;
+
+use lpl::combinators::{maybe_then, optional};
+use lpl::{Diagnostic, ParseResult, ParseStream, Parser};
+
+use super::asm::{mem_addr, mem_addr_str, opcode, reg_name, register};
+use super::defs::*;
+use super::reloc::set_reloc;
+
+/// Round to nearest, ties to even
+const RM_RNE: u8 = 0b000;
+/// Use the rounding mode from the frm field of fcsr
+const RM_DYN: u8 = 0b111;
+
+/// Rounding modes and their encoding in the rm field. Encodings 5 and 6 are reserved.
+const ROUNDING_MODES: [(&str, u8); 6] = [
+    ("rne", RM_RNE),
+    ("rtz", 0b001),
+    ("rdn", 0b010),
+    ("rup", 0b011),
+    ("rmm", 0b100),
+    ("dyn", RM_DYN),
+];
+
+fn rounding_mode_name(rm: u8) -> Option<&'static str> {
+    ROUNDING_MODES
+        .iter()
+        .find(|(_, encoding)| *encoding == rm)
+        .map(|(name, _)| *name)
+}
+
+/// Verifier of instructions with a rounding mode, the reserved modes can not be encoded
+pub(super) fn verify_rounding_mode(op: &impl Op) -> std::result::Result<(), ValidateErr> {
+    let rm = op.get_attrs().get("rm").and_then(|attr| attr.as_i64());
+    match rm {
+        Some(rm) if rounding_mode_name(rm as u8).is_none() => {
+            Err(ValidateErr::ConstraintViolation(
+                op.get_operation_name(),
+                format!("rounding mode {} is reserved", rm),
+                op.get_location(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Rounding mode operand in assembly, omitted for the default mode of the instruction
+fn rounding_mode_str(rm: i64, default: u8) -> String {
+    match rounding_mode_name(rm as u8) {
+        Some(name) if rm as u8 != default => format!(", {}", name),
+        _ => String::new(),
+    }
+}
+
+/// Optional rounding mode operand, `, rne`. Instructions, that omit it, use `default`.
+fn with_rounding_mode<'a, P, Output>(
+    operands: P,
+    default: u8,
+) -> impl Parser<'a, TokenStream<'a>, (Output, u8)>
+where
+    P: Parser<'a, TokenStream<'a>, Output> + 'a,
+    Output: 'a,
+{
+    let rm = optional(comma().and_then(asm_ident()).map(|(_, name)| name))
+        .try_map(move |name, s| match name {
+            None => Ok(default),
+            Some(name) => ROUNDING_MODES
+                .iter()
+                .find(|(mode, _)| mode.eq_ignore_ascii_case(name))
+                .map(|(_, encoding)| *encoding)
+                .ok_or(Into::<Diagnostic>::into(DiagKind::UnknownRoundingMode(
+                    name.to_string(),
+                    s,
+                ))),
+        })
+        .label("rounding mode");
+    // The rounding mode may be the last token of the input
+    maybe_then(operands, rm).map(move |(operands, rm)| (operands, rm.unwrap_or(default)))
+}
+
+macro_rules! rounding_mode_impl {
+    ($struct_name:ident) => {
+        impl $struct_name {
+            /// Name of the rounding mode, `dyn` refers to the frm field of fcsr
+            pub fn get_rm_name(&self) -> String {
+                rounding_mode_name(self.rm() as u8)
+                    .unwrap_or_default()
+                    .to_string()
+            }
+        }
+    };
+}
+
+macro_rules! float_op_base {
+    // Loads from memory to floating-point registers
+    (load $struct_name:ident { name = $op_name:literal, width = $width:literal }) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Read]
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::FLoadOp::builder(&context)
+                    .dst(self.get_rd().into())
+                    .base_addr(self.get_rs1().into())
+                    .offset(self.offset() as i16)
+                    .width($width)
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(register::<FPR>())
+                    .and_then(comma())
+                    .and_then(mem_addr())
+                    .map(|(((_, rd), _), (offset, rs1))| (rd, offset, rs1))
+                    .label($op_name);
+                let ((rd, (offset, reloc), rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .offset((offset as i16).into())
+                    .build();
+                set_reloc(&mut *op.borrow_mut(), reloc);
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    mem_addr_str(self, self.offset(), self.get_rs1(), target_opts),
+                )
+            }
+        }
+    };
+
+    // Stores from floating-point registers to memory
+    (store $struct_name:ident { name = $op_name:literal, width = $width:literal }) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Write]
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::StoreOp::builder(&context)
+                    .src(self.get_rs2().into())
+                    .base_addr(self.get_rs1().into())
+                    .offset(self.offset() as i16)
+                    .width($width)
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(register::<FPR>())
+                    .and_then(comma())
+                    .and_then(mem_addr())
+                    .map(|(((_, rs2), _), (offset, rs1))| (rs2, offset, rs1))
+                    .label($op_name);
+                let ((rs2, (offset, reloc), rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rs1(rs1.into())
+                    .rs2(rs2.into())
+                    .offset((offset as i16).into())
+                    .build();
+                set_reloc(&mut *op.borrow_mut(), reloc);
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rs2(), target_opts),
+                    mem_addr_str(self, self.offset(), self.get_rs1(), target_opts),
+                )
+            }
+        }
+    };
+
+    // Fused multiply-add, R4-format. rs3 occupies the upper bits of funct7.
+    (fma $struct_name:ident { name = $op_name:literal, isema = { $($attr:ident = $value:expr),* } }) => {
+        rounding_mode_impl!($struct_name);
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::FMaddOp::builder(&context)
+                    .rs1(self.get_rs1().into())
+                    .rs2(self.get_rs2().into())
+                    .rs3(self.get_rs3().into())
+                    .rd(self.get_rd().into())
+                    .rm(self.get_rm_name())
+                    $(.$attr($value.into()))*
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let sources = register::<FPR>()
+                    .and_then(comma())
+                    .and_then(register::<FPR>())
+                    .and_then(comma())
+                    .and_then(register::<FPR>())
+                    .map(|((((rs1, _), rs2), _), rs3)| (rs1, rs2, rs3));
+                let operands = opcode($op_name)
+                    .and_then(register::<FPR>())
+                    .and_then(comma())
+                    .and_then(sources);
+                let parser = with_rounding_mode(operands, RM_DYN)
+                    .map(|((((_, rd), _), (rs1, rs2, rs3)), rm)| (rd, rs1, rs2, rs3, rm))
+                    .label($op_name);
+                let ((rd, rs1, rs2, rs3, rm), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .rs2(rs2.into())
+                    .rs3(rs3.into())
+                    .rm(rm.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, {}, {}{}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts),
+                    reg_name(self.get_rs2(), target_opts),
+                    reg_name(self.get_rs3(), target_opts),
+                    rounding_mode_str(self.rm(), RM_DYN),
+                )
+            }
+        }
+    };
+
+    // Two source operations with a rounding mode
+    (binary_rm $struct_name:ident { name = $op_name:literal, isema = $isema:ident { $($attr:ident = $value:expr),* } }) => {
+        rounding_mode_impl!($struct_name);
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::$isema::builder(&context)
+                    .rs1(self.get_rs1().into())
+                    .rs2(self.get_rs2().into())
+                    .rd(self.get_rd().into())
+                    .rm(self.get_rm_name())
+                    $(.$attr($value.into()))*
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let operands = opcode($op_name)
+                    .and_then(register::<FPR>())
+                    .and_then(comma())
+                    .and_then(register::<FPR>())
+                    .and_then(comma())
+                    .and_then(register::<FPR>());
+                let parser = with_rounding_mode(operands, RM_DYN)
+                    .map(|((((((_, rd), _), rs1), _), rs2), rm)| (rd, rs1, rs2, rm))
+                    .label($op_name);
+                let ((rd, rs1, rs2, rm), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .rs2(rs2.into())
+                    .rm(rm.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, {}{}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts),
+                    reg_name(self.get_rs2(), target_opts),
+                    rounding_mode_str(self.rm(), RM_DYN),
+                )
+            }
+        }
+    };
+
+    // Two source operations without rounding, funct3 selects the operation
+    (binary $struct_name:ident { name = $op_name:literal, rd = $rd:ident, isema = $isema:ident { $($attr:ident = $value:expr),* } }) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::$isema::builder(&context)
+                    .rs1(self.get_rs1().into())
+                    .rs2(self.get_rs2().into())
+                    .rd(self.get_rd().into())
+                    $(.$attr($value.into()))*
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(register::<$rd>())
+                    .and_then(comma())
+                    .and_then(register::<FPR>())
+                    .and_then(comma())
+                    .and_then(register::<FPR>())
+                    .map(|(((((_, rd), _), rs1), _), rs2)| (rd, rs1, rs2))
+                    .label($op_name);
+                let ((rd, rs1, rs2), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .rs2(rs2.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts),
+                    reg_name(self.get_rs2(), target_opts),
+                )
+            }
+        }
+    };
+
+    // Single source operations with a rounding mode, the rs2 field selects the operation
+    (unary_rm $struct_name:ident { name = $op_name:literal, rd = $rd:ident, rs1 = $rs1:ident, rm = $default_rm:ident, isema = $isema:ident { $($attr:ident = $value:expr),* } }) => {
+        rounding_mode_impl!($struct_name);
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::$isema::builder(&context)
+                    .rs1(self.get_rs1().into())
+                    .rd(self.get_rd().into())
+                    .rm(self.get_rm_name())
+                    $(.$attr($value.into()))*
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let operands = opcode($op_name)
+                    .and_then(register::<$rd>())
+                    .and_then(comma())
+                    .and_then(register::<$rs1>());
+                let parser = with_rounding_mode(operands, $default_rm)
+                    .map(|((((_, rd), _), rs1), rm)| (rd, rs1, rm))
+                    .label($op_name);
+                let ((rd, rs1, rm), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .rm(rm.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}{}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts),
+                    rounding_mode_str(self.rm(), $default_rm),
+                )
+            }
+        }
+    };
+
+    // Single source operations without rounding
+    (unary $struct_name:ident { name = $op_name:literal, rd = $rd:ident, rs1 = $rs1:ident, isema = $isema:ident { $($attr:ident = $value:expr),* } }) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::$isema::builder(&context)
+                    .rs1(self.get_rs1().into())
+                    .rd(self.get_rd().into())
+                    $(.$attr($value.into()))*
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(register::<$rd>())
+                    .and_then(comma())
+                    .and_then(register::<$rs1>())
+                    .map(|(((_, rd), _), rs1)| (rd, rs1))
+                    .label($op_name);
+                let ((rd, rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts),
+                )
+            }
+        }
+    };
+}
+
+macro_rules! float_ops {
+    ($($kind:ident { $($struct_name:ident => { $($fields:tt)* })* })*) => {
+        $($(
+        float_op_base!($kind $struct_name { $($fields)* });
+        )*)*
+    };
+}
+
+float_ops! {
+    load {
+        FLoadWord => { name = "flw", width = 32 }
+        FLoadDouble => { name = "fld", width = 64 }
+    }
+    store {
+        FStoreWord => { name = "fsw", width = 32 }
+        FStoreDouble => { name = "fsd", width = 64 }
+    }
+    fma {
+        FMaddSOp => { name = "fmadd.s", isema = { width = 32, negate_product = false, negate_addend = false } }
+        FMsubSOp => { name = "fmsub.s", isema = { width = 32, negate_product = false, negate_addend = true } }
+        FNmsubSOp => { name = "fnmsub.s", isema = { width = 32, negate_product = true, negate_addend = false } }
+        FNmaddSOp => { name = "fnmadd.s", isema = { width = 32, negate_product = true, negate_addend = true } }
+        FMaddDOp => { name = "fmadd.d", isema = { width = 64, negate_product = false, negate_addend = false } }
+        FMsubDOp => { name = "fmsub.d", isema = { width = 64, negate_product = false, negate_addend = true } }
+        FNmsubDOp => { name = "fnmsub.d", isema = { width = 64, negate_product = true, negate_addend = false } }
+        FNmaddDOp => { name = "fnmadd.d", isema = { width = 64, negate_product = true, negate_addend = true } }
+    }
+    binary_rm {
+        FAddSOp => { name = "fadd.s", isema = FAddOp { width = 32 } }
+        FSubSOp => { name = "fsub.s", isema = FSubOp { width = 32 } }
+        FMulSOp => { name = "fmul.s", isema = FMulOp { width = 32 } }
+        FDivSOp => { name = "fdiv.s", isema = FDivOp { width = 32 } }
+        FAddDOp => { name = "fadd.d", isema = FAddOp { width = 64 } }
+        FSubDOp => { name = "fsub.d", isema = FSubOp { width = 64 } }
+        FMulDOp => { name = "fmul.d", isema = FMulOp { width = 64 } }
+        FDivDOp => { name = "fdiv.d", isema = FDivOp { width = 64 } }
+    }
+    binary {
+        FSgnjSOp => { name = "fsgnj.s", rd = FPR, isema = FSgnjOp { width = 32, mode = "copy" } }
+        FSgnjnSOp => { name = "fsgnjn.s", rd = FPR, isema = FSgnjOp { width = 32, mode = "negate" } }
+        FSgnjxSOp => { name = "fsgnjx.s", rd = FPR, isema = FSgnjOp { width = 32, mode = "xor" } }
+        FMinSOp => { name = "fmin.s", rd = FPR, isema = FMinOp { width = 32 } }
+        FMaxSOp => { name = "fmax.s", rd = FPR, isema = FMaxOp { width = 32 } }
+        FEqSOp => { name = "feq.s", rd = GPR, isema = FCmpOp { width = 32, cond = "eq" } }
+        FLtSOp => { name = "flt.s", rd = GPR, isema = FCmpOp { width = 32, cond = "lt" } }
+        FLeSOp => { name = "fle.s", rd = GPR, isema = FCmpOp { width = 32, cond = "le" } }
+        FSgnjDOp => { name = "fsgnj.d", rd = FPR, isema = FSgnjOp { width = 64, mode = "copy" } }
+        FSgnjnDOp => { name = "fsgnjn.d", rd = FPR, isema = FSgnjOp { width = 64, mode = "negate" } }
+        FSgnjxDOp => { name = "fsgnjx.d", rd = FPR, isema = FSgnjOp { width = 64, mode = "xor" } }
+        FMinDOp => { name = "fmin.d", rd = FPR, isema = FMinOp { width = 64 } }
+        FMaxDOp => { name = "fmax.d", rd = FPR, isema = FMaxOp { width = 64 } }
+        FEqDOp => { name = "feq.d", rd = GPR, isema = FCmpOp { width = 64, cond = "eq" } }
+        FLtDOp => { name = "flt.d", rd = GPR, isema = FCmpOp { width = 64, cond = "lt" } }
+        FLeDOp => { name = "fle.d", rd = GPR, isema = FCmpOp { width = 64, cond = "le" } }
+    }
+    unary_rm {
+        FSqrtSOp => { name = "fsqrt.s", rd = FPR, rs1 = FPR, rm = RM_DYN, isema = FSqrtOp { width = 32 } }
+        FCvtWSOp => { name = "fcvt.w.s", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f32", to = "i32" } }
+        FCvtWuSOp => { name = "fcvt.wu.s", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f32", to = "u32" } }
+        FCvtLSOp => { name = "fcvt.l.s", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f32", to = "i64" } }
+        FCvtLuSOp => { name = "fcvt.lu.s", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f32", to = "u64" } }
+        FCvtSWOp => { name = "fcvt.s.w", rd = FPR, rs1 = GPR, rm = RM_DYN, isema = FCvtOp { from = "i32", to = "f32" } }
+        FCvtSWuOp => { name = "fcvt.s.wu", rd = FPR, rs1 = GPR, rm = RM_DYN, isema = FCvtOp { from = "u32", to = "f32" } }
+        FCvtSLOp => { name = "fcvt.s.l", rd = FPR, rs1 = GPR, rm = RM_DYN, isema = FCvtOp { from = "i64", to = "f32" } }
+        FCvtSLuOp => { name = "fcvt.s.lu", rd = FPR, rs1 = GPR, rm = RM_DYN, isema = FCvtOp { from = "u64", to = "f32" } }
+        FSqrtDOp => { name = "fsqrt.d", rd = FPR, rs1 = FPR, rm = RM_DYN, isema = FSqrtOp { width = 64 } }
+        FCvtSDOp => { name = "fcvt.s.d", rd = FPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f64", to = "f32" } }
+        // Conversions, that are always exact, default to rne
+        FCvtDSOp => { name = "fcvt.d.s", rd = FPR, rs1 = FPR, rm = RM_RNE, isema = FCvtOp { from = "f32", to = "f64" } }
+        FCvtWDOp => { name = "fcvt.w.d", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f64", to = "i32" } }
+        FCvtWuDOp => { name = "fcvt.wu.d", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f64", to = "u32" } }
+        FCvtLDOp => { name = "fcvt.l.d", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f64", to = "i64" } }
+        FCvtLuDOp => { name = "fcvt.lu.d", rd = GPR, rs1 = FPR, rm = RM_DYN, isema = FCvtOp { from = "f64", to = "u64" } }
+        FCvtDWOp => { name = "fcvt.d.w", rd = FPR, rs1 = GPR, rm = RM_RNE, isema = FCvtOp { from = "i32", to = "f64" } }
+        FCvtDWuOp => { name = "fcvt.d.wu", rd = FPR, rs1 = GPR, rm = RM_RNE, isema = FCvtOp { from = "u32", to = "f64" } }
+        FCvtDLOp => { name = "fcvt.d.l", rd = FPR, rs1 = GPR, rm = RM_DYN, isema = FCvtOp { from = "i64", to = "f64" } }
+        FCvtDLuOp => { name = "fcvt.d.lu", rd = FPR, rs1 = GPR, rm = RM_DYN, isema = FCvtOp { from = "u64", to = "f64" } }
+    }
+    unary {
+        FMvXWOp => { name = "fmv.x.w", rd = GPR, rs1 = FPR, isema = FMvOp { width = 32, to_float = false } }
+        FMvWXOp => { name = "fmv.w.x", rd = FPR, rs1 = GPR, isema = FMvOp { width = 32, to_float = true } }
+        FClassSOp => { name = "fclass.s", rd = GPR, rs1 = FPR, isema = FClassOp { width = 32 } }
+        FMvXDOp => { name = "fmv.x.d", rd = GPR, rs1 = FPR, isema = FMvOp { width = 64, to_float = false } }
+        FMvDXOp => { name = "fmv.d.x", rd = FPR, rs1 = GPR, isema = FMvOp { width = 64, to_float = true } }
+        FClassDOp => { name = "fclass.d", rd = GPR, rs1 = FPR, isema = FClassOp { width = 64 } }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+    use tir_core::builtin::ModuleOp;
+    use tir_core::utils::op_cast;
+
+    #[test]
+    fn test_disassemble_float_invalid() {
+        let context = context();
+
+        // fadd.s with the reserved rounding mode 5
+        assert!(decode(&context, &0x0020d053_u32.to_le_bytes(), 0).is_none());
+        // fsgnj.s with funct3 = 3 is not defined
+        assert!(decode(&context, &0x2020b053_u32.to_le_bytes(), 0).is_none());
+        // fcvt.w.s with rs2 = 4 is not defined
+        assert!(decode(&context, &0xc0407553_u32.to_le_bytes(), 0).is_none());
+    }
+
+    #[test]
+    fn test_parse_float_errors() {
+        let context = context();
+        // Integer registers are not accepted as floating-point operands and vice versa
+        assert!(parse_asm(&context, ".text\nl:\n fadd.s x5, ft1, ft2").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n feq.s ft0, ft1, ft2").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n flw ft0, 8(ft1)").is_err());
+
+        let err = parse_asm(&context, ".text\nl:\n fadd.s ft0, ft1, ft2, rnd").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Unknown rounding mode 'rnd', expected one of rne, rtz, rdn, rup, rmm or dyn"
+        );
+        // Operations, that do not round, do not accept a rounding mode
+        assert!(parse_asm(&context, ".text\nl:\n fsgnj.s ft0, ft1, ft2, rne").is_err());
+    }
+
+    #[test]
+    fn test_float_sema() {
+        let context = context();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+
+        let op = FNmsubDOp::builder(&context)
+            .rd(FPR::F10.into())
+            .rs1(FPR::F11.into())
+            .rs2(FPR::F12.into())
+            .rs3(FPR::F13.into())
+            .rm(1_i64.into())
+            .build();
+        builder.insert(&op);
+        let op = FCvtWuSOp::builder(&context)
+            .rd(GPR::X10.into())
+            .rs1(FPR::F0.into())
+            .rm(7_i64.into())
+            .build();
+        builder.insert(&op);
+        let op = FLoadWord::builder(&context)
+            .rd(FPR::F0.into())
+            .rs1(GPR::X10.into())
+            .offset(8_i64.into())
+            .build();
+        builder.insert(&op);
+        let op = FLtDOp::builder(&context)
+            .rd(GPR::X10.into())
+            .rs1(FPR::F10.into())
+            .rs2(FPR::F11.into())
+            .build();
+        builder.insert(&op);
+
+        assert!(tir_backend::isema::convert_to_isema(&module).is_ok());
+
+        let ops: Vec<_> = module.borrow().get_body().iter().collect();
+        let fma = op_cast::<tir_backend::isema::FMaddOp>(ops[0].clone()).unwrap();
+        assert_eq!(fma.borrow().rs3(), "f13");
+        assert_eq!(fma.borrow().rm(), "rtz");
+        assert_eq!(fma.borrow().width(), 64);
+        assert!(fma.borrow().negate_product());
+        assert!(!fma.borrow().negate_addend());
+        let cvt = op_cast::<tir_backend::isema::FCvtOp>(ops[1].clone()).unwrap();
+        assert_eq!(cvt.borrow().rd(), "x10");
+        assert_eq!((cvt.borrow().from(), cvt.borrow().to()), ("f32", "u32"));
+        assert_eq!(cvt.borrow().rm(), "dyn");
+        let load = op_cast::<tir_backend::isema::FLoadOp>(ops[2].clone()).unwrap();
+        assert_eq!(load.borrow().dst(), "f0");
+        assert_eq!(load.borrow().width(), 32);
+        let cmp = op_cast::<tir_backend::isema::FCmpOp>(ops[3].clone()).unwrap();
+        assert_eq!(cmp.borrow().cond(), "lt");
+    }
+}
@@ -1,54 +1,24 @@
-use crate::utils::{ITypeInstr, STypeInstr};
-use crate::{parse_gpr, DiagKind, GPR};
+use crate::{parse_gpr, DiagKind};
 use tir_backend::isema::WithISema;
-use tir_backend::parser::{asm_ident, close_paren, comma, number, open_paren};
-use tir_backend::BinaryEmittable;
+use tir_backend::parser::{asm_ident, comma};
+use tir_backend::AsmPrintable;
 use tir_backend::ISAParser;
 use tir_backend::TokenStream;
-use tir_core::OpAssembly;
 use tir_core::*;
 use tir_macros::{lowercase, uppercase};
-use tir_macros::{Op, OpAssembly, OpValidator};
 
 use lpl::{Diagnostic, ParseResult, ParseStream, Parser};
 
-use crate::DIALECT_NAME;
-
-const LOAD_OPCODE: u8 = 0b0000011;
-const STORE_OPCODE: u8 = 0b0100011;
+use super::asm::{mem_addr, mem_addr_str, reg_name};
+use super::defs::*;
+use super::reloc::set_reloc;
 
 macro_rules! load_op_base {
-    ($struct_name:ident, $op_name:literal, $funct3:literal, $width:literal, $sign_extend:literal) => {
-#[rustfmt::skip]
-        #[derive(Op, OpAssembly, OpValidator)]
-        #[operation(name = $op_name, dialect = riscv, known_attrs(offset: IntegerAttr))]
-        pub struct $struct_name {
-            #[operand]
-            rd: tir_backend::Register::<GPR>,
-            #[operand]
-            rs1: tir_backend::Register::<GPR>,
-            r#impl: OpImpl,
-        }
-
-        impl BinaryEmittable for $struct_name {
-            fn encode(
-                &self,
-                _target_opts: &tir_backend::TargetOptions,
-                stream: &mut Box<dyn tir_backend::BinaryStream>,
-            ) -> tir_core::Result<()> {
-                let instr = ITypeInstr::builder()
-                    .opcode(LOAD_OPCODE)
-                    .rd(self.get_rd().as_arch().encode())
-                    .funct3($funct3)
-                    .rs1(self.get_rs1().as_arch().encode())
-                    .imm(
-                        self.get_offset_attr()
-                            .try_into()
-                            .map_err(|_| tir_core::Error::Unknown)?,
-                    )
-                    .build();
-                stream.write(&instr.to_bytes());
-                Ok(())
+    ($struct_name:ident, $op_name:literal, $width:literal, $sign_extend:literal) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Read]
             }
         }
 
@@ -59,9 +29,9 @@ macro_rules! load_op_base {
                 let op = tir_backend::isema::LoadOp::builder(&context)
                     .dst(self.get_rd().into())
                     .base_addr(self.get_rs1().into())
-                    .offset(self.get_offset_attr().clone())
-                    .width($width.into())
-                    .sign_extend($sign_extend.into())
+                    .offset(self.offset() as i16)
+                    .width($width)
+                    .sign_extend($sign_extend)
                     .build();
                 builder.insert(&op);
             }
@@ -87,105 +57,57 @@ macro_rules! load_op_base {
                         .label("register")
                 };
 
-                let offset = number().map(|num| num as i16).label("offset");
-
-                let addr = offset
-                    .and_then(open_paren())
-                    .and_then(reg())
-                    .and_then(close_paren())
-                    .map(|(((offset, _), reg), _)| (offset, reg))
-                    .label("address");
-
                 let parser = opcode
                     .and_then(reg())
                     .and_then(comma())
-                    .and_then(addr)
+                    .and_then(mem_addr())
                     .map(|(((_, rd), _), (offset, ra))| (rd, offset, ra))
                     .label($op_name);
 
-                let ((rd, offset_value, base_reg), ni) = parser.parse(input)?;
+                let ((rd, (offset_value, reloc), base_reg), ni) = parser.parse(input)?;
 
                 let builder = asm_ctx.get_builder();
                 let context = builder.get_context();
                 let op = $struct_name::builder(&context)
                     .rs1(base_reg.into())
                     .rd(rd.into())
-                    .offset(offset_value.into())
+                    .offset((offset_value as i16).into())
                     .build();
+                set_reloc(&mut *op.borrow_mut(), reloc);
                 builder.insert(&op);
 
                 Ok(((), ni))
             }
         }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    mem_addr_str(self, self.offset(), self.get_rs1(), target_opts)
+                )
+            }
+        }
     };
 }
 
 macro_rules! load_ops {
     // I-format Load operations
-    ($($struct_name:ident => { name = $op_name:literal, funct3 = $funct3:literal, width = $width:literal, sign_extend = $sign_extend:literal })*) => {
+    ($($struct_name:ident => { name = $op_name:literal, width = $width:literal, sign_extend = $sign_extend:literal })*) => {
         $(
-        load_op_base!($struct_name, $op_name, $funct3, $width, $sign_extend);
+        load_op_base!($struct_name, $op_name, $width, $sign_extend);
         )*
-
-        pub fn disassemble_load_instr(context: &ContextRef, stream: &[u8]) -> Option<OpRef> {
-            if stream.len() < 4 {
-                return None;
-            }
-
-            let instr = ITypeInstr::from_bytes(&stream[0..4].try_into().unwrap());
-            if instr.opcode() != LOAD_OPCODE {
-                return None;
-            }
-
-            let rd = GPR::try_from(instr.rd() as usize).ok()?;
-            let rs1 = GPR::try_from(instr.rs1() as usize).ok()?;
-            let imm = instr.imm();
-
-            match instr.funct3() {
-                $(
-                $funct3 => {
-                    let op = $struct_name::builder(&context).rd(rd.into()).rs1(rs1.into()).offset(imm.into()).build();
-                    Some(op)
-                },
-                )*
-                _ => None,
-            }
-        }
     }
 }
 
 macro_rules! store_op_base {
-    ($struct_name:ident, $op_name:literal, $funct3:literal, $width:literal) => {
-#[rustfmt::skip]
-        #[derive(Op, OpAssembly, OpValidator)]
-        #[operation(name = $op_name, dialect = riscv, known_attrs(offset: IntegerAttr))]
-        pub struct $struct_name {
-            #[operand]
-            rs1: tir_backend::Register::<GPR>,
-            #[operand]
-            rs2: tir_backend::Register::<GPR>,
-            r#impl: OpImpl,
-        }
-
-        impl BinaryEmittable for $struct_name {
-            fn encode(
-                &self,
-                _target_opts: &tir_backend::TargetOptions,
-                stream: &mut Box<dyn tir_backend::BinaryStream>,
-            ) -> tir_core::Result<()> {
-                let instr = STypeInstr::builder()
-                    .opcode(STORE_OPCODE)
-                    .funct3($funct3)
-                    .rs1(self.get_rs1().as_arch().encode())
-                    .rs2(self.get_rs2().as_arch().encode())
-                    .imm(
-                        self.get_offset_attr()
-                            .try_into()
-                            .map_err(|_| tir_core::Error::Unknown)?,
-                    )
-                    .build();
-                stream.write(&instr.to_bytes());
-                Ok(())
+    ($struct_name:ident, $op_name:literal, $width:literal) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl MemoryEffects for $struct_name {
+            fn get_effects(&self) -> Vec<MemoryEffect> {
+                vec![MemoryEffect::Write]
             }
         }
 
@@ -196,8 +118,8 @@ macro_rules! store_op_base {
                 let op = tir_backend::isema::StoreOp::builder(&context)
                     .src(self.get_rs2().into())
                     .base_addr(self.get_rs1().into())
-                    .offset(self.get_offset_attr().clone())
-                    .width($width.into())
+                    .offset(self.offset() as i16)
+                    .width($width)
                     .build();
                 builder.insert(&op);
             }
@@ -223,86 +145,64 @@ macro_rules! store_op_base {
                         .label("register")
                 };
 
-                let offset = number().map(|num| num as i16).label("offset");
-
-                let addr = offset
-                    .and_then(open_paren())
-                    .and_then(reg())
-                    .and_then(close_paren())
-                    .map(|(((offset, _), reg), _)| (offset, reg))
-                    .label("address");
-
                 let parser = opcode
                     .and_then(reg())
                     .and_then(comma())
-                    .and_then(addr)
+                    .and_then(mem_addr())
                     .map(|(((_, rd), _), (offset, ra))| (rd, offset, ra))
                     .label($op_name);
 
-                let ((rs2, offset_value, base_reg), ni) = parser.parse(input)?;
+                let ((rs2, (offset_value, reloc), base_reg), ni) = parser.parse(input)?;
 
                 let builder = asm_ctx.get_builder();
                 let context = builder.get_context();
                 let op = $struct_name::builder(&context)
                     .rs1(base_reg.into())
                     .rs2(rs2.into())
-                    .offset(offset_value.into())
+                    .offset((offset_value as i16).into())
                     .build();
+                set_reloc(&mut *op.borrow_mut(), reloc);
                 builder.insert(&op);
 
                 Ok(((), ni))
             }
         }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rs2(), target_opts),
+                    mem_addr_str(self, self.offset(), self.get_rs1(), target_opts)
+                )
+            }
+        }
     };
 }
 
 macro_rules! store_ops {
-    // I-format Load operations
-    ($($struct_name:ident => { name = $op_name:literal, funct3 = $funct3:literal, width = $width:literal })*) => {
+    // S-format Store operations
+    ($($struct_name:ident => { name = $op_name:literal, width = $width:literal })*) => {
         $(
-        store_op_base!($struct_name, $op_name, $funct3, $width);
+        store_op_base!($struct_name, $op_name, $width);
         )*
-
-        pub fn disassemble_store_instr(context: &ContextRef, stream: &[u8]) -> Option<OpRef> {
-            if stream.len() < 4 {
-                return None;
-            }
-
-            let instr = STypeInstr::from_bytes(&stream[0..4].try_into().unwrap());
-            if instr.opcode() != STORE_OPCODE {
-                return None;
-            }
-
-            let rs1 = GPR::try_from(instr.rs1() as usize).ok()?;
-            let rs2 = GPR::try_from(instr.rs2() as usize).ok()?;
-            let imm = instr.imm();
-
-            match instr.funct3() {
-                $(
-                $funct3 => {
-                    let op = $struct_name::builder(&context).rs1(rs1.into()).rs2(rs2.into()).offset(imm.into()).build();
-                    Some(op)
-                },
-                )*
-                _ => None,
-            }
-        }
     }
 }
 
 load_ops! {
-    LoadByte => {name = "lb", funct3 = 0b000, width = 8, sign_extend = true }
-    LoadHalfword => {name = "lh", funct3 = 0b001, width = 16, sign_extend = true }
-    LoadWord => {name = "lw", funct3 = 0b010, width = 32, sign_extend = true }
-    LoadDouble => {name = "ld", funct3 = 0b011, width = 64, sign_extend = true }
-    LoadByteUnsigned => {name = "lbu", funct3 = 0b100, width = 8, sign_extend = false }
-    LoadHalfwordUnsigned => {name = "lhu", funct3 = 0b101, width = 16, sign_extend = false }
-    LoadWordUnsigned => {name = "lwu", funct3 = 0b110, width = 32, sign_extend = false }
+    LoadByte => {name = "lb", width = 8, sign_extend = true }
+    LoadHalfword => {name = "lh", width = 16, sign_extend = true }
+    LoadWord => {name = "lw", width = 32, sign_extend = true }
+    LoadDouble => {name = "ld", width = 64, sign_extend = true }
+    LoadByteUnsigned => {name = "lbu", width = 8, sign_extend = false }
+    LoadHalfwordUnsigned => {name = "lhu", width = 16, sign_extend = false }
+    LoadWordUnsigned => {name = "lwu", width = 32, sign_extend = false }
 }
 
 store_ops! {
-    StoreByte => {name = "sb", funct3 = 0b000, width = 8 }
-    StoreHalfword => {name = "sh", funct3 = 0b001, width = 16 }
-    StoreWord => {name = "sw", funct3 = 0b010, width = 32 }
-    StoreDouble => {name = "sd", funct3 = 0b011, width = 64 }
+    StoreByte => {name = "sb", width = 8 }
+    StoreHalfword => {name = "sh", width = 16 }
+    StoreWord => {name = "sw", width = 32 }
+    StoreDouble => {name = "sd", width = 64 }
 }
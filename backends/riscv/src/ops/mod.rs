@@ -1,5 +1,34 @@
 mod alu;
+mod asm;
+mod atomic;
+mod compress;
+mod compressed;
+mod control;
+mod defs;
+mod float;
 mod mem;
+mod pseudo;
+mod reloc;
+mod system;
+#[cfg(test)]
+pub(crate) mod testing;
+mod unknown;
 
-pub use alu::*;
-pub use mem::*;
+pub use compress::*;
+pub use compressed::*;
+pub use control::*;
+pub use defs::*;
+pub use pseudo::*;
+pub use reloc::*;
+pub use system::*;
+
+/// Size of the encoded operation of the RISC-V dialect in bytes
+pub fn get_instr_size(op: &dyn tir_core::Op) -> u64 {
+    if let Some(word) = (op as &dyn std::any::Any).downcast_ref::<UnknownWordOp>() {
+        word.size() as u64
+    } else if compressed::is_compressed(op) {
+        2
+    } else {
+        4 * pseudo::pseudo_instr_count(op).unwrap_or(1)
+    }
+}
@@ -0,0 +1,1265 @@
+//! Pseudo-instructions of the assembler along with their expansion to real instructions
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::GPR;
+use tir_backend::isema::WithISema;
+use tir_backend::parser::{asm_ident, comma};
+use tir_backend::AsmPrintable;
+use tir_backend::BinaryEmittable;
+use tir_backend::ISAParser;
+use tir_backend::Register;
+use tir_backend::{AsmToken, TokenStream};
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::{op_cast, op_dyn_cast};
+use tir_core::*;
+
+use lpl::{ParseResult, ParseStream, Parser};
+
+use super::asm::{imm, opcode, reg, reg_name};
+use super::compress::reg_num;
+use super::control::get_label_offset;
+use super::defs::*;
+use super::reloc::{get_reloc, LabelRef, LabelReloc};
+
+/// Step of the sequence, that writes an immediate to a register
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LiStep {
+    Lui(i64),
+    Addi(i64),
+    Addiw(i64),
+    Slli(i64),
+}
+
+impl LiStep {
+    /// Value of the register after the step
+    fn apply(self, value: i64) -> i64 {
+        match self {
+            LiStep::Lui(imm) => ((imm << 12) as i32) as i64,
+            LiStep::Addi(imm) => value.wrapping_add(imm),
+            LiStep::Addiw(imm) => (value.wrapping_add(imm) as i32) as i64,
+            LiStep::Slli(shamt) => value.wrapping_shl(shamt as u32),
+        }
+    }
+}
+
+fn is_int(value: i64, bits: u32) -> bool {
+    (value << (64 - bits)) >> (64 - bits) == value
+}
+
+/// Instructions, that write the immediate to a register on RV64. 32-bit values take
+/// `lui` and `addiw`, larger values are built from their upper bits, that are shifted
+/// into place with `slli` and completed with `addi`. This is the base algorithm of
+/// LLVM, without its special cases for shorter sequences.
+fn li_sequence(value: i64) -> Vec<LiStep> {
+    let mut steps = vec![];
+    li_steps(value, &mut steps);
+    steps
+}
+
+fn li_steps(value: i64, steps: &mut Vec<LiStep>) {
+    let lo12 = (value << 52) >> 52;
+
+    if is_int(value, 32) {
+        let hi20 = ((value + 0x800) >> 12) & 0xfffff;
+        if hi20 != 0 {
+            steps.push(LiStep::Lui(hi20));
+        }
+        if lo12 != 0 || hi20 == 0 {
+            steps.push(if hi20 != 0 {
+                LiStep::Addiw(lo12)
+            } else {
+                LiStep::Addi(lo12)
+            });
+        }
+        return;
+    }
+
+    let mut upper = value.wrapping_sub(lo12);
+    let mut shamt = 0;
+    if !is_int(upper, 32) {
+        shamt = upper.trailing_zeros() as i64;
+        upper >>= shamt;
+        // Shift by 12 bits less, if the remaining bits can be written with lui alone
+        if shamt > 12 && !is_int(upper, 12) && is_int(upper << 12, 32) {
+            shamt -= 12;
+            upper <<= 12;
+        }
+    }
+
+    li_steps(upper, steps);
+    if shamt != 0 {
+        steps.push(LiStep::Slli(shamt));
+    }
+    if lo12 != 0 {
+        steps.push(LiStep::Addi(lo12));
+    }
+}
+
+/// Number of real instructions of a pseudo-instruction. Returns `None` for other
+/// operations.
+pub(super) fn pseudo_instr_count(op: &dyn Op) -> Option<u64> {
+    let op = op as &dyn Any;
+    if let Some(li) = op.downcast_ref::<PseudoLoadImmOp>() {
+        return Some(li_sequence(li.imm()).len() as u64);
+    }
+
+    let type_id = op.type_id();
+    if [
+        TypeId::of::<PseudoLoadAddrOp>(),
+        TypeId::of::<PseudoCallOp>(),
+        TypeId::of::<PseudoTailOp>(),
+    ]
+    .contains(&type_id)
+    {
+        Some(2)
+    } else if [
+        TypeId::of::<PseudoMoveOp>(),
+        TypeId::of::<PseudoNotOp>(),
+        TypeId::of::<PseudoNegOp>(),
+        TypeId::of::<PseudoNopOp>(),
+        TypeId::of::<PseudoJumpOp>(),
+        TypeId::of::<PseudoJumpRegOp>(),
+        TypeId::of::<PseudoRetOp>(),
+        TypeId::of::<PseudoBranchEqzOp>(),
+        TypeId::of::<PseudoBranchNezOp>(),
+        TypeId::of::<PseudoSetEqzOp>(),
+        TypeId::of::<PseudoSetNezOp>(),
+        TypeId::of::<PseudoSextWOp>(),
+    ]
+    .contains(&type_id)
+    {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+fn encode_ops(
+    ops: &[OpRef],
+    target_opts: &tir_backend::TargetOptions,
+    stream: &mut Box<dyn tir_backend::BinaryStream>,
+) -> tir_core::Result<()> {
+    for op in ops {
+        crate::encode_op(op, target_opts, stream).unwrap_or(Err(tir_core::Error::Unknown))?;
+    }
+    Ok(())
+}
+
+fn convert_ops(ops: &[OpRef], builder: &OpBuilder) {
+    for op in ops {
+        if let Some(op) = op_dyn_cast::<dyn WithISema>(op.clone()) {
+            op.borrow().convert(builder);
+        }
+    }
+}
+
+/// Insert the real instructions of a pseudo-instruction
+fn insert_ops(ops: &[OpRef], builder: &OpBuilder) {
+    for op in ops {
+        builder.insert_generic(op);
+    }
+}
+
+/// Encoding and semantics of pseudo-instructions, that are those of their expansion
+macro_rules! expanded_pseudo_op {
+    ($struct_name:ident) => {
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                encode_ops(&self.expand(), target_opts, stream)
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                convert_ops(&self.expand(), builder);
+            }
+        }
+    };
+}
+
+/// Pseudo-instructions, that take a destination and a source register
+macro_rules! unary_pseudo_ops {
+    (
+        imm { $($imm_struct:ident => { name = $imm_name:literal, op = $imm_op:ident, imm = $imm:literal })* }
+        zero_reg { $($reg_struct:ident => { name = $reg_name:literal, op = $reg_op:ident })* }
+    ) => {
+        $(
+        impl $imm_struct {
+            fn expand(&self) -> Vec<OpRef> {
+                let op = $imm_op::builder(&self.get_context())
+                    .rd(self.get_rd())
+                    .rs1(self.get_rs1())
+                    .imm(($imm as i16).into())
+                    .build();
+                vec![op]
+            }
+        }
+        )*
+
+        $(
+        impl $reg_struct {
+            fn expand(&self) -> Vec<OpRef> {
+                let op = $reg_op::builder(&self.get_context())
+                    .rd(self.get_rd())
+                    .rs1(GPR::X0.into())
+                    .rs2(self.get_rs1())
+                    .build();
+                vec![op]
+            }
+        }
+        )*
+
+        $(
+        unary_pseudo_op_base!($imm_struct, $imm_name);
+        )*
+        $(
+        unary_pseudo_op_base!($reg_struct, $reg_name);
+        )*
+
+        /// Pseudo-instruction, that stands for the operation with a register, that is
+        /// either combined with a fixed immediate, or is subtracted from or compared with
+        /// x0
+        fn fold_unary(op: &OpRef) -> Option<OpRef> {
+            $(
+            if let Some(op) = op_cast::<$imm_op>(op.clone()) {
+                let op = op.borrow();
+                if op.imm() != $imm {
+                    return None;
+                }
+                return Some(
+                    $imm_struct::builder(&op.get_context())
+                        .rd(op.get_rd())
+                        .rs1(op.get_rs1())
+                        .build(),
+                );
+            }
+            )*
+            $(
+            if let Some(op) = op_cast::<$reg_op>(op.clone()) {
+                let op = op.borrow();
+                if reg_num(op.get_rs1())? != 0 {
+                    return None;
+                }
+                return Some(
+                    $reg_struct::builder(&op.get_context())
+                        .rd(op.get_rd())
+                        .rs1(op.get_rs2())
+                        .build(),
+                );
+            }
+            )*
+            None
+        }
+    };
+}
+
+macro_rules! unary_pseudo_op_base {
+    ($struct_name:ident, $op_name:literal) => {
+        expanded_pseudo_op!($struct_name);
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(reg())
+                    .map(|(((_, rd), _), rs1)| (rd, rs1))
+                    .label($op_name);
+
+                let ((rd, rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .build();
+                insert_ops(&op.borrow().expand(), &builder);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    reg_name(self.get_rs1(), target_opts)
+                )
+            }
+        }
+    };
+}
+
+unary_pseudo_ops! {
+    imm {
+        PseudoMoveOp => { name = "mv", op = AddImmOp, imm = 0 }
+        PseudoNotOp => { name = "not", op = XorImmOp, imm = -1 }
+        PseudoSetEqzOp => { name = "seqz", op = SltuImmOp, imm = 1 }
+        PseudoSextWOp => { name = "sext.w", op = AddImmWOp, imm = 0 }
+    }
+    zero_reg {
+        PseudoNegOp => { name = "neg", op = SubOp }
+        PseudoSetNezOp => { name = "snez", op = SltuOp }
+    }
+}
+
+impl PseudoLoadImmOp {
+    fn expand(&self) -> Vec<OpRef> {
+        let context = self.get_context();
+        let rd = self.get_rd();
+        li_sequence(self.imm())
+            .into_iter()
+            .enumerate()
+            .map(|(idx, step)| -> OpRef {
+                let rs1 = if idx == 0 { GPR::X0.into() } else { rd };
+                match step {
+                    LiStep::Lui(imm) => LuiOp::builder(&context)
+                        .rd(rd)
+                        .imm((imm as i32).into())
+                        .build(),
+                    LiStep::Addi(imm) => AddImmOp::builder(&context)
+                        .rd(rd)
+                        .rs1(rs1)
+                        .imm((imm as i16).into())
+                        .build(),
+                    LiStep::Addiw(imm) => AddImmWOp::builder(&context)
+                        .rd(rd)
+                        .rs1(rs1)
+                        .imm((imm as i16).into())
+                        .build(),
+                    LiStep::Slli(shamt) => SllImmOp::builder(&context)
+                        .rd(rd)
+                        .rs1(rs1)
+                        .imm((shamt as i16).into())
+                        .build(),
+                }
+            })
+            .collect()
+    }
+}
+
+expanded_pseudo_op!(PseudoLoadImmOp);
+
+impl ISAParser for PseudoLoadImmOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let parser = opcode("li")
+            .and_then(reg())
+            .and_then(comma())
+            .and_then(imm(i64::MIN, i64::MAX))
+            .map(|(((_, rd), _), imm)| (rd, imm))
+            .label("li");
+
+        let ((rd, imm), ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = PseudoLoadImmOp::builder(&context)
+            .rd(rd.into())
+            .imm(imm.into())
+            .build();
+        insert_ops(&op.borrow().expand(), &builder);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for PseudoLoadImmOp {
+    fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+        format!(
+            "li {}, {}",
+            reg_name(self.get_rd(), target_opts),
+            self.imm()
+        )
+    }
+}
+
+/// Step of the `li` sequence, that the operation performs on rd. The first step starts
+/// from x0, the following ones modify rd.
+fn li_step(op: &OpRef, rd: u8, first: bool) -> Option<LiStep> {
+    if get_reloc(&*op.borrow()).is_some() {
+        return None;
+    }
+    let rs1 = if first { 0 } else { rd };
+    if let Some(op) = op_cast::<LuiOp>(op.clone()) {
+        let op = op.borrow();
+        return Some(LiStep::Lui(op.imm())).filter(|_| first && reg_num(op.get_rd()) == Some(rd));
+    }
+
+    macro_rules! imm_step {
+        ($op_type:ident => $step:ident) => {
+            if let Some(op) = op_cast::<$op_type>(op.clone()) {
+                let op = op.borrow();
+                let regs = (reg_num(op.get_rd())?, reg_num(op.get_rs1())?);
+                return Some(LiStep::$step(op.imm())).filter(|_| regs == (rd, rs1));
+            }
+        };
+    }
+    imm_step!(AddImmOp => Addi);
+    if !first {
+        imm_step!(AddImmWOp => Addiw);
+        imm_step!(SllImmOp => Slli);
+    }
+
+    None
+}
+
+/// `li`, that stands for the longest sequence at the start of the operations, that is
+/// exactly what `li` expands to
+fn fold_li(ops: &[OpRef]) -> Option<(OpRef, usize)> {
+    let rd = ops.first().and_then(|op| {
+        let op = op.borrow();
+        let op: &dyn Any = &*op;
+        op.downcast_ref::<LuiOp>()
+            .map(|op| op.get_rd())
+            .or_else(|| op.downcast_ref::<AddImmOp>().map(|op| op.get_rd()))
+    })?;
+    let rd_num = reg_num(rd).filter(|num| *num != 0)?;
+
+    let steps: Vec<_> = ops
+        .iter()
+        .enumerate()
+        .map_while(|(idx, op)| li_step(op, rd_num, idx == 0))
+        .collect();
+
+    (1..=steps.len()).rev().find_map(|len| {
+        // A single lui is printed as is
+        if let [LiStep::Lui(_)] = steps[..len] {
+            return None;
+        }
+        let value = steps[..len].iter().fold(0, |value, step| step.apply(value));
+        if li_sequence(value) != steps[..len] {
+            return None;
+        }
+        let op = PseudoLoadImmOp::builder(&ops[0].borrow().get_context())
+            .rd(rd)
+            .imm(value.into())
+            .build();
+        Some((op as OpRef, len))
+    })
+}
+
+impl PseudoNopOp {
+    fn expand(&self) -> Vec<OpRef> {
+        let op = AddImmOp::builder(&self.get_context())
+            .rd(GPR::X0.into())
+            .rs1(GPR::X0.into())
+            .imm(0_i16.into())
+            .build();
+        vec![op]
+    }
+}
+
+expanded_pseudo_op!(PseudoNopOp);
+
+/// Pseudo-instructions, that jump to the address in a register
+macro_rules! jump_reg_pseudo_ops {
+    ($($struct_name:ident => { name = $op_name:literal, rs1 = $rs1:tt, operand = $operand:tt })*) => {
+        $(
+        impl $struct_name {
+            fn expand(&self) -> Vec<OpRef> {
+                let op = JumpAndLinkRegOp::builder(&self.get_context())
+                    .rd(GPR::X0.into())
+                    .rs1(jump_reg_pseudo_ops!(@rs1 self, $rs1))
+                    .offset(0_i16.into())
+                    .build();
+                vec![op]
+            }
+        }
+
+        expanded_pseudo_op!($struct_name);
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                jump_reg_pseudo_ops!(@parse $struct_name, $op_name, $operand, input, context, builder)
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+                jump_reg_pseudo_ops!(@print self, $op_name, $operand, _target_opts)
+            }
+        }
+        )*
+    };
+    (@print $self:ident, $op_name:literal, true, $target_opts:ident) => {
+        format!("{} {}", $op_name, reg_name($self.get_rs1(), $target_opts))
+    };
+    (@print $self:ident, $op_name:literal, false, $target_opts:ident) => {
+        $op_name.to_string()
+    };
+    (@rs1 $self:ident, operand) => { $self.get_rs1() };
+    (@rs1 $self:ident, $reg:ident) => { GPR::$reg.into() };
+    (@parse $struct_name:ident, $op_name:literal, true, $input:ident, $context:ident, $builder:ident) => {{
+        let parser = opcode($op_name).and_then(reg()).map(|(_, rs1)| rs1).label($op_name);
+        let (rs1, ni) = parser.parse($input)?;
+        let op = $struct_name::builder(&$context).rs1(rs1.into()).build();
+        insert_ops(&op.borrow().expand(), &$builder);
+        Ok(((), ni))
+    }};
+    (@parse $struct_name:ident, $op_name:literal, false, $input:ident, $context:ident, $builder:ident) => {{
+        let (_, ni) = opcode($op_name).label($op_name).parse($input)?;
+        let op = $struct_name::builder(&$context).build();
+        insert_ops(&op.borrow().expand(), &$builder);
+        Ok(((), ni))
+    }};
+}
+
+jump_reg_pseudo_ops! {
+    PseudoJumpRegOp => { name = "jr", rs1 = operand, operand = true }
+    PseudoRetOp => { name = "ret", rs1 = X1, operand = false }
+}
+
+impl ISAParser for PseudoNopOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let (_, ni) = opcode("nop").label("nop").parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = PseudoNopOp::builder(&context).build();
+        insert_ops(&op.borrow().expand(), &builder);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for PseudoNopOp {
+    fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+        "nop".to_string()
+    }
+}
+
+fn target_successors(op: &dyn Op, target: &str) -> Vec<BlockRef> {
+    op.get_parent_region()
+        .and_then(|region| region.get_block_by_name(target))
+        .into_iter()
+        .collect()
+}
+
+impl PseudoJumpOp {
+    fn expand(&self) -> Vec<OpRef> {
+        let op = JumpAndLinkOp::builder(&self.get_context())
+            .rd(GPR::X0.into())
+            .target(self.target().to_string())
+            .build();
+        vec![op]
+    }
+}
+
+impl BinaryEmittable for PseudoJumpOp {
+    fn encode(
+        &self,
+        target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        self.encode_with_offset(get_label_offset(self, self.target()), target_opts, stream)
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl LabelRef for PseudoJumpOp {
+    fn get_label(&self) -> &str {
+        self.target()
+    }
+
+    fn get_label_reloc(&self) -> LabelReloc {
+        LabelReloc::Jal
+    }
+
+    fn encode_with_offset(
+        &self,
+        offset: Option<i64>,
+        _target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        let op = JumpAndLinkOp::builder(&self.get_context())
+            .rd(GPR::X0.into())
+            .target(self.target().to_string())
+            .build();
+        let op = op.borrow();
+        op.encode_with_label_offset(offset, stream)
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl BranchOpInterface for PseudoJumpOp {
+    fn get_successors(&self) -> Vec<BlockRef> {
+        target_successors(self, self.target())
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for PseudoJumpOp {
+    fn convert(&self, builder: &OpBuilder) {
+        convert_ops(&self.expand(), builder);
+    }
+}
+
+/// Pseudo-instructions, that compare a register with zero
+macro_rules! branch_zero_pseudo_ops {
+    ($($struct_name:ident => { name = $op_name:literal, op = $real_op:ident })*) => {
+        $(
+        impl $struct_name {
+            fn expand(&self) -> Vec<OpRef> {
+                let op = $real_op::builder(&self.get_context())
+                    .rs1(self.get_rs1())
+                    .rs2(GPR::X0.into())
+                    .target(self.target().to_string())
+                    .build();
+                vec![op]
+            }
+        }
+
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let offset = get_label_offset(self, self.target());
+                self.encode_with_offset(offset, target_opts, stream)
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl LabelRef for $struct_name {
+            fn get_label(&self) -> &str {
+                self.target()
+            }
+
+            fn get_label_reloc(&self) -> LabelReloc {
+                LabelReloc::Branch
+            }
+
+            fn encode_with_offset(
+                &self,
+                offset: Option<i64>,
+                _target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let op = $real_op::builder(&self.get_context())
+                    .rs1(self.get_rs1())
+                    .rs2(GPR::X0.into())
+                    .target(self.target().to_string())
+                    .build();
+                let op = op.borrow();
+                op.encode_with_label_offset(offset, stream)
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl BranchOpInterface for $struct_name {
+            fn get_successors(&self) -> Vec<BlockRef> {
+                target_successors(self, self.target())
+            }
+
+            fn may_fall_through(&self) -> bool {
+                true
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                convert_ops(&self.expand(), builder);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(asm_ident().label("label"))
+                    .map(|(((_, rs1), _), target)| (rs1, target))
+                    .label($op_name);
+
+                let ((rs1, target), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rs1(rs1.into())
+                    .target(target.to_string())
+                    .build();
+                insert_ops(&op.borrow().expand(), &builder);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}",
+                    $op_name,
+                    reg_name(self.get_rs1(), target_opts),
+                    self.target()
+                )
+            }
+        }
+        )*
+    };
+}
+
+branch_zero_pseudo_ops! {
+    PseudoBranchEqzOp => { name = "beqz", op = BranchEqOp }
+    PseudoBranchNezOp => { name = "bnez", op = BranchNeOp }
+}
+
+impl ISAParser for PseudoJumpOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let parser = opcode("j")
+            .and_then(asm_ident().label("label"))
+            .map(|(_, target)| target)
+            .label("j");
+
+        let (target, ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = PseudoJumpOp::builder(&context)
+            .target(target.to_string())
+            .build();
+        insert_ops(&op.borrow().expand(), &builder);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for PseudoJumpOp {
+    fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+        format!("j {}", self.target())
+    }
+}
+
+/// Upper 20 bits and lower 12 bits of a PC-relative offset, as taken by `auipc` and the
+/// instruction, that follows it. The lower part is sign-extended, so the upper part is
+/// rounded.
+pub(crate) fn pcrel_parts(offset: Option<i64>) -> tir_core::Result<(i64, i64)> {
+    let offset = offset.ok_or(tir_core::Error::Unknown)?;
+    let hi20 = (offset + 0x800) >> 12;
+    if !is_int(hi20, 20) {
+        return Err(tir_core::Error::Unknown);
+    }
+    Ok((hi20 & 0xfffff, offset - (hi20 << 12)))
+}
+
+/// Pseudo-instructions, that reach the label with `auipc` followed by an instruction,
+/// that adds the lower part of the offset. The offset is only known for the final
+/// layout, so they are expanded during encoding.
+macro_rules! pcrel_pseudo_ops {
+    ($($struct_name:ident => { name = $op_name:literal, rd = $rd:tt, auipc_rd = $auipc_rd:tt, lo = $lo:ident, reloc = $reloc:ident })*) => {
+        $(
+        impl BinaryEmittable for $struct_name {
+            fn encode(
+                &self,
+                target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                self.encode_with_offset(get_label_offset(self, self.target()), target_opts, stream)
+            }
+        }
+
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl LabelRef for $struct_name {
+            fn get_label(&self) -> &str {
+                self.target()
+            }
+
+            fn get_label_reloc(&self) -> LabelReloc {
+                LabelReloc::$reloc
+            }
+
+            fn encode_with_offset(
+                &self,
+                offset: Option<i64>,
+                target_opts: &tir_backend::TargetOptions,
+                stream: &mut Box<dyn tir_backend::BinaryStream>,
+            ) -> tir_core::Result<()> {
+                let context = self.get_context();
+                let (hi20, lo12) = pcrel_parts(offset)?;
+                let auipc_rd: Register<GPR> = pcrel_pseudo_ops!(@reg self, $auipc_rd);
+                let auipc = AuipcOp::builder(&context)
+                    .rd(auipc_rd)
+                    .imm((hi20 as i32).into())
+                    .build();
+                let lo = pcrel_pseudo_ops!(@$lo context, pcrel_pseudo_ops!(@reg self, $rd), auipc_rd, lo12);
+                encode_ops(&[auipc, lo], target_opts, stream)
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                pcrel_pseudo_ops!(@parse $struct_name, $op_name, $rd, input, context, builder)
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+                pcrel_pseudo_ops!(@print self, $op_name, $rd, _target_opts)
+            }
+        }
+        )*
+    };
+    (@print $self:ident, $op_name:literal, rd, $target_opts:ident) => {
+        format!("{} {}, {}", $op_name, reg_name($self.get_rd(), $target_opts), $self.target())
+    };
+    (@print $self:ident, $op_name:literal, $rd:ident, $target_opts:ident) => {
+        format!("{} {}", $op_name, $self.target())
+    };
+    (@reg $self:ident, rd) => { $self.get_rd() };
+    (@reg $self:ident, $reg:ident) => { GPR::$reg.into() };
+    (@addi $context:ident, $rd:expr, $rs1:expr, $lo12:expr) => {
+        AddImmOp::builder(&$context)
+            .rd($rd)
+            .rs1($rs1)
+            .imm(($lo12 as i16).into())
+            .build() as OpRef
+    };
+    (@jalr $context:ident, $rd:expr, $rs1:expr, $lo12:expr) => {
+        JumpAndLinkRegOp::builder(&$context)
+            .rd($rd)
+            .rs1($rs1)
+            .offset(($lo12 as i16).into())
+            .build() as OpRef
+    };
+    (@parse $struct_name:ident, $op_name:literal, rd, $input:ident, $context:ident, $builder:ident) => {{
+        let parser = opcode($op_name)
+            .and_then(reg())
+            .and_then(comma())
+            .and_then(asm_ident().label("label"))
+            .map(|(((_, rd), _), target)| (rd, target))
+            .label($op_name);
+        let ((rd, target), ni) = parser.parse($input)?;
+        let op = $struct_name::builder(&$context)
+            .rd(rd.into())
+            .target(target.to_string())
+            .build();
+        $builder.insert(&op);
+        Ok(((), ni))
+    }};
+    (@parse $struct_name:ident, $op_name:literal, $rd:ident, $input:ident, $context:ident, $builder:ident) => {{
+        let parser = opcode($op_name)
+            .and_then(call_target())
+            .map(|(_, target)| target)
+            .label($op_name);
+        let (target, ni) = parser.parse($input)?;
+        let op = $struct_name::builder(&$context)
+            .target(target.to_string())
+            .build();
+        $builder.insert(&op);
+        Ok(((), ni))
+    }};
+}
+
+/// Target of a call, that may be marked with `@plt`
+fn call_target<'a>() -> impl Parser<'a, TokenStream<'a>, &'a str> {
+    let parser = move |input: TokenStream<'a>| {
+        let (target, ni) = asm_ident().parse(input)?;
+        match ni {
+            Some(ni) if ni.peek() == Some(AsmToken::Tag("plt")) => {
+                Ok((target, ni.slice(1..ni.len())))
+            }
+            ni => Ok((target, ni)),
+        }
+    };
+
+    parser.label("label")
+}
+
+pcrel_pseudo_ops! {
+    PseudoLoadAddrOp => { name = "la", rd = rd, auipc_rd = rd, lo = addi, reloc = Pcrel }
+    PseudoCallOp => { name = "call", rd = X1, auipc_rd = X1, lo = jalr, reloc = Call }
+    PseudoTailOp => { name = "tail", rd = X0, auipc_rd = X6, lo = jalr, reloc = Call }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl CallOpInterface for PseudoCallOp {
+    fn get_callee(&self) -> String {
+        self.target().to_string()
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl CallOpInterface for PseudoTailOp {
+    fn get_callee(&self) -> String {
+        self.target().to_string()
+    }
+
+    fn returns(&self) -> bool {
+        false
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for PseudoCallOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let op = tir_backend::isema::JumpOp::builder(&self.get_context())
+            .rd(Register::from(GPR::X1).into())
+            .target(self.target().to_string())
+            .build();
+        builder.insert(&op);
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for PseudoTailOp {
+    /// The scratch register t1 is not modelled
+    fn convert(&self, builder: &OpBuilder) {
+        let op = tir_backend::isema::JumpOp::builder(&self.get_context())
+            .rd(Register::from(GPR::X0).into())
+            .target(self.target().to_string())
+            .build();
+        builder.insert(&op);
+    }
+}
+
+/// Pseudo-instruction, that stands for the operations at the start of the slice, along
+/// with the number of the operations it replaces
+fn fold_ops(ops: &[OpRef]) -> Option<(OpRef, usize)> {
+    let op = ops.first()?;
+    // Relocated immediates are only known after linking
+    if get_reloc(&*op.borrow()).is_some() {
+        return None;
+    }
+    let context = op.borrow().get_context();
+
+    if let Some(addi) = op_cast::<AddImmOp>(op.clone()) {
+        let addi = addi.borrow();
+        let regs = (reg_num(addi.get_rd())?, reg_num(addi.get_rs1())?);
+        if regs == (0, 0) && addi.imm() == 0 {
+            return Some((PseudoNopOp::builder(&context).build(), 1));
+        }
+    }
+    if let Some(folded) = fold_li(ops) {
+        return Some(folded);
+    }
+    if let Some(folded) = fold_unary(op) {
+        return Some((folded, 1));
+    }
+
+    if let Some(jal) = op_cast::<JumpAndLinkOp>(op.clone()) {
+        let jal = jal.borrow();
+        if reg_num(jal.get_rd())? != 0 {
+            return None;
+        }
+        let target = jal.target().to_string();
+        return Some((PseudoJumpOp::builder(&context).target(target).build(), 1));
+    }
+    if let Some(jalr) = op_cast::<JumpAndLinkRegOp>(op.clone()) {
+        let jalr = jalr.borrow();
+        if reg_num(jalr.get_rd())? != 0 || jalr.offset() != 0 {
+            return None;
+        }
+        let op: OpRef = match reg_num(jalr.get_rs1())? {
+            1 => PseudoRetOp::builder(&context).build(),
+            _ => PseudoJumpRegOp::builder(&context)
+                .rs1(jalr.get_rs1())
+                .build(),
+        };
+        return Some((op, 1));
+    }
+
+    macro_rules! fold_branch {
+        ($real_op:ident => $pseudo:ident) => {
+            if let Some(branch) = op_cast::<$real_op>(op.clone()) {
+                let branch = branch.borrow();
+                if reg_num(branch.get_rs2())? != 0 {
+                    return None;
+                }
+                let op = $pseudo::builder(&context)
+                    .rs1(branch.get_rs1())
+                    .target(branch.target().to_string())
+                    .build();
+                return Some((op, 1));
+            }
+        };
+    }
+    fold_branch!(BranchEqOp => PseudoBranchEqzOp);
+    fold_branch!(BranchNeOp => PseudoBranchNezOp);
+
+    None
+}
+
+/// Replace sequences of real instructions with the pseudo-instructions, that expand to
+/// them, to print the module in the pseudo form. `la`, `call` and `tail` are never
+/// expanded by the assembler, so they are not recovered.
+pub fn fold_pseudo_instrs(module: &Arc<RwCell<ModuleOp>>) {
+    let builder = OpBuilder::new(module.borrow().get_context(), module.borrow().get_body());
+    let module: OpRef = module.clone();
+
+    let ops = RefCell::new(vec![]);
+    dfs_walk(module, |op| ops.borrow_mut().push(op.clone()));
+
+    let mut folded = HashSet::new();
+    for op in ops.into_inner() {
+        let op_id = op.borrow().get_alloc_id();
+        if folded.contains(&op_id) {
+            continue;
+        }
+        let Some(block) = op
+            .borrow()
+            .get_parent_region()
+            .and_then(|region| region.find_op_block(&op))
+        else {
+            continue;
+        };
+
+        let following: Vec<_> = block.iter().skip(block.find(op_id).unwrap()).collect();
+        if let Some((pseudo, count)) = fold_ops(&following) {
+            builder.set_insertion_point_after(&op);
+            builder.set_location(op.borrow().get_location());
+            builder.insert_generic(&pseudo);
+            for real in &following[..count] {
+                folded.insert(real.borrow().get_alloc_id());
+                builder.erase(real);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+
+    #[test]
+    fn test_li_sequence() {
+        use LiStep::*;
+
+        // Reference sequences produced by llvm-mc
+        assert_eq!(li_sequence(0), vec![Addi(0)]);
+        assert_eq!(li_sequence(-2048), vec![Addi(-2048)]);
+        assert_eq!(li_sequence(4096), vec![Lui(1)]);
+        assert_eq!(li_sequence(0x12345678), vec![Lui(74565), Addiw(1656)]);
+        assert_eq!(li_sequence(0x7fffffff), vec![Lui(524288), Addiw(-1)]);
+        assert_eq!(li_sequence(-2147483648), vec![Lui(524288)]);
+        assert_eq!(li_sequence(0x800), vec![Lui(1), Addiw(-2048)]);
+        assert_eq!(li_sequence(0x80000000), vec![Addi(1), Slli(31)]);
+        assert_eq!(
+            li_sequence(0x123456789),
+            vec![Lui(146), Addiw(-1493), Slli(13), Addi(1929)]
+        );
+        assert_eq!(
+            li_sequence(0x1234567890abcdef),
+            vec![
+                Lui(583),
+                Addiw(-1875),
+                Slli(15),
+                Addi(-1903),
+                Slli(12),
+                Addi(-1347),
+                Slli(12),
+                Addi(-529)
+            ]
+        );
+
+        for value in [
+            1,
+            -1,
+            2047,
+            0x7ff800,
+            0x7ffff800,
+            0xffffffff,
+            -0x123456789ab,
+            i64::MAX,
+            i64::MIN,
+            0x5555555555555555,
+            -0x0123456789abcdef,
+        ] {
+            let steps = li_sequence(value);
+            assert!(steps.len() <= 8);
+            assert_eq!(steps.iter().fold(0, |acc, step| step.apply(acc)), value);
+            for step in steps {
+                match step {
+                    Lui(imm) => assert!((1..=0xfffff).contains(&imm)),
+                    Addi(imm) | Addiw(imm) => assert!(is_int(imm, 12)),
+                    Slli(shamt) => assert!((1..64).contains(&shamt)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pcrel_pseudo_size() {
+        let input = ".text
+start:
+    la a0, data
+    call func
+    tail start
+func:
+    ret
+data:
+    nop";
+
+        let context = context();
+        let module = parse_asm(&context, input).unwrap();
+        let section = module.borrow().get_body().first().unwrap();
+        let ops: Vec<_> = section
+            .borrow()
+            .get_regions()
+            .next()
+            .unwrap()
+            .iter()
+            .collect();
+        let first = ops[0].first().unwrap();
+        assert!(op_cast::<PseudoLoadAddrOp>(first.clone()).is_some());
+        assert_eq!(pseudo_instr_count(&*first.borrow()), Some(2));
+    }
+
+    #[test]
+    fn test_fold_pseudo() {
+        let input = ".text
+start:
+    addi a0, a1, 0
+    li a1, 1311768467463790320
+    lui t0, 1
+    addi t0, t0, 5
+    addi zero, zero, 0
+    sub a0, zero, a2
+    sltiu a0, a0, 1
+    bne a0, zero, start
+    bne zero, a0, start
+    jal zero, start
+    jal ra, start
+    jalr zero, 0(ra)
+    jalr zero, 4(ra)";
+
+        let context = context();
+        let module = parse_asm(&context, input).unwrap();
+        fold_pseudo_instrs(&module);
+
+        let section = module.borrow().get_body().first().unwrap();
+        let block = section
+            .borrow()
+            .get_regions()
+            .next()
+            .unwrap()
+            .first()
+            .unwrap();
+        let names: Vec<_> = block
+            .iter()
+            .map(|op| op.borrow().get_operation_name())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "mv", "li", "lui", "addi", "nop", "neg", "seqz", "bnez", "bne", "j", "jal", "ret",
+                "jalr",
+            ]
+        );
+
+        let li = op_cast::<PseudoLoadImmOp>(block.iter().nth(1).unwrap()).unwrap();
+        assert_eq!(li.borrow().imm(), 1311768467463790320);
+    }
+
+    #[test]
+    fn test_fold_relocs() {
+        let input = ".text
+    lui a0, %hi(msg)
+    addi a0, a0, %lo(msg)
+    addi a1, zero, %lo(msg)
+    li a2, 4 * 1000
+    call puts@plt";
+
+        let context = context();
+        let module = parse_asm(&context, input).unwrap();
+        fold_pseudo_instrs(&module);
+
+        let section = module.borrow().get_body().first().unwrap();
+        let block = section
+            .borrow()
+            .get_regions()
+            .next()
+            .unwrap()
+            .first()
+            .unwrap();
+        let names: Vec<_> = block
+            .iter()
+            .map(|op| op.borrow().get_operation_name())
+            .collect();
+        assert_eq!(names, vec!["lui", "addi", "addi", "li", "call"]);
+
+        let li = op_cast::<PseudoLoadImmOp>(block.iter().nth(3).unwrap()).unwrap();
+        assert_eq!(li.borrow().imm(), 4000);
+    }
+
+    #[test]
+    fn test_pseudo_sema() {
+        let context = context();
+        let module = parse_asm(&context, ".text\nl:\n call l\n tail l").unwrap();
+        fold_pseudo_instrs(&module);
+        assert!(tir_backend::isema::convert_to_isema(&module).is_ok());
+
+        let section = module.borrow().get_body().first().unwrap();
+        let block = section
+            .borrow()
+            .get_regions()
+            .next()
+            .unwrap()
+            .first()
+            .unwrap();
+        let ops: Vec<_> = block.iter().collect();
+        let call = op_cast::<tir_backend::isema::JumpOp>(ops[0].clone()).unwrap();
+        assert_eq!(call.borrow().rd(), "x1");
+        assert_eq!(call.borrow().target(), Some("l"));
+        let tail = op_cast::<tir_backend::isema::JumpOp>(ops[1].clone()).unwrap();
+        assert_eq!(tail.borrow().rd(), "x0");
+    }
+
+    #[test]
+    fn test_call_interface() {
+        let context = context();
+        let module = parse_asm(&context, ".text\nl:\n call foo\n tail bar").unwrap();
+
+        let section = module.borrow().get_body().first().unwrap();
+        let block = section
+            .borrow()
+            .get_regions()
+            .next()
+            .unwrap()
+            .first()
+            .unwrap();
+        let ops: Vec<_> = block.iter().collect();
+
+        let call = op_dyn_cast::<dyn CallOpInterface>(ops[0].clone()).unwrap();
+        assert_eq!(call.borrow().get_callee(), "foo");
+        assert!(call.borrow().returns());
+        let tail = op_dyn_cast::<dyn CallOpInterface>(ops[1].clone()).unwrap();
+        assert_eq!(tail.borrow().get_callee(), "bar");
+        assert!(!tail.borrow().returns());
+
+        for op in ops {
+            assert!(op_dyn_cast::<dyn BranchOpInterface>(op).is_none());
+        }
+    }
+}
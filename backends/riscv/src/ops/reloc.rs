@@ -0,0 +1,251 @@
+//! Relocation operators of immediate operands, like `%hi(sym)`
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use tir_backend::{BinaryStream, TargetOptions};
+use tir_core::{Attr, Op};
+
+/// Name of the attribute, that holds the relocation of the immediate
+const RELOC_ATTR: &str = "reloc";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocKind {
+    /// Upper 20 bits of the absolute address, taken by `lui`
+    Hi,
+    /// Lower 12 bits of the absolute address
+    Lo,
+    /// Upper 20 bits of the offset from `auipc`
+    PcrelHi,
+    /// Lower 12 bits of the offset, that is computed by the `auipc` at the label
+    PcrelLo,
+}
+
+impl RelocKind {
+    pub fn from_modifier(name: &str) -> Option<Self> {
+        let kind = match name {
+            "hi" => RelocKind::Hi,
+            "lo" => RelocKind::Lo,
+            "pcrel_hi" => RelocKind::PcrelHi,
+            "pcrel_lo" => RelocKind::PcrelLo,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    pub fn get_modifier(&self) -> &'static str {
+        match self {
+            RelocKind::Hi => "hi",
+            RelocKind::Lo => "lo",
+            RelocKind::PcrelHi => "pcrel_hi",
+            RelocKind::PcrelLo => "pcrel_lo",
+        }
+    }
+
+    /// Value of an absolute relocation of a constant. PC-relative relocations of
+    /// constants are not supported.
+    pub fn apply(&self, value: i64) -> Option<i64> {
+        match self {
+            RelocKind::Hi => Some((value.wrapping_add(0x800) >> 12) & 0xfffff),
+            RelocKind::Lo => Some((value << 52) >> 52),
+            RelocKind::PcrelHi | RelocKind::PcrelLo => None,
+        }
+    }
+}
+
+/// Symbol with a constant addend, that the immediate of an instruction refers to. The
+/// value of the immediate is only known after linking, so the instruction holds zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reloc {
+    pub kind: RelocKind,
+    pub symbol: String,
+    pub addend: i64,
+}
+
+impl fmt::Display for Reloc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}({}", self.kind.get_modifier(), self.symbol)?;
+        if self.addend != 0 {
+            write!(f, "{:+}", self.addend)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl FromStr for Reloc {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (modifier, operand) = value
+            .strip_prefix('%')
+            .and_then(|value| value.strip_suffix(')'))
+            .and_then(|value| value.split_once('('))
+            .ok_or(())?;
+        let kind = RelocKind::from_modifier(modifier).ok_or(())?;
+        // Symbols may start with a dot, but never with a sign
+        let (symbol, addend) = match operand.get(1..).and_then(|rest| rest.find(['+', '-'])) {
+            Some(pos) => {
+                let (symbol, addend) = operand.split_at(pos + 1);
+                (symbol, addend.parse().map_err(|_| ())?)
+            }
+            None => (operand, 0),
+        };
+
+        Ok(Reloc {
+            kind,
+            symbol: symbol.to_string(),
+            addend,
+        })
+    }
+}
+
+pub const R_RISCV_32: u32 = 1;
+pub const R_RISCV_64: u32 = 2;
+pub const R_RISCV_BRANCH: u32 = 16;
+pub const R_RISCV_JAL: u32 = 17;
+pub const R_RISCV_CALL: u32 = 18;
+pub const R_RISCV_CALL_PLT: u32 = 19;
+pub const R_RISCV_PCREL_HI20: u32 = 23;
+pub const R_RISCV_PCREL_LO12_I: u32 = 24;
+pub const R_RISCV_PCREL_LO12_S: u32 = 25;
+pub const R_RISCV_HI20: u32 = 26;
+pub const R_RISCV_LO12_I: u32 = 27;
+pub const R_RISCV_LO12_S: u32 = 28;
+pub const R_RISCV_RVC_BRANCH: u32 = 44;
+pub const R_RISCV_RVC_JUMP: u32 = 45;
+
+impl RelocKind {
+    /// ELF relocation type of the immediate of an I-type or, for stores, an S-type
+    /// instruction
+    pub fn get_elf_type(&self, is_store: bool) -> u32 {
+        match (self, is_store) {
+            (RelocKind::Hi, _) => R_RISCV_HI20,
+            (RelocKind::Lo, false) => R_RISCV_LO12_I,
+            (RelocKind::Lo, true) => R_RISCV_LO12_S,
+            (RelocKind::PcrelHi, _) => R_RISCV_PCREL_HI20,
+            (RelocKind::PcrelLo, false) => R_RISCV_PCREL_LO12_I,
+            (RelocKind::PcrelLo, true) => R_RISCV_PCREL_LO12_S,
+        }
+    }
+}
+
+/// Relocation of a label, that is not in the same section as the instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelReloc {
+    Branch,
+    Jal,
+    /// `auipc` and `jalr` pair of `call` and `tail`
+    Call,
+    /// `auipc` and `addi` pair of `la`
+    Pcrel,
+}
+
+/// Operation, that refers to a label by its offset from the operation
+pub trait LabelRef {
+    fn get_label(&self) -> &str;
+
+    fn get_label_reloc(&self) -> LabelReloc;
+
+    /// Encode the operation with the given offset of the label, that must be in range
+    /// of the encoding
+    fn encode_with_offset(
+        &self,
+        offset: Option<i64>,
+        target_opts: &TargetOptions,
+        stream: &mut Box<dyn BinaryStream>,
+    ) -> tir_core::Result<()>;
+}
+
+/// Relocation of the immediate of an instruction
+pub fn get_reloc(op: &dyn Op) -> Option<Reloc> {
+    match op.get_attrs().get(RELOC_ATTR) {
+        Some(Attr::String(reloc)) => reloc.parse().ok(),
+        _ => None,
+    }
+}
+
+pub(super) fn set_reloc(op: &mut dyn Op, reloc: Option<Reloc>) {
+    if let Some(reloc) = reloc {
+        let attrs = HashMap::from([(RELOC_ATTR.to_string(), Attr::String(reloc.to_string()))]);
+        op.add_attrs(&attrs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+    use tir_core::OpRef;
+
+    #[test]
+    fn test_reloc_syntax() {
+        for reloc in [
+            "%hi(msg)",
+            "%lo(msg+8)",
+            "%pcrel_hi(.L.str-4)",
+            "%pcrel_lo(.Lpcrel_hi0)",
+        ] {
+            assert_eq!(reloc.parse::<Reloc>().unwrap().to_string(), reloc);
+        }
+
+        let reloc: Reloc = "%pcrel_hi(.L.str-4)".parse().unwrap();
+        assert_eq!(reloc.kind, RelocKind::PcrelHi);
+        assert_eq!(reloc.symbol, ".L.str");
+        assert_eq!(reloc.addend, -4);
+
+        assert!("%got(msg)".parse::<Reloc>().is_err());
+        assert!("msg".parse::<Reloc>().is_err());
+    }
+
+    #[test]
+    fn test_reloc_constants() {
+        assert_eq!(RelocKind::Hi.apply(0x12345fff), Some(0x12346));
+        assert_eq!(RelocKind::Lo.apply(0x12345fff), Some(-1));
+        assert_eq!(RelocKind::PcrelHi.apply(0x1000), None);
+    }
+
+    #[test]
+    fn test_parse_relocs() {
+        let input = ".text
+    lui a0, %hi(msg)
+    addi a0, a0, %lo(msg)
+    sd a1, %lo(msg+8)(a0)
+.Lpcrel_hi0:
+    auipc a2, %pcrel_hi(msg)
+    jalr ra, %pcrel_lo(.Lpcrel_hi0)(a2)
+    addi a3, a3, %lo(0x12345678)";
+
+        let context = context();
+        let module: OpRef = parse_asm(&context, input).unwrap();
+        let section = module.borrow().get_regions().next().unwrap();
+        let section = section.first().unwrap().first().unwrap();
+        let relocs: Vec<_> = section
+            .borrow()
+            .get_regions()
+            .next()
+            .unwrap()
+            .iter()
+            .flat_map(|block| block.iter().collect::<Vec<_>>())
+            .map(|op| get_reloc(&*op.borrow()).map(|reloc| reloc.to_string()))
+            .collect();
+        assert_eq!(
+            relocs,
+            [
+                Some("%hi(msg)"),
+                Some("%lo(msg)"),
+                Some("%lo(msg+8)"),
+                Some("%pcrel_hi(msg)"),
+                Some("%pcrel_lo(.Lpcrel_hi0)"),
+                None,
+            ]
+            .map(|reloc| reloc.map(str::to_string))
+        );
+
+        // Relocation, that does not fit the immediate of the instruction
+        assert!(parse_asm(&context, ".text\n lui a0, %lo(msg)").is_err());
+        assert!(parse_asm(&context, ".text\n slli a0, a0, %lo(msg)").is_err());
+        assert!(parse_asm(&context, ".text\n addi a0, a0, %got(msg)").is_err());
+    }
+}
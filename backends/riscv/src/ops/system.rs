@@ -0,0 +1,524 @@
+use crate::DiagKind;
+use tir_backend::isema::WithISema;
+use tir_backend::parser::{asm_ident, comma};
+use tir_backend::AsmPrintable;
+use tir_backend::ISAParser;
+use tir_backend::TokenStream;
+use tir_core::*;
+
+use lpl::combinators::optional;
+use lpl::{Diagnostic, ParseResult, ParseStream, Parser};
+
+use super::asm::{imm, opcode, reg, reg_name};
+use super::defs::*;
+
+/// Read and write memory access set
+const FENCE_RW: u16 = 0b0011;
+
+/// Access kinds of fence sets, from the highest bit to the lowest one
+const FENCE_SET_CHARS: [char; 4] = ['i', 'o', 'r', 'w'];
+
+/// Largest CSR address
+const MAX_CSR: i64 = 0xfff;
+
+/// Names of commonly used CSRs
+const CSR_NAMES: &[(&str, u16)] = &[
+    // Unprivileged floating-point CSRs
+    ("fflags", 0x001),
+    ("frm", 0x002),
+    ("fcsr", 0x003),
+    // Unprivileged counters
+    ("cycle", 0xc00),
+    ("time", 0xc01),
+    ("instret", 0xc02),
+    // Supervisor CSRs
+    ("sstatus", 0x100),
+    ("sie", 0x104),
+    ("stvec", 0x105),
+    ("scounteren", 0x106),
+    ("sscratch", 0x140),
+    ("sepc", 0x141),
+    ("scause", 0x142),
+    ("stval", 0x143),
+    ("sip", 0x144),
+    ("satp", 0x180),
+    // Machine CSRs
+    ("mstatus", 0x300),
+    ("misa", 0x301),
+    ("medeleg", 0x302),
+    ("mideleg", 0x303),
+    ("mie", 0x304),
+    ("mtvec", 0x305),
+    ("mcounteren", 0x306),
+    ("mscratch", 0x340),
+    ("mepc", 0x341),
+    ("mcause", 0x342),
+    ("mtval", 0x343),
+    ("mip", 0x344),
+    ("mcycle", 0xb00),
+    ("minstret", 0xb02),
+    ("mvendorid", 0xf11),
+    ("marchid", 0xf12),
+    ("mimpid", 0xf13),
+    ("mhartid", 0xf14),
+];
+
+/// Address of the CSR with the given name
+pub fn parse_csr(name: &str) -> Option<u16> {
+    CSR_NAMES
+        .iter()
+        .find(|(csr_name, _)| csr_name.eq_ignore_ascii_case(name))
+        .map(|(_, csr)| *csr)
+}
+
+/// Name of the CSR with the given address, if it is known
+pub fn csr_name(csr: u16) -> Option<&'static str> {
+    CSR_NAMES
+        .iter()
+        .find(|(_, address)| *address == csr)
+        .map(|(name, _)| *name)
+}
+
+/// CSR by its name, if it is known, or by its address otherwise
+fn csr_str(csr: i64) -> String {
+    csr_name(csr as u16)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{:#x}", csr))
+}
+
+/// Fence access set written as a subset of `iorw`, i.e. `rw`
+fn fence_set_to_string(set: u16) -> String {
+    FENCE_SET_CHARS
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| set & (1 << (3 - idx)) != 0)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+fn fence_set<'a>() -> impl Parser<'a, TokenStream<'a>, u16> {
+    asm_ident()
+        .try_map(|set, s| {
+            let mut value = 0;
+            let mut chars = FENCE_SET_CHARS.iter().enumerate();
+            for c in set.chars() {
+                // Access kinds must be listed in the `iorw` order without repetitions
+                let (idx, _) = chars.find(|(_, kind)| c.eq_ignore_ascii_case(kind)).ok_or(
+                    Into::<Diagnostic>::into(DiagKind::InvalidFenceSet(set.to_string(), s.clone())),
+                )?;
+                value |= 1 << (3 - idx);
+            }
+            Ok(value)
+        })
+        .label("fence_set")
+}
+
+fn csr<'a>() -> impl Parser<'a, TokenStream<'a>, u16> {
+    let csr_name = asm_ident().try_map(|name, s| {
+        parse_csr(name).ok_or(Into::<Diagnostic>::into(DiagKind::UnknownCSR(
+            name.to_string(),
+            s,
+        )))
+    });
+
+    csr_name
+        .or_else(imm(0, MAX_CSR).map(|csr| csr as u16))
+        .label("csr")
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl MemoryEffects for FenceOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read, MemoryEffect::Write]
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for FenceOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::FenceOp::builder(&context)
+            .pred(fence_set_to_string(self.pred() as u16))
+            .succ(fence_set_to_string(self.succ() as u16))
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for FenceOp {
+    /// Fence without access sets orders all accesses, i.e. `fence iorw, iorw`
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let sets = fence_set()
+            .and_then(comma())
+            .and_then(fence_set())
+            .map(|((pred, _), succ)| (pred, succ));
+        let parser = opcode("fence")
+            .and_then(optional(sets))
+            .map(|(_, sets)| sets.unwrap_or((0b1111, 0b1111)))
+            .label("fence");
+
+        let ((pred, succ), ni) = parser.parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = FenceOp::builder(&context)
+            .pred(pred.into())
+            .succ(succ.into())
+            .build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for FenceOp {
+    fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+        format!(
+            "fence {}, {}",
+            fence_set_to_string(self.pred() as u16),
+            fence_set_to_string(self.succ() as u16)
+        )
+    }
+}
+
+impl AsmPrintable for FenceTsoOp {
+    fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+        "fence.tso".to_string()
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl MemoryEffects for FenceTsoOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read, MemoryEffect::Write]
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for FenceTsoOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::FenceOp::builder(&context)
+            .pred(fence_set_to_string(FENCE_RW))
+            .succ(fence_set_to_string(FENCE_RW))
+            .mode("tso".to_string())
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for FenceTsoOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let (_, ni) = opcode("fence.tso").label("fence.tso").parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = FenceTsoOp::builder(&context).build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+impl AsmPrintable for FenceIOp {
+    fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+        "fence.i".to_string()
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl MemoryEffects for FenceIOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read, MemoryEffect::Write]
+    }
+}
+
+#[tir_macros::op_implements(dialect = riscv)]
+impl WithISema for FenceIOp {
+    fn convert(&self, builder: &OpBuilder) {
+        let context = self.get_context();
+        let op = tir_backend::isema::FenceOp::builder(&context)
+            .pred("w".to_string())
+            .succ(String::new())
+            .mode("fetch".to_string())
+            .build();
+        builder.insert(&op);
+    }
+}
+
+impl ISAParser for FenceIOp {
+    fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+        let asm_ctx = input.get_extra().unwrap().clone();
+
+        let (_, ni) = opcode("fence.i").label("fence.i").parse(input)?;
+
+        let builder = asm_ctx.get_builder();
+        let context = builder.get_context();
+        let op = FenceIOp::builder(&context).build();
+        builder.insert(&op);
+
+        Ok(((), ni))
+    }
+}
+
+macro_rules! env_call_op_base {
+    ($struct_name:ident, $op_name:literal, $cause:literal) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::TrapOp::builder(&context)
+                    .cause($cause.to_string())
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let (_, ni) = opcode($op_name).label($op_name).parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context).build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+                $op_name.to_string()
+            }
+        }
+    };
+}
+
+env_call_op_base!(EcallOp, "ecall", "syscall");
+env_call_op_base!(EbreakOp, "ebreak", "breakpoint");
+
+/// The execution environment may access any memory
+#[tir_macros::op_implements(dialect = riscv)]
+impl MemoryEffects for EcallOp {
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![MemoryEffect::Read, MemoryEffect::Write]
+    }
+}
+
+/// Name of the CSR for the isema dialect
+fn sysreg_name(csr: i64) -> String {
+    csr_name(csr as u16)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("0x{:x}", csr))
+}
+
+macro_rules! csr_op_base {
+    ($struct_name:ident, $op_name:literal, $action:literal) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::SysRegOp::builder(&context)
+                    .rd(self.get_rd().into())
+                    .sysreg(sysreg_name(self.csr()))
+                    .action($action.to_string())
+                    .rs1(self.get_rs1().into())
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(csr())
+                    .and_then(comma())
+                    .and_then(reg())
+                    .map(|(((((_, rd), _), csr), _), rs1)| (rd, csr, rs1))
+                    .label($op_name);
+                let ((rd, csr, rs1), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .rs1(rs1.into())
+                    .csr(csr.into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    csr_str(self.csr()),
+                    reg_name(self.get_rs1(), target_opts)
+                )
+            }
+        }
+    };
+}
+
+macro_rules! csr_imm_op_base {
+    ($struct_name:ident, $op_name:literal, $action:literal) => {
+        #[tir_macros::op_implements(dialect = riscv)]
+        impl WithISema for $struct_name {
+            fn convert(&self, builder: &OpBuilder) {
+                let context = self.get_context();
+                let op = tir_backend::isema::SysRegOp::builder(&context)
+                    .rd(self.get_rd().into())
+                    .sysreg(sysreg_name(self.csr()))
+                    .action($action.to_string())
+                    .imm(self.uimm() as i16)
+                    .build();
+                builder.insert(&op);
+            }
+        }
+
+        impl ISAParser for $struct_name {
+            fn parse(input: TokenStream) -> ParseResult<TokenStream, ()> {
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = opcode($op_name)
+                    .and_then(reg())
+                    .and_then(comma())
+                    .and_then(csr())
+                    .and_then(comma())
+                    .and_then(imm(0, 31))
+                    .map(|(((((_, rd), _), csr), _), uimm)| (rd, csr, uimm))
+                    .label($op_name);
+                let ((rd, csr, uimm), ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = $struct_name::builder(&context)
+                    .rd(rd.into())
+                    .csr(csr.into())
+                    .uimm((uimm as u8).into())
+                    .build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl AsmPrintable for $struct_name {
+            fn print(&self, target_opts: &tir_backend::TargetOptions) -> String {
+                format!(
+                    "{} {}, {}, {}",
+                    $op_name,
+                    reg_name(self.get_rd(), target_opts),
+                    csr_str(self.csr()),
+                    self.uimm()
+                )
+            }
+        }
+    };
+}
+
+macro_rules! csr_ops {
+    (
+        reg { $($struct_name:ident => { name = $op_name:literal, action = $action:literal })* }
+        imm { $($imm_struct_name:ident => { name = $imm_op_name:literal, action = $imm_action:literal })* }
+    ) => {
+        $(
+        csr_op_base!($struct_name, $op_name, $action);
+        )*
+        $(
+        csr_imm_op_base!($imm_struct_name, $imm_op_name, $imm_action);
+        )*
+
+    };
+}
+
+csr_ops! {
+    reg {
+        CsrReadWriteOp => { name = "csrrw", action = "write" }
+        CsrReadSetOp => { name = "csrrs", action = "set" }
+        CsrReadClearOp => { name = "csrrc", action = "clear" }
+    }
+    imm {
+        CsrReadWriteImmOp => { name = "csrrwi", action = "write" }
+        CsrReadSetImmOp => { name = "csrrsi", action = "set" }
+        CsrReadClearImmOp => { name = "csrrci", action = "clear" }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::parse_asm;
+    use crate::GPR;
+    use tir_core::builtin::ModuleOp;
+    use tir_core::utils::op_cast;
+
+    #[test]
+    fn test_disassemble_system_invalid() {
+        let context = context();
+
+        // ecall with non-zero rd
+        assert!(decode(&context, &0x000000f3_u32.to_le_bytes(), 0).is_none());
+        // fence.i with a non-zero immediate
+        assert!(decode(&context, &0x0010100f_u32.to_le_bytes(), 0).is_none());
+        // mret is not a part of the base ISA
+        assert!(decode(&context, &0x30200073_u32.to_le_bytes(), 0).is_none());
+    }
+
+    #[test]
+    fn test_parse_system_errors() {
+        let context = context();
+        assert!(parse_asm(&context, ".text\nl:\n fence wr, rw").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n fence io, rwx").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n csrrw x5, unknown, x6").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n csrrw x5, 4096, x6").is_err());
+        assert!(parse_asm(&context, ".text\nl:\n csrrwi x5, mstatus, 32").is_err());
+    }
+
+    #[test]
+    fn test_system_sema() {
+        let context = context();
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+
+        let op = FenceOp::builder(&context)
+            .pred(0b1010_i64.into())
+            .succ(0b0101_i64.into())
+            .build();
+        builder.insert(&op);
+        let op = CsrReadSetImmOp::builder(&context)
+            .rd(GPR::X5.into())
+            .csr(0x7c0_i64.into())
+            .uimm(4_i64.into())
+            .build();
+        builder.insert(&op);
+
+        assert!(tir_backend::isema::convert_to_isema(&module).is_ok());
+
+        let ops: Vec<_> = module.borrow().get_body().iter().collect();
+        let fence = op_cast::<tir_backend::isema::FenceOp>(ops[0].clone()).unwrap();
+        assert_eq!(fence.borrow().pred(), "ir");
+        assert_eq!(fence.borrow().succ(), "ow");
+        let csr = op_cast::<tir_backend::isema::SysRegOp>(ops[1].clone()).unwrap();
+        assert_eq!(csr.borrow().sysreg(), "0x7c0");
+        assert_eq!(csr.borrow().action(), "set");
+        assert_eq!(csr.borrow().imm(), Some(4));
+    }
+}
@@ -0,0 +1,68 @@
+//! Helpers for unit tests of operation encodings
+
+use tir_backend::target::SectionOp;
+use tir_backend::BinaryStream;
+use tir_core::utils::op_cast;
+use tir_core::{Context, ContextRef, OpRef};
+
+/// Binary stream, that collects the data to a shared vector
+#[derive(Default)]
+pub struct VecStream(pub std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+impl BinaryStream for VecStream {
+    fn write(&mut self, data: &[u8]) {
+        self.0.borrow_mut().extend_from_slice(data);
+    }
+}
+
+pub fn context() -> ContextRef {
+    let context = Context::new();
+    context.add_dialect(crate::create_dialect());
+    context.add_dialect(tir_backend::target::create_dialect());
+    context.add_dialect(tir_backend::isema::create_dialect());
+    context
+}
+
+pub fn target_options(flags: &[&str]) -> tir_backend::TargetOptions {
+    tir_backend::TargetOptions {
+        endiannes: tir_backend::Endianness::Little,
+        word_size: 8,
+        flags: flags.iter().map(|flag| flag.to_string()).collect(),
+    }
+}
+
+/// Encode every instruction of the first section
+pub fn encode_bytes(module: &OpRef) -> Vec<u8> {
+    let section = module
+        .borrow()
+        .get_regions()
+        .next()
+        .unwrap()
+        .first()
+        .unwrap()
+        .first()
+        .unwrap();
+    let section = op_cast::<SectionOp>(section).unwrap();
+
+    let data = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    let mut stream: Box<dyn BinaryStream> = Box::new(VecStream(data.clone()));
+    let opts = target_options(&[]);
+
+    for block in section.borrow().get_body_region().iter() {
+        for op in block.iter() {
+            if let Some(result) = crate::encode_op(&op, &opts, &mut stream) {
+                assert!(result.is_ok());
+            }
+        }
+    }
+
+    data.take()
+}
+
+/// Encode every instruction of the first section, that must consist of 32-bit instructions
+pub fn encode(module: &OpRef) -> Vec<u32> {
+    encode_bytes(module)
+        .chunks(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect()
+}
@@ -0,0 +1,55 @@
+//! Opaque words, that the disassembler could not decode
+
+use tir_backend::AsmPrintable;
+use tir_backend::BinaryEmittable;
+use tir_core::*;
+
+use super::defs::*;
+
+impl UnknownWordOp {
+    /// Raw encoding of the word, the first bytes of the stream up to 4 bytes
+    pub fn from_bytes(context: &ContextRef, bytes: &[u8]) -> OpRef {
+        let value = bytes
+            .iter()
+            .take(4)
+            .rev()
+            .fold(0_i64, |value, byte| (value << 8) | *byte as i64);
+        UnknownWordOp::builder(context)
+            .value(value.into())
+            .size((bytes.len().min(4) as i64).into())
+            .build()
+    }
+}
+
+impl BinaryEmittable for UnknownWordOp {
+    fn encode(
+        &self,
+        _target_opts: &tir_backend::TargetOptions,
+        stream: &mut Box<dyn tir_backend::BinaryStream>,
+    ) -> tir_core::Result<()> {
+        let size = self.size() as usize;
+        if !(1..=4).contains(&size) {
+            return Err(tir_core::Error::Unknown);
+        }
+        stream.write(&(self.value() as u32).to_le_bytes()[..size]);
+        Ok(())
+    }
+}
+
+impl AsmPrintable for UnknownWordOp {
+    /// The word is printed as data, that assembles to the same bytes
+    fn print(&self, _target_opts: &tir_backend::TargetOptions) -> String {
+        let value = self.value() as u32;
+        match self.size() {
+            2 => format!(".half {:#06x}", value),
+            4 => format!(".word {:#010x}", value),
+            size => {
+                let bytes: Vec<_> = value.to_le_bytes()[..size.clamp(0, 4) as usize]
+                    .iter()
+                    .map(|byte| format!("{:#04x}", byte))
+                    .collect();
+                format!(".byte {}", bytes.join(", "))
+            }
+        }
+    }
+}
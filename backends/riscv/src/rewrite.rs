@@ -0,0 +1,1007 @@
+//! Rewriting of ELF files. Functions, that have been recovered from machine code, are
+//! transformed in the IR, encoded again at their original addresses and patched into a
+//! copy of the file.
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use thiserror::Error;
+use tir_backend::elf::{ElfError, ElfFile};
+use tir_backend::target::{FunctionOp, SectionOp};
+use tir_backend::{BinaryStream, Endianness, TargetOptions};
+use tir_core::builtin::ModuleOp;
+use tir_core::utils::{op_cast, op_dyn_cast};
+use tir_core::{
+    Attr, Block, BlockRef, ContextRef, Location, Op, OpBuilder, OpRef, RegionRef, RwCell,
+};
+
+use crate::cfg::{get_block_successors, get_flow, reg_num, ByteStream, Flow};
+use crate::object::code_padding;
+use crate::{
+    address_label, encode_op, get_instr_size, get_op_extension, get_target, parse_address_label,
+    pcrel_parts, AddImmOp, AuipcOp, BranchEqOp, BranchGeOp, BranchGeuOp, BranchLtOp, BranchLtuOp,
+    BranchNeOp, CBranchEqzOp, CBranchNezOp, CJumpOp, JumpAndLinkOp, JumpAndLinkRegOp, LabelRef,
+    LoadByte, LoadByteUnsigned, LoadDouble, LoadHalfword, LoadHalfwordUnsigned, LoadWord,
+    LoadWordUnsigned, UnknownWordOp, GPR, MARCH_FLAG,
+};
+
+/// Execution counts of control flow edges, i.e. sampled with a branch recorder
+#[derive(Debug, Default)]
+pub struct Profile {
+    edges: HashMap<(u64, u64), u64>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ProfileError {
+    #[error("line {0}: expected `<from> <to> <count>`")]
+    Syntax(usize),
+}
+
+impl Profile {
+    /// Parse edge counts, one edge per line as `<from> <to> <count>`. The source is the
+    /// address of any instruction of the source block, usually the branch, and the
+    /// destination is the address, that control is transferred to. Numbers are decimal
+    /// or hexadecimal with the `0x` prefix, `#` starts a comment.
+    pub fn parse(input: &str) -> Result<Profile, ProfileError> {
+        let mut profile = Profile::default();
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Option<Vec<u64>> = line.split_whitespace().map(parse_number).collect();
+            match fields.as_deref() {
+                Some(&[from, to, count]) => *profile.edges.entry((from, to)).or_default() += count,
+                _ => return Err(ProfileError::Syntax(idx + 1)),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+fn parse_number(text: &str) -> Option<u64> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Reason, why a function is left untouched
+#[derive(Debug, Error, PartialEq)]
+pub enum RewriteError {
+    #[error("function contains data")]
+    Data,
+    #[error("unknown instruction at 0x{0:x}")]
+    UnknownWord(u64),
+    #[error("instructions are not contiguous")]
+    NotContiguous,
+    #[error("entry block has been moved")]
+    EntryMoved,
+    #[error("relocation at 0x{0:x}")]
+    Relocation(u64),
+    #[error("symbol `{0}` points inside the function")]
+    InnerSymbol(String),
+    #[error("indirect jump at 0x{0:x}")]
+    IndirectJump(u64),
+    #[error("pc-relative address of the instruction at 0x{0:x} can not be moved")]
+    PcRelative(u64),
+    #[error("instruction at 0x{0:x} can not be encoded")]
+    Encoding(u64),
+    #[error("function needs {0} bytes, but only {1} are available")]
+    TooLarge(u64, u64),
+}
+
+/// Outcome of rewriting a function. Functions, that have been encoded to the same bytes,
+/// are unchanged.
+#[derive(Debug)]
+pub struct FunctionStatus {
+    pub name: String,
+    pub address: u64,
+    pub result: Result<bool, RewriteError>,
+}
+
+/// Lay out the blocks of recovered functions, so that the frequently executed edges of
+/// the profile fall through.
+///
+/// Blocks are chained along the hottest edges first. The chain of the entry block stays
+/// first, other chains follow in the order of their hottest block. Where the new layout
+/// breaks a fall-through, the branch is inverted or a jump is added, and jumps to the
+/// next block are removed.
+pub fn reorder_blocks(module: &Arc<RwCell<ModuleOp>>, profile: &Profile) {
+    for function in get_functions(module) {
+        reorder_function(&function.borrow(), profile);
+    }
+}
+
+/// Encode the functions of the module at their original addresses and patch them into
+/// a copy of the ELF file, that the module has been disassembled from.
+///
+/// A function occupies the bytes up to the next operation of its section and is padded
+/// with `nop` if it became shorter. Branches, that do not reach their targets any more,
+/// are relaxed in the module. Functions, that can not be moved safely, i.e. because of
+/// relocations, symbols or indirect jumps in their body, are left untouched.
+pub fn patch_elf(
+    module: &Arc<RwCell<ModuleOp>>,
+    data: &[u8],
+    isa: &str,
+) -> Result<(Vec<u8>, Vec<FunctionStatus>), ElfError> {
+    let elf = ElfFile::read(data)?;
+    let target_opts = TargetOptions {
+        endiannes: Endianness::Little,
+        word_size: 8,
+        flags: vec![format!("{}{}", MARCH_FLAG, isa)],
+    };
+    let labels = collect_labels(module);
+
+    let mut output = data.to_vec();
+    let mut statuses = vec![];
+    let sections: Vec<_> = module
+        .borrow()
+        .get_body()
+        .iter()
+        .filter_map(op_cast::<SectionOp>)
+        .collect();
+    for section_op in sections {
+        let section_op = section_op.borrow();
+        let Some(idx) = elf.sections.iter().position(|section| {
+            section.name == section_op.name()
+                && section_op.get_location() == Location::Address(section.address)
+        }) else {
+            continue;
+        };
+        let section = &elf.sections[idx];
+        let patcher = Patcher {
+            elf: &elf,
+            section: idx,
+            labels: &labels,
+            target_opts: &target_opts,
+        };
+
+        let ops: Vec<OpRef> = section_op
+            .get_body_region()
+            .iter()
+            .flat_map(|block| block.iter())
+            .collect();
+        for (pos, op) in ops.iter().enumerate() {
+            let (Some(function), Some(start)) =
+                (op_cast::<FunctionOp>(op.clone()), get_address(op))
+            else {
+                continue;
+            };
+            let end = ops[pos + 1..]
+                .iter()
+                .find_map(get_address)
+                .unwrap_or(section.address + section.size);
+            let function = function.borrow();
+            let result = patcher.rewrite(&function, start, end).map(|bytes| {
+                let offset = (section.offset + start - section.address) as usize;
+                let original = &mut output[offset..offset + bytes.len()];
+                let changed = original != bytes.as_slice();
+                original.copy_from_slice(&bytes);
+                changed
+            });
+            statuses.push(FunctionStatus {
+                name: function.name().to_string(),
+                address: start,
+                result,
+            });
+        }
+    }
+
+    Ok((output, statuses))
+}
+
+fn get_functions(module: &Arc<RwCell<ModuleOp>>) -> Vec<Arc<RwCell<FunctionOp>>> {
+    let sections: Vec<_> = module
+        .borrow()
+        .get_body()
+        .iter()
+        .filter_map(op_cast::<SectionOp>)
+        .collect();
+    sections
+        .iter()
+        .flat_map(|section| section.borrow().get_body_region().iter())
+        .flat_map(|block| block.iter())
+        .filter_map(op_cast::<FunctionOp>)
+        .collect()
+}
+
+fn get_address(op: &OpRef) -> Option<u64> {
+    match op.borrow().get_location() {
+        Location::Address(address) => Some(address),
+        _ => None,
+    }
+}
+
+/// Blocks, that are laid out together: the empty blocks, that only add labels, and the
+/// block, that they fall through to
+fn get_units(region: &RegionRef) -> Vec<Vec<BlockRef>> {
+    let mut units = vec![];
+    let mut unit = vec![];
+    for block in region.iter() {
+        let is_empty = block.first().is_none();
+        unit.push(block);
+        if !is_empty {
+            units.push(std::mem::take(&mut unit));
+        }
+    }
+    if !unit.is_empty() {
+        units.push(unit);
+    }
+    units
+}
+
+fn falls_through(block: &BlockRef) -> bool {
+    match block.last() {
+        Some(last) => matches!(
+            get_flow(&last, &HashMap::new()),
+            Flow::Next | Flow::Call(_) | Flow::Branch(_, true)
+        ),
+        None => true,
+    }
+}
+
+fn reorder_function(function: &FunctionOp, profile: &Profile) {
+    let body = function.get_body_region();
+    let units = get_units(&body);
+    if units.len() < 2 {
+        return;
+    }
+
+    let ops: Vec<OpRef> = body.iter().flat_map(|block| block.iter()).collect();
+    let Some(end) = ops
+        .iter()
+        .filter_map(|op| Some(get_address(op)? + get_instr_size(&*op.borrow())))
+        .max()
+    else {
+        return;
+    };
+
+    // Targets of the fall-through in the original layout. The last unit falls through
+    // to the code, that follows the function.
+    let fall_through: Vec<Option<String>> = units
+        .iter()
+        .enumerate()
+        .map(|(idx, unit)| {
+            falls_through(unit.last().unwrap()).then(|| match units.get(idx + 1) {
+                Some(next) => next[0].get_name(),
+                None => address_label(end),
+            })
+        })
+        .collect();
+
+    let order = chain_units(units.len(), &get_edge_weights(&units, profile));
+    if order.iter().copied().eq(0..units.len()) {
+        return;
+    }
+
+    for block in body.iter().collect::<Vec<_>>() {
+        body.remove_block(&block);
+    }
+    for (pos, idx) in order.iter().enumerate() {
+        for block in &units[*idx] {
+            body.add_block(block.clone());
+        }
+        let next = order.get(pos + 1).map(|next| units[*next].as_slice());
+        if let Some(block) =
+            fix_fall_through(&body, &units[*idx], fall_through[*idx].as_deref(), next)
+        {
+            body.add_block(block);
+        }
+    }
+}
+
+/// Counts of the profile edges between the units. Edges, that are not in the control
+/// flow graph, are ignored.
+fn get_edge_weights(units: &[Vec<BlockRef>], profile: &Profile) -> HashMap<(usize, usize), u64> {
+    let ranges: Vec<Option<(u64, u64)>> = units
+        .iter()
+        .map(|unit| {
+            let block = unit.last().unwrap();
+            Some((get_address(&block.first()?)?, get_address(&block.last()?)?))
+        })
+        .collect();
+    let find_unit = |block: &BlockRef| {
+        units
+            .iter()
+            .position(|unit| unit.iter().any(|candidate| Arc::ptr_eq(candidate, block)))
+    };
+    let successors: Vec<Vec<usize>> = units
+        .iter()
+        .map(|unit| {
+            get_block_successors(unit.last().unwrap())
+                .iter()
+                .filter_map(find_unit)
+                .collect()
+        })
+        .collect();
+
+    let mut weights = HashMap::new();
+    for ((from, to), count) in &profile.edges {
+        let src = ranges
+            .iter()
+            .position(|range| range.is_some_and(|(start, end)| (start..=end).contains(from)));
+        let dst = ranges
+            .iter()
+            .position(|range| range.is_some_and(|(start, _)| start == *to));
+        if let (Some(src), Some(dst)) = (src, dst) {
+            if successors[src].contains(&dst) {
+                *weights.entry((src, dst)).or_default() += count;
+            }
+        }
+    }
+    weights
+}
+
+/// Order of the units, where the hottest edges are chained together
+fn chain_units(count: usize, weights: &HashMap<(usize, usize), u64>) -> Vec<usize> {
+    let mut chains: Vec<Vec<usize>> = (0..count).map(|idx| vec![idx]).collect();
+    let mut chain_of: Vec<usize> = (0..count).collect();
+
+    let mut edges: Vec<_> = weights
+        .iter()
+        .map(|(edge, weight)| (*weight, *edge))
+        .collect();
+    edges.sort_by_key(|(weight, edge)| (Reverse(*weight), *edge));
+    for (_, (src, dst)) in edges {
+        let (src_chain, dst_chain) = (chain_of[src], chain_of[dst]);
+        // The entry is never moved away from the start of the function
+        if dst == 0
+            || src_chain == dst_chain
+            || chains[src_chain].last() != Some(&src)
+            || chains[dst_chain].first() != Some(&dst)
+        {
+            continue;
+        }
+        let tail = std::mem::take(&mut chains[dst_chain]);
+        for idx in &tail {
+            chain_of[*idx] = src_chain;
+        }
+        chains[src_chain].extend(tail);
+    }
+
+    let mut heat = vec![0; count];
+    for ((_, dst), weight) in weights {
+        heat[*dst] += weight;
+    }
+    let mut rest: Vec<&Vec<usize>> = chains[1..]
+        .iter()
+        .filter(|chain| !chain.is_empty())
+        .collect();
+    rest.sort_by_key(|chain| Reverse(chain.iter().map(|idx| heat[*idx]).max()));
+    chains[0]
+        .iter()
+        .chain(rest.into_iter().flatten())
+        .copied()
+        .collect()
+}
+
+/// Restore the fall-through of the unit to its original target after the unit has been
+/// placed before `next`. Returns a new block with a jump, that must follow the unit.
+fn fix_fall_through(
+    region: &RegionRef,
+    unit: &[BlockRef],
+    fall_through: Option<&str>,
+    next: Option<&[BlockRef]>,
+) -> Option<BlockRef> {
+    let context = region.get_context();
+    let next_names: Vec<String> = next
+        .unwrap_or_default()
+        .iter()
+        .map(|block| block.get_name())
+        .collect();
+    let jumps_to_next =
+        |op: &OpRef| get_target(op).is_some_and(|target| next_names.contains(&target));
+    let block = unit.last().unwrap();
+    let last = block.last();
+
+    let Some(target) = fall_through else {
+        // A jump to the next block is redundant
+        if let Some(last) = last {
+            if matches!(get_flow(&last, &HashMap::new()), Flow::Branch(_, false))
+                && jumps_to_next(&last)
+            {
+                block.erase(&last);
+            }
+        }
+        return None;
+    };
+    if next_names.iter().any(|name| name == target) {
+        return None;
+    }
+
+    let conditional =
+        last.filter(|last| matches!(get_flow(last, &HashMap::new()), Flow::Branch(_, true)));
+    let Some(branch) = conditional else {
+        block.push(&jump(&context, target));
+        return None;
+    };
+    if jumps_to_next(&branch) {
+        if let Some(inverted) = invert_branch(&branch, target) {
+            replace_op(block, &branch, &inverted);
+            return None;
+        }
+    }
+
+    // The branch ends the block, so the jump needs a block of its own
+    let jump_block = new_block(region, &block.get_name());
+    jump_block.push(&jump(&context, target));
+    Some(jump_block)
+}
+
+fn jump(context: &ContextRef, target: &str) -> OpRef {
+    JumpAndLinkOp::builder(context)
+        .rd(GPR::X0.into())
+        .target(target.to_string())
+        .build()
+}
+
+/// Empty block, that is named after the block it follows
+fn new_block(region: &RegionRef, prev: &str) -> BlockRef {
+    let mut name = format!("{}.next", prev);
+    let mut idx = 0;
+    while region.get_block_by_name(&name).is_some() {
+        idx += 1;
+        name = format!("{}.next{}", prev, idx);
+    }
+    Block::with_arguments::<&str>(&name, region, &[], &[])
+}
+
+fn insert_block_after(region: &RegionRef, prev: &BlockRef, block: BlockRef) {
+    let blocks: Vec<_> = region.iter().collect();
+    for candidate in &blocks {
+        region.remove_block(candidate);
+    }
+    for candidate in blocks {
+        let is_prev = Arc::ptr_eq(&candidate, prev);
+        region.add_block(candidate);
+        if is_prev {
+            region.add_block(block.clone());
+        }
+    }
+}
+
+fn replace_op(block: &BlockRef, op: &OpRef, new_op: &OpRef) {
+    let builder = OpBuilder::new(block.get_context(), block.clone());
+    builder.set_insertion_point_after(op);
+    builder.set_location(op.borrow().get_location());
+    builder.insert_generic(new_op);
+    builder.erase(op);
+}
+
+/// Branch to the target, that is taken, when the condition of the branch does not hold
+fn invert_branch(op: &OpRef, target: &str) -> Option<OpRef> {
+    macro_rules! invert {
+        ($($op:ident => $inverted:ident),*) => {
+            $(
+            if let Some(branch) = op_cast::<$op>(op.clone()) {
+                let branch = branch.borrow();
+                return Some(
+                    $inverted::builder(&branch.get_context())
+                        .rs1(branch.get_rs1())
+                        .rs2(branch.get_rs2())
+                        .target(target.to_string())
+                        .build(),
+                );
+            }
+            )*
+        };
+    }
+    macro_rules! invert_compressed {
+        ($($op:ident => $inverted:ident),*) => {
+            $(
+            if let Some(branch) = op_cast::<$op>(op.clone()) {
+                let branch = branch.borrow();
+                return Some(
+                    $inverted::builder(&branch.get_context())
+                        .rs1(branch.get_rs1())
+                        .target(target.to_string())
+                        .build(),
+                );
+            }
+            )*
+        };
+    }
+
+    invert!(
+        BranchEqOp => BranchNeOp,
+        BranchNeOp => BranchEqOp,
+        BranchLtOp => BranchGeOp,
+        BranchGeOp => BranchLtOp,
+        BranchLtuOp => BranchGeuOp,
+        BranchGeuOp => BranchLtuOp
+    );
+    invert_compressed!(CBranchEqzOp => CBranchNezOp, CBranchNezOp => CBranchEqzOp);
+    None
+}
+
+/// Full-size equivalent of a compressed branch or jump
+fn widen(op: &OpRef) -> Option<OpRef> {
+    let context = op.borrow().get_context();
+    macro_rules! widen_branch {
+        ($($op:ident => $wide:ident),*) => {
+            $(
+            if let Some(branch) = op_cast::<$op>(op.clone()) {
+                let branch = branch.borrow();
+                return Some(
+                    $wide::builder(&context)
+                        .rs1(branch.get_rs1())
+                        .rs2(GPR::X0.into())
+                        .target(branch.target().to_string())
+                        .build(),
+                );
+            }
+            )*
+        };
+    }
+
+    widen_branch!(CBranchEqzOp => BranchEqOp, CBranchNezOp => BranchNeOp);
+    if let Some(jump_op) = op_cast::<CJumpOp>(op.clone()) {
+        return Some(jump(&context, jump_op.borrow().target()));
+    }
+    None
+}
+
+/// Replace an instruction, that does not reach its target, with a longer form.
+/// Compressed branches and jumps are widened, conditional branches jump over a `jal`
+/// to the target, if the condition does not hold.
+fn relax(region: &RegionRef, op: &OpRef) -> bool {
+    let Some(block) = region.find_op_block(op) else {
+        return false;
+    };
+    if let Some(wide) = widen(op) {
+        replace_op(&block, op, &wide);
+        return true;
+    }
+
+    let (Some(target), Some(last)) = (get_target(op), block.last()) else {
+        return false;
+    };
+    let blocks: Vec<_> = region.iter().collect();
+    let next = blocks
+        .iter()
+        .position(|candidate| Arc::ptr_eq(candidate, &block))
+        .and_then(|idx| blocks.get(idx + 1));
+    let Some(next) = next.filter(|_| Arc::ptr_eq(&last, op)) else {
+        return false;
+    };
+    let Some(inverted) = invert_branch(op, &next.get_name()) else {
+        return false;
+    };
+    replace_op(&block, op, &inverted);
+    let jump_block = new_block(region, &block.get_name());
+    jump_block.push(&jump(&region.get_context(), &target));
+    insert_block_after(region, &block, jump_block);
+    true
+}
+
+/// Addresses of the labels of the module. A label is located at the next operation,
+/// that has an address.
+fn collect_labels(module: &Arc<RwCell<ModuleOp>>) -> HashMap<String, u64> {
+    fn collect(region: &RegionRef, pending: &mut Vec<String>, labels: &mut HashMap<String, u64>) {
+        for block in region.iter() {
+            pending.push(block.get_name());
+            for op in block.iter() {
+                if let Some(address) = get_address(&op) {
+                    for name in pending.drain(..) {
+                        labels.entry(name).or_insert(address);
+                    }
+                }
+                if let Some(function) = op_cast::<FunctionOp>(op.clone()) {
+                    collect(&function.borrow().get_body_region(), pending, labels);
+                }
+            }
+        }
+    }
+
+    let mut labels = HashMap::new();
+    for section in module.borrow().get_body().iter() {
+        if let Some(section) = op_cast::<SectionOp>(section) {
+            collect(
+                &section.borrow().get_body_region(),
+                &mut vec![],
+                &mut labels,
+            );
+        }
+    }
+    labels
+}
+
+/// Attribute of the instruction, that adds the lower 12 bits of a pc-relative address
+/// to the register of an `auipc`. Only instructions, that overwrite the register or
+/// leave the function, are accepted, so that the register has no other uses.
+fn get_pcrel_lo(op: &OpRef, reg: u8) -> Option<&'static str> {
+    macro_rules! pcrel_lo {
+        ($($op:ident => $attr:literal),*) => {
+            $(
+            if let Some(op) = op_cast::<$op>(op.clone()) {
+                let op = op.borrow();
+                let (rd, rs1) = (reg_num(op.get_rd()), reg_num(op.get_rs1()));
+                return (rs1 == Some(reg) && rd == Some(reg)).then_some($attr);
+            }
+            )*
+        };
+    }
+
+    pcrel_lo!(
+        AddImmOp => "imm",
+        LoadByte => "offset",
+        LoadHalfword => "offset",
+        LoadWord => "offset",
+        LoadDouble => "offset",
+        LoadByteUnsigned => "offset",
+        LoadHalfwordUnsigned => "offset",
+        LoadWordUnsigned => "offset"
+    );
+    if let Some(jalr) = op_cast::<JumpAndLinkRegOp>(op.clone()) {
+        let jalr = jalr.borrow();
+        let (rd, rs1) = (reg_num(jalr.get_rd()), reg_num(jalr.get_rs1()));
+        return (rs1 == Some(reg) && (rd == Some(reg) || rd == Some(0))).then_some("offset");
+    }
+    None
+}
+
+/// `auipc` and the instruction, that completes the pc-relative address
+struct PcrelPair {
+    auipc: OpRef,
+    lo: Option<(OpRef, &'static str)>,
+    /// Original address of the `auipc` and its immediates
+    address: u64,
+    hi_attr: Attr,
+    lo_attr: Option<Attr>,
+    /// Address, that is computed by the pair
+    value: i64,
+}
+
+impl PcrelPair {
+    fn find(region: &RegionRef) -> Vec<PcrelPair> {
+        let mut pairs = vec![];
+        for block in region.iter() {
+            let ops: Vec<_> = block.iter().collect();
+            for (idx, op) in ops.iter().enumerate() {
+                let (Some(auipc), Some(address)) =
+                    (op_cast::<AuipcOp>(op.clone()), get_address(op))
+                else {
+                    continue;
+                };
+                let auipc = auipc.borrow();
+                let rd = reg_num(auipc.get_rd());
+                let lo = ops
+                    .get(idx + 1)
+                    .and_then(|next| Some((next.clone(), get_pcrel_lo(next, rd?)?)));
+                let lo_attr = lo
+                    .as_ref()
+                    .and_then(|(lo, attr)| lo.borrow().get_attrs().get(*attr).cloned());
+                let value = address as i64
+                    + ((auipc.imm() << 12) as i32) as i64
+                    + lo_attr.as_ref().and_then(Attr::as_i64).unwrap_or(0);
+                pairs.push(PcrelPair {
+                    auipc: op.clone(),
+                    lo,
+                    address,
+                    hi_attr: auipc.get_imm_attr(),
+                    lo_attr,
+                    value,
+                });
+            }
+        }
+        pairs
+    }
+
+    fn is_lo(&self, op: &OpRef) -> bool {
+        self.lo.as_ref().is_some_and(|(lo, _)| Arc::ptr_eq(lo, op))
+    }
+
+    /// Adjust the immediates to compute the same address at the new address of `auipc`
+    fn place(&self, address: u64) -> Result<(), RewriteError> {
+        let (hi_attr, lo_attr) = if address == self.address {
+            (self.hi_attr.clone(), self.lo_attr.clone())
+        } else {
+            if self.lo.is_none() {
+                return Err(RewriteError::PcRelative(self.address));
+            }
+            let (hi20, lo12) = pcrel_parts(Some(self.value - address as i64))
+                .map_err(|_| RewriteError::PcRelative(self.address))?;
+            (Attr::I32(hi20 as i32), Some(Attr::I16(lo12 as i16)))
+        };
+        let attrs = HashMap::from([("imm".to_string(), hi_attr)]);
+        self.auipc.borrow_mut().add_attrs(&attrs);
+        if let (Some((lo, name)), Some(attr)) = (&self.lo, lo_attr) {
+            lo.borrow_mut()
+                .add_attrs(&HashMap::from([(name.to_string(), attr)]));
+        }
+        Ok(())
+    }
+}
+
+/// Encoder of the functions of a section
+struct Patcher<'a> {
+    elf: &'a ElfFile,
+    section: usize,
+    labels: &'a HashMap<String, u64>,
+    target_opts: &'a TargetOptions,
+}
+
+impl Patcher<'_> {
+    /// Bytes of the function, that occupies the addresses from `start` to `end`
+    fn rewrite(
+        &self,
+        function: &FunctionOp,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, RewriteError> {
+        let body = function.get_body_region();
+        let entry = get_units(&body).into_iter().next().unwrap_or_default();
+        if !entry
+            .iter()
+            .any(|block| block.get_name() == function.name())
+        {
+            return Err(RewriteError::EntryMoved);
+        }
+
+        let ops: Vec<OpRef> = body.iter().flat_map(|block| block.iter()).collect();
+        for op in &ops {
+            if op_cast::<UnknownWordOp>(op.clone()).is_some() {
+                return Err(RewriteError::UnknownWord(get_address(op).unwrap_or(start)));
+            }
+            if get_op_extension(op).is_none() {
+                return Err(RewriteError::Data);
+            }
+            if get_address(op).is_some_and(|address| address < start || address >= end) {
+                return Err(RewriteError::NotContiguous);
+            }
+        }
+
+        let section = &self.elf.sections[self.section];
+        for offset in self.elf.get_relocation_offsets(self.section) {
+            let address = match self.elf.is_relocatable() {
+                true => section.address + offset,
+                false => offset,
+            };
+            if (start..end).contains(&address) {
+                return Err(RewriteError::Relocation(address));
+            }
+        }
+        for symbol in &self.elf.symbols {
+            if symbol.section == Some(self.section) && symbol.value > start && symbol.value < end {
+                return Err(RewriteError::InnerSymbol(symbol.name.clone()));
+            }
+        }
+
+        // Jumps through registers may only leave the function
+        let pairs = PcrelPair::find(&body);
+        for op in &ops {
+            let is_indirect = matches!(get_flow(op, &HashMap::new()), Flow::IndirectJump(_));
+            if is_indirect && !pairs.iter().any(|pair| pair.is_lo(op)) {
+                return Err(RewriteError::IndirectJump(get_address(op).unwrap_or(start)));
+            }
+        }
+
+        let mut bytes = self.encode(&body, start, &pairs)?;
+        let size = end - start;
+        if bytes.len() as u64 > size {
+            return Err(RewriteError::TooLarge(bytes.len() as u64, size));
+        }
+        bytes.extend(code_padding(size - bytes.len() as u64));
+        Ok(bytes)
+    }
+
+    /// Encode the blocks from the address, relaxing branches until all of them reach
+    /// their targets
+    fn encode(
+        &self,
+        body: &RegionRef,
+        start: u64,
+        pairs: &[PcrelPair],
+    ) -> Result<Vec<u8>, RewriteError> {
+        loop {
+            let mut labels = HashMap::new();
+            let mut ops = vec![];
+            let mut address = start;
+            for block in body.iter() {
+                labels.insert(block.get_name(), address);
+                for op in block.iter() {
+                    let size = get_instr_size(&*op.borrow());
+                    ops.push((address, op));
+                    address += size;
+                }
+            }
+            for pair in pairs {
+                let (address, _) = ops
+                    .iter()
+                    .find(|(_, op)| Arc::ptr_eq(op, &pair.auipc))
+                    .ok_or(RewriteError::PcRelative(pair.address))?;
+                pair.place(*address)?;
+            }
+
+            let mut bytes = vec![];
+            let mut relaxed = false;
+            for (address, op) in &ops {
+                match self.encode_instr(op, *address, &labels) {
+                    Some(encoded) => bytes.extend(encoded),
+                    None if relax(body, op) => relaxed = true,
+                    None => return Err(RewriteError::Encoding(*address)),
+                }
+            }
+            if !relaxed {
+                return Ok(bytes);
+            }
+        }
+    }
+
+    /// Bytes of the instruction at the address. Labels of the function take precedence
+    /// over the labels of the module.
+    fn encode_instr(
+        &self,
+        op: &OpRef,
+        address: u64,
+        labels: &HashMap<String, u64>,
+    ) -> Option<Vec<u8>> {
+        let bytes = Rc::new(RefCell::new(vec![]));
+        let mut stream: Box<dyn BinaryStream> = Box::new(ByteStream(bytes.clone()));
+
+        if let Some(label_ref) = op_dyn_cast::<dyn LabelRef>(op.clone()) {
+            let label_ref = label_ref.borrow();
+            let label = label_ref.get_label();
+            let target = labels
+                .get(label)
+                .or_else(|| self.labels.get(label))
+                .copied()
+                .or_else(|| parse_address_label(label))?;
+            let offset = target.wrapping_sub(address) as i64;
+            label_ref
+                .encode_with_offset(Some(offset), self.target_opts, &mut stream)
+                .ok()?;
+        } else {
+            encode_op(op, self.target_opts, &mut stream)?.ok()?;
+        }
+        let bytes = bytes.borrow().clone();
+        Some(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::testing::context;
+    use crate::{disassemble_elf, emit_elf_object, parse_asm, recover_cfg, ISAFeatures};
+
+    const ISA: &str = "rv64imac";
+
+    fn object(input: &str) -> Vec<u8> {
+        let context = context();
+        let module = parse_asm(&context, input).unwrap();
+        emit_elf_object(&module, ISA).unwrap()
+    }
+
+    fn text(data: &[u8]) -> Vec<u8> {
+        ElfFile::read(data).unwrap().sections[0].data.clone()
+    }
+
+    /// Rewrite the object file with the profile
+    fn rewrite(object: &[u8], profile: &str) -> (Vec<u8>, Vec<FunctionStatus>) {
+        let context = context();
+        let features = ISAFeatures::parse(ISA).unwrap();
+        let module = disassemble_elf(&context, object, &features).unwrap();
+        recover_cfg(&module).unwrap();
+        reorder_blocks(&module, &Profile::parse(profile).unwrap());
+        patch_elf(&module, object, ISA).unwrap()
+    }
+
+    #[test]
+    fn test_profile() {
+        let profile = Profile::parse("0x10 0x20 3\n\n# comment\n16 32 4 # edge").unwrap();
+        assert_eq!(profile.edges, HashMap::from([((0x10, 0x20), 7)]));
+        assert_eq!(
+            Profile::parse("0x10 0x20 1\n0x10 0x20").unwrap_err(),
+            ProfileError::Syntax(2)
+        );
+        assert_eq!(
+            Profile::parse("0x10 0x2g 1").unwrap_err(),
+            ProfileError::Syntax(1)
+        );
+    }
+
+    #[test]
+    fn test_reorder_relax() {
+        let nops = "    addi zero, zero, 0\n".repeat(80);
+        let input = object(&format!(
+            ".text
+    .globl main
+main:
+    c.beqz a0, .Lcold
+    jal zero, .Lhot
+.Lcold:
+    c.li a0, 1
+    ret
+.Lhot:
+{nops}    ret"
+        ));
+        let (output, statuses) = rewrite(&input, "0x0 0x2 100\n0x2 0xc 100\n0x0 0x6 1");
+        assert_eq!(statuses[0].name, "main");
+        assert_eq!(statuses[0].result, Ok(true));
+
+        // The jump to the hot block is removed, the branch to the cold block does not
+        // reach it in the compressed form any more
+        let expected = object(&format!(
+            ".text
+main:
+    beq a0, zero, .Lcold
+{nops}    ret
+.Lcold:
+    c.li a0, 1
+    ret
+    c.nop"
+        ));
+        assert_eq!(text(&output), text(&expected));
+    }
+
+    #[test]
+    fn test_reorder_pcrel() {
+        let input = object(
+            ".text
+    .globl main
+main:
+    beq a0, zero, .Lhot
+    addi a0, zero, 1
+    ret
+.Lhot:
+    auipc a0, 1
+    addi a0, a0, 16
+    ret",
+        );
+        let (output, statuses) = rewrite(&input, "0x0 0xc 10");
+        assert_eq!(statuses[0].result, Ok(true));
+
+        // The address is computed relative to the new location of `auipc`
+        let expected = object(
+            ".text
+main:
+    bne a0, zero, .Lcold
+    auipc a0, 1
+    addi a0, a0, 24
+    ret
+.Lcold:
+    addi a0, zero, 1
+    ret",
+        );
+        assert_eq!(text(&output), text(&expected));
+    }
+
+    #[test]
+    fn test_untouched() {
+        let input = object(
+            ".text
+    .globl caller
+    .globl jumper
+    .globl grower
+caller:
+    call puts
+    ret
+jumper:
+    jalr zero, 0(a5)
+grower:
+    beq a0, a1, .Lexit
+    addi a0, a0, 1
+.Lexit:
+    ret",
+        );
+        let (output, statuses) = rewrite(&input, "0x10 0x18 100");
+        let results: Vec<_> = statuses
+            .iter()
+            .map(|status| (status.name.as_str(), &status.result))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("caller", &Err(RewriteError::Relocation(0))),
+                ("jumper", &Err(RewriteError::IndirectJump(12))),
+                ("grower", &Err(RewriteError::TooLarge(16, 12))),
+            ]
+        );
+        assert_eq!(output, input);
+    }
+}
@@ -133,14 +133,9 @@ impl ITypeInstr {
         ((self.instr & (0b11111 << 15)) >> 15) as u8
     }
 
+    /// Sign-extended 12-bit immediate
     pub fn imm(&self) -> i16 {
-        let num = ((self.instr & (0b11111111111 << 19)) >> 19) as i16;
-        let sign = (self.instr & (0b1 << 31)) >> 31;
-        if sign == 0 {
-            num
-        } else {
-            -num
-        }
+        ((self.instr as i32) >> 20) as i16
     }
 }
 
@@ -182,11 +177,8 @@ impl ITypeBuilder {
     }
 
     pub fn imm(mut self, imm: i16) -> Self {
-        let num = imm.abs();
-        assert!(num <= 0b11111111111);
-        let sign = if imm < 0 { 1 } else { 0 };
-        self.instr += (num as u32) << 19;
-        self.instr += (sign as u32) << 31;
+        assert!((-2048..=2047).contains(&imm));
+        self.instr += ((imm as u32) & 0xfff) << 20;
         self
     }
 
@@ -230,16 +222,11 @@ impl STypeInstr {
         ((self.instr & (0b11111 << 20)) >> 20) as u8
     }
 
+    /// Sign-extended 12-bit immediate, assembled from imm[11:5] and imm[4:0] fields
     pub fn imm(&self) -> i16 {
-        let part1 = ((self.instr & (0b11111 << 6)) >> 6) as i16;
-        let part2 = ((self.instr & (0b111111 << 24)) >> 19) as i16;
-        let sign = (self.instr & (0b1 << 31)) >> 31;
-        let num = part1 + part2;
-        if sign == 0 {
-            num
-        } else {
-            -num
-        }
+        let low = (self.instr & (0b11111 << 7)) >> 7;
+        let high = ((self.instr as i32) >> 25) << 5;
+        (high | low as i32) as i16
     }
 }
 
@@ -281,12 +268,10 @@ impl STypeBuilder {
     }
 
     pub fn imm(mut self, imm: i16) -> Self {
-        let part1 = (imm & 0b11111) as u32;
-        let part2 = ((imm & (0b111111 << 5)) >> 5) as u32;
-        let sign = if imm < 0 { 1 } else { 0 };
-        self.instr += part1 << 6;
-        self.instr += part2 << 24;
-        self.instr += (sign as u32) << 31;
+        assert!((-2048..=2047).contains(&imm));
+        let imm = (imm as u32) & 0xfff;
+        self.instr += (imm & 0b11111) << 7;
+        self.instr += (imm >> 5) << 25;
         self
     }
 
@@ -295,9 +280,442 @@ impl STypeBuilder {
     }
 }
 
+pub struct BTypeInstr {
+    instr: u32,
+}
+
+impl BTypeInstr {
+    pub fn from_bytes(bytes: &[u8; 4]) -> Self {
+        BTypeInstr {
+            instr: u32::from_le_bytes(*bytes),
+        }
+    }
+
+    pub fn builder() -> BTypeBuilder {
+        BTypeBuilder::default()
+    }
+
+    pub fn to_bytes(&self) -> [u8; 4] {
+        self.instr.to_le_bytes()
+    }
+
+    pub fn opcode(&self) -> u8 {
+        (self.instr & 0b1111111) as u8
+    }
+
+    pub fn funct3(&self) -> u8 {
+        ((self.instr & (0b111 << 12)) >> 12) as u8
+    }
+
+    pub fn rs1(&self) -> u8 {
+        ((self.instr & (0b11111 << 15)) >> 15) as u8
+    }
+
+    pub fn rs2(&self) -> u8 {
+        ((self.instr & (0b11111 << 20)) >> 20) as u8
+    }
+
+    /// Sign-extended 13-bit branch offset. The lowest bit is always zero.
+    pub fn imm(&self) -> i16 {
+        let bit11 = (self.instr & (0b1 << 7)) << 4;
+        let low = (self.instr & (0b1111 << 8)) >> 7;
+        let mid = (self.instr & (0b111111 << 25)) >> 20;
+        let sign = ((self.instr as i32) >> 31) << 12;
+        (sign | (bit11 | mid | low) as i32) as i16
+    }
+}
+
+impl Debug for BTypeInstr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = format!(
+            "{:#032b}: opcode = {:#07b}, funct3 = {:#03b}, rs1 = {:#05b}, rs2 = {:#05b}, imm = {}",
+            self.instr,
+            self.opcode(),
+            self.funct3(),
+            self.rs1(),
+            self.rs2(),
+            self.imm()
+        );
+        f.write_str(&string)
+    }
+}
+
+#[derive(Default)]
+pub struct BTypeBuilder {
+    instr: u32,
+}
+
+impl BTypeBuilder {
+    pub fn opcode(mut self, opcode: u8) -> Self {
+        assert!(opcode <= 0b1111111);
+        self.instr += opcode as u32;
+        self
+    }
+
+    pub fn funct3(mut self, funct3: u8) -> Self {
+        assert!(funct3 <= 0b111);
+        self.instr += (funct3 as u32) << 12;
+        self
+    }
+
+    pub fn rs1(mut self, rs1: u8) -> Self {
+        assert!(rs1 <= 0b11111);
+        self.instr += (rs1 as u32) << 15;
+        self
+    }
+
+    pub fn rs2(mut self, rs2: u8) -> Self {
+        assert!(rs2 <= 0b11111);
+        self.instr += (rs2 as u32) << 20;
+        self
+    }
+
+    pub fn imm(mut self, imm: i16) -> Self {
+        assert!((-4096..=4094).contains(&imm) && imm % 2 == 0);
+        let imm = (imm as u32) & 0x1fff;
+        self.instr += ((imm >> 11) & 0b1) << 7;
+        self.instr += ((imm >> 1) & 0b1111) << 8;
+        self.instr += ((imm >> 5) & 0b111111) << 25;
+        self.instr += (imm >> 12) << 31;
+        self
+    }
+
+    pub fn build(self) -> BTypeInstr {
+        BTypeInstr { instr: self.instr }
+    }
+}
+
+pub struct UTypeInstr {
+    instr: u32,
+}
+
+impl UTypeInstr {
+    pub fn from_bytes(bytes: &[u8; 4]) -> Self {
+        UTypeInstr {
+            instr: u32::from_le_bytes(*bytes),
+        }
+    }
+
+    pub fn builder() -> UTypeBuilder {
+        UTypeBuilder::default()
+    }
+
+    pub fn to_bytes(&self) -> [u8; 4] {
+        self.instr.to_le_bytes()
+    }
+
+    pub fn opcode(&self) -> u8 {
+        (self.instr & 0b1111111) as u8
+    }
+
+    pub fn rd(&self) -> u8 {
+        ((self.instr & (0b11111 << 7)) >> 7) as u8
+    }
+
+    /// Upper 20 bits of the immediate, not shifted
+    pub fn imm(&self) -> u32 {
+        self.instr >> 12
+    }
+}
+
+impl Debug for UTypeInstr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = format!(
+            "{:#032b}: opcode = {:#07b}, rd = {:#05b}, imm = {:#x}",
+            self.instr,
+            self.opcode(),
+            self.rd(),
+            self.imm()
+        );
+        f.write_str(&string)
+    }
+}
+
+#[derive(Default)]
+pub struct UTypeBuilder {
+    instr: u32,
+}
+
+impl UTypeBuilder {
+    pub fn opcode(mut self, opcode: u8) -> Self {
+        assert!(opcode <= 0b1111111);
+        self.instr += opcode as u32;
+        self
+    }
+
+    pub fn rd(mut self, rd: u8) -> Self {
+        assert!(rd <= 0b11111);
+        self.instr += (rd as u32) << 7;
+        self
+    }
+
+    pub fn imm(mut self, imm: u32) -> Self {
+        assert!(imm <= 0xfffff);
+        self.instr += imm << 12;
+        self
+    }
+
+    pub fn build(self) -> UTypeInstr {
+        UTypeInstr { instr: self.instr }
+    }
+}
+
+pub struct JTypeInstr {
+    instr: u32,
+}
+
+impl JTypeInstr {
+    pub fn from_bytes(bytes: &[u8; 4]) -> Self {
+        JTypeInstr {
+            instr: u32::from_le_bytes(*bytes),
+        }
+    }
+
+    pub fn builder() -> JTypeBuilder {
+        JTypeBuilder::default()
+    }
+
+    pub fn to_bytes(&self) -> [u8; 4] {
+        self.instr.to_le_bytes()
+    }
+
+    pub fn opcode(&self) -> u8 {
+        (self.instr & 0b1111111) as u8
+    }
+
+    pub fn rd(&self) -> u8 {
+        ((self.instr & (0b11111 << 7)) >> 7) as u8
+    }
+
+    /// Sign-extended 21-bit jump offset. The lowest bit is always zero.
+    pub fn imm(&self) -> i32 {
+        let high = self.instr & (0b11111111 << 12);
+        let bit11 = (self.instr & (0b1 << 20)) >> 9;
+        let low = (self.instr & (0b1111111111 << 21)) >> 20;
+        let sign = ((self.instr as i32) >> 31) << 20;
+        sign | (high | bit11 | low) as i32
+    }
+}
+
+impl Debug for JTypeInstr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = format!(
+            "{:#032b}: opcode = {:#07b}, rd = {:#05b}, imm = {}",
+            self.instr,
+            self.opcode(),
+            self.rd(),
+            self.imm()
+        );
+        f.write_str(&string)
+    }
+}
+
+#[derive(Default)]
+pub struct JTypeBuilder {
+    instr: u32,
+}
+
+impl JTypeBuilder {
+    pub fn opcode(mut self, opcode: u8) -> Self {
+        assert!(opcode <= 0b1111111);
+        self.instr += opcode as u32;
+        self
+    }
+
+    pub fn rd(mut self, rd: u8) -> Self {
+        assert!(rd <= 0b11111);
+        self.instr += (rd as u32) << 7;
+        self
+    }
+
+    pub fn imm(mut self, imm: i32) -> Self {
+        assert!((-(1 << 20)..(1 << 20)).contains(&imm) && imm % 2 == 0);
+        let imm = (imm as u32) & 0x1fffff;
+        self.instr += imm & (0b11111111 << 12);
+        self.instr += ((imm >> 11) & 0b1) << 20;
+        self.instr += ((imm >> 1) & 0b1111111111) << 21;
+        self.instr += (imm >> 20) << 31;
+        self
+    }
+
+    pub fn build(self) -> JTypeInstr {
+        JTypeInstr { instr: self.instr }
+    }
+}
+
+/// Immediate bit, that each of the instruction bits 12 down to 2 of a compressed instruction
+/// holds. `None` marks the bits of register fields and function codes.
+pub type CImmLayout = [Option<u8>; 11];
+
+/// 16-bit instruction of the C extension
+pub struct CInstr {
+    instr: u16,
+}
+
+impl CInstr {
+    pub fn from_bytes(bytes: &[u8; 2]) -> Self {
+        CInstr {
+            instr: u16::from_le_bytes(*bytes),
+        }
+    }
+
+    pub fn builder() -> CBuilder {
+        CBuilder::default()
+    }
+
+    pub fn to_bytes(&self) -> [u8; 2] {
+        self.instr.to_le_bytes()
+    }
+
+    /// Quadrant of the instruction, 0b11 is reserved for 32-bit instructions
+    pub fn opcode(&self) -> u8 {
+        (self.instr & 0b11) as u8
+    }
+
+    pub fn funct3(&self) -> u8 {
+        (self.instr >> 13) as u8
+    }
+
+    /// Bit 12, that selects the operation of the CR format
+    pub fn bit12(&self) -> u8 {
+        ((self.instr >> 12) & 0b1) as u8
+    }
+
+    /// Bits 11:10, that select the operation of the CB-format ALU instructions
+    pub fn funct2(&self) -> u8 {
+        ((self.instr >> 10) & 0b11) as u8
+    }
+
+    /// Bits 6:5, that select the operation of the CA format
+    pub fn ca_funct2(&self) -> u8 {
+        ((self.instr >> 5) & 0b11) as u8
+    }
+
+    /// Full 5-bit register field in bits 11:7
+    pub fn rd(&self) -> u8 {
+        ((self.instr >> 7) & 0b11111) as u8
+    }
+
+    /// Full 5-bit register field in bits 6:2
+    pub fn rs2(&self) -> u8 {
+        ((self.instr >> 2) & 0b11111) as u8
+    }
+
+    /// Number of the register x8-x15 encoded in bits 9:7
+    pub fn rs1_prime(&self) -> u8 {
+        ((self.instr >> 7) & 0b111) as u8 + 8
+    }
+
+    /// Number of the register x8-x15 encoded in bits 4:2
+    pub fn rs2_prime(&self) -> u8 {
+        ((self.instr >> 2) & 0b111) as u8 + 8
+    }
+
+    /// Zero-extended immediate gathered from the instruction bits 12:2
+    pub fn imm(&self, layout: &CImmLayout) -> u32 {
+        layout
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, bit)| bit.map(|bit| (12 - idx, bit)))
+            .fold(0, |imm, (instr_bit, bit)| {
+                imm | ((((self.instr >> instr_bit) & 0b1) as u32) << bit)
+            })
+    }
+}
+
+impl Debug for CInstr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = format!(
+            "{:#018b}: opcode = {:#04b}, funct3 = {:#05b}",
+            self.instr,
+            self.opcode(),
+            self.funct3()
+        );
+        f.write_str(&string)
+    }
+}
+
+#[derive(Default)]
+pub struct CBuilder {
+    instr: u16,
+}
+
+impl CBuilder {
+    pub fn opcode(mut self, opcode: u8) -> Self {
+        assert!(opcode < 0b11);
+        self.instr += opcode as u16;
+        self
+    }
+
+    pub fn funct3(mut self, funct3: u8) -> Self {
+        assert!(funct3 <= 0b111);
+        self.instr += (funct3 as u16) << 13;
+        self
+    }
+
+    pub fn bit12(mut self, bit12: u8) -> Self {
+        assert!(bit12 <= 0b1);
+        self.instr += (bit12 as u16) << 12;
+        self
+    }
+
+    pub fn funct2(mut self, funct2: u8) -> Self {
+        assert!(funct2 <= 0b11);
+        self.instr += (funct2 as u16) << 10;
+        self
+    }
+
+    pub fn ca_funct2(mut self, funct2: u8) -> Self {
+        assert!(funct2 <= 0b11);
+        self.instr += (funct2 as u16) << 5;
+        self
+    }
+
+    pub fn rd(mut self, rd: u8) -> Self {
+        assert!(rd <= 0b11111);
+        self.instr += (rd as u16) << 7;
+        self
+    }
+
+    pub fn rs2(mut self, rs2: u8) -> Self {
+        assert!(rs2 <= 0b11111);
+        self.instr += (rs2 as u16) << 2;
+        self
+    }
+
+    pub fn rs1_prime(mut self, rs1: u8) -> Self {
+        assert!((8..16).contains(&rs1));
+        self.instr += ((rs1 - 8) as u16) << 7;
+        self
+    }
+
+    pub fn rs2_prime(mut self, rs2: u8) -> Self {
+        assert!((8..16).contains(&rs2));
+        self.instr += ((rs2 - 8) as u16) << 2;
+        self
+    }
+
+    /// Scatter the immediate over the instruction bits 12:2. Bits of the immediate, that
+    /// are not present in the layout, must be handled by the caller.
+    pub fn imm(mut self, layout: &CImmLayout, imm: u32) -> Self {
+        for (idx, bit) in layout.iter().enumerate() {
+            if let Some(bit) = bit {
+                self.instr += (((imm >> bit) & 0b1) as u16) << (12 - idx);
+            }
+        }
+        self
+    }
+
+    pub fn build(self) -> CInstr {
+        CInstr { instr: self.instr }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::utils::RTypeInstr;
+    use crate::utils::{
+        BTypeInstr, CInstr, ITypeInstr, JTypeInstr, RTypeInstr, STypeInstr, UTypeInstr,
+    };
 
     #[test]
     fn test_rtype() {
@@ -333,4 +751,127 @@ mod tests {
         assert_eq!(instr.rs2(), 0b11000);
         assert_eq!(instr.funct7(), 0b1100000);
     }
+
+    #[test]
+    fn test_itype_imm() {
+        // addi x28, x6, -1000
+        let instr = ITypeInstr::from_bytes(&0xc1830e13_u32.to_le_bytes());
+        assert_eq!(instr.imm(), -1000);
+
+        let instr = ITypeInstr::builder()
+            .opcode(0b0010011)
+            .rd(28)
+            .rs1(6)
+            .imm(-1000)
+            .build();
+        assert_eq!(u32::from_le_bytes(instr.to_bytes()), 0xc1830e13);
+
+        // lw x5, 8(x6)
+        let instr = ITypeInstr::from_bytes(&0x00832283_u32.to_le_bytes());
+        assert_eq!(instr.imm(), 8);
+    }
+
+    #[test]
+    fn test_stype_imm() {
+        // sw x5, -20(x6)
+        let instr = STypeInstr::builder()
+            .opcode(0b0100011)
+            .funct3(0b010)
+            .rs1(6)
+            .rs2(5)
+            .imm(-20)
+            .build();
+        assert_eq!(u32::from_le_bytes(instr.to_bytes()), 0xfe532623);
+        assert_eq!(instr.imm(), -20);
+
+        // sw x5, 8(x6)
+        let instr = STypeInstr::from_bytes(&0x00532423_u32.to_le_bytes());
+        assert_eq!(instr.imm(), 8);
+    }
+
+    #[test]
+    fn test_btype() {
+        // beq x5, x6, -16
+        let instr = BTypeInstr::builder()
+            .opcode(0b1100011)
+            .funct3(0b000)
+            .rs1(5)
+            .rs2(6)
+            .imm(-16)
+            .build();
+        assert_eq!(u32::from_le_bytes(instr.to_bytes()), 0xfe6288e3);
+        assert_eq!(instr.imm(), -16);
+
+        // bgeu x10, x11, 2050
+        let instr = BTypeInstr::from_bytes(&0x00b571e3_u32.to_le_bytes());
+        assert_eq!(instr.funct3(), 0b111);
+        assert_eq!(instr.imm(), 2050);
+    }
+
+    #[test]
+    fn test_jtype() {
+        // jal x1, -4
+        let instr = JTypeInstr::from_bytes(&0xffdff0ef_u32.to_le_bytes());
+        assert_eq!(instr.rd(), 1);
+        assert_eq!(instr.imm(), -4);
+
+        // jal x0, 4096
+        let instr = JTypeInstr::builder()
+            .opcode(0b1101111)
+            .rd(0)
+            .imm(4096)
+            .build();
+        assert_eq!(u32::from_le_bytes(instr.to_bytes()), 0x0000106f);
+        assert_eq!(instr.imm(), 4096);
+    }
+
+    #[test]
+    fn test_utype() {
+        // lui x5, 0x12345
+        let instr = UTypeInstr::builder()
+            .opcode(0b0110111)
+            .rd(5)
+            .imm(0x12345)
+            .build();
+        assert_eq!(u32::from_le_bytes(instr.to_bytes()), 0x123452b7);
+        assert_eq!(instr.imm(), 0x12345);
+        assert_eq!(instr.rd(), 5);
+    }
+
+    #[test]
+    fn test_ctype() {
+        // c.lwsp ra, 252(sp): offset[5] in bit 12, offset[4:2|7:6] in bits 6:2
+        let layout = [
+            Some(5),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(4),
+            Some(3),
+            Some(2),
+            Some(7),
+            Some(6),
+        ];
+        let instr = CInstr::from_bytes(&0x50fe_u16.to_le_bytes());
+        assert_eq!(instr.opcode(), 0b10);
+        assert_eq!(instr.funct3(), 0b010);
+        assert_eq!(instr.rd(), 1);
+        assert_eq!(instr.imm(&layout), 252);
+
+        let instr = CInstr::builder()
+            .opcode(0b10)
+            .funct3(0b010)
+            .rd(1)
+            .imm(&layout, 252)
+            .build();
+        assert_eq!(u16::from_le_bytes(instr.to_bytes()), 0x50fe);
+
+        // c.sub a0, a1
+        let instr = CInstr::from_bytes(&0x8d0d_u16.to_le_bytes());
+        assert_eq!(instr.rs1_prime(), 10);
+        assert_eq!(instr.rs2_prime(), 11);
+        assert_eq!(instr.ca_funct2(), 0b00);
+    }
 }
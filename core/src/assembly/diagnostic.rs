@@ -11,6 +11,18 @@ pub enum DiagKind {
     UnknownType(String, String, Span),
     #[error("Duplicate attribute '{0}'")]
     DuplicateAttr(String, Span),
+    #[error("Operation '{0}' expects {1} region(s), got {2}")]
+    RegionCountMismatch(String, usize, usize, Span),
+    #[error("Operation '{0}' {1} a return type")]
+    ReturnTypeMismatch(String, &'static str, Span),
+    #[error("Operation '{0}' has {1} operand(s), but its signature lists {2} type(s)")]
+    OperandCountMismatch(String, usize, usize, Span),
+    #[error("Unknown attribute kind '{0}'")]
+    UnknownAttrKind(String, Span),
+    #[error("Invalid value '{0}' of '{1}' attribute")]
+    InvalidAttrValue(String, String, Span),
+    #[error("Location {0} '{1}' is out of range")]
+    LocationOutOfRange(&'static str, String, Span),
 }
 
 impl DiagnosticLike for DiagKind {
@@ -24,6 +36,12 @@ impl DiagnosticLike for DiagKind {
             DiagKind::UnknownOperation(_, _, span) => span.clone(),
             DiagKind::UnknownType(_, _, span) => span.clone(),
             DiagKind::DuplicateAttr(_, span) => span.clone(),
+            DiagKind::RegionCountMismatch(_, _, _, span) => span.clone(),
+            DiagKind::ReturnTypeMismatch(_, _, span) => span.clone(),
+            DiagKind::OperandCountMismatch(_, _, _, span) => span.clone(),
+            DiagKind::UnknownAttrKind(_, span) => span.clone(),
+            DiagKind::InvalidAttrValue(_, _, span) => span.clone(),
+            DiagKind::LocationOutOfRange(_, _, span) => span.clone(),
         }
     }
 
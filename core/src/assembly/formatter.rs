@@ -1,4 +1,14 @@
+/// Options that control how IR is printed
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PrintFlags {
+    /// Print every operation in the generic form, ignoring custom assembly
+    pub generic: bool,
+    /// Print operation locations
+    pub debuginfo: bool,
+}
+
 pub trait IRFormatter {
+    fn get_flags(&self) -> PrintFlags;
     fn increase_indent(&mut self);
     fn decrease_indent(&mut self);
     fn get_indent(&self) -> u32;
@@ -28,6 +38,7 @@ pub trait IRFormatter {
 
 pub struct StdoutPrinter {
     indent: u32,
+    flags: PrintFlags,
 }
 
 impl Default for StdoutPrinter {
@@ -38,11 +49,19 @@ impl Default for StdoutPrinter {
 
 impl StdoutPrinter {
     pub fn new() -> Self {
-        StdoutPrinter { indent: 0 }
+        Self::with_flags(PrintFlags::default())
+    }
+
+    pub fn with_flags(flags: PrintFlags) -> Self {
+        StdoutPrinter { indent: 0, flags }
     }
 }
 
 impl IRFormatter for StdoutPrinter {
+    fn get_flags(&self) -> PrintFlags {
+        self.flags
+    }
+
     fn increase_indent(&mut self) {
         self.indent += 1;
     }
@@ -63,6 +82,7 @@ impl IRFormatter for StdoutPrinter {
 pub struct StringPrinter {
     indent: u32,
     data: String,
+    flags: PrintFlags,
 }
 
 impl Default for StringPrinter {
@@ -73,9 +93,14 @@ impl Default for StringPrinter {
 
 impl StringPrinter {
     pub fn new() -> Self {
+        Self::with_flags(PrintFlags::default())
+    }
+
+    pub fn with_flags(flags: PrintFlags) -> Self {
         StringPrinter {
             indent: 0,
             data: String::new(),
+            flags,
         }
     }
 
@@ -85,6 +110,10 @@ impl StringPrinter {
 }
 
 impl IRFormatter for StringPrinter {
+    fn get_flags(&self) -> PrintFlags {
+        self.flags
+    }
+
     fn increase_indent(&mut self) {
         self.indent += 1;
     }
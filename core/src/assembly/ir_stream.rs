@@ -4,7 +4,7 @@ use std::{
     sync::Arc,
 };
 
-use crate::{ContextRef, RegionRef, Type};
+use crate::{ContextRef, Location, RegionRef, Type};
 use lpl::{ParseStream, Span};
 
 #[derive(Debug, Clone)]
@@ -21,6 +21,7 @@ pub struct ParserStateImpl {
     deferred_type_list: Vec<Type>,
     deferred_arg_names: Vec<String>,
     cur_region: Vec<RegionRef>,
+    source: String,
 }
 
 #[derive(Debug)]
@@ -30,6 +31,7 @@ impl<'a> IRStrStream<'a> {
     pub fn new(string: &'a str, filename: &'a str, context: ContextRef) -> Self {
         let filename = Arc::new(filename.to_string());
         let state = ParserState::new(context);
+        state.0.borrow_mut().source = string.to_owned();
         Self {
             string,
             filename,
@@ -125,6 +127,7 @@ impl ParserState {
             deferred_type_list: vec![],
             deferred_arg_names: vec![],
             cur_region: vec![],
+            source: String::new(),
         })))
     }
 
@@ -159,4 +162,9 @@ impl ParserState {
     pub fn set_deferred_names(&self, names: Vec<String>) {
         self.0.borrow_mut().deferred_arg_names = names;
     }
+
+    /// Convert parser span to a source location
+    pub fn location(&self, span: &Span) -> Location {
+        Location::from_span(span, &self.0.borrow().source)
+    }
 }
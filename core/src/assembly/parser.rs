@@ -19,14 +19,18 @@ use lpl::combinators::text::take_while;
 use lpl::combinators::zero_or_more;
 use lpl::Diagnostic;
 use lpl::ParseResult;
+use lpl::Span;
 use lpl::{ParseStream, Parser};
 
 use crate::assembly::ir_stream::{IRStrStream, ParserState};
+use crate::builtin::OpaqueOp;
 use crate::Attr;
 use crate::Block;
 use crate::BlockRef;
+use crate::Location;
 use crate::Region;
 use crate::RegionRef;
+use crate::Type;
 use crate::{ContextRef, OpRef};
 
 use super::DiagKind;
@@ -38,6 +42,7 @@ pub trait Parsable<T> {
 
 /// Parse textual TIR into inner structures
 pub fn parse_ir(context: ContextRef, input: &str, filename: &str) -> Result<OpRef, Diagnostic> {
+    context.get_diagnostic_engine().add_source(filename, input);
     let stream = IRStrStream::new(input, filename, context);
 
     let parser = single_op();
@@ -112,6 +117,36 @@ pub fn identifier<'a>() -> impl Parser<'a, IRStrStream<'a>, &'a str> {
     ident(|c| c == '_' || c == '.').label("identifier")
 }
 
+/// Parse double-quoted string literal. A backslash escapes the character that
+/// follows it, e.g. `\"` or `\\`.
+pub fn string_literal<'a>() -> impl Parser<'a, IRStrStream<'a>, String> {
+    let parser = move |input: IRStrStream<'a>| {
+        let span = input.span();
+        let (_, next_input) = literal("\"").parse(input)?;
+        let Some(next_input) = next_input else {
+            return Err(lpl::InternalError::ExpectedNotFound("\"", span).into());
+        };
+
+        let mut value = String::new();
+        let mut escaped = false;
+        for (offset, c) in next_input.chars().as_str().char_indices() {
+            match c {
+                _ if escaped => {
+                    value.push(c);
+                    escaped = false;
+                }
+                '\\' => escaped = true,
+                '"' => return Ok((value, next_input.slice(offset + 1..next_input.len()))),
+                _ => value.push(c),
+            }
+        }
+
+        Err(lpl::InternalError::ExpectedNotFound("\"", span).into())
+    };
+
+    parser.label("string_literal")
+}
+
 /// Parse all operations inside a single basic block region.
 /// Syntax is:
 /// ```tir
@@ -136,31 +171,57 @@ pub fn single_block_region<'a>() -> impl Parser<'a, IRStrStream<'a>, Vec<OpRef>>
 /// attrs = {attr1 = <str: "Hello, World!">, attr2 = <i8: 42>}
 /// ```
 pub fn attr_list<'a>() -> impl Parser<'a, IRStrStream<'a>, HashMap<String, Attr>> {
-    let single_attribute = identifier()
+    spaced(literal("attrs"))
         .and_then(spaced(literal("=")))
-        .and_then(Attr::parse)
-        .map(|((name, _), attr)| (name.to_string(), attr));
+        .and_then(attr_dict())
+        .map(|(_, attrs)| attrs)
+        .label("attr_list")
+}
 
-    let attr_pairs = separated_ignore(single_attribute, spaced(literal(",")).void());
+/// Parse attributes dictionary.
+///
+/// Syntax example:
+/// ```tir
+/// {attr1 = <str: "Hello, World!">, attr2 = <i8: 42>}
+/// ```
+pub fn attr_dict<'a>() -> impl Parser<'a, IRStrStream<'a>, HashMap<String, Attr>> {
+    let parser = move |input: IRStrStream<'a>| {
+        let span = input.span();
+        let (_, mut next_input) = spaced(literal("{")).parse(input)?;
+
+        let mut attrs = HashMap::new();
+        while let Some(input) = next_input.clone() {
+            let Ok((name, Some(value_input))) = identifier()
+                .and_then(spaced(literal("=")))
+                .map(|(name, _)| name)
+                .parse(input)
+            else {
+                break;
+            };
+            // The value is mandatory after the name, so that invalid values are reported
+            let (attr, ni) = Attr::parse(value_input)?;
+            if attrs.insert(name.to_string(), attr).is_some() {
+                return Err(DiagKind::DuplicateAttr(name.to_string(), span).into());
+            }
+            next_input = ni;
+
+            match next_input
+                .clone()
+                .map(|input| spaced(literal(",")).parse(input))
+            {
+                Some(Ok((_, ni))) => next_input = ni,
+                _ => break,
+            }
+        }
 
-    literal("attrs")
-        .and_then(spaced(literal("=")))
-        .and_then(spaced(literal("{")))
-        .and_then(attr_pairs)
-        .and_then(spaced(literal("}")))
-        .flat()
-        .try_map(|(_, _, _, pairs, _), span| {
-            let mut map = HashMap::new();
-            for (k, v) in pairs.iter() {
-                if map.contains_key(k) {
-                    return Err(DiagKind::DuplicateAttr(k.clone(), span).into());
-                }
+        let Some(input) = next_input else {
+            return Err(lpl::InternalError::ExpectedNotFound("}", span).into());
+        };
+        let (_, next_input) = spaced(literal("}")).parse(input)?;
+        Ok((attrs, next_input))
+    };
 
-                map.insert(k.clone(), v.clone());
-            }
-            Ok(map)
-        })
-        .label("attr_list")
+    parser.label("attr_dict")
 }
 
 pub fn skip_attrs<'a>() -> impl Parser<'a, IRStrStream<'a>, HashMap<String, Attr>> {
@@ -174,16 +235,19 @@ pub fn single_block<'a>() -> impl Parser<'a, IRStrStream<'a>, BlockRef> {
         .and_then(literal(":"))
         .flat()
         .map(|(_, _, name, _)| name)
-        .and_then(one_or_more(single_op()))
-        .map_with(|(block_name, ops), extra| {
-            let state = extra.unwrap();
+        .map_with(|block_name, extra| {
+            let state: &Arc<ParserState> = extra.unwrap();
 
             let region = state.get_region();
 
+            // Arguments must be taken before nested operations are parsed, as those
+            // may defer arguments for their own blocks.
             let names = state.take_deferred_names();
             let types = state.take_deferred_types();
-            let block = Block::with_arguments(block_name, &region, &types, &names);
-
+            Block::with_arguments(block_name, &region, &types, &names)
+        })
+        .and_then(one_or_more(single_op()))
+        .map(|(block, ops)| {
             for op in ops {
                 block.push(&op);
             }
@@ -218,6 +282,174 @@ pub fn region_with_blocks<'a>() -> impl Parser<'a, IRStrStream<'a>, RegionRef> {
         })
 }
 
+/// Parse a block in the generic form. Unlike [`single_block`], block arguments are
+/// spelled out explicitly, and the block may be empty.
+///
+/// Syntax example:
+/// ```tir
+/// ^entry(%arg0: !void, %arg1: !int<8>):
+/// ```
+fn generic_block<'a>() -> impl Parser<'a, IRStrStream<'a>, BlockRef> {
+    let single_arg = literal("%")
+        .and_then(identifier())
+        .and_then(spaced(literal(":")))
+        .and_then(Type::parse)
+        .map(|(((_, name), _), ty)| (name, ty));
+    let args = spaced(literal("("))
+        .and_then(separated_ignore(single_arg, spaced(literal(",")).void()))
+        .and_then(spaced(literal(")")))
+        .flat()
+        .map(|(_, args, _)| args);
+
+    spaced(literal("^"))
+        .and_then(ident(|c| c == '_'))
+        .and_then(optional(args))
+        .and_then(spaced(literal(":")))
+        .flat()
+        .map(|(_, name, args, _)| (name, args.unwrap_or_default()))
+        .and_then(zero_or_more(single_op()))
+        .map_with(|((block_name, args), ops), extra| {
+            let state = extra.unwrap();
+
+            let region = state.get_region();
+
+            let (names, types): (Vec<&str>, Vec<Type>) = args.into_iter().unzip();
+            let block = Block::with_arguments(block_name, &region, &types, &names);
+
+            for op in ops {
+                block.push(&op);
+            }
+
+            block
+        })
+        .label("generic_block")
+}
+
+/// Parse a region in the generic form
+fn generic_region<'a>() -> impl Parser<'a, IRStrStream<'a>, RegionRef> {
+    spaced(literal("{"))
+        .map_with(|_, extra| {
+            let state: &Arc<ParserState> = extra.unwrap();
+            let context = state.context();
+
+            let region = Region::empty(&context);
+            state.push_region(region.clone());
+
+            region
+        })
+        .and_then(zero_or_more(generic_block()))
+        .and_then(spaced(literal("}")))
+        .map_with(|((region, blocks), _), extra| {
+            let state: &Arc<ParserState> = extra.unwrap();
+
+            for block in blocks {
+                region.add_block(block);
+            }
+
+            state.pop_region();
+
+            region
+        })
+        .label("generic_region")
+}
+
+/// Everything that follows operands in the generic operation form
+pub struct GenericOpTail {
+    pub attrs: HashMap<String, Attr>,
+    pub regions: Vec<RegionRef>,
+    pub operand_types: Vec<Type>,
+    pub return_type: Option<Type>,
+}
+
+/// Parse attributes, regions and signature of an operation in the generic form.
+/// Attributes and regions may be omitted, the signature is mandatory.
+///
+/// Syntax example:
+/// ```tir
+/// {attr = <i8: 0>} ({ ^entry: ... }, { ^entry: ... }) : (!i8, !i8) -> !void
+/// ```
+pub fn generic_op_tail<'a>() -> impl Parser<'a, IRStrStream<'a>, GenericOpTail> {
+    let parser = move |input: IRStrStream<'a>| {
+        let span = input.span();
+
+        // Once the opening brace is seen, the attribute dictionary is mandatory, so that
+        // invalid attribute values are reported
+        let (attrs, next_input) = match spaced(literal("{")).parse(input.clone()) {
+            Ok(_) => attr_dict().parse(input)?,
+            Err(_) => (HashMap::new(), Some(input)),
+        };
+
+        let Some(next_input) = next_input else {
+            return Err(lpl::InternalError::ExpectedNotFound(":", span).into());
+        };
+
+        // Once the opening parenthesis is seen, region list is mandatory. That way
+        // errors inside nested regions are reported to the user.
+        let (regions, next_input) = match spaced(literal("(")).parse(next_input.clone()) {
+            Ok((_, Some(next_input))) => {
+                separated_ignore(generic_region(), spaced(literal(",")).void())
+                    .and_then(spaced(literal(")")))
+                    .map(|(regions, _)| regions)
+                    .parse(next_input)?
+            }
+            _ => (vec![], Some(next_input)),
+        };
+
+        let Some(next_input) = next_input else {
+            return Err(lpl::InternalError::ExpectedNotFound(":", span).into());
+        };
+
+        let ((operand_types, return_type), next_input) = spaced(literal(":"))
+            .and_then(generic_signature())
+            .map(|(_, signature)| signature)
+            .parse(next_input)?;
+
+        let tail = GenericOpTail {
+            attrs,
+            regions,
+            operand_types,
+            return_type,
+        };
+
+        Ok((tail, next_input))
+    };
+
+    parser.label("generic_op_tail")
+}
+
+/// Parse `(operand types) -> result type` signature. Operations without a result
+/// specify `()` instead of the result type.
+fn generic_signature<'a>() -> impl Parser<'a, IRStrStream<'a>, (Vec<Type>, Option<Type>)> {
+    let no_result = spaced(literal("("))
+        .and_then(spaced(literal(")")))
+        .map(|_| None);
+    let result = spaced(Type::parse).map(Some).or_else(no_result);
+
+    spaced(literal("("))
+        .and_then(separated_ignore(
+            spaced(Type::parse),
+            spaced(literal(",")).void(),
+        ))
+        .and_then(spaced(literal(")")))
+        .and_then(spaced(literal("->")))
+        .and_then(result)
+        .map(|((((_, operand_types), _), _), return_type)| (operand_types, return_type))
+        .label("generic_signature")
+}
+
+/// Parse operands of an unregistered operation. Since operand types are unknown,
+/// operands are preserved as raw strings.
+fn opaque_operands<'a>() -> impl Parser<'a, IRStrStream<'a>, Vec<String>> {
+    let operand = take_while(|&c| c != ',' && c != ')').map(|o| o.trim().to_string());
+
+    spaced(literal("("))
+        .and_then(separated_ignore(operand, spaced(literal(",")).void()))
+        .and_then(spaced(literal(")")))
+        .flat()
+        .map(|(_, operands, _)| operands)
+        .label("opaque_operands")
+}
+
 pub fn parse_int_bits<'a>() -> impl Parser<'a, IRStrStream<'a>, HashMap<String, Attr>> {
     literal("<")
         .and_then(take_while(|c| c.is_numeric()))
@@ -246,6 +478,15 @@ fn dialect_op<'a>() -> impl Parser<'a, IRStrStream<'a>, (&'a str, &'a str)> {
         .label("dialect_op")
 }
 
+/// "dialect_name.op_name" -> (dialect_name, op_name)
+fn generic_op_name<'a>() -> impl Parser<'a, IRStrStream<'a>, (&'a str, &'a str)> {
+    literal("\"")
+        .and_then(op_name())
+        .and_then(literal("\""))
+        .map(|((_, name), _)| name)
+        .label("generic_op_name")
+}
+
 /// "builtin op"-style identifier
 fn builtin_op<'a>() -> impl Parser<'a, IRStrStream<'a>, (&'a str, &'a str)> {
     ident(|c| c == '_')
@@ -253,8 +494,59 @@ fn builtin_op<'a>() -> impl Parser<'a, IRStrStream<'a>, (&'a str, &'a str)> {
         .label("builtin_op")
 }
 
+/// Parse the rest of the operation in the generic form, once its name is known
+fn generic_op<'a>(
+    input: IRStrStream<'a>,
+    dialect_name: &'a str,
+    op_name: &'a str,
+    next_input: IRStrStream<'a>,
+) -> ParseResult<IRStrStream<'a>, OpRef> {
+    let state = input.get_extra().unwrap();
+    let context = state.context();
+
+    let Some(dialect) = context.get_dialect_by_name(dialect_name) else {
+        if !context.allows_unregistered_dialects() {
+            return Err(DiagKind::UnknownDialect(dialect_name.to_owned(), input.span()).into());
+        }
+
+        let (operands, next_input) = opaque_operands().parse(next_input)?;
+        let (tail, next_input) = generic_op_tail().parse(next_input.unwrap())?;
+
+        if tail.operand_types.len() != operands.len() {
+            return Err(DiagKind::OperandCountMismatch(
+                format!("{}.{}", dialect_name, op_name),
+                operands.len(),
+                tail.operand_types.len(),
+                input.span(),
+            )
+            .into());
+        }
+
+        let op: OpRef = OpaqueOp::new(&context, dialect_name, op_name, operands, tail);
+        return Ok((op, next_input));
+    };
+
+    let operation_id = dialect
+        .get_operation_id(op_name)
+        .ok_or(Into::<Diagnostic>::into(DiagKind::UnknownOperation(
+            op_name.to_owned(),
+            dialect_name.to_owned(),
+            input.span(),
+        )))?;
+
+    // It is impossible to add an operation without specifying its parser
+    let parser = dialect.get_operation_generic_parser(operation_id).unwrap();
+    parser.parse(next_input)
+}
+
 fn single_op<'a>() -> impl Parser<'a, IRStrStream<'a>, OpRef> {
     let parser = move |input: IRStrStream<'a>| {
+        if let Ok(((dialect_name, op_name), next_input)) =
+            spaced(generic_op_name()).parse(input.clone())
+        {
+            return generic_op(input, dialect_name, op_name, next_input.unwrap());
+        }
+
         let ((dialect_name, op_name), next_input) = spaced(op_name()).parse(input.clone())?;
 
         // It is impossible to construct IRStrStream without a context
@@ -281,6 +573,35 @@ fn single_op<'a>() -> impl Parser<'a, IRStrStream<'a>, OpRef> {
         parser.parse(next_input.unwrap())
     };
 
+    // Attach source location to every parsed operation. An explicit trailing
+    // location takes precedence over the position in the current file.
+    let parser = move |input: IRStrStream<'a>| {
+        let state = input.get_extra().unwrap().clone();
+        let text = input.substr(0..input.len()).unwrap_or_default();
+        let skip = text.len() - text.trim_start().len();
+        let span = Span::new(
+            input.span().clone_filename(),
+            input.span().get_offset_start() + skip,
+            input.span().get_offset_start() + skip,
+        );
+
+        let (op, next_input) = parser.parse(input)?;
+
+        // Once `loc(` is seen, the location is mandatory, so that malformed
+        // locations are reported
+        let (location, next_input) = match next_input {
+            Some(next_input) if spaced(literal("loc(")).parse(next_input.clone()).is_ok() => {
+                spaced(Location::parse).map(Some).parse(next_input)?
+            }
+            next_input => (None, next_input),
+        };
+
+        let location = location.unwrap_or_else(|| state.location(&span));
+        op.borrow_mut().set_location(location);
+
+        Ok((op, next_input))
+    };
+
     maybe_then(
         optional(
             zero_or_more(
@@ -318,6 +639,23 @@ mod tests {
         assert_eq!(attrs.get("attr2").unwrap(), &Attr::I8(42));
     }
 
+    #[test]
+    fn test_attr_list_special_strings() {
+        let context = crate::Context::new();
+        let input = "attrs = {attr1 = <str: \"a > b, {c}\">, attr2 = <str: \"\">, attr3 = <str: \"\\\"\\\\\">}";
+        let input = IRStrStream::new(input, "-", context);
+        let (attrs, _) = attr_list().parse(input).unwrap();
+        assert_eq!(
+            attrs.get("attr1").unwrap(),
+            &Attr::String("a > b, {c}".to_string())
+        );
+        assert_eq!(attrs.get("attr2").unwrap(), &Attr::String(String::new()));
+        assert_eq!(
+            attrs.get("attr3").unwrap(),
+            &Attr::String("\"\\".to_string())
+        );
+    }
+
     #[test]
     fn test_attr_list_duplicate() {
         let context = crate::Context::new();
@@ -327,6 +665,21 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_attr_list_invalid_values() {
+        let context = crate::Context::new();
+        for input in [
+            "attrs = {attr = <bool: 2>}",
+            "attrs = {attr = <i8: 128>}",
+            "attrs = {attr = <u32s: [1, x]>}",
+            "attrs = {attr = <i64s: 1>}",
+            "attrs = {attr = <float: 1.0>}",
+        ] {
+            let input = IRStrStream::new(input, "-", context.clone());
+            assert!(attr_list().parse(input).is_err());
+        }
+    }
+
     #[test]
     fn parse_op_name() {
         let context = crate::Context::new();
@@ -1,9 +1,19 @@
-use crate::{BlockRef, IRFormatter, RegionRef};
+use std::collections::HashMap;
+
+use crate::{Attr, BlockRef, IRFormatter, Location, RegionRef, StringPrinter, Type};
 
 pub trait Printable {
     fn print(&self, fmt: &mut dyn IRFormatter);
 }
 
+/// Prints a double-quoted string literal, escaping quotes and backslashes, so
+/// that it can be read back by [`crate::parser::string_literal`]
+pub fn print_string_literal(fmt: &mut dyn IRFormatter, value: &str) {
+    fmt.write_direct("\"");
+    fmt.write_direct(&value.replace('\\', "\\\\").replace('"', "\\\""));
+    fmt.write_direct("\"");
+}
+
 /// Prints given values as a comma separated list
 pub fn print_comma_separated(fmt: &mut dyn IRFormatter, tokens: &[String]) {
     // FIXME: come up with zero allocation way
@@ -30,10 +40,115 @@ pub fn print_region(fmt: &mut dyn IRFormatter, region: &RegionRef) {
     fmt.end_region();
 }
 
+/// Prints block with explicit argument list, as expected by the generic operation form
+fn print_generic_block(fmt: &mut dyn IRFormatter, block: &BlockRef) {
+    fmt.indent();
+    fmt.write_direct(&format!("^{}", block.get_name()));
+
+    let args: Vec<_> = block
+        .get_args()
+        .map(|arg| {
+            let mut printer = StringPrinter::new();
+            printer.write_direct(&format!("%{}: ", &arg.get_name()));
+            arg.get_type().print(&mut printer);
+            printer.get()
+        })
+        .collect();
+    if !args.is_empty() {
+        fmt.write_direct("(");
+        print_comma_separated(fmt, &args);
+        fmt.write_direct(")");
+    }
+    fmt.write_direct(":\n");
+
+    for op in block.iter() {
+        op.borrow().print(fmt);
+    }
+}
+
+/// Types of an operation in the generic form
+pub struct GenericSignature<'a> {
+    pub operand_types: &'a [Type],
+    pub return_type: Option<Type>,
+}
+
+/// Prints operation in the generic form. The signature lists operand types and
+/// the return type, or `()` if there is none.
+///
+/// Syntax example:
+/// ```tir
+/// "dialect.op"(%a, %b) {attr = <i8: 0>} ({ ... }, { ... }) : (!i8, !i8) -> !void
+/// ```
+pub fn print_generic_op(
+    fmt: &mut dyn IRFormatter,
+    name: &str,
+    operands: &[String],
+    attrs: &HashMap<String, Attr>,
+    regions: &[RegionRef],
+    signature: GenericSignature<'_>,
+    location: &Location,
+) {
+    fmt.indent();
+    fmt.write_direct(&format!("\"{}\"(", name));
+    print_comma_separated(fmt, operands);
+    fmt.write_direct(") {");
+
+    let mut names: Vec<_> = attrs.keys().collect();
+    names.sort();
+    let attrs: Vec<_> = names
+        .into_iter()
+        .map(|name| {
+            let mut printer = StringPrinter::new();
+            printer.write_direct(&format!("{} = ", name));
+            attrs[name].print(&mut printer);
+            printer.get()
+        })
+        .collect();
+    print_comma_separated(fmt, &attrs);
+    fmt.write_direct("}");
+
+    if !regions.is_empty() {
+        fmt.write_direct(" (");
+        for (idx, region) in regions.iter().enumerate() {
+            if idx != 0 {
+                fmt.write_direct(", ");
+            }
+            fmt.start_region();
+            for block in region.iter() {
+                print_generic_block(fmt, &block);
+            }
+            fmt.end_region();
+        }
+        fmt.write_direct(")");
+    }
+
+    fmt.write_direct(" : (");
+    for (idx, ty) in signature.operand_types.iter().enumerate() {
+        if idx != 0 {
+            fmt.write_direct(", ");
+        }
+        ty.print(fmt);
+    }
+    fmt.write_direct(") -> ");
+    match signature.return_type {
+        Some(ty) => ty.print(fmt),
+        None => fmt.write_direct("()"),
+    }
+
+    if fmt.get_flags().debuginfo {
+        fmt.write_direct(" ");
+        location.print(fmt);
+    }
+
+    fmt.write_direct("\n");
+}
+
 #[cfg(test)]
 mod tests {
     use crate::builtin::ModuleOp;
+    use crate::parse_ir;
     use crate::Context;
+    use crate::PrintFlags;
     use crate::Printable;
     use crate::StringPrinter;
 
@@ -51,4 +166,90 @@ mod tests {
         let golden = "module {\n}\n";
         assert_eq!(result, golden);
     }
+
+    #[test]
+    fn test_module_print_generic() {
+        let context = Context::new();
+        let module = ModuleOp::builder(&context).build();
+
+        let mut printer = StringPrinter::with_flags(PrintFlags {
+            generic: true,
+            ..Default::default()
+        });
+
+        module.borrow().print(&mut printer);
+
+        let result = printer.get();
+
+        let golden = "\"builtin.module\"() {} ({\n  ^entry:\n}) : () -> ()\n";
+        assert_eq!(result, golden);
+
+        let module = parse_ir(context.clone(), &result, "-").unwrap();
+        let mut printer = StringPrinter::new();
+        module.borrow().print(&mut printer);
+        assert_eq!(printer.get(), "module {\n}\n");
+    }
+
+    mod format_dialect {
+        use crate as tir_core;
+        use crate::{Dialect, IRFormatter, Op, OpAssembly, OpImpl, Type};
+        use lpl::ParseStream;
+        use tir_macros::{dialect, populate_dialect_ops, Op, OpAssembly, OpValidator};
+
+        #[derive(Op, OpAssembly, OpValidator)]
+        #[operation(
+            name = "cast",
+            dialect = format_test,
+            known_attrs(label: String, width: u32, note: Option<String>),
+            assembly_format = "$label `(` $width `)` attr-dict `:` type($result)"
+        )]
+        pub struct CastOp {
+            #[ret_type]
+            result: Type,
+            r#impl: OpImpl,
+        }
+
+        dialect!(format_test);
+        populate_dialect_ops!(CastOp);
+
+        fn populate_dialect_types(_dialect: &mut Dialect) {}
+    }
+
+    #[test]
+    fn test_assembly_format_roundtrip() {
+        use crate::builtin::IntType;
+        use crate::OpBuilder;
+        use format_dialect::CastOp;
+
+        let context = Context::new();
+        context.add_dialect(format_dialect::create_dialect());
+
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let cast = CastOp::builder(&context)
+            .label("x".to_string())
+            .width(8)
+            .result(IntType::build(context.clone(), 8).into())
+            .build();
+        builder.insert(&cast);
+        let cast = CastOp::builder(&context)
+            .label("y".to_string())
+            .width(16)
+            .note("wide".to_string())
+            .result(IntType::build(context.clone(), 16).into())
+            .build();
+        builder.insert(&cast);
+
+        let mut printer = StringPrinter::new();
+        module.borrow().print(&mut printer);
+        let result = printer.get();
+
+        let golden = "module {\n  format_test.cast \"x\" (<u32: 8>) : !int<8>\n  format_test.cast \"y\" (<u32: 16>) {note = <str: \"wide\">} : !int<16>\n}\n";
+        assert_eq!(result, golden);
+
+        let module = parse_ir(context.clone(), &result, "-").unwrap();
+        let mut printer = StringPrinter::new();
+        module.borrow().print(&mut printer);
+        assert_eq!(printer.get(), golden);
+    }
 }
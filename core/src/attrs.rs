@@ -1,9 +1,12 @@
 use lpl::{
-    combinators::{lang::ident, literal, spaced, text::take_while},
-    ParseResult, Parser,
+    combinators::{lang::ident, literal, separated_ignore, spaced, text::take_while},
+    Diagnostic, ParseResult, Parser, Span,
 };
 
-use crate::{parser::Parsable, IRStrStream, Printable, Type};
+use crate::{
+    parser::{string_literal, Parsable},
+    DiagKind, IRStrStream, Printable, Type,
+};
 
 macro_rules! impl_from {
     ($case:ident, $from:ty) => {
@@ -64,7 +67,11 @@ pub enum Attr {
 impl Printable for Attr {
     fn print(&self, fmt: &mut dyn crate::IRFormatter) {
         match self {
-            Attr::String(value) => fmt.write_direct(&format!("<str: \"{}\">", &value)),
+            Attr::String(value) => {
+                fmt.write_direct("<str: ");
+                crate::print_string_literal(fmt, value);
+                fmt.write_direct(">");
+            }
             Attr::Bool(value) => fmt.write_direct(&format!("<bool: {}>", &value)),
             Attr::I8(value) => fmt.write_direct(&format!("<i8: {}>", &value)),
             Attr::U8(value) => fmt.write_direct(&format!("<u8: {}>", &value)),
@@ -74,43 +81,156 @@ impl Printable for Attr {
             Attr::U32(value) => fmt.write_direct(&format!("<u32: {}>", &value)),
             Attr::I64(value) => fmt.write_direct(&format!("<i64: {}>", &value)),
             Attr::U64(value) => fmt.write_direct(&format!("<u64: {}>", &value)),
-            _ => todo!(),
+            Attr::Type(value) => {
+                fmt.write_direct("<type: ");
+                value.print(fmt);
+                fmt.write_direct(">");
+            }
+            Attr::TypeArray(values) => {
+                fmt.write_direct("<types: [");
+                for (idx, value) in values.iter().enumerate() {
+                    if idx != 0 {
+                        fmt.write_direct(", ");
+                    }
+                    value.print(fmt);
+                }
+                fmt.write_direct("]>");
+            }
+            Attr::I8Array(values) => print_array(fmt, "i8s", values),
+            Attr::U8Array(values) => print_array(fmt, "u8s", values),
+            Attr::I16Array(values) => print_array(fmt, "i16s", values),
+            Attr::U16Array(values) => print_array(fmt, "u16s", values),
+            Attr::I32Array(values) => print_array(fmt, "i32s", values),
+            Attr::U32Array(values) => print_array(fmt, "u32s", values),
+            Attr::I64Array(values) => print_array(fmt, "i64s", values),
+            Attr::U64Array(values) => print_array(fmt, "u64s", values),
         }
     }
 }
 
+fn print_array<T: std::fmt::Display>(fmt: &mut dyn crate::IRFormatter, kind: &str, values: &[T]) {
+    let values: Vec<_> = values.iter().map(|value| value.to_string()).collect();
+    fmt.write_direct(&format!("<{}: [{}]>", kind, values.join(", ")));
+}
+
+/// Scalar value of an attribute of the given kind
+fn parse_value<T: std::str::FromStr>(
+    value: &str,
+    kind: &str,
+    span: &Span,
+) -> Result<T, Diagnostic> {
+    value.parse::<T>().map_err(|_| {
+        DiagKind::InvalidAttrValue(value.to_string(), kind.to_string(), span.clone()).into()
+    })
+}
+
+/// Elements of an integer array, i.e. `[1, 2, 3]`
+fn parse_array<T: std::str::FromStr>(
+    value: &str,
+    kind: &str,
+    span: &Span,
+) -> Result<Vec<T>, Diagnostic> {
+    let elements = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .ok_or_else(|| {
+            Diagnostic::from(DiagKind::InvalidAttrValue(
+                value.to_string(),
+                kind.to_string(),
+                span.clone(),
+            ))
+        })?;
+    elements
+        .split(',')
+        .map(str::trim)
+        .filter(|element| !element.is_empty())
+        .map(|element| parse_value(element, kind, span))
+        .collect()
+}
+
 impl Parsable<Attr> for Attr {
     fn parse(input: IRStrStream) -> ParseResult<IRStrStream, Attr> {
-        let parser = spaced(literal("<"))
+        let (kind, next_input) = spaced(literal("<"))
             .and_then(ident(|_| false))
             .and_then(spaced(literal(":")))
-            .and_then(take_while(|&c| c != '>'))
-            .and_then(spaced(literal(">")))
             .flat()
-            .try_map(|(_, ty, _, value, _), _span| {
+            .map(|(_, kind, _)| kind)
+            .parse(input)?;
+
+        // Types have their own grammar, that may contain '>'
+        match kind.trim() {
+            "type" => {
+                return Type::parse
+                    .and_then(spaced(literal(">")))
+                    .map(|(ty, _)| Attr::Type(ty))
+                    .parse(next_input.unwrap());
+            }
+            "types" => {
+                return spaced(literal("["))
+                    .and_then(separated_ignore(
+                        spaced(Type::parse),
+                        spaced(literal(",")).void(),
+                    ))
+                    .and_then(spaced(literal("]")))
+                    .and_then(spaced(literal(">")))
+                    .flat()
+                    .map(|(_, types, _, _)| Attr::TypeArray(types))
+                    .parse(next_input.unwrap());
+            }
+            // Strings may contain '>'
+            "str" => {
+                return spaced(string_literal())
+                    .and_then(spaced(literal(">")))
+                    .map(|(value, _)| Attr::String(value))
+                    .parse(next_input.unwrap());
+            }
+            _ => {}
+        }
+
+        let parser = take_while(|&c| c != '>')
+            .and_then(spaced(literal(">")))
+            .try_map(move |(value, _), span| {
                 let value = value.trim();
-                let ty = ty.trim();
-                match ty {
-                    "str" => Ok(Attr::String(
-                        value
-                            .strip_prefix("\"")
-                            .unwrap()
-                            .strip_suffix("\"")
-                            .unwrap()
-                            .to_string(),
-                    )),
-                    "i8" => Ok(Attr::I8(value.parse::<i8>().unwrap())),
-                    "u8" => Ok(Attr::U8(value.parse::<u8>().unwrap())),
-                    "i16" => Ok(Attr::I16(value.parse::<i16>().unwrap())),
-                    "u16" => Ok(Attr::U16(value.parse::<u16>().unwrap())),
-                    "i32" => Ok(Attr::I32(value.parse::<i32>().unwrap())),
-                    "u32" => Ok(Attr::U32(value.parse::<u32>().unwrap())),
-                    "i64" => Ok(Attr::I64(value.parse::<i64>().unwrap())),
-                    "u64" => Ok(Attr::U64(value.parse::<u64>().unwrap())),
-                    _ => todo!(),
+                let kind = kind.trim();
+                match kind {
+                    "bool" => Ok(Attr::Bool(parse_value(value, kind, &span)?)),
+                    "i8" => Ok(Attr::I8(parse_value(value, kind, &span)?)),
+                    "u8" => Ok(Attr::U8(parse_value(value, kind, &span)?)),
+                    "i16" => Ok(Attr::I16(parse_value(value, kind, &span)?)),
+                    "u16" => Ok(Attr::U16(parse_value(value, kind, &span)?)),
+                    "i32" => Ok(Attr::I32(parse_value(value, kind, &span)?)),
+                    "u32" => Ok(Attr::U32(parse_value(value, kind, &span)?)),
+                    "i64" => Ok(Attr::I64(parse_value(value, kind, &span)?)),
+                    "u64" => Ok(Attr::U64(parse_value(value, kind, &span)?)),
+                    "i8s" => Ok(Attr::I8Array(parse_array(value, kind, &span)?)),
+                    "u8s" => Ok(Attr::U8Array(parse_array(value, kind, &span)?)),
+                    "i16s" => Ok(Attr::I16Array(parse_array(value, kind, &span)?)),
+                    "u16s" => Ok(Attr::U16Array(parse_array(value, kind, &span)?)),
+                    "i32s" => Ok(Attr::I32Array(parse_array(value, kind, &span)?)),
+                    "u32s" => Ok(Attr::U32Array(parse_array(value, kind, &span)?)),
+                    "i64s" => Ok(Attr::I64Array(parse_array(value, kind, &span)?)),
+                    "u64s" => Ok(Attr::U64Array(parse_array(value, kind, &span)?)),
+                    _ => Err(DiagKind::UnknownAttrKind(kind.to_string(), span).into()),
                 }
             });
-        parser.parse(input)
+        parser.parse(next_input.unwrap())
+    }
+}
+
+impl Attr {
+    /// Value of an integer attribute of any width
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Attr::I8(value) => Some(*value as i64),
+            Attr::U8(value) => Some(*value as i64),
+            Attr::I16(value) => Some(*value as i64),
+            Attr::U16(value) => Some(*value as i64),
+            Attr::I32(value) => Some(*value as i64),
+            Attr::U32(value) => Some(*value as i64),
+            Attr::I64(value) => Some(*value),
+            Attr::U64(value) => Some(*value as i64),
+            _ => None,
+        }
     }
 }
 
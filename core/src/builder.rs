@@ -1,6 +1,26 @@
-use crate::{BlockRef, ContextRef, Op, OpRef};
+use crate::{BlockRef, ContextRef, Location, Op, OpRef, RwCell};
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::Arc;
+
+/// Marks a required attribute that has been provided to an operation builder
+///
+/// Builders generated by `#[derive(Op)]` only allow calling `build()` once all
+/// required attributes are set:
+/// ```compile_fail
+/// use tir_core::Context;
+/// use tir_core::builtin::{ConstOp, VoidType};
+///
+/// let context = Context::new();
+/// let ret_type = VoidType::build(context.clone());
+/// // error: `value` attribute is missing
+/// let constant = ConstOp::builder(&context)
+///     .return_type(ret_type.into())
+///     .build();
+/// ```
+pub struct AttrSet;
+/// Marks a required attribute that has not been provided to an operation builder yet
+pub struct AttrUnset;
 
 #[derive(Debug)]
 pub struct InsertionPoint {
@@ -12,6 +32,7 @@ pub struct InsertionPoint {
 pub struct OpBuilderImpl {
     context: ContextRef,
     insertion_point: InsertionPoint,
+    location: Location,
 }
 
 impl OpBuilderImpl {
@@ -20,10 +41,14 @@ impl OpBuilderImpl {
         Rc::new(RefCell::new(Self {
             context,
             insertion_point,
+            location: Location::Unknown,
         }))
     }
 
     fn insert(&mut self, op: &OpRef) {
+        if op.borrow().get_location().is_unknown() {
+            op.borrow_mut().set_location(self.location.clone());
+        }
         self.insertion_point
             .block
             .insert(self.insertion_point.index, op);
@@ -43,7 +68,12 @@ impl OpBuilderImpl {
         self.insertion_point.index = 0;
     }
 
-    fn set_insertion_point_after<T: Op + ?Sized>(&mut self, op: &Rc<RefCell<T>>) {
+    fn set_insertion_point_to_end(&mut self, block: BlockRef) {
+        self.insertion_point.index = block.iter().count();
+        self.insertion_point.block = block;
+    }
+
+    fn set_insertion_point_after<T: Op + ?Sized>(&mut self, op: &Arc<RwCell<T>>) {
         let parent = op.borrow().get_parent_region().unwrap();
         let (block, id) = parent
             .iter()
@@ -63,7 +93,7 @@ impl OpBuilder {
         Self(OpBuilderImpl::new(context, block))
     }
 
-    pub fn insert<T>(&self, op: &Rc<RefCell<T>>)
+    pub fn insert<T>(&self, op: &Arc<RwCell<T>>)
     where
         T: Op,
     {
@@ -87,7 +117,21 @@ impl OpBuilder {
         self.0.borrow_mut().set_insertion_point_to_start(block);
     }
 
-    pub fn set_insertion_point_after<T: Op + ?Sized>(&self, op: &Rc<RefCell<T>>) {
+    pub fn set_insertion_point_to_end(&self, block: BlockRef) {
+        self.0.borrow_mut().set_insertion_point_to_end(block);
+    }
+
+    pub fn set_insertion_point_after<T: Op + ?Sized>(&self, op: &Arc<RwCell<T>>) {
         self.0.borrow_mut().set_insertion_point_after(op);
     }
+
+    /// Set location for newly inserted operations, that do not have one yet.
+    /// Rewrites are expected to set it to the location of the operation being replaced.
+    pub fn set_location(&self, location: Location) {
+        self.0.borrow_mut().location = location;
+    }
+
+    pub fn get_location(&self) -> Location {
+        self.0.borrow().location.clone()
+    }
 }
@@ -1,9 +1,9 @@
 use crate::builtin::DIALECT_NAME;
 use crate::OpAssembly;
 use crate::Printable;
-use crate::{Op, OpImpl, Type};
+use crate::{MemoryEffects, Op, OpImpl, Type};
 use lpl::{ParseStream, Parser};
-use tir_macros::{Op, OpAssembly, OpValidator};
+use tir_macros::{op_implements, Op, OpAssembly, OpValidator};
 
 use crate as tir_core;
 
@@ -15,12 +15,16 @@ pub struct ConstOp {
     r#impl: OpImpl,
 }
 
+#[op_implements(dialect = builtin)]
+impl MemoryEffects for ConstOp {}
+
 #[cfg(test)]
 mod test {
     use crate::parse_ir;
     use crate::Attr;
     use crate::Context;
     use crate::OpBuilder;
+    use crate::OpValidator;
     use crate::Printable;
     use crate::StringPrinter;
     use crate::Value;
@@ -74,6 +78,45 @@ mod test {
         assert!(v2.is_ok());
     }
 
+    #[test]
+    fn typed_attrs() {
+        let context = Context::new();
+        let ret_type = VoidType::build(context.clone());
+        let constant = ConstOp::builder(&context)
+            .value(Attr::I8(16))
+            .return_type(ret_type.into())
+            .build();
+
+        assert_eq!(constant.borrow().value(), 16);
+        assert!(constant.borrow().validate_op().is_ok());
+
+        constant.borrow_mut().set_value(Attr::U32(42));
+        assert_eq!(constant.borrow().value(), 42);
+
+        constant
+            .borrow_mut()
+            .set_value_attr(Attr::String("x".to_string()));
+        let err = constant.borrow().validate_op().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Operation 'builtin.const' requires attribute 'value' of type integer"
+        );
+    }
+
+    #[test]
+    fn missing_required_attr() {
+        let context = Context::new();
+        let ret_type = VoidType::build(context.clone());
+        let constant = ConstOp::builder(&context)
+            .return_type(ret_type.into())
+            .build_unchecked();
+
+        assert!(matches!(
+            constant.borrow().validate_op(),
+            Err(crate::ValidateErr::InvalidAttr(_, "value", _, _))
+        ));
+    }
+
     #[test]
     fn parse_const() {
         let ir = "
@@ -11,7 +11,12 @@ use crate as tir_core;
 use super::FuncType;
 
 #[derive(Op, OpValidator)]
-#[operation(name = "func", dialect = builtin, known_attrs(sym_name: String, func_type: Type))]
+#[operation(
+    name = "func",
+    dialect = builtin,
+    known_attrs(sym_name: String, func_type: Type),
+    generator = generate_func
+)]
 pub struct FuncOp {
     #[region]
     body: RegionRef,
@@ -26,6 +31,26 @@ pub struct ReturnOp {
     r#impl: OpImpl,
 }
 
+/// Call a function by its symbol name
+#[derive(Op, OpValidator, OpAssembly)]
+#[operation(name = "call", dialect = builtin, known_attrs(callee: String))]
+pub struct CallOp {
+    // TODO add call operands
+    #[ret_type]
+    return_type: Type,
+    r#impl: OpImpl,
+}
+
+#[op_implements(dialect = builtin)]
+impl IsolatedFromAbove for FuncOp {}
+
+#[op_implements(dialect = builtin)]
+impl CallOpInterface for CallOp {
+    fn get_callee(&self) -> String {
+        self.callee().to_string()
+    }
+}
+
 #[op_implements(dialect = builtin)]
 impl Terminator for ReturnOp {}
 
@@ -57,6 +82,28 @@ fn signature<'a>() -> impl Parser<'a, IRStrStream<'a>, (Vec<&'a str>, FuncType)>
         .label("signature")
 }
 
+/// Functions must have a valid signature matching entry block arguments, which
+/// the derived generator can not guarantee.
+fn generate_func(gen: &mut OpGenerator) -> GenerateResult<OpRef> {
+    let context = gen.context();
+
+    let inputs: Vec<Type> = gen.generate()?;
+    let func_ty = FuncType::build(context.clone(), &inputs, gen.ty()?);
+
+    let names: Vec<_> = (0..inputs.len()).map(|idx| format!("arg{}", idx)).collect();
+    let body = Region::empty(&context);
+    let entry = Block::with_arguments("entry", &body, &inputs, &names);
+    body.add_block(entry.clone());
+    gen.fill_block(&entry)?;
+
+    let func = FuncOp::builder(&context)
+        .sym_name(format!("func{}", gen.generate::<u8>()?))
+        .func_type(func_ty.into())
+        .body(body)
+        .build();
+    Ok(func)
+}
+
 impl OpAssembly for FuncOp {
     fn parse_assembly(input: IRStrStream) -> ParseResult<IRStrStream, OpRef>
     where
@@ -79,20 +126,17 @@ impl OpAssembly for FuncOp {
         let context = state.context();
 
         let func = FuncOp::builder(&context)
-            .sym_name(Attr::String(func_name.into()))
-            .func_type(Attr::Type(func_ty.into()))
+            .sym_name(func_name.to_string())
+            .func_type(func_ty.into())
             .body(region)
             .build();
         Ok((func, ni))
     }
 
     fn print_assembly(&self, fmt: &mut dyn IRFormatter) {
-        fmt.write_direct(&format!(
-            "@{}",
-            TryInto::<String>::try_into(self.get_sym_name_attr().clone()).unwrap()
-        ));
+        fmt.write_direct(&format!("@{}", self.sym_name()));
 
-        let func_ty: FuncType = self.get_func_type_attr().clone().try_into().unwrap();
+        let func_ty: FuncType = self.func_type().clone().try_into().unwrap();
 
         fmt.write_direct("(");
 
@@ -140,7 +184,7 @@ mod test {
         let func_type = FuncType::build(context.clone(), &inputs, result.into());
 
         let func = func::FuncOp::builder(&context)
-            .sym_name("test".to_string().into())
+            .sym_name("test".to_string())
             .func_type(func_type.into())
             .body(Region::empty(&context))
             .build();
@@ -149,6 +193,7 @@ mod test {
             TryInto::<String>::try_into(func.borrow().get_sym_name_attr().clone()).unwrap(),
             "test"
         );
+        assert_eq!(func.borrow().sym_name(), "test");
         let body = module.borrow().get_body().clone();
         let op = body.first().unwrap().clone();
         assert_eq!((*op.borrow()).type_id(), TypeId::of::<FuncOp>());
@@ -4,11 +4,13 @@ use crate::Ty;
 mod arith;
 mod func;
 mod module;
+mod opaque;
 mod types;
 
 pub use arith::*;
 pub use func::*;
 pub use module::*;
+pub use opaque::*;
 use tir_macros::dialect;
 use tir_macros::populate_dialect_ops;
 use tir_macros::populate_dialect_types;
@@ -18,5 +20,5 @@ use crate::assembly::OpAssembly;
 use crate::assembly::TyAssembly;
 
 dialect!(builtin);
-populate_dialect_ops!(ModuleOp, ModuleEndOp, FuncOp, ReturnOp, ConstOp);
+populate_dialect_ops!(ModuleOp, ModuleEndOp, FuncOp, ReturnOp, CallOp, ConstOp);
 populate_dialect_types!(FuncType, VoidType, IntType);
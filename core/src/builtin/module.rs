@@ -1,15 +1,12 @@
 use crate::builtin::DIALECT_NAME;
-use crate::parser::single_block_region;
-use crate::{
-    IRFormatter, IRStrStream, Op, OpAssembly, OpImpl, OpRef, Printable, RegionRef, Terminator,
-};
-use lpl::{ParseResult, ParseStream, Parser};
+use crate::{IRFormatter, Op, OpAssembly, OpImpl, Printable, RegionRef, Terminator};
+use lpl::{ParseStream, Parser};
 use tir_macros::{op_implements, Op, OpAssembly, OpValidator};
 
 use crate as tir_core;
 
-#[derive(Op, Debug, OpValidator)]
-#[operation(name = "module", dialect = builtin)]
+#[derive(Op, Debug, OpValidator, OpAssembly)]
+#[operation(name = "module", dialect = builtin, assembly_format = "$body attr-dict")]
 pub struct ModuleOp {
     #[region(single_block, no_args)]
     body: RegionRef,
@@ -25,36 +22,6 @@ pub struct ModuleEndOp {
 #[op_implements(dialect = builtin)]
 impl Terminator for ModuleEndOp {}
 
-impl OpAssembly for ModuleOp {
-    fn parse_assembly<'a>(input: IRStrStream<'_>) -> ParseResult<IRStrStream<'_>, OpRef>
-    where
-        Self: Sized,
-    {
-        let parser = single_block_region();
-        let state = input.get_extra().unwrap().clone();
-        let context = state.context();
-        parser.parse(input).map(|(ops, next_input)| {
-            let module = ModuleOp::builder(&context).build();
-            for op in ops {
-                module.borrow_mut().get_body().push(&op);
-            }
-
-            let module: OpRef = module;
-
-            (module, next_input)
-        })
-    }
-
-    fn print_assembly(&self, fmt: &mut dyn IRFormatter) {
-        fmt.start_region();
-        let body = self.get_body();
-        for op in body.iter() {
-            op.borrow().print(fmt);
-        }
-        fmt.end_region();
-    }
-}
-
 #[cfg(test)]
 mod test {
     use std::any::TypeId;
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use lpl::ParseResult;
+
+use crate::parser::GenericOpTail;
+use crate::utils::CastableMeta;
+use crate::{
+    print_generic_op, AllocId, Attr, ContextRef, GenericSignature, IRFormatter, IRStrStream,
+    Location, Op, OpAssembly, OpImpl, OpRef, OpRegionIter, OpValidator, Printable, RegionRef,
+    RegionWRef, Type, Validate, ValidateErr, Value,
+};
+
+#[linkme::distributed_slice]
+pub static BUILTIN_OPAQUEOP_METADATA: [fn() -> CastableMeta];
+
+/// Operation from a dialect that is not registered with the context.
+///
+/// Opaque operations are only created by the parser when the context allows
+/// unregistered dialects. Operands are kept as raw strings, along with the types
+/// from the signature, and the operation is always printed in the generic form.
+#[derive(Debug)]
+pub struct OpaqueOp {
+    dialect_name: String,
+    op_name: String,
+    operands: Vec<String>,
+    operand_types: Vec<Type>,
+    regions: Vec<RegionRef>,
+    return_type: Option<Type>,
+    r#impl: OpImpl,
+}
+
+impl OpaqueOp {
+    pub fn new(
+        context: &ContextRef,
+        dialect_name: &str,
+        op_name: &str,
+        operands: Vec<String>,
+        tail: GenericOpTail,
+    ) -> std::sync::Arc<crate::RwCell<OpaqueOp>> {
+        let dialect = context.get_dialect_by_name(super::DIALECT_NAME).unwrap();
+
+        let r#impl = OpImpl {
+            context: std::sync::Arc::downgrade(context),
+            dialect_id: dialect.get_id(),
+            operation_id: u32::MAX,
+            alloc_id: AllocId::default(),
+            parent_region: None,
+            attrs: tail.attrs,
+            location: Location::Unknown,
+        };
+
+        let op = context.allocate_op(OpaqueOp {
+            dialect_name: dialect_name.to_owned(),
+            op_name: op_name.to_owned(),
+            operands,
+            operand_types: tail.operand_types,
+            regions: tail.regions,
+            return_type: tail.return_type,
+            r#impl,
+        });
+
+        let alloc_id = op.borrow().get_alloc_id();
+        for region in &op.borrow().regions {
+            region.set_parent_op(alloc_id);
+        }
+
+        op
+    }
+
+    /// Name of the dialect this operation claims to belong to
+    pub fn get_dialect_name(&self) -> &str {
+        &self.dialect_name
+    }
+
+    /// Name of the operation within its dialect
+    pub fn get_op_name(&self) -> &str {
+        &self.op_name
+    }
+
+    /// Operands in their textual form
+    pub fn get_operands(&self) -> &[String] {
+        &self.operands
+    }
+
+    /// Operand types from the signature
+    pub fn get_operand_types(&self) -> &[Type] {
+        &self.operand_types
+    }
+}
+
+impl Printable for OpaqueOp {
+    fn print(&self, fmt: &mut dyn IRFormatter) {
+        self.print_assembly(fmt);
+    }
+}
+
+impl OpAssembly for OpaqueOp {
+    fn print_assembly(&self, fmt: &mut dyn IRFormatter) {
+        print_generic_op(
+            fmt,
+            &format!("{}.{}", self.dialect_name, self.op_name),
+            &self.operands,
+            &self.r#impl.attrs,
+            &self.regions,
+            GenericSignature {
+                operand_types: &self.operand_types,
+                return_type: self.return_type.clone(),
+            },
+            &self.r#impl.location,
+        );
+    }
+
+    fn parse_assembly(_input: IRStrStream<'_>) -> ParseResult<IRStrStream<'_>, OpRef>
+    where
+        Self: Sized,
+    {
+        unreachable!("opaque operations are only created from the generic form")
+    }
+}
+
+impl OpValidator for OpaqueOp {
+    fn validate_op(&self) -> Result<(), ValidateErr> {
+        Ok(())
+    }
+}
+
+impl Validate for OpaqueOp {
+    fn validate(&self) -> Result<(), ValidateErr> {
+        for region in &self.regions {
+            region.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Op for OpaqueOp {
+    fn get_operation_name(&self) -> &'static str {
+        "unregistered"
+    }
+
+    fn get_attrs(&self) -> &HashMap<String, Attr> {
+        &self.r#impl.attrs
+    }
+
+    fn add_attrs(&mut self, attrs: &HashMap<String, Attr>) {
+        for (k, v) in attrs {
+            self.r#impl.attrs.insert(k.clone(), v.clone());
+        }
+    }
+
+    fn get_context(&self) -> ContextRef {
+        self.r#impl.context.upgrade().unwrap()
+    }
+
+    fn get_parent_region(&self) -> Option<RegionRef> {
+        self.r#impl.parent_region.clone().map(|r| r.upgrade())?
+    }
+
+    fn set_parent_region(&mut self, region: RegionWRef) {
+        self.r#impl.parent_region = Some(region)
+    }
+
+    fn get_return_type(&self) -> Option<Type> {
+        self.return_type.clone()
+    }
+
+    fn get_return_value(&self) -> Option<Value> {
+        self.return_type
+            .as_ref()
+            .map(|_| Value::from_op(self.get_context(), "todo", self.r#impl.alloc_id))
+    }
+
+    fn set_alloc_id(&mut self, id: AllocId) {
+        assert_eq!(self.r#impl.alloc_id, AllocId::default());
+        assert_ne!(id, AllocId::default());
+        self.r#impl.alloc_id = id;
+    }
+
+    fn get_alloc_id(&self) -> AllocId {
+        assert_ne!(self.r#impl.alloc_id, AllocId::default());
+        self.r#impl.alloc_id
+    }
+
+    fn get_dialect_id(&self) -> u32 {
+        self.r#impl.dialect_id
+    }
+
+    fn get_location(&self) -> Location {
+        self.r#impl.location.clone()
+    }
+
+    fn set_location(&mut self, location: Location) {
+        self.r#impl.location = location;
+    }
+
+    fn get_regions(&self) -> OpRegionIter {
+        OpRegionIter::new(&self.regions)
+    }
+
+    fn has_regions(&self) -> bool {
+        !self.regions.is_empty()
+    }
+
+    fn has_trait(&self, type_id: std::any::TypeId) -> bool {
+        BUILTIN_OPAQUEOP_METADATA
+            .iter()
+            .any(|func| func().type_id == type_id)
+    }
+
+    fn get_meta(&self) -> &'static linkme::DistributedSlice<[fn() -> CastableMeta]> {
+        &BUILTIN_OPAQUEOP_METADATA
+    }
+}
@@ -21,7 +21,9 @@ impl TyAssembly for VoidType {
         fmt.write_direct("void");
     }
 
-    fn parse_assembly(input: IRStrStream) -> ParseResult<IRStrStream<'_>, HashMap<String, Attr>> {
+    fn parse_assembly(
+        input: IRStrStream<'_>,
+    ) -> ParseResult<IRStrStream<'_>, HashMap<String, Attr>> {
         let parser = skip_attrs();
         parser.parse(input)
     }
@@ -139,7 +141,9 @@ impl TyAssembly for IntType {
         fmt.write_direct(">");
     }
 
-    fn parse_assembly(input: IRStrStream) -> ParseResult<IRStrStream<'_>, HashMap<String, Attr>> {
+    fn parse_assembly(
+        input: IRStrStream<'_>,
+    ) -> ParseResult<IRStrStream<'_>, HashMap<String, Attr>> {
         tir_core::parser::parse_int_bits().parse(input)
     }
 }
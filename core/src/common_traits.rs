@@ -1,3 +1,17 @@
 use std::any::Any;
 
+use crate::{ContextRef, Type};
+
 pub trait Terminator: Any {}
+
+/// Operations that can not reference values defined outside of their regions.
+///
+/// Such operations form a boundary for transformations: everything nested inside
+/// can be processed independently of the rest of the IR. The pass manager relies
+/// on this to run function-level passes concurrently.
+pub trait IsolatedFromAbove: Any {}
+
+/// Operand field, that has an IR type to list in the generic op signature.
+pub trait OperandType {
+    fn operand_type(&self, context: &ContextRef) -> Type;
+}
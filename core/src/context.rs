@@ -1,12 +1,9 @@
-#![allow(clippy::arc_with_non_send_sync)]
-
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::rc::Rc;
-use std::sync::{Arc, RwLock, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 
-use crate::{builtin, Dialect, Op, OpRef};
+use crate::{builtin, DiagnosticEngine, Dialect, Op, OpRef, RwCell};
 
 pub type ContextRef = Arc<Context>;
 pub type ContextWRef = Weak<Context>;
@@ -25,7 +22,8 @@ impl Default for AllocId {
 
 struct ContextImpl {
     dialects: Vec<Arc<Dialect>>,
-    allocated_operations: HashMap<AllocId, OpRef>,
+    allow_unregistered_dialects: bool,
+    num_threads: usize,
 }
 
 impl ContextImpl {
@@ -33,7 +31,8 @@ impl ContextImpl {
         let builtin_dialect = builtin::create_dialect();
         let mut r#impl = ContextImpl {
             dialects: vec![],
-            allocated_operations: HashMap::new(),
+            allow_unregistered_dialects: false,
+            num_threads: 1,
         };
         r#impl.add_dialect(builtin_dialect);
         RwLock::new(r#impl)
@@ -58,31 +57,12 @@ impl ContextImpl {
     }
 
     fn get_dialect(&self, id: u32) -> Option<Arc<Dialect>> {
-        if id as usize > self.dialects.len() {
+        if id as usize >= self.dialects.len() {
             None
         } else {
             Some(self.dialects[id as usize].clone())
         }
     }
-
-    fn allocate_op<T: Op + 'static>(&mut self, op: T) -> Rc<RefCell<T>> {
-        let id = AllocId {
-            id: self.allocated_operations.len(),
-        };
-
-        let mut op = op;
-        op.set_alloc_id(id);
-
-        let op = Rc::new(RefCell::new(op));
-
-        self.allocated_operations.insert(id, op.clone());
-
-        op
-    }
-
-    pub fn get_op(&self, id: AllocId) -> Option<OpRef> {
-        self.allocated_operations.get(&id).cloned()
-    }
 }
 
 /// Context holds all the resources required for building an IR
@@ -100,8 +80,15 @@ impl ContextImpl {
 /// ```
 pub struct Context {
     r#impl: RwLock<ContextImpl>,
+    next_op_id: AtomicUsize,
+    /// Allocated operations, sharded by their IDs, so that threads, that build the IR
+    /// concurrently, rarely contend for the same lock
+    allocated_operations: [Mutex<HashMap<AllocId, OpRef>>; OP_SHARDS],
+    diagnostic_engine: DiagnosticEngine,
 }
 
+const OP_SHARDS: usize = 16;
+
 impl Debug for Context {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("Context")?;
@@ -117,6 +104,9 @@ impl Context {
     pub fn new() -> Arc<Context> {
         Arc::new(Context {
             r#impl: ContextImpl::new(),
+            next_op_id: AtomicUsize::new(0),
+            allocated_operations: Default::default(),
+            diagnostic_engine: DiagnosticEngine::default(),
         })
     }
 
@@ -138,16 +128,65 @@ impl Context {
         lock.get_dialect(id)
     }
 
+    /// Get all registered dialects in the order of registration
+    pub fn get_dialects(&self) -> Vec<Arc<Dialect>> {
+        let lock = self.r#impl.read().unwrap();
+        lock.dialects.clone()
+    }
+
     /// Take ownership of operation data and return a shared reference
-    pub fn allocate_op<T: Op + 'static>(&self, op: T) -> Rc<RefCell<T>> {
-        let mut lock = self.r#impl.write().unwrap();
-        lock.allocate_op(op)
+    pub fn allocate_op<T: Op + 'static>(&self, op: T) -> Arc<RwCell<T>> {
+        let id = AllocId {
+            id: self.next_op_id.fetch_add(1, Ordering::Relaxed),
+        };
+
+        let mut op = op;
+        op.set_alloc_id(id);
+
+        let op = Arc::new(RwCell::new(op));
+        self.op_shard(id).insert(id, op.clone());
+
+        op
     }
 
     /// Find allocated operation in the current context and return a shared reference
     pub fn get_op(&self, id: AllocId) -> Option<OpRef> {
+        self.op_shard(id).get(&id).cloned()
+    }
+
+    fn op_shard(&self, id: AllocId) -> std::sync::MutexGuard<'_, HashMap<AllocId, OpRef>> {
+        self.allocated_operations[id.id % OP_SHARDS].lock().unwrap()
+    }
+
+    /// Allow parsing operations from dialects that are not registered with this context.
+    /// Such operations are loaded as [`builtin::OpaqueOp`].
+    pub fn allow_unregistered_dialects(&self, allow: bool) {
+        let mut lock = self.r#impl.write().unwrap();
+        lock.allow_unregistered_dialects = allow;
+    }
+
+    /// Check if operations from unregistered dialects can be loaded
+    pub fn allows_unregistered_dialects(&self) -> bool {
+        let lock = self.r#impl.read().unwrap();
+        lock.allow_unregistered_dialects
+    }
+
+    /// Set the maximum number of threads passes are allowed to use.
+    /// Values less than 2 disable multi-threading.
+    pub fn set_num_threads(&self, num_threads: usize) {
+        let mut lock = self.r#impl.write().unwrap();
+        lock.num_threads = num_threads.max(1);
+    }
+
+    /// Maximum number of threads passes are allowed to use
+    pub fn get_num_threads(&self) -> usize {
         let lock = self.r#impl.read().unwrap();
-        lock.get_op(id)
+        lock.num_threads
+    }
+
+    /// Get diagnostic engine used to report problems in the IR
+    pub fn get_diagnostic_engine(&self) -> &DiagnosticEngine {
+        &self.diagnostic_engine
     }
 }
 
@@ -0,0 +1,547 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use ariadne::{Color, Config, Label, Report, ReportKind, Source};
+
+use crate::{Location, Op, ValidateErr};
+
+/// Severity of a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Remark,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Remark => "remark",
+            Severity::Note => "note",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A diagnostic message anchored to a location in the IR
+#[derive(Debug, Clone, PartialEq)]
+pub struct IRDiagnostic {
+    severity: Severity,
+    location: Location,
+    message: String,
+    notes: Vec<IRDiagnostic>,
+}
+
+impl IRDiagnostic {
+    pub fn new(severity: Severity, location: Location, message: impl ToString) -> Self {
+        Self {
+            severity,
+            location,
+            message: message.to_string(),
+            notes: vec![],
+        }
+    }
+
+    /// Create an error anchored to the operation
+    pub fn error(op: &dyn Op, message: impl ToString) -> Self {
+        Self::new(Severity::Error, op.get_location(), message)
+    }
+
+    /// Create a warning anchored to the operation
+    pub fn warning(op: &dyn Op, message: impl ToString) -> Self {
+        Self::new(Severity::Warning, op.get_location(), message)
+    }
+
+    /// Create a remark anchored to the operation
+    pub fn remark(op: &dyn Op, message: impl ToString) -> Self {
+        Self::new(Severity::Remark, op.get_location(), message)
+    }
+
+    /// Attach a note to the diagnostic
+    pub fn with_note(mut self, location: Location, message: impl ToString) -> Self {
+        self.notes
+            .push(IRDiagnostic::new(Severity::Note, location, message));
+        self
+    }
+
+    pub fn get_severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn get_location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_notes(&self) -> &[IRDiagnostic] {
+        &self.notes
+    }
+}
+
+impl From<ValidateErr> for IRDiagnostic {
+    fn from(value: ValidateErr) -> Self {
+        IRDiagnostic::new(Severity::Error, value.location(), value)
+    }
+}
+
+/// Identifier of a registered diagnostic handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandlerId(usize);
+
+pub type DiagnosticHandler = Box<dyn Fn(&IRDiagnostic) + Send>;
+
+#[derive(Default)]
+struct DiagnosticEngineImpl {
+    handlers: Vec<(HandlerId, DiagnosticHandler)>,
+    next_handler_id: usize,
+    sources: HashMap<String, String>,
+    num_errors: usize,
+}
+
+/// Central place to report problems found in the IR.
+///
+/// Every context owns a diagnostic engine. By default diagnostics are rendered to
+/// stderr. Once a handler is registered, the most recently registered handler
+/// receives all diagnostics instead. Handlers must not emit diagnostics themselves.
+///
+/// **Example:**
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use tir_core::{Context, IRDiagnostic, Location, Severity};
+///
+/// let context = Context::new();
+/// let engine = context.get_diagnostic_engine();
+///
+/// let seen = Arc::new(Mutex::new(vec![]));
+/// let seen_clone = seen.clone();
+/// let id = engine.register_handler(Box::new(move |diag| {
+///     seen_clone.lock().unwrap().push(diag.get_message().to_string());
+/// }));
+///
+/// engine.emit(IRDiagnostic::new(Severity::Error, Location::Unknown, "oops"));
+/// engine.unregister_handler(id);
+///
+/// assert_eq!(seen.lock().unwrap().as_slice(), ["oops"]);
+/// assert_eq!(engine.get_num_errors(), 1);
+/// ```
+#[derive(Default)]
+pub struct DiagnosticEngine {
+    r#impl: Mutex<DiagnosticEngineImpl>,
+}
+
+impl fmt::Debug for DiagnosticEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DiagnosticEngine")
+    }
+}
+
+impl DiagnosticEngine {
+    /// Report a diagnostic
+    pub fn emit(&self, diag: IRDiagnostic) {
+        let mut lock = self.r#impl.lock().unwrap();
+        if diag.severity == Severity::Error {
+            lock.num_errors += 1;
+        }
+
+        Self::dispatch(&lock, &diag);
+    }
+
+    /// Pass an already counted diagnostic to the active handler
+    pub(crate) fn replay(&self, diag: &IRDiagnostic) {
+        let lock = self.r#impl.lock().unwrap();
+        Self::dispatch(&lock, diag);
+    }
+
+    fn dispatch(r#impl: &DiagnosticEngineImpl, diag: &IRDiagnostic) {
+        match r#impl.handlers.last() {
+            Some((_, handler)) => handler(diag),
+            None => render(&r#impl.sources, diag),
+        }
+    }
+
+    /// Register a handler that receives all diagnostics instead of the default renderer
+    pub fn register_handler(&self, handler: DiagnosticHandler) -> HandlerId {
+        let mut lock = self.r#impl.lock().unwrap();
+        let id = HandlerId(lock.next_handler_id);
+        lock.next_handler_id += 1;
+        lock.handlers.push((id, handler));
+        id
+    }
+
+    pub fn unregister_handler(&self, id: HandlerId) {
+        let mut lock = self.r#impl.lock().unwrap();
+        lock.handlers.retain(|(hid, _)| *hid != id);
+    }
+
+    /// Make source text available for rendering diagnostics with code snippets
+    pub fn add_source(&self, filename: &str, source: &str) {
+        let mut lock = self.r#impl.lock().unwrap();
+        lock.sources
+            .insert(filename.to_string(), source.to_string());
+    }
+
+    /// Render diagnostic to stderr, regardless of registered handlers
+    pub fn render(&self, diag: &IRDiagnostic) {
+        let lock = self.r#impl.lock().unwrap();
+        render(&lock.sources, diag);
+    }
+
+    /// Number of errors reported so far
+    pub fn get_num_errors(&self) -> usize {
+        self.r#impl.lock().unwrap().num_errors
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.get_num_errors() != 0
+    }
+}
+
+fn report_kind(severity: Severity) -> ReportKind<'static> {
+    match severity {
+        Severity::Error => ReportKind::Error,
+        Severity::Warning => ReportKind::Warning,
+        Severity::Remark => ReportKind::Custom("Remark", Color::Blue),
+        Severity::Note => ReportKind::Advice,
+    }
+}
+
+/// Resolve file location to a byte offset in a known source file
+fn source_offset<'a>(
+    sources: &'a HashMap<String, String>,
+    location: &Location,
+) -> Option<(&'a str, &'a str, usize)> {
+    let Location::FileLineCol {
+        filename,
+        line,
+        column,
+    } = location
+    else {
+        return None;
+    };
+
+    let (filename, source) = sources.get_key_value(filename)?;
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take((*line as usize).checked_sub(1)?)
+        .map(|l| l.len())
+        .sum();
+    let offset = source[line_start..]
+        .char_indices()
+        .nth((*column as usize).saturating_sub(1))
+        .map(|(o, _)| line_start + o)
+        .unwrap_or(source.len());
+
+    Some((filename, source, offset))
+}
+
+fn render(sources: &HashMap<String, String>, diag: &IRDiagnostic) {
+    let Some((filename, source, offset)) = source_offset(sources, &diag.location) else {
+        let mut printer = crate::StringPrinter::new();
+        crate::Printable::print(&diag.location, &mut printer);
+        eprintln!("{}: {}: {}", printer.get(), diag.severity, diag.message);
+        for note in &diag.notes {
+            render(sources, note);
+        }
+        return;
+    };
+
+    let mut builder = Report::build(report_kind(diag.severity), (filename, offset..offset))
+        .with_config(
+            Config::default()
+                .with_tab_width(2)
+                .with_index_type(ariadne::IndexType::Byte),
+        )
+        .with_message(&diag.message)
+        .with_label(Label::new((filename, offset..(offset + 1))).with_message(&diag.message));
+
+    for note in &diag.notes {
+        match source_offset(sources, &note.location) {
+            Some((note_file, _, note_offset)) if note_file == filename => {
+                builder = builder.with_label(
+                    Label::new((filename, note_offset..(note_offset + 1)))
+                        .with_message(&note.message)
+                        .with_color(Color::Blue),
+                );
+            }
+            _ => builder = builder.with_note(&note.message),
+        }
+    }
+
+    builder
+        .finish()
+        .eprint((filename, Source::from(source)))
+        .unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ExpectedDiagnostic {
+    severity: Severity,
+    line: u32,
+    message: String,
+}
+
+/// Checks emitted diagnostics against `expected-*` annotations in the source.
+///
+/// Annotations are placed in comments:
+/// ```tir
+/// ; expected-error @+1 {{The last operation in basic block must be a terminator}}
+/// ```
+/// The optional `@+N`/`@-N` suffix makes the expectation refer to a line relative
+/// to the annotation. Expected message must be a substring of the actual one.
+pub struct DiagnosticVerifier {
+    filename: String,
+    expected: Vec<ExpectedDiagnostic>,
+    /// Annotations, that could not be turned into an expectation
+    invalid: Vec<IRDiagnostic>,
+    emitted: std::sync::Arc<Mutex<Vec<IRDiagnostic>>>,
+    handler: HandlerId,
+    context: crate::ContextRef,
+}
+
+impl DiagnosticVerifier {
+    /// Parse annotations from the source and start capturing diagnostics
+    pub fn new(context: &crate::ContextRef, filename: &str, source: &str) -> Self {
+        let (expected, invalid) = parse_expectations(filename, source);
+        let emitted = std::sync::Arc::new(Mutex::new(vec![]));
+
+        let captured = emitted.clone();
+        let handler = context
+            .get_diagnostic_engine()
+            .register_handler(Box::new(move |diag| {
+                let mut captured = captured.lock().unwrap();
+                captured.push(diag.clone());
+                captured.extend(diag.notes.iter().cloned());
+            }));
+
+        Self {
+            filename: filename.to_string(),
+            expected,
+            invalid,
+            emitted,
+            handler,
+            context: context.clone(),
+        }
+    }
+
+    /// Stop capturing diagnostics and check them against expectations.
+    /// Returns `true` if every diagnostic was expected and every expectation was met.
+    pub fn verify(self) -> bool {
+        let engine = self.context.get_diagnostic_engine();
+        engine.unregister_handler(self.handler);
+
+        let mut expected = self.expected;
+        let emitted = std::mem::take(&mut *self.emitted.lock().unwrap());
+        let mut success = self.invalid.is_empty();
+
+        for diag in &self.invalid {
+            engine.render(diag);
+        }
+
+        for diag in emitted {
+            let line = match &diag.location {
+                Location::FileLineCol { filename, line, .. } if *filename == self.filename => {
+                    Some(*line)
+                }
+                _ => None,
+            };
+
+            let found = expected.iter().position(|e| {
+                Some(e.line) == line
+                    && e.severity == diag.severity
+                    && diag.message.contains(&e.message)
+            });
+
+            match found {
+                Some(idx) => {
+                    expected.remove(idx);
+                }
+                None => {
+                    success = false;
+                    let unexpected = IRDiagnostic::new(
+                        Severity::Error,
+                        diag.location.clone(),
+                        format!("unexpected {}: {}", diag.severity, diag.message),
+                    );
+                    engine.render(&unexpected);
+                }
+            }
+        }
+
+        for e in expected {
+            success = false;
+            let missing = IRDiagnostic::new(
+                Severity::Error,
+                Location::file_line_col(&self.filename, e.line, 1),
+                format!("expected {} \"{}\" was not produced", e.severity, e.message),
+            );
+            engine.render(&missing);
+        }
+
+        success
+    }
+}
+
+/// Returns expectations and diagnostics for annotations, that refer to a line
+/// before the start of the file.
+fn parse_expectations(
+    filename: &str,
+    source: &str,
+) -> (Vec<ExpectedDiagnostic>, Vec<IRDiagnostic>) {
+    let mut result = vec![];
+    let mut invalid = vec![];
+
+    for (idx, line) in source.lines().enumerate() {
+        let line_no = idx as i64 + 1;
+        let Some(pos) = line.find("expected-") else {
+            continue;
+        };
+        let rest = &line[pos + "expected-".len()..];
+
+        let severity = [
+            ("error", Severity::Error),
+            ("warning", Severity::Warning),
+            ("remark", Severity::Remark),
+            ("note", Severity::Note),
+        ]
+        .into_iter()
+        .find(|(name, _)| rest.starts_with(name));
+        let Some((name, severity)) = severity else {
+            continue;
+        };
+        let rest = rest[name.len()..].trim_start();
+
+        let (offset, rest) = match rest.strip_prefix('@') {
+            Some(rest) => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let Ok(offset) = rest[..end].parse::<i64>() else {
+                    continue;
+                };
+                (offset, rest[end..].trim_start())
+            }
+            None => (0, rest),
+        };
+
+        let Some(message) = rest
+            .strip_prefix("{{")
+            .and_then(|rest| rest.find("}}").map(|end| &rest[..end]))
+        else {
+            continue;
+        };
+
+        let Some(line) = u32::try_from(line_no + offset)
+            .ok()
+            .filter(|&line| line > 0)
+        else {
+            invalid.push(IRDiagnostic::new(
+                Severity::Error,
+                Location::file_line_col(filename, line_no as u32, (pos + 1) as u32),
+                format!(
+                    "expected-{} annotation refers to line {}, which is before the start of the file",
+                    name,
+                    line_no + offset
+                ),
+            ));
+            continue;
+        };
+
+        result.push(ExpectedDiagnostic {
+            severity,
+            line,
+            message: message.to_string(),
+        });
+    }
+
+    (result, invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::ModuleOp;
+    use crate::Context;
+
+    #[test]
+    fn test_parse_expectations() {
+        let source = "; expected-error {{foo}}
+op
+; expected-warning @+1 {{bar baz}}
+op
+; expected-remark @-3 {{qux}}
+; expected-unknown {{ignored}}";
+        let (expected, invalid) = parse_expectations("test.tir", source);
+        assert!(invalid.is_empty());
+        assert_eq!(
+            expected,
+            vec![
+                ExpectedDiagnostic {
+                    severity: Severity::Error,
+                    line: 1,
+                    message: "foo".to_string()
+                },
+                ExpectedDiagnostic {
+                    severity: Severity::Warning,
+                    line: 4,
+                    message: "bar baz".to_string()
+                },
+                ExpectedDiagnostic {
+                    severity: Severity::Remark,
+                    line: 2,
+                    message: "qux".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_expectations_before_start() {
+        let source = "op
+; expected-error @-2 {{foo}}
+; expected-error @-2 {{bar}}";
+        let (expected, invalid) = parse_expectations("test.tir", source);
+        assert_eq!(
+            expected,
+            vec![ExpectedDiagnostic {
+                severity: Severity::Error,
+                line: 1,
+                message: "bar".to_string()
+            }]
+        );
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(
+            invalid[0].message,
+            "expected-error annotation refers to line 0, which is before the start of the file"
+        );
+        assert!(matches!(
+            invalid[0].location,
+            Location::FileLineCol { line: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_verifier() {
+        let context = Context::new();
+        let module = ModuleOp::builder(&context)
+            .location(Location::file_line_col("test.tir", 2, 1))
+            .build();
+        let source = "; expected-error @+1 {{bad module}}\nmodule";
+
+        let verifier = DiagnosticVerifier::new(&context, "test.tir", source);
+        context
+            .get_diagnostic_engine()
+            .emit(IRDiagnostic::error(&*module.borrow(), "very bad module"));
+        assert!(verifier.verify());
+
+        let verifier = DiagnosticVerifier::new(&context, "test.tir", source);
+        context
+            .get_diagnostic_engine()
+            .emit(IRDiagnostic::warning(&*module.borrow(), "bad module"));
+        assert!(!verifier.verify());
+    }
+}
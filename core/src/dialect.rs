@@ -1,10 +1,11 @@
 use lpl::ParseResult;
 
-use crate::{Attr, IRFormatter, IRStrStream, OpRef};
+use crate::{Attr, IRFormatter, IRStrStream, OpGenerateFn, OpRef};
 use std::any::Any;
 use std::collections::HashMap;
 
-type ParseFn<T> = dyn for<'a> Fn(IRStrStream<'a>) -> ParseResult<IRStrStream<'a>, T> + 'static;
+type ParseFn<T> =
+    dyn for<'a> Fn(IRStrStream<'a>) -> ParseResult<IRStrStream<'a>, T> + Send + Sync + 'static;
 pub type OpParseFn = ParseFn<OpRef>;
 pub type TyParseFn = ParseFn<HashMap<String, Attr>>;
 pub type TyPrintFn = fn(&HashMap<String, Attr>, &mut dyn IRFormatter);
@@ -15,9 +16,11 @@ pub struct Dialect {
     operation_ids: HashMap<&'static str, u32>,
     type_ids: HashMap<&'static str, u32>,
     op_parse_fn: HashMap<u32, Box<OpParseFn>>,
+    op_generic_parse_fn: HashMap<u32, Box<OpParseFn>>,
+    op_generate_fn: HashMap<u32, OpGenerateFn>,
     ty_parse_fn: HashMap<u32, Box<TyParseFn>>,
     ty_print_fn: HashMap<u32, TyPrintFn>,
-    ext: Option<Box<dyn Any>>,
+    ext: Option<Box<dyn Any + Send + Sync>>,
 }
 
 impl Dialect {
@@ -28,6 +31,8 @@ impl Dialect {
             operation_ids: HashMap::new(),
             type_ids: HashMap::new(),
             op_parse_fn: HashMap::new(),
+            op_generic_parse_fn: HashMap::new(),
+            op_generate_fn: HashMap::new(),
             ty_parse_fn: HashMap::new(),
             ty_print_fn: HashMap::new(),
             ext: None,
@@ -49,14 +54,22 @@ impl Dialect {
         self.name
     }
 
-    pub fn add_operation(&mut self, name: &'static str, parser: Box<OpParseFn>) {
+    pub fn add_operation(
+        &mut self,
+        name: &'static str,
+        parser: Box<OpParseFn>,
+        generic_parser: Box<OpParseFn>,
+        generator: OpGenerateFn,
+    ) {
         if self
             .operation_ids
             .insert(name, self.operation_ids.len() as u32)
             .is_none()
         {
-            self.op_parse_fn
-                .insert((self.operation_ids.len() - 1) as u32, parser);
+            let id = (self.operation_ids.len() - 1) as u32;
+            self.op_parse_fn.insert(id, parser);
+            self.op_generic_parse_fn.insert(id, generic_parser);
+            self.op_generate_fn.insert(id, generator);
         }
     }
 
@@ -68,6 +81,22 @@ impl Dialect {
         self.op_parse_fn.get(&id).map(|f| f.as_ref())
     }
 
+    /// Get parser for the generic form of the operation
+    pub fn get_operation_generic_parser(&self, id: u32) -> Option<&OpParseFn> {
+        self.op_generic_parse_fn.get(&id).map(|f| f.as_ref())
+    }
+
+    /// Iterate over random generators of all operations in the dialect, sorted by name
+    pub fn get_operation_generators(&self) -> impl Iterator<Item = (&'static str, OpGenerateFn)> {
+        let mut names: Vec<_> = self.operation_ids.iter().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|(name, id)| (*name, self.op_generate_fn[id]))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
     pub fn add_type(&mut self, name: &'static str, print_fn: TyPrintFn, parse_fn: Box<TyParseFn>) {
         let id: u32 = self.type_ids.len() as u32;
         self.type_ids.insert(name, id);
@@ -103,14 +132,14 @@ impl Dialect {
     }
 
     pub fn get_dialect_extension(&self) -> Option<&dyn Any> {
-        self.ext.as_ref().map(|e| e.as_ref())
+        self.ext.as_ref().map(|e| e.as_ref() as &dyn Any)
     }
 
     pub fn get_dialect_extension_mut(&mut self) -> Option<&mut dyn Any> {
-        self.ext.as_mut().map(|e| e.as_mut())
+        self.ext.as_mut().map(|e| e.as_mut() as &mut dyn Any)
     }
 
-    pub fn set_dialect_extension(&mut self, ext: Box<dyn Any>) {
+    pub fn set_dialect_extension(&mut self, ext: Box<dyn Any + Send + Sync>) {
         assert!(self.ext.is_none());
         self.ext = Some(ext);
     }
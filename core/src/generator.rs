@@ -0,0 +1,201 @@
+//! Random IR generation for round-trip testing.
+//!
+//! Every operation derived with `#[derive(Op)]` gets a `generate_op` function,
+//! that builds an instance of the operation with random operands, attributes,
+//! regions and result type. Dialects register those functions next to the
+//! parsers, so that test harnesses can walk all operations known to a
+//! [`Context`](crate::Context) without knowing anything about them.
+//!
+//! Randomness is drawn from [`arbitrary::Unstructured`], which makes generators
+//! directly usable from `cargo fuzz` targets.
+
+use arbitrary::Unstructured;
+
+use crate::builtin::{IntType, VoidType};
+use crate::utils::op_has_trait;
+use crate::{Attr, BlockRef, ContextRef, Dialect, OpRef, Region, RegionRef, Terminator, Type};
+
+pub type GenerateResult<T> = arbitrary::Result<T>;
+pub type OpGenerateFn = fn(&mut OpGenerator) -> GenerateResult<OpRef>;
+
+/// Maximum number of nested regions in a generated operation
+const MAX_DEPTH: usize = 3;
+/// Maximum number of non-terminator operations in a generated block
+const MAX_BLOCK_LEN: usize = 3;
+
+/// Source of random operations and their components
+pub struct OpGenerator<'a, 'u> {
+    context: ContextRef,
+    u: &'a mut Unstructured<'u>,
+    /// Operations, that may appear in the middle of a block
+    ops: Vec<OpGenerateFn>,
+    terminators: Vec<OpGenerateFn>,
+    depth: usize,
+}
+
+impl<'a, 'u> OpGenerator<'a, 'u> {
+    /// Create a generator, that fills regions with operations from the given dialects
+    pub fn new(context: ContextRef, dialects: &[&Dialect], u: &'a mut Unstructured<'u>) -> Self {
+        let ops: Vec<OpGenerateFn> = dialects
+            .iter()
+            .flat_map(|dialect| dialect.get_operation_generators())
+            .map(|(_, generate)| generate)
+            .collect();
+
+        // Terminators can only be told apart from other operations by an instance.
+        // Probe instances are built from an empty data source without nested operations.
+        let mut probe_data = Unstructured::new(&[]);
+        let mut probe = OpGenerator {
+            context: context.clone(),
+            u: &mut probe_data,
+            ops: vec![],
+            terminators: vec![],
+            depth: MAX_DEPTH,
+        };
+        let (terminators, ops) = ops
+            .into_iter()
+            .partition(|generate| generate(&mut probe).is_ok_and(op_has_trait::<dyn Terminator>));
+
+        OpGenerator {
+            context,
+            u,
+            ops,
+            terminators,
+            depth: 0,
+        }
+    }
+
+    pub fn context(&self) -> ContextRef {
+        self.context.clone()
+    }
+
+    /// Underlying source of randomness
+    pub fn unstructured(&mut self) -> &mut Unstructured<'u> {
+        self.u
+    }
+
+    /// Generate a random value of type `T`
+    pub fn generate<T: Generate>(&mut self) -> GenerateResult<T> {
+        T::generate(self)
+    }
+
+    /// Generate one of the non-terminator operations known to the generator
+    pub fn op(&mut self) -> GenerateResult<OpRef> {
+        let generate = *self.u.choose(&self.ops)?;
+        generate(self)
+    }
+
+    /// Generate a random builtin type
+    pub fn ty(&mut self) -> GenerateResult<Type> {
+        if self.u.arbitrary()? {
+            Ok(VoidType::build(self.context.clone()).into())
+        } else {
+            let bits = self.u.int_in_range(1..=128)?;
+            Ok(IntType::build(self.context.clone(), bits).into())
+        }
+    }
+
+    /// Generate an integer attribute of random width
+    pub fn integer_attr(&mut self) -> GenerateResult<Attr> {
+        let attr = match self.u.int_in_range(0..=7)? {
+            0 => Attr::I8(self.u.arbitrary()?),
+            1 => Attr::U8(self.u.arbitrary()?),
+            2 => Attr::I16(self.u.arbitrary()?),
+            3 => Attr::U16(self.u.arbitrary()?),
+            4 => Attr::I32(self.u.arbitrary()?),
+            5 => Attr::U32(self.u.arbitrary()?),
+            6 => Attr::I64(self.u.arbitrary()?),
+            _ => Attr::U64(self.u.arbitrary()?),
+        };
+        Ok(attr)
+    }
+
+    /// Generate a region with a single `entry` block
+    pub fn region(&mut self) -> GenerateResult<RegionRef> {
+        let region = Region::with_single_block(&self.context);
+        self.fill_block(&region.first().unwrap())?;
+        Ok(region)
+    }
+
+    /// Append random operations to the block, finishing it with a terminator
+    pub fn fill_block(&mut self, block: &BlockRef) -> GenerateResult<()> {
+        if self.depth >= MAX_DEPTH {
+            return self.terminate_block(block);
+        }
+
+        self.depth += 1;
+        let len = self.u.int_in_range(0..=MAX_BLOCK_LEN)?;
+        for _ in 0..len {
+            if self.ops.is_empty() {
+                break;
+            }
+            let op = self.op()?;
+            block.push(&op);
+        }
+        let result = self.terminate_block(block);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn terminate_block(&mut self, block: &BlockRef) -> GenerateResult<()> {
+        if self.terminators.is_empty() {
+            return Ok(());
+        }
+
+        let generate = *self.u.choose(&self.terminators)?;
+        let op = generate(self)?;
+        block.push(&op);
+
+        Ok(())
+    }
+}
+
+/// Values, that can be randomly generated for round-trip testing.
+///
+/// Every operand type and attribute type of a derived operation must implement
+/// this trait.
+pub trait Generate: Sized {
+    fn generate(gen: &mut OpGenerator) -> GenerateResult<Self>;
+}
+
+macro_rules! impl_generate {
+    ($($ty:ty),*) => {
+        $(
+        impl Generate for $ty {
+            fn generate(gen: &mut OpGenerator) -> GenerateResult<Self> {
+                gen.unstructured().arbitrary()
+            }
+        }
+        )*
+    };
+}
+
+impl_generate!(bool, i8, u8, i16, u16, i32, u32, i64, u64);
+
+impl Generate for String {
+    /// Strings are restricted to printable ASCII without quotes, as the textual
+    /// IR has no escape sequences.
+    fn generate(gen: &mut OpGenerator) -> GenerateResult<Self> {
+        let len = gen.unstructured().int_in_range(0..=16)?;
+        (0..len)
+            .map(|_| {
+                let c = gen.unstructured().int_in_range(b' '..=b'~')?;
+                Ok(if c == b'"' { '_' } else { c as char })
+            })
+            .collect()
+    }
+}
+
+impl Generate for Type {
+    fn generate(gen: &mut OpGenerator) -> GenerateResult<Self> {
+        gen.ty()
+    }
+}
+
+impl<T: Generate> Generate for Vec<T> {
+    fn generate(gen: &mut OpGenerator) -> GenerateResult<Self> {
+        let len = gen.unstructured().int_in_range(0..=4)?;
+        (0..len).map(|_| T::generate(gen)).collect()
+    }
+}
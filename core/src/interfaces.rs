@@ -0,0 +1,166 @@
+//! Common operation interfaces
+//!
+//! Interfaces are regular Rust traits registered for an operation with
+//! [`tir_macros::op_implements`], or attached to an operation from another crate
+//! with [`tir_macros::external_model`]. Either way, they can be queried
+//! uniformly with [`crate::utils::op_dyn_cast`], which lets generic passes work
+//! across all dialects.
+
+use std::any::Any;
+
+use crate::{utils::op_dyn_cast, BlockRef, OpRef, Value};
+
+/// Kind of interaction between an operation and memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryEffect {
+    Read,
+    Write,
+    Allocate,
+    Free,
+}
+
+/// Operations that describe how they interact with memory
+pub trait MemoryEffects: Any {
+    /// List of memory effects. Operations have no effects by default.
+    fn get_effects(&self) -> Vec<MemoryEffect> {
+        vec![]
+    }
+
+    fn has_effect(&self, effect: MemoryEffect) -> bool {
+        self.get_effects().contains(&effect)
+    }
+
+    /// Operation does not touch memory at all
+    fn is_pure(&self) -> bool {
+        self.get_effects().is_empty()
+    }
+}
+
+/// Operations that transfer control to a function. Unlike branches, calls have no
+/// successors within the function, the callee is referred to by its symbol.
+pub trait CallOpInterface: Any {
+    /// Symbol name of the called function
+    fn get_callee(&self) -> String;
+
+    /// Control continues at the next operation once the callee returns. Tail calls
+    /// never return.
+    fn returns(&self) -> bool {
+        true
+    }
+
+    /// Values passed to the callee
+    fn get_call_operands(&self) -> Vec<Value> {
+        vec![]
+    }
+}
+
+/// Operations that transfer control to other blocks
+pub trait BranchOpInterface: Any {
+    /// Blocks control may be transferred to
+    fn get_successors(&self) -> Vec<BlockRef>;
+
+    /// Control may continue to the next operation, e.g. when a condition is not met
+    fn may_fall_through(&self) -> bool {
+        false
+    }
+
+    fn get_num_successors(&self) -> usize {
+        self.get_successors().len()
+    }
+}
+
+/// Memory effects of an arbitrary operation, if it implements [`MemoryEffects`]
+pub fn get_memory_effects(op: &OpRef) -> Option<Vec<MemoryEffect>> {
+    op_dyn_cast::<dyn MemoryEffects>(op.clone()).map(|op| op.borrow().get_effects())
+}
+
+/// Check that an operation is known not to interact with memory
+pub fn is_memory_effect_free(op: &OpRef) -> bool {
+    get_memory_effects(op).is_some_and(|effects| effects.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::builtin::{CallOp, ConstOp, ModuleEndOp, ModuleOp, VoidType};
+    use crate::utils::{op_has_trait, op_interfaces};
+    use crate::{self as tir_core, Attr, Context, Op, RwCell};
+
+    trait TestInterface: Any {
+        fn answer(&self) -> u32;
+
+        fn double_answer(&self) -> u32 {
+            self.answer() * 2
+        }
+    }
+
+    struct ModuleEndModel(Arc<RwCell<ModuleEndOp>>);
+
+    impl From<Arc<RwCell<ModuleEndOp>>> for ModuleEndModel {
+        fn from(op: Arc<RwCell<ModuleEndOp>>) -> Self {
+            Self(op)
+        }
+    }
+
+    #[tir_macros::external_model(op = ModuleEndOp)]
+    impl TestInterface for ModuleEndModel {
+        fn answer(&self) -> u32 {
+            assert!(self.0.borrow().get_parent_region().is_none());
+            21
+        }
+    }
+
+    #[test]
+    fn test_external_model() {
+        let context = Context::new();
+        let module_end: OpRef = ModuleEndOp::builder(&context).build();
+        let module: OpRef = ModuleOp::builder(&context).build();
+
+        assert!(op_has_trait::<dyn TestInterface>(module_end.clone()));
+        assert!(!op_has_trait::<dyn TestInterface>(module.clone()));
+
+        let iface = op_dyn_cast::<dyn TestInterface>(module_end.clone()).unwrap();
+        assert_eq!(iface.borrow().double_answer(), 42);
+
+        let interfaces = op_interfaces(&module_end);
+        assert!(interfaces.contains(&"Terminator"));
+        assert!(interfaces.contains(&"TestInterface"));
+        assert!(!op_interfaces(&module).contains(&"TestInterface"));
+    }
+
+    #[test]
+    fn test_memory_effects() {
+        let context = Context::new();
+        let constant: OpRef = ConstOp::builder(&context)
+            .value(Attr::I8(1))
+            .return_type(VoidType::build(context.clone()).into())
+            .build();
+        let module: OpRef = ModuleOp::builder(&context).build();
+
+        assert_eq!(get_memory_effects(&constant), Some(vec![]));
+        assert!(is_memory_effect_free(&constant));
+        assert_eq!(get_memory_effects(&module), None);
+        assert!(!is_memory_effect_free(&module));
+    }
+
+    #[test]
+    fn test_call_op_interface() {
+        let context = Context::new();
+        let call: OpRef = CallOp::builder(&context)
+            .callee("foo".to_string())
+            .return_type(VoidType::build(context.clone()).into())
+            .build();
+        let module: OpRef = ModuleOp::builder(&context).build();
+
+        let iface = op_dyn_cast::<dyn CallOpInterface>(call.clone()).unwrap();
+        assert_eq!(iface.borrow().get_callee(), "foo");
+        assert!(iface.borrow().returns());
+        assert!(iface.borrow().get_call_operands().is_empty());
+        assert!(op_dyn_cast::<dyn CallOpInterface>(module.clone()).is_none());
+
+        assert!(op_interfaces(&call).contains(&"CallOpInterface"));
+        assert!(!op_interfaces(&module).contains(&"CallOpInterface"));
+    }
+}
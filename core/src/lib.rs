@@ -4,11 +4,16 @@ mod builder;
 pub mod builtin;
 mod common_traits;
 mod context;
+mod diagnostic_engine;
 mod dialect;
 mod error;
+mod generator;
+mod interfaces;
+mod location;
 mod operation;
 mod pass_manager;
 mod region;
+mod sync;
 mod r#type;
 pub mod utils;
 mod validate;
@@ -20,12 +25,17 @@ pub use attrs::*;
 pub use builder::*;
 pub use common_traits::*;
 pub use context::*;
+pub use diagnostic_engine::*;
 pub use dialect::*;
 pub use error::*;
+pub use generator::*;
+pub use interfaces::*;
+pub use location::*;
 pub use operation::*;
 pub use pass_manager::*;
 pub use r#type::*;
 pub use region::*;
+pub use sync::*;
 pub use validate::*;
 pub use value::*;
 pub use walkers::*;
@@ -0,0 +1,312 @@
+use lpl::combinators::text::take_while;
+use lpl::combinators::{literal, spaced};
+use lpl::{InternalError, ParseResult, ParseStream, Parser, Span};
+
+use std::sync::Arc;
+
+use crate::builtin::FuncOp;
+use crate::parser::{string_literal, Parsable};
+use crate::{
+    self as tir_core, dfs_walk, print_string_literal, DiagKind, IRFormatter, IRStrStream,
+    PassError, Printable, RwCell,
+};
+
+/// Source location of an operation.
+///
+/// Locations are attached to every operation and are expected to be preserved
+/// by transformations, so that diagnostics can be traced back to the input.
+///
+/// Textual form:
+/// ```tir
+/// loc(unknown)
+/// loc("input.tir":3:5)
+/// loc("name")
+/// loc("name"("input.tir":3:5))
+/// loc(fused["input.tir":3:5, "input.tir":4:5])
+/// loc(0x1000)
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// Operation origin is unknown, e.g. it was created programmatically
+    #[default]
+    Unknown,
+    /// Position in a source file. Both line and column are 1-based.
+    FileLineCol {
+        filename: String,
+        line: u32,
+        column: u32,
+    },
+    /// Named location with an optional underlying location
+    Name {
+        name: String,
+        child: Option<Box<Location>>,
+    },
+    /// Operation has been produced from several other operations
+    Fused(Vec<Location>),
+    /// Operation was decoded from machine code at the given address
+    Address(u64),
+}
+
+impl Location {
+    pub fn file_line_col(filename: &str, line: u32, column: u32) -> Self {
+        Location::FileLineCol {
+            filename: filename.to_owned(),
+            line,
+            column,
+        }
+    }
+
+    pub fn name(name: &str, child: Option<Location>) -> Self {
+        Location::Name {
+            name: name.to_owned(),
+            child: child.map(Box::new),
+        }
+    }
+
+    /// Create a fused location. Unknown locations are dropped, nested fused
+    /// locations are flattened and duplicates are removed.
+    pub fn fused<I: IntoIterator<Item = Location>>(locations: I) -> Self {
+        let mut result: Vec<Location> = vec![];
+
+        for loc in locations {
+            let nested = match loc {
+                Location::Unknown => vec![],
+                Location::Fused(nested) => nested,
+                loc => vec![loc],
+            };
+            for loc in nested {
+                if !result.contains(&loc) {
+                    result.push(loc);
+                }
+            }
+        }
+
+        match result.len() {
+            0 => Location::Unknown,
+            1 => result.pop().unwrap(),
+            _ => Location::Fused(result),
+        }
+    }
+
+    /// Create a location from parser span
+    pub fn from_span(span: &Span, source: &str) -> Self {
+        let offset = span.get_offset_start().min(source.len());
+        let prefix = &source[..offset];
+        let line = prefix.matches('\n').count() + 1;
+        let line_start = prefix.rfind('\n').map(|p| p + 1).unwrap_or(0);
+        let column = prefix[line_start..].chars().count() + 1;
+
+        Location::file_line_col(
+            span.get_filename().unwrap_or("-"),
+            line as u32,
+            column as u32,
+        )
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Location::Unknown)
+    }
+
+    fn print_raw(&self, fmt: &mut dyn IRFormatter) {
+        match self {
+            Location::Unknown => fmt.write_direct("unknown"),
+            Location::FileLineCol {
+                filename,
+                line,
+                column,
+            } => {
+                print_string_literal(fmt, filename);
+                fmt.write_direct(&format!(":{}:{}", line, column));
+            }
+            Location::Name { name, child } => {
+                print_string_literal(fmt, name);
+                if let Some(child) = child {
+                    fmt.write_direct("(");
+                    child.print_raw(fmt);
+                    fmt.write_direct(")");
+                }
+            }
+            Location::Fused(locations) => {
+                fmt.write_direct("fused[");
+                for (idx, loc) in locations.iter().enumerate() {
+                    if idx != 0 {
+                        fmt.write_direct(", ");
+                    }
+                    loc.print_raw(fmt);
+                }
+                fmt.write_direct("]");
+            }
+            Location::Address(address) => fmt.write_direct(&format!("{:#x}", address)),
+        }
+    }
+}
+
+impl Printable for Location {
+    fn print(&self, fmt: &mut dyn IRFormatter) {
+        fmt.write_direct("loc(");
+        self.print_raw(fmt);
+        fmt.write_direct(")");
+    }
+}
+
+/// Parse a decimal line or column number
+fn position<'a>(kind: &'static str) -> impl Parser<'a, IRStrStream<'a>, u32> {
+    take_while(char::is_ascii_digit).try_map(move |value, span| {
+        value
+            .parse()
+            .map_err(|_| DiagKind::LocationOutOfRange(kind, value.to_string(), span).into())
+    })
+}
+
+fn raw_location(input: IRStrStream<'_>) -> ParseResult<IRStrStream<'_>, Location> {
+    let span = input.span();
+
+    // Alternatives are picked by their prefix, so that out of range numbers are
+    // reported instead of falling through to the next alternative
+    if let Ok((_, next_input)) = literal("unknown").parse(input.clone()) {
+        return Ok((Location::Unknown, next_input));
+    }
+
+    if let Ok((_, Some(next_input))) = literal("0x").parse(input.clone()) {
+        return take_while(char::is_ascii_hexdigit)
+            .try_map(|addr, span| {
+                u64::from_str_radix(addr, 16)
+                    .map(Location::Address)
+                    .map_err(|_| {
+                        DiagKind::LocationOutOfRange("address", format!("0x{}", addr), span).into()
+                    })
+            })
+            .parse(next_input);
+    }
+
+    if let Ok((_, Some(mut next_input))) = literal("fused[").parse(input.clone()) {
+        let mut locations = vec![];
+        loop {
+            if let Ok((_, next_input)) = spaced(literal("]")).parse(next_input.clone()) {
+                return Ok((Location::Fused(locations), next_input));
+            }
+            if !locations.is_empty() {
+                let (_, ni) = spaced(literal(",")).parse(next_input)?;
+                next_input = ni.ok_or(InternalError::ExpectedNotFound("]", span.clone()))?;
+            }
+            let (location, ni) = spaced(raw_location).parse(next_input)?;
+            locations.push(location);
+            next_input = ni.ok_or(InternalError::ExpectedNotFound("]", span.clone()))?;
+        }
+    }
+
+    let (name, next_input) = string_literal().parse(input)?;
+    let Some(next_input) = next_input else {
+        return Ok((Location::name(&name, None), None));
+    };
+
+    if let Ok((_, Some(next_input))) = literal(":").parse(next_input.clone()) {
+        let ((line, column), next_input) = position("line")
+            .and_then(literal(":"))
+            .and_then(position("column"))
+            .map(|((line, _), column)| (line, column))
+            .parse(next_input)?;
+        return Ok((Location::file_line_col(&name, line, column), next_input));
+    }
+
+    if let Ok((_, Some(next_input))) = literal("(").parse(next_input.clone()) {
+        let (child, next_input) = raw_location
+            .and_then(literal(")"))
+            .map(|(child, _)| child)
+            .parse(next_input)?;
+        return Ok((Location::name(&name, Some(child)), next_input));
+    }
+
+    Ok((Location::name(&name, None), Some(next_input)))
+}
+
+impl Parsable<Location> for Location {
+    fn parse(input: IRStrStream) -> ParseResult<IRStrStream, Location> {
+        literal("loc(")
+            .and_then(spaced(raw_location))
+            .and_then(literal(")"))
+            .map(|((_, loc), _)| loc)
+            .parse(input)
+    }
+}
+
+/// Reset locations of a function and all operations inside it
+#[tir_macros::pass(name = "strip-debuginfo", wrapper = tir_core::FuncPassWrapper)]
+fn strip_debuginfo(func: &Arc<RwCell<FuncOp>>) -> Result<(), PassError> {
+    dfs_walk(func.clone(), |op| {
+        op.borrow_mut().set_location(Location::Unknown)
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, StringPrinter};
+
+    fn roundtrip(loc: Location) {
+        let mut printer = StringPrinter::new();
+        loc.print(&mut printer);
+        let text = printer.get();
+
+        let context = Context::new();
+        let input = IRStrStream::new(&text, "-", context);
+        let (parsed, _) = Location::parse(input).unwrap();
+        assert_eq!(parsed, loc, "{}", text);
+    }
+
+    #[test]
+    fn test_location_roundtrip() {
+        roundtrip(Location::Unknown);
+        roundtrip(Location::file_line_col("test.tir", 3, 5));
+        roundtrip(Location::name("foo", None));
+        roundtrip(Location::name(
+            "foo",
+            Some(Location::file_line_col("test.tir", 1, 1)),
+        ));
+        roundtrip(Location::fused([
+            Location::file_line_col("test.tir", 1, 1),
+            Location::Address(0x1000),
+        ]));
+        roundtrip(Location::Address(0x42));
+        roundtrip(Location::Address(u64::MAX));
+        roundtrip(Location::file_line_col("dir\\\"a\".tir", 1, 1));
+        roundtrip(Location::name("\"", Some(Location::Unknown)));
+    }
+
+    #[test]
+    fn test_location_out_of_range() {
+        for text in [
+            "loc(0x1ffffffffffffffff)",
+            "loc(\"a.c\":99999999999:1)",
+            "loc(\"a.c\":1:4294967296)",
+        ] {
+            let context = Context::new();
+            let input = IRStrStream::new(text, "-", context);
+            assert!(Location::parse(input).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_fused() {
+        let a = Location::file_line_col("a.tir", 1, 1);
+        let b = Location::Address(4);
+
+        assert_eq!(Location::fused([]), Location::Unknown);
+        assert_eq!(Location::fused([a.clone(), Location::Unknown]), a);
+        assert_eq!(
+            Location::fused([a.clone(), Location::fused([a.clone(), b.clone()])]),
+            Location::Fused(vec![a, b])
+        );
+    }
+
+    #[test]
+    fn test_from_span() {
+        let source = "module {\n  module_end\n}";
+        let span = Span::new(None, 11, 21);
+        assert_eq!(
+            Location::from_span(&span, source),
+            Location::file_line_col("-", 2, 3)
+        );
+    }
+}
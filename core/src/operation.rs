@@ -1,14 +1,14 @@
 use crate::utils::CastableMeta;
 use crate::{
-    AllocId, Attr, ContextRef, ContextWRef, OpAssembly, OpValidator, Printable, RegionRef,
-    RegionWRef, Type, Validate, Value,
+    AllocId, Attr, ContextRef, ContextWRef, Location, OpAssembly, OpValidator, Printable,
+    RegionRef, RegionWRef, RwCell, Type, Validate, Value,
 };
 use std::any::Any;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{collections::HashMap, sync::Arc};
 
-pub type OpRef = Rc<RefCell<dyn Op>>;
+pub type OpRef = Arc<RwCell<dyn Op>>;
 
-pub trait Op: Any + OpAssembly + Printable + Validate + OpValidator {
+pub trait Op: Any + Send + Sync + OpAssembly + Printable + Validate + OpValidator {
     fn get_operation_name(&self) -> &'static str;
     fn get_attrs(&self) -> &HashMap<String, Attr>;
     fn add_attrs(&mut self, attrs: &HashMap<String, Attr>);
@@ -23,6 +23,9 @@ pub trait Op: Any + OpAssembly + Printable + Validate + OpValidator {
 
     fn get_dialect_id(&self) -> u32;
 
+    fn get_location(&self) -> Location;
+    fn set_location(&mut self, location: Location);
+
     fn get_regions(&self) -> OpRegionIter;
     fn has_regions(&self) -> bool;
 
@@ -40,6 +43,7 @@ pub struct OpImpl {
     pub alloc_id: AllocId,
     pub parent_region: Option<RegionWRef>,
     pub attrs: HashMap<String, Attr>,
+    pub location: Location,
 }
 
 pub struct OpRegionIter {
@@ -1,17 +1,45 @@
 use core::fmt;
-use std::{cell::RefCell, fmt::Write, rc::Rc};
+use std::{
+    cell::Cell,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use dyn_clone::DynClone;
 use thiserror::Error;
 
-use crate::{builtin::ModuleOp, utils, OpRef};
+use crate::{
+    builtin::{FuncOp, ModuleOp},
+    utils, ContextRef, IRDiagnostic, IsolatedFromAbove, Location, OpRef, RwCell,
+};
 
 #[derive(Error, Debug)]
 pub enum PassError {
     #[error("No pass registered with name `{0}`")]
     UnknownPass(String),
     #[error("Unexpected op type, expected `{0}`, got `{1}`")]
-    UnexpectedOpType(String, String),
+    UnexpectedOpType(String, String, Location),
+    /// Pass can not process the operation at the location
+    #[error("{1}")]
+    InvalidOp(Location, String),
+    /// Pass has failed and already reported the reason via [`crate::DiagnosticEngine`]
+    #[error("Pass failed")]
+    Failure,
+}
+
+impl PassError {
+    /// Location of the operation, that caused the error
+    pub fn get_location(&self) -> Option<&Location> {
+        match self {
+            PassError::UnexpectedOpType(_, _, location) | PassError::InvalidOp(location, _) => {
+                Some(location)
+            }
+            PassError::UnknownPass(_) | PassError::Failure => None,
+        }
+    }
 }
 
 pub trait PassWrapper: DynClone + Sync + Send {
@@ -84,7 +112,7 @@ impl fmt::Debug for PassManager {
     }
 }
 
-type ModulePassFn = fn(op: &Rc<RefCell<ModuleOp>>) -> Result<(), PassError>;
+type ModulePassFn = fn(op: &Arc<RwCell<ModuleOp>>) -> Result<(), PassError>;
 
 #[derive(Debug, Clone)]
 pub struct ModulePassWrapper {
@@ -114,6 +142,7 @@ impl PassWrapper for ModulePassWrapper {
                 return Err(PassError::UnexpectedOpType(
                     "module".to_string(),
                     op.borrow().get_operation_name().to_string(),
+                    op.borrow().get_location(),
                 ))
             }
         };
@@ -122,6 +151,133 @@ impl PassWrapper for ModulePassWrapper {
     }
 }
 
+type FuncPassFn = fn(op: &Arc<RwCell<FuncOp>>) -> Result<(), PassError>;
+
+/// Runs a pass on every function nested directly in the input operation.
+///
+/// Functions are isolated from above, so if the context allows more than one
+/// thread, they are processed concurrently. Diagnostics are reported in the order
+/// functions appear in the IR, so the output does not depend on the number of threads.
+#[derive(Debug, Clone)]
+pub struct FuncPassWrapper {
+    pass: FuncPassFn,
+    name: &'static str,
+}
+
+impl FuncPassWrapper {
+    pub fn new(name: &'static str, pass: FuncPassFn) -> Self {
+        FuncPassWrapper { pass, name }
+    }
+}
+
+impl PassWrapper for FuncPassWrapper {
+    fn get_wrapper_name(&self) -> &'static str {
+        "FuncPass"
+    }
+
+    fn get_pass_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn run(&self, op: &OpRef) -> Result<(), PassError> {
+        if let Some(func) = utils::op_cast::<FuncOp>(op.clone()) {
+            return (self.pass)(&func);
+        }
+
+        if !op.borrow().has_regions() {
+            return Err(PassError::UnexpectedOpType(
+                "func".to_string(),
+                op.borrow().get_operation_name().to_string(),
+                op.borrow().get_location(),
+            ));
+        }
+
+        let funcs: Vec<Arc<RwCell<FuncOp>>> = op
+            .borrow()
+            .get_regions()
+            .flat_map(|region| region.iter())
+            .flat_map(|block| block.iter())
+            .filter_map(utils::op_cast)
+            .collect();
+        let isolated = funcs
+            .iter()
+            .all(|func| utils::op_has_trait::<dyn IsolatedFromAbove>(func.clone()));
+
+        let context = op.borrow().get_context();
+        let num_threads = context.get_num_threads().min(funcs.len());
+
+        if !isolated || num_threads < 2 {
+            // Same policy as run_parallel: every function is processed, and the
+            // first error in IR order is reported
+            let results: Vec<_> = funcs.iter().map(self.pass).collect();
+            return results.into_iter().try_for_each(|result| result);
+        }
+
+        run_parallel(&context, &funcs, num_threads, self.pass)
+    }
+}
+
+thread_local! {
+    /// Index of the function currently processed by this thread
+    static CURRENT_UNIT: Cell<usize> = const { Cell::new(0) };
+}
+
+fn run_parallel(
+    context: &ContextRef,
+    funcs: &[Arc<RwCell<FuncOp>>],
+    num_threads: usize,
+    pass: FuncPassFn,
+) -> Result<(), PassError> {
+    let engine = context.get_diagnostic_engine();
+
+    // Diagnostics are buffered per function and replayed in IR order once all
+    // threads are done.
+    let captured = Arc::new(Mutex::new(vec![]));
+    let handler = {
+        let captured = captured.clone();
+        engine.register_handler(Box::new(move |diag: &IRDiagnostic| {
+            let unit = CURRENT_UNIT.with(|u| u.get());
+            captured.lock().unwrap().push((unit, diag.clone()));
+        }))
+    };
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, Result<(), PassError>)> = std::thread::scope(|s| {
+        let workers: Vec<_> = (0..num_threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(func) = funcs.get(idx) else {
+                            break;
+                        };
+                        CURRENT_UNIT.with(|u| u.set(idx));
+                        results.push((idx, pass(func)));
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect()
+    });
+
+    engine.unregister_handler(handler);
+
+    let mut diagnostics = std::mem::take(&mut *captured.lock().unwrap());
+    diagnostics.sort_by_key(|(unit, _)| *unit);
+    for (_, diag) in &diagnostics {
+        engine.replay(diag);
+    }
+
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().try_for_each(|(_, result)| result)
+}
+
 pub struct PassRegistryEntry {
     wrapper: Box<dyn PassWrapper>,
 }
@@ -147,14 +303,37 @@ fn find_pass_by_name(name: &str) -> Option<Box<dyn PassWrapper>> {
 
 #[cfg(test)]
 mod tests {
-    use std::{cell::RefCell, rc::Rc};
+    use std::sync::{Arc, Mutex};
 
-    use crate::builtin::{ConstOp, VoidType};
-    use crate::{self as tir_core, Attr, Context, OpRef};
+    use crate::builtin::{ConstOp, FuncOp, FuncType, ReturnOp, VoidType};
+    use crate::{
+        self as tir_core, Attr, Context, IRDiagnostic, Op, OpBuilder, OpRef, Region, RwCell,
+        StringPrinter,
+    };
     use crate::{builtin::ModuleOp, PassError, PassManager};
 
     #[tir_macros::pass(name = "test-pass", wrapper = super::ModulePassWrapper)]
-    fn test_pass(_op: &Rc<RefCell<ModuleOp>>) -> Result<(), PassError> {
+    fn test_pass(_op: &Arc<RwCell<ModuleOp>>) -> Result<(), PassError> {
+        Ok(())
+    }
+
+    #[tir_macros::pass(name = "test-func-pass", wrapper = super::FuncPassWrapper)]
+    fn test_func_pass(op: &Arc<RwCell<FuncOp>>) -> Result<(), PassError> {
+        let func = op.borrow();
+        let name = func.sym_name().to_string();
+        let context = func.get_context();
+        context
+            .get_diagnostic_engine()
+            .emit(IRDiagnostic::remark(&*func, &name));
+
+        if name == "f13" || name == "f27" {
+            return Err(PassError::Failure);
+        }
+
+        let region = func.get_body_region();
+        let builder = OpBuilder::new(context.clone(), region.first().unwrap());
+        builder.insert(&ReturnOp::builder(&context).build());
+
         Ok(())
     }
 
@@ -189,4 +368,62 @@ mod tests {
         let passes = format!("{:?}", pm);
         assert!(passes.contains("test-pass"));
     }
+
+    /// Runs `test-func-pass` over 32 functions with `num_threads` threads and
+    /// returns the emitted remarks together with the printed module
+    fn run_func_pass(num_threads: usize) -> (Vec<String>, String) {
+        let context = Context::new();
+        context.set_num_threads(num_threads);
+
+        let module = ModuleOp::builder(&context).build();
+        let builder = OpBuilder::new(context.clone(), module.borrow().get_body());
+        let void = VoidType::build(context.clone());
+        let func_type = FuncType::build(context.clone(), &[], void.into());
+
+        for i in 0..32 {
+            let func = FuncOp::builder(&context)
+                .sym_name(format!("f{}", i))
+                .func_type(func_type.clone().into())
+                .body(Region::with_single_block(&context))
+                .build();
+            builder.insert(&func);
+        }
+
+        let remarks = Arc::new(Mutex::new(vec![]));
+        let handler = {
+            let remarks = remarks.clone();
+            context
+                .get_diagnostic_engine()
+                .register_handler(Box::new(move |diag| {
+                    remarks.lock().unwrap().push(diag.get_message().to_string())
+                }))
+        };
+
+        let pm = PassManager::new_from_list(&["test-func-pass"]).unwrap();
+        let module: OpRef = module;
+        assert!(matches!(pm.run(&module), Err(PassError::Failure)));
+        context.get_diagnostic_engine().unregister_handler(handler);
+
+        let mut printer = StringPrinter::new();
+        module.borrow().print(&mut printer);
+
+        let remarks = std::mem::take(&mut *remarks.lock().unwrap());
+        (remarks, printer.get())
+    }
+
+    #[test]
+    fn func_passes_run_in_parallel() {
+        let (remarks, printed) = run_func_pass(4);
+
+        let expected: Vec<_> = (0..32).map(|i| format!("f{}", i)).collect();
+        assert_eq!(remarks, expected);
+
+        // Every function has been processed despite failures in some of them
+        assert_eq!(printed.matches("return").count(), 30);
+    }
+
+    #[test]
+    fn func_pass_failures_do_not_depend_on_threads() {
+        assert_eq!(run_func_pass(1), run_func_pass(4));
+    }
 }
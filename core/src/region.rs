@@ -1,18 +1,17 @@
 use std::{
-    cell::RefCell,
+    any::TypeId,
     iter::zip,
-    rc::{Rc, Weak},
-    sync::Arc,
+    sync::{Arc, Weak},
 };
 
 use crate::{
-    utils::op_has_trait, AllocId, ContextRef, ContextWRef, OpRef, Terminator, Type, Validate,
-    ValidateErr, Value,
+    builtin::OpaqueOp, utils::op_has_trait, AllocId, ContextRef, ContextWRef, OpRef, RwCell,
+    Terminator, Type, Validate, ValidateErr, Value,
 };
 
-pub type RegionRef = Rc<Region>;
+pub type RegionRef = Arc<Region>;
 pub type RegionWRef = Weak<Region>;
-pub type BlockRef = Rc<Block>;
+pub type BlockRef = Arc<Block>;
 pub type BlockWRef = Weak<Block>;
 
 #[derive(Debug, Clone)]
@@ -124,7 +123,7 @@ impl BlockImpl {
 }
 
 #[derive(Debug)]
-pub struct Block(RefCell<BlockImpl>);
+pub struct Block(RwCell<BlockImpl>);
 
 pub struct BlockIter {
     context: ContextRef,
@@ -159,9 +158,9 @@ impl Iterator for BlockArgIter {
 
 impl Block {
     pub fn empty(parent: &RegionRef) -> BlockRef {
-        Rc::new(Block(RefCell::new(BlockImpl::new(
+        Arc::new(Block(RwCell::new(BlockImpl::new(
             "entry".to_string(),
-            Rc::downgrade(parent),
+            Arc::downgrade(parent),
         ))))
     }
 
@@ -171,9 +170,9 @@ impl Block {
         arg_types: &[Type],
         arg_names: &[T],
     ) -> BlockRef {
-        let block = Rc::new(Block(RefCell::new(BlockImpl::new(
+        let block = Arc::new(Block(RwCell::new(BlockImpl::new(
             name.to_string(),
-            Rc::downgrade(parent),
+            Arc::downgrade(parent),
         ))));
 
         block.clone().add_arguments(zip(arg_names, arg_types));
@@ -225,10 +224,10 @@ impl Block {
     }
 
     pub fn add_arguments<'s, S: AsRef<str>, T: IntoIterator<Item = (S, &'s Type)>>(
-        self: Rc<Self>,
+        self: Arc<Self>,
         args: T,
     ) {
-        let this = Rc::downgrade(&self);
+        let this = Arc::downgrade(&self);
         for (name, ty) in args {
             self.0
                 .borrow_mut()
@@ -255,7 +254,9 @@ impl Validate for Block {
         };
 
         if let Some(op) = self.last() {
-            if !op_has_trait::<dyn Terminator>(op) {
+            // Nothing is known about unregistered operations, assume they can terminate blocks
+            let is_opaque = (*op.borrow()).type_id() == TypeId::of::<OpaqueOp>();
+            if !is_opaque && !op_has_trait::<dyn Terminator>(op) {
                 return Err(ValidateErr::BlockMissingTerminator(self_ref));
             }
         } else {
@@ -307,11 +308,11 @@ impl RegionImpl {
 }
 
 #[derive(Debug)]
-pub struct Region(RefCell<RegionImpl>);
+pub struct Region(RwCell<RegionImpl>);
 
 impl Region {
     pub fn empty(context: &ContextRef) -> RegionRef {
-        Rc::new(Region(RefCell::new(RegionImpl::new(Arc::downgrade(
+        Arc::new(Region(RwCell::new(RegionImpl::new(Arc::downgrade(
             context,
         )))))
     }
@@ -332,9 +333,30 @@ impl Region {
         self.0.borrow_mut().blocks.push(block);
     }
 
+    /// Detach the block from the region. Operations of the block are not erased.
+    pub fn remove_block(&self, block: &BlockRef) {
+        self.0
+            .borrow_mut()
+            .blocks
+            .retain(|candidate| !Arc::ptr_eq(candidate, block));
+    }
+
     pub fn get_parent_op(&self) -> OpRef {
-        let context = self.0.borrow().context.upgrade().unwrap();
-        context.get_op(self.0.borrow().parent_op).unwrap()
+        self.try_get_parent_op().unwrap()
+    }
+
+    /// Get operation that owns this region, if the region has been attached to one
+    pub fn try_get_parent_op(&self) -> Option<OpRef> {
+        let (context, parent_op) = {
+            let region = self.0.borrow();
+            (region.context.upgrade()?, region.parent_op)
+        };
+        context.get_op(parent_op)
+    }
+
+    #[doc(hidden)]
+    pub fn set_parent_op(&self, op: AllocId) {
+        self.0.borrow_mut().parent_op = op;
     }
 
     pub fn first(&self) -> Option<BlockRef> {
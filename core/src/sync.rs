@@ -0,0 +1,250 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+/// Thread-safe interior mutability for IR objects.
+///
+/// `RwCell` is a readers-writer lock with an API that mirrors [`std::cell::RefCell`].
+/// Conflicting borrows from different threads block, which allows independent parts
+/// of the IR (e.g. functions) to be processed concurrently. Conflicting borrows from
+/// the same thread can never succeed and panic immediately, like with `RefCell`.
+/// Nested immutable borrows of the same thread never block, even if a writer is
+/// waiting for the value.
+pub struct RwCell<T: ?Sized> {
+    state: Mutex<BorrowState>,
+    released: Condvar,
+    value: UnsafeCell<T>,
+}
+
+#[derive(Default)]
+struct BorrowState {
+    writer: Option<ThreadId>,
+    /// Threads, that hold immutable borrows, with the number of borrows of each one
+    readers: Vec<(ThreadId, usize)>,
+    waiting_writers: usize,
+}
+
+impl BorrowState {
+    fn is_reader(&self, thread: ThreadId) -> bool {
+        self.readers.iter().any(|(reader, _)| *reader == thread)
+    }
+}
+
+// SAFETY: access to the value is synchronized by the borrow state, the same way
+// `RwLock` does it.
+unsafe impl<T: ?Sized + Send> Send for RwCell<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwCell<T> {}
+
+/// Immutable borrow of the value of [`RwCell`]
+pub struct RwCellRef<'a, T: ?Sized> {
+    cell: &'a RwCell<T>,
+    thread: ThreadId,
+}
+
+/// Mutable borrow of the value of [`RwCell`]
+pub struct RwCellRefMut<'a, T: ?Sized> {
+    cell: &'a RwCell<T>,
+}
+
+impl<T> RwCell<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            state: Mutex::default(),
+            released: Condvar::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwCell<T> {
+    /// Immutably borrow the wrapped value. Blocks while the value is mutably borrowed
+    /// by another thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is mutably borrowed by the current thread.
+    pub fn borrow(&self) -> RwCellRef<'_, T> {
+        let thread = thread::current().id();
+        let mut state = self.lock_state();
+
+        if state.writer == Some(thread) {
+            drop(state);
+            panic!("RwCell is already mutably borrowed by this thread");
+        }
+
+        // Waiting for a queued writer would deadlock with the outer borrow
+        if !state.is_reader(thread) {
+            state = self
+                .released
+                .wait_while(state, |state| {
+                    state.writer.is_some() || state.waiting_writers > 0
+                })
+                .unwrap();
+        }
+
+        match state
+            .readers
+            .iter_mut()
+            .find(|(reader, _)| *reader == thread)
+        {
+            Some((_, count)) => *count += 1,
+            None => state.readers.push((thread, 1)),
+        }
+
+        RwCellRef { cell: self, thread }
+    }
+
+    /// Mutably borrow the wrapped value. Blocks while the value is borrowed by other
+    /// threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is borrowed by the current thread.
+    pub fn borrow_mut(&self) -> RwCellRefMut<'_, T> {
+        let thread = thread::current().id();
+        let mut state = self.lock_state();
+
+        if state.writer == Some(thread) || state.is_reader(thread) {
+            drop(state);
+            panic!("RwCell is already borrowed by this thread");
+        }
+
+        state.waiting_writers += 1;
+        let mut state = self
+            .released
+            .wait_while(state, |state| {
+                state.writer.is_some() || !state.readers.is_empty()
+            })
+            .unwrap();
+        state.waiting_writers -= 1;
+        state.writer = Some(thread);
+
+        RwCellRefMut { cell: self }
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, BorrowState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl<T: Default> Default for RwCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock_state();
+        let mut d = f.debug_struct("RwCell");
+        if state.writer.is_some() {
+            d.field("value", &format_args!("<borrowed>"));
+        } else {
+            // SAFETY: the state lock keeps writers away while the value is printed
+            d.field("value", &unsafe { &*self.value.get() });
+        }
+        d.finish()
+    }
+}
+
+impl<T: ?Sized> Deref for RwCellRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: there are no mutable borrows while this one exists
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwCellRef<'_, T> {
+    fn drop(&mut self) {
+        let mut state = self.cell.lock_state();
+        if let Some(idx) = state
+            .readers
+            .iter()
+            .position(|(reader, _)| *reader == self.thread)
+        {
+            state.readers[idx].1 -= 1;
+            if state.readers[idx].1 == 0 {
+                state.readers.swap_remove(idx);
+            }
+        }
+        if state.readers.is_empty() {
+            self.cell.released.notify_all();
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for RwCellRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: this is the only borrow of the value
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwCellRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: this is the only borrow of the value
+        unsafe { &mut *self.cell.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwCellRefMut<'_, T> {
+    fn drop(&mut self) {
+        self.cell.lock_state().writer = None;
+        self.cell.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "RwCell is already borrowed by this thread")]
+    fn test_borrow_mut_while_borrowed() {
+        let cell = RwCell::new(0);
+        let _value = cell.borrow();
+        let _ = cell.borrow_mut();
+    }
+
+    #[test]
+    #[should_panic(expected = "RwCell is already mutably borrowed by this thread")]
+    fn test_borrow_while_mutably_borrowed() {
+        let cell = RwCell::new(0);
+        let _value = cell.borrow_mut();
+        let _ = cell.borrow();
+    }
+
+    #[test]
+    fn test_nested_borrow_with_waiting_writer() {
+        let cell = Arc::new(RwCell::new(0));
+        let outer = cell.borrow();
+
+        let writer = {
+            let cell = cell.clone();
+            thread::spawn(move || *cell.borrow_mut() += 1)
+        };
+        while cell.lock_state().waiting_writers == 0 {
+            thread::yield_now();
+        }
+
+        let inner = cell.borrow();
+        assert_eq!(*inner, 0);
+        drop(inner);
+        drop(outer);
+
+        writer.join().unwrap();
+        assert_eq!(*cell.borrow(), 1);
+    }
+}
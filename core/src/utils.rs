@@ -1,22 +1,47 @@
-use std::{any::TypeId, cell::RefCell, rc::Rc};
+use std::{any::TypeId, sync::Arc};
 
-use crate::{Op, OpRef};
+use crate::{Op, OpRef, RwCell};
 
 #[doc(hidden)]
 pub struct CastableMeta {
     pub type_id: TypeId,
+    pub name: &'static str,
     pub caster: *const (),
 }
 
 unsafe impl Sync for CastableMeta {}
 unsafe impl Send for CastableMeta {}
 
-pub fn op_cast<T: Op>(op: OpRef) -> Option<Rc<RefCell<T>>> {
+/// Interface implementation attached to an operation from outside of its crate
+#[doc(hidden)]
+pub struct ExternalModelMeta {
+    pub op_type_id: TypeId,
+    pub interface: CastableMeta,
+}
+
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static TIR_EXTERNAL_MODELS: [fn() -> ExternalModelMeta];
+
+/// Find all interface entries of an operation, including external models
+fn op_interface_entries(op: &OpRef) -> impl Iterator<Item = CastableMeta> {
+    let op_type_id = (*op.borrow()).type_id();
+    let own = op.borrow().get_meta().iter().map(|func| func());
+    let external = TIR_EXTERNAL_MODELS
+        .iter()
+        .map(|func| func())
+        .filter(move |model| model.op_type_id == op_type_id)
+        .map(|model| model.interface);
+
+    own.chain(external)
+}
+
+pub fn op_cast<T: Op>(op: OpRef) -> Option<Arc<RwCell<T>>> {
     if op.borrow().type_id() != TypeId::of::<T>() {
         return None;
     }
 
-    Some(unsafe { Rc::from_raw(Rc::into_raw(op) as *const RefCell<T>) })
+    Some(unsafe { Arc::from_raw(Arc::into_raw(op) as *const RwCell<T>) })
 }
 
 /// Cast an operation to a registered trait
@@ -26,7 +51,7 @@ pub fn op_cast<T: Op>(op: OpRef) -> Option<Rc<RefCell<T>>> {
 /// That is easily done with `tir_macros::op_implements` attribute:
 /// ```
 /// # use tir_macros::{Op, OpAssembly, OpValidator};
-/// # use winnow::Parser;
+/// # use lpl::{ParseStream, Parser};
 /// # use tir_core::{Op, OpAssembly, OpRef, OpImpl, Printable};
 /// # use tir_core::builtin::DIALECT_NAME;
 /// # #[derive(Op, Debug, Clone, OpAssembly, OpValidator)]
@@ -44,6 +69,9 @@ pub fn op_cast<T: Op>(op: OpRef) -> Option<Rc<RefCell<T>>> {
 /// And later users can dynamically query known traits and perform generic
 /// transformations or analysis on operations.
 ///
+/// Traits can also be attached to operations defined in other crates with
+/// `tir_macros::external_model`. Such models are queried the same way.
+///
 /// **Example:**
 /// ```
 /// use tir_core::{OpRef, utils::op_dyn_cast, Terminator};
@@ -54,20 +82,13 @@ pub fn op_cast<T: Op>(op: OpRef) -> Option<Rc<RefCell<T>>> {
 ///     }
 /// }
 /// ```
-pub fn op_dyn_cast<T: ?Sized + 'static>(op: OpRef) -> Option<Rc<RefCell<T>>> {
-    let meta = op.borrow().get_meta();
-    let entry = meta.iter().find_map(|func| {
-        let entry = func();
-        if entry.type_id == TypeId::of::<RefCell<T>>() {
-            Some(entry)
-        } else {
-            None
-        }
-    })?;
+pub fn op_dyn_cast<T: ?Sized + 'static>(op: OpRef) -> Option<Arc<RwCell<T>>> {
+    let entry =
+        op_interface_entries(&op).find(|entry| entry.type_id == TypeId::of::<RwCell<T>>())?;
     let context = op.borrow().get_context();
     let op_ref = context.get_op(op.borrow().get_alloc_id())?;
     let caster =
-        unsafe { std::mem::transmute::<*const (), fn(OpRef) -> Rc<RefCell<T>>>(entry.caster) };
+        unsafe { std::mem::transmute::<*const (), fn(OpRef) -> Arc<RwCell<T>>>(entry.caster) };
     Some(caster(op_ref))
 }
 
@@ -86,5 +107,22 @@ pub fn op_dyn_cast<T: ?Sized + 'static>(op: OpRef) -> Option<Rc<RefCell<T>>> {
 /// }
 /// ```
 pub fn op_has_trait<T: ?Sized + 'static>(op: OpRef) -> bool {
-    op.borrow().has_trait(TypeId::of::<RefCell<T>>())
+    let type_id = TypeId::of::<RwCell<T>>();
+    op.borrow().has_trait(type_id) || op_interface_entries(&op).any(|e| e.type_id == type_id)
+}
+
+/// List names of all traits and interfaces an operation implements, including
+/// the ones attached with external models.
+///
+/// **Example:**
+/// ```
+/// use tir_core::{Context, OpRef, utils::op_interfaces};
+/// use tir_core::builtin::ModuleEndOp;
+///
+/// let context = Context::new();
+/// let op: OpRef = ModuleEndOp::builder(&context).build();
+/// assert!(op_interfaces(&op).contains(&"Terminator"));
+/// ```
+pub fn op_interfaces(op: &OpRef) -> Vec<&'static str> {
+    op_interface_entries(op).map(|entry| entry.name).collect()
 }
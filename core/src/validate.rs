@@ -1,4 +1,4 @@
-use crate::BlockRef;
+use crate::{BlockRef, Location};
 use thiserror::Error;
 
 pub trait Validate {
@@ -15,4 +15,29 @@ pub enum ValidateErr {
     BlockNotRegisteredWithRegion(String),
     #[error("The last operation in basic block must be a terminator")]
     BlockMissingTerminator(BlockRef),
+    #[error("Operation '{0}' requires attribute '{1}' of type {2}")]
+    InvalidAttr(&'static str, &'static str, &'static str, Location),
+    #[error("Operation '{0}' violates constraint: {1}")]
+    ConstraintViolation(&'static str, String, Location),
+}
+
+impl ValidateErr {
+    /// Location the error should be reported at
+    pub fn location(&self) -> Location {
+        match self {
+            ValidateErr::BlockNotRegisteredWithRegion(_) => Location::Unknown,
+            ValidateErr::InvalidAttr(_, _, _, location) => location.clone(),
+            ValidateErr::ConstraintViolation(_, _, location) => location.clone(),
+            ValidateErr::BlockMissingTerminator(block) => {
+                if let Some(op) = block.last() {
+                    return op.borrow().get_location();
+                }
+                block
+                    .get_parent_region()
+                    .try_get_parent_op()
+                    .map(|op| op.borrow().get_location())
+                    .unwrap_or_default()
+            }
+        }
+    }
 }
@@ -9,6 +9,6 @@ fuzz_target!(|data: &[u8]| {
     let context = Context::new();
 
     if let Ok(ir) = from_utf8(data) {
-        let _ = parse_ir(context, "-", ir);
+        let _ = parse_ir(context, ir, "-");
     }
 });
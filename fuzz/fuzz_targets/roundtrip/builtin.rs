@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tir_core::Context;
+use tir_fuzz::check_dialect_roundtrip;
+
+fuzz_target!(|data: &[u8]| {
+    let context = Context::new();
+
+    if let Err(err) = check_dialect_roundtrip(context, "builtin", data) {
+        panic!("{}", err);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tir_core::Context;
+use tir_fuzz::check_dialect_roundtrip;
+
+fuzz_target!(|data: &[u8]| {
+    let context = Context::new();
+    context.add_dialect(tir_backend::isema::create_dialect());
+
+    if let Err(err) = check_dialect_roundtrip(context, "isema", data) {
+        panic!("{}", err);
+    }
+});
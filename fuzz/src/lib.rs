@@ -0,0 +1,216 @@
+//! Round-trip property testing for the textual IR.
+//!
+//! For every operation registered in a [`Context`], printing it, parsing the
+//! result back and printing it again must produce the same text. The same holds
+//! for the generic form, which must parse back into an operation, that prints
+//! identically to the original. Round-tripping must also not change whether an
+//! operation passes validation.
+//!
+//! Operations are produced by [`OpGenerator`] from arbitrary bytes, so the same
+//! checks are used by unit tests with fixed seeds and by `cargo fuzz` targets.
+
+use std::fmt;
+
+use arbitrary::Unstructured;
+use tir_core::{parse_ir, ContextRef, OpGenerateFn, OpGenerator, OpRef, PrintFlags, StringPrinter};
+
+/// Violation of the round-trip property
+#[derive(Debug)]
+pub enum RoundTripError {
+    /// Printed operation could not be parsed back
+    Parse { text: String, message: String },
+    /// Parsed operation printed differently from the original
+    Mismatch { expected: String, actual: String },
+    /// Parsed operation passes validation, while the original does not, or
+    /// vice versa
+    Validity { text: String, before: bool },
+}
+
+impl fmt::Display for RoundTripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundTripError::Parse { text, message } => {
+                write!(f, "failed to parse printed IR: {}\n{}", message, text)
+            }
+            RoundTripError::Mismatch { expected, actual } => {
+                write!(
+                    f,
+                    "IR changed after round trip\nexpected:\n{}\nactual:\n{}",
+                    expected, actual
+                )
+            }
+            RoundTripError::Validity { text, before } => {
+                let (before, after) = if *before {
+                    ("valid", "invalid")
+                } else {
+                    ("invalid", "valid")
+                };
+                write!(
+                    f,
+                    "{} operation became {} after round trip\n{}",
+                    before, after, text
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoundTripError {}
+
+fn print_op(op: &OpRef, generic: bool) -> String {
+    let mut printer = StringPrinter::with_flags(PrintFlags {
+        generic,
+        ..Default::default()
+    });
+    op.borrow().print(&mut printer);
+    printer.get()
+}
+
+fn reparse(context: &ContextRef, text: &str) -> Result<OpRef, RoundTripError> {
+    parse_ir(context.clone(), text, "-").map_err(|diag| RoundTripError::Parse {
+        text: text.to_string(),
+        message: format!("{:?}", diag),
+    })
+}
+
+/// Check that the operation survives printing and parsing in both the custom
+/// and the generic forms.
+pub fn check_op_roundtrip(op: &OpRef) -> Result<(), RoundTripError> {
+    let context = op.borrow().get_context();
+    let valid = op.borrow().validate().is_ok();
+
+    let expected = print_op(op, false);
+
+    let check = |parsed: OpRef| {
+        let actual = print_op(&parsed, false);
+        if actual != expected {
+            return Err(RoundTripError::Mismatch {
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        if parsed.borrow().validate().is_ok() != valid {
+            return Err(RoundTripError::Validity {
+                text: expected.clone(),
+                before: valid,
+            });
+        }
+        Ok(())
+    };
+
+    check(reparse(&context, &expected)?)?;
+    check(reparse(&context, &print_op(op, true))?)
+}
+
+/// Generate a random operation of the dialect and check that it round trips.
+///
+/// Regions of the operation are filled with operations of all dialects
+/// registered in the context. Running out of random data is not an error.
+///
+/// # Panics
+///
+/// Panics if the dialect is not registered in the context.
+pub fn check_dialect_roundtrip(
+    context: ContextRef,
+    dialect: &str,
+    data: &[u8],
+) -> Result<(), RoundTripError> {
+    let root = context
+        .get_dialect_by_name(dialect)
+        .unwrap_or_else(|| panic!("dialect '{}' is not registered", dialect));
+    let roots: Vec<OpGenerateFn> = root
+        .get_operation_generators()
+        .map(|(_, generate)| generate)
+        .collect();
+
+    let dialects = context.get_dialects();
+    let dialects: Vec<_> = dialects.iter().map(|dialect| dialect.as_ref()).collect();
+
+    let mut u = Unstructured::new(data);
+    let mut gen = OpGenerator::new(context.clone(), &dialects, &mut u);
+
+    let op = match gen
+        .unstructured()
+        .choose(&roots)
+        .and_then(|generate| generate(&mut gen))
+    {
+        Ok(op) => op,
+        Err(_) => return Ok(()),
+    };
+
+    check_op_roundtrip(&op)
+}
+
+/// Check one random operation of every dialect registered in the context
+pub fn check_context_roundtrip(context: ContextRef, data: &[u8]) -> Result<(), RoundTripError> {
+    for dialect in context.get_dialects() {
+        check_dialect_roundtrip(context.clone(), dialect.get_name(), data)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tir_core::Context;
+
+    /// Number of random operations to check per dialect
+    const NUM_ITERATIONS: usize = 500;
+    const DATA_LEN: usize = 512;
+
+    fn context() -> ContextRef {
+        let context = Context::new();
+        context.add_dialect(tir_backend::target::create_dialect());
+        context.add_dialect(tir_backend::isema::create_dialect());
+        context.add_dialect(tir_riscv::create_dialect());
+        context
+    }
+
+    /// Simple xorshift generator to get reproducible random data
+    fn random_data(seed: u64) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..DATA_LEN)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn check_dialect(dialect: &str) {
+        for seed in 0..NUM_ITERATIONS as u64 {
+            let data = random_data(seed);
+            if let Err(err) = check_dialect_roundtrip(context(), dialect, &data) {
+                panic!("seed {}: {}", seed, err);
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_roundtrip() {
+        check_dialect("builtin");
+    }
+
+    #[test]
+    fn test_target_roundtrip() {
+        check_dialect("target");
+    }
+
+    #[test]
+    fn test_isema_roundtrip() {
+        check_dialect("isema");
+    }
+
+    #[test]
+    fn test_riscv_roundtrip() {
+        check_dialect("riscv");
+    }
+
+    #[test]
+    fn test_context_roundtrip() {
+        check_context_roundtrip(context(), &random_data(42)).unwrap();
+    }
+}
@@ -0,0 +1,16 @@
+; RUN: isasim --experiment %S/Inputs/load_store.yaml %s | filecheck %s
+
+.text
+entry:
+c.mv x8, x1
+c.lw x9, 0(x8)
+c.add x9, x2
+c.sw x9, 48(x8)
+c.ld x10, 48(x8)
+c.addi x10, 1
+c.sub x10, x9
+c.slli x10, 4
+
+; CHECK: "x8": 4096,
+; CHECK: "x9": 78,
+; CHECK: "x10": 16,
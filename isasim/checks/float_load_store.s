@@ -0,0 +1,17 @@
+; RUN: isasim --experiment %S/Inputs/load_store.yaml %s | filecheck %s
+
+.text
+entry:
+flw f1, 0(x1)
+fsd f1, 16(x1)
+ld x3, 16(x1)
+fmv.x.w x4, f1
+fld f2, 16(x1)
+fmv.d.x f3, x2
+
+; CHECK: "x3": 18446744069414584356,
+; CHECK: "x4": 36,
+; CHECK: "f1": 18446744069414584356,
+; CHECK: "f2": 18446744069414584356,
+; CHECK: "f3": 42,
+; CHECK: "fcsr": 0,
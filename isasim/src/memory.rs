@@ -76,7 +76,7 @@ impl MemoryMap {
     }
 
     pub fn store(&mut self, address: u64, data: &[u8]) -> Result<(), SimErr> {
-        if address % data.len() as u64 != 0 && !self.unaligned_access {
+        if !address.is_multiple_of(data.len() as u64) && !self.unaligned_access {
             return Err(SimErr::UnalignedAccess(address, data.len()));
         }
 
@@ -90,7 +90,7 @@ impl MemoryMap {
     }
 
     pub fn load(&self, address: u64, size: u8) -> Result<Vec<u8>, SimErr> {
-        if address % size as u64 != 0 && !self.unaligned_access {
+        if !address.is_multiple_of(size as u64) && !self.unaligned_access {
             return Err(SimErr::UnalignedAccess(address, size as usize));
         }
 
@@ -97,9 +97,19 @@ pub trait RegFile {
     fn dump(&self) -> String;
 }
 
+/// Bits of the fflags field of fcsr, that accrues floating-point exceptions
+const FFLAGS_MASK: u64 = 0x1f;
+/// Offset and bits of the frm field of fcsr, that holds the dynamic rounding mode
+const FRM_SHIFT: u64 = 5;
+const FRM_MASK: u64 = 0x7;
+/// Bits of fcsr, that are not reserved
+const FCSR_MASK: u64 = 0xff;
+
 #[derive(Debug)]
 pub struct RISCVRegFile {
     registers: Vec<Value>,
+    fp_registers: Vec<Value>,
+    fcsr: u64,
     base_width: u8,
 }
 
@@ -107,12 +117,34 @@ impl RISCVRegFile {
     pub fn new() -> Rc<RefCell<Self>> {
         let mut registers = vec![];
         registers.resize(32, Value::default());
+        let mut fp_registers = vec![];
+        fp_registers.resize(32, Value::default());
 
         Rc::new(RefCell::new(Self {
             registers,
+            fp_registers,
+            fcsr: 0,
             base_width: 8,
         }))
     }
+
+    fn read_fcsr(&self, reg_name: &str) -> Option<u64> {
+        match reg_name {
+            "fflags" => Some(self.fcsr & FFLAGS_MASK),
+            "frm" => Some((self.fcsr >> FRM_SHIFT) & FRM_MASK),
+            "fcsr" => Some(self.fcsr),
+            _ => None,
+        }
+    }
+
+    fn write_fcsr(&mut self, reg_name: &str, value: u64) {
+        self.fcsr = match reg_name {
+            "fflags" => (self.fcsr & !FFLAGS_MASK) | (value & FFLAGS_MASK),
+            "frm" => (self.fcsr & !(FRM_MASK << FRM_SHIFT)) | ((value & FRM_MASK) << FRM_SHIFT),
+            "fcsr" => value & FCSR_MASK,
+            _ => panic!("unknown register '{}'", reg_name),
+        };
+    }
 }
 
 impl RegFile for RISCVRegFile {
@@ -121,19 +153,34 @@ impl RegFile for RISCVRegFile {
     }
 
     fn read_register(&self, reg_name: &str) -> Value {
-        let reg = tir_riscv::parse_gpr(reg_name).unwrap();
-        self.registers[reg.get_reg_num()].clone()
+        if let Some(reg) = tir_riscv::parse_gpr(reg_name) {
+            return self.registers[reg.get_reg_num()].clone();
+        }
+        if let Some(reg) = tir_riscv::parse_fpr(reg_name) {
+            return self.fp_registers[reg.get_reg_num()].clone();
+        }
+
+        self.read_fcsr(reg_name)
+            .unwrap_or_else(|| panic!("unknown register '{}'", reg_name))
+            .into()
     }
 
     fn write_register(&mut self, reg_name: &str, value: &Value) {
-        let reg = tir_riscv::parse_gpr(reg_name).unwrap();
+        if let Some(reg) = tir_riscv::parse_gpr(reg_name) {
+            // hardwired zero
+            if let tir_riscv::GPR::X0 = reg {
+                return;
+            }
 
-        // hardwired zero
-        if let tir_riscv::GPR::X0 = reg {
+            self.registers[reg.get_reg_num()] = value.clone();
+            return;
+        }
+        if let Some(reg) = tir_riscv::parse_fpr(reg_name) {
+            self.fp_registers[reg.get_reg_num()] = value.clone();
             return;
         }
 
-        self.registers[reg.get_reg_num()] = value.clone();
+        self.write_fcsr(reg_name, value.get_lower64());
     }
 
     fn dump(&self) -> String {
@@ -149,6 +196,16 @@ impl RegFile for RISCVRegFile {
             ));
         }
 
+        for id in 0..self.fp_registers.len() {
+            let reg: tir_riscv::FPR = TryFrom::try_from(id).expect("A valid register");
+            strings.push(format!(
+                "    \"{}\": {},",
+                reg.get_names()[0],
+                self.fp_registers[id].get_lower64()
+            ));
+        }
+
+        strings.push(format!("    \"fcsr\": {},", self.fcsr));
         strings.push("}".to_string());
 
         strings.join("\n")
@@ -171,4 +228,29 @@ mod tests {
 
         assert_eq!(value, other_value);
     }
+
+    #[test]
+    fn riscv_fp_regfile() {
+        let reg_file: Rc<RefCell<dyn RegFile>> = RISCVRegFile::new();
+
+        let value = 0xffffffff3f800000_u64;
+        reg_file.borrow_mut().write_register("fa0", &value.into());
+        assert_eq!(reg_file.borrow().read_register("f10").get_lower64(), value);
+        assert_eq!(reg_file.borrow().read_register("x10").get_lower64(), 0);
+
+        // frm and fflags are fields of fcsr
+        reg_file.borrow_mut().write_register("frm", &1_u64.into());
+        reg_file
+            .borrow_mut()
+            .write_register("fflags", &0b10001_u64.into());
+        assert_eq!(
+            reg_file.borrow().read_register("fcsr").get_lower64(),
+            0b00110001
+        );
+        reg_file
+            .borrow_mut()
+            .write_register("fcsr", &0x1ff_u64.into());
+        assert_eq!(reg_file.borrow().read_register("frm").get_lower64(), 0b111);
+        assert_eq!(reg_file.borrow().read_register("fcsr").get_lower64(), 0xff);
+    }
 }
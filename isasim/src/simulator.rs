@@ -1,13 +1,14 @@
 use std::rc::Rc;
+use std::sync::Arc;
 use std::{cell::RefCell, ops::BitAnd};
 use tir_core::builtin::ModuleOp;
-use tir_core::{OpRef, StdoutPrinter};
+use tir_core::{OpRef, RwCell, StdoutPrinter};
 use tir_macros::match_op;
 
 use crate::{MemoryMap, RegFile, SimErr, Value};
 
 pub struct Simulator {
-    module: Rc<RefCell<ModuleOp>>,
+    module: Arc<RwCell<ModuleOp>>,
 }
 
 enum Either<L, R> {
@@ -21,65 +22,54 @@ fn exec_alu_op_impl<T>(
     rd: &str,
     get_bits: impl Fn(Value) -> T,
     imm_cvt: impl Fn(i16) -> T,
-    op: impl Fn(T, T) -> T,
+    op: impl Fn(T, T) -> Value,
     reg_file: &Rc<RefCell<dyn RegFile>>,
-) where
-    Value: From<T>,
-{
+) {
     let a = get_bits(reg_file.borrow().read_register(rs1));
     let b = match rhs {
         Either::Left(ref rs2) => get_bits(reg_file.borrow().read_register(rs2)),
         Either::Right(imm) => imm_cvt(imm),
     };
 
-    let c = Value::from(op(a, b));
+    let c = op(a, b);
     reg_file.borrow_mut().write_register(rd, &c);
 }
 
 macro_rules! exec_alu {
     ($name:ident, $op_ty:ty, $op:tt) => {
-        fn $name(op: &Rc<RefCell<$op_ty>>, reg_file: &Rc<RefCell<dyn RegFile>>) -> Result<(), SimErr> {
-            let rs1: String = op
-                .borrow()
-                .get_rs1_attr()
-                .clone()
-                .try_into()
-                .expect("reg name is a String attr");
-            let rd: String = op
-                .borrow()
-                .get_rd_attr()
-                .clone()
-                .try_into()
-                .expect("reg name is a String attr");
-
-            let rhs = if let Some(rs2) = op.borrow().get_rs2_attr().clone() {
-                let rs2: String = rs2.try_into().expect("should have been validated");
-                Either::Left(rs2)
+        fn $name(op: &Arc<RwCell<$op_ty>>, reg_file: &Rc<RefCell<dyn RegFile>>) -> Result<(), SimErr> {
+            let rs1 = op.borrow().rs1().to_string();
+            let rd = op.borrow().rd().to_string();
+
+            let rhs = if let Some(rs2) = op.borrow().rs2() {
+                Either::Left(rs2.to_string())
             } else {
-                let imm: i16 = op
-                    .borrow()
-                    .get_imm_attr()
-                    .expect("Either rs2 or imm must be present")
-                    .clone()
-                    .try_into()
-                    .expect("Either rs2 or imm must be present");
+                let imm = op.borrow().imm().expect("Either rs2 or imm must be present");
                 Either::Right(imm)
             };
 
-            let width: u8 = op.borrow().get_width_attr().clone().try_into().expect("must succeed");
+            let width = op.borrow().width();
+            let sign_extend = op.borrow().sign_extend().unwrap_or(false);
 
             match width {
                 64 => {
                     let get_bits = |value: Value| value.get_lower64();
                     let imm_cvt = |imm: i16| imm as u64;
-                    let op_impl = |a: u64, b: u64| a $op b;
+                    let op_impl = |a: u64, b: u64| Value::from(a $op b);
 
                     exec_alu_op_impl(&rs1, rhs, &rd, get_bits, imm_cvt, op_impl, reg_file);
                 },
                 32 => {
                     let get_bits = |value: Value| value.get_lower32();
                     let imm_cvt = |imm: i16| imm as u32;
-                    let op_impl = |a: u32, b: u32| a $op b;
+                    let op_impl = |a: u32, b: u32| {
+                        let c = a $op b;
+                        if sign_extend {
+                            Value::from(c as i32 as i64)
+                        } else {
+                            Value::from(c)
+                        }
+                    };
 
                     exec_alu_op_impl(&rs1, rhs, &rd, get_bits, imm_cvt, op_impl, reg_file);
                 },
@@ -101,31 +91,26 @@ exec_alu!(exec_sll, tir_backend::isema::SllOp, <<);
 exec_alu!(exec_srl, tir_backend::isema::SrlOp, >>);
 
 fn execute_load(
-    op: &Rc<RefCell<tir_backend::isema::LoadOp>>,
+    op: &Arc<RwCell<tir_backend::isema::LoadOp>>,
     reg_file: &Rc<RefCell<dyn RegFile>>,
     mem: &Rc<RefCell<MemoryMap>>,
 ) -> Result<(), SimErr> {
-    let base_reg: String = op
-        .borrow()
-        .get_base_addr_attr()
-        .clone()
-        .try_into()
-        .expect("reg name is a String attr");
+    let base_reg = op.borrow().base_addr().to_string();
 
     let base_addr = reg_file.borrow().read_register(&base_reg).get_lower64();
 
-    let offset: i16 = op.borrow().get_offset_attr().try_into().expect("");
+    let offset = op.borrow().offset();
 
     let addr = (base_addr as i64 + offset as i64) as u64;
 
-    let width: i32 = op.borrow().get_width_attr().try_into().expect("");
+    let width = op.borrow().width();
 
     let mut data = mem
         .borrow()
-        .load(addr, (width / 8) as u8)
+        .load(addr, width / 8)
         .map_err(|_| SimErr::MemoryAccess(addr))?;
 
-    let sign_extend: bool = op.borrow().get_sign_extend_attr().try_into().expect("");
+    let sign_extend = op.borrow().sign_extend();
 
     let extent: u8 = if sign_extend && data.last().unwrap().bitand(1 << 7) != 0 {
         255
@@ -138,32 +123,90 @@ fn execute_load(
 
     let reg_value: crate::Value = data.try_into().expect("");
 
-    let dst: String = op.borrow().get_dst_attr().clone().try_into().expect("");
+    let dst = op.borrow().dst().to_string();
 
     reg_file.borrow_mut().write_register(&dst, &reg_value);
 
     Ok(())
 }
 
-fn execute_store(
-    op: &Rc<RefCell<tir_backend::isema::StoreOp>>,
+/// Floating-point values narrower than the register are NaN-boxed: the upper bytes are set
+/// to ones
+fn nan_box(mut data: Vec<u8>, reg_file: &Rc<RefCell<dyn RegFile>>) -> Value {
+    data.resize(reg_file.borrow().base_width() as usize, 255);
+    data.try_into().expect("")
+}
+
+fn execute_fp_load(
+    op: &Arc<RwCell<tir_backend::isema::FLoadOp>>,
     reg_file: &Rc<RefCell<dyn RegFile>>,
     mem: &Rc<RefCell<MemoryMap>>,
 ) -> Result<(), SimErr> {
-    let base_reg: String = op
+    let base_reg = op.borrow().base_addr().to_string();
+
+    let base_addr = reg_file.borrow().read_register(&base_reg).get_lower64();
+    let offset = op.borrow().offset();
+
+    let addr = (base_addr as i64 + offset as i64) as u64;
+    let width = op.borrow().width();
+
+    let data = mem
         .borrow()
-        .get_base_addr_attr()
-        .clone()
-        .try_into()
-        .expect("reg name is a String attr");
+        .load(addr, width / 8)
+        .map_err(|_| SimErr::MemoryAccess(addr))?;
+
+    let dst = op.borrow().dst().to_string();
+    let reg_value = nan_box(data, reg_file);
+    reg_file.borrow_mut().write_register(&dst, &reg_value);
+
+    Ok(())
+}
+
+fn execute_fp_move(
+    op: &Arc<RwCell<tir_backend::isema::FMvOp>>,
+    reg_file: &Rc<RefCell<dyn RegFile>>,
+) -> Result<(), SimErr> {
+    let rs1 = op.borrow().rs1().to_string();
+    let rd = op.borrow().rd().to_string();
+    let width = op.borrow().width() as usize;
+
+    let data = reg_file
+        .borrow()
+        .read_register(&rs1)
+        .raw_bytes(width / 8)
+        .expect("");
+
+    let reg_value = if op.borrow().to_float() {
+        nan_box(data, reg_file)
+    } else {
+        let extent: u8 = if data.last().unwrap().bitand(1 << 7) != 0 {
+            255
+        } else {
+            0
+        };
+        let mut data = data;
+        data.resize(reg_file.borrow().base_width() as usize, extent);
+        data.try_into().expect("")
+    };
+    reg_file.borrow_mut().write_register(&rd, &reg_value);
+
+    Ok(())
+}
+
+fn execute_store(
+    op: &Arc<RwCell<tir_backend::isema::StoreOp>>,
+    reg_file: &Rc<RefCell<dyn RegFile>>,
+    mem: &Rc<RefCell<MemoryMap>>,
+) -> Result<(), SimErr> {
+    let base_reg = op.borrow().base_addr().to_string();
 
     let base_addr = reg_file.borrow().read_register(&base_reg).get_lower64();
-    let offset: i16 = op.borrow().get_offset_attr().try_into().expect("");
+    let offset = op.borrow().offset();
 
     let addr = (base_addr as i64 + offset as i64) as u64;
-    let width: i32 = op.borrow().get_width_attr().try_into().expect("");
+    let width = op.borrow().width();
 
-    let src: String = op.borrow().get_src_attr().clone().try_into().expect("");
+    let src = op.borrow().src().to_string();
     let value = reg_file
         .borrow()
         .read_register(&src)
@@ -195,6 +238,8 @@ fn execute_op(
         SrlOp => |srl| exec_srl(&srl, reg_file),
         LoadOp => |load| execute_load(&load, reg_file, mem),
         StoreOp => |store| execute_store(&store, reg_file, mem),
+        FLoadOp => |load| execute_fp_load(&load, reg_file, mem),
+        FMvOp => |mv| execute_fp_move(&mv, reg_file),
         _ => || {
             let mut printer = StdoutPrinter::new();
             op.borrow().print(&mut printer);
@@ -205,7 +250,7 @@ fn execute_op(
 }
 
 impl Simulator {
-    pub fn new(module: Rc<RefCell<ModuleOp>>) -> Self {
+    pub fn new(module: Arc<RwCell<ModuleOp>>) -> Self {
         Simulator { module }
     }
 
@@ -219,9 +264,7 @@ impl Simulator {
             if let Some(section) =
                 tir_core::utils::op_cast::<tir_backend::target::SectionOp>(instr.clone())
             {
-                let attr = section.borrow().get_name_attr().clone();
-                let name: String = attr.try_into().unwrap();
-                if name != ".text" {
+                if section.borrow().name() != ".text" {
                     continue;
                 }
 
@@ -1,4 +1,5 @@
-use crate::{OpFieldAttrs, OpFieldReceiver, OpReceiver};
+use crate::assembly_format::{generate_assembly, parse_format, FormatVar, FormatVarKind};
+use crate::{AttrKind, OpAttrs, OpFieldAttrs, OpFieldReceiver, OpReceiver};
 use darling::FromDeriveInput;
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
@@ -76,7 +77,7 @@ fn make_operands_parser(fields: &[OpFieldReceiver]) -> proc_macro2::TokenStream
                 .and_then(
                     lpl::combinators::literal(#operand_str)
                         .and_then(lpl::combinators::spaced(lpl::combinators::literal("=")))
-                        .and_then(#ty::parse)
+                        .and_then(<#ty>::parse)
                         .and_then(lpl::combinators::spaced(lpl::combinators::literal(",")))
                         .flat().map(|(_, _, value, _)| {
                             value
@@ -87,9 +88,16 @@ fn make_operands_parser(fields: &[OpFieldReceiver]) -> proc_macro2::TokenStream
         }
     }));
 
-    parsers.push(quote! {
-        .flat()
-    });
+    if names.is_empty() {
+        return quote! {};
+    }
+
+    // A single operand already forms a flat pair with the leading whitespace
+    if names.len() > 1 {
+        parsers.push(quote! {
+            .flat()
+        });
+    }
 
     let mut builder = vec![];
 
@@ -100,10 +108,6 @@ fn make_operands_parser(fields: &[OpFieldReceiver]) -> proc_macro2::TokenStream
         })
     }
 
-    if parsers.len() == 2 {
-        return quote! {};
-    }
-
     let operands_parser = quote! {
         #(#parsers)*
     };
@@ -115,8 +119,56 @@ fn make_operands_parser(fields: &[OpFieldReceiver]) -> proc_macro2::TokenStream
     }
 }
 
+fn make_format_vars(
+    known_attrs: Option<&OpAttrs>,
+    fields: &[OpFieldReceiver],
+) -> syn::Result<Vec<FormatVar>> {
+    let mut vars = vec![];
+
+    for f in fields {
+        let name = f.ident.as_ref().unwrap().to_string();
+        let kind = match &f.attrs {
+            OpFieldAttrs::Operand => FormatVarKind::Value(Box::new(f.ty.clone())),
+            OpFieldAttrs::Return => FormatVarKind::Result,
+            OpFieldAttrs::Region(region) => FormatVarKind::Region {
+                single_block: region.single_block,
+            },
+            OpFieldAttrs::None => continue,
+        };
+        vars.push(FormatVar { name, kind });
+    }
+
+    for attr in known_attrs.into_iter().flat_map(|attrs| attrs.attrs.iter()) {
+        let kind = AttrKind::new(&attr.1)?;
+        vars.push(FormatVar {
+            name: attr.0.to_string(),
+            kind: FormatVarKind::Attr {
+                optional: kind.optional,
+                string: kind.expected == "string",
+            },
+        });
+    }
+
+    Ok(vars)
+}
+
+fn make_custom_printer_parser(
+    op_name: &syn::Ident,
+    known_attrs: Option<&OpAttrs>,
+    fields: &[OpFieldReceiver],
+    format: &syn::LitStr,
+) -> syn::Result<TokenStream> {
+    let vars = make_format_vars(known_attrs, fields)?;
+    parse_format(&format.value())
+        .and_then(|elements| generate_assembly(op_name, &elements, &vars))
+        .map_err(|err| syn::Error::new(format.span(), err))
+}
+
 pub fn make_generic_ir_printer_parser(op: DeriveInput) -> TokenStream {
-    let op = OpReceiver::from_derive_input(&op).unwrap();
+    let op = match OpReceiver::from_derive_input(&op) {
+        Ok(op) => op,
+        Err(err) => return err.write_errors(),
+    };
     let op_name = op.ident;
 
     let fields = op
@@ -126,6 +178,11 @@ pub fn make_generic_ir_printer_parser(op: DeriveInput) -> TokenStream {
         .into_iter()
         .collect::<Vec<OpFieldReceiver>>();
 
+    if let Some(format) = &op.assembly_format {
+        return make_custom_printer_parser(&op_name, op.known_attrs.as_ref(), &fields, format)
+            .unwrap_or_else(|err| err.to_compile_error());
+    }
+
     let operand_printer = make_operand_printer(&fields);
     let return_printer = make_return_type_printer(&fields);
     let operands_parser = make_operands_parser(&fields);
@@ -138,14 +195,14 @@ pub fn make_generic_ir_printer_parser(op: DeriveInput) -> TokenStream {
             #operand_printer
 
             fmt.write_direct("attrs = {");
-            let attrs: Vec<_> = self
-                .r#impl
-                .attrs
-                .iter()
-                .map(|(name, attr)| {
+            let mut names: Vec<_> = self.r#impl.attrs.keys().collect();
+            names.sort();
+            let attrs: Vec<_> = names
+                .into_iter()
+                .map(|name| {
                     let mut printer = tir_core::StringPrinter::new();
                     printer.write_direct(&format!("{} = ", name));
-                    attr.print(&mut printer);
+                    self.r#impl.attrs[name].print(&mut printer);
                     printer.get()
                 })
                 .collect();
@@ -170,7 +227,7 @@ pub fn make_generic_ir_printer_parser(op: DeriveInput) -> TokenStream {
 
             #return_parser
 
-            let op = builder.build();
+            let op = builder.build_unchecked();
             op.borrow_mut().add_attrs(&attr_list);
             let op: tir_core::OpRef = op;
 
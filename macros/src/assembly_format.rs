@@ -0,0 +1,483 @@
+//! Declarative assembly format for operations.
+//!
+//! A format is a whitespace separated list of directives:
+//!
+//! - `$name` refers to an operand, an attribute or a region of the operation;
+//! - `type($name)` refers to the result type of the operation;
+//! - `` `lit` `` is a literal token, that is printed and expected verbatim;
+//! - `attr-dict` prints all attributes, not mentioned elsewhere in the format, as a dictionary.
+//!
+//! Example: ``$lhs `,` $rhs attr-dict `:` type($result)``.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatElement {
+    Literal(String),
+    Variable(String),
+    Type(String),
+    AttrDict,
+}
+
+/// Kind of an operation field, that can be referenced from the format
+#[derive(Clone)]
+pub enum FormatVarKind {
+    /// Operand. Printed with `Printable` and parsed with `Parsable`.
+    Value(Box<syn::Type>),
+    /// Result type, only accessible with `type(...)` directive.
+    Result,
+    /// Operation attribute. String attributes are spelled as plain quoted
+    /// literals, everything else uses generic `Attr` syntax.
+    Attr {
+        optional: bool,
+        string: bool,
+    },
+    Region {
+        single_block: bool,
+    },
+}
+
+#[derive(Clone)]
+pub struct FormatVar {
+    pub name: String,
+    pub kind: FormatVarKind,
+}
+
+fn parse_variable(word: &str) -> Result<String, String> {
+    let name = word.strip_prefix('$').unwrap_or_default();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("invalid variable '{}' in assembly format", word));
+    }
+    Ok(name.to_string())
+}
+
+/// Splits format string into directives
+pub fn parse_format(format: &str) -> Result<Vec<FormatElement>, String> {
+    let mut elements = vec![];
+    let mut chars = format.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == '`' {
+            chars.next();
+            let literal: String = chars.by_ref().take_while(|&c| c != '`').collect();
+            if literal.is_empty() {
+                return Err("empty literal in assembly format".to_string());
+            }
+            elements.push(FormatElement::Literal(literal));
+            continue;
+        }
+
+        let word: String = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect();
+        if word.starts_with('$') {
+            elements.push(FormatElement::Variable(parse_variable(&word)?));
+        } else if let Some(var) = word
+            .strip_prefix("type(")
+            .and_then(|word| word.strip_suffix(')'))
+        {
+            elements.push(FormatElement::Type(parse_variable(var)?));
+        } else if word == "attr-dict" {
+            elements.push(FormatElement::AttrDict);
+        } else {
+            return Err(format!("unknown directive '{}' in assembly format", word));
+        }
+    }
+
+    Ok(elements)
+}
+
+fn needs_space_before(prev: Option<&FormatElement>, cur: &FormatElement) -> bool {
+    if let FormatElement::Literal(lit) = cur {
+        if matches!(lit.as_str(), "," | ")" | "]") {
+            return false;
+        }
+    }
+
+    match prev {
+        None => false,
+        Some(FormatElement::Literal(lit)) => !matches!(lit.as_str(), "(" | "["),
+        Some(_) => true,
+    }
+}
+
+/// Checks that every piece of the operation can be recovered from the printed form
+fn check_format(format: &[FormatElement], vars: &[FormatVar]) -> Result<(), String> {
+    let mut seen = vec![];
+    let mut has_attr_dict = false;
+
+    for element in format {
+        match element {
+            FormatElement::Variable(name) | FormatElement::Type(name) => {
+                let var = vars
+                    .iter()
+                    .find(|v| &v.name == name)
+                    .ok_or_else(|| format!("unknown variable '${}' in assembly format", name))?;
+                match (element, &var.kind) {
+                    (FormatElement::Type(_), FormatVarKind::Result) => {}
+                    (FormatElement::Type(_), _) => {
+                        return Err(format!("'type(${})' expects a result", name));
+                    }
+                    (_, FormatVarKind::Result) => {
+                        return Err(format!(
+                            "result '{}' must be referenced as 'type(${})'",
+                            name, name
+                        ));
+                    }
+                    (_, FormatVarKind::Attr { optional: true, .. }) => {
+                        return Err(format!(
+                            "optional attribute '{}' can not be used in assembly format",
+                            name
+                        ));
+                    }
+                    _ => {}
+                }
+                if seen.contains(&name) {
+                    return Err(format!("variable '${}' is used more than once", name));
+                }
+                seen.push(name);
+            }
+            FormatElement::AttrDict => {
+                if has_attr_dict {
+                    return Err("'attr-dict' is used more than once".to_string());
+                }
+                has_attr_dict = true;
+            }
+            FormatElement::Literal(_) => {}
+        }
+    }
+
+    if !has_attr_dict {
+        return Err("assembly format must contain 'attr-dict'".to_string());
+    }
+
+    for var in vars {
+        if !matches!(var.kind, FormatVarKind::Attr { .. }) && !seen.contains(&&var.name) {
+            return Err(format!(
+                "'{}' must be present in assembly format to parse the operation",
+                var.name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Generates `tir_core::OpAssembly` implementation for the given format
+pub fn generate_assembly(
+    op: &syn::Ident,
+    format: &[FormatElement],
+    vars: &[FormatVar],
+) -> Result<TokenStream, String> {
+    check_format(format, vars)?;
+
+    let elided_attrs: Vec<_> = vars
+        .iter()
+        .filter(|v| {
+            matches!(v.kind, FormatVarKind::Attr { .. })
+                && format.contains(&FormatElement::Variable(v.name.clone()))
+        })
+        .map(|v| v.name.clone())
+        .collect();
+
+    let mut printers = vec![];
+    let mut parsers = vec![];
+    let mut setters = vec![];
+    let mut region_fillers = vec![];
+    let mut needs_eof_span = false;
+
+    let mut prev = None;
+    for element in format {
+        let space = needs_space_before(prev, element);
+        let space = if space {
+            quote! { fmt.write_direct(" "); }
+        } else {
+            quote! {}
+        };
+        prev = Some(element);
+
+        let first = parsers.is_empty();
+        let input = if first {
+            quote! { input }
+        } else {
+            needs_eof_span |= *element != FormatElement::AttrDict;
+            quote! { next_input.ok_or_else(|| -> lpl::Diagnostic { lpl::InternalError::UnexpectedEof(span.clone()).into() })? }
+        };
+
+        match element {
+            FormatElement::Literal(lit) => {
+                printers.push(quote! {
+                    #space
+                    fmt.write_direct(#lit);
+                });
+                parsers.push(quote! {
+                    let (_, next_input) = lpl::combinators::spaced(lpl::combinators::literal(#lit))
+                        .parse(#input)?;
+                });
+            }
+            FormatElement::AttrDict => {
+                printers.push(quote! {
+                    let mut names: Vec<_> = self
+                        .r#impl
+                        .attrs
+                        .keys()
+                        .filter(|name| ![#(#elided_attrs),*].contains(&name.as_str()))
+                        .collect();
+                    names.sort();
+                    if !names.is_empty() {
+                        let attrs: Vec<_> = names
+                            .iter()
+                            .map(|name| {
+                                let mut printer = tir_core::StringPrinter::new();
+                                printer.write_direct(&format!("{} = ", name));
+                                self.r#impl.attrs[name.as_str()].print(&mut printer);
+                                printer.get()
+                            })
+                            .collect();
+                        #space
+                        fmt.write_direct("{");
+                        tir_core::print_comma_separated(fmt, &attrs);
+                        fmt.write_direct("}");
+                    }
+                });
+                let dict_parser = quote! {
+                    lpl::combinators::optional(tir_core::parser::attr_dict())
+                };
+                // Attribute dictionary may be omitted at the very end of the input
+                if first {
+                    parsers.push(quote! {
+                        let (dict, next_input) = #dict_parser.parse(input)?;
+                        attrs.extend(dict.unwrap_or_default());
+                    });
+                } else {
+                    parsers.push(quote! {
+                        let (dict, next_input) = match next_input {
+                            Some(next_input) => #dict_parser.parse(next_input)?,
+                            None => (None, None),
+                        };
+                        attrs.extend(dict.unwrap_or_default());
+                    });
+                }
+            }
+            FormatElement::Type(name) => {
+                let ident = format_ident!("{}", name);
+                printers.push(quote! {
+                    #space
+                    self.#ident.print(fmt);
+                });
+                parsers.push(quote! {
+                    let (#ident, next_input) = lpl::combinators::spaced(
+                        <tir_core::Type as tir_core::parser::Parsable<tir_core::Type>>::parse,
+                    )
+                        .parse(#input)?;
+                });
+                setters.push(quote! {
+                    let builder = builder.#ident(#ident);
+                });
+            }
+            FormatElement::Variable(name) => {
+                let var = vars.iter().find(|v| &v.name == name).unwrap();
+                let ident = format_ident!("{}", name);
+                match &var.kind {
+                    FormatVarKind::Value(ty) => {
+                        printers.push(quote! {
+                            #space
+                            self.#ident.print(fmt);
+                        });
+                        parsers.push(quote! {
+                            let (#ident, next_input) = lpl::combinators::spaced(<#ty as tir_core::parser::Parsable<#ty>>::parse)
+                                .parse(#input)?;
+                        });
+                        setters.push(quote! {
+                            let builder = builder.#ident(#ident);
+                        });
+                    }
+                    FormatVarKind::Result => unreachable!("checked by check_format"),
+                    FormatVarKind::Attr { string: true, .. } => {
+                        printers.push(quote! {
+                            if let Some(tir_core::Attr::String(value)) = self.r#impl.attrs.get(#name) {
+                                #space
+                                tir_core::print_string_literal(fmt, value);
+                            }
+                        });
+                        parsers.push(quote! {
+                            let (value, next_input) = lpl::combinators::spaced(tir_core::parser::string_literal())
+                                .parse(#input)?;
+                            attrs.insert(#name.to_string(), tir_core::Attr::String(value));
+                        });
+                    }
+                    FormatVarKind::Attr { .. } => {
+                        printers.push(quote! {
+                            if let Some(attr) = self.r#impl.attrs.get(#name) {
+                                #space
+                                attr.print(fmt);
+                            }
+                        });
+                        parsers.push(quote! {
+                            let (attr, next_input) = lpl::combinators::spaced(
+                                <tir_core::Attr as tir_core::parser::Parsable<tir_core::Attr>>::parse,
+                            )
+                                .parse(#input)?;
+                            attrs.insert(#name.to_string(), attr);
+                        });
+                    }
+                    FormatVarKind::Region { single_block: true } => {
+                        let get_block = format_ident!("get_{}", name);
+                        printers.push(quote! {
+                            #space
+                            fmt.start_region();
+                            for op in self.#get_block().iter() {
+                                op.borrow().print(fmt);
+                            }
+                            fmt.end_region();
+                        });
+                        parsers.push(quote! {
+                            let (#ident, next_input) = tir_core::parser::single_block_region()
+                                .parse(#input)?;
+                        });
+                        region_fillers.push(quote! {
+                            for child in #ident {
+                                op.borrow_mut().#get_block().push(&child);
+                            }
+                        });
+                    }
+                    FormatVarKind::Region {
+                        single_block: false,
+                    } => {
+                        let get_region = format_ident!("get_{}_region", name);
+                        printers.push(quote! {
+                            #space
+                            tir_core::print_region(fmt, &self.#get_region());
+                        });
+                        parsers.push(quote! {
+                            let (#ident, next_input) = tir_core::parser::region_with_blocks()
+                                .parse(#input)?;
+                        });
+                        setters.push(quote! {
+                            let builder = builder.#ident(#ident);
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let eof_span = if needs_eof_span {
+        quote! { let span = lpl::ParseStream::span(&input); }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl tir_core::OpAssembly for #op {
+            fn print_assembly(&self, fmt: &mut dyn tir_core::IRFormatter) {
+                use tir_core::Printable;
+                #(#printers)*
+            }
+
+            fn parse_assembly<'a>(
+                input: tir_core::IRStrStream<'a>,
+            ) -> lpl::ParseResult<tir_core::IRStrStream<'a>, tir_core::OpRef> {
+                use lpl::Parser;
+
+                let state = input.get_extra().unwrap();
+                let context = state.context();
+                let builder = Self::builder(&context);
+                #eof_span
+                #[allow(unused_mut)]
+                let mut attrs = std::collections::HashMap::<String, tir_core::Attr>::new();
+
+                #(#parsers)*
+                #(#setters)*
+
+                let op = builder.build_unchecked();
+                op.borrow_mut().add_attrs(&attrs);
+                #(#region_fillers)*
+                let op: tir_core::OpRef = op;
+
+                Ok((op, next_input))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_directives() {
+        let format = parse_format("$rd `,` $rs1 `(` $imm `)` attr-dict `:` type($res)").unwrap();
+        assert_eq!(
+            format,
+            vec![
+                FormatElement::Variable("rd".to_string()),
+                FormatElement::Literal(",".to_string()),
+                FormatElement::Variable("rs1".to_string()),
+                FormatElement::Literal("(".to_string()),
+                FormatElement::Variable("imm".to_string()),
+                FormatElement::Literal(")".to_string()),
+                FormatElement::AttrDict,
+                FormatElement::Literal(":".to_string()),
+                FormatElement::Type("res".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_directive() {
+        assert!(parse_format("$rd attrs").is_err());
+        assert!(parse_format("$ attr-dict").is_err());
+        assert!(parse_format("type(res) attr-dict").is_err());
+    }
+
+    #[test]
+    fn check_variables() {
+        let op = format_ident!("TestOp");
+        let vars = vec![
+            FormatVar {
+                name: "rd".to_string(),
+                kind: FormatVarKind::Value(Box::new(syn::parse_quote!(Register))),
+            },
+            FormatVar {
+                name: "imm".to_string(),
+                kind: FormatVarKind::Attr {
+                    optional: true,
+                    string: false,
+                },
+            },
+            FormatVar {
+                name: "res".to_string(),
+                kind: FormatVarKind::Result,
+            },
+        ];
+
+        let unknown = parse_format("$rd $rs1 attr-dict type($res)").unwrap();
+        assert!(generate_assembly(&op, &unknown, &vars).is_err());
+
+        let optional = parse_format("$rd $imm attr-dict type($res)").unwrap();
+        assert!(generate_assembly(&op, &optional, &vars).is_err());
+
+        let missing_operand = parse_format("attr-dict type($res)").unwrap();
+        assert!(generate_assembly(&op, &missing_operand, &vars).is_err());
+
+        let missing_result = parse_format("$rd attr-dict").unwrap();
+        assert!(generate_assembly(&op, &missing_result, &vars).is_err());
+
+        let bare_result = parse_format("$rd attr-dict $res").unwrap();
+        assert!(generate_assembly(&op, &bare_result, &vars).is_err());
+
+        let operand_type = parse_format("type($rd) attr-dict type($res)").unwrap();
+        assert!(generate_assembly(&op, &operand_type, &vars).is_err());
+
+        let no_dict = parse_format("$rd type($res)").unwrap();
+        assert!(generate_assembly(&op, &no_dict, &vars).is_err());
+
+        let valid = parse_format("$rd attr-dict `:` type($res)").unwrap();
+        assert!(generate_assembly(&op, &valid, &vars).is_ok());
+    }
+}
@@ -1,6 +1,7 @@
 extern crate proc_macro;
 
 mod assembly;
+mod assembly_format;
 mod helpers;
 mod op_impl;
 
@@ -105,10 +106,15 @@ fn dialect_type_extension(name_ident: syn::Ident) -> TokenStream {
                 fmt.write_direct(#name_str);
                 fmt.write_direct(" ");
                 fmt.write_direct("attrs = {");
-                for (name, attr) in attrs {
+                let mut names: Vec<_> = attrs.keys().collect();
+                names.sort();
+                for (idx, name) in names.into_iter().enumerate() {
+                    if idx != 0 {
+                        fmt.write_direct(", ");
+                    }
                     fmt.write_direct(name);
                     fmt.write_direct(" = ");
-                    attr.print(fmt);
+                    attrs[name].print(fmt);
                 }
                 fmt.write_direct("}");
             }
@@ -144,7 +150,7 @@ fn dialect_type_base(name_ident: syn::Ident) -> TokenStream {
         }
 
         impl tir_core::Printable for #name_ident {
-            fn print(&self, fmt: &mut dyn crate::IRFormatter) {
+            fn print(&self, fmt: &mut dyn tir_core::IRFormatter) {
                 fmt.write_direct("!");
                 if DIALECT_NAME != tir_core::builtin::DIALECT_NAME {
                     fmt.write_direct(&format!("{}.", DIALECT_NAME));
@@ -225,7 +231,7 @@ pub fn populate_dialect_ops(input: TokenStream) -> TokenStream {
 
     TokenStream::from(quote! {
         fn populate_dialect_ops(dialect: &mut Dialect) {
-            #(dialect.add_operation(#ty::get_operation_name(), Box::new(<#ty>::parse_assembly));)*
+            #(dialect.add_operation(#ty::get_operation_name(), Box::new(<#ty>::parse_assembly), Box::new(<#ty>::parse_generic), <#ty>::generate_op);)*
         }
     })
 }
@@ -330,6 +336,200 @@ fn build_return_type_accessor(fields: &[OpFieldReceiver]) -> proc_macro2::TokenS
     }
 }
 
+fn build_op_generator(
+    fields: &[OpFieldReceiver],
+    attrs: &[Attr],
+    custom: Option<&syn::Path>,
+) -> proc_macro2::TokenStream {
+    let body = if let Some(custom) = custom {
+        quote! { #custom(gen) }
+    } else {
+        let mut setters = vec![];
+        for field in fields {
+            let ident = field.ident.clone().unwrap();
+            match &field.attrs {
+                OpFieldAttrs::Operand => setters.push(quote! {
+                    let builder = builder.#ident(gen.generate()?);
+                }),
+                OpFieldAttrs::Region(_) => setters.push(quote! {
+                    let builder = builder.#ident(gen.region()?);
+                }),
+                OpFieldAttrs::Return => setters.push(quote! {
+                    let builder = builder.#ident(gen.ty()?);
+                }),
+                OpFieldAttrs::None => {}
+            }
+        }
+
+        let mut attr_generators = vec![];
+        for attr in attrs {
+            let kind = match AttrKind::new(&attr.1) {
+                Ok(kind) => kind,
+                Err(err) => return err.to_compile_error(),
+            };
+            let attr_str = attr.0.to_string();
+            let arg_ty = &kind.arg_ty;
+            let to_attr = &kind.to_attr;
+            let value = if kind.expected == "integer" {
+                quote! { gen.integer_attr()? }
+            } else {
+                quote! { gen.generate()? }
+            };
+            let generate = quote! {
+                let value: #arg_ty = #value;
+                op.borrow_mut().r#impl.attrs.insert(#attr_str.to_string(), #to_attr);
+            };
+            if kind.optional {
+                attr_generators.push(quote! {
+                    if gen.generate::<bool>()? {
+                        #generate
+                    }
+                });
+            } else {
+                attr_generators.push(generate);
+            }
+        }
+
+        quote! {
+            let context = gen.context();
+            let builder = Self::builder(&context);
+            #(#setters)*
+            let op = builder.build_unchecked();
+            #(#attr_generators)*
+            let op: tir_core::OpRef = op;
+            Ok(op)
+        }
+    };
+
+    quote! {
+        /// Build the operation from random data, see [`tir_core::OpGenerator`]
+        pub fn generate_op(gen: &mut tir_core::OpGenerator) -> tir_core::GenerateResult<tir_core::OpRef> {
+            #body
+        }
+    }
+}
+
+fn build_generic_printer_parser(
+    op_name: &str,
+    fields: &[OpFieldReceiver],
+) -> proc_macro2::TokenStream {
+    let mut operands = vec![];
+    let mut operand_types = vec![];
+    let mut regions = vec![];
+    let mut return_type = None;
+
+    for field in fields {
+        let ident = field.ident.clone().unwrap();
+        match &field.attrs {
+            OpFieldAttrs::Operand => {
+                operands.push(ident);
+                operand_types.push(field.ty.clone());
+            }
+            OpFieldAttrs::Region(_) => regions.push(ident),
+            OpFieldAttrs::Return => return_type = Some(ident),
+            OpFieldAttrs::None => {}
+        }
+    }
+
+    let operand_parsers = operands.iter().zip(&operand_types).enumerate().map(|(idx, (ident, ty))| {
+        let comma = if idx != 0 {
+            quote! {
+                let (_, next_input) = lpl::combinators::spaced(lpl::combinators::literal(",")).parse(next_input.unwrap())?;
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #comma
+            let (#ident, next_input) = lpl::combinators::spaced(<#ty>::parse).parse(next_input.unwrap())?;
+            builder = builder.#ident(#ident);
+        }
+    });
+
+    let num_regions = regions.len();
+    let num_operands = operands.len();
+
+    let return_type_builder = if let Some(ident) = &return_type {
+        quote! {
+            let Some(return_type) = tail.return_type else {
+                return Err(tir_core::DiagKind::ReturnTypeMismatch(#op_name.to_string(), "requires", span).into());
+            };
+            builder = builder.#ident(return_type);
+        }
+    } else {
+        quote! {
+            if tail.return_type.is_some() {
+                return Err(tir_core::DiagKind::ReturnTypeMismatch(#op_name.to_string(), "does not have", span).into());
+            }
+        }
+    };
+
+    quote! {
+        /// Print operation in the generic form
+        pub fn print_generic(&self, fmt: &mut dyn tir_core::IRFormatter) {
+            use tir_core::Printable;
+
+            let operands: Vec<String> = vec![#({
+                let mut printer = tir_core::StringPrinter::with_flags(fmt.get_flags());
+                self.#operands.print(&mut printer);
+                printer.get()
+            }),*];
+            let regions: Vec<tir_core::RegionRef> = vec![#(self.#regions.clone()),*];
+            #[allow(unused_variables)]
+            let context = tir_core::Op::get_context(self);
+            let operand_types: Vec<tir_core::Type> = vec![#(tir_core::OperandType::operand_type(&self.#operands, &context)),*];
+
+            tir_core::print_generic_op(
+                fmt,
+                &format!("{}.{}", DIALECT_NAME, #op_name),
+                &operands,
+                &self.r#impl.attrs,
+                &regions,
+                tir_core::GenericSignature {
+                    operand_types: &operand_types,
+                    return_type: tir_core::Op::get_return_type(self),
+                },
+                &self.r#impl.location,
+            );
+        }
+
+        /// Parse operation in the generic form, starting right after its name
+        pub fn parse_generic<'a>(input: tir_core::IRStrStream<'a>) -> lpl::ParseResult<tir_core::IRStrStream<'a>, tir_core::OpRef> {
+            use lpl::{ParseStream, Parser};
+            use tir_core::parser::Parsable;
+
+            let span = input.span();
+            let state = input.get_extra().unwrap();
+            let context = state.context();
+            let mut builder = Self::builder(&context);
+
+            let (_, next_input) = lpl::combinators::spaced(lpl::combinators::literal("(")).parse(input)?;
+            #(#operand_parsers)*
+            let (_, next_input) = lpl::combinators::spaced(lpl::combinators::literal(")")).parse(next_input.unwrap())?;
+
+            let (tail, next_input) = tir_core::parser::generic_op_tail().parse(next_input.unwrap())?;
+
+            if tail.operand_types.len() != #num_operands {
+                return Err(tir_core::DiagKind::OperandCountMismatch(#op_name.to_string(), #num_operands, tail.operand_types.len(), span).into());
+            }
+            if tail.regions.len() != #num_regions {
+                return Err(tir_core::DiagKind::RegionCountMismatch(#op_name.to_string(), #num_regions, tail.regions.len(), span).into());
+            }
+            #[allow(unused_mut, unused_variables)]
+            let mut regions = tail.regions.into_iter();
+            #(builder = builder.#regions(regions.next().unwrap());)*
+
+            #return_type_builder
+
+            let op = builder.build_unchecked();
+            op.borrow_mut().add_attrs(&tail.attrs);
+            let op: tir_core::OpRef = op;
+
+            Ok((op, next_input))
+        }
+    }
+}
+
 fn build_op_builder(
     op: syn::Ident,
     op_name: &str,
@@ -343,19 +543,78 @@ fn build_op_builder(
     let mut builder_setters = vec![];
     let mut field_idents = vec![];
     let mut attr_setters = vec![];
+    let mut region_idents = vec![];
+
+    let kinds = match attrs
+        .iter()
+        .map(|attr| AttrKind::new(&attr.1))
+        .collect::<syn::Result<Vec<_>>>()
+    {
+        Ok(kinds) => kinds,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    // Every required attribute gets its own type parameter, which turns from
+    // `AttrUnset` into `AttrSet` once the attribute is provided. `build()` is
+    // only available when all of them are set.
+    let required = attrs
+        .iter()
+        .zip(kinds.iter())
+        .filter(|(_, kind)| !kind.optional)
+        .map(|(attr, _)| attr.0.clone())
+        .collect::<Vec<_>>();
+    let state_params = (0..required.len())
+        .map(|i| format_ident!("AttrState{}", i))
+        .collect::<Vec<_>>();
+    let all_idents = attrs
+        .iter()
+        .map(|attr| attr.0.clone())
+        .chain(
+            fields
+                .iter()
+                .filter(|field| !matches!(field.attrs, OpFieldAttrs::None))
+                .filter_map(|field| field.ident.clone()),
+        )
+        .collect::<Vec<_>>();
 
-    for attr in attrs {
+    for (attr, kind) in attrs.iter().zip(kinds.iter()) {
         let ident = &attr.0;
+        let AttrKind {
+            arg_ty, to_attr, ..
+        } = kind;
         builder_fields.push(quote! {
             #ident: Option<tir_core::Attr>,
         });
 
-        builder_accessors.push(quote! {
-            pub fn #ident(mut self, value: tir_core::Attr) -> Self {
-                self.#ident = Some(value);
-                self
-            }
-        });
+        if kind.optional {
+            builder_accessors.push(quote! {
+                pub fn #ident(mut self, value: #arg_ty) -> Self {
+                    self.#ident = Some(#to_attr);
+                    self
+                }
+            });
+        } else {
+            let position = required.iter().position(|r| r == ident).unwrap();
+            let out_params = state_params.iter().enumerate().map(|(i, param)| {
+                if i == position {
+                    quote! { tir_core::AttrSet }
+                } else {
+                    quote! { #param }
+                }
+            });
+            let others = all_idents.iter().filter(|other| *other != ident);
+            builder_accessors.push(quote! {
+                pub fn #ident(self, value: #arg_ty) -> #builder_name<#(#out_params),*> {
+                    #builder_name {
+                        context: self.context,
+                        location: self.location,
+                        #ident: Some(#to_attr),
+                        #(#others: self.#others,)*
+                        _state: std::marker::PhantomData,
+                    }
+                }
+            });
+        }
 
         builder_setters.push(quote! {
             #ident: None,
@@ -397,6 +656,7 @@ fn build_op_builder(
 
         match &field.attrs {
             OpFieldAttrs::Region(region) => {
+                region_idents.push(name.clone());
                 if region.single_block && region.no_args {
                     builder_setters.push(quote! {
                         #name: Some(tir_core::Region::with_single_block(&context)),
@@ -409,26 +669,41 @@ fn build_op_builder(
         }
     }
 
+    let set_states = state_params.iter().map(|_| quote! { tir_core::AttrSet });
+
     quote! {
-        pub struct #builder_name {
+        /// Builder for the operation. Required attributes are tracked in the
+        /// type parameters, so that `build()` is only callable once they are set.
+        pub struct #builder_name<#(#state_params = tir_core::AttrUnset),*> {
             context: tir_core::ContextRef,
+            location: tir_core::Location,
             #(#builder_fields)*
+            _state: std::marker::PhantomData<(#(#state_params,)*)>,
         }
 
         impl #op {
             pub fn builder(context: &tir_core::ContextRef) -> #builder_name {
                 #builder_name {
                     context: context.clone(),
+                    location: tir_core::Location::Unknown,
                     #(#builder_setters)*
+                    _state: std::marker::PhantomData,
                 }
             }
         }
 
-
-        impl #builder_name {
+        impl<#(#state_params),*> #builder_name<#(#state_params),*> {
             #(#builder_accessors)*
 
-            pub fn build(self) -> std::rc::Rc<std::cell::RefCell<#op>> {
+            pub fn location(mut self, location: tir_core::Location) -> Self {
+                self.location = location;
+                self
+            }
+
+            /// Build the operation without checking that required attributes
+            /// are set. Used by parsers, which rely on the verifier instead.
+            #[doc(hidden)]
+            pub fn build_unchecked(self) -> std::sync::Arc<tir_core::RwCell<#op>> {
                 let context = self.context.clone();
                 let dialect = context.get_dialect_by_name(DIALECT_NAME).expect("Did you forget to register the dialect?");
                 let dialect_id = dialect.get_id();
@@ -437,8 +712,6 @@ fn build_op_builder(
 
                 #(#attr_setters)*
 
-                let weak = std::sync::Arc::downgrade(&context).clone();
-
                 let r#impl = tir_core::OpImpl {
                     context: std::sync::Arc::downgrade(&context),
                     dialect_id,
@@ -446,6 +719,7 @@ fn build_op_builder(
                     alloc_id: tir_core::AllocId::default(),
                     parent_region: None,
                     attrs,
+                    location: self.location,
                 };
 
                 let operation = #op {
@@ -453,7 +727,18 @@ fn build_op_builder(
                     r#impl,
                 };
 
-                context.allocate_op(operation)
+                let operation = context.allocate_op(operation);
+
+                let alloc_id = tir_core::Op::get_alloc_id(&*operation.borrow());
+                #(operation.borrow().#region_idents.set_parent_op(alloc_id);)*
+
+                operation
+            }
+        }
+
+        impl #builder_name<#(#set_states),*> {
+            pub fn build(self) -> std::sync::Arc<tir_core::RwCell<#op>> {
+                self.build_unchecked()
             }
         }
     }
@@ -485,6 +770,8 @@ pub fn derive_op(input: TokenStream) -> TokenStream {
     };
 
     let builder = build_op_builder(op_ident.clone(), &name, &fields, &attrs);
+    let generic = build_generic_printer_parser(&name, &fields);
+    let generator = build_op_generator(&fields, &attrs, op.generator.as_ref());
 
     let attr_accessors = if !attrs.is_empty() {
         build_attr_accessors(&attrs)
@@ -513,6 +800,11 @@ pub fn derive_op(input: TokenStream) -> TokenStream {
 
         impl tir_core::Printable for #op_ident {
             fn print(&self, fmt: &mut dyn tir_core::IRFormatter) where Self: tir_core::OpAssembly {
+                if fmt.get_flags().generic {
+                    self.print_generic(fmt);
+                    return;
+                }
+
                 fmt.indent();
                 if DIALECT_NAME != tir_core::builtin::DIALECT_NAME {
                     fmt.write_direct(DIALECT_NAME);
@@ -523,6 +815,10 @@ pub fn derive_op(input: TokenStream) -> TokenStream {
                 fmt.write_direct(" ");
 
                 self.print_assembly(fmt);
+                if fmt.get_flags().debuginfo {
+                    fmt.write_direct(" ");
+                    tir_core::Printable::print(&self.r#impl.location, fmt);
+                }
                 fmt.write_direct("\n");
             }
         }
@@ -546,7 +842,7 @@ pub fn derive_op(input: TokenStream) -> TokenStream {
             }
 
             fn get_attrs(&self) -> &std::collections::HashMap<String, tir_core::Attr> {
-                todo!();
+                &self.r#impl.attrs
             }
 
             fn add_attrs(&mut self, attrs: &std::collections::HashMap<String, tir_core::Attr>) {
@@ -583,6 +879,14 @@ pub fn derive_op(input: TokenStream) -> TokenStream {
                 self.r#impl.dialect_id
             }
 
+            fn get_location(&self) -> tir_core::Location {
+                self.r#impl.location.clone()
+            }
+
+            fn set_location(&mut self, location: tir_core::Location) {
+                self.r#impl.location = location;
+            }
+
             fn has_trait(&self, type_id: std::any::TypeId) -> bool {
                 let entry = #op_ident_const.iter().find_map(|func| {
                     let entry = func();
@@ -616,6 +920,8 @@ pub fn derive_op(input: TokenStream) -> TokenStream {
             #region_accessors
             #operand_accessors
             #attr_accessors
+            #generic
+            #generator
 
             pub fn get_operation_name() -> &'static str {
                 #name
@@ -635,12 +941,56 @@ pub fn derive_op_assembly(input: TokenStream) -> TokenStream {
 
 #[proc_macro_derive(OpValidator)]
 pub fn derive_op_validator(input: TokenStream) -> TokenStream {
-    let op = parse_macro_input!(input as syn::DeriveInput);
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let op = match OpReceiver::from_derive_input(&input) {
+        Ok(op) => op,
+        Err(err) => return err.write_errors().into(),
+    };
     let struct_name = op.ident;
+    let op_name = format!("{}.{}", op.dialect, op.name);
+    let attrs = op.known_attrs.map(|attrs| attrs.attrs).unwrap_or_default();
+    let verifier = op.verifier.map(|verifier| quote! { #verifier(self)?; });
+
+    let mut checks = vec![];
+    for attr in &attrs {
+        let kind = match AttrKind::new(&attr.1) {
+            Ok(kind) => kind,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        let attr_str = attr.0.to_string();
+        let from_attr = &kind.from_attr;
+        let expected = &kind.expected;
+        let missing = if kind.optional {
+            quote! { None => {} }
+        } else {
+            quote! {
+                None => {
+                    return Err(tir_core::ValidateErr::InvalidAttr(
+                        #op_name, #attr_str, #expected, self.r#impl.location.clone(),
+                    ));
+                }
+            }
+        };
+        checks.push(quote! {
+            match self.r#impl.attrs.get(#attr_str) {
+                Some(attr) => {
+                    let value = { let attr: &tir_core::Attr = attr; #from_attr };
+                    if value.is_none() {
+                        return Err(tir_core::ValidateErr::InvalidAttr(
+                            #op_name, #attr_str, #expected, self.r#impl.location.clone(),
+                        ));
+                    }
+                }
+                #missing
+            }
+        });
+    }
 
     quote! {
         impl tir_core::OpValidator for #struct_name {
             fn validate_op(&self) -> std::result::Result<(), tir_core::ValidateErr> {
+                #(#checks)*
+                #verifier
                 Ok(())
             }
         }
@@ -743,12 +1093,13 @@ pub fn op_implements(attr: TokenStream, item: TokenStream) -> TokenStream {
 
         fn #caster_wrapper() -> tir_core::utils::CastableMeta {
             tir_core::utils::CastableMeta {
-                type_id: std::any::TypeId::of::<std::cell::RefCell<dyn #trait_>>(),
+                type_id: std::any::TypeId::of::<tir_core::RwCell<dyn #trait_>>(),
+                name: stringify!(#trait_),
                 caster: #caster_func as *const (),
             }
         }
 
-        fn #caster_func(ref_: tir_core::OpRef) -> std::rc::Rc<std::cell::RefCell<dyn #trait_ + 'static>> {
+        fn #caster_func(ref_: tir_core::OpRef) -> std::sync::Arc<tir_core::RwCell<dyn #trait_ + 'static>> {
             let self_ = tir_core::utils::op_cast::<#self_ty>(ref_).expect("ill formed");
             self_
         }
@@ -757,6 +1108,83 @@ pub fn op_implements(attr: TokenStream, item: TokenStream) -> TokenStream {
     }.into()
 }
 
+#[derive(Debug, FromMeta)]
+struct ExternalModelInput {
+    op: syn::Path,
+}
+
+/// Attach an interface to an operation defined in another crate.
+///
+/// The model type must be constructible from a typed operation reference with
+/// `From<Arc<RwCell<Op>>>`. Once registered, `op_dyn_cast` and `op_has_trait`
+/// treat the interface as if the operation implemented it directly.
+#[proc_macro_attribute]
+pub fn external_model(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr_args = match NestedMeta::parse_meta_list(attr.into()) {
+        Ok(v) => v,
+        Err(e) => {
+            return TokenStream::from(darling::Error::from(e).write_errors());
+        }
+    };
+
+    let attr = match ExternalModelInput::from_list(&attr_args) {
+        Ok(v) => v,
+        Err(e) => {
+            return TokenStream::from(e.write_errors());
+        }
+    };
+
+    let orig: proc_macro2::TokenStream = item.clone().into();
+    let impl_ = parse_macro_input!(item as syn::ItemImpl);
+    let model = match *impl_.self_ty {
+        syn::Type::Path(path) => path.path.get_ident().cloned().unwrap(),
+        _ => panic!("not a path"),
+    };
+    let trait_path = impl_.trait_.unwrap().1;
+    let trait_ = trait_path.segments.last().unwrap().ident.clone();
+    let op = attr.op;
+
+    let caster_func = format_ident!(
+        "{}_{}_external_model_",
+        model.to_string().to_lowercase(),
+        trait_.to_string().to_lowercase()
+    );
+    let caster_wrapper = format_ident!(
+        "{}_{}_external_model_wrapper_",
+        model.to_string().to_lowercase(),
+        trait_.to_string().to_lowercase()
+    );
+    let caster_const = format_ident!(
+        "{}_{}_EXTERNAL_MODEL",
+        model.to_string().to_uppercase(),
+        trait_.to_string().to_uppercase()
+    );
+
+    quote! {
+        #[linkme::distributed_slice(tir_core::utils::TIR_EXTERNAL_MODELS)]
+        static #caster_const: fn() -> tir_core::utils::ExternalModelMeta = #caster_wrapper;
+
+        fn #caster_wrapper() -> tir_core::utils::ExternalModelMeta {
+            tir_core::utils::ExternalModelMeta {
+                op_type_id: std::any::TypeId::of::<#op>(),
+                interface: tir_core::utils::CastableMeta {
+                    type_id: std::any::TypeId::of::<tir_core::RwCell<dyn #trait_path>>(),
+                    name: stringify!(#trait_),
+                    caster: #caster_func as *const (),
+                },
+            }
+        }
+
+        fn #caster_func(ref_: tir_core::OpRef) -> std::sync::Arc<tir_core::RwCell<dyn #trait_path + 'static>> {
+            let op = tir_core::utils::op_cast::<#op>(ref_).expect("ill formed");
+            std::sync::Arc::new(tir_core::RwCell::new(#model::from(op)))
+        }
+
+        #orig
+    }
+    .into()
+}
+
 #[derive(Debug, FromMeta)]
 struct PassImplInput {
     name: String,
@@ -66,6 +66,15 @@ pub struct OpReceiver {
     pub dialect: syn::Ident,
     #[darling(default)]
     pub known_attrs: Option<OpAttrs>,
+    /// Extra verification hook, called after all known attributes have been checked
+    #[darling(default)]
+    pub verifier: Option<syn::Path>,
+    /// Custom assembly format, see `assembly_format` module for the syntax
+    #[darling(default)]
+    pub assembly_format: Option<syn::LitStr>,
+    /// Custom random generator, used instead of the derived one
+    #[darling(default)]
+    pub generator: Option<syn::Path>,
 }
 
 #[derive(Default, Debug, FromMeta)]
@@ -121,10 +130,163 @@ pub struct OpFieldReceiver {
     pub attrs: OpFieldAttrs,
 }
 
+/// How an attribute declared in `known_attrs` is stored and accessed
+pub struct AttrKind {
+    /// Type accepted by setters
+    pub arg_ty: proc_macro2::TokenStream,
+    /// Type returned by getters
+    pub ret_ty: proc_macro2::TokenStream,
+    /// Expression converting `value: arg_ty` into `tir_core::Attr`
+    pub to_attr: proc_macro2::TokenStream,
+    /// Expression converting `attr: &tir_core::Attr` into `Option<ret_ty>`
+    pub from_attr: proc_macro2::TokenStream,
+    /// Human-readable type name used in diagnostics
+    pub expected: String,
+    pub optional: bool,
+}
+
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let Type::Path(ty_path) = ty else {
+        return None;
+    };
+    if !path_is_option(&ty_path.path) {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &ty_path.path.segments[0].arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(syn::GenericArgument::Type(inner)) => Some(inner),
+        _ => None,
+    }
+}
+
+fn scalar_variant(name: &str) -> Option<&'static str> {
+    let variant = match name {
+        "bool" => "Bool",
+        "i8" => "I8",
+        "u8" => "U8",
+        "i16" => "I16",
+        "u16" => "U16",
+        "i32" => "I32",
+        "u32" => "U32",
+        "i64" => "I64",
+        "u64" => "U64",
+        _ => return None,
+    };
+    Some(variant)
+}
+
+impl AttrKind {
+    pub fn new(ty: &syn::Type) -> syn::Result<Self> {
+        if let Some(inner) = option_inner(ty) {
+            let mut kind = Self::new(inner)?;
+            kind.optional = true;
+            return Ok(kind);
+        }
+
+        let unsupported = || syn::Error::new_spanned(ty, "unsupported attribute type");
+
+        let Type::Path(ty_path) = ty else {
+            return Err(unsupported());
+        };
+        let segment = ty_path.path.segments.last().ok_or_else(unsupported)?;
+        let name = segment.ident.to_string();
+
+        let kind = match name.as_str() {
+            "String" => AttrKind {
+                arg_ty: quote! { String },
+                ret_ty: quote! { &str },
+                to_attr: quote! { tir_core::Attr::String(value) },
+                from_attr: quote! { match attr { tir_core::Attr::String(v) => Some(v.as_str()), _ => None } },
+                expected: "string".to_string(),
+                optional: false,
+            },
+            "Type" => AttrKind {
+                arg_ty: quote! { tir_core::Type },
+                ret_ty: quote! { &tir_core::Type },
+                to_attr: quote! { tir_core::Attr::Type(value) },
+                from_attr: quote! { match attr { tir_core::Attr::Type(v) => Some(v), _ => None } },
+                expected: "type".to_string(),
+                optional: false,
+            },
+            "IntegerAttr" => AttrKind {
+                arg_ty: quote! { tir_core::Attr },
+                ret_ty: quote! { i64 },
+                to_attr: quote! { value },
+                from_attr: quote! { attr.as_i64() },
+                expected: "integer".to_string(),
+                optional: false,
+            },
+            "Vec" => {
+                let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+                    return Err(unsupported());
+                };
+                let Some(syn::GenericArgument::Type(Type::Path(elem))) = args.args.first() else {
+                    return Err(unsupported());
+                };
+                let elem_name = elem
+                    .path
+                    .segments
+                    .last()
+                    .ok_or_else(unsupported)?
+                    .ident
+                    .to_string();
+                let (variant, elem_ty) = if elem_name == "Type" {
+                    ("TypeArray".to_string(), quote! { tir_core::Type })
+                } else {
+                    let variant = scalar_variant(&elem_name).ok_or_else(unsupported)?;
+                    if variant == "Bool" {
+                        return Err(unsupported());
+                    }
+                    let elem_ty = format_ident!("{}", elem_name);
+                    (format!("{}Array", variant), quote! { #elem_ty })
+                };
+                let variant = format_ident!("{}", variant);
+                AttrKind {
+                    arg_ty: quote! { Vec<#elem_ty> },
+                    ret_ty: quote! { &[#elem_ty] },
+                    to_attr: quote! { tir_core::Attr::#variant(value) },
+                    from_attr: quote! { match attr { tir_core::Attr::#variant(v) => Some(v.as_slice()), _ => None } },
+                    expected: format!("array of {}", elem_name),
+                    optional: false,
+                }
+            }
+            _ => {
+                let variant = format_ident!("{}", scalar_variant(&name).ok_or_else(unsupported)?);
+                let scalar = format_ident!("{}", name);
+                AttrKind {
+                    arg_ty: quote! { #scalar },
+                    ret_ty: quote! { #scalar },
+                    to_attr: quote! { tir_core::Attr::#variant(value) },
+                    from_attr: quote! { match attr { tir_core::Attr::#variant(v) => Some(*v), _ => None } },
+                    expected: name,
+                    optional: false,
+                }
+            }
+        };
+
+        Ok(kind)
+    }
+}
+
 pub fn build_attr_accessors(attrs: &[Attr]) -> proc_macro2::TokenStream {
     let mut attr_accessors = vec![];
 
     for attr in attrs {
+        let kind = match AttrKind::new(&attr.1) {
+            Ok(kind) => kind,
+            Err(err) => return err.to_compile_error(),
+        };
+        let typed_getter = attr.0.clone();
+        let typed_setter = format_ident!("set_{}", attr.0);
+        let AttrKind {
+            arg_ty,
+            ret_ty,
+            to_attr,
+            from_attr,
+            ..
+        } = kind;
         let getter_name = format_ident!("get_{}_attr", attr.0);
         let setter_name = format_ident!("set_{}_attr", attr.0);
         let attr_str = attr.0.to_string();
@@ -157,6 +319,41 @@ pub fn build_attr_accessors(attrs: &[Attr]) -> proc_macro2::TokenStream {
                 }
             });
         }
+
+        if kind.optional {
+            attr_accessors.push(quote! {
+                pub fn #typed_getter(&self) -> Option<#ret_ty> {
+                    self.r#impl.attrs.get(#attr_str).and_then(|attr| #from_attr)
+                }
+
+                pub fn #typed_setter(&mut self, value: Option<#arg_ty>) {
+                    match value {
+                        Some(value) => {
+                            self.r#impl.attrs.insert(#attr_str.to_string(), #to_attr);
+                        }
+                        None => {
+                            self.r#impl.attrs.remove(#attr_str);
+                        }
+                    }
+                }
+            });
+        } else {
+            let message = format!("attribute '{}' is missing or has invalid type", attr_str);
+            attr_accessors.push(quote! {
+                pub fn #typed_getter(&self) -> #ret_ty {
+                    self.r#impl
+                        .attrs
+                        .get(#attr_str)
+                        .and_then(|attr| #from_attr)
+                        .expect(#message)
+                }
+
+                pub fn #typed_setter(&mut self, value: impl Into<#arg_ty>) {
+                    let value: #arg_ty = value.into();
+                    self.r#impl.attrs.insert(#attr_str.to_string(), #to_attr);
+                }
+            });
+        }
     }
 
     quote! {
@@ -1,6 +1,9 @@
 use core::fmt;
 
-use lpl::{syntax::NodeOrToken, Span};
+use lpl::{
+    syntax::{NodeOrToken, SyntaxLike},
+    Span,
+};
 
 use crate::{SyntaxElement, SyntaxKind, SyntaxNode};
 
@@ -98,6 +101,7 @@ pub trait AttrListOwner: ASTNode {
 #[derive(Clone)]
 pub enum Type {
     Bits(u16),
+    Bool,
     String,
     Integer,
     Unresolved(SyntaxElement),
@@ -117,6 +121,7 @@ pub enum Item {
     ImplDecl(ImplDecl),
     FlagDecl(FlagDecl),
     FnDecl(FnDecl),
+    OpDecl(OpDecl),
 }
 
 #[derive(Clone)]
@@ -144,19 +149,18 @@ pub struct InstrDecl {
     syntax: SyntaxNode,
     template_args: Vec<InstrTemplateArg>,
 }
+ast_with_doc!(InstrDecl);
 
 trivial_ast_node!(InstrTemplateArg, SyntaxKind::InstrParentTemplateArg);
 
 #[derive(Clone)]
 pub struct EncodingDecl {
-    #[allow(dead_code)]
     syntax: SyntaxNode,
     body: BlockExpr,
 }
 
 #[derive(Clone)]
 pub struct AsmDecl {
-    #[allow(dead_code)]
     syntax: SyntaxNode,
     body: BlockExpr,
 }
@@ -165,7 +169,15 @@ trivial_ast_node!(ImplDecl, SyntaxKind::ImplDecl);
 ast_printer!(ImplDecl, trait_name, target_name);
 
 trivial_ast_node!(StructFieldDecl, SyntaxKind::StructField);
-ast_printer!(StructFieldDecl, name, ty);
+ast_printer!(StructFieldDecl, name, ty, attributes);
+
+#[derive(Clone)]
+pub struct OpDecl {
+    syntax: SyntaxNode,
+    fields: Vec<StructFieldDecl>,
+}
+ast_with_doc!(OpDecl);
+ast_printer!(OpDecl, name, doc, attributes, fields);
 
 #[derive(Clone)]
 pub struct EnumDecl {
@@ -188,14 +200,12 @@ pub enum Expr {
 
 #[derive(Clone)]
 pub struct LiteralExpr {
-    #[allow(dead_code)]
     syntax: SyntaxNode,
     ty: Type,
 }
 
 #[derive(Clone)]
 pub struct BlockExpr {
-    #[allow(dead_code)]
     syntax: SyntaxNode,
     stmts: Vec<Expr>,
     ty: Type,
@@ -203,7 +213,6 @@ pub struct BlockExpr {
 
 #[derive(Clone)]
 pub struct ListExpr {
-    #[allow(dead_code)]
     syntax: SyntaxNode,
     elements: Vec<Expr>,
     ty: Type,
@@ -216,7 +225,6 @@ pub enum BinOpKind {
 
 #[derive(Clone)]
 pub struct BinOpExpr {
-    #[allow(dead_code)]
     syntax: SyntaxNode,
     kind: BinOpKind,
     left: Box<Expr>,
@@ -249,6 +257,7 @@ impl Type {
 
         match ident.as_ref() {
             "str" => Some(Type::String),
+            "bool" => Some(Type::Bool),
             "bits" => {
                 let param = syntax
                     .children()
@@ -290,12 +299,24 @@ impl fmt::Debug for Type {
             Type::Unresolved(_) => write!(f, "<unresolved>"),
             Type::String => write!(f, "str"),
             Type::Bits(num) => write!(f, "bits<{}>", num),
+            Type::Bool => write!(f, "bool"),
             Type::Integer => write!(f, "int"),
             Type::Void => write!(f, "()"),
         }
     }
 }
 
+impl fmt::Display for Type {
+    /// Type as it is written in the source, e.g. `bits<7>` or the name of a register class
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Unresolved(NodeOrToken::Node(node)) => write!(f, "{}", syntax_text(node)),
+            Type::Unresolved(NodeOrToken::Token(token)) => write!(f, "{}", token.text()),
+            _ => fmt::Debug::fmt(self, f),
+        }
+    }
+}
+
 impl Item {
     pub fn name(&self) -> String {
         match self {
@@ -303,6 +324,7 @@ impl Item {
             Item::InstrTemplateDecl(instr) => instr.name(),
             Item::EnumDecl(instr) => instr.name(),
             Item::FnDecl(fn_) => fn_.signature().name(),
+            Item::OpDecl(op) => op.name(),
             _ => "unknown".to_owned(),
         }
     }
@@ -319,6 +341,7 @@ impl fmt::Debug for Item {
             Item::ImplDecl(i) => i.fmt(f),
             Item::FlagDecl(i) => i.fmt(f),
             Item::FnDecl(i) => i.fmt(f),
+            Item::OpDecl(i) => i.fmt(f),
         }
     }
 }
@@ -371,6 +394,12 @@ impl From<FnDecl> for Item {
     }
 }
 
+impl From<OpDecl> for Item {
+    fn from(i: OpDecl) -> Self {
+        Item::OpDecl(i)
+    }
+}
+
 impl SourceFile {
     pub fn new(root: SyntaxNode) -> Option<SourceFile> {
         if root.kind() != SyntaxKind::TranslationUnit {
@@ -391,6 +420,7 @@ impl SourceFile {
                     SyntaxKind::ImplDecl => ImplDecl::new(node.clone()).map(|t| t.into()),
                     SyntaxKind::FlagDecl => FlagDecl::new(node.clone()).map(|t| t.into()),
                     SyntaxKind::FnDecl => FnDecl::new(node.clone()).map(|t| t.into()),
+                    SyntaxKind::OpDecl => OpDecl::new(node.clone()).map(|t| t.into()),
                     _ => None,
                 },
                 _ => None,
@@ -559,11 +589,22 @@ impl InstrTemplateDecl {
             })
     }
 
+    /// All attributes, attached to the template, in order of appearance
+    pub fn attributes(&self) -> Vec<Attr> {
+        collect_attributes(self.syntax())
+    }
+
     pub fn parent_template_args(&self) -> std::slice::Iter<'_, InstrTemplateArg> {
         self.parent_template_args.iter()
     }
 }
 
+impl ASTNode for InstrTemplateDecl {
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
 ast_printer!(
     InstrTemplateDecl,
     name,
@@ -691,10 +732,31 @@ impl InstrDecl {
     pub fn template_args(&self) -> &[InstrTemplateArg] {
         &self.template_args
     }
+
+    /// All attributes, attached to the instruction, in order of appearance
+    pub fn attributes(&self) -> Vec<Attr> {
+        collect_attributes(self.syntax())
+    }
+}
+
+impl ASTNode for InstrDecl {
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
 }
 
 ast_printer!(InstrDecl, name, template_name, template_args);
 
+impl InstrTemplateArg {
+    /// Literal, that is passed to the template parameter
+    pub fn value(&self) -> Option<LiteralExpr> {
+        self.syntax().children().find_map(|c| match c {
+            NodeOrToken::Node(n) => LiteralExpr::new(n),
+            _ => None,
+        })
+    }
+}
+
 // FIXME remove this
 impl fmt::Debug for InstrTemplateArg {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -749,6 +811,12 @@ impl EncodingDecl {
     }
 }
 
+impl ASTNode for EncodingDecl {
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
 ast_printer!(EncodingDecl, target_name, body);
 
 impl AsmDecl {
@@ -798,6 +866,12 @@ impl AsmDecl {
     }
 }
 
+impl ASTNode for AsmDecl {
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
 ast_printer!(AsmDecl, target_name, body);
 
 impl ImplDecl {
@@ -860,12 +934,11 @@ impl ImplDecl {
 
 impl StructFieldDecl {
     pub fn name(&self) -> String {
+        // Field names can be keywords, so take the first significant token
         self.syntax()
             .children()
             .find_map(|c| match c {
-                NodeOrToken::Token(t) if t.kind() == SyntaxKind::Identifier => {
-                    Some(t.text().to_string())
-                }
+                NodeOrToken::Token(t) if !t.kind().is_trivia() => Some(t.text().to_string()),
                 _ => None,
             })
             .unwrap_or("unknown".to_string())
@@ -880,6 +953,105 @@ impl StructFieldDecl {
             })
             .unwrap()
     }
+
+    /// Source text of the field type without any trivia, e.g. `Register<GPR>`
+    pub fn ty_text(&self) -> String {
+        self.syntax()
+            .children()
+            .find_map(|c| match c {
+                NodeOrToken::Node(n) if n.kind() == SyntaxKind::Type => Some(syntax_text(&n)),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    /// All attributes, attached to the field, in order of appearance
+    pub fn attributes(&self) -> Vec<Attr> {
+        collect_attributes(self.syntax())
+    }
+}
+
+impl AttrListOwner for StructFieldDecl {}
+
+impl ASTNode for OpDecl {
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
+impl OpDecl {
+    pub fn new(syntax: SyntaxNode) -> Option<Self> {
+        if syntax.kind() != SyntaxKind::OpDecl {
+            return None;
+        }
+
+        let fields = syntax
+            .children()
+            .find_map(|c| match c {
+                NodeOrToken::Node(node) if node.kind() == SyntaxKind::StructBody => Some(node),
+                _ => None,
+            })
+            .iter()
+            .flat_map(|n| n.children())
+            .filter_map(|c| match c {
+                NodeOrToken::Node(node) if node.kind() == SyntaxKind::StructField => {
+                    StructFieldDecl::new(node)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        Some(Self { syntax, fields })
+    }
+
+    pub fn name(&self) -> String {
+        self.syntax
+            .children()
+            .find_map(|c| match c {
+                NodeOrToken::Node(node) if node.kind() == SyntaxKind::OpName => Some(node),
+                _ => None,
+            })
+            .iter()
+            .flat_map(|node| node.children())
+            .find_map(|c| match c {
+                NodeOrToken::Token(t) if t.kind() == SyntaxKind::Identifier => {
+                    Some(t.text().to_string())
+                }
+                _ => None,
+            })
+            .unwrap_or("unknown".to_string())
+    }
+
+    /// All attributes, attached to the operation, in order of appearance
+    pub fn attributes(&self) -> Vec<Attr> {
+        collect_attributes(self.syntax())
+    }
+
+    pub fn fields(&self) -> &[StructFieldDecl] {
+        &self.fields
+    }
+}
+
+fn collect_attributes(syntax: &SyntaxNode) -> Vec<Attr> {
+    syntax
+        .children()
+        .filter_map(|c| match c {
+            NodeOrToken::Node(n) => AttrList::new(n),
+            _ => None,
+        })
+        .flat_map(|list| list.attributes().collect::<Vec<_>>())
+        .collect()
+}
+
+fn syntax_text(syntax: &SyntaxNode) -> String {
+    syntax
+        .children()
+        .map(|c| match c {
+            NodeOrToken::Node(n) => syntax_text(&n),
+            NodeOrToken::Token(t) if t.kind().is_trivia() => String::new(),
+            NodeOrToken::Token(t) => t.text().to_string(),
+        })
+        .collect()
 }
 
 ast_printer!(EnumDecl, name, doc, variants);
@@ -1015,6 +1187,17 @@ impl ExprNode for Expr {
     }
 }
 
+impl ASTNode for Expr {
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expr::Literal(l) => &l.syntax,
+            Expr::Block(b) => &b.syntax,
+            Expr::BinOp(b) => &b.syntax,
+            Expr::List(l) => &l.syntax,
+        }
+    }
+}
+
 impl fmt::Debug for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1066,6 +1249,94 @@ impl LiteralExpr {
             })
             .unwrap()
     }
+
+    /// Value of an integer literal, including the optional leading minus
+    pub fn int_value(&self) -> Option<i64> {
+        let mut negative = false;
+        for c in self.syntax.children() {
+            match c {
+                NodeOrToken::Token(t) if t.kind() == SyntaxKind::Minus => negative = true,
+                NodeOrToken::Token(t) if t.kind() == SyntaxKind::IntegerLiteral => {
+                    let value = t.text().parse::<i64>().ok()?;
+                    return Some(if negative { -value } else { value });
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Name of an identifier literal
+    pub fn ident(&self) -> Option<String> {
+        self.syntax.children().find_map(|c| match c {
+            NodeOrToken::Token(t) if t.kind() == SyntaxKind::Identifier => {
+                Some(t.text().to_string())
+            }
+            _ => None,
+        })
+    }
+
+    /// Value of a bit literal, i.e. `0b0110011`
+    pub fn bits_value(&self) -> Option<u64> {
+        self.syntax.children().find_map(|c| match c {
+            NodeOrToken::Token(t) if t.kind() == SyntaxKind::BitLiteral => {
+                u64::from_str_radix(&t.text()[2..], 2).ok()
+            }
+            _ => None,
+        })
+    }
+
+    /// Name of the field in a `self.field` expression
+    pub fn self_field(&self) -> Option<String> {
+        let field = self.syntax.children().find_map(|c| match c {
+            NodeOrToken::Node(n) if n.kind() == SyntaxKind::FieldExpr => Some(n),
+            _ => None,
+        })?;
+        let tokens = field
+            .children()
+            .filter_map(|c| match c {
+                NodeOrToken::Token(t) if !t.kind().is_trivia() => Some(t),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        match &tokens[..] {
+            [base, _, name] if base.kind() == SyntaxKind::SelfKw => Some(name.text().to_string()),
+            _ => None,
+        }
+    }
+
+    /// Highest and lowest bits of the field in a `self.field[hi:lo]` expression. A single
+    /// bit, `self.field[hi]`, has the same highest and lowest bit.
+    pub fn bit_range(&self) -> Option<(u16, u16)> {
+        let range = self
+            .syntax
+            .children()
+            .find_map(|c| match c {
+                NodeOrToken::Node(n) if n.kind() == SyntaxKind::FieldExpr => Some(n),
+                _ => None,
+            })?
+            .children()
+            .find_map(|c| match c {
+                NodeOrToken::Node(n) if n.kind() == SyntaxKind::BitRange => Some(n),
+                _ => None,
+            })?;
+        let bits = range
+            .children()
+            .filter_map(|c| match c {
+                NodeOrToken::Token(t) if t.kind() == SyntaxKind::IntegerLiteral => {
+                    t.text().parse::<u16>().ok()
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        match bits[..] {
+            [hi, lo] => Some((hi, lo)),
+            [bit] => Some((bit, bit)),
+            _ => None,
+        }
+    }
 }
 
 impl ExprNode for LiteralExpr {
@@ -1074,6 +1345,12 @@ impl ExprNode for LiteralExpr {
     }
 }
 
+impl ASTNode for LiteralExpr {
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
 ast_printer!(LiteralExpr, ty);
 
 impl ExprNode for BlockExpr {
@@ -1097,6 +1374,10 @@ impl BlockExpr {
 
         Some(Self { syntax, stmts, ty })
     }
+
+    pub fn stmts(&self) -> &[Expr] {
+        &self.stmts
+    }
 }
 
 impl fmt::Debug for BlockExpr {
@@ -1195,6 +1476,18 @@ impl BinOpExpr {
             right,
         })
     }
+
+    pub fn kind(&self) -> &BinOpKind {
+        &self.kind
+    }
+
+    pub fn left(&self) -> &Expr {
+        &self.left
+    }
+
+    pub fn right(&self) -> &Expr {
+        &self.right
+    }
 }
 
 impl fmt::Debug for BinOpExpr {
@@ -4,7 +4,9 @@ use std::{fs, io};
 
 use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
 
-use crate::{ast, emit_rust, lex, parse, SyntaxNodeData};
+use lpl::{DiagnosticLike, Span};
+
+use crate::{analyze, ast, emit_rust, lex, parse, DiagKind, ImmElement, SyntaxNodeData};
 
 pub struct Compiler {
     action: Action,
@@ -39,7 +41,9 @@ pub enum Action {
 pub struct Cli {
     #[arg(value_enum, long)]
     pub action: Action,
-    pub input: String,
+    /// Input files. Declarations of each one are visible from the others.
+    #[arg(required = true)]
+    pub inputs: Vec<String>,
     #[arg(short, long)]
     pub output: String,
     #[arg(short, long)]
@@ -57,7 +61,29 @@ impl Compiler {
     }
 
     pub fn compile(&self) -> Result<(), Box<dyn std::error::Error>> {
-        for input in &self.inputs {
+        let sources = self
+            .inputs
+            .iter()
+            .map(std::fs::read_to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // All inputs form a single unit, so that instructions can refer to the
+        // registers of another file
+        let units = match &self.action {
+            Action::EmitRust => self
+                .inputs
+                .iter()
+                .zip(&sources)
+                .map(|(input, source)| {
+                    let tokens = lex_source(input, source)?;
+                    let root = parse(&tokens);
+                    Ok(ast::SourceFile::new(SyntaxNodeData::new(root)).unwrap())
+                })
+                .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?,
+            _ => vec![],
+        };
+
+        for (idx, (input, source)) in self.inputs.iter().zip(&sources).enumerate() {
             let mut output: Box<dyn Write> = match &self.output {
                 OutputKind::Stdout => Box::new(io::BufWriter::new(io::stdout())),
                 OutputKind::File(path) => {
@@ -83,20 +109,18 @@ impl Compiler {
                 }
             };
 
-            let source = std::fs::read_to_string(input)?;
-
             match &self.action {
                 Action::EmitTokens => {
-                    let tokens = lex(&source).unwrap();
+                    let tokens = lex_source(input, source)?;
                     writeln!(output, "{:#?}", tokens)?;
                 }
                 Action::EmitSyntaxTree => {
-                    let tokens = lex(&source).unwrap();
+                    let tokens = lex_source(input, source)?;
                     let root = parse(&tokens);
                     writeln!(output, "{:#?}", root)?;
                 }
                 Action::EmitAst => {
-                    let tokens = lex(&source).unwrap();
+                    let tokens = lex_source(input, source)?;
                     let root = parse(&tokens);
                     let red_root = SyntaxNodeData::new(root);
                     let translation_unit = ast::SourceFile::new(red_root);
@@ -111,15 +135,41 @@ impl Compiler {
                         )
                         .exit();
                     }
-                    let tokens = lex(&source).unwrap();
-                    let root = parse(&tokens);
-                    let red_root = SyntaxNodeData::new(root);
-                    let translation_unit = ast::SourceFile::new(red_root);
+                    let imports = units
+                        .iter()
+                        .enumerate()
+                        .filter(|(other, _)| *other != idx)
+                        .map(|(_, unit)| unit)
+                        .collect::<Vec<_>>();
+
+                    let diags = analyze(&units[idx], &imports);
+                    if !diags.is_empty() {
+                        for diag in &diags {
+                            eprintln!(
+                                "{}",
+                                diagnostic_message(input, source, diag.span(), &diag.message())
+                            );
+                        }
+                        return Err(format!(
+                            "could not compile '{}' due to {} errors",
+                            input,
+                            diags.len()
+                        )
+                        .into());
+                    }
+
                     emit_rust(
                         &mut output,
-                        &translation_unit.unwrap(),
+                        &units[idx],
+                        &imports,
                         self.dialect.as_ref().unwrap(),
-                    )?;
+                    )
+                    .map_err(|err| match err.downcast_ref::<DiagKind>() {
+                        Some(diag) => {
+                            diagnostic_message(input, source, diag.span(), &diag.message()).into()
+                        }
+                        None => err,
+                    })?;
                 }
             }
         }
@@ -178,6 +228,19 @@ impl CompilerBuilder {
     }
 }
 
+fn lex_source(input: &str, source: &str) -> Result<Vec<ImmElement>, Box<dyn std::error::Error>> {
+    lex(source).map_err(|err| diagnostic_message(input, source, err.span(), &err.message()).into())
+}
+
+/// Formats the diagnostic as `file:line:column: message`
+fn diagnostic_message(input: &str, source: &str, span: Span, message: &str) -> String {
+    let offset = span.get_offset_start().min(source.len());
+    let line_start = source[..offset].rfind('\n').map_or(0, |pos| pos + 1);
+    let line = source[..offset].matches('\n').count() + 1;
+    let column = source[line_start..offset].chars().count() + 1;
+    format!("{}:{}:{}: {}", input, line, column, message)
+}
+
 pub fn compiler_main(args: Option<&ArgMatches>) -> Result<(), Box<dyn std::error::Error>> {
     let args = match args {
         Some(args) => Cli::from_arg_matches(args),
@@ -188,9 +251,13 @@ pub fn compiler_main(args: Option<&ArgMatches>) -> Result<(), Box<dyn std::error
         "-" => OutputKind::Stdout,
         _ => OutputKind::File(args.output.clone()),
     };
-    let compiler = Compiler::builder()
+    let compiler = args
+        .inputs
+        .iter()
+        .fold(Compiler::builder(), |builder, input| {
+            builder.add_input(input)
+        })
         .action(args.action)
-        .add_input(&args.input)
         .dialect(args.dialect.clone())
         .output(output)
         .build();
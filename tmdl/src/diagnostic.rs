@@ -11,6 +11,40 @@ pub enum DiagKind {
     TokenNotFound(SyntaxKind, Span),
     #[error("Expected tokens not found")]
     MultipleTokensNotFound(Span),
+    #[error("Encoding of '{0}' overlaps with the encoding of '{1}'")]
+    OverlappingEncodings(String, String, Span),
+    #[error("Unknown instruction template '{0}'")]
+    UnknownTemplate(String, Span),
+    #[error("Instruction template '{0}' derives from itself")]
+    RecursiveTemplate(String, Span),
+    #[error("Template '{0}' takes {1} arguments, got {2}")]
+    TemplateArgCount(String, usize, usize, Span),
+    #[error("Parameter '{0}' of template '{1}' has type '{2}', got '{3}'")]
+    TemplateArgType(String, String, String, String, Span),
+    #[error("Unknown template parameter '{0}'")]
+    UnknownParameter(String, Span),
+    #[error("Unknown type '{0}'")]
+    UnknownType(String, Span),
+    #[error("Unknown register class '{0}'")]
+    UnknownRegisterClass(String, Span),
+    #[error("Field '{0}' must be a register class, bits or bool, got '{1}'")]
+    InvalidFieldType(String, String, Span),
+    #[error("Unknown field '{0}' of '{1}'")]
+    UnknownField(String, String, Span),
+    #[error("Field '{0}' is encoded more than once")]
+    FieldEncodedTwice(String, Span),
+    #[error("Field '{0}' has no bits [{1}:{2}]")]
+    InvalidBitRange(String, u32, u32, Span),
+    #[error("Register field '{0}' can not be sliced")]
+    SlicedRegister(String, Span),
+    #[error("Field '{0}' of '{1}' is not encoded")]
+    FieldNotEncoded(String, String, Span),
+    #[error("Only bits and fields can be encoded")]
+    NotEncodable(Span),
+    #[error("Register '{0}' must have a list of names in #[reg_names]")]
+    MissingRegNames(String, Span),
+    #[error("Encoding of '{0}' is {1} bits wide, instructions must be whole bytes up to 64 bits")]
+    EncodingWidth(String, u32, Span),
 }
 
 impl DiagnosticLike for DiagKind {
@@ -23,6 +57,23 @@ impl DiagnosticLike for DiagKind {
             DiagKind::UnexpectedEof(span) => span.clone(),
             DiagKind::TokenNotFound(_, span) => span.clone(),
             DiagKind::MultipleTokensNotFound(span) => span.clone(),
+            DiagKind::OverlappingEncodings(_, _, span) => span.clone(),
+            DiagKind::UnknownTemplate(_, span) => span.clone(),
+            DiagKind::RecursiveTemplate(_, span) => span.clone(),
+            DiagKind::TemplateArgCount(_, _, _, span) => span.clone(),
+            DiagKind::TemplateArgType(_, _, _, _, span) => span.clone(),
+            DiagKind::UnknownParameter(_, span) => span.clone(),
+            DiagKind::UnknownType(_, span) => span.clone(),
+            DiagKind::UnknownRegisterClass(_, span) => span.clone(),
+            DiagKind::InvalidFieldType(_, _, span) => span.clone(),
+            DiagKind::UnknownField(_, _, span) => span.clone(),
+            DiagKind::FieldEncodedTwice(_, span) => span.clone(),
+            DiagKind::InvalidBitRange(_, _, _, span) => span.clone(),
+            DiagKind::SlicedRegister(_, span) => span.clone(),
+            DiagKind::FieldNotEncoded(_, _, span) => span.clone(),
+            DiagKind::NotEncodable(span) => span.clone(),
+            DiagKind::MissingRegNames(_, span) => span.clone(),
+            DiagKind::EncodingWidth(_, _, span) => span.clone(),
         }
     }
 
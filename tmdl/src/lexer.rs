@@ -14,12 +14,10 @@ use crate::{SyntaxKind, Token, TokenData};
 ///
 /// This function takes a string input and attempts to tokenize it using various lexing functions.
 /// It processes the input in the following order:
-/// 1. Keywords
-/// 2. Punctuation
-/// 3. Built-in types
-/// 4. Operators
-/// 5. Identifiers
-/// 6. Comments
+/// 1. Literals
+/// 2. Identifiers and keywords
+/// 3. Punctuation
+/// 4. Comments
 ///
 /// # Arguments
 ///
@@ -32,8 +30,7 @@ use crate::{SyntaxKind, Token, TokenData};
 pub fn lex(input: &str) -> Result<Vec<GreenElement<SyntaxKind>>, Diagnostic> {
     let stream: StrStream = input.into();
 
-    let token = lex_keyword()
-        .or_else(lex_bit_literal())
+    let token = lex_bit_literal()
         .or_else(lex_string_literal())
         .or_else(lex_identifier())
         .or_else(lex_punctuation())
@@ -62,21 +59,24 @@ fn lex_bit_literal<'a>() -> impl Parser<'a, StrStream<'a>, Token> {
         .map(|(prefix, bits)| TokenData::new(SyntaxKind::BitLiteral, format!("{}{}", prefix, bits)))
 }
 
-fn lex_keyword<'a>() -> impl Parser<'a, StrStream<'a>, Token> {
-    literal("instr_template")
-        .map(|kw| TokenData::new(SyntaxKind::InstrTemplateKw, kw.to_owned()))
-        .or_else(
-            literal("encoding").map(|kw| TokenData::new(SyntaxKind::EncodingKw, kw.to_owned())),
-        )
-        .or_else(literal("asm").map(|kw| TokenData::new(SyntaxKind::AsmKw, kw.to_owned())))
-        .or_else(literal("instr").map(|kw| TokenData::new(SyntaxKind::InstrKw, kw.to_owned())))
-        .or_else(literal("for").map(|kw| TokenData::new(SyntaxKind::ForKw, kw.to_owned())))
-        .or_else(literal("let").map(|kw| TokenData::new(SyntaxKind::LetKw, kw.to_owned())))
-        .or_else(literal("enum").map(|kw| TokenData::new(SyntaxKind::EnumKw, kw.to_owned())))
-        .or_else(literal("impl").map(|kw| TokenData::new(SyntaxKind::ImplKw, kw.to_owned())))
-        .or_else(literal("self").map(|kw| TokenData::new(SyntaxKind::SelfKw, kw.to_owned())))
-        .or_else(literal("flag").map(|kw| TokenData::new(SyntaxKind::FlagKw, kw.to_owned())))
-        .or_else(literal("fn").map(|kw| TokenData::new(SyntaxKind::FnKw, kw.to_owned())))
+/// Maps identifier-like words to keyword tokens. Keywords are only recognized as whole words,
+/// so that identifiers like `opcode` or `format` are not split into a keyword and a suffix.
+fn keyword_kind(word: &str) -> Option<SyntaxKind> {
+    match word {
+        "instr_template" => Some(SyntaxKind::InstrTemplateKw),
+        "encoding" => Some(SyntaxKind::EncodingKw),
+        "asm" => Some(SyntaxKind::AsmKw),
+        "instr" => Some(SyntaxKind::InstrKw),
+        "for" => Some(SyntaxKind::ForKw),
+        "let" => Some(SyntaxKind::LetKw),
+        "enum" => Some(SyntaxKind::EnumKw),
+        "impl" => Some(SyntaxKind::ImplKw),
+        "self" => Some(SyntaxKind::SelfKw),
+        "flag" => Some(SyntaxKind::FlagKw),
+        "fn" => Some(SyntaxKind::FnKw),
+        "op" => Some(SyntaxKind::OpKw),
+        _ => None,
+    }
 }
 
 fn lex_punctuation<'a>() -> impl Parser<'a, StrStream<'a>, Token> {
@@ -102,7 +102,10 @@ fn lex_punctuation<'a>() -> impl Parser<'a, StrStream<'a>, Token> {
 }
 
 fn lex_identifier<'a>() -> impl Parser<'a, StrStream<'a>, Token> {
-    ident(|c| c == '_' || c == '$').map(|i| TokenData::new(SyntaxKind::Identifier, i.to_string()))
+    ident(|c| c == '_' || c == '$').map(|i| {
+        let kind = keyword_kind(i).unwrap_or(SyntaxKind::Identifier);
+        TokenData::new(kind, i.to_string())
+    })
 }
 
 fn lex_comment<'a>() -> impl Parser<'a, StrStream<'a>, Token> {
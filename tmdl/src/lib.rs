@@ -4,6 +4,7 @@ mod diagnostic;
 mod lexer;
 mod parser;
 mod rustgen;
+mod sema;
 mod syntax_kind;
 
 pub use compiler::*;
@@ -11,4 +12,5 @@ pub use diagnostic::*;
 pub use lexer::*;
 pub use parser::*;
 pub use rustgen::*;
+pub use sema::*;
 pub use syntax_kind::*;
@@ -19,7 +19,8 @@ pub fn parse(tokens: &[ImmElement]) -> ImmNode {
         .or_else(enum_())
         .or_else(impl_())
         .or_else(flag())
-        .or_else(func_decl());
+        .or_else(func_decl())
+        .or_else(op_decl());
     let parser = zero_or_more(top_level_decl.map(NodeOrToken::Node).or_else(catch_all()));
 
     let result = parser.parse(stream).unwrap();
@@ -69,25 +70,30 @@ fn catch_all<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
 }
 
 fn attr<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
-    let reg_names = just_token("reg_names")
-        .and_then(just_token(SyntaxKind::LeftParen))
-        .and_then(inline_expr())
-        .and_then(just_token(SyntaxKind::RightParen))
+    let args = just_token(SyntaxKind::LeftParen)
+        .and_then(separated(inline_expr(), just_token(SyntaxKind::Comma)))
+        .and_then(token(SyntaxKind::RightParen))
         .flat()
-        .map(|(attr_name, left_paren, expr, right_paren)| {
+        .label("attr arguments");
+
+    token(SyntaxKind::Identifier)
+        .and_then(optional(args))
+        .map(|(attr_name, args)| {
             let mut elements = vec![];
 
-            let span = attr_name.as_token().span();
-            elements.push(attr_name);
-            elements.push(left_paren);
-            elements.push(expr);
-            elements.push(right_paren);
+            let span = attr_name.token().as_token().span();
+            elements.extend(attr_name.trivia().iter().cloned());
+            elements.push(attr_name.token().clone());
+            if let Some((left_paren, exprs, right_paren)) = args {
+                elements.push(left_paren);
+                elements.extend(exprs);
+                elements.extend(right_paren.trivia().iter().cloned());
+                elements.push(right_paren.token().clone());
+            }
 
             NodeOrToken::Node(GreenNodeData::new(SyntaxKind::Attr, elements, span))
         })
-        .label("reg_names");
-
-    reg_names.label("attr")
+        .label("attr")
 }
 
 fn attr_list<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
@@ -118,36 +124,41 @@ fn attr_list<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
 fn instr_template_decl<'a>() -> impl Parser<'a, TokenStream<'a>, ImmNode> {
     let parent_template = token(SyntaxKind::Colon).and_then(template_instantiation());
     attached_comment()
-        .and_then(just_token(SyntaxKind::InstrTemplateKw))
+        .and_then(zero_or_more(attr_list()))
+        .and_then(token(SyntaxKind::InstrTemplateKw))
         .and_then(token(SyntaxKind::Identifier))
         .and_then(instr_template_parameters())
         .and_then(optional(parent_template))
         .and_then(trivia())
         .and_then(struct_body())
         .flat()
-        .map(|(comment, kw, name, params, parent, aliens, body)| {
-            let mut elements = comment;
+        .map(
+            |(comment, attr_lists, kw, name, params, parent, aliens, body)| {
+                let mut elements = comment;
 
-            let kw_span = kw.as_token().span();
-            elements.push(kw);
-            elements.extend(name.trivia().iter().cloned());
-            let name_span = name.token().as_token().span();
-            elements.push(GreenElement::Node(GreenNodeData::new(
-                SyntaxKind::InstrTemplateName,
-                vec![name.token().clone()],
-                name_span,
-            )));
-            elements.push(NodeOrToken::Node(params));
-            if let Some((colon, parent_inst)) = parent {
-                elements.extend(colon.trivia().iter().cloned());
-                elements.push(colon.token().clone());
-                elements.push(parent_inst);
-            }
-            elements.extend(aliens);
-            elements.push(NodeOrToken::Node(body));
+                elements.extend(attr_lists);
+                let kw_span = kw.token().as_token().span();
+                elements.extend(kw.trivia().iter().cloned());
+                elements.push(kw.token().clone());
+                elements.extend(name.trivia().iter().cloned());
+                let name_span = name.token().as_token().span();
+                elements.push(GreenElement::Node(GreenNodeData::new(
+                    SyntaxKind::InstrTemplateName,
+                    vec![name.token().clone()],
+                    name_span,
+                )));
+                elements.push(NodeOrToken::Node(params));
+                if let Some((colon, parent_inst)) = parent {
+                    elements.extend(colon.trivia().iter().cloned());
+                    elements.push(colon.token().clone());
+                    elements.push(parent_inst);
+                }
+                elements.extend(aliens);
+                elements.push(NodeOrToken::Node(body));
 
-            GreenNodeData::new(SyntaxKind::InstrTemplateDecl, elements, kw_span)
-        })
+                GreenNodeData::new(SyntaxKind::InstrTemplateDecl, elements, kw_span)
+            },
+        )
         .label("instr_template_decl")
 }
 
@@ -158,6 +169,7 @@ fn ty<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
                 SyntaxKind::IntegerLiteral,
                 SyntaxKind::StringLiteral,
                 SyntaxKind::BitLiteral,
+                SyntaxKind::Identifier,
             ]))
             .and_then(just_token(SyntaxKind::RightAngle))
             .flat()
@@ -262,14 +274,31 @@ fn instr_template_parameters<'a>() -> impl Parser<'a, TokenStream<'a>, ImmNode>
 }
 
 fn struct_field<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
-    token(SyntaxKind::Identifier)
+    // Field name is always followed by a colon, so keywords are unambiguous here
+    // and can be used as names, e.g. `asm: str`.
+    let name = token([
+        SyntaxKind::Identifier,
+        SyntaxKind::InstrTemplateKw,
+        SyntaxKind::EncodingKw,
+        SyntaxKind::AsmKw,
+        SyntaxKind::ForKw,
+        SyntaxKind::LetKw,
+        SyntaxKind::InstrKw,
+        SyntaxKind::EnumKw,
+        SyntaxKind::ImplKw,
+        SyntaxKind::FnKw,
+        SyntaxKind::FlagKw,
+        SyntaxKind::OpKw,
+    ]);
+    zero_or_more(attr_list())
+        .and_then(name)
         .and_then(token(SyntaxKind::Colon))
         .and_then(ty())
         .and_then(trivia())
         .flat()
-        .map(|(name, colon, ty, aliens)| {
+        .map(|(attr_lists, name, colon, ty, aliens)| {
             let span = name.token().as_token().span();
-            let mut elements = vec![];
+            let mut elements = attr_lists;
             elements.extend(name.trivia().iter().cloned());
             elements.push(name.token().clone());
             elements.extend(colon.trivia().iter().cloned());
@@ -363,6 +392,25 @@ fn asm_<'a>() -> impl Parser<'a, TokenStream<'a>, ImmNode> {
 
 fn inline_expr<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
     recursive(|inline_expr| {
+        let negative_literal = move || {
+            token(SyntaxKind::Minus)
+                .and_then(just_token(SyntaxKind::IntegerLiteral))
+                .map(|(minus, lit)| {
+                    let mut elements = vec![];
+
+                    elements.extend(minus.trivia().iter().cloned());
+                    elements.push(minus.token().clone());
+                    elements.push(lit);
+
+                    NodeOrToken::Node(GreenNodeData::new(
+                        SyntaxKind::LiteralExpr,
+                        elements,
+                        minus.token().as_token().span(),
+                    ))
+                })
+                .label("negative literal")
+        };
+
         let literal_expr = move || {
             token(SyntaxKind::BitLiteral)
                 .or_else(token(SyntaxKind::StringLiteral))
@@ -411,14 +459,16 @@ fn inline_expr<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
             token([SyntaxKind::Identifier, SyntaxKind::SelfKw])
                 .and_then(just_token(SyntaxKind::Dot))
                 .and_then(just_token(SyntaxKind::Identifier))
+                .and_then(optional(bit_range()))
                 .flat()
-                .map(|(left, dot, right)| {
+                .map(|(left, dot, right, range)| {
                     let span = dot.as_token().span();
                     let mut elements = vec![];
                     elements.extend(left.trivia().iter().cloned());
                     elements.push(left.token().clone());
                     elements.push(dot);
                     elements.push(right);
+                    elements.extend(range);
                     let node = NodeOrToken::Node(GreenNodeData::new(
                         SyntaxKind::FieldExpr,
                         elements,
@@ -433,7 +483,11 @@ fn inline_expr<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
                 .label("field expr")
         };
 
-        let atom = move || field_expr().or_else(literal_expr());
+        let atom = move || {
+            field_expr()
+                .or_else(negative_literal())
+                .or_else(literal_expr())
+        };
 
         let bit_concat = fold_left(atom(), token(SyntaxKind::At), |left, op, right| {
             let span = op.token().as_token().span();
@@ -465,6 +519,29 @@ fn inline_expr<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
     .label("inline expr")
 }
 
+/// Bits of a field, `[11:5]`, or a single bit, `[11]`
+fn bit_range<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
+    let lo = just_token(SyntaxKind::Colon).and_then(just_token(SyntaxKind::IntegerLiteral));
+
+    just_token(SyntaxKind::LeftBracket)
+        .and_then(just_token(SyntaxKind::IntegerLiteral))
+        .and_then(optional(lo))
+        .and_then(just_token(SyntaxKind::RightBracket))
+        .flat()
+        .map(|(left_bracket, hi, lo, right_bracket)| {
+            let span = left_bracket.as_token().span();
+            let mut elements = vec![left_bracket, hi];
+            if let Some((colon, lo)) = lo {
+                elements.push(colon);
+                elements.push(lo);
+            }
+            elements.push(right_bracket);
+
+            NodeOrToken::Node(GreenNodeData::new(SyntaxKind::BitRange, elements, span))
+        })
+        .label("bit range")
+}
+
 fn expr<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
     recursive(
         |expr: Recursive<'a, _, ImmElement, dyn Parser<'a, _, ImmElement> + 'a>| {
@@ -519,32 +596,37 @@ fn expr<'a>() -> impl Parser<'a, TokenStream<'a>, ImmElement> {
 
 fn instr_decl<'a>() -> impl Parser<'a, TokenStream<'a>, ImmNode> {
     attached_comment()
-        .and_then(just_token(SyntaxKind::InstrKw))
+        .and_then(zero_or_more(attr_list()))
+        .and_then(token(SyntaxKind::InstrKw))
         .and_then(token(SyntaxKind::Identifier))
         .and_then(token(SyntaxKind::Colon))
         .and_then(template_instantiation())
         .and_then(token(SyntaxKind::Semicolon))
         .flat()
-        .map(|(comment, instr_kw, name, colon, template, semicolon)| {
-            let mut elements = comment;
+        .map(
+            |(comment, attr_lists, instr_kw, name, colon, template, semicolon)| {
+                let mut elements = comment;
 
-            let span = instr_kw.as_token().span();
-            elements.push(instr_kw);
-            let name_span = name.token().as_token().span();
-            elements.extend(name.trivia().iter().cloned());
-            elements.push(NodeOrToken::Node(GreenNodeData::new(
-                SyntaxKind::InstrName,
-                vec![name.token().clone()],
-                name_span,
-            )));
-            elements.extend(colon.trivia().iter().cloned());
-            elements.push(colon.token().clone());
-            elements.push(template);
-            elements.extend(semicolon.trivia().iter().cloned());
-            elements.push(semicolon.token().clone());
+                elements.extend(attr_lists);
+                let span = instr_kw.token().as_token().span();
+                elements.extend(instr_kw.trivia().iter().cloned());
+                elements.push(instr_kw.token().clone());
+                let name_span = name.token().as_token().span();
+                elements.extend(name.trivia().iter().cloned());
+                elements.push(NodeOrToken::Node(GreenNodeData::new(
+                    SyntaxKind::InstrName,
+                    vec![name.token().clone()],
+                    name_span,
+                )));
+                elements.extend(colon.trivia().iter().cloned());
+                elements.push(colon.token().clone());
+                elements.push(template);
+                elements.extend(semicolon.trivia().iter().cloned());
+                elements.push(semicolon.token().clone());
 
-            GreenNodeData::new(SyntaxKind::InstrDecl, elements, span)
-        })
+                GreenNodeData::new(SyntaxKind::InstrDecl, elements, span)
+            },
+        )
         .label("instr decl")
 }
 
@@ -893,3 +975,33 @@ fn func_decl<'a>() -> impl Parser<'a, TokenStream<'a>, ImmNode> {
         })
         .label("function declaration")
 }
+
+fn op_decl<'a>() -> impl Parser<'a, TokenStream<'a>, ImmNode> {
+    attached_comment()
+        .and_then(zero_or_more(attr_list()))
+        .and_then(token(SyntaxKind::OpKw))
+        .and_then(token(SyntaxKind::Identifier))
+        .and_then(trivia())
+        .and_then(struct_body())
+        .flat()
+        .map(|(comment, attr_lists, kw, name, aliens, body)| {
+            let mut elements = comment;
+
+            elements.extend(attr_lists);
+            let span = kw.token().as_token().span();
+            elements.extend(kw.trivia().iter().cloned());
+            elements.push(kw.token().clone());
+            elements.extend(name.trivia().iter().cloned());
+            let name_span = name.token().as_token().span();
+            elements.push(NodeOrToken::Node(GreenNodeData::new(
+                SyntaxKind::OpName,
+                vec![name.token().clone()],
+                name_span,
+            )));
+            elements.extend(aliens);
+            elements.push(NodeOrToken::Node(body));
+
+            GreenNodeData::new(SyntaxKind::OpDecl, elements, span)
+        })
+        .label("op declaration")
+}
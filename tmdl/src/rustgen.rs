@@ -1,50 +1,39 @@
+use lpl::Span;
 use quote::{format_ident, quote};
-use std::{collections::HashMap, io::Write};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::Write,
+};
 
-use crate::ast::{self, AttrListOwner};
+use crate::ast::{self, ASTNode, AttrListOwner, ExprNode};
+use crate::sema::{encoding_bits, encoding_terms};
+use crate::DiagKind;
 
+/// Emits Rust code for the items of the source file. Declarations of the imported files
+/// can be referred to, but no code is emitted for them.
 pub fn emit_rust<'a>(
     buf: &mut dyn Write,
     ast: &'a ast::SourceFile,
+    imports: &[&'a ast::SourceFile],
     dialect_name: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut items = HashMap::<String, &'a ast::Item>::new();
     let mut impls = HashMap::<String, Vec<&'a ast::Item>>::new();
 
-    for i in ast.items() {
+    for i in imports
+        .iter()
+        .flat_map(|unit| unit.items())
+        .chain(ast.items())
+    {
         match i {
             ast::Item::AsmDecl(decl) => {
-                let name = decl.target_name();
-                let vec = if impls.contains_key(&name) {
-                    impls.get_mut(&name).unwrap()
-                } else {
-                    let vec = vec![];
-                    impls.insert(name.clone(), vec);
-                    impls.get_mut(&name).unwrap()
-                };
-                vec.push(i);
+                impls.entry(decl.target_name()).or_default().push(i);
             }
             ast::Item::EncodingDecl(decl) => {
-                let name = decl.target_name();
-                let vec = if impls.contains_key(&name) {
-                    impls.get_mut(&name).unwrap()
-                } else {
-                    let vec = vec![];
-                    impls.insert(name.clone(), vec);
-                    impls.get_mut(&name).unwrap()
-                };
-                vec.push(i);
+                impls.entry(decl.target_name()).or_default().push(i);
             }
             ast::Item::ImplDecl(decl) => {
-                let name = decl.target_name();
-                let vec = if impls.contains_key(&name) {
-                    impls.get_mut(&name).unwrap()
-                } else {
-                    let vec = vec![];
-                    impls.insert(name.clone(), vec);
-                    impls.get_mut(&name).unwrap()
-                };
-                vec.push(i);
+                impls.entry(decl.target_name()).or_default().push(i);
             }
             _ => {
                 items.insert(i.name(), i);
@@ -52,17 +41,31 @@ pub fn emit_rust<'a>(
         }
     }
 
-    let rust_items: Vec<_> = ast
+    let mut patterns = vec![];
+    let mut rust_items = ast
         .items()
         .filter_map(|item| match item {
-            ast::Item::FlagDecl(ref flag) => Some(generate_flag(flag)),
-            ast::Item::EnumDecl(ref enum_) => Some(generate_enum(&impls, enum_)),
-            ast::Item::InstrDecl(ref instr) => Some(generate_instr(&items, instr, dialect_name)),
+            ast::Item::FlagDecl(ref flag) => Some(Ok(generate_flag(flag))),
+            ast::Item::EnumDecl(ref enum_) => {
+                Some(generate_enum(&impls, enum_).map_err(Into::into))
+            }
+            ast::Item::InstrDecl(ref instr) => Some(
+                generate_instr(&items, &impls, instr, dialect_name).map(|(tokens, pattern)| {
+                    patterns.extend(pattern);
+                    tokens
+                }),
+            ),
+            ast::Item::OpDecl(ref op) => Some(generate_op(op, dialect_name)),
             _ => None,
         })
-        .collect();
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let file: syn::File = syn::parse2(quote! { #(#rust_items)* }).unwrap();
+    if !patterns.is_empty() {
+        rust_items.push(generate_decoder(&patterns)?);
+    }
+
+    let file: syn::File = syn::parse2(quote! { #(#rust_items)* })
+        .map_err(|err| format!("generated code is not valid Rust: {}", err))?;
 
     writeln!(buf, "{}", prettyplease::unparse(&file))?;
 
@@ -77,16 +80,21 @@ fn generate_flag(decl: &ast::FlagDecl) -> proc_macro2::TokenStream {
         quote! {}
     };
 
+    let flag_name = decl.name();
+
     quote! {
         #doc
         pub struct #name;
+        impl tir_backend::TargetFlag for #name {
+            const NAME: &'static str = #flag_name;
+        }
     }
 }
 
 fn generate_enum<'a>(
     impls: &'a HashMap<String, Vec<&'a ast::Item>>,
     decl: &ast::EnumDecl,
-) -> proc_macro2::TokenStream {
+) -> Result<proc_macro2::TokenStream, DiagKind> {
     let variants = decl.variants().iter().map(|v| {
         let ident = format_ident!("{}", v.name());
         let doc = if let Some(doc) = v.doc() {
@@ -108,141 +116,136 @@ fn generate_enum<'a>(
         quote! {}
     };
 
-    let impl_tokens = impls.get(&decl.name()).into_iter().flatten().filter_map(
-        |impl_| -> Option<proc_macro2::TokenStream> {
-            if let ast::Item::ImplDecl(impl_) = impl_ {
-                if impl_.trait_name() == "Register" {
-                    let names_arms = decl.variants().iter().map(|v| {
-                        let attr_list = v.attr_list().unwrap().clone();
-                        let list_expr = attr_list
-                            .attributes()
-                            .find(|v| v.name() == "reg_names")
-                            .unwrap()
-                            .exprs()
-                            .next()
-                            .unwrap()
-                            .as_list();
-                        let names = list_expr.elements().iter().map(|el| {
-                            let text = el.as_literal().text();
-                            text[1..text.len() - 1].to_string()
-                        });
-                        let ident = format_ident!("{}", v.name());
-                        quote! {
-                            #name::#ident => &[#(#names),*]
-                        }
-                    });
+    let mut impl_tokens = vec![];
+    for impl_ in impls.get(&decl.name()).into_iter().flatten() {
+        let ast::Item::ImplDecl(impl_) = impl_ else {
+            continue;
+        };
+        if impl_.trait_name() != "Register" {
+            continue;
+        }
 
-                    let parser_arms = decl.variants().iter().map(|v| {
-                        let attr_list = v.attr_list().unwrap().clone();
-                        let list_expr = attr_list
-                            .attributes()
-                            .find(|v| v.name() == "reg_names")
-                            .unwrap()
-                            .exprs()
-                            .next()
-                            .unwrap()
-                            .as_list();
-                        let names = list_expr.elements().iter().map(|el| {
-                            let text = el.as_literal().text();
-                            text[1..text.len() - 1].to_string()
-                        });
+        let reg_names = decl
+            .variants()
+            .iter()
+            .map(reg_names)
+            .collect::<Result<Vec<_>, _>>()?;
 
-                        let ident = format_ident!("{}", v.name());
-                        quote! {
-                            #(#names)|* => Some(#name::#ident)
-                        }
-                    });
+        let names_arms = decl.variants().iter().zip(&reg_names).map(|(v, names)| {
+            let ident = format_ident!("{}", v.name());
+            quote! {
+                #name::#ident => &[#(#names),*]
+            }
+        });
 
-                    let to_num_arms = decl.variants().iter().enumerate().map(|(id, v)| {
-                        let ident = format_ident!("{}", v.name());
+        let parser_arms = decl.variants().iter().zip(&reg_names).map(|(v, names)| {
+            let ident = format_ident!("{}", v.name());
+            quote! {
+                #(#names)|* => Some(#name::#ident)
+            }
+        });
 
-                        quote! {
-                            #name::#ident => #id
-                        }
-                    });
+        let to_num_arms = decl.variants().iter().enumerate().map(|(id, v)| {
+            let ident = format_ident!("{}", v.name());
 
-                    let from_num_arms = decl.variants().iter().enumerate().map(|(id, v)| {
-                        let ident = format_ident!("{}", v.name());
+            quote! {
+                #name::#ident => #id
+            }
+        });
 
-                        quote! {
-                            #id => Ok(#name::#ident)
-                        }
-                    });
+        let from_num_arms = decl.variants().iter().enumerate().map(|(id, v)| {
+            let ident = format_ident!("{}", v.name());
 
-                    let parser_name = format_ident!("parse_{}", name.to_string().to_lowercase());
+            quote! {
+                #id => Ok(#name::#ident)
+            }
+        });
 
-                    return Some(quote! {
-                        impl #name {
-                            pub fn get_names(&self) -> &'static [&'static str] {
-                                match &self {
-                                    #(#names_arms),*
-                                }
-                            }
-                            pub fn get_reg_num(&self) -> usize {
-                                match &self {
-                                    #(#to_num_arms),*
-                                }
-                            }
+        let parser_name = format_ident!("parse_{}", name.to_string().to_lowercase());
+        let variant_idents = decl
+            .variants()
+            .iter()
+            .map(|v| format_ident!("{}", v.name()));
+        let encoding_bits = encoding_bits(decl);
 
-                            pub fn encode(&self) -> u8 {
-                                self.get_reg_num() as u8
-                            }
-                        }
-                        impl tir_core::Printable for #name {
-                            fn print(&self, fmt: &mut dyn tir_core::IRFormatter) {
-                                fmt.write_direct(self.get_names()[0])
-                            }
-                        }
+        impl_tokens.push(quote! {
+            impl #name {
+                /// Width of the register number in instruction encodings
+                pub const ENCODING_BITS: u32 = #encoding_bits;
 
-                        #[allow(clippy::from_over_into)]
-                        impl Into<tir_core::Attr> for #name {
-                            fn into(self) -> tir_core::Attr {
-                                tir_core::Attr::String(self.get_names()[0].to_string())
-                            }
-                        }
+                pub fn get_names(&self) -> &'static [&'static str] {
+                    match &self {
+                        #(#names_arms),*
+                    }
+                }
+                pub fn get_reg_num(&self) -> usize {
+                    match &self {
+                        #(#to_num_arms),*
+                    }
+                }
 
-                        pub fn #parser_name(input: &str) -> Option<#name> {
-                            match input {
-                                #(#parser_arms),*,
-                                _ => None,
-                            }
-                        }
-                        impl tir_core::parser::Parsable<#name> for #name {
-                            fn parse(input: tir_core::IRStrStream) -> lpl::ParseResult<tir_core::IRStrStream, #name> {
-                                let parser = lpl::combinators::lang::ident(|_| false).try_map(|r, s| {
-                                    #parser_name(r).ok_or(Into::<lpl::Diagnostic>::into(DiagKind::UnknownRegister(
-                                        r.to_string(),
-                                        s,
-                                    )))
-                                });
-                                parser.parse(input)
-                            }
-                        }
+                pub fn encode(&self) -> u8 {
+                    self.get_reg_num() as u8
+                }
+            }
+            impl tir_core::Printable for #name {
+                fn print(&self, fmt: &mut dyn tir_core::IRFormatter) {
+                    fmt.write_direct(self.get_names()[0])
+                }
+            }
 
-                        impl TryFrom<usize> for #name {
-                            type Error = ();
-                            fn try_from(value: usize) -> Result<Self, Self::Error> {
-                                match value {
-                                    #(#from_num_arms),*,
-                                    _ => Err(())
-                                }
-                            }
-                        }
+            #[allow(clippy::from_over_into)]
+            impl Into<tir_core::Attr> for #name {
+                fn into(self) -> tir_core::Attr {
+                    tir_core::Attr::String(self.get_names()[0].to_string())
+                }
+            }
 
-                        impl From<#name> for tir_backend::Register<#name> {
-                            fn from(value: #name) -> tir_backend::Register<#name> {
-                                tir_backend::Register::Architecture(value)
-                            }
-                        }
+            pub fn #parser_name(input: &str) -> Option<#name> {
+                match input {
+                    #(#parser_arms),*,
+                    _ => None,
+                }
+            }
+            impl tir_core::parser::Parsable<#name> for #name {
+                fn parse(input: tir_core::IRStrStream) -> lpl::ParseResult<tir_core::IRStrStream, #name> {
+                    let parser = lpl::combinators::lang::ident(|_| false).try_map(|r, s| {
+                        #parser_name(r).ok_or(Into::<lpl::Diagnostic>::into(DiagKind::UnknownRegister(
+                            r.to_string(),
+                            s,
+                        )))
                     });
+                    parser.parse(input)
                 }
             }
 
-            None
-        },
-    );
+            impl TryFrom<usize> for #name {
+                type Error = ();
+                fn try_from(value: usize) -> Result<Self, Self::Error> {
+                    match value {
+                        #(#from_num_arms),*,
+                        _ => Err(())
+                    }
+                }
+            }
 
-    quote! {
+            impl tir_core::Generate for #name {
+                fn generate(gen: &mut tir_core::OpGenerator) -> tir_core::GenerateResult<Self> {
+                    gen.unstructured()
+                        .choose(&[#(#name::#variant_idents),*])
+                        .copied()
+                }
+            }
+
+            impl From<#name> for tir_backend::Register<#name> {
+                fn from(value: #name) -> tir_backend::Register<#name> {
+                    tir_backend::Register::Architecture(value)
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
         #[derive(Clone, Copy, Debug)]
         #doc
         pub enum #name {
@@ -252,46 +255,1368 @@ fn generate_enum<'a>(
         impl lpl::combinators::NotTuple for #name {}
 
         #(#impl_tokens)*
+    })
+}
+
+/// Names of the register from its `#[reg_names([...])]` attribute
+fn reg_names(variant: &ast::EnumVariantDecl) -> Result<Vec<String>, DiagKind> {
+    let names = variant
+        .attr_list()
+        .and_then(|list| list.attributes().find(|attr| attr.name() == "reg_names"))
+        .and_then(|attr| attr.exprs().next());
+    let Some(ast::Expr::List(list)) = names else {
+        return Err(DiagKind::MissingRegNames(variant.name(), variant.span()));
+    };
+
+    list.elements()
+        .iter()
+        .map(|el| match el {
+            ast::Expr::Literal(lit) if matches!(lit.ty(), ast::Type::String) => {
+                let text = lit.text();
+                Ok(text[1..text.len() - 1].to_string())
+            }
+            _ => Err(DiagKind::MissingRegNames(variant.name(), variant.span())),
+        })
+        .collect()
+}
+
+/// Value of an instruction template parameter
+#[derive(Clone)]
+enum TemplateValue {
+    Bits(u64, u16),
+    Int(i64),
+    Str(String),
+}
+
+impl TemplateValue {
+    /// Text of the value, when it is substituted into an assembly format
+    fn asm_text(&self) -> String {
+        match self {
+            TemplateValue::Bits(value, _) => value.to_string(),
+            TemplateValue::Int(value) => value.to_string(),
+            TemplateValue::Str(value) => value.clone(),
+        }
+    }
+}
+
+/// Instruction template with its parameters bound to the values of an instantiation
+struct BoundTemplate<'a> {
+    decl: &'a ast::InstrTemplateDecl,
+    params: HashMap<String, TemplateValue>,
+}
+
+enum InstrField {
+    /// Register of the given class, that takes the given number of bits in encodings
+    Register(String, u32),
+    /// Immediate of the given width, two's complement unless it is unsigned
+    Imm { width: u16, signed: bool },
+    /// Label, that is encoded as the two's complement byte offset of the given width from
+    /// the instruction
+    Label(u16),
+    /// Single bit
+    Flag,
+}
+
+impl InstrField {
+    /// Number of bits of the field, registers are not sliced
+    fn width(&self) -> Option<u32> {
+        match self {
+            InstrField::Register(..) => None,
+            InstrField::Imm { width, .. } | InstrField::Label(width) => Some(*width as u32),
+            InstrField::Flag => Some(1),
+        }
+    }
+
+    /// Smallest and largest values of an immediate or a label offset
+    fn range(&self) -> Option<(i64, i64)> {
+        match self {
+            InstrField::Imm {
+                width,
+                signed: false,
+            } => Some((0, bit_mask(*width as u32) as i64)),
+            InstrField::Imm { width, .. } | InstrField::Label(width) => {
+                let max = (1i64 << (width - 1)) - 1;
+                Some((-max - 1, max))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Bits of an encoding, that have the same value in every instance of the instruction
+#[derive(Clone, Copy)]
+struct FixedBits {
+    width: u32,
+    mask: u64,
+    value: u64,
+}
+
+impl FixedBits {
+    /// Both encodings match some bit string
+    fn overlaps(&self, other: &FixedBits) -> bool {
+        self.width == other.width && (self.value ^ other.value) & self.mask & other.mask == 0
+    }
+}
+
+/// Instruction, that the decoder dispatches to
+struct EncodingPattern {
+    instr: String,
+    span: Span,
+    fixed: FixedBits,
+}
+
+enum AsmPiece {
+    Text(String),
+    Field(String),
+}
+
+/// Evaluates a template argument, that is either a literal or a parameter of the
+/// instantiating template. Bit literals take the width of the parameter type.
+fn template_value(
+    lit: &ast::LiteralExpr,
+    ty: &ast::Type,
+    params: &HashMap<String, TemplateValue>,
+) -> Result<TemplateValue, String> {
+    if let Some(name) = lit.ident() {
+        return params
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| format!("unknown template parameter '{}'", name));
+    }
+
+    if let Some(value) = lit.bits_value() {
+        let width = match (ty, lit.ty()) {
+            (ast::Type::Bits(width), _) | (_, ast::Type::Bits(width)) => *width,
+            _ => return Err(format!("bit literal '{}' has no width", lit.text())),
+        };
+        return Ok(TemplateValue::Bits(value, width));
+    }
+
+    if let Some(value) = lit.int_value() {
+        return Ok(TemplateValue::Int(value));
+    }
+
+    if matches!(lit.ty(), ast::Type::String) {
+        let text = lit.text();
+        return Ok(TemplateValue::Str(text[1..text.len() - 1].to_string()));
+    }
+
+    Err("unsupported template argument".to_string())
+}
+
+/// Chain of templates of the instruction, from its own template up to the root one
+fn bind_templates<'a>(
+    other_decls: &'a HashMap<String, &'a ast::Item>,
+    decl: &ast::InstrDecl,
+) -> Result<Vec<BoundTemplate<'a>>, String> {
+    let mut chain = vec![];
+    let mut name = Some(decl.template_name());
+    let mut args = decl
+        .template_args()
+        .iter()
+        .map(|arg| arg.value())
+        .collect::<Option<Vec<_>>>()
+        .ok_or("invalid template argument")?;
+    let mut outer_params = HashMap::new();
+
+    while let Some(template_name) = name {
+        let Some(ast::Item::InstrTemplateDecl(template)) = other_decls.get(&template_name) else {
+            return Err(format!("unknown instruction template '{}'", template_name));
+        };
+
+        if template.parameters().len() != args.len() {
+            return Err(format!(
+                "template '{}' takes {} arguments, got {}",
+                template_name,
+                template.parameters().len(),
+                args.len()
+            ));
+        }
+
+        let params = template
+            .parameters()
+            .iter()
+            .zip(&args)
+            .map(|(param, arg)| {
+                Ok((
+                    param.name(),
+                    template_value(arg, &param.ty(), &outer_params)?,
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        name = template.parent_template_name();
+        args = template
+            .parent_template_args()
+            .map(|arg| arg.value())
+            .collect::<Option<Vec<_>>>()
+            .ok_or("invalid template argument")?;
+        outer_params = params.clone();
+
+        chain.push(BoundTemplate {
+            decl: template,
+            params,
+        });
+    }
+
+    Ok(chain)
+}
+
+/// Splits the assembly format into literal text and fields, substituting the template
+/// parameters
+fn asm_pieces(
+    format: &str,
+    params: &HashMap<String, TemplateValue>,
+) -> Result<Vec<AsmPiece>, String> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut rest = format;
+
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        let end = start
+            + rest[start..]
+                .find('}')
+                .ok_or("unterminated '{' in assembly format")?;
+        let placeholder = rest[start + 1..end].trim();
+
+        if let Some(field) = placeholder.strip_prefix("self.") {
+            if !text.is_empty() {
+                pieces.push(AsmPiece::Text(std::mem::take(&mut text)));
+            }
+            pieces.push(AsmPiece::Field(field.to_string()));
+        } else {
+            let value = params
+                .get(placeholder)
+                .ok_or_else(|| format!("unknown template parameter '{}'", placeholder))?;
+            text.push_str(&value.asm_text());
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        pieces.push(AsmPiece::Text(text));
+    }
+
+    Ok(pieces)
+}
+
+/// Words and punctuation of the literal text of an assembly format
+fn asm_tokens(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '.' || c == '_' {
+            word.push(c);
+            continue;
+        }
+
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+
+        match c {
+            ',' | '(' | ')' => tokens.push(c.to_string()),
+            c if c.is_whitespace() => {}
+            c => return Err(format!("unsupported character '{}' in assembly format", c)),
+        }
+    }
+
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+
+    Ok(tokens)
+}
+
+/// Generates the encoder and the decoder of the instruction. Fields are encoded whole or
+/// by bit ranges, `self.imm[11:5]`, bits, that are not encoded, decode as zeros. Labels are
+/// encoded as offsets from the instruction, that the generated code resolves with
+/// `get_label_offset` and names with `address_label`.
+fn generate_encoding(
+    name: &proc_macro2::Ident,
+    encoding: &ast::EncodingDecl,
+    params: &HashMap<String, TemplateValue>,
+    fields: &[(String, InstrField)],
+    verifier: Option<&proc_macro2::Ident>,
+) -> Result<(proc_macro2::TokenStream, FixedBits), String> {
+    let terms = encoding_terms(encoding).map_err(|err| err.to_string())?;
+
+    // Width and the fixed value of each term, if there is one
+    let mut layout = vec![];
+    let mut widths = vec![];
+    let mut values = vec![];
+    let mut decoders = vec![];
+    // Bits of each field, that are already encoded
+    let mut encoded = HashMap::<&str, u64>::new();
+    // Fields, that are encoded by a single term with all of their bits
+    let mut whole = HashSet::new();
+
+    for term in &terms {
+        if let Some(field_name) = term.self_field() {
+            let (field_name, field) = fields
+                .iter()
+                .find(|(name, _)| *name == field_name)
+                .ok_or_else(|| format!("unknown field '{}'", field_name))?;
+            let ident = format_ident!("{}", field_name);
+
+            let (hi, lo) = match (field.width(), term.bit_range()) {
+                (None, Some(_)) => {
+                    return Err(format!("register field '{}' can not be sliced", field_name))
+                }
+                (None, None) => (0, 0),
+                (Some(width), None) => (width - 1, 0),
+                (Some(width), Some((hi, lo))) => {
+                    let (hi, lo) = (hi as u32, lo as u32);
+                    if lo > hi || hi >= width {
+                        return Err(format!(
+                            "field '{}' has no bits [{}:{}]",
+                            field_name, hi, lo
+                        ));
+                    }
+                    (hi, lo)
+                }
+            };
+            let mask = bit_mask(hi - lo + 1) << lo;
+            let field_bits = encoded.entry(field_name).or_default();
+            if *field_bits & mask != 0 {
+                return Err(format!("field '{}' is encoded more than once", field_name));
+            }
+            *field_bits |= mask;
+
+            match field {
+                InstrField::Register(class, bits) => {
+                    let class = format_ident!("{}", class);
+                    let getter = format_ident!("get_{}", field_name);
+                    layout.push((*bits, None));
+                    widths.push(quote! { #class::ENCODING_BITS });
+                    values.push(quote! { self.#getter().as_arch().encode() as u64 });
+                    decoders.push(quote! {
+                        let #ident = #class::try_from(fields.take(#class::ENCODING_BITS) as usize).ok()?;
+                    });
+                }
+                _ => {
+                    let width = hi - lo + 1;
+                    layout.push((width, None));
+                    widths.push(quote! { #width });
+                    if term.bit_range().is_none() {
+                        whole.insert(field_name.as_str());
+                        values.push(quote! { #ident });
+                        decoders.push(quote! { let #ident = fields.take(#width); });
+                    } else if lo == 0 {
+                        values.push(quote! { #ident });
+                        decoders.push(quote! { #ident |= fields.take(#width); });
+                    } else {
+                        values.push(quote! { #ident >> #lo });
+                        decoders.push(quote! { #ident |= fields.take(#width) << #lo; });
+                    }
+                }
+            }
+        } else {
+            let TemplateValue::Bits(value, width) = template_value(term, &ast::Type::Void, params)?
+            else {
+                return Err("only bits and fields can be encoded".to_string());
+            };
+            let width = width as u32;
+            layout.push((width, Some(value)));
+            widths.push(quote! { #width });
+            values.push(quote! { #value });
+            decoders.push(quote! {
+                if fields.take(#width) != #value {
+                    return None;
+                }
+            });
+        }
+    }
+
+    if let Some((field_name, _)) = fields
+        .iter()
+        .find(|(name, _)| !encoded.contains_key(name.as_str()))
+    {
+        return Err(format!("field '{}' is not encoded", field_name));
+    }
+
+    let width = layout.iter().map(|(width, _)| width).sum::<u32>();
+    if width > u64::BITS {
+        return Err(format!(
+            "encoding is {} bits wide, at most 64 are supported",
+            width
+        ));
+    }
+
+    let mut fixed = FixedBits {
+        width,
+        mask: 0,
+        value: 0,
+    };
+    let mut lo = width;
+    for (width, value) in layout {
+        lo -= width;
+        if let Some(value) = value {
+            fixed.mask |= bit_mask(width) << lo;
+            fixed.value |= (value & bit_mask(width)) << lo;
+        }
+    }
+
+    // Raw bits of the fields, that are not registers, are collected before they are
+    // converted to attribute values
+    let mut raw_fields = vec![];
+    let mut field_values = vec![];
+    let mut conversions = vec![];
+    let mut builder_args = vec![];
+    let mut label = None;
+
+    for (field_name, field) in fields {
+        let ident = format_ident!("{}", field_name);
+        match field {
+            InstrField::Register(..) => {
+                builder_args.push(quote! { .#ident(#ident.into()) });
+                continue;
+            }
+            InstrField::Imm { width, signed } => {
+                let width = *width as u32;
+                field_values.push(quote! { let #ident = self.#ident() as u64; });
+                conversions.push(if *signed {
+                    quote! { let #ident = tir_backend::encoding::sign_extend(#ident, #width); }
+                } else {
+                    quote! { let #ident = #ident as i64; }
+                });
+                builder_args.push(quote! { .#ident(tir_core::Attr::from(#ident)) });
+            }
+            InstrField::Label(width) => {
+                if label.is_some() {
+                    return Err("only one label field is supported".to_string());
+                }
+                label = Some((ident.clone(), field));
+
+                let width = *width as u32;
+                conversions.push(quote! {
+                    let #ident = address_label(
+                        address.checked_add_signed(tir_backend::encoding::sign_extend(#ident, #width))?,
+                    );
+                });
+                builder_args.push(quote! { .#ident(#ident) });
+            }
+            InstrField::Flag => {
+                field_values.push(quote! { let #ident = self.#ident() as u64; });
+                conversions.push(quote! { let #ident = #ident != 0; });
+                builder_args.push(quote! { .#ident(#ident) });
+            }
+        }
+        if !whole.contains(field_name.as_str()) {
+            raw_fields.push(quote! { let mut #ident = 0u64; });
+        }
+    }
+
+    let check = verifier.map(|verifier| {
+        quote! { #verifier(self).map_err(|_| tir_core::Error::Unknown)?; }
+    });
+    let build = quote! { #name::builder(context)#(#builder_args)*.build() };
+    let decoded = match verifier {
+        Some(verifier) => quote! {
+            let op = #build;
+            #verifier(&op.borrow()).ok()?;
+            Some(op)
+        },
+        None => quote! { Some(#build) },
+    };
+    let write = quote! {
+        #check
+        #(#field_values)*
+        let encoding = tir_backend::encoding::BitEncoder::default()
+            #(.push(#values, #widths))*;
+        stream.write(&encoding.to_bytes());
+        Ok(())
+    };
+
+    let (emittable, label_encoder, address) = match label {
+        Some((ident, field)) => {
+            let (min, max) = field.range().unwrap_or_default();
+            // Low bits of the offset, that are not encoded, must be zero
+            let dropped = (bit_mask(field.width().unwrap_or_default())
+                & !encoded[ident.to_string().as_str()]) as i64;
+            let aligned = (dropped != 0).then(|| quote! { && offset & #dropped == 0 });
+            let getter = ident.clone();
+            (
+                quote! {
+                    impl tir_backend::BinaryEmittable for #name {
+                        fn encode(
+                            &self,
+                            _target_opts: &tir_backend::TargetOptions,
+                            stream: &mut Box<dyn tir_backend::BinaryStream>,
+                        ) -> tir_core::Result<()> {
+                            self.encode_with_label_offset(get_label_offset(self, self.#getter()), stream)
+                        }
+                    }
+                },
+                quote! {
+                    /// Encode the instruction with the given byte offset of the label, that
+                    /// must be in range of the encoding
+                    pub fn encode_with_label_offset(
+                        &self,
+                        #ident: Option<i64>,
+                        stream: &mut Box<dyn tir_backend::BinaryStream>,
+                    ) -> tir_core::Result<()> {
+                        let #ident = #ident
+                            .filter(|offset| (#min..=#max).contains(offset) #aligned)
+                            .ok_or(tir_core::Error::Unknown)? as u64;
+                        #write
+                    }
+                },
+                format_ident!("address"),
+            )
+        }
+        None => (
+            quote! {
+                impl tir_backend::BinaryEmittable for #name {
+                    fn encode(
+                        &self,
+                        _target_opts: &tir_backend::TargetOptions,
+                        stream: &mut Box<dyn tir_backend::BinaryStream>,
+                    ) -> tir_core::Result<()> {
+                        #write
+                    }
+                }
+            },
+            quote! {},
+            format_ident!("_address"),
+        ),
+    };
+
+    let tokens = quote! {
+        #emittable
+
+        impl #name {
+            #label_encoder
+
+            /// Decode the instruction at the start of the stream, that is located at the
+            /// given address
+            pub fn decode(
+                context: &tir_core::ContextRef,
+                stream: &[u8],
+                #address: u64,
+            ) -> Option<tir_core::OpRef> {
+                let mut fields = tir_backend::encoding::BitDecoder::new(stream, #(#widths)+*)?;
+                #(#raw_fields)*
+                #(#decoders)*
+                #(#conversions)*
+                #decoded
+            }
+        }
+    };
+
+    Ok((tokens, fixed))
+}
+
+fn generate_asm(
+    name: &proc_macro2::Ident,
+    op_name: &str,
+    pieces: &[AsmPiece],
+    fields: &[(String, InstrField)],
+) -> Result<proc_macro2::TokenStream, String> {
+    let mut parsers = vec![];
+    let mut patterns = vec![];
+    let mut builder_args = vec![];
+    let mut format = String::new();
+    let mut format_args = vec![];
+
+    for piece in pieces {
+        match piece {
+            AsmPiece::Text(text) => {
+                format.push_str(text);
+                for token in asm_tokens(text)? {
+                    let parser = match token.as_str() {
+                        "," => quote! { tir_backend::parser::comma() },
+                        "(" => quote! { tir_backend::parser::open_paren() },
+                        ")" => quote! { tir_backend::parser::close_paren() },
+                        word => quote! {
+                            tir_backend::parser::asm_ident().try_map(|t, s| {
+                                if t.eq_ignore_ascii_case(#word) {
+                                    Ok(())
+                                } else {
+                                    Err(Into::<lpl::Diagnostic>::into(DiagKind::UnknownOpcode(s)))
+                                }
+                            })
+                        },
+                    };
+                    parsers.push(parser);
+                    patterns.push(quote! { _ });
+                }
+            }
+            AsmPiece::Field(field_name) => {
+                let (_, field) = fields
+                    .iter()
+                    .find(|(name, _)| name == field_name)
+                    .ok_or_else(|| format!("unknown field '{}'", field_name))?;
+                let ident = format_ident!("{}", field_name);
+                format.push_str("{}");
+
+                match field {
+                    InstrField::Register(class, _) => {
+                        let parse_fn = format_ident!("parse_{}", class.to_lowercase());
+                        let getter = format_ident!("get_{}", field_name);
+                        parsers.push(quote! {
+                            tir_backend::parser::asm_ident()
+                                .try_map(|r, s| {
+                                    #parse_fn(r).ok_or(Into::<lpl::Diagnostic>::into(
+                                        DiagKind::UnknownRegister(r.to_string(), s),
+                                    ))
+                                })
+                                .label("register")
+                        });
+                        builder_args.push(quote! { .#ident(#ident.into()) });
+                        format_args.push(quote! { reg_name(self.#getter(), target_opts) });
+                    }
+                    InstrField::Imm { .. } => {
+                        let (min, max) = field.range().unwrap_or_default();
+                        parsers.push(quote! {
+                            tir_backend::parser::number()
+                                .spanned()
+                                .try_map(|(value, span), _| {
+                                    if (#min..=#max).contains(&value) {
+                                        Ok(value)
+                                    } else {
+                                        Err(Into::<lpl::Diagnostic>::into(DiagKind::ImmOutOfRange(
+                                            value, #min, #max, span,
+                                        )))
+                                    }
+                                })
+                                .label("imm")
+                        });
+                        builder_args.push(quote! { .#ident(tir_core::Attr::from(#ident)) });
+                        format_args.push(quote! { self.#ident() });
+                    }
+                    InstrField::Label(_) => {
+                        parsers.push(quote! { tir_backend::parser::asm_ident().label("label") });
+                        builder_args.push(quote! { .#ident(#ident.to_string()) });
+                        format_args.push(quote! { self.#ident() });
+                    }
+                    InstrField::Flag => {
+                        return Err(format!(
+                            "flag '{}' can not be used in assembly formats",
+                            field_name
+                        ))
+                    }
+                }
+                patterns.push(quote! { #ident });
+            }
+        }
+    }
+
+    if let Some((field_name, _)) = fields.iter().find(|(name, _)| {
+        !pieces
+            .iter()
+            .any(|p| matches!(p, AsmPiece::Field(f) if f == name))
+    }) {
+        return Err(format!(
+            "field '{}' is missing from the assembly format",
+            field_name
+        ));
     }
+
+    let mut parsers = parsers.into_iter();
+    let first = parsers.next().ok_or("assembly format is empty")?;
+    let pattern = patterns
+        .into_iter()
+        .reduce(|acc, p| quote! { (#acc, #p) })
+        .ok_or("assembly format is empty")?;
+
+    let uses_target_opts = fields
+        .iter()
+        .any(|(_, field)| matches!(field, InstrField::Register(..)));
+    let target_opts = if uses_target_opts {
+        format_ident!("target_opts")
+    } else {
+        format_ident!("_target_opts")
+    };
+    let text = format.trim().to_string();
+    let printed = if format_args.is_empty() {
+        quote! { #text.to_string() }
+    } else {
+        quote! { format!(#text, #(#format_args),*) }
+    };
+
+    Ok(quote! {
+        impl tir_backend::ISAParser for #name {
+            fn parse(input: tir_backend::TokenStream) -> lpl::ParseResult<tir_backend::TokenStream, ()> {
+                // The assembly parser always attaches its context to the stream
+                let asm_ctx = input.get_extra().unwrap().clone();
+
+                let parser = #first
+                    #(.and_then(#parsers))*
+                    .label(#op_name);
+                let (#pattern, ni) = parser.parse(input)?;
+
+                let builder = asm_ctx.get_builder();
+                let context = builder.get_context();
+                let op = #name::builder(&context)#(#builder_args)*.build();
+                builder.insert(&op);
+
+                Ok(((), ni))
+            }
+        }
+
+        impl tir_backend::AsmPrintable for #name {
+            fn print(&self, #target_opts: &tir_backend::TargetOptions) -> String {
+                #printed
+            }
+        }
+    })
 }
 
+/// Finds the first impl selected by `select` along the template chain, together
+/// with the parameters of the template it is attached to.
+fn find_impl<'a, 'b, T>(
+    chain: &'b [BoundTemplate<'a>],
+    impls: &HashMap<String, Vec<&'a ast::Item>>,
+    select: impl Fn(&'a ast::Item) -> Option<T>,
+) -> Option<(T, &'b HashMap<String, TemplateValue>)> {
+    chain.iter().find_map(|template| {
+        impls
+            .get(&template.decl.name())
+            .into_iter()
+            .flatten()
+            .find_map(|item| select(item))
+            .map(|found| (found, &template.params))
+    })
+}
+
+/// Generates an operation for the instruction, that is fully defined by its templates:
+/// fields are typed operands and attributes, and the encoder, the decoder, the assembly
+/// parser and the printer are derived from the nearest `encoding for` and `asm for`
+/// blocks. The generated code expects `DiagKind`, `reg_name` and the register parsers to
+/// be in scope.
 fn generate_instr<'a>(
     other_decls: &'a HashMap<String, &'a ast::Item>,
+    impls: &HashMap<String, Vec<&'a ast::Item>>,
     decl: &ast::InstrDecl,
     dialect_name: &str,
+) -> Result<(proc_macro2::TokenStream, Option<EncodingPattern>), Box<dyn std::error::Error>> {
+    let instr_name = decl.name();
+    let error = |message: String| format!("instr '{}': {}", instr_name, message);
+
+    let chain = bind_templates(other_decls, decl).map_err(error)?;
+
+    let mut op_name = None;
+    let mut custom_verifiers = vec![];
+    for attr in decl.attributes() {
+        match attr.name().as_str() {
+            "name" => {
+                let text = attr
+                    .exprs()
+                    .next()
+                    .filter(|e| matches!(e.ty(), ast::Type::String))
+                    .ok_or_else(|| error("name must be a string".to_string()))?
+                    .as_literal()
+                    .text();
+                op_name = Some(text[1..text.len() - 1].to_string());
+            }
+            "verify" => custom_verifiers.extend(attr_idents(&attr)),
+            other => return Err(error(format!("unknown attribute '{}'", other)).into()),
+        }
+    }
+    // Verifiers of the templates apply to all their instructions
+    for template in &chain {
+        for attr in template.decl.attributes() {
+            match attr.name().as_str() {
+                "verify" => custom_verifiers.extend(attr_idents(&attr)),
+                other => {
+                    return Err(error(format!(
+                        "unknown attribute '{}' of template '{}'",
+                        other,
+                        template.decl.name()
+                    ))
+                    .into())
+                }
+            }
+        }
+    }
+
+    let mut fields = vec![];
+    let mut operands = vec![];
+    let mut known_attrs = vec![];
+
+    for template in &chain {
+        for field in template.decl.fields() {
+            let field_name = field.name();
+            let ident = format_ident!("{}", field_name);
+
+            let mut unsigned = false;
+            let mut label = false;
+            for attr in field.attributes() {
+                match attr.name().as_str() {
+                    "unsigned" => unsigned = true,
+                    "label" => label = true,
+                    other => {
+                        return Err(error(format!(
+                            "field '{}' has unknown attribute '{}'",
+                            field_name, other
+                        ))
+                        .into())
+                    }
+                }
+            }
+            if (unsigned || label) && !matches!(field.ty(), ast::Type::Bits(_)) {
+                return Err(error(format!(
+                    "only bits fields can be unsigned or labels, '{}' is not",
+                    field_name
+                ))
+                .into());
+            }
+            if unsigned && label {
+                return Err(error(format!("label '{}' can not be unsigned", field_name)).into());
+            }
+
+            let instr_field = match field.ty() {
+                ast::Type::Bits(width) if label => {
+                    known_attrs.push(quote! { #ident: String });
+                    InstrField::Label(width)
+                }
+                ast::Type::Bits(width) => {
+                    known_attrs.push(quote! { #ident: IntegerAttr });
+                    InstrField::Imm {
+                        width,
+                        signed: !unsigned,
+                    }
+                }
+                ast::Type::Bool => {
+                    known_attrs.push(quote! { #ident: bool });
+                    InstrField::Flag
+                }
+                ast::Type::Unresolved(_) => {
+                    let class = field.ty_text();
+                    let Some(ast::Item::EnumDecl(class_decl)) = other_decls.get(&class) else {
+                        return Err(error(format!("unknown register class '{}'", class)).into());
+                    };
+                    let class_ident = format_ident!("{}", class);
+                    operands.push(quote! {
+                        #[operand]
+                        #ident: Register<#class_ident>
+                    });
+                    InstrField::Register(class, encoding_bits(class_decl))
+                }
+                _ => {
+                    return Err(error(format!(
+                        "field '{}' must be a register class, bits or bool",
+                        field_name
+                    ))
+                    .into())
+                }
+            };
+            fields.push((field_name, instr_field));
+        }
+    }
+
+    let name = format_ident!("{}", instr_name);
+
+    let asm = find_impl(&chain, impls, |item| match item {
+        ast::Item::AsmDecl(asm) => Some(asm),
+        _ => None,
+    })
+    .map(|(asm, params)| {
+        let format = match asm.body().stmts().last() {
+            Some(ast::Expr::Literal(lit)) if matches!(lit.ty(), ast::Type::String) => {
+                let text = lit.text();
+                text[1..text.len() - 1].to_string()
+            }
+            _ => return Err("assembly format must be a string".to_string()),
+        };
+        asm_pieces(&format, params)
+    })
+    .transpose()
+    .map_err(error)?;
+
+    let op_name = op_name.unwrap_or_else(|| {
+        asm.iter()
+            .flatten()
+            .find_map(|piece| match piece {
+                AsmPiece::Text(text) => text.split_whitespace().next().map(|w| w.to_string()),
+                AsmPiece::Field(_) => None,
+            })
+            .unwrap_or_else(|| instr_name.to_lowercase())
+    });
+    let full_name = format!("{}.{}", dialect_name, op_name);
+
+    let constraints = fields
+        .iter()
+        .filter(|(_, field)| matches!(field, InstrField::Imm { .. }))
+        .filter_map(|(field_name, field)| {
+            let (min, max) = field.range()?;
+            Some(range_constraint(&full_name, field_name, min, max))
+        })
+        .collect::<Vec<_>>();
+    let verifier_name = (!constraints.is_empty() || !custom_verifiers.is_empty())
+        .then(|| format_ident!("verify_{}", op_name.replace('.', "_")));
+
+    let asm_impls = asm
+        .map(|pieces| generate_asm(&name, &op_name, &pieces, &fields))
+        .transpose()
+        .map_err(error)?;
+
+    let encoding = find_impl(&chain, impls, |item| match item {
+        ast::Item::EncodingDecl(encoding) => Some(encoding),
+        _ => None,
+    })
+    .map(|(encoding, params)| {
+        generate_encoding(&name, encoding, params, &fields, verifier_name.as_ref())
+    })
+    .transpose()
+    .map_err(error)?;
+    let (encoding_impls, pattern) = match encoding {
+        Some((tokens, fixed)) => (
+            Some(tokens),
+            Some(EncodingPattern {
+                instr: instr_name.clone(),
+                span: decl.span(),
+                fixed,
+            }),
+        ),
+        None => (None, None),
+    };
+
+    let (verifier_attr, verifier) = match &verifier_name {
+        Some(verifier_name) => {
+            let custom_verifiers = custom_verifiers.iter().map(|v| format_ident!("{}", v));
+            (
+                quote! { , verifier = #verifier_name },
+                quote! {
+                    fn #verifier_name(op: &#name) -> std::result::Result<(), tir_core::ValidateErr> {
+                        #(#constraints)*
+                        #(#custom_verifiers(op)?;)*
+                        Ok(())
+                    }
+                },
+            )
+        }
+        None => (quote! {}, quote! {}),
+    };
+
+    let doc = decl.doc().map(|doc| {
+        let lines = doc
+            .lines()
+            .map(|line| line.strip_prefix("///").unwrap_or(line).to_string())
+            .collect::<Vec<_>>();
+        quote! { #(#[doc = #lines])* }
+    });
+
+    let dialect_name = format_ident!("{}", dialect_name);
+    let known_attrs = if known_attrs.is_empty() {
+        quote! {}
+    } else {
+        quote! { , known_attrs(#(#known_attrs),*) }
+    };
+
+    let tokens = quote! {
+        #doc
+        #[derive(Op, OpAssembly, OpValidator)]
+        #[operation(name = #op_name, dialect = #dialect_name #known_attrs #verifier_attr)]
+        pub struct #name {
+            #(#operands,)*
+            r#impl: OpImpl,
+        }
+
+        #encoding_impls
+
+        #asm_impls
+
+        #verifier
+    };
+
+    Ok((tokens, pattern))
+}
+
+/// Decision tree over the fixed bits of the encodings
+enum DecodeNode {
+    /// Dispatch on the value of `width` bits starting from `lo`
+    Switch {
+        lo: u32,
+        width: u32,
+        cases: Vec<(u64, DecodeNode)>,
+    },
+    /// Try each instruction in order. Their encodings never overlap.
+    Try(Vec<String>),
+}
+
+/// Builds the decision tree for the encodings of the same width. Each level switches
+/// on the lowest run of bits, that is fixed in all the remaining encodings and has not
+/// been switched on yet, e.g. the opcode, then funct3, then funct7 of RISC-V.
+fn build_decode_tree(patterns: &[&EncodingPattern], switched: u64) -> Result<DecodeNode, DiagKind> {
+    let common = patterns
+        .iter()
+        .fold(!switched, |mask, p| mask & p.fixed.mask);
+
+    if patterns.len() < 2 || common == 0 {
+        for (idx, p) in patterns.iter().enumerate() {
+            if let Some(prev) = patterns[..idx]
+                .iter()
+                .find(|prev| prev.fixed.overlaps(&p.fixed))
+            {
+                return Err(DiagKind::OverlappingEncodings(
+                    p.instr.clone(),
+                    prev.instr.clone(),
+                    p.span.clone(),
+                ));
+            }
+        }
+
+        return Ok(DecodeNode::Try(
+            patterns.iter().map(|p| p.instr.clone()).collect(),
+        ));
+    }
+
+    let lo = common.trailing_zeros();
+    let width = (common >> lo).trailing_ones();
+    let field = bit_mask(width) << lo;
+
+    let mut groups = BTreeMap::<u64, Vec<&EncodingPattern>>::new();
+    for p in patterns {
+        groups
+            .entry((p.fixed.value & field) >> lo)
+            .or_default()
+            .push(p);
+    }
+
+    let cases = groups
+        .into_iter()
+        .map(|(value, group)| Ok((value, build_decode_tree(&group, switched | field)?)))
+        .collect::<Result<Vec<_>, DiagKind>>()?;
+
+    Ok(DecodeNode::Switch { lo, width, cases })
+}
+
+fn generate_decode_node(node: &DecodeNode) -> proc_macro2::TokenStream {
+    match node {
+        DecodeNode::Switch { lo, width, cases } => {
+            let mask = bit_mask(*width);
+            let arms = cases.iter().map(|(value, node)| {
+                let body = generate_decode_node(node);
+                quote! { #value => #body }
+            });
+            let field = if *lo == 0 {
+                quote! { bits & #mask }
+            } else {
+                quote! { (bits >> #lo) & #mask }
+            };
+            quote! {
+                match #field {
+                    #(#arms,)*
+                    _ => None,
+                }
+            }
+        }
+        DecodeNode::Try(instrs) => instrs
+            .iter()
+            .map(|instr| {
+                let instr = format_ident!("{}", instr);
+                quote! { #instr::decode(context, stream, address) }
+            })
+            .reduce(|acc, decoder| quote! { #acc.or_else(|| #decoder) })
+            .unwrap_or(quote! { None }),
+    }
+}
+
+/// Generates `decode`, that dispatches to the decoders of the instructions through
+/// decision trees over the fixed bits of their encodings, one per encoding width
+fn generate_decoder(
+    patterns: &[EncodingPattern],
+) -> Result<proc_macro2::TokenStream, Box<dyn std::error::Error>> {
+    let mut by_width = BTreeMap::<u32, Vec<&EncodingPattern>>::new();
+    for p in patterns {
+        by_width.entry(p.fixed.width).or_default().push(p);
+    }
+
+    let mut decoders = vec![];
+    let mut calls = vec![];
+    for (width, patterns) in by_width {
+        let tree = build_decode_tree(&patterns, 0)?;
+        let bits = match tree {
+            DecodeNode::Switch { .. } => quote! {
+                let bits = tir_backend::encoding::BitDecoder::new(stream, #width)?.take(#width);
+            },
+            DecodeNode::Try(_) => quote! {},
+        };
+        let tree = generate_decode_node(&tree);
+        let name = format_ident!("decode_{}", width);
+        decoders.push(quote! {
+            fn #name(context: &tir_core::ContextRef, stream: &[u8], address: u64) -> Option<tir_core::OpRef> {
+                #bits
+                #tree
+            }
+        });
+        calls.push(quote! { #name(context, stream, address) });
+    }
+
+    let call = calls
+        .into_iter()
+        .reduce(|acc, call| quote! { #acc.or_else(|| #call) })
+        .unwrap_or(quote! { None });
+
+    Ok(quote! {
+        /// Decode the instruction at the start of the stream, that is located at the given
+        /// address
+        pub fn decode(context: &tir_core::ContextRef, stream: &[u8], address: u64) -> Option<tir_core::OpRef> {
+            #(#decoders)*
+
+            #call
+        }
+    })
+}
+
+/// Lower `width` bits set
+fn bit_mask(width: u32) -> u64 {
+    u64::MAX.checked_shr(u64::BITS - width).unwrap_or(0)
+}
+
+/// Converts a CamelCase op struct name to the default IR name, e.g. `CompInstrEndOp` to
+/// `comp_instr_end`.
+fn default_op_name(struct_name: &str) -> String {
+    let base = struct_name.strip_suffix("Op").unwrap_or(struct_name);
+    let mut name = String::new();
+    for (idx, c) in base.chars().enumerate() {
+        if c.is_uppercase() {
+            if idx != 0 {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn attr_type(text: &str, optional: bool) -> Result<syn::Type, Box<dyn std::error::Error>> {
+    let base = match text {
+        "str" => "String".to_string(),
+        "int" => "IntegerAttr".to_string(),
+        _ => match text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            Some(elem) => format!("Vec<{}>", elem),
+            None => text.to_string(),
+        },
+    };
+
+    let ty = if optional {
+        format!("Option<{}>", base)
+    } else {
+        base
+    };
+
+    Ok(syn::parse_str(&ty)?)
+}
+
+fn attr_idents(attr: &ast::Attr) -> Vec<String> {
+    attr.exprs()
+        .filter_map(|e| match e {
+            ast::Expr::Literal(lit) => lit.ident(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Verifier statements, that check that the integer attribute is in the range
+fn range_constraint(
+    full_name: &str,
+    field_name: &str,
+    min: i64,
+    max: i64,
 ) -> proc_macro2::TokenStream {
+    let message = format!(
+        "attribute '{}' must be in range [{}, {}], got {{}}",
+        field_name, min, max
+    );
+    let range = quote! { #min..=#max };
+    quote! {
+        let value = op.r#impl.attrs.get(#field_name).and_then(|attr| attr.as_i64());
+        if let Some(value) = value.filter(|value| !(#range).contains(value)) {
+            return Err(tir_core::ValidateErr::ConstraintViolation(
+                #full_name,
+                format!(#message, value),
+                op.r#impl.location.clone(),
+            ));
+        }
+    }
+}
+
+fn generate_op(
+    decl: &ast::OpDecl,
+    dialect_name: &str,
+) -> Result<proc_macro2::TokenStream, Box<dyn std::error::Error>> {
+    let struct_name = decl.name();
+    let name = format_ident!("{}", struct_name);
+    let dialect = format_ident!("{}", dialect_name);
+
+    let mut op_name = default_op_name(&struct_name);
+    let mut traits = vec![];
+    let mut format = None;
+    let mut custom_verifiers = vec![];
+
+    for attr in decl.attributes() {
+        match attr.name().as_str() {
+            "name" => {
+                let text = attr
+                    .exprs()
+                    .next()
+                    .filter(|e| matches!(e.ty(), ast::Type::String))
+                    .ok_or_else(|| format!("op '{}': name must be a string", struct_name))?
+                    .as_literal()
+                    .text();
+                op_name = text[1..text.len() - 1].to_string();
+            }
+            "traits" => traits.extend(attr_idents(&attr)),
+            "verify" => custom_verifiers.extend(attr_idents(&attr)),
+            "assembly_format" => {
+                let text = attr
+                    .exprs()
+                    .next()
+                    .filter(|e| matches!(e.ty(), ast::Type::String))
+                    .ok_or_else(|| {
+                        format!("op '{}': assembly format must be a string", struct_name)
+                    })?
+                    .as_literal()
+                    .text();
+                format = Some(text[1..text.len() - 1].to_string());
+            }
+            other => {
+                return Err(format!("op '{}': unknown attribute '{}'", struct_name, other).into())
+            }
+        }
+    }
+
+    let full_name = format!("{}.{}", dialect_name, op_name);
+
     let mut fields = vec![];
+    let mut known_attrs = vec![];
+    let mut constraints = vec![];
 
-    let mut parent = other_decls.get(&decl.template_name());
+    for field in decl.fields() {
+        let field_name = field.name();
+        let ident = format_ident!("{}", field_name);
+        let attrs = field.attributes();
+        let kind = attrs
+            .first()
+            .ok_or_else(|| format!("op '{}': field '{}' has no kind", struct_name, field_name))?;
 
-    while parent.is_some() {
-        if let Some(ast::Item::InstrTemplateDecl(template)) = parent {
-            for f in template.fields() {
-                let name = format_ident!("{}", f.name());
+        match kind.name().as_str() {
+            "operand" => {
+                let ty: syn::Type = syn::parse_str(&field.ty_text())?;
                 fields.push(quote! {
                     #[operand]
-                    #name: Register
+                    #ident: #ty
+                });
+            }
+            "result" => {
+                fields.push(quote! {
+                    #[ret_type]
+                    #ident: tir_core::Type
                 });
             }
+            "region" => {
+                let args = attr_idents(kind);
+                let args = args.iter().map(|a| format_ident!("{}", a));
+                fields.push(quote! {
+                    #[region(#(#args),*)]
+                    #ident: tir_core::RegionRef
+                });
+            }
+            "attr" => {
+                let optional = attr_idents(kind).iter().any(|a| a == "optional");
+                let ty = attr_type(&field.ty_text(), optional)?;
+                known_attrs.push(quote! { #ident: #ty });
 
-            parent = template
-                .parent_template_name()
-                .and_then(|n| other_decls.get(&n));
-        } else {
-            unreachable!("must have been an InstrTemplateDecl");
+                for constraint in attrs.iter().skip(1) {
+                    if constraint.name() != "range" {
+                        return Err(format!(
+                            "op '{}': unknown constraint '{}' on attribute '{}'",
+                            struct_name,
+                            constraint.name(),
+                            field_name
+                        )
+                        .into());
+                    }
+
+                    let bounds = constraint
+                        .exprs()
+                        .filter_map(|e| match e {
+                            ast::Expr::Literal(lit) => lit.int_value(),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    let [min, max] = bounds[..] else {
+                        return Err(format!(
+                            "op '{}': range of '{}' must have two integer bounds",
+                            struct_name, field_name
+                        )
+                        .into());
+                    };
+
+                    constraints.push(range_constraint(&full_name, &field_name, min, max));
+                }
+            }
+            other => {
+                return Err(format!(
+                    "op '{}': field '{}' has unknown kind '{}'",
+                    struct_name, field_name, other
+                )
+                .into())
+            }
         }
     }
 
-    let name = format_ident!("{}", decl.name());
-    let dialect_name = format_ident!("{}", dialect_name);
-    let op_name = decl.name().to_lowercase();
+    let doc = decl.doc().map(|doc| {
+        let lines = doc
+            .lines()
+            .map(|line| line.strip_prefix("///").unwrap_or(line).to_string())
+            .collect::<Vec<_>>();
+        quote! { #(#[doc = #lines])* }
+    });
 
-    quote! {
+    let known_attrs = if known_attrs.is_empty() {
+        quote! {}
+    } else {
+        quote! { , known_attrs(#(#known_attrs),*) }
+    };
+
+    let (verifier_attr, verifier) = if constraints.is_empty() && custom_verifiers.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        let verifier_name = format_ident!("verify_{}", default_op_name(&struct_name));
+        let custom_verifiers = custom_verifiers.iter().map(|v| format_ident!("{}", v));
+        (
+            quote! { , verifier = #verifier_name },
+            quote! {
+                fn #verifier_name(op: &#name) -> std::result::Result<(), tir_core::ValidateErr> {
+                    #(#constraints)*
+                    #(#custom_verifiers(op)?;)*
+                    Ok(())
+                }
+            },
+        )
+    };
+
+    let format = format.map(|format| quote! { , assembly_format = #format });
+
+    let traits = traits.iter().map(|t| {
+        let t = format_ident!("{}", t);
+        quote! {
+            #[tir_macros::op_implements(dialect = #dialect)]
+            impl #t for #name {}
+        }
+    });
+
+    Ok(quote! {
+        #doc
         #[derive(Op, OpAssembly, OpValidator)]
-        #[operation(name = #op_name, dialect = #dialect_name)]
+        #[operation(name = #op_name, dialect = #dialect #known_attrs #verifier_attr #format)]
         pub struct #name {
-            #(#fields),*,
+            #(#fields,)*
             r#impl: OpImpl,
         }
-    }
+
+        #(#traits)*
+
+        #verifier
+    })
 }
@@ -0,0 +1,415 @@
+//! Semantic analysis of tmdl sources. Resolves the names of instruction templates,
+//! register classes and template parameters, and checks the types of template arguments
+//! and the widths of encodings, so that code generation only sees well-formed input.
+
+use std::collections::HashMap;
+
+use crate::ast::{self, ASTNode, AttrListOwner, ExprNode};
+use crate::DiagKind;
+
+/// Declarations, that the names of a source file resolve to
+struct Scope<'a> {
+    items: HashMap<String, &'a ast::Item>,
+    encodings: HashMap<String, &'a ast::EncodingDecl>,
+}
+
+impl<'a> Scope<'a> {
+    fn template(&self, name: &str) -> Option<&'a ast::InstrTemplateDecl> {
+        match self.items.get(name).copied() {
+            Some(ast::Item::InstrTemplateDecl(template)) => Some(template),
+            _ => None,
+        }
+    }
+
+    fn register_class(&self, name: &str) -> Option<&'a ast::EnumDecl> {
+        match self.items.get(name).copied() {
+            Some(ast::Item::EnumDecl(class)) => Some(class),
+            _ => None,
+        }
+    }
+
+    /// The template, followed by its ancestors. Stops at an unknown or a repeated
+    /// template, these are reported for the template, that refers to them.
+    fn chain(&self, template: &'a ast::InstrTemplateDecl) -> Vec<&'a ast::InstrTemplateDecl> {
+        let mut chain = vec![template];
+        while let Some(parent) = chain
+            .last()
+            .and_then(|t| t.parent_template_name())
+            .and_then(|name| self.template(&name))
+        {
+            if chain.iter().any(|t| t.name() == parent.name()) {
+                break;
+            }
+            chain.push(parent);
+        }
+        chain
+    }
+}
+
+/// Checks the items of the source file. Names are resolved against its own declarations
+/// and the ones of the imported files. Diagnostics are returned in the order of items.
+pub fn analyze<'a>(ast: &'a ast::SourceFile, imports: &[&'a ast::SourceFile]) -> Vec<DiagKind> {
+    let mut scope = Scope {
+        items: HashMap::new(),
+        encodings: HashMap::new(),
+    };
+
+    for item in imports
+        .iter()
+        .flat_map(|unit| unit.items())
+        .chain(ast.items())
+    {
+        match item {
+            ast::Item::EncodingDecl(encoding) => {
+                scope
+                    .encodings
+                    .entry(encoding.target_name())
+                    .or_insert(encoding);
+            }
+            ast::Item::AsmDecl(_) | ast::Item::ImplDecl(_) => {}
+            _ => {
+                scope.items.insert(item.name(), item);
+            }
+        }
+    }
+
+    let mut diags = vec![];
+    for item in ast.items() {
+        match item {
+            ast::Item::InstrTemplateDecl(template) => check_template(&scope, template, &mut diags),
+            ast::Item::InstrDecl(instr) => check_instr(&scope, instr, &mut diags),
+            ast::Item::EncodingDecl(encoding) => check_encoding(&scope, encoding, &mut diags),
+            ast::Item::AsmDecl(asm) if scope.template(&asm.target_name()).is_none() => {
+                diags.push(DiagKind::UnknownTemplate(asm.target_name(), asm.span()));
+            }
+            ast::Item::ImplDecl(impl_) if impl_.trait_name() == "Register" => {
+                check_register_impl(&scope, impl_, &mut diags)
+            }
+            _ => {}
+        }
+    }
+
+    diags
+}
+
+/// Registers are parsed and printed by their names, so every one of them must have some
+fn check_register_impl(scope: &Scope<'_>, impl_: &ast::ImplDecl, diags: &mut Vec<DiagKind>) {
+    let Some(class) = scope.register_class(&impl_.target_name()) else {
+        diags.push(DiagKind::UnknownRegisterClass(
+            impl_.target_name(),
+            impl_.span(),
+        ));
+        return;
+    };
+
+    for variant in class.variants() {
+        let names = variant
+            .attr_list()
+            .and_then(|list| list.attributes().find(|attr| attr.name() == "reg_names"))
+            .and_then(|attr| attr.exprs().next());
+        let valid = match names {
+            Some(ast::Expr::List(list)) => list.elements().iter().all(
+                |el| matches!(el, ast::Expr::Literal(lit) if matches!(lit.ty(), ast::Type::String)),
+            ),
+            _ => false,
+        };
+        if !valid {
+            diags.push(DiagKind::MissingRegNames(variant.name(), variant.span()));
+        }
+    }
+}
+
+fn check_template(scope: &Scope<'_>, template: &ast::InstrTemplateDecl, diags: &mut Vec<DiagKind>) {
+    for param in template.parameters() {
+        let ty = param.ty();
+        if !matches!(ty, ast::Type::Bits(_) | ast::Type::String) {
+            diags.push(DiagKind::UnknownType(ty.to_string(), param.span()));
+        }
+    }
+
+    for field in template.fields() {
+        match field.ty() {
+            ast::Type::Bits(_) | ast::Type::Bool => {}
+            ast::Type::Unresolved(_) => {
+                let class = field.ty_text();
+                if scope.register_class(&class).is_none() {
+                    diags.push(DiagKind::UnknownRegisterClass(class, field.span()));
+                }
+            }
+            ty => diags.push(DiagKind::InvalidFieldType(
+                field.name(),
+                ty.to_string(),
+                field.span(),
+            )),
+        }
+    }
+
+    let Some(parent_name) = template.parent_template_name() else {
+        return;
+    };
+    let Some(parent) = scope.template(&parent_name) else {
+        diags.push(DiagKind::UnknownTemplate(parent_name, template.span()));
+        return;
+    };
+
+    let args = template.parent_template_args().cloned().collect::<Vec<_>>();
+    check_args(parent, &args, template.parameters(), template.span(), diags);
+
+    if scope
+        .chain(parent)
+        .iter()
+        .any(|t| t.name() == template.name())
+    {
+        diags.push(DiagKind::RecursiveTemplate(
+            template.name(),
+            template.span(),
+        ));
+    }
+}
+
+/// Checks the arguments of a template instantiation. Arguments are literals or the
+/// parameters of the instantiating template, their types must match the parameters.
+fn check_args(
+    template: &ast::InstrTemplateDecl,
+    args: &[ast::InstrTemplateArg],
+    outer_params: &[ast::InstrTemplateParameterDecl],
+    span: lpl::Span,
+    diags: &mut Vec<DiagKind>,
+) {
+    let params = template.parameters();
+    if params.len() != args.len() {
+        diags.push(DiagKind::TemplateArgCount(
+            template.name(),
+            params.len(),
+            args.len(),
+            span,
+        ));
+        return;
+    }
+
+    for (param, arg) in params.iter().zip(args) {
+        let Some(value) = arg.value() else {
+            continue;
+        };
+
+        let ty = match value.ident() {
+            Some(name) => match outer_params.iter().find(|p| p.name() == name) {
+                Some(outer_param) => outer_param.ty(),
+                None => {
+                    diags.push(DiagKind::UnknownParameter(name, arg.span()));
+                    continue;
+                }
+            },
+            None => value.ty().clone(),
+        };
+
+        let expected = param.ty();
+        let matches = match (&expected, &ty) {
+            (ast::Type::Bits(expected), ast::Type::Bits(actual)) => expected == actual,
+            (ast::Type::String, ast::Type::String) => true,
+            _ => false,
+        };
+        if !matches {
+            diags.push(DiagKind::TemplateArgType(
+                param.name(),
+                template.name(),
+                expected.to_string(),
+                ty.to_string(),
+                arg.span(),
+            ));
+        }
+    }
+}
+
+fn check_instr(scope: &Scope<'_>, instr: &ast::InstrDecl, diags: &mut Vec<DiagKind>) {
+    let Some(template) = scope.template(&instr.template_name()) else {
+        diags.push(DiagKind::UnknownTemplate(
+            instr.template_name(),
+            instr.span(),
+        ));
+        return;
+    };
+
+    check_args(template, instr.template_args(), &[], instr.span(), diags);
+
+    // The nearest encoding must cover the fields of all the templates, including the ones
+    // added by the descendants of the encoded template
+    let chain = scope.chain(template);
+    let Some(encoding) = chain.iter().find_map(|t| scope.encodings.get(&t.name())) else {
+        return;
+    };
+    // Malformed encodings are reported on their own
+    let Ok(terms) = encoding_terms(encoding) else {
+        return;
+    };
+
+    let encoded = terms
+        .iter()
+        .filter_map(|term| term.self_field())
+        .collect::<Vec<_>>();
+    for field in chain.iter().flat_map(|t| t.fields()) {
+        if !encoded.contains(&field.name()) {
+            diags.push(DiagKind::FieldNotEncoded(
+                field.name(),
+                instr.name(),
+                instr.span(),
+            ));
+        }
+    }
+}
+
+fn check_encoding(scope: &Scope<'_>, encoding: &ast::EncodingDecl, diags: &mut Vec<DiagKind>) {
+    let target = encoding.target_name();
+    let Some(template) = scope.template(&target) else {
+        diags.push(DiagKind::UnknownTemplate(target, encoding.span()));
+        return;
+    };
+
+    let terms = match encoding_terms(encoding) {
+        Ok(terms) => terms,
+        Err(diag) => {
+            diags.push(diag);
+            return;
+        }
+    };
+
+    let chain = scope.chain(template);
+    // Bits of each field, that are already encoded
+    let mut encoded = HashMap::<String, u64>::new();
+    // Unknown once any of the terms is malformed
+    let mut width = Some(0);
+
+    for term in &terms {
+        let term_width = if let Some(field_name) = term.self_field() {
+            let field = chain
+                .iter()
+                .flat_map(|t| t.fields())
+                .find(|field| field.name() == field_name);
+            match field {
+                Some(field) => {
+                    // Unknown classes are reported for the template
+                    let field_width = match field.ty() {
+                        ast::Type::Bits(width) => Some(width as u32),
+                        ast::Type::Bool => Some(1),
+                        _ => scope.register_class(&field.ty_text()).map(encoding_bits),
+                    };
+
+                    let bits = match (term.bit_range(), field.ty()) {
+                        (None, _) => field_width.map(|width| (width.saturating_sub(1), 0)),
+                        (Some(_), ast::Type::Unresolved(_)) => {
+                            diags.push(DiagKind::SlicedRegister(field_name.clone(), term.span()));
+                            None
+                        }
+                        (Some((hi, lo)), _) => {
+                            let (hi, lo) = (hi as u32, lo as u32);
+                            if lo > hi || field_width.is_some_and(|width| hi >= width) {
+                                diags.push(DiagKind::InvalidBitRange(
+                                    field_name.clone(),
+                                    hi,
+                                    lo,
+                                    term.span(),
+                                ));
+                                None
+                            } else {
+                                Some((hi, lo))
+                            }
+                        }
+                    };
+
+                    // Wider fields never fit into an encoding, that is reported below
+                    if let Some((hi, lo)) = bits.filter(|(hi, _)| *hi < u64::BITS) {
+                        let mask = (u64::MAX >> (u64::BITS - 1 - hi)) & (u64::MAX << lo);
+                        let field_bits = encoded.entry(field_name.clone()).or_default();
+                        if *field_bits & mask != 0 {
+                            diags.push(DiagKind::FieldEncodedTwice(field_name, term.span()));
+                        }
+                        *field_bits |= mask;
+                    }
+
+                    bits.map(|(hi, lo)| hi - lo + 1)
+                }
+                None => {
+                    diags.push(DiagKind::UnknownField(
+                        field_name,
+                        target.clone(),
+                        term.span(),
+                    ));
+                    None
+                }
+            }
+        } else {
+            let ty = match term.ident() {
+                Some(name) => match template.parameters().iter().find(|p| p.name() == name) {
+                    Some(param) => Some(param.ty()),
+                    None => {
+                        diags.push(DiagKind::UnknownParameter(name, term.span()));
+                        None
+                    }
+                },
+                None => Some(term.ty().clone()),
+            };
+            match ty {
+                Some(ast::Type::Bits(width)) => Some(width as u32),
+                Some(_) => {
+                    diags.push(DiagKind::NotEncodable(term.span()));
+                    None
+                }
+                None => None,
+            }
+        };
+
+        width = width
+            .zip(term_width)
+            .map(|(width, term_width)| width + term_width);
+    }
+
+    if let Some(width) = width {
+        if width == 0 || width % 8 != 0 || width > u64::BITS {
+            diags.push(DiagKind::EncodingWidth(target, width, encoding.span()));
+        }
+    }
+}
+
+/// Operands of the `@` concatenation of the encoding, starting from the most significant
+/// one
+pub(crate) fn encoding_terms(
+    encoding: &ast::EncodingDecl,
+) -> Result<Vec<ast::LiteralExpr>, DiagKind> {
+    let value = encoding
+        .body()
+        .stmts()
+        .last()
+        .ok_or_else(|| DiagKind::NotEncodable(encoding.span()))?;
+
+    let mut terms = vec![];
+    concat_terms(value, &mut terms)?;
+    Ok(terms)
+}
+
+fn concat_terms(expr: &ast::Expr, terms: &mut Vec<ast::LiteralExpr>) -> Result<(), DiagKind> {
+    match expr {
+        ast::Expr::Literal(lit) => terms.push(lit.clone()),
+        ast::Expr::BinOp(op) => match op.kind() {
+            ast::BinOpKind::BitConcat => {
+                concat_terms(op.left(), terms)?;
+                concat_terms(op.right(), terms)?;
+            }
+        },
+        ast::Expr::Block(block) => {
+            let value = block
+                .stmts()
+                .last()
+                .ok_or_else(|| DiagKind::NotEncodable(expr.span()))?;
+            concat_terms(value, terms)?;
+        }
+        ast::Expr::List(_) => return Err(DiagKind::NotEncodable(expr.span())),
+    }
+
+    Ok(())
+}
+
+/// Width of the register number of the class in instruction encodings
+pub(crate) fn encoding_bits(decl: &ast::EnumDecl) -> u32 {
+    let last_reg = decl.variants().len().saturating_sub(1);
+    (usize::BITS - last_reg.leading_zeros()).max(1)
+}
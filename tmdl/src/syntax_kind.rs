@@ -43,6 +43,8 @@ pub enum SyntaxKind {
     FnKw,
     /// `flag`
     FlagKw,
+    /// `op`
+    OpKw,
 
     // Identifiers and literals
     Identifier,
@@ -131,10 +133,14 @@ pub enum SyntaxKind {
 
     FlagDecl,
 
+    OpDecl,
+    OpName,
+
     ListExpr,
     BlockExpr,
     LiteralExpr,
     FieldExpr,
+    BitRange,
     BinOpExpr,
     BinOpExprLeft,
     BinOpExprRight,
@@ -1,11 +1,54 @@
-use clap::{ArgMatches, FromArgMatches, Parser};
-use tir_core::{ContextRef, Printable, StdoutPrinter};
+use clap::{ArgMatches, FromArgMatches, Parser, ValueEnum};
+use tir_backend::{Endianness, TargetFlag, TargetOptions};
+use tir_core::{ContextRef, Printable, StdoutPrinter, StringPrinter};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Emit {
+    /// IR of the riscv dialect
+    Ir,
+    /// GNU assembly
+    Asm,
+    /// ELF relocatable object file
+    Obj,
+}
 
 #[derive(Debug, Parser)]
 #[command(name = "asm")]
 pub struct Cli {
     #[arg(default_value = "-")]
     input: String,
+    /// ISA string of the enabled extensions, i.e. rv64imac
+    #[arg(long, default_value = tir_riscv::DEFAULT_ISA)]
+    march: String,
+    /// Replace eligible instructions with compressed ones, if the C extension is enabled
+    #[arg(long)]
+    compress: bool,
+    /// Print instruction sequences, that match a pseudo-instruction, in the pseudo form
+    #[arg(long)]
+    pseudo: bool,
+    /// Output kind, an object file when writing to a file and IR otherwise by default
+    #[arg(value_enum, long)]
+    emit: Option<Emit>,
+    /// Print registers by their ABI names in the emitted assembly
+    #[arg(long)]
+    abi_names: bool,
+    /// Print the encoding of every instruction in the emitted assembly
+    #[arg(long)]
+    show_encoding: bool,
+    /// Output file, the standard output by default
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+/// Write the text to the output file or to the standard output
+fn write_text(output: Option<&str>, text: &str) -> std::io::Result<()> {
+    match output {
+        Some(output) => std::fs::write(output, text),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
 }
 
 pub fn main(
@@ -23,12 +66,57 @@ pub fn main(
         std::fs::read_to_string(args.input)?
     };
 
-    let module = tir_riscv::parse_asm(&context, &ir);
+    let features = tir_riscv::ISAFeatures::parse(&args.march)?;
+    let module = tir_riscv::parse_asm_with_features(&context, &ir, &features);
 
     match module {
         Ok(module) => {
-            let mut printer = StdoutPrinter::new();
-            module.borrow().print(&mut printer);
+            if args.compress {
+                tir_riscv::compress_instrs(&module, &features);
+            }
+            let emit = match (args.emit, &args.output) {
+                (Some(emit), _) => emit,
+                (None, Some(_)) => Emit::Obj,
+                (None, None) => Emit::Ir,
+            };
+            if emit == Emit::Obj {
+                let output = args.output.ok_or("object files are only written with -o")?;
+                let object = tir_riscv::emit_elf_object(&module, &args.march)?;
+                std::fs::write(output, object)?;
+                return Ok(());
+            }
+            if args.pseudo {
+                tir_riscv::fold_pseudo_instrs(&module);
+            }
+            match emit {
+                Emit::Asm => {
+                    let mut flags = vec![format!("{}{}", tir_riscv::MARCH_FLAG, args.march)];
+                    if args.abi_names {
+                        flags.push(tir_riscv::ABIRegName::NAME.to_string());
+                    }
+                    if args.show_encoding {
+                        flags.push(tir_riscv::ShowEncoding::NAME.to_string());
+                    }
+                    let target_opts = TargetOptions {
+                        endiannes: Endianness::Little,
+                        word_size: 8,
+                        flags,
+                    };
+                    let asm = tir_riscv::print_asm(&module, &target_opts)?;
+                    write_text(args.output.as_deref(), &asm)?;
+                }
+                _ => match args.output {
+                    Some(output) => {
+                        let mut printer = StringPrinter::new();
+                        module.borrow().print(&mut printer);
+                        write_text(Some(&output), &printer.get())?;
+                    }
+                    None => {
+                        let mut printer = StdoutPrinter::new();
+                        module.borrow().print(&mut printer);
+                    }
+                },
+            }
         }
         Err(err) => {
             // FIXME figure out how to tie syntax errors with tokens
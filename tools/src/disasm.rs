@@ -0,0 +1,64 @@
+use std::io::Read;
+
+use clap::{ArgMatches, FromArgMatches, Parser};
+use tir_core::{ContextRef, PassManager, PrintFlags, Printable, StdoutPrinter};
+
+#[derive(Debug, Parser)]
+#[command(name = "disasm")]
+pub struct Cli {
+    /// ELF file to disassemble
+    #[arg(default_value = "-")]
+    input: String,
+    /// ISA string of the enabled extensions, i.e. rv64imac
+    #[arg(long, default_value = tir_riscv::DEFAULT_ISA)]
+    march: String,
+    /// Passes to run on the disassembled module, i.e. `recover-cfg`
+    #[arg(long)]
+    pass: Vec<String>,
+    /// Print the original address of each operation
+    #[arg(long)]
+    print_debuginfo: bool,
+    /// Keep words, that can not be decoded, as `riscv.unknown_word` and report the
+    /// coverage of the executable sections
+    #[arg(long)]
+    recover: bool,
+}
+
+pub fn main(
+    context: ContextRef,
+    args: Option<&ArgMatches>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = match args {
+        Some(args) => Cli::from_arg_matches(args),
+        None => Ok(Cli::parse()),
+    }?;
+
+    let data = if args.input == "-" {
+        let mut data = vec![];
+        std::io::stdin().read_to_end(&mut data)?;
+        data
+    } else {
+        std::fs::read(&args.input)?
+    };
+
+    let features = tir_riscv::ISAFeatures::parse(&args.march)?;
+    let module = if args.recover {
+        let (module, coverage) =
+            tir_riscv::disassemble_elf_with_recovery(&context, &data, &features)?;
+        eprintln!("note: {}", coverage);
+        module
+    } else {
+        tir_riscv::disassemble_elf(&context, &data, &features).map_err(|err| err.to_string())?
+    };
+
+    let pm = PassManager::new_from_list(&args.pass)?;
+    pm.run(&(module.clone() as tir_core::OpRef))?;
+
+    let mut printer = StdoutPrinter::with_flags(PrintFlags {
+        generic: false,
+        debuginfo: args.print_debuginfo,
+    });
+    module.borrow().print(&mut printer);
+
+    Ok(())
+}
@@ -2,10 +2,17 @@ use clap::{Command, CommandFactory};
 use tir_core::ContextRef;
 
 pub mod r#asm;
+pub mod disasm;
 pub mod opt;
+pub mod rewrite;
 
-fn get_commands() -> [Command; 2] {
-    [opt::Cli::command(), r#asm::Cli::command()]
+fn get_commands() -> [Command; 4] {
+    [
+        opt::Cli::command(),
+        r#asm::Cli::command(),
+        disasm::Cli::command(),
+        rewrite::Cli::command(),
+    ]
 }
 
 pub fn tir_main(ctx: ContextRef) -> Result<(), Box<dyn std::error::Error>> {
@@ -21,6 +28,8 @@ pub fn tir_main(ctx: ContextRef) -> Result<(), Box<dyn std::error::Error>> {
     match subcommand {
         Some(("opt", m)) => opt::main(ctx, Some(m)),
         Some(("asm", m)) => r#asm::main(ctx, Some(m)),
+        Some(("disasm", m)) => disasm::main(ctx, Some(m)),
+        Some(("rewrite", m)) => rewrite::main(ctx, Some(m)),
         _ => unreachable!("unhandled subcommand"),
     }
 }
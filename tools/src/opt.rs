@@ -1,5 +1,8 @@
 use clap::{ArgMatches, FromArgMatches, Parser};
-use tir_core::{parse_ir, parser::print_parser_diag, ContextRef, PassManager, StdoutPrinter};
+use tir_core::{
+    parse_ir, parser::print_parser_diag, ContextRef, DiagnosticVerifier, IRDiagnostic, Location,
+    PassError, PassManager, PrintFlags, Severity, StdoutPrinter,
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "opt")]
@@ -8,6 +11,21 @@ pub struct Cli {
     input: String,
     #[arg(long)]
     pass: Vec<String>,
+    /// Print all operations in the generic form
+    #[arg(long)]
+    print_generic: bool,
+    /// Print operation locations
+    #[arg(long)]
+    print_debuginfo: bool,
+    /// Load operations from unknown dialects as opaque operations
+    #[arg(long)]
+    allow_unregistered_dialects: bool,
+    /// Check emitted diagnostics against `expected-*` annotations in the input
+    #[arg(long)]
+    verify_diagnostics: bool,
+    /// Number of threads passes may use, 0 means all available cores
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
 }
 
 pub fn main(
@@ -25,18 +43,25 @@ pub fn main(
         std::fs::read_to_string(&args.input)?
     };
 
+    context.allow_unregistered_dialects(args.allow_unregistered_dialects);
+    context.set_num_threads(match args.threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    });
+
+    let verifier = args
+        .verify_diagnostics
+        .then(|| DiagnosticVerifier::new(&context, &args.input, &ir));
+    let engine = context.get_diagnostic_engine();
+
     let module = parse_ir(context.clone(), &ir, &args.input);
 
-    match module {
-        Ok(module) => {
-            module.borrow().validate()?;
-            let mut printer = StdoutPrinter::new();
-            let pm = PassManager::new_from_list(&args.pass)?;
-            if let Err(e) = pm.run(&module) {
-                eprintln!("{:?}", e);
-                std::process::exit(0);
-            }
-            module.borrow().print(&mut printer);
+    let module = match module {
+        Ok(module) => Some(module),
+        Err(err) if verifier.is_some() => {
+            let location = Location::from_span(&err.span(), &ir);
+            engine.emit(IRDiagnostic::new(Severity::Error, location, err.message()));
+            None
         }
         Err(err) => {
             print_parser_diag(&ir, &err);
@@ -44,6 +69,42 @@ pub fn main(
             // winnow errors do not implement std::error::Error
             std::process::exit(1);
         }
+    };
+
+    let mut failed = module.is_none();
+    if let Some(module) = &module {
+        let pm = PassManager::new_from_list(&args.pass)?;
+
+        let validated = module.borrow().validate();
+        if let Err(err) = validated {
+            engine.emit(err.into());
+            failed = true;
+        } else if let Err(err) = pm.run(module) {
+            if !matches!(err, PassError::Failure) {
+                let location = err
+                    .get_location()
+                    .cloned()
+                    .unwrap_or_else(|| module.borrow().get_location());
+                engine.emit(IRDiagnostic::new(Severity::Error, location, err));
+            }
+            failed = true;
+        }
+    }
+
+    if let Some(verifier) = verifier {
+        if !verifier.verify() {
+            std::process::exit(1);
+        }
+    } else if failed {
+        std::process::exit(1);
+    }
+
+    if let (Some(module), false) = (module, failed) {
+        let mut printer = StdoutPrinter::with_flags(PrintFlags {
+            generic: args.print_generic,
+            debuginfo: args.print_debuginfo,
+        });
+        module.borrow().print(&mut printer);
     }
 
     Ok(())
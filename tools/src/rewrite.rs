@@ -0,0 +1,72 @@
+use std::io::{Read, Write};
+
+use clap::{ArgMatches, FromArgMatches, Parser};
+use tir_core::{ContextRef, PassManager};
+
+#[derive(Debug, Parser)]
+#[command(name = "rewrite")]
+pub struct Cli {
+    /// ELF file to rewrite
+    #[arg(default_value = "-")]
+    input: String,
+    /// ISA string of the enabled extensions, i.e. rv64imac
+    #[arg(long, default_value = tir_riscv::DEFAULT_ISA)]
+    march: String,
+    /// Edge counts to reorder the blocks of the functions by, one `<from> <to> <count>`
+    /// per line
+    #[arg(long)]
+    profile: Option<String>,
+    /// Passes to run on the recovered functions before encoding them again
+    #[arg(long)]
+    pass: Vec<String>,
+    /// Path of the patched ELF file
+    #[arg(short, long, default_value = "-")]
+    output: String,
+}
+
+pub fn main(
+    context: ContextRef,
+    args: Option<&ArgMatches>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = match args {
+        Some(args) => Cli::from_arg_matches(args),
+        None => Ok(Cli::parse()),
+    }?;
+
+    let data = if args.input == "-" {
+        let mut data = vec![];
+        std::io::stdin().read_to_end(&mut data)?;
+        data
+    } else {
+        std::fs::read(&args.input)?
+    };
+
+    let features = tir_riscv::ISAFeatures::parse(&args.march)?;
+    let module = tir_riscv::disassemble_elf(&context, &data, &features)?;
+    tir_riscv::recover_cfg(&module)?;
+
+    if let Some(profile) = &args.profile {
+        let profile = tir_riscv::Profile::parse(&std::fs::read_to_string(profile)?)?;
+        tir_riscv::reorder_blocks(&module, &profile);
+    }
+    let pm = PassManager::new_from_list(&args.pass)?;
+    pm.run(&(module.clone() as tir_core::OpRef))?;
+
+    let (output, statuses) = tir_riscv::patch_elf(&module, &data, &args.march)?;
+    for status in statuses {
+        if let Err(err) = status.result {
+            eprintln!(
+                "warning: function `{}` at 0x{:x} is left untouched: {}",
+                status.name, status.address, err
+            );
+        }
+    }
+
+    if args.output == "-" {
+        std::io::stdout().write_all(&output)?;
+    } else {
+        std::fs::write(&args.output, output)?;
+    }
+
+    Ok(())
+}